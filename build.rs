@@ -0,0 +1,54 @@
+use protobuf::Message;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// sqlx::migrate! embeds migrations/ at compile time; rebuild when it changes.
+// The gRPC code is generated from proto/ with a pure-Rust parser, so building
+// doesn't need protoc installed.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=proto");
+    build_info();
+
+    let descriptors = protobuf_parse::Parser::new()
+        .pure()
+        .include("proto")
+        .input("proto/wallet.proto")
+        .file_descriptor_set()
+        .expect("proto/wallet.proto should parse");
+    let bytes = descriptors.write_to_bytes().expect("descriptors should encode");
+    let descriptors = <prost_types::FileDescriptorSet as prost::Message>::decode(bytes.as_slice()).expect("descriptors should decode");
+
+    tonic_prost_build::configure()
+        .compile_fds(descriptors)
+        .expect("gRPC code should generate");
+}
+
+/// `GIT_HASH` and `BUILD_TIME` (Unix seconds, or `SOURCE_DATE_EPOCH` for
+/// reproducible builds) for `/version` and `doctor`. Builds outside a git
+/// checkout report the hash as "unknown".
+fn build_info() {
+    if Path::new(".git").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        println!("cargo:rerun-if-changed=.git/refs");
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    let built_at = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()));
+
+    println!("cargo:rustc-env=GIT_HASH={}", hash);
+    println!("cargo:rustc-env=BUILD_TIME={}", built_at);
+}
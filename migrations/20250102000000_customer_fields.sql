@@ -0,0 +1,16 @@
+-- SEP-9 KYC attributes, each sealed under its own data key (envelope encryption).
+CREATE TABLE customer_fields (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    field_name TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    wrapped_key TEXT NOT NULL,
+    key_nonce TEXT NOT NULL,
+    key_id TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (user_id, field_name),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_customer_fields_key_id ON customer_fields(key_id);
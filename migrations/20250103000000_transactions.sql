@@ -0,0 +1,21 @@
+-- Payments the wallet submitted, plus history backfilled from Horizon. One row
+-- per payment operation, as seen from `account`.
+CREATE TABLE transactions (
+    id TEXT PRIMARY KEY,
+    account TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    operation_index INTEGER NOT NULL,
+    direction TEXT NOT NULL,
+    asset_code TEXT NOT NULL,
+    amount_stroops INTEGER NOT NULL,
+    counterparty TEXT NOT NULL,
+    memo TEXT,
+    status TEXT NOT NULL,
+    ledger INTEGER,
+    error TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (account, hash, operation_index)
+);
+
+CREATE INDEX idx_transactions_account_created_at ON transactions(account, created_at);
@@ -0,0 +1,20 @@
+-- Phone number for SMS verification codes and alerts. Codes are only required
+-- once the user has confirmed the number and switched verification on.
+ALTER TABLE user_settings ADD COLUMN sms_phone_number TEXT;
+ALTER TABLE user_settings ADD COLUMN sms_verification_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Every SMS handed to a provider, for per-country rate limits and cost reports.
+-- The destination number itself is not kept.
+CREATE TABLE sms_messages (
+    id TEXT PRIMARY KEY,
+    user_id TEXT,
+    country_code TEXT NOT NULL,
+    purpose TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    provider_message_id TEXT,
+    cost_usd REAL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_sms_messages_country ON sms_messages(country_code, created_at);
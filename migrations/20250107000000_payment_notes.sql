@@ -0,0 +1,18 @@
+-- End-to-end encrypted notes attached to payments between two wallet users.
+-- Only ciphertext is stored; the key is the X25519 secret of the two accounts.
+CREATE TABLE payment_notes (
+    id TEXT PRIMARY KEY,
+    transaction_hash TEXT NOT NULL,
+    sender_user_id TEXT NOT NULL,
+    sender_public_key TEXT NOT NULL,
+    recipient_user_id TEXT NOT NULL,
+    recipient_public_key TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (sender_user_id) REFERENCES users(id),
+    FOREIGN KEY (recipient_user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_payment_notes_sender ON payment_notes(sender_user_id, created_at);
+CREATE INDEX idx_payment_notes_recipient ON payment_notes(recipient_user_id, created_at);
@@ -0,0 +1,17 @@
+-- Indexes for history search, so filtered lookups stay fast on accounts with
+-- 100k+ payments. Memo text search scans the account's rows.
+CREATE INDEX idx_transactions_account_hash ON transactions(account, hash);
+CREATE INDEX idx_transactions_account_counterparty ON transactions(account, counterparty, created_at);
+CREATE INDEX idx_transactions_account_asset ON transactions(account, asset_code, created_at);
+CREATE INDEX idx_transactions_account_amount ON transactions(account, amount_stroops);
+
+-- Free-form labels users put on their payments, e.g. "rent" or "tax-2025".
+CREATE TABLE transaction_tags (
+    account TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (account, hash, tag)
+);
+
+CREATE INDEX idx_transaction_tags_tag ON transaction_tags(account, tag);
@@ -0,0 +1,14 @@
+-- Match incoming payments to users by their Stellar address, and stop the
+-- same address being linked to two accounts. Earlier builds allowed that, so
+-- only the account that has been around longest keeps a duplicated link; the
+-- others can link a different address.
+UPDATE users
+SET stellar_public_key = NULL
+WHERE stellar_public_key IS NOT NULL
+  AND EXISTS (
+      SELECT 1 FROM users AS older
+      WHERE older.stellar_public_key = users.stellar_public_key
+        AND (older.created_at < users.created_at OR (older.created_at = users.created_at AND older.id < users.id))
+  );
+
+CREATE UNIQUE INDEX idx_users_stellar_public_key ON users(stellar_public_key) WHERE stellar_public_key IS NOT NULL;
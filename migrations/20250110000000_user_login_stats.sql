@@ -0,0 +1,3 @@
+-- When each user last logged in and how often, for the login screen and reports.
+ALTER TABLE users ADD COLUMN last_login_at TEXT;
+ALTER TABLE users ADD COLUMN login_count INTEGER NOT NULL DEFAULT 0;
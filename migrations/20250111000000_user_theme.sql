@@ -0,0 +1,2 @@
+-- Color theme the CLI uses once the user logs in.
+ALTER TABLE user_settings ADD COLUMN theme TEXT NOT NULL DEFAULT 'default';
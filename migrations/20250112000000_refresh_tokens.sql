@@ -0,0 +1,12 @@
+-- Refresh tokens issued for a session, stored as SHA-256 hashes. Using one
+-- marks it used and issues the next; presenting a used token again means it
+-- was copied, and the whole session is revoked.
+CREATE TABLE refresh_tokens (
+    token_hash TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    used_at TEXT,
+    FOREIGN KEY (session_id) REFERENCES sessions(id)
+);
+
+CREATE INDEX idx_refresh_tokens_session_id ON refresh_tokens(session_id);
@@ -0,0 +1,26 @@
+-- TOTP second factor. The secret is sealed under CUSTOMER_DATA_KEYS like KYC
+-- fields; confirmed_at stays NULL until the user proves their app has it.
+-- last_used_step stops a code from being accepted twice.
+CREATE TABLE totp_secrets (
+    user_id TEXT PRIMARY KEY,
+    ciphertext TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    wrapped_key TEXT NOT NULL,
+    key_nonce TEXT NOT NULL,
+    key_id TEXT NOT NULL,
+    confirmed_at TEXT,
+    last_used_step INTEGER,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+-- One-time codes for when the authenticator app is lost, as SHA-256 hashes.
+-- A code is deleted when used.
+CREATE TABLE recovery_codes (
+    code_hash TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_recovery_codes_user_id ON recovery_codes(user_id);
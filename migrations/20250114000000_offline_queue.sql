@@ -0,0 +1,24 @@
+-- The last balances seen for each account, shown when Horizon can't be reached.
+CREATE TABLE balance_snapshots (
+    network TEXT NOT NULL,
+    account TEXT NOT NULL,
+    balances TEXT NOT NULL,
+    fetched_at TEXT NOT NULL,
+    PRIMARY KEY (network, account)
+);
+
+-- Payments written while offline. Nothing here has been signed or submitted;
+-- each is reviewed and sent with a fresh sequence number once back online.
+CREATE TABLE queued_payments (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    network TEXT NOT NULL,
+    source TEXT NOT NULL,
+    destination TEXT NOT NULL,
+    amount TEXT NOT NULL,
+    memo TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_queued_payments_user ON queued_payments(user_id, network, created_at);
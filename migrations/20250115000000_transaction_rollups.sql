@@ -0,0 +1,18 @@
+-- Monthly totals of the confirmed transactions `db archive` moved out of the
+-- `transactions` table into a compressed archive file.
+CREATE TABLE transaction_rollups (
+    account TEXT NOT NULL,
+    month TEXT NOT NULL,
+    asset_code TEXT NOT NULL,
+    direction TEXT NOT NULL,
+    payment_count INTEGER NOT NULL,
+    total_stroops INTEGER NOT NULL,
+    PRIMARY KEY (account, month, asset_code, direction)
+);
+
+-- History before `archived_before` lives in archive files. The Horizon
+-- backfill skips older payments so they aren't copied back in.
+CREATE TABLE transaction_archive_cutoffs (
+    account TEXT PRIMARY KEY,
+    archived_before TEXT NOT NULL
+);
@@ -0,0 +1,8 @@
+-- Consecutive failed logins per account (`account:<user id>`) and per source
+-- (`source:<device label>`), and until when each is locked out.
+CREATE TABLE login_throttles (
+    key TEXT PRIMARY KEY,
+    failures INTEGER NOT NULL,
+    locked_until TEXT,
+    last_failure_at TEXT NOT NULL
+);
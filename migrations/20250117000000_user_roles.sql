@@ -0,0 +1,4 @@
+-- What each account may do beyond its own wallet, and whether an admin
+-- disabled it.
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user';
+ALTER TABLE users ADD COLUMN disabled_at TEXT;
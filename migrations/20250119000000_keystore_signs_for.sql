@@ -0,0 +1,3 @@
+-- After a key rotation the account keeps its address but is signed for by a
+-- newer keystore key; that key records the account here.
+ALTER TABLE keystore ADD COLUMN signs_for TEXT;
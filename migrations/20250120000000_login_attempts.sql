@@ -0,0 +1,14 @@
+-- Every login attempt on a known account, so its owner can spot ones that
+-- weren't theirs. user_agent is only sent by API clients.
+CREATE TABLE login_attempts (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    succeeded BOOLEAN NOT NULL,
+    failure_reason TEXT,
+    source TEXT NOT NULL,
+    user_agent TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_login_attempts_user_id ON login_attempts(user_id, created_at);
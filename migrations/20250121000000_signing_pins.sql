@@ -0,0 +1,10 @@
+-- An optional short PIN asked for before each transaction is signed, separate
+-- from the login password. Failures in a row lock signing for a while.
+CREATE TABLE signing_pins (
+    user_id TEXT PRIMARY KEY,
+    pin_hash TEXT NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0,
+    locked_until TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
@@ -0,0 +1,16 @@
+-- One-time codes issued with a recovery phrase. Each holds its own copy of
+-- the phrase, encrypted with the code, so a user who forgot their password
+-- can still reach the phrase and set a new one. The codes themselves aren't
+-- stored.
+CREATE TABLE phrase_recovery_codes (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    encrypted_phrase TEXT NOT NULL,
+    salt TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    used_at TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_phrase_recovery_codes_user_id ON phrase_recovery_codes(user_id);
@@ -0,0 +1,13 @@
+-- A requested email change waiting for both confirmation codes: one sent to
+-- the current address and one to the new one. Only hashes of the codes are
+-- stored. A user has at most one pending change.
+CREATE TABLE email_changes (
+    user_id TEXT PRIMARY KEY,
+    new_email TEXT NOT NULL,
+    old_code_hash TEXT NOT NULL,
+    new_code_hash TEXT NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
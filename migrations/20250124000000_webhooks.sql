@@ -0,0 +1,49 @@
+-- When the account's email address was last verified, so the API server can
+-- announce verifications that happened in the CLI.
+ALTER TABLE users ADD COLUMN verified_at TEXT;
+
+-- URLs that get signed event notifications: a user's own endpoints receive
+-- their account's events, and an admin's endpoints with no user_id receive
+-- every account's. The signing secret is sealed under CUSTOMER_DATA_KEYS.
+-- event_types is a comma-separated list, e.g. 'payment.received,transaction.failed'.
+CREATE TABLE webhook_endpoints (
+    id TEXT PRIMARY KEY,
+    user_id TEXT,
+    url TEXT NOT NULL,
+    event_types TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    wrapped_key TEXT NOT NULL,
+    key_nonce TEXT NOT NULL,
+    key_id TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_webhook_endpoints_user_id ON webhook_endpoints(user_id);
+
+-- One row per event per endpoint, updated after every attempt until it is
+-- delivered or given up on. user_id is the account the event is about. Event
+-- ids come from what happened, so API servers sharing the database queue each
+-- event once.
+CREATE TABLE webhook_deliveries (
+    id TEXT PRIMARY KEY,
+    endpoint_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    last_error TEXT,
+    next_attempt_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (endpoint_id) REFERENCES webhook_endpoints(id)
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+CREATE UNIQUE INDEX idx_webhook_deliveries_event ON webhook_deliveries(endpoint_id, event_id);
+CREATE INDEX idx_webhook_deliveries_endpoint_id ON webhook_deliveries(endpoint_id, created_at);
@@ -0,0 +1,17 @@
+-- Idempotency-Key headers seen on signups and payments, so a retried request
+-- gets the first one's response instead of running again. A row without a
+-- response is still being handled. Rows are dropped after a day. Only a hash
+-- of the request's non-secret fields is kept to tell retries from reuse.
+CREATE TABLE idempotency_keys (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    user_id TEXT,
+    response_status INTEGER,
+    response_body TEXT,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+
+CREATE INDEX idx_idempotency_keys_created_at ON idempotency_keys (created_at);
+CREATE INDEX idx_idempotency_keys_user_id ON idempotency_keys (user_id);
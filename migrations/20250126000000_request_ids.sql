@@ -0,0 +1,8 @@
+-- The X-Request-Id of the API request behind each audit entry, submitted
+-- transaction and webhook delivery, so one request can be followed through
+-- all three. Empty for anything done outside the API.
+ALTER TABLE audit_log ADD COLUMN request_id TEXT;
+ALTER TABLE transactions ADD COLUMN request_id TEXT;
+ALTER TABLE webhook_deliveries ADD COLUMN request_id TEXT;
+
+CREATE INDEX idx_audit_log_request_id ON audit_log(request_id);
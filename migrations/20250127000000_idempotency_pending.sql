@@ -0,0 +1,4 @@
+-- The hash of a transaction a request signed and sent without learning how it
+-- ended, e.g. Horizon timed out. A retry with the key looks the transaction up
+-- instead of running the request again, which would sign a second one.
+ALTER TABLE idempotency_keys ADD COLUMN pending_hash TEXT;
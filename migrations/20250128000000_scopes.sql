@@ -0,0 +1,8 @@
+-- Scope names gained a namespace when logins got scopes too, e.g. `read` is
+-- now `read:account`.
+UPDATE api_keys
+SET scopes = replace(replace(replace(scopes, 'contacts', 'write:contacts'), 'payments', 'write:payments'), 'read', 'read:account');
+
+-- The scopes a login asked for, comma-separated. NULL grants every scope, as
+-- logins from before scopes had.
+ALTER TABLE sessions ADD COLUMN scopes TEXT;
@@ -0,0 +1,23 @@
+-- Accounts at OAuth providers (Google, GitHub) linked to users, so they can
+-- log in through the provider. A provider account belongs to one user, and a
+-- user links at most one account per provider.
+CREATE TABLE oauth_identities (
+    provider TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (provider, subject),
+    UNIQUE (user_id, provider),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+-- OAuth logins sent to a provider and not back yet. Only a hash of the state
+-- parameter is stored; coming back deletes the row, so each works once.
+CREATE TABLE oauth_states (
+    state_hash TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    code_verifier TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,27 @@
+-- WebAuthn credentials (passkeys) registered for API logins, as a second
+-- factor or without a password. `public_key` is the authenticator's COSE
+-- key; `sign_count` is its signature counter, which should only go up.
+CREATE TABLE passkeys (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    credential_id TEXT NOT NULL UNIQUE,
+    public_key BLOB NOT NULL,
+    sign_count INTEGER NOT NULL DEFAULT 0,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_used_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_passkeys_user_id ON passkeys (user_id);
+
+-- Challenges handed to browsers for a passkey registration (for the user)
+-- or assertion (for whoever answers). Answering one deletes it, so each
+-- works once. Only hashes are stored.
+CREATE TABLE webauthn_challenges (
+    challenge_hash TEXT PRIMARY KEY,
+    purpose TEXT NOT NULL,
+    user_id TEXT,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,13 @@
+-- Single-use tickets for opening the event stream from a browser's
+-- EventSource, which can't send an Authorization header. Only hashes are
+-- stored, and opening the stream deletes the row. The stream ends when the
+-- credential that asked for the ticket would have (`stream_expires_at`).
+CREATE TABLE stream_tickets (
+    ticket_hash TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    session_id TEXT,
+    stream_expires_at TEXT,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
@@ -0,0 +1,18 @@
+-- Orders every change to a transaction record, so `db export` can pick up
+-- exactly the rows inserted or changed since its last run without trusting
+-- timestamps. Each insert, and each update that changes what was recorded,
+-- takes the next number.
+ALTER TABLE transactions ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 0;
+UPDATE transactions SET change_seq = rowid;
+CREATE INDEX idx_transactions_change_seq ON transactions(change_seq);
+
+CREATE TRIGGER transactions_change_seq_insert AFTER INSERT ON transactions
+BEGIN
+    UPDATE transactions SET change_seq = (SELECT MAX(change_seq) + 1 FROM transactions) WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER transactions_change_seq_update AFTER UPDATE OF status, ledger, error, memo ON transactions
+WHEN OLD.status IS NOT NEW.status OR OLD.ledger IS NOT NEW.ledger OR OLD.error IS NOT NEW.error OR OLD.memo IS NOT NEW.memo
+BEGIN
+    UPDATE transactions SET change_seq = (SELECT MAX(change_seq) + 1 FROM transactions) WHERE id = NEW.id;
+END;
@@ -0,0 +1,18 @@
+-- Domain events waiting to be published to the message broker, kept for a
+-- while after they are. Event ids come from what happened, so API servers
+-- sharing the database queue each event once. next_attempt_at is NULL once
+-- the broker has stored the event.
+CREATE TABLE event_outbox (
+    event_id TEXT PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TEXT,
+    published_at TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_event_outbox_due ON event_outbox(next_attempt_at);
+CREATE INDEX idx_event_outbox_published_at ON event_outbox(published_at);
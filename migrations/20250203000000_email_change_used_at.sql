@@ -0,0 +1,4 @@
+-- Keeps a confirmed email change's code hashes for a while instead of
+-- deleting them, so the codes being tried again is recognised as reuse and
+-- the user is warned.
+ALTER TABLE email_changes ADD COLUMN used_at TEXT;
@@ -0,0 +1,3 @@
+-- Remembers which API key asked for a stream ticket, so the stream it opens
+-- ends when that key is revoked.
+ALTER TABLE stream_tickets ADD COLUMN api_key_id TEXT;
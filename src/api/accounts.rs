@@ -0,0 +1,130 @@
+use super::auth::Authenticated;
+use super::error::ApiResult;
+use super::idempotency::{idempotent, nothing_pending};
+use super::rate_limit::enforce;
+use super::v1::dto::User;
+use super::validation::{FieldErrors, Valid, Validate};
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::user::{CreateUserRequest, UserResponse};
+use crate::services::admin_service::MAX_BATCH_SIGNUPS;
+use crate::services::breach_check_service::BreachPolicy;
+use crate::services::rate_limit_service::LimitedAction;
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::Json;
+use schemars::JsonSchema;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchCreateAccountsRequest {
+    /// Up to 100 signups, each checked like `POST /accounts`.
+    pub accounts: Vec<CreateUserRequest>,
+}
+
+impl Validate for BatchCreateAccountsRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.accounts.is_empty() || self.accounts.len() > MAX_BATCH_SIGNUPS {
+            let message = format!("Send between 1 and {} accounts at a time", MAX_BATCH_SIGNUPS);
+            errors.check("accounts", Err(AppError::ValidationError(message)));
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchCreateAccountsResponse {
+    pub created: usize,
+    pub failed: usize,
+    /// One for each requested account, in the same order.
+    pub results: Vec<BatchAccountResult>,
+}
+
+/// Either `user` or `error` is set.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchAccountResult {
+    /// Position of the account in the request.
+    pub index: usize,
+    pub user: Option<User>,
+    pub error: Option<String>,
+}
+
+/// `POST /accounts`: signs up with an email, username and password. Shares
+/// the login rate limit for the client's IP address. A retry with the same
+/// `Idempotency-Key` gets the first signup's response.
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Valid(request): Valid<CreateUserRequest>,
+) -> ApiResult<Response> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+    let fingerprint = json!({ "email": request.email, "username": request.username });
+
+    let handle = async {
+        let user = sign_up(&state, request).await?;
+        Ok((user.id, User::from(user)))
+    };
+    idempotent(&state, &headers, "accounts", &fingerprint, StatusCode::CREATED, handle, nothing_pending).await
+}
+
+/// `POST /accounts/batch`: creates many accounts in one transaction, for
+/// staff moving an existing user base over. One account failing doesn't stop
+/// the others; each gets its own result.
+pub async fn create_batch(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    Valid(request): Valid<BatchCreateAccountsRequest>,
+) -> ApiResult<Json<BatchCreateAccountsResponse>> {
+    let results = state.admin_service.create_users(&caller.user_id, request.accounts).await?;
+
+    let results: Vec<_> = results
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| match result {
+            Ok(user) => BatchAccountResult {
+                index,
+                user: Some(user.into()),
+                error: None,
+            },
+            Err(e) => BatchAccountResult {
+                index,
+                user: None,
+                error: Some(e.message().to_string()),
+            },
+        })
+        .collect();
+    let created = results.iter().filter(|result| result.user.is_some()).count();
+    Ok(Json(BatchCreateAccountsResponse {
+        created,
+        failed: results.len() - created,
+        results,
+    }))
+}
+
+pub(super) async fn sign_up(state: &ApiState, request: CreateUserRequest) -> Result<UserResponse> {
+    if let Some(breach_check) = state.breach_check.as_ref().filter(|check| check.policy() == BreachPolicy::Reject) {
+        // Like the CLI, an unreachable breach service doesn't block signups.
+        if let Ok(times) = breach_check.times_breached(request.password.expose_secret()).await {
+            if times > 0 {
+                return Err(AppError::ValidationError(format!(
+                    "This password has appeared in {} known data breaches. Please choose another one.",
+                    times
+                )));
+            }
+        }
+    }
+
+    let user = state.user_service.create_user(request).await?;
+    state
+        .audit_service
+        .record(Some(&user.id), AuditEvent::AccountCreated, &format!("username '{}'", user.username))
+        .await?;
+
+    Ok(user)
+}
@@ -0,0 +1,577 @@
+use super::error::{ApiError, ApiResult};
+use super::rate_limit::enforce;
+use super::v1::dto::LoginResponse;
+use super::validation::Valid;
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::{ApiKey, ApiScope, API_KEY_PREFIX};
+use crate::models::audit::AuditEvent;
+use crate::models::login_throttle::LoginScope;
+use crate::models::oauth::OAuthProvider;
+use crate::models::passkey::PasskeyAssertion;
+use crate::models::session::Session;
+use crate::models::user::UserResponse;
+use crate::models::wallet_event::WalletEvent;
+use crate::services::oauth_service::OAuthService;
+use crate::services::passkey_service::PasskeyService;
+use crate::services::rate_limit_service::LimitedAction;
+use crate::services::two_factor_service::SecondFactor;
+use axum::extract::{ConnectInfo, FromRequestParts, Path, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, StatusCode};
+use axum::http::request::Parts;
+use axum::response::Redirect;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct LoginRequest {
+    /// Email or username.
+    pub identifier: String,
+    #[schemars(with = "String")]
+    pub password: SecretString,
+    /// An authenticator or recovery code, for accounts with 2FA on.
+    #[serde(default)]
+    pub code: Option<String>,
+    /// One of the account's passkeys answering `/auth/passkey/options`, for
+    /// accounts with a passkey; in place of `code` if 2FA is on too.
+    #[serde(default)]
+    pub passkey: Option<PasskeyAssertion>,
+    /// What the access token may be used for, e.g. `["read:account"]`;
+    /// every scope if left out. Ask for no more than the client needs.
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+}
+
+impl LoginRequest {
+    /// The scopes asked for, or every scope if none were.
+    pub fn requested_scopes(&self) -> Result<Vec<ApiScope>> {
+        requested_scopes(self.scopes.as_deref())
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RefreshRequest {
+    /// The refresh token from the login or the last refresh.
+    #[schemars(with = "String")]
+    pub refresh_token: SecretString,
+}
+
+/// What an OAuth provider sent the browser back to `OAUTH_REDIRECT_URL`
+/// with, for the client to finish the login.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct OAuthCallbackRequest {
+    /// The `state` query parameter the provider sent back.
+    pub state: String,
+    /// The `code` query parameter the provider sent back.
+    pub authorization_code: String,
+    /// An authenticator or recovery code, for accounts with 2FA on.
+    #[serde(default)]
+    pub code: Option<String>,
+    /// One of the account's passkeys answering `/auth/passkey/options`, for
+    /// accounts with a passkey; in place of `code` if 2FA is on too.
+    #[serde(default)]
+    pub passkey: Option<PasskeyAssertion>,
+    /// What the access token may be used for; every scope if left out.
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+}
+
+impl OAuthCallbackRequest {
+    /// The scopes asked for, or every scope if none were.
+    pub fn requested_scopes(&self) -> Result<Vec<ApiScope>> {
+        requested_scopes(self.scopes.as_deref())
+    }
+}
+
+/// A login with only a passkey.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PasskeyLoginRequest {
+    /// A passkey answering `/auth/passkey/options` after checking the user's
+    /// PIN or biometric.
+    pub passkey: PasskeyAssertion,
+    /// What the access token may be used for; every scope if left out.
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+}
+
+impl PasskeyLoginRequest {
+    /// The scopes asked for, or every scope if none were.
+    pub fn requested_scopes(&self) -> Result<Vec<ApiScope>> {
+        requested_scopes(self.scopes.as_deref())
+    }
+}
+
+fn requested_scopes(names: Option<&[String]>) -> Result<Vec<ApiScope>> {
+    let Some(names) = names else {
+        return Ok(ApiScope::ALL.to_vec());
+    };
+    let scopes = ApiScope::parse_list(names.iter().map(String::as_str))
+        .map_err(|scope| AppError::ValidationError(format!("Unknown scope '{}'", scope)))?;
+    if scopes.is_empty() {
+        return Err(AppError::ValidationError("Ask for at least one scope, or leave 'scopes' out".to_string()));
+    }
+    Ok(scopes)
+}
+
+/// A successful login, whichever API it came through.
+pub struct Login {
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Trades for the next access token once this one expires. Works once.
+    pub refresh_token: String,
+    /// What the access token may be used for.
+    pub scopes: Vec<ApiScope>,
+    pub user: UserResponse,
+}
+
+/// Leaves the tokens out, so logging a login doesn't leak it.
+impl fmt::Debug for Login {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Login")
+            .field("access_token", &"[redacted]")
+            .field("expires_at", &self.expires_at)
+            .field("refresh_token", &"[redacted]")
+            .field("scopes", &self.scopes)
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
+/// `POST /auth/login`: the CLI login for API clients, with the same lockouts,
+/// audit entries and login history. Failures count against the client's IP
+/// address and, if it exists, the account; every attempt counts against the
+/// IP address's rate limit.
+pub async fn login(
+    State(state): State<Arc<ApiState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Valid(request): Valid<LoginRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+    Ok(Json(log_in(&state, format!("api {}", peer.ip()), request).await?.into()))
+}
+
+/// Logs in from `source`, which names the client in login history and is
+/// the lockout scope for failures that aren't tied to an account.
+pub(super) async fn log_in(state: &ApiState, source: String, request: LoginRequest) -> Result<Login> {
+    let granted = request.requested_scopes()?;
+    let user_id = state.user_service.find_user(&request.identifier).await?.map(|user| user.id);
+    let mut scopes = vec![LoginScope::Source(source.clone())];
+    scopes.extend(user_id.map(LoginScope::Account));
+
+    if let Err(e) = state.login_throttle_service.check(&scopes, Utc::now()).await {
+        state
+            .audit_service
+            .record(user_id.as_ref(), AuditEvent::LoginFailed, &format!("identifier '{}': locked out", request.identifier))
+            .await?;
+        if let Some(user_id) = user_id {
+            record_in_background(state, user_id, &source, "locked out", None);
+        }
+        return Err(e);
+    }
+
+    let user = match state.user_service.authenticate_user(&request.identifier, request.password.expose_secret()).await {
+        Ok(user) => user,
+        Err(e) => {
+            let reason = match &e {
+                AppError::AuthenticationError(reason) => reason.clone(),
+                other => other.to_string(),
+            };
+            let details = format!("identifier '{}'", request.identifier);
+            let alert = Some("someone just failed to log in to your account.");
+            record_failed_login(state, user_id.as_ref(), &source, &scopes, &details, &reason, alert).await?;
+            return Err(e);
+        }
+    };
+
+    check_second_factor(state, &user.id, &source, &scopes, request.code.as_deref(), request.passkey.as_ref()).await?;
+    start_session(state, user, &source, &granted).await
+}
+
+/// Checks the second factor of a login that got past its first: a passkey,
+/// or what [`second_factor_confirmed`] asks for. Accounts with a passkey
+/// need it, unless 2FA is on and they send the code instead.
+async fn check_second_factor(
+    state: &ApiState,
+    user_id: &Uuid,
+    source: &str,
+    scopes: &[LoginScope],
+    code: Option<&str>,
+    passkey: Option<&PasskeyAssertion>,
+) -> Result<()> {
+    if let Some(assertion) = passkey {
+        if let Err(e) = passkeys(state)?.authenticate(assertion, Some(user_id), false).await {
+            if let AppError::AuthenticationError(reason) = &e {
+                let details = format!("passkey: {}", reason);
+                record_failed_login(state, Some(user_id), source, scopes, &details, "passkey not confirmed", None).await?;
+            }
+            return Err(e);
+        }
+        return Ok(());
+    }
+
+    if let Some(passkeys) = &state.passkeys {
+        if passkeys.has_passkeys(user_id).await? && !state.two_factor_service.is_enabled(user_id).await? {
+            return Err(AppError::AuthenticationError(
+                "This account logs in with a passkey; send its answer to /auth/passkey/options as 'passkey'".to_string(),
+            ));
+        }
+    }
+    if !second_factor_confirmed(state, user_id, code, "logins").await? {
+        let reason = "2FA code not confirmed";
+        record_failed_login(state, Some(user_id), source, scopes, reason, reason, None).await?;
+        return Err(AppError::AuthenticationError(reason.to_string()));
+    }
+    Ok(())
+}
+
+/// The end of every kind of login, once `user` has proved who they are and
+/// passed their second factor: a session from `source` with the `granted`
+/// scopes.
+async fn start_session(state: &ApiState, user: UserResponse, source: &str, granted: &[ApiScope]) -> Result<Login> {
+    state.login_throttle_service.record_success(&user.id).await?;
+
+    let session = state.session_service.start_with_scopes(&user.id, source, granted).await?;
+    state
+        .audit_service
+        .record(Some(&user.id), AuditEvent::LoginSucceeded, &format!("session {} ({})", session.id, session.device_label))
+        .await?;
+    state.login_history_service.record(&user.id, source, None).await?;
+
+    let refresh_token = state.session_service.issue_refresh_token(&session).await?;
+    grant(state, session, refresh_token, user)
+}
+
+/// `GET /auth/oauth/{provider}`: sends the browser to log in with `google`
+/// or `github`. The provider sends it back to `OAUTH_REDIRECT_URL` with the
+/// `state` and `code` to finish the login with [`oauth_callback`]. Counts
+/// against the IP address's rate limit.
+pub async fn oauth_start(
+    State(state): State<Arc<ApiState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Path(provider): Path<String>,
+) -> ApiResult<Redirect> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+    let url = oauth(&state)?.authorization_url(oauth_provider(&provider)?).await?;
+    Ok(Redirect::to(&url))
+}
+
+/// `POST /auth/oauth/{provider}/callback`: logs in as the user the provider
+/// account is linked to, linking it by its verified email the first time.
+/// Accounts with 2FA on still need their code, and lockouts apply as for
+/// password logins. Every attempt counts against the IP address's rate limit.
+pub async fn oauth_callback(
+    State(state): State<Arc<ApiState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Path(provider): Path<String>,
+    Valid(request): Valid<OAuthCallbackRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+    let provider = oauth_provider(&provider)?;
+    let granted = request.requested_scopes()?;
+
+    let user_id = match oauth(&state)?.complete(provider, &request.state, &request.authorization_code).await {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            let details = format!("{} login: {}", provider.as_str(), e.message());
+            state.audit_service.record(None, AuditEvent::LoginFailed, &details).await?;
+            return Err(e.into());
+        }
+    };
+    let scopes = [LoginScope::Source(format!("api {}", peer.ip())), LoginScope::Account(user_id)];
+    if let Err(e) = state.login_throttle_service.check(&scopes, Utc::now()).await {
+        let details = format!("{} login: locked out", provider.as_str());
+        state.audit_service.record(Some(&user_id), AuditEvent::LoginFailed, &details).await?;
+        return Err(e.into());
+    }
+
+    let user = state.user_service.authenticate_linked_user(&user_id).await?;
+    let source = format!("api {} via {}", peer.ip(), provider.display_name());
+    check_second_factor(&state, &user.id, &source, &scopes, request.code.as_deref(), request.passkey.as_ref()).await?;
+    Ok(Json(start_session(&state, user, &source, &granted).await?.into()))
+}
+
+fn oauth(state: &ApiState) -> Result<&OAuthService> {
+    state
+        .oauth
+        .as_ref()
+        .ok_or_else(|| AppError::ValidationError("Logging in with OAuth isn't set up here".to_string()))
+}
+
+fn oauth_provider(name: &str) -> Result<OAuthProvider> {
+    OAuthProvider::parse(name)
+        .ok_or_else(|| AppError::ValidationError(format!("Unknown OAuth provider '{}'; use google or github", name)))
+}
+
+/// `POST /auth/passkey/options`: a challenge for `navigator.credentials.get()`,
+/// to log in with a passkey or to confirm a password or OAuth login with one.
+/// Counts against the IP address's rate limit.
+pub async fn passkey_options(State(state): State<Arc<ApiState>>, ConnectInfo(peer): ConnectInfo<SocketAddr>) -> ApiResult<Json<Value>> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+    Ok(Json(passkeys(&state)?.authentication_options().await?))
+}
+
+/// `POST /auth/passkey`: logs in without a password as the owner of a
+/// passkey that checked their PIN or biometric, which stands in for both the
+/// password and 2FA. Lockouts apply as for password logins. Every attempt
+/// counts against the IP address's rate limit.
+pub async fn passkey_login(
+    State(state): State<Arc<ApiState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Valid(request): Valid<PasskeyLoginRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+    let granted = request.requested_scopes()?;
+
+    let passkey = match passkeys(&state)?.authenticate(&request.passkey, None, true).await {
+        Ok(passkey) => passkey,
+        Err(e) => {
+            state.audit_service.record(None, AuditEvent::LoginFailed, &format!("passkey login: {}", e.message())).await?;
+            return Err(e.into());
+        }
+    };
+    let scopes = [LoginScope::Source(format!("api {}", peer.ip())), LoginScope::Account(passkey.user_id)];
+    if let Err(e) = state.login_throttle_service.check(&scopes, Utc::now()).await {
+        state.audit_service.record(Some(&passkey.user_id), AuditEvent::LoginFailed, "passkey login: locked out").await?;
+        return Err(e.into());
+    }
+
+    let user = state.user_service.authenticate_linked_user(&passkey.user_id).await?;
+    let source = format!("api {} with passkey '{}'", peer.ip(), passkey.name);
+    Ok(Json(start_session(&state, user, &source, &granted).await?.into()))
+}
+
+pub(super) fn passkeys(state: &ApiState) -> Result<&PasskeyService> {
+    state
+        .passkeys
+        .as_ref()
+        .ok_or_else(|| AppError::ValidationError("Passkeys aren't set up here".to_string()))
+}
+
+/// `POST /auth/refresh`: trades a refresh token for a new access token and
+/// the next refresh token, for the same session and scopes. A refresh token
+/// that was already used ends the session. Every attempt counts against the
+/// IP address's rate limit.
+pub async fn refresh(
+    State(state): State<Arc<ApiState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Valid(request): Valid<RefreshRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    enforce(&state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+
+    let (session, refresh_token) = state.session_service.rotate_refresh_token(request.refresh_token.expose_secret().trim()).await?;
+    let user = state
+        .user_service
+        .get_user(&session.user_id)
+        .await?
+        .ok_or_else(|| AppError::AuthenticationError("Your session has ended; please log in again".to_string()))?;
+    Ok(Json(grant(&state, session, refresh_token, user)?.into()))
+}
+
+/// `POST /auth/logout`: ends the session the access token belongs to, along
+/// with its refresh token. API keys have no session; revoke them instead.
+pub async fn logout(State(state): State<Arc<ApiState>>, headers: HeaderMap) -> ApiResult<StatusCode> {
+    let token = bearer_token(&headers)
+        .ok_or_else(|| AppError::AuthenticationError("Send the access token as 'Authorization: Bearer <token>'".to_string()))?
+        .trim();
+    if token.starts_with(API_KEY_PREFIX) {
+        return Err(AppError::AuthenticationError("API keys can't log out; revoke the key instead".to_string()).into());
+    }
+
+    let session = state.tokens.authenticate(token, &state.session_service).await?;
+    state.session_service.logout(&session.user_id, &session.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// An access token for `session`, handed out with `refresh_token`.
+fn grant(state: &ApiState, session: Session, refresh_token: String, user: UserResponse) -> Result<Login> {
+    let access_token = state.tokens.issue(&session)?;
+    let expires_at = DateTime::from_timestamp(state.tokens.verify(&access_token)?.exp, 0).unwrap_or(session.expires_at);
+
+    Ok(Login {
+        access_token,
+        expires_at,
+        refresh_token,
+        scopes: session.scopes,
+        user,
+    })
+}
+
+/// Whether the user passed their second factor, if they have one, before
+/// `action` ("logins", "payments"). Accounts that confirm these by SMS can't
+/// use the API for them yet: there is no second request to carry the texted
+/// code.
+pub(super) async fn second_factor_confirmed(state: &ApiState, user_id: &Uuid, code: Option<&str>, action: &str) -> Result<bool> {
+    if state.settings_service.settings(user_id).await?.sms_verification_number().is_some() {
+        return Err(AppError::AuthenticationError(format!(
+            "This account confirms {} with an SMS code, which the API doesn't support; use the wallet instead",
+            action
+        )));
+    }
+    if !state.two_factor_service.is_enabled(user_id).await? {
+        return Ok(true);
+    }
+
+    let Some(code) = code.filter(|code| !code.trim().is_empty()) else {
+        return Err(AppError::AuthenticationError(
+            "Two-factor authentication is on; send an authenticator or recovery code as 'code'".to_string(),
+        ));
+    };
+    Ok(state.two_factor_service.verify(user_id, code, Utc::now()).await? != SecondFactor::Rejected)
+}
+
+async fn record_failed_login(
+    state: &ApiState,
+    user_id: Option<&Uuid>,
+    source: &str,
+    scopes: &[LoginScope],
+    details: &str,
+    reason: &str,
+    alert: Option<&'static str>,
+) -> Result<()> {
+    state.audit_service.record(user_id, AuditEvent::LoginFailed, details).await?;
+    state.login_throttle_service.record_failure(scopes, Utc::now()).await?;
+    if let Some(user_id) = user_id {
+        record_in_background(state, *user_id, source, reason, alert);
+    }
+    Ok(())
+}
+
+/// Adds a failed attempt to the account's login history and texts its owner
+/// `alert`, without making the client wait. Failures on identifiers without
+/// an account do neither, so waiting would let response times tell which
+/// identifiers have one.
+fn record_in_background(state: &ApiState, user_id: Uuid, source: &str, reason: &str, alert: Option<&'static str>) {
+    let login_history_service = state.login_history_service.clone();
+    let sms_service = state.sms_service.clone();
+    let (source, reason) = (source.to_string(), reason.to_string());
+
+    tokio::spawn(async move {
+        if let Err(e) = login_history_service.record(&user_id, &source, Some(&reason)).await {
+            tracing::warn!("Failed to add a failed login to {}'s history: {}", user_id, e);
+        }
+        if let Some(alert) = alert {
+            sms_service.alert(&user_id, alert).await;
+        }
+    });
+}
+
+/// Who a request acts for: a login's session, or one of the user's API keys.
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub user_id: Uuid,
+    /// The login's session; `None` for API keys.
+    pub session_id: Option<Uuid>,
+    /// The API key; `None` for logins.
+    pub api_key_id: Option<Uuid>,
+    /// When the access token or API key stops working; `None` for API keys
+    /// that don't expire. Streams end then.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Caller {
+    /// Finishes at [`Caller::expires_at`], or never.
+    pub fn expiry(&self) -> impl Future<Output = ()> + Send + 'static {
+        let remaining = self.expires_at.map(|at| (at - Utc::now()).to_std().unwrap_or_default());
+        async move {
+            match remaining {
+                Some(remaining) => tokio::time::sleep(remaining).await,
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    /// Whether `event` takes this caller's access away: its own session or
+    /// API key being revoked, or the account being disabled. Streams end on it.
+    pub fn ends_with(&self, event: &WalletEvent) -> bool {
+        match event {
+            WalletEvent::SessionRevoked { session_id } => self.session_id == Some(*session_id),
+            WalletEvent::ApiKeyRevoked { key_id } => self.api_key_id == Some(*key_id),
+            WalletEvent::AccountDisabled { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<Session> for Caller {
+    fn from(session: Session) -> Self {
+        Self {
+            user_id: session.user_id,
+            session_id: Some(session.id),
+            api_key_id: None,
+            expires_at: Some(session.expires_at),
+        }
+    }
+}
+
+impl From<ApiKey> for Caller {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            user_id: key.user_id,
+            session_id: None,
+            api_key_id: Some(key.id),
+            expires_at: key.expires_at,
+        }
+    }
+}
+
+/// Whoever a request's `Authorization: Bearer` header stands for: an access
+/// token's active session or an API key, either holding the scope the route
+/// was given as an `Extension<ApiScope>`. Routes without one need every
+/// scope. Handlers that take this reject requests without either.
+pub struct Authenticated(pub Caller);
+
+impl FromRequestParts<Arc<ApiState>> for Authenticated {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<ApiState>) -> ApiResult<Self> {
+        let token = bearer_token(&parts.headers).ok_or_else(|| {
+            AppError::AuthenticationError("Send an access token or API key as 'Authorization: Bearer <token>'".to_string())
+        })?;
+
+        let scope = parts.extensions.get::<ApiScope>().copied();
+        Ok(Self(authenticate(state, token, scope).await?))
+    }
+}
+
+/// Authenticates `token`, an access token or an API key, which must hold
+/// `scope`, or every scope without one.
+pub(super) async fn authenticate(state: &ApiState, token: &str, scope: Option<ApiScope>) -> Result<Caller> {
+    let token = token.trim();
+    if token.starts_with(API_KEY_PREFIX) {
+        let scope = scope.ok_or_else(|| AppError::AuthenticationError("API keys can't be used for this; log in instead".to_string()))?;
+        return Ok(state.api_key_service.authenticate(token, scope).await?.into());
+    }
+
+    let exp = state.tokens.verify(token)?.exp;
+    let session = state.tokens.authenticate(token, &state.session_service).await?;
+    let missing = match scope {
+        Some(scope) => (!session.scopes.contains(&scope)).then_some(scope),
+        None => ApiScope::ALL.into_iter().find(|scope| !session.scopes.contains(scope)),
+    };
+    if let Some(scope) = missing {
+        return Err(AppError::AuthenticationError(format!("This login doesn't have the '{}' scope", scope.as_str())));
+    }
+    let expires_at = DateTime::from_timestamp(exp, 0).map_or(session.expires_at, |exp| exp.min(session.expires_at));
+    Ok(Caller {
+        expires_at: Some(expires_at),
+        ..session.into()
+    })
+}
+
+/// The token in an `Authorization: Bearer <token>` header.
+pub(super) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
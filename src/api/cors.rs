@@ -0,0 +1,156 @@
+use super::request_id::REQUEST_ID;
+use crate::config;
+use crate::errors::{AppError, Result};
+use axum::http::{HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Methods browsers may use when `API_CORS_ALLOWED_METHODS` isn't set.
+const DEFAULT_METHODS: &str = "GET, POST";
+/// Request headers browsers may send when `API_CORS_ALLOWED_HEADERS` isn't set.
+const DEFAULT_HEADERS: &str = "authorization, content-type, idempotency-key, x-request-id";
+/// How long browsers may cache a preflight answer.
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Which web pages may call the API from a browser. Without a policy the
+/// API sends no CORS headers, so only same-origin pages can read responses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsPolicy {
+    /// `None` allows any origin.
+    origins: Option<Vec<HeaderValue>>,
+    methods: Vec<Method>,
+    headers: Vec<HeaderName>,
+}
+
+impl CorsPolicy {
+    /// Set up from `API_CORS_ALLOWED_ORIGINS`, a comma-separated list such as
+    /// `https://wallet.example.com` or `*` for any page; `None` when it is
+    /// unset. `API_CORS_ALLOWED_METHODS` and `API_CORS_ALLOWED_HEADERS`
+    /// default to `GET, POST` and `authorization, content-type, idempotency-key,
+    /// x-request-id`. Browsers may read the `X-Request-Id` of responses.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(origins) = config::var("API_CORS_ALLOWED_ORIGINS") else {
+            return Ok(None);
+        };
+        let methods = config::var("API_CORS_ALLOWED_METHODS").unwrap_or_else(|| DEFAULT_METHODS.to_string());
+        let headers = config::var("API_CORS_ALLOWED_HEADERS").unwrap_or_else(|| DEFAULT_HEADERS.to_string());
+
+        Self::parse(&origins, &methods, &headers).map(Some)
+    }
+
+    pub fn parse(origins: &str, methods: &str, headers: &str) -> Result<Self> {
+        let origins = if origins.trim() == "*" {
+            None
+        } else {
+            Some(list(origins).map(parse_origin).collect::<Result<Vec<_>>>()?)
+        };
+        let methods = list(methods)
+            .map(|method| {
+                Method::from_bytes(method.to_uppercase().as_bytes())
+                    .map_err(|_| AppError::ValidationError(format!("Invalid HTTP method '{}' in API_CORS_ALLOWED_METHODS", method)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let headers = list(headers)
+            .map(|header| {
+                HeaderName::from_bytes(header.to_lowercase().as_bytes())
+                    .map_err(|_| AppError::ValidationError(format!("Invalid header name '{}' in API_CORS_ALLOWED_HEADERS", header)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { origins, methods, headers })
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let origins = match &self.origins {
+            Some(origins) => AllowOrigin::list(origins.iter().cloned()),
+            None => AllowOrigin::any(),
+        };
+
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(self.methods.clone())
+            .allow_headers(self.headers.clone())
+            .expose_headers([REQUEST_ID])
+            .max_age(PREFLIGHT_MAX_AGE)
+    }
+}
+
+fn list(spec: &str) -> impl Iterator<Item = &str> {
+    spec.split(',').map(str::trim).filter(|entry| !entry.is_empty())
+}
+
+/// Browsers send `Origin` as scheme, host and port with no path, so a
+/// trailing slash in the setting would never match.
+fn parse_origin(origin: &str) -> Result<HeaderValue> {
+    let origin = origin.trim_end_matches('/');
+    let invalid = || AppError::ValidationError(format!("Invalid origin '{}' in API_CORS_ALLOWED_ORIGINS, e.g. https://wallet.example.com", origin));
+
+    let host = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .filter(|host| !host.is_empty() && !host.contains('/'))
+        .ok_or_else(invalid)?;
+    if host.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    HeaderValue::from_str(origin).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{serve, ApiState};
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_lists_and_normalises_case() {
+        let policy = CorsPolicy::parse("https://wallet.example.com/, http://localhost:3000", "get, Post", "Authorization").unwrap();
+
+        assert_eq!(
+            policy.origins,
+            Some(vec![HeaderValue::from_static("https://wallet.example.com"), HeaderValue::from_static("http://localhost:3000")])
+        );
+        assert_eq!(policy.methods, vec![Method::GET, Method::POST]);
+        assert_eq!(policy.headers, vec![HeaderName::from_static("authorization")]);
+        assert_eq!(CorsPolicy::parse(" * ", DEFAULT_METHODS, DEFAULT_HEADERS).unwrap().origins, None);
+    }
+
+    #[test]
+    fn rejects_origins_that_could_never_match() {
+        for origin in ["wallet.example.com", "https://", "https://wallet.example.com/app", "ftp://wallet.example.com"] {
+            assert!(CorsPolicy::parse(origin, DEFAULT_METHODS, DEFAULT_HEADERS).is_err(), "{} was accepted", origin);
+        }
+        assert!(CorsPolicy::parse("*", "GET, NOT A METHOD", DEFAULT_HEADERS).is_err());
+        assert!(CorsPolicy::parse("*", DEFAULT_METHODS, "x-ok, bad header").is_err());
+    }
+
+    #[tokio::test]
+    async fn answers_preflights_from_allowed_origins_only() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let policy = CorsPolicy::parse("https://wallet.example.com", DEFAULT_METHODS, DEFAULT_HEADERS).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/auth/login", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens).with_cors(policy)));
+        let client = reqwest::Client::new();
+
+        let preflight = |origin: &'static str| {
+            client
+                .request(reqwest::Method::OPTIONS, &url)
+                .header("origin", origin)
+                .header("access-control-request-method", "POST")
+                .header("access-control-request-headers", "content-type")
+                .send()
+        };
+
+        let allowed = preflight("https://wallet.example.com").await.unwrap();
+        assert_eq!(allowed.headers()["access-control-allow-origin"], "https://wallet.example.com");
+        assert!(allowed.headers()["access-control-allow-methods"].to_str().unwrap().contains("POST"));
+
+        let other = preflight("https://evil.example.com").await.unwrap();
+        assert!(other.headers().get("access-control-allow-origin").is_none());
+    }
+}
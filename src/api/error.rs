@@ -0,0 +1,191 @@
+use crate::errors::{AppError, ErrorCode};
+use crate::utils::request_id;
+use async_graphql::ErrorExtensions;
+use axum::http::header::RETRY_AFTER;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tonic::metadata::{MetadataMap, MetadataValue};
+
+/// An [`AppError`] as a JSON response, GraphQL error or gRPC status. Database
+/// and internal errors are logged and reported without their details.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    error: AppError,
+    details: Option<Value>,
+    retry_after: Option<Duration>,
+}
+
+impl From<AppError> for ApiError {
+    fn from(error: AppError) -> Self {
+        let details = match &error {
+            AppError::UnconfirmedError { hash, .. } => Some(json!({ "hash": hash })),
+            _ => None,
+        };
+        Self {
+            error,
+            details,
+            retry_after: None,
+        }
+    }
+}
+
+/// Drops the details, for callers that report errors outside an API.
+impl From<ApiError> for AppError {
+    fn from(error: ApiError) -> Self {
+        error.error
+    }
+}
+
+impl ApiError {
+    /// Too many requests; the client may try again after `wait`, which is
+    /// sent as `Retry-After` and `details.retry_after_secs`.
+    pub fn rate_limited(wait: Duration) -> Self {
+        let secs = retry_after_secs(wait);
+        Self {
+            error: AppError::RateLimitError(format!("Too many requests; try again in {} seconds", secs)),
+            details: Some(json!({ "retry_after_secs": secs })),
+            retry_after: Some(wait),
+        }
+    }
+
+    /// Adds structured context for clients, such as per-field messages.
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// The status for this error and the body clients may see.
+    pub fn public(self) -> (StatusCode, ErrorResponse) {
+        let code = self.error.code();
+        let status = StatusCode::from_u16(code.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let request_id = request_id::current();
+        if !code.is_public() {
+            eprintln!("API request {} failed: {}", request_id.as_deref().unwrap_or("-"), self.error);
+            let message = "Something went wrong; try again later".to_string();
+            return (status, ErrorResponse { code, message, details: None, request_id });
+        }
+
+        let message = self.error.message().to_string();
+        (status, ErrorResponse { code, message, details: self.details, request_id })
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let retry_after = self.retry_after.map(retry_after_secs);
+        let (status, body) = self.public();
+        match retry_after {
+            Some(secs) => (status, [(RETRY_AFTER, secs.to_string())], Json(body)).into_response(),
+            None => (status, Json(body)).into_response(),
+        }
+    }
+}
+
+/// GraphQL errors carry the error code and the HTTP status the REST
+/// endpoints would have used as the `code` and `status` extensions.
+impl From<ApiError> for async_graphql::Error {
+    fn from(error: ApiError) -> Self {
+        let (status, body) = error.public();
+        async_graphql::Error::new(body.message).extend_with(|_, extensions| {
+            extensions.set("code", body.code.as_str());
+            extensions.set("status", status.as_u16());
+            if let Some(request_id) = &body.request_id {
+                extensions.set("request_id", request_id.as_str());
+            }
+        })
+    }
+}
+
+/// gRPC statuses use the code closest to the HTTP status, with the error
+/// code in the `error-code` metadata and any wait in `retry-after`.
+impl From<ApiError> for tonic::Status {
+    fn from(error: ApiError) -> Self {
+        let retry_after = error.retry_after.map(retry_after_secs);
+        let (status, body) = error.public();
+        let code = match status {
+            StatusCode::BAD_REQUEST => tonic::Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => tonic::Code::Unauthenticated,
+            StatusCode::TOO_MANY_REQUESTS => tonic::Code::ResourceExhausted,
+            StatusCode::CONFLICT => tonic::Code::Aborted,
+            StatusCode::BAD_GATEWAY => tonic::Code::Unavailable,
+            StatusCode::GATEWAY_TIMEOUT => tonic::Code::DeadlineExceeded,
+            _ => tonic::Code::Internal,
+        };
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert("error-code", MetadataValue::from_static(body.code.as_str()));
+        if let Some(secs) = retry_after {
+            metadata.insert("retry-after", MetadataValue::from(secs));
+        }
+        if let Some(request_id) = body.request_id.and_then(|id| MetadataValue::try_from(id).ok()) {
+            metadata.insert("x-request-id", request_id);
+        }
+        tonic::Status::with_metadata(code, body.message, metadata)
+    }
+}
+
+/// The body of every error response.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    /// More about the error where there is more to say; otherwise null.
+    pub details: Option<Value>,
+    /// The request's `X-Request-Id`, to quote when reporting a problem.
+    pub request_id: Option<String>,
+}
+
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// Whole seconds, rounded up so clients don't come back a moment too soon.
+fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_millis().div_ceil(1000).max(1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_codes_and_hides_server_side_details() {
+        let (status, body) = ApiError::from(AppError::ValidationError("Amount must be positive".to_string())).public();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, ErrorCode::ValidationFailed);
+        assert_eq!(body.message, "Amount must be positive");
+
+        let (status, body) = ApiError::from(AppError::DatabaseError("no such table: users".to_string())).public();
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(serde_json::to_value(&body).unwrap()["code"], "database_error");
+        assert!(!body.message.contains("users"));
+
+        let status = tonic::Status::from(ApiError::from(AppError::StellarError("tx_bad_seq".to_string())));
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert_eq!(status.message(), "tx_bad_seq");
+        assert_eq!(status.metadata().get("error-code").unwrap(), "stellar_error");
+
+        let unconfirmed = AppError::UnconfirmedError {
+            hash: "ab12".to_string(),
+            message: "Horizon timed out".to_string(),
+        };
+        let (status, body) = ApiError::from(unconfirmed).public();
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body.details, Some(json!({ "hash": "ab12" })));
+    }
+
+    #[test]
+    fn rate_limits_say_when_to_retry() {
+        let response = ApiError::rate_limited(Duration::from_millis(12_001)).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "13");
+
+        let status = tonic::Status::from(ApiError::rate_limited(Duration::from_millis(200)));
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "1");
+        assert_eq!(status.metadata().get("error-code").unwrap(), "rate_limited");
+    }
+}
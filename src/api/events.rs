@@ -0,0 +1,159 @@
+use super::auth::{self, Caller};
+use super::error::ApiError;
+use super::v1::dto::Event;
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a new connection has to send its access token.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Not `Debug`, so the token can't end up in a log.
+#[derive(Deserialize)]
+struct Hello {
+    token: String,
+}
+
+/// `GET /v1/ws`: the user's account [`Event`]s as JSON text messages. Browsers
+/// can't set headers on WebSockets, so the first message authenticates:
+/// `{"token": "<access token or API key>"}`, answered with `{"type": "ready"}` or
+/// `{"type": "error", "error": <error response>}`. The connection closes once
+/// its own session or API key is revoked, the account is disabled, or the
+/// token expires.
+pub async fn connect(State(state): State<Arc<ApiState>>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| listen(state, socket))
+}
+
+async fn listen(state: Arc<ApiState>, mut socket: WebSocket) {
+    let caller = match authenticate(&state, &mut socket).await {
+        Ok(caller) => caller,
+        Err(e) => {
+            let (_, error) = ApiError::from(e).public();
+            let _ = socket.send(text(&json!({ "type": "error", "error": error }))).await;
+            return;
+        }
+    };
+
+    let mut events = state.events.subscribe(caller.user_id);
+    if socket.send(text(&json!({ "type": "ready" }))).await.is_err() {
+        return;
+    }
+
+    let expiry = caller.expiry();
+    tokio::pin!(expiry);
+    loop {
+        tokio::select! {
+            _ = &mut expiry => break,
+            event = events.next() => {
+                let Some(event) = event else { break };
+                let ended = caller.ends_with(&event);
+                if socket.send(text(&Event::from(event))).await.is_err() || ended {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn authenticate(state: &ApiState, socket: &mut WebSocket) -> Result<Caller> {
+    let rejected = || AppError::AuthenticationError("Send {\"token\": \"<access token or API key>\"} first".to_string());
+
+    let hello = match tokio::time::timeout(AUTH_TIMEOUT, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(hello)))) => hello,
+        _ => return Err(rejected()),
+    };
+    let hello: Hello = serde_json::from_str(hello.as_str()).map_err(|_| rejected())?;
+    auth::authenticate(state, &hello.token, Some(ApiScope::Read)).await
+}
+
+fn text(value: &impl Serialize) -> Message {
+    Message::Text(serde_json::to_string(value).unwrap_or_default().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::serve;
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::api_key_service::ApiKeyService;
+    use crate::services::session_service::SessionService;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use futures::SinkExt;
+    use serde_json::Value;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    async fn next_json<S>(socket: &mut S) -> Value
+    where
+        S: futures::Stream<Item = std::result::Result<ClientMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        match socket.next().await {
+            Some(Ok(ClientMessage::Text(text))) => serde_json::from_str(text.as_str()).unwrap(),
+            other => panic!("expected a text message, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_events_until_the_session_is_revoked() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let sessions = SessionService::new(db.clone());
+        let session = sessions.start(&user, "test").await.unwrap();
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let token = tokens.issue(&session).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/v1/ws", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db.clone(), &Network::testnet(), tokens)));
+
+        let (mut stranger, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        stranger.send(ClientMessage::Text(json!({ "token": "forged" }).to_string().into())).await.unwrap();
+        let rejection = next_json(&mut stranger).await;
+        assert_eq!(rejection["type"], "error");
+        assert_eq!(rejection["error"]["code"], "unauthenticated");
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        socket.send(ClientMessage::Text(json!({ "token": token }).to_string().into())).await.unwrap();
+        assert_eq!(next_json(&mut socket).await["type"], "ready");
+
+        sessions.revoke(&user, &session.id).await.unwrap();
+        let event = next_json(&mut socket).await;
+        assert_eq!(event, json!({ "type": "session_revoked", "session_id": session.id }));
+    }
+
+    #[tokio::test]
+    async fn closes_when_its_api_key_is_revoked() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let keys = ApiKeyService::new(db.clone());
+        let (key, token) = keys.create(&user, "dashboard", &[ApiScope::Read], None).await.unwrap();
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/v1/ws", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db.clone(), &Network::testnet(), tokens)));
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        socket.send(ClientMessage::Text(json!({ "token": token }).to_string().into())).await.unwrap();
+        assert_eq!(next_json(&mut socket).await["type"], "ready");
+
+        keys.revoke(&user, &key).await.unwrap();
+        let event = next_json(&mut socket).await;
+        assert_eq!(event, json!({ "type": "api_key_revoked", "key_id": key.id }));
+        let next = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.expect("the connection stays open");
+        assert!(!matches!(next, Some(Ok(ClientMessage::Text(_)))), "got {:?}", next);
+    }
+}
@@ -0,0 +1,259 @@
+use super::auth::{authenticate, Authenticated, Caller};
+use super::error::{ApiError, ApiResult};
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::contact::Contact;
+use crate::models::api_key::ApiScope;
+use crate::models::transaction::WalletTransaction;
+use crate::models::user::UserResponse;
+use crate::models::wallet_event::WalletEvent;
+use crate::stellar::amount::format_stroops;
+use async_graphql::http::{WebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::{ComplexObject, Context, Data, EmptyMutation, Object, Schema, SimpleObject, Subscription};
+use axum::extract::ws::{CloseFrame, Message, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::header::SEC_WEBSOCKET_PROTOCOL;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::{Extension, Json};
+use futures::{future, SinkExt, Stream, StreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Deep enough for `wallets { transactions { ... } }` with room to spare.
+const MAX_QUERY_DEPTH: usize = 6;
+
+pub type WalletSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+
+pub fn schema(state: Arc<ApiState>) -> WalletSchema {
+    Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+        .data(state)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+/// `POST /graphql`: queries about the account the access token belongs to.
+pub async fn execute(
+    Extension(schema): Extension<WalletSchema>,
+    Authenticated(caller): Authenticated,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request.data(caller)).await)
+}
+
+/// `GET /graphql/ws`: subscriptions over the `graphql-transport-ws` or older
+/// `graphql-ws` protocol. Browsers can't set headers on WebSockets, so the
+/// access token goes in the `connection_init` payload as
+/// `{"authorization": "Bearer <token>"}`.
+pub async fn subscribe(
+    State(state): State<Arc<ApiState>>,
+    Extension(schema): Extension<WalletSchema>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let protocol = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|protocols| protocols.split(',').find_map(|protocol| protocol.trim().parse::<WebSocketProtocols>().ok()))
+        .ok_or_else(|| AppError::ValidationError("Use the graphql-transport-ws or graphql-ws WebSocket protocol".to_string()))?;
+
+    Ok(upgrade.protocols([protocol.sec_websocket_protocol()]).on_upgrade(move |socket| async move {
+        let (mut sink, stream) = socket.split();
+        let input = stream.take_while(|message| future::ready(message.is_ok())).filter_map(|message| {
+            future::ready(match message {
+                Ok(Message::Text(text)) => Some(text.as_str().as_bytes().to_vec()),
+                Ok(Message::Binary(bytes)) => Some(bytes.to_vec()),
+                _ => None,
+            })
+        });
+
+        let mut output = WebSocket::new(schema, input, protocol).on_connection_init(move |payload| async move {
+            let token = payload
+                .get("authorization")
+                .and_then(Value::as_str)
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .ok_or_else(|| AppError::AuthenticationError("Send an access token or API key as {\"authorization\": \"Bearer <token>\"}".to_string()))
+                .map_err(ApiError::from)?;
+            let caller = authenticate(&state, token, Some(ApiScope::Read)).await.map_err(ApiError::from)?;
+
+            let mut data = Data::default();
+            data.insert(caller);
+            Ok(data)
+        });
+
+        while let Some(message) = output.next().await {
+            let message = match message {
+                WsMessage::Text(text) => Message::Text(text.into()),
+                WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame { code, reason: reason.into() })),
+            };
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a ApiState {
+    ctx.data_unchecked::<Arc<ApiState>>()
+}
+
+/// Every request carries its caller: `execute` and `subscribe` only run
+/// the schema for authenticated clients.
+fn caller<'a>(ctx: &Context<'a>) -> &'a Caller {
+    ctx.data_unchecked::<Caller>()
+}
+
+/// An address the account can receive payments at.
+#[derive(Debug, SimpleObject)]
+#[graphql(complex)]
+pub struct Wallet {
+    pub address: String,
+    pub label: Option<String>,
+    /// The address payments and the dashboard use by default.
+    pub primary: bool,
+}
+
+#[ComplexObject]
+impl Wallet {
+    /// The XLM balance from Horizon, e.g. `"10.0000000"`; null until the
+    /// account is funded.
+    async fn native_balance(&self, ctx: &Context<'_>) -> ApiResult<Option<String>> {
+        let account = state(ctx).accounts.get_account(&self.address).await?;
+        Ok(account.map(|account| account.native_balance().to_string()))
+    }
+
+    /// The latest payments, newest first, brought up to date from Horizon
+    /// when it can be reached.
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20, validator(maximum = 200))] limit: u32,
+    ) -> ApiResult<Vec<WalletTransaction>> {
+        Ok(state(ctx).history_service.recent(&self.address, limit).await?.transactions)
+    }
+}
+
+#[ComplexObject]
+impl WalletTransaction {
+    /// The amount in units of the asset, e.g. `"12.5"`.
+    async fn amount(&self) -> String {
+        format_stroops(self.amount_stroops)
+    }
+}
+
+/// The account's software keys, plus its primary address when that is held
+/// elsewhere (a Ledger).
+pub(super) async fn wallets(state: &ApiState, user_id: &Uuid) -> Result<Vec<Wallet>> {
+    let primary = state.user_service.get_user(user_id).await?.and_then(|user| user.stellar_public_key);
+    let mut wallets: Vec<Wallet> = state
+        .signer_service
+        .software_keys(user_id)
+        .await?
+        .into_iter()
+        .map(|entry| Wallet {
+            primary: primary.as_ref() == Some(&entry.public_key),
+            address: entry.public_key,
+            label: entry.label,
+        })
+        .collect();
+
+    if let Some(primary) = primary.filter(|primary| !wallets.iter().any(|wallet| wallet.address == *primary)) {
+        wallets.insert(0, Wallet { address: primary, label: None, primary: true });
+    }
+    Ok(wallets)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// The account the access token belongs to.
+    async fn me(&self, ctx: &Context<'_>) -> ApiResult<UserResponse> {
+        let user = state(ctx).user_service.get_profile(&caller(ctx).user_id).await?;
+        Ok(user.ok_or_else(|| AppError::AuthenticationError("Invalid or expired access token".to_string()))?)
+    }
+
+    async fn wallets(&self, ctx: &Context<'_>) -> ApiResult<Vec<Wallet>> {
+        Ok(wallets(state(ctx), &caller(ctx).user_id).await?)
+    }
+
+    /// The address book, by name.
+    async fn contacts(&self, ctx: &Context<'_>) -> ApiResult<Vec<Contact>> {
+        Ok(state(ctx).contact_service.list_contacts(&caller(ctx).user_id).await?)
+    }
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Payments into one of the account's wallets as the server sees them
+    /// reach the network.
+    async fn incoming_payments(&self, ctx: &Context<'_>, address: String) -> async_graphql::Result<impl Stream<Item = WalletTransaction>> {
+        let state = state(ctx);
+        if !wallets(state, &caller(ctx).user_id).await.map_err(ApiError::from)?.iter().any(|wallet| wallet.address == address) {
+            return Err(ApiError::from(AppError::ValidationError(format!("{} isn't one of your wallets", address))).into());
+        }
+
+        let caller = caller(ctx).clone();
+        let mut ended = false;
+        let events = state.events.subscribe(caller.user_id).take_while(move |event| {
+            let open = !ended;
+            ended = caller.ends_with(event);
+            future::ready(open)
+        });
+        Ok(events.filter_map(move |event| {
+            future::ready(match event {
+                WalletEvent::PaymentReceived { transaction } if transaction.account == address => Some(transaction),
+                _ => None,
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::models::contact::CreateContactRequest;
+    use crate::services::contact_service::ContactService;
+    use crate::services::keystore_service::KeystoreService;
+    use crate::services::session_service::SessionService;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::keypair::Keypair;
+    use crate::stellar::network::Network;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn answers_queries_about_the_signed_in_account_only() {
+        let db = SqliteDatabase::in_memory().await;
+        let (user, other) = (db.insert_test_user().await, db.insert_test_user().await);
+        let keypair = Keypair::random();
+        let address = keypair.public_key();
+        KeystoreService::new(db.clone())
+            .store_keypair(&user, &keypair, "Velvet-Otter-92!", Some("Savings".to_string()))
+            .await
+            .unwrap();
+        for (owner, name) in [(user, "Alice"), (other, "Mallory")] {
+            let contact = CreateContactRequest { name: name.to_string(), address: address.clone(), memo: None, federation_name: None };
+            ContactService::new(db.clone()).add_contact(&owner, contact).await.unwrap();
+        }
+
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let schema = schema(Arc::new(ApiState::new(db.clone(), &Network::testnet(), tokens)));
+        let session = SessionService::new(db.clone()).start(&user, "test").await.unwrap();
+        let query = async_graphql::Request::new("{ me { id role } wallets { label primary } contacts { name } }").data(Caller::from(session));
+        let response = schema.execute(query).await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            json!({
+                "me": { "id": user.to_string(), "role": "USER" },
+                "wallets": [{ "label": "Savings", "primary": false }],
+                "contacts": [{ "name": "Alice" }],
+            })
+        );
+    }
+}
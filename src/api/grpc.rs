@@ -0,0 +1,285 @@
+use super::accounts::sign_up;
+use super::auth::{self, log_in, Caller};
+use super::error::ApiResult;
+use super::payments::{send, PaymentRequest};
+use super::rate_limit::enforce;
+use super::transactions::recent;
+use super::validation::validate;
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::models::role::Role;
+use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+use crate::models::user::{CreateUserRequest, UserResponse};
+use crate::services::rate_limit_service::LimitedAction;
+use crate::stellar::amount::format_stroops;
+use axum::extract::ConnectInfo;
+use axum::Router;
+use proto::wallet_server::{Wallet, WalletServer};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::service::Routes;
+use tonic::{Request, Response, Status};
+
+/// Messages and service stubs generated from `proto/wallet.proto`.
+pub mod proto {
+    tonic::include_proto!("stellar_wallet.v1");
+}
+
+type GrpcResult<T> = std::result::Result<Response<T>, Status>;
+
+/// The `stellar_wallet.v1.Wallet` service, to merge into the HTTP router.
+pub fn routes(state: Arc<ApiState>) -> Router {
+    Routes::new(WalletServer::new(WalletService { state })).into_axum_router()
+}
+
+/// The HTTP API's operations for gRPC clients, sharing their checks, audit
+/// entries and errors.
+pub struct WalletService {
+    state: Arc<ApiState>,
+}
+
+impl WalletService {
+    /// The client's IP address, which logins and signups are limited by.
+    fn peer<T>(request: &Request<T>) -> Option<SocketAddr> {
+        request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0).or_else(|| request.remote_addr())
+    }
+
+    /// Whoever the `authorization: Bearer` metadata stands for: a login, or
+    /// an API key holding `scope`.
+    async fn authenticate<T>(&self, request: &Request<T>, scope: ApiScope) -> Result<Caller> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                AppError::AuthenticationError("Send an access token or API key as 'authorization: Bearer <token>' metadata".to_string())
+            })?;
+
+        auth::authenticate(&self.state, token, Some(scope)).await
+    }
+}
+
+fn reply<T>(result: ApiResult<T>) -> GrpcResult<T> {
+    result.map(Response::new).map_err(Status::from)
+}
+
+#[tonic::async_trait]
+impl Wallet for WalletService {
+    async fn create_account(&self, request: Request<proto::CreateAccountRequest>) -> GrpcResult<proto::User> {
+        let peer = Self::peer(&request);
+        let request = request.into_inner();
+        let request = CreateUserRequest {
+            email: request.email,
+            username: request.username,
+            password: request.password.into(),
+        };
+        let result = async {
+            if let Some(peer) = peer {
+                enforce(&self.state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+            }
+            validate(&request)?;
+            Ok(sign_up(&self.state, request).await?.into())
+        };
+        reply(result.await)
+    }
+
+    /// Like `POST /auth/login`, failures count against the client's IP address,
+    /// and so does every attempt against its rate limit.
+    async fn login(&self, request: Request<proto::LoginRequest>) -> GrpcResult<proto::LoginResponse> {
+        let peer = Self::peer(&request);
+        let source = match peer {
+            Some(peer) => format!("grpc {}", peer.ip()),
+            None => "grpc".to_string(),
+        };
+        let request = request.into_inner();
+        let request = super::auth::LoginRequest {
+            identifier: request.identifier,
+            password: request.password.into(),
+            code: request.code,
+            passkey: None,
+            scopes: (!request.scopes.is_empty()).then_some(request.scopes),
+        };
+
+        let result = async {
+            if let Some(peer) = peer {
+                enforce(&self.state, LimitedAction::Auth, &peer.ip().to_string()).await?;
+            }
+            validate(&request)?;
+            let login = log_in(&self.state, source, request).await?;
+            Ok(proto::LoginResponse {
+                access_token: login.access_token,
+                expires_at: login.expires_at.to_rfc3339(),
+                refresh_token: login.refresh_token,
+                user: Some(login.user.into()),
+                scopes: login.scopes.iter().map(|scope| scope.as_str().to_string()).collect(),
+            })
+        };
+        reply(result.await)
+    }
+
+    async fn get_me(&self, request: Request<proto::GetMeRequest>) -> GrpcResult<proto::User> {
+        let result = async {
+            let caller = self.authenticate(&request, ApiScope::Read).await?;
+            let user = self
+                .state
+                .user_service
+                .get_profile(&caller.user_id)
+                .await?
+                .ok_or_else(|| AppError::AuthenticationError("Invalid or expired access token".to_string()))?;
+            Ok(user.into())
+        };
+        reply(result.await)
+    }
+
+    async fn send_payment(&self, request: Request<proto::SendPaymentRequest>) -> GrpcResult<proto::SendPaymentResponse> {
+        let result = async {
+            let caller = self.authenticate(&request, ApiScope::Payments).await?;
+            let request = request.into_inner();
+            let request = PaymentRequest {
+                destination: request.destination,
+                amount: request.amount,
+                memo: request.memo,
+                password: request.password.into(),
+                pin: request.pin.map(Into::into),
+                code: request.code,
+                allow_duplicate: request.allow_duplicate,
+            };
+            validate(&request)?;
+            enforce(&self.state, LimitedAction::Payments, &caller.user_id.to_string()).await?;
+
+            let payment = send(&self.state, &caller.user_id, request).await?;
+            Ok(proto::SendPaymentResponse {
+                hash: payment.hash,
+                ledger: payment.ledger,
+            })
+        };
+        reply(result.await)
+    }
+
+    async fn list_transactions(&self, request: Request<proto::ListTransactionsRequest>) -> GrpcResult<proto::ListTransactionsResponse> {
+        let result = async {
+            let caller = self.authenticate(&request, ApiScope::Read).await?;
+            let request = request.into_inner();
+            let transactions = recent(&self.state, &caller.user_id, &request.address, request.limit).await?;
+            Ok(proto::ListTransactionsResponse {
+                transactions: transactions.into_iter().map(Into::into).collect(),
+            })
+        };
+        reply(result.await)
+    }
+}
+
+impl From<Role> for proto::Role {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::User => proto::Role::User,
+            Role::Support => proto::Role::Support,
+            Role::Admin => proto::Role::Admin,
+        }
+    }
+}
+
+impl From<UserResponse> for proto::User {
+    fn from(user: UserResponse) -> Self {
+        proto::User {
+            id: user.id.to_string(),
+            email: user.email,
+            username: user.username,
+            is_verified: user.is_verified,
+            stellar_public_key: user.stellar_public_key,
+            last_login_at: user.last_login_at.map(|at| at.to_rfc3339()),
+            login_count: user.login_count,
+            role: proto::Role::from(user.role).into(),
+            disabled_at: user.disabled_at.map(|at| at.to_rfc3339()),
+            created_at: user.created_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<WalletTransaction> for proto::Transaction {
+    fn from(transaction: WalletTransaction) -> Self {
+        let direction = match transaction.direction {
+            TransactionDirection::Incoming => proto::Direction::Incoming,
+            TransactionDirection::Outgoing => proto::Direction::Outgoing,
+        };
+        let status = match transaction.status {
+            TransactionStatus::Pending => proto::Status::Pending,
+            TransactionStatus::Confirmed => proto::Status::Confirmed,
+            TransactionStatus::Failed => proto::Status::Failed,
+        };
+
+        proto::Transaction {
+            account: transaction.account,
+            hash: transaction.hash,
+            operation_index: transaction.operation_index,
+            direction: direction.into(),
+            asset_code: transaction.asset_code,
+            amount: format_stroops(transaction.amount_stroops),
+            counterparty: transaction.counterparty,
+            memo: transaction.memo,
+            status: status.into(),
+            ledger: transaction.ledger,
+            error: transaction.error,
+            created_at: transaction.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::wallet_client::WalletClient;
+    use super::*;
+    use crate::api::serve;
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::keypair::Keypair;
+    use crate::stellar::network::Network;
+    use tokio::net::TcpListener;
+    use tonic::Code;
+
+    #[tokio::test]
+    async fn serves_the_api_over_grpc_on_the_http_port() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let mut client = WalletClient::connect(address).await.unwrap();
+
+        let signup = proto::CreateAccountRequest {
+            email: "otter@example.com".to_string(),
+            username: "otter".to_string(),
+            password: "Velvet-Otter-92!".to_string(),
+        };
+        let user = client.create_account(signup).await.unwrap().into_inner();
+        assert_eq!(user.role(), proto::Role::User);
+
+        let mut login = proto::LoginRequest {
+            identifier: "otter".to_string(),
+            password: "Velvet-Otter-93!".to_string(),
+            code: None,
+            scopes: Vec::new(),
+        };
+        let error = client.login(login.clone()).await.unwrap_err();
+        assert_eq!(error.code(), Code::Unauthenticated);
+        assert_eq!(error.metadata().get("error-code").unwrap(), "unauthenticated");
+        login.password = "Velvet-Otter-92!".to_string();
+        let token = client.login(login).await.unwrap().into_inner().access_token;
+
+        assert_eq!(client.get_me(proto::GetMeRequest {}).await.unwrap_err().code(), Code::Unauthenticated);
+        let mut me = Request::new(proto::GetMeRequest {});
+        me.metadata_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+        assert_eq!(client.get_me(me).await.unwrap().into_inner().id, user.id);
+
+        let mut history = Request::new(proto::ListTransactionsRequest {
+            address: Keypair::random().public_key(),
+            limit: None,
+        });
+        history.metadata_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+        let error = client.list_transactions(history).await.unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+        assert!(error.message().contains("isn't one of your wallets"));
+    }
+}
@@ -0,0 +1,32 @@
+use super::ApiState;
+use crate::services::health_service::{Readiness, VersionInfo};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Health {
+    /// Always `ok`.
+    pub status: &'static str,
+}
+
+/// `GET /health`: answers as long as the process is serving requests.
+pub async fn health() -> Json<Health> {
+    Json(Health { status: "ok" })
+}
+
+/// `GET /ready`: 200 when the database and Horizon are both reachable, 503
+/// otherwise, so load balancers only send traffic that can be served.
+pub async fn ready(State(state): State<Arc<ApiState>>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.health_service.readiness().await;
+    let status = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(readiness))
+}
+
+/// `GET /version`: the build and the network it's configured for.
+pub async fn version(State(state): State<Arc<ApiState>>) -> Json<VersionInfo> {
+    Json(state.health_service.version())
+}
@@ -0,0 +1,162 @@
+use super::error::{ApiError, ApiResult};
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::services::idempotency_service::Claim;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, HeaderName, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::Utc;
+use serde::Serialize;
+use std::future::Future;
+use uuid::Uuid;
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Set on responses replayed for a repeated `Idempotency-Key`.
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Runs `handle` once per `Idempotency-Key` in `scope` and answers retries
+/// with the response it got. `request` tells a retry from a key reused for
+/// something else, so it should hold the request's fields minus any
+/// secrets. `handle` returns the account the response is about along with
+/// it. If `handle` sent a transaction without learning how it ended, retries
+/// call `resume` with its hash to find out rather than sending another.
+/// Requests without the header just run.
+pub(super) async fn idempotent<T: Serialize, R: Future<Output = Result<(Uuid, T)>>>(
+    state: &ApiState,
+    headers: &HeaderMap,
+    scope: &str,
+    request: &impl Serialize,
+    status: StatusCode,
+    handle: impl Future<Output = Result<(Uuid, T)>>,
+    resume: impl FnOnce(String) -> R,
+) -> ApiResult<Response> {
+    let Some(key) = headers.get(IDEMPOTENCY_KEY) else {
+        let (_, body) = handle.await?;
+        return Ok((status, Json(body)).into_response());
+    };
+    let key = key
+        .to_str()
+        .map_err(|_| AppError::ValidationError("Idempotency-Key must be printable ASCII".to_string()))?;
+
+    match state.idempotency_service.claim(scope, key, request, Utc::now()).await? {
+        Claim::Replay { status, body } => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            Ok(json_response(status, body, true))
+        }
+        Claim::Run => settle(state, scope, key, status, handle.await, false).await,
+        Claim::Pending { hash } => settle(state, scope, key, status, resume(hash).await, true).await,
+    }
+}
+
+/// For requests that never leave a transaction pending, as the `resume` of
+/// [`idempotent`].
+pub(super) async fn nothing_pending<T>(hash: String) -> Result<(Uuid, T)> {
+    Err(AppError::InternalError(format!("Nothing submits transactions here, yet {} is pending", hash)))
+}
+
+/// Keeps what a claimed request ended with for its retries. A request that
+/// failed before changing anything frees its key instead, and one that left
+/// a transaction unconfirmed holds on to it. Other failures may have come
+/// after the work was done, so retries get the same error rather than doing
+/// it again; while resuming, the transaction stays pending instead.
+async fn settle<T: Serialize>(
+    state: &ApiState,
+    scope: &str,
+    key: &str,
+    status: StatusCode,
+    result: Result<(Uuid, T)>,
+    resumed: bool,
+) -> ApiResult<Response> {
+    let service = &state.idempotency_service;
+    let error = match result {
+        Ok((user_id, body)) => {
+            let body = serde_json::to_string(&body).map_err(|e| AppError::InternalError(format!("Can't encode the response: {}", e)))?;
+            // The work is done; failing now would only invite a retry
+            // that does it again.
+            if let Err(e) = service.complete(scope, key, Some(&user_id), status.as_u16(), &body).await {
+                tracing::warn!("Couldn't save the response for an Idempotency-Key: {}", e);
+            }
+            return Ok(json_response(status, body, resumed));
+        }
+        Err(e) => e,
+    };
+
+    let kept = match &error {
+        AppError::UnconfirmedError { hash, .. } => service.hold(scope, key, hash).await,
+        e if changed_nothing(e) => service.release(scope, key).await,
+        _ if resumed => Ok(()),
+        _ => {
+            let (status, body) = ApiError::from(error).public();
+            let body = serde_json::to_string(&body).map_err(|e| AppError::InternalError(format!("Can't encode the response: {}", e)))?;
+            if let Err(e) = service.complete(scope, key, None, status.as_u16(), &body).await {
+                tracing::warn!("Couldn't save the response for an Idempotency-Key: {}", e);
+            }
+            return Ok(json_response(status, body, false));
+        }
+    };
+    if let Err(e) = kept {
+        tracing::warn!("Couldn't update an Idempotency-Key: {}", e);
+    }
+    Err(error.into())
+}
+
+/// Errors raised before a request changed anything: bad input, missing
+/// credentials, limits, clashes, and transactions Horizon turned down.
+fn changed_nothing(error: &AppError) -> bool {
+    matches!(
+        error,
+        AppError::ValidationError(_)
+            | AppError::AuthenticationError(_)
+            | AppError::RateLimitError(_)
+            | AppError::ConflictError(_)
+            | AppError::StellarError(_)
+    )
+}
+
+fn json_response(status: StatusCode, body: String, replayed: bool) -> Response {
+    if replayed {
+        (status, [(CONTENT_TYPE, "application/json"), (IDEMPOTENT_REPLAYED, "true")], body).into_response()
+    } else {
+        (status, [(CONTENT_TYPE, "application/json")], body).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{serve, ApiState};
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn replays_a_retried_signup_instead_of_failing_it() {
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/accounts", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(SqliteDatabase::in_memory().await, &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+        let signup = json!({ "email": "otter@example.com", "username": "otter", "password": "Velvet-Otter-92!" });
+
+        let first = client.post(&url).header("idempotency-key", "signup-1").json(&signup).send().await.unwrap();
+        assert_eq!(first.status(), 201);
+        assert!(first.headers().get("idempotent-replayed").is_none());
+        let user: Value = first.json().await.unwrap();
+
+        let retry = client.post(&url).header("idempotency-key", "signup-1").json(&signup).send().await.unwrap();
+        assert_eq!(retry.status(), 201);
+        assert_eq!(retry.headers()["idempotent-replayed"], "true");
+        assert_eq!(retry.json::<Value>().await.unwrap(), user);
+
+        let without_key = client.post(&url).json(&signup).send().await.unwrap();
+        assert_eq!(without_key.status(), 400);
+
+        let other = json!({ "email": "heron@example.com", "username": "heron", "password": "Velvet-Otter-92!" });
+        let reused = client.post(&url).header("idempotency-key", "signup-1").json(&other).send().await.unwrap();
+        assert_eq!(reused.status(), 400);
+        assert!(reused.json::<Value>().await.unwrap()["message"].as_str().unwrap().contains("Idempotency-Key"));
+    }
+}
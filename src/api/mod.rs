@@ -0,0 +1,691 @@
+//! JSON API over the same services the CLI uses, started with `--serve`.
+//! Requests authenticate with the access tokens [`TokenService`] issues, so
+//! `JWT_SIGNING_KEY` must be set, or with API keys. Either only reaches the
+//! routes its scopes cover; see [`ApiScope`]. The OpenAPI document is served at
+//! `/openapi.json`, with interactive docs at `/docs`. `/graphql` offers the
+//! same data as one GraphQL schema, with subscriptions at `/graphql/ws`, and
+//! `/ws` streams account events as they happen, as does `/events` for
+//! clients that only speak server-sent events, until the access token
+//! expires. gRPC clients reach the same operations on the same address, as
+//! described in `proto/wallet.proto`.
+//! `/health`, `/ready` and `/version` are open, for load balancers and ops.
+//! `/auth/oauth/{provider}` logs in through Google or GitHub when
+//! [`OAuthService`] is configured, and `/passkeys` registers passkeys for
+//! logins when [`PasskeyService`] is.
+//! Logins, signups and payments are rate limited; see [`RateLimitService`].
+//! Signups and payments sent with an `Idempotency-Key` are safe to retry.
+//! `/webhooks` registers URLs that receive signed event notifications.
+//! The REST routes are versioned under `/v1`; see [`v1`] for how versions
+//! change. Every response carries an `X-Request-Id`, which also marks the
+//! request's errors, audit entries, Horizon submissions and webhooks.
+
+pub mod accounts;
+pub mod auth;
+pub mod cors;
+pub mod docs;
+pub mod error;
+pub mod events;
+pub mod graphql;
+pub mod grpc;
+pub mod health;
+pub mod idempotency;
+pub mod passkeys;
+pub mod payments;
+pub mod rate_limit;
+pub mod request_id;
+pub mod scripted;
+pub mod sse;
+pub mod stats;
+pub mod tls;
+pub mod transactions;
+pub mod users;
+pub mod v1;
+pub mod validation;
+pub mod webhooks;
+
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::services::account_cache_service::AccountCacheService;
+use crate::services::admin_service::AdminService;
+use crate::services::api_key_service::ApiKeyService;
+use crate::services::audit_service::AuditService;
+use crate::services::breach_check_service::BreachCheckService;
+use crate::services::contact_service::ContactService;
+use crate::services::event_bus::EventBus;
+use crate::services::event_outbox_service::EventOutboxService;
+use crate::services::event_watch_service::EventWatchService;
+use crate::services::health_service::HealthService;
+use crate::services::history_service::HistoryService;
+use crate::services::hook_service::HookService;
+use crate::services::idempotency_service::IdempotencyService;
+use crate::services::login_history_service::LoginHistoryService;
+use crate::services::login_throttle_service::LoginThrottleService;
+use crate::services::oauth_service::OAuthService;
+use crate::services::passkey_service::PasskeyService;
+use crate::services::policy_service::PolicyService;
+use crate::services::rate_limit_service::RateLimitService;
+use crate::services::session_service::SessionService;
+use crate::services::settings_service::SettingsService;
+use crate::services::signer_service::SignerService;
+use crate::services::signing_pin_service::SigningPinService;
+use crate::services::sms_service::SmsService;
+use crate::services::token_service::TokenService;
+use crate::services::transaction_service::TransactionService;
+use crate::services::two_factor_service::TwoFactorService;
+use crate::services::user_service::UserService;
+use crate::services::stream_ticket_service::StreamTicketService;
+use crate::services::webhook_endpoint_service::WebhookEndpointService;
+use crate::services::webhook_service::WebhookService;
+use crate::stellar::horizon::HorizonClient;
+use crate::stellar::network::Network;
+use axum::routing::{get, post};
+use axum::serve::{Listener, ListenerExt};
+use cors::CorsPolicy;
+use axum::{Extension, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Where `--serve` listens when neither it nor `API_LISTEN_ADDR` says.
+pub const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:8080";
+
+/// The services every request handler shares.
+pub struct ApiState {
+    user_service: UserService,
+    session_service: SessionService,
+    tokens: TokenService,
+    api_key_service: ApiKeyService,
+    audit_service: AuditService,
+    admin_service: AdminService,
+    login_throttle_service: LoginThrottleService,
+    login_history_service: LoginHistoryService,
+    settings_service: SettingsService,
+    two_factor_service: TwoFactorService,
+    sms_service: SmsService,
+    signer_service: SignerService,
+    signing_pin_service: SigningPinService,
+    policy_service: PolicyService,
+    transaction_service: TransactionService,
+    contact_service: ContactService,
+    history_service: HistoryService,
+    accounts: Arc<AccountCacheService>,
+    health_service: HealthService,
+    idempotency_service: IdempotencyService,
+    events: EventBus,
+    event_watch_service: EventWatchService,
+    webhook_endpoint_service: Arc<WebhookEndpointService>,
+    webhook_service: WebhookService,
+    event_outbox: Option<Arc<EventOutboxService>>,
+    stream_ticket_service: StreamTicketService,
+    breach_check: Option<BreachCheckService>,
+    oauth: Option<OAuthService>,
+    passkeys: Option<PasskeyService>,
+    rate_limits: RateLimitService,
+    cors: Option<CorsPolicy>,
+}
+
+impl ApiState {
+    pub fn new(db: SqliteDatabase, network: &Network, tokens: TokenService) -> Self {
+        let events = EventBus::new();
+
+        Self {
+            user_service: UserService::new(db.clone()),
+            session_service: SessionService::new(db.clone()),
+            tokens,
+            api_key_service: ApiKeyService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            admin_service: AdminService::new(db.clone()),
+            login_throttle_service: LoginThrottleService::new(db.clone()),
+            login_history_service: LoginHistoryService::new(db.clone()),
+            settings_service: SettingsService::new(db.clone()),
+            two_factor_service: TwoFactorService::new(db.clone()),
+            sms_service: SmsService::new(db.clone()),
+            signer_service: SignerService::new(db.clone()),
+            signing_pin_service: SigningPinService::new(db.clone()),
+            policy_service: PolicyService::new(db.clone()),
+            transaction_service: TransactionService::new(network.clone()).with_db(db.clone()),
+            contact_service: ContactService::new(db.clone()),
+            history_service: HistoryService::new(db.clone(), network),
+            accounts: Arc::new(AccountCacheService::new(HorizonClient::new(&network.horizon_url))),
+            health_service: HealthService::new(db.clone(), network),
+            idempotency_service: IdempotencyService::new(db.clone()),
+            webhook_endpoint_service: Arc::new(WebhookEndpointService::new(db.clone())),
+            webhook_service: WebhookService::disabled(),
+            event_outbox: None,
+            stream_ticket_service: StreamTicketService::new(db.clone()),
+            event_watch_service: EventWatchService::new(db, network, events.clone()),
+            events,
+            breach_check: None,
+            oauth: None,
+            passkeys: None,
+            rate_limits: RateLimitService::default(),
+            cors: None,
+        }
+    }
+
+    /// Runs the operator's hooks on signups and payments through the API too.
+    pub fn with_hooks(mut self, hooks: Arc<HookService>) -> Self {
+        self.user_service = self.user_service.with_hooks(hooks.clone());
+        self.transaction_service = self.transaction_service.with_hooks(hooks);
+        self
+    }
+
+    /// Checks signup passwords against known breaches. Under the `warn`
+    /// policy there's nobody to ask, so only `reject` turns them away.
+    pub fn with_breach_check(mut self, breach_check: BreachCheckService) -> Self {
+        self.breach_check = Some(breach_check);
+        self
+    }
+
+    /// Lets users log in through the providers `oauth` has apps for.
+    pub fn with_oauth(mut self, oauth: OAuthService) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Lets users register passkeys and log in with them.
+    pub fn with_passkeys(mut self, passkeys: PasskeyService) -> Self {
+        self.passkeys = Some(passkeys);
+        self
+    }
+
+    /// Publishes domain events to a message broker through `outbox`.
+    pub fn with_event_outbox(mut self, outbox: EventOutboxService) -> Self {
+        self.event_outbox = Some(Arc::new(outbox));
+        self
+    }
+
+    /// Lets admins send test events to the operator's webhook through it.
+    pub fn with_webhook(mut self, webhook_service: WebhookService) -> Self {
+        self.webhook_service = webhook_service;
+        self
+    }
+
+    /// Caches Horizon account lookups, e.g. balances, and user profiles
+    /// through `accounts`.
+    pub fn with_account_cache(mut self, accounts: AccountCacheService) -> Self {
+        self.user_service = self.user_service.with_cache(&accounts);
+        self.accounts = Arc::new(accounts);
+        self
+    }
+
+    /// Replaces the default in-memory rate limits.
+    pub fn with_rate_limits(mut self, rate_limits: RateLimitService) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Lets the pages `cors` allows call the API from a browser.
+    pub fn with_cors(mut self, cors: CorsPolicy) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+}
+
+pub fn router(mut state: ApiState) -> Router {
+    let cors = state.cors.take();
+    let state = Arc::new(state);
+    let schema = graphql::schema(state.clone());
+
+    let router = Router::new()
+        .route("/health", get(health::health))
+        .route("/ready", get(health::ready))
+        .route("/version", get(health::version))
+        .route("/openapi.json", get(docs::spec))
+        .route("/docs", get(docs::ui))
+        .route("/graphql", post(graphql::execute).layer(Extension(ApiScope::Read)))
+        .route("/graphql/ws", get(graphql::subscribe))
+        .nest("/v1", v1::routes())
+        .merge(v1::unversioned_routes())
+        .layer(Extension(schema))
+        .with_state(state.clone())
+        .merge(grpc::routes(state))
+        .layer(axum::middleware::from_fn(request_id::assign));
+    match cors {
+        Some(cors) => router.layer(cors.layer()),
+        None => router,
+    }
+}
+
+/// Serves the API on `listener`, a [`TcpListener`](tokio::net::TcpListener) or a [`tls::TlsListener`],
+/// until Ctrl-C, watching for account events and delivering webhooks and
+/// broker events in the background.
+pub async fn serve<L>(listener: L, state: ApiState) -> Result<()>
+where
+    L: Listener<Addr = SocketAddr>,
+{
+    tokio::spawn(state.event_watch_service.clone().run());
+    tokio::spawn(state.webhook_endpoint_service.clone().run(state.events.clone()));
+    tokio::spawn(state.accounts.clone().run(state.events.clone()));
+    if let Some(outbox) = state.event_outbox.clone() {
+        tokio::spawn(outbox.run(state.events.clone()));
+    }
+    let app = router(state).into_make_service_with_connect_info::<SocketAddr>();
+
+    // A tapped listener of any kind hands its client addresses to
+    // `ConnectInfo`; a bare one only does for `TcpListener`.
+    axum::serve(listener.tap_io(|_| {}), app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| AppError::InternalError(format!("API server failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::role::Role;
+    use chrono::Utc;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn signs_up_logs_in_and_reads_the_profile_over_http() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db.clone(), &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let health: Value = client.get(format!("{}/health", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(health["status"], "ok");
+        let version: Value = client.get(format!("{}/version", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(version["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(version["network"], "testnet");
+
+        let invalid = json!({ "email": "otter", "username": "otter", "password": "Velvet-Otter-92!" });
+        let response = client.post(format!("{}/v1/accounts", base)).json(&invalid).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["details"]["fields"], json!({ "email": ["Invalid email format"] }));
+        let response = client.post(format!("{}/v1/accounts", base)).body("{").header("content-type", "application/json").send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        assert_eq!(response.json::<Value>().await.unwrap()["code"], "validation_failed");
+
+        let signup = json!({ "email": "otter@example.com", "username": "otter", "password": "Velvet-Otter-92!" });
+        let response = client.post(format!("{}/v1/accounts", base)).json(&signup).send().await.unwrap();
+        assert_eq!(response.status(), 201);
+        let user: Value = response.json().await.unwrap();
+        assert_eq!(user["username"], "otter");
+        assert!(user.get("password_hash").is_none());
+
+        let wrong = json!({ "identifier": "otter", "password": "Velvet-Otter-93!" });
+        let response = client.post(format!("{}/v1/auth/login", base)).json(&wrong).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["code"], "unauthenticated");
+        assert!(error["message"].is_string());
+        assert!(error["details"].is_null());
+
+        let login = json!({ "identifier": "otter@example.com", "password": "Velvet-Otter-92!" });
+        let response = client.post(format!("{}/v1/auth/login", base)).json(&login).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let token = response.json::<Value>().await.unwrap()["access_token"].as_str().unwrap().to_string();
+
+        let me: Value = client
+            .get(format!("{}/v1/users/me", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(me["id"], user["id"]);
+        assert_eq!(client.get(format!("{}/v1/users/me", base)).send().await.unwrap().status(), 401);
+
+        let unversioned = client.get(format!("{}/users/me", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(unversioned.status(), 200);
+        assert_eq!(unversioned.headers()["deprecation"], "true");
+        assert_eq!(unversioned.json::<Value>().await.unwrap(), me);
+
+        let stats = client.get(format!("{}/v1/stats", base)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(stats.status(), 401);
+        let user_id = me["id"].as_str().unwrap().parse().unwrap();
+        db.update_user_role(&user_id, Role::Support, Utc::now()).await.unwrap();
+        let stats: Value = client
+            .get(format!("{}/v1/stats", base))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(stats["total_users"], 1);
+    }
+
+    #[tokio::test]
+    async fn api_keys_reach_only_the_routes_their_scopes_cover() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let (_, key) = ApiKeyService::new(db.clone()).create(&user, "Accounting", &[ApiScope::Read], None).await.unwrap();
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let me: Value = client.get(format!("{}/v1/users/me", base)).bearer_auth(&key).send().await.unwrap().json().await.unwrap();
+        assert_eq!(me["id"], user.to_string());
+
+        let payment = json!({ "destination": "GABC", "amount": "1", "password": "Velvet-Otter-92!" });
+        let response = client.post(format!("{}/v1/payments", base)).bearer_auth(&key).json(&payment).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        assert!(response.json::<Value>().await.unwrap()["message"].as_str().unwrap().contains("'write:payments' scope"));
+
+        let response = client.get(format!("{}/v1/webhooks", base)).bearer_auth(&key).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        assert_eq!(client.get(format!("{}/v1/users/me", base)).bearer_auth("swk_forged").send().await.unwrap().status(), 401);
+    }
+
+    #[tokio::test]
+    async fn admins_send_test_webhooks_with_the_admin_scope() {
+        use axum::extract::State;
+        use axum::http::HeaderMap;
+        use std::sync::Mutex;
+        use stellar_wallet::webhook;
+
+        type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+        let received = Received::default();
+        let receiver = Router::new()
+            .route(
+                "/hook",
+                post(|State(received): State<Received>, headers: HeaderMap, body: String| async move {
+                    received.lock().unwrap().push((headers, body));
+                }),
+            )
+            .with_state(received.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hook = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let db = SqliteDatabase::in_memory().await;
+        let admin = db.insert_test_user().await;
+        let user = db.insert_test_user().await;
+        db.update_user_role(&admin, Role::Admin, Utc::now()).await.unwrap();
+        let keys = ApiKeyService::new(db.clone());
+        let (_, admin_key) = keys.create(&admin, "Ops", &[ApiScope::AdminWebhooks], None).await.unwrap();
+        let (_, unscoped_key) = keys.create(&admin, "Hooks", &[ApiScope::Webhooks], None).await.unwrap();
+        let (_, user_key) = keys.create(&user, "Ops", &[ApiScope::AdminWebhooks], None).await.unwrap();
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let state = ApiState::new(db, &Network::testnet(), tokens).with_webhook(WebhookService::new(&hook, "operator-webhook-secret"));
+        tokio::spawn(serve(listener, state));
+        let client = reqwest::Client::new();
+        let test = |key: &str| client.post(format!("{}/v1/webhooks/test", base)).bearer_auth(key).send();
+
+        assert_eq!(test(&unscoped_key).await.unwrap().status(), 401);
+        assert_eq!(test(&user_key).await.unwrap().status(), 401);
+        assert!(received.lock().unwrap().is_empty());
+
+        let response = test(&admin_key).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let event_id = response.json::<Value>().await.unwrap()["event_id"].as_str().unwrap().to_string();
+        let (headers, body) = received.lock().unwrap().pop().unwrap();
+        assert_eq!(headers[webhook::EVENT_ID_HEADER], event_id.as_str());
+        let signature = headers[webhook::SIGNATURE_HEADER].to_str().unwrap();
+        assert!(webhook::verify(b"operator-webhook-secret", signature, body.as_bytes(), Utc::now().timestamp(), 60).is_ok());
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["type"], "webhook.test");
+    }
+
+    #[tokio::test]
+    async fn logins_can_ask_for_fewer_scopes() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let signup = json!({ "email": "heron@example.com", "username": "heron", "password": "Quiet-Heron-58!" });
+        assert_eq!(client.post(format!("{}/v1/accounts", base)).json(&signup).send().await.unwrap().status(), 201);
+
+        let unknown = json!({ "identifier": "heron", "password": "Quiet-Heron-58!", "scopes": ["admin:everything"] });
+        let response = client.post(format!("{}/v1/auth/login", base)).json(&unknown).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+
+        let login = json!({ "identifier": "heron", "password": "Quiet-Heron-58!", "scopes": ["read:account"] });
+        let login: Value = client.post(format!("{}/v1/auth/login", base)).json(&login).send().await.unwrap().json().await.unwrap();
+        assert_eq!(login["scopes"], json!(["read:account"]));
+        let token = login["access_token"].as_str().unwrap();
+
+        assert_eq!(client.get(format!("{}/v1/users/me", base)).bearer_auth(token).send().await.unwrap().status(), 200);
+        let response = client.get(format!("{}/v1/webhooks", base)).bearer_auth(token).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        assert!(response.json::<Value>().await.unwrap()["message"].as_str().unwrap().contains("'write:webhooks' scope"));
+    }
+
+    #[tokio::test]
+    async fn refresh_tokens_rotate_until_logout() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let signup = json!({ "email": "kestrel@example.com", "username": "kestrel", "password": "Amber-Kestrel-41!" });
+        assert_eq!(client.post(format!("{}/v1/accounts", base)).json(&signup).send().await.unwrap().status(), 201);
+        let login = json!({ "identifier": "kestrel", "password": "Amber-Kestrel-41!", "scopes": ["read:account"] });
+        let login: Value = client.post(format!("{}/v1/auth/login", base)).json(&login).send().await.unwrap().json().await.unwrap();
+        let first = login["refresh_token"].as_str().unwrap();
+
+        let refresh = |token: &str| client.post(format!("{}/v1/auth/refresh", base)).json(&json!({ "refresh_token": token })).send();
+        let refreshed: Value = refresh(first).await.unwrap().json().await.unwrap();
+        assert_eq!(refreshed["scopes"], json!(["read:account"]));
+        assert_eq!(refreshed["user"]["username"], "kestrel");
+        let token = refreshed["access_token"].as_str().unwrap();
+        assert_eq!(client.get(format!("{}/v1/users/me", base)).bearer_auth(token).send().await.unwrap().status(), 200);
+
+        let logout = client.post(format!("{}/v1/auth/logout", base)).bearer_auth(token).send().await.unwrap();
+        assert_eq!(logout.status(), 204);
+        assert_eq!(client.get(format!("{}/v1/users/me", base)).bearer_auth(token).send().await.unwrap().status(), 401);
+        let next = refreshed["refresh_token"].as_str().unwrap();
+        assert_eq!(refresh(next).await.unwrap().status(), 401);
+        assert_eq!(refresh(first).await.unwrap().status(), 401);
+    }
+
+    #[tokio::test]
+    async fn oauth_logs_in_as_the_account_with_the_verified_email() {
+        use crate::services::oauth_service::tests::{fake_google, state_of};
+        use crate::services::oauth_service::OAuthService;
+
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let (google, profile) = fake_google().await;
+        let oauth = OAuthService::new(db.clone(), "https://app.example.com/oauth", vec![google]).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens).with_oauth(oauth)));
+        let client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap();
+
+        let signup = json!({ "email": "merlin@example.com", "username": "merlin", "password": "Swift-Merlin-58!" });
+        assert_eq!(client.post(format!("{}/v1/accounts", base)).json(&signup).send().await.unwrap().status(), 201);
+        *profile.lock().unwrap() = json!({ "sub": "g-merlin", "email": "merlin@example.com", "email_verified": true });
+
+        let start = client.get(format!("{}/v1/auth/oauth/google", base)).send().await.unwrap();
+        assert_eq!(start.status(), 303);
+        let state = state_of(start.headers()["location"].to_str().unwrap());
+        let callback = json!({ "state": state, "authorization_code": "good-code", "scopes": ["read:account"] });
+        let finish = || client.post(format!("{}/v1/auth/oauth/google/callback", base)).json(&callback).send();
+        let login: Value = finish().await.unwrap().json().await.unwrap();
+        assert_eq!(login["user"]["username"], "merlin");
+        assert_eq!(login["scopes"], json!(["read:account"]));
+        let token = login["access_token"].as_str().unwrap();
+        assert_eq!(client.get(format!("{}/v1/users/me", base)).bearer_auth(token).send().await.unwrap().status(), 200);
+        assert_eq!(finish().await.unwrap().status(), 401);
+
+        assert_eq!(client.get(format!("{}/v1/auth/oauth/github", base)).send().await.unwrap().status(), 400);
+        assert_eq!(client.get(format!("{}/v1/auth/oauth/myspace", base)).send().await.unwrap().status(), 400);
+    }
+
+    #[tokio::test]
+    async fn passkeys_confirm_password_logins_and_log_in_on_their_own() {
+        use crate::services::passkey_service::PasskeyService;
+        use crate::utils::webauthn::tests::SoftAuthenticator;
+
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let passkeys = PasskeyService::new(db.clone(), "wallet.example.com", "https://wallet.example.com");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens).with_passkeys(passkeys)));
+        let client = reqwest::Client::new();
+        let mut authenticator = SoftAuthenticator::new("wallet.example.com", "https://wallet.example.com");
+
+        let signup = json!({ "email": "avocet@example.com", "username": "avocet", "password": "Pied-Avocet-35!" });
+        assert_eq!(client.post(format!("{}/v1/accounts", base)).json(&signup).send().await.unwrap().status(), 201);
+        let password_login = json!({ "identifier": "avocet", "password": "Pied-Avocet-35!" });
+        let login: Value = client.post(format!("{}/v1/auth/login", base)).json(&password_login).send().await.unwrap().json().await.unwrap();
+        let token = login["access_token"].as_str().unwrap();
+
+        let options: Value = client
+            .post(format!("{}/v1/passkeys/registration-options", base))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let registration = json!({ "name": "Phone", "credential": authenticator.register(&options) });
+        let created = client.post(format!("{}/v1/passkeys", base)).bearer_auth(token).json(&registration).send().await.unwrap();
+        assert_eq!(created.status(), 201);
+        let passkey: Value = created.json().await.unwrap();
+        let listed: Value = client.get(format!("{}/v1/passkeys", base)).bearer_auth(token).send().await.unwrap().json().await.unwrap();
+        assert_eq!(listed, json!([passkey]));
+
+        // The password alone no longer does.
+        let response = client.post(format!("{}/v1/auth/login", base)).json(&password_login).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        let challenge = || async {
+            let response = client.post(format!("{}/v1/auth/passkey/options", base)).send().await.unwrap();
+            response.json::<Value>().await.unwrap()
+        };
+        let confirmed = json!({
+            "identifier": "avocet",
+            "password": "Pied-Avocet-35!",
+            "passkey": authenticator.assert(&challenge().await, None),
+        });
+        assert_eq!(client.post(format!("{}/v1/auth/login", base)).json(&confirmed).send().await.unwrap().status(), 200);
+
+        let handle = crate::utils::webauthn::encode(login["user"]["id"].as_str().unwrap().parse::<uuid::Uuid>().unwrap().as_bytes());
+        let passwordless = json!({ "passkey": authenticator.assert(&challenge().await, Some(&handle)), "scopes": ["read:account"] });
+        let login: Value = client.post(format!("{}/v1/auth/passkey", base)).json(&passwordless).send().await.unwrap().json().await.unwrap();
+        assert_eq!(login["user"]["username"], "avocet");
+        assert_eq!(login["scopes"], json!(["read:account"]));
+        assert_eq!(client.post(format!("{}/v1/auth/passkey", base)).json(&passwordless).send().await.unwrap().status(), 401);
+
+        // Read-only logins can't manage passkeys.
+        let limited = login["access_token"].as_str().unwrap();
+        let remove = |token: &str| client.delete(format!("{}/v1/passkeys/{}", base, passkey["id"].as_str().unwrap())).bearer_auth(token).send();
+        assert_eq!(remove(limited).await.unwrap().status(), 401);
+        assert_eq!(remove(token).await.unwrap().status(), 204);
+        assert_eq!(client.post(format!("{}/v1/auth/login", base)).json(&password_login).send().await.unwrap().status(), 200);
+    }
+
+    #[tokio::test]
+    async fn the_client_library_speaks_the_api() {
+        use stellar_wallet::client::{self, Client, NewAccount};
+
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let mut client = Client::new(&base).unwrap();
+        assert!(Client::new("wallet.example.com").is_err());
+
+        assert!(client.health().await.unwrap());
+        assert_eq!(client.version().await.unwrap().network, "testnet");
+        assert!(matches!(client.me().await, Err(client::Error::NotLoggedIn)));
+
+        let account = NewAccount {
+            email: "plover@example.com".to_string(),
+            username: "plover".to_string(),
+            password: "Sandy-Plover-64!".to_string().into(),
+        };
+        let user = client.create_account(&account, Some("signup-1")).await.unwrap();
+        let login = client.login("plover", "Sandy-Plover-64!", None, &[]).await.unwrap();
+        assert_eq!(login.user.id, user.id);
+        assert_eq!(client.me().await.unwrap().username, "plover");
+        let logged = format!("{:?} {:?}", login, client);
+        assert!(!logged.contains(&login.access_token) && !logged.contains(&login.refresh_token));
+
+        let Err(client::Error::Api(error)) = client.transactions("GNOTANADDRESS", Some(5)).await else {
+            panic!("an invalid address was accepted");
+        };
+        assert_eq!((error.status, error.code.as_str()), (400, "validation_failed"));
+        let Err(client::Error::Api(error)) = client.stats().await else {
+            panic!("a user could read the stats");
+        };
+        assert_eq!(error.status, 401);
+        assert!(client.webhooks(false).await.unwrap().is_empty());
+
+        let refreshed = client.refresh(&login.refresh_token).await.unwrap();
+        assert_eq!(client.access_token(), Some(refreshed.access_token.as_str()));
+        client.logout().await.unwrap();
+        assert!(matches!(client.me().await, Err(client::Error::NotLoggedIn)));
+    }
+
+    #[tokio::test]
+    async fn the_client_library_logs_in_with_oauth_and_passkeys() {
+        use crate::services::oauth_service::tests::{fake_google, state_of};
+        use crate::services::oauth_service::OAuthService;
+        use crate::services::passkey_service::PasskeyService;
+        use crate::services::rate_limit_service::RateLimitService;
+        use crate::utils::webauthn::tests::SoftAuthenticator;
+        use stellar_wallet::client::{Client, NewAccount, SecondFactor};
+
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let (google, profile) = fake_google().await;
+        let oauth = OAuthService::new(db.clone(), "https://app.example.com/oauth", vec![google]).unwrap();
+        let passkeys = PasskeyService::new(db.clone(), "wallet.example.com", "https://wallet.example.com");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let state = ApiState::new(db, &Network::testnet(), tokens)
+            .with_oauth(oauth)
+            .with_passkeys(passkeys)
+            .with_rate_limits(RateLimitService::with_limits(None, None));
+        tokio::spawn(serve(listener, state));
+        let mut authenticator = SoftAuthenticator::new("wallet.example.com", "https://wallet.example.com");
+
+        let mut client = Client::new(&base).unwrap();
+        for username in ["merlin", "avocet"] {
+            let account = NewAccount {
+                email: format!("{}@example.com", username),
+                username: username.to_string(),
+                password: "Swift-Merlin-58!".to_string().into(),
+            };
+            client.create_account(&account, None).await.unwrap();
+        }
+
+        *profile.lock().unwrap() = json!({ "sub": "g-merlin", "email": "merlin@example.com", "email_verified": true });
+        let state = state_of(&client.oauth_url("google").await.unwrap());
+        let login = client.oauth_callback("google", &state, "good-code", None, &["read:account"]).await.unwrap();
+        assert_eq!((login.user.username.as_str(), login.scopes.as_slice()), ("merlin", ["read:account".to_string()].as_slice()));
+        assert!(client.stream_ticket().await.unwrap().expires_at > chrono::Utc::now());
+        assert!(client.oauth_url("myspace").await.is_err());
+
+        let login = client.login("avocet", "Swift-Merlin-58!", None, &[]).await.unwrap();
+        let options = client.passkey_registration_options().await.unwrap();
+        let credential = serde_json::from_value(json!(authenticator.register(&options))).unwrap();
+        let passkey = client.create_passkey(Some("Phone"), &credential).await.unwrap();
+        assert_eq!(client.passkeys().await.unwrap()[0].name, "Phone");
+
+        assert!(client.login("avocet", "Swift-Merlin-58!", None, &[]).await.is_err());
+        let assertion = serde_json::from_value(json!(authenticator.assert(&client.passkey_options().await.unwrap(), None))).unwrap();
+        client.login("avocet", "Swift-Merlin-58!", Some(SecondFactor::Passkey(&assertion)), &[]).await.unwrap();
+
+        let handle = crate::utils::webauthn::encode(login.user.id.as_bytes());
+        let assertion = serde_json::from_value(json!(authenticator.assert(&client.passkey_options().await.unwrap(), Some(&handle)))).unwrap();
+        assert_eq!(client.passkey_login(&assertion, &[]).await.unwrap().user.id, login.user.id);
+        client.remove_passkey(&passkey.id).await.unwrap();
+        assert!(client.passkeys().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+use super::auth::{passkeys, Authenticated};
+use super::error::ApiResult;
+use super::validation::{FieldErrors, Valid, Validate};
+use super::ApiState;
+use crate::errors::AppError;
+use crate::models::passkey::{Passkey, PasskeyRegistration};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreatePasskeyRequest {
+    /// What the user calls it, e.g. "Work laptop"; "Passkey" when left out.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// What `navigator.credentials.create()` returned for the options from
+    /// `/passkeys/registration-options`.
+    pub credential: PasskeyRegistration,
+}
+
+impl Validate for CreatePasskeyRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.require("name", name);
+            if name.trim().chars().count() > MAX_NAME_LENGTH {
+                errors.check("name", Err(AppError::ValidationError(format!("At most {} characters", MAX_NAME_LENGTH))));
+            }
+        }
+        errors.require("credential", &self.credential.id);
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PasskeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<Passkey> for PasskeyResponse {
+    fn from(passkey: Passkey) -> Self {
+        Self {
+            id: passkey.id,
+            name: passkey.name,
+            created_at: passkey.created_at,
+            last_used_at: passkey.last_used_at,
+        }
+    }
+}
+
+/// `POST /passkeys/registration-options`: the options for
+/// `navigator.credentials.create()` to add a passkey to the account.
+pub async fn registration_options(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated) -> ApiResult<Json<Value>> {
+    Ok(Json(passkeys(&state)?.registration_options(&caller.user_id).await?))
+}
+
+/// `POST /passkeys`: saves the passkey the browser created. From then on,
+/// logins need it unless 2FA is on.
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    Valid(request): Valid<CreatePasskeyRequest>,
+) -> ApiResult<(StatusCode, Json<PasskeyResponse>)> {
+    let name = request.name.as_deref().map(str::trim).unwrap_or("Passkey");
+    let passkey = passkeys(&state)?.register(&caller.user_id, name, &request.credential).await?;
+    Ok((StatusCode::CREATED, Json(passkey.into())))
+}
+
+/// `GET /passkeys`: the account's passkeys, oldest first.
+pub async fn list(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated) -> ApiResult<Json<Vec<PasskeyResponse>>> {
+    let passkeys = passkeys(&state)?.passkeys(&caller.user_id).await?;
+    Ok(Json(passkeys.into_iter().map(Into::into).collect()))
+}
+
+/// `DELETE /passkeys/{id}`: stops a passkey working for logins.
+pub async fn remove(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated, Path(id): Path<Uuid>) -> ApiResult<StatusCode> {
+    passkeys(&state)?.remove(&caller.user_id, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,158 @@
+use super::auth::{second_factor_confirmed, Authenticated};
+use super::error::ApiResult;
+use super::idempotency::idempotent;
+use super::rate_limit::enforce;
+use super::validation::Valid;
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::policy::PaymentIntent;
+use crate::models::wallet_event::WalletEvent;
+use crate::services::rate_limit_service::LimitedAction;
+use crate::services::signer_service::SignerKind;
+use crate::stellar::amount::parse_stroops;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use chrono::Utc;
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PaymentRequest {
+    pub destination: String,
+    /// In XLM, e.g. `"12.5"`.
+    pub amount: String,
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Unlocks the keystore key that signs for the wallet address.
+    #[schemars(with = "String")]
+    pub password: SecretString,
+    /// The signing PIN, for accounts that have one.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub pin: Option<SecretString>,
+    /// An authenticator or recovery code, for accounts with 2FA on.
+    #[serde(default)]
+    pub code: Option<String>,
+    /// Send even if the same payment went out in the last few minutes.
+    #[serde(default)]
+    pub allow_duplicate: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PaymentResponse {
+    pub hash: String,
+    pub ledger: u32,
+}
+
+/// `POST /payments`: sends XLM from the account's wallet address, within the
+/// account's payment rate limit. A retry with the same `Idempotency-Key`
+/// gets the first payment's response instead of paying again, or, if
+/// Horizon never confirmed the first payment, finds out what became of it.
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    headers: HeaderMap,
+    Valid(request): Valid<PaymentRequest>,
+) -> ApiResult<Response> {
+    enforce(&state, LimitedAction::Payments, &caller.user_id.to_string()).await?;
+    let scope = format!("payments:{}", caller.user_id);
+    let fingerprint = json!({
+        "destination": request.destination,
+        "amount": request.amount,
+        "memo": request.memo,
+        "allow_duplicate": request.allow_duplicate,
+    });
+
+    let (destination, amount) = (request.destination.trim().to_string(), request.amount.trim().to_string());
+
+    let state = &state;
+    let handle = async { Ok((caller.user_id, send(state, &caller.user_id, request).await?)) };
+    let resume = |hash: String| async move {
+        let result = state.transaction_service.confirm(&hash).await;
+        if !matches!(result, Err(AppError::UnconfirmedError { .. })) {
+            state.audit_service.record_payment(&caller.user_id, &destination, &amount, &result).await?;
+        }
+        let result = result?;
+        Ok((caller.user_id, PaymentResponse { hash: result.hash, ledger: result.ledger }))
+    };
+    idempotent(state, &headers, &scope, &fingerprint, StatusCode::OK, handle, resume).await
+}
+
+/// The CLI's payment checks without the prompts: transaction policies, the
+/// repeat-payment guard, 2FA and the signing PIN all apply, and the outcome
+/// is audited. Only keystore keys can sign here; a Ledger needs someone at
+/// the device. `request` must have passed its [`Validate`] checks.
+///
+/// [`Validate`]: super::validation::Validate
+pub(super) async fn send(state: &ApiState, user_id: &Uuid, request: PaymentRequest) -> Result<PaymentResponse> {
+    let destination = request.destination.trim();
+    let amount = request.amount.trim();
+    let memo = request.memo.as_deref().map(str::trim).filter(|memo| !memo.is_empty());
+
+    let user = state
+        .user_service
+        .get_user(user_id)
+        .await?
+        .ok_or_else(|| AppError::AuthenticationError("Invalid or expired access token".to_string()))?;
+    let source = user
+        .stellar_public_key
+        .as_deref()
+        .ok_or_else(|| AppError::StellarError("No Stellar address is linked to this account yet".to_string()))?;
+    if state.signer_service.signer_kind(user_id, source).await? != SignerKind::Software {
+        return Err(AppError::ValidationError(
+            "This address signs with a Ledger, which the API can't reach; send the payment from the wallet".to_string(),
+        ));
+    }
+
+    let intent = PaymentIntent {
+        destination: destination.to_string(),
+        asset_code: "XLM".to_string(),
+        amount_stroops: parse_stroops(amount)?,
+        network: state.transaction_service.network().name.clone(),
+    };
+    state.policy_service.check(user_id, &intent).await?;
+
+    if !request.allow_duplicate {
+        if let Some(ago) = state.transaction_service.recent_duplicate(source, destination, amount, memo).await? {
+            return Err(AppError::ValidationError(format!(
+                "You sent this exact payment {}s ago; set 'allow_duplicate' to send it again",
+                ago.as_secs()
+            )));
+        }
+    }
+
+    if !second_factor_confirmed(state, user_id, request.code.as_deref(), "payments").await? {
+        return Err(AppError::AuthenticationError("2FA code not confirmed".to_string()));
+    }
+    if state.signing_pin_service.is_enabled(user_id).await? {
+        let pin = request
+            .pin
+            .as_ref()
+            .ok_or_else(|| AppError::AuthenticationError("This account has a signing PIN; send it as 'pin'".to_string()))?;
+        state.signing_pin_service.verify(user_id, pin.expose_secret(), Utc::now()).await?;
+    }
+
+    let signer = state.signer_service.unlock_software(user_id, source, request.password.expose_secret()).await?;
+    let result = state.transaction_service.send_payment(signer.as_ref(), destination, amount, memo).await;
+    state.accounts.invalidate(&[source, destination]).await;
+    state.audit_service.record_payment(user_id, destination, amount, &result).await?;
+    let result = result?;
+
+    // The event watcher would only notice a payment to a wallet here on its
+    // next look at Horizon.
+    match state.transaction_service.received(&result.hash, destination).await {
+        Ok(Some((recipient, transaction))) => state.events.publish(recipient, WalletEvent::PaymentReceived { transaction }),
+        Ok(None) => {}
+        Err(e) => tracing::warn!(hash = %result.hash, "Couldn't tell the recipient about a payment: {}", e),
+    }
+
+    Ok(PaymentResponse {
+        hash: result.hash,
+        ledger: result.ledger,
+    })
+}
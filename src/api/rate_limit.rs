@@ -0,0 +1,46 @@
+use super::error::{ApiError, ApiResult};
+use super::ApiState;
+use crate::services::rate_limit_service::LimitedAction;
+
+/// Counts a request against `key`'s limit for `action`, turning it away with
+/// `429 Too Many Requests` and a `Retry-After` once the limit is used up.
+pub(super) async fn enforce(state: &ApiState, action: LimitedAction, key: &str) -> ApiResult<()> {
+    match state.rate_limits.check(action, key).await {
+        Some(wait) => Err(ApiError::rate_limited(wait)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{serve, ApiState};
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::rate_limit_service::{RateLimit, RateLimitService};
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn turns_away_repeated_logins_from_one_address() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/auth/login", listener.local_addr().unwrap());
+        let limits = RateLimitService::with_limits(Some(RateLimit { per_minute: 2 }), None);
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens).with_rate_limits(limits)));
+        let client = reqwest::Client::new();
+        let login = json!({ "identifier": "otter", "password": "Velvet-Otter-92!" });
+
+        for _ in 0..2 {
+            assert_ne!(client.post(&url).json(&login).send().await.unwrap().status(), 429);
+        }
+        let response = client.post(&url).json(&login).send().await.unwrap();
+        assert_eq!(response.status(), 429);
+        let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+        assert!((1..=30).contains(&retry_after));
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["code"], "rate_limited");
+        assert_eq!(error["details"]["retry_after_secs"], retry_after);
+    }
+}
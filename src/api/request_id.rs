@@ -0,0 +1,76 @@
+use crate::utils::request_id;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Gives every request an id: the client's `X-Request-Id` if it sent a
+/// usable one, or a new one. The handler runs in a tracing span and a
+/// [`request_id::scope`] for it, and the response carries it back.
+pub(super) async fn assign(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(request_id::parse)
+        .unwrap_or_else(request_id::generate);
+    let span = tracing::info_span!("request", request_id = %id, method = %request.method(), path = %request.uri().path());
+
+    let mut response = request_id::scope(id.clone(), next.run(request)).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{serve, ApiState};
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::models::audit::AuditFilter;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn ties_responses_and_audit_entries_to_the_request() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db.clone(), &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let health = client.get(format!("{}/health", base)).send().await.unwrap();
+        assert_eq!(health.headers()["x-request-id"].to_str().unwrap().len(), 36);
+
+        let signup = json!({ "email": "otter@example.com", "username": "otter", "password": "Velvet-Otter-92!" });
+        let created = client
+            .post(format!("{}/v1/accounts", base))
+            .header("x-request-id", "signup-7f3a")
+            .json(&signup)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(created.headers()["x-request-id"], "signup-7f3a");
+        let entries = db.get_audit_entries(&AuditFilter { limit: 10, ..AuditFilter::default() }).await.unwrap();
+        assert_eq!(entries[0].event_type, "account_created");
+        assert_eq!(entries[0].request_id.as_deref(), Some("signup-7f3a"));
+
+        let unusable = client
+            .post(format!("{}/v1/accounts", base))
+            .header("x-request-id", "has spaces")
+            .json(&signup)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unusable.status(), 400);
+        let id = unusable.headers()["x-request-id"].to_str().unwrap().to_string();
+        assert_ne!(id, "has spaces");
+        assert_eq!(unusable.json::<Value>().await.unwrap()["request_id"], id);
+    }
+}
@@ -0,0 +1,164 @@
+use super::auth::{log_in, Login, LoginRequest};
+use super::payments::{send, PaymentRequest, PaymentResponse};
+use super::stats::{collect, Stats};
+use super::validation::validate;
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::models::session::{SavedLogin, Session};
+use crate::services::session_service;
+use crate::services::token_service::SessionStore;
+
+/// The API's login, payments and statistics for the CLI's subcommands,
+/// without a server in between. `login` saves the session the way "stay
+/// logged in" does, and the other commands act as that session, so they
+/// share the interactive wallet's saved login.
+pub struct Scripted {
+    state: ApiState,
+    session_store: SessionStore,
+}
+
+impl Scripted {
+    pub fn new(state: ApiState, session_store: SessionStore) -> Self {
+        Self { state, session_store }
+    }
+
+    /// Logs in as this device, with the interactive login's lockouts and
+    /// audit entries, and saves the login.
+    pub async fn login(&self, request: LoginRequest) -> Result<Login> {
+        validate(&request)?;
+        let login = log_in(&self.state, session_service::device_label(), request).await?;
+
+        self.session_store.save(&SavedLogin {
+            access_token: login.access_token.clone(),
+            refresh_token: login.refresh_token.clone(),
+            remote: None,
+        })?;
+        Ok(login)
+    }
+
+    /// Ends the saved login's session and forgets it. Succeeds when there
+    /// is nothing to end.
+    pub async fn logout(&self) -> Result<()> {
+        match self.session().await {
+            Ok(session) => self.state.session_service.logout(&session.user_id, &session.id).await?,
+            Err(AppError::AuthenticationError(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.session_store.clear()
+    }
+
+    /// Sends a payment as the logged-in user, with the same checks as
+    /// `POST /payments`.
+    pub async fn pay(&self, request: PaymentRequest) -> Result<PaymentResponse> {
+        validate(&request)?;
+        let session = self.session_for(ApiScope::Payments).await?;
+        send(&self.state, &session.user_id, request).await
+    }
+
+    pub async fn stats(&self) -> Result<Stats> {
+        let session = self.session_for(ApiScope::Reports).await?;
+        collect(&self.state, &session.user_id).await
+    }
+
+    /// The saved login's session, if it was granted `scope`.
+    async fn session_for(&self, scope: ApiScope) -> Result<Session> {
+        let session = self.session().await?;
+        if !session.scopes.contains(&scope) {
+            return Err(AppError::AuthenticationError(format!(
+                "The saved login doesn't have the '{}' scope; log in again with it",
+                scope.as_str()
+            )));
+        }
+        Ok(session)
+    }
+
+    /// The saved login's session, renewing an expired access token with the
+    /// refresh token. A login that has ended is forgotten.
+    async fn session(&self) -> Result<Session> {
+        let Some(saved) = self.session_store.load()? else {
+            return Err(AppError::AuthenticationError("Not logged in; run `login` first".to_string()));
+        };
+        if let Some(remote) = &saved.remote {
+            return Err(AppError::AuthenticationError(format!(
+                "The saved login is to {}; add `--remote {}`, or run `login` again",
+                remote, remote
+            )));
+        }
+        let tokens = &self.state.tokens;
+        let session_service = &self.state.session_service;
+
+        match tokens.authenticate(&saved.access_token, session_service).await {
+            Err(AppError::AuthenticationError(_)) => {}
+            resumed => return resumed,
+        }
+        match session_service.rotate_refresh_token(&saved.refresh_token).await {
+            Ok((session, refresh_token)) => {
+                self.session_store.save(&SavedLogin {
+                    access_token: tokens.issue(&session)?,
+                    refresh_token,
+                    remote: None,
+                })?;
+                Ok(session)
+            }
+            Err(AppError::AuthenticationError(_)) => {
+                self.session_store.clear()?;
+                Err(AppError::AuthenticationError("Your saved login has ended; run `login` again".to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::models::role::Role;
+    use crate::models::user::CreateUserRequest;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn acts_as_the_saved_login_until_logout() {
+        let db = SqliteDatabase::in_memory().await;
+        let state = ApiState::new(db, &Network::testnet(), TokenService::new(&[7; 32], None).unwrap());
+        let user = state
+            .user_service
+            .create_user(CreateUserRequest {
+                email: "heron@example.com".to_string(),
+                username: "heron".to_string(),
+                password: "Quiet-Heron-58!".to_string().into(),
+            })
+            .await
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("wallet-scripted-{}", Uuid::new_v4()));
+        let store = SessionStore::File(path.to_string_lossy().into_owned());
+        let scripted = Scripted::new(state, store.clone());
+
+        let not_logged_in = scripted.stats().await.unwrap_err();
+        assert!(matches!(not_logged_in, AppError::AuthenticationError(_)));
+
+        let request = |password: &str| LoginRequest {
+            identifier: "heron".to_string(),
+            password: password.to_string().into(),
+            code: None,
+            passkey: None,
+            scopes: None,
+        };
+        assert!(scripted.login(request("wrong-password")).await.is_err());
+        assert_eq!(store.load().unwrap(), None);
+
+        let login = scripted.login(request("Quiet-Heron-58!")).await.unwrap();
+        assert_eq!(login.user.id, user.id);
+        assert!(scripted.stats().await.unwrap_err().message().contains("isn't allowed"));
+
+        scripted.state.user_service.set_role(&user.id, Role::Admin).await.unwrap();
+        assert_eq!(scripted.stats().await.unwrap().total_users, 1);
+
+        scripted.logout().await.unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        assert!(matches!(scripted.stats().await, Err(AppError::AuthenticationError(_))));
+    }
+}
@@ -0,0 +1,175 @@
+use super::auth::{authenticate, bearer_token, Authenticated, Caller};
+use super::error::ApiResult;
+use super::v1::dto;
+use super::ApiState;
+use crate::errors::AppError;
+use crate::models::api_key::ApiScope;
+use crate::models::stream_ticket::StreamTicket;
+use crate::models::wallet_event::WalletEvent;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// From `POST /v1/events/tickets`, for `EventSource`, which can't send an
+    /// `Authorization` header.
+    #[serde(default)]
+    pub ticket: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StreamTicketResponse {
+    /// Opens `/v1/events?ticket=` once, until `expires_at`.
+    pub ticket: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// `POST /v1/events/tickets`: a ticket for a browser to open `/v1/events`
+/// with, so the access token never goes in a URL. The stream it opens ends
+/// when the token would have.
+pub async fn ticket(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+) -> ApiResult<(StatusCode, Json<StreamTicketResponse>)> {
+    let ticket = StreamTicket {
+        user_id: caller.user_id,
+        session_id: caller.session_id,
+        api_key_id: caller.api_key_id,
+        stream_expires_at: caller.expires_at,
+    };
+    let (ticket, expires_at) = state.stream_ticket_service.issue(&ticket).await?;
+    Ok((StatusCode::CREATED, Json(StreamTicketResponse { ticket, expires_at })))
+}
+
+/// `GET /v1/events`: the user's account [`dto::Event`]s as server-sent events, for
+/// clients that can't hold a WebSocket. Each is named after its `type`, with
+/// the event's JSON as data. Authenticates like any other request, or with a
+/// `?ticket=` from a browser's `EventSource`. The stream ends once its own
+/// session or API key is revoked, the account is disabled, or its access
+/// token expires.
+pub async fn stream(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Query(query): Query<EventsQuery>,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let caller = match (bearer_token(&headers), query.ticket.as_deref()) {
+        (Some(token), _) => authenticate(&state, token, Some(ApiScope::Read)).await?,
+        (None, Some(ticket)) => {
+            let ticket = state.stream_ticket_service.redeem(ticket).await?;
+            Caller {
+                user_id: ticket.user_id,
+                session_id: ticket.session_id,
+                api_key_id: ticket.api_key_id,
+                expires_at: ticket.stream_expires_at,
+            }
+        }
+        (None, None) => {
+            return Err(AppError::AuthenticationError(
+                "Send an access token as 'Authorization: Bearer <token>', or a ticket from /v1/events/tickets as '?ticket='".to_string(),
+            )
+            .into())
+        }
+    };
+
+    let expiry = caller.expiry();
+    let events = stream::unfold(Some((state.events.subscribe(caller.user_id), caller)), |open| async move {
+        let (mut events, caller) = open?;
+        let event = events.next().await?;
+        let ended = caller.ends_with(&event);
+        Some((Ok(message(event)), (!ended).then_some((events, caller))))
+    });
+    let ready = stream::once(async { Ok(Event::default().event("ready").data("{}")) });
+
+    Ok(Sse::new(ready.chain(events).take_until(expiry)).keep_alive(KeepAlive::default()))
+}
+
+fn message(event: WalletEvent) -> Event {
+    let data = serde_json::to_value(dto::Event::from(event)).unwrap_or_default();
+    let name = data.get("type").and_then(Value::as_str).unwrap_or("message").to_string();
+    Event::default().event(name).data(data.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{serve, ApiState};
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::session_service::SessionService;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use chrono::Duration;
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn streams_events_until_the_session_is_revoked() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let sessions = SessionService::new(db.clone());
+        let session = sessions.start(&user, "test").await.unwrap();
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let token = tokens.issue(&session).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/events", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db.clone(), &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 401);
+        assert_eq!(response.json::<Value>().await.unwrap()["code"], "unauthenticated");
+        assert_eq!(client.get(format!("{}?access_token={}", url, token)).send().await.unwrap().status(), 401);
+
+        let ticket = client.post(format!("{}/tickets", url)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(ticket.status(), 201);
+        let ticket = ticket.json::<Value>().await.unwrap()["ticket"].as_str().unwrap().to_string();
+        let mut response = client.get(format!("{}?ticket={}", url, ticket)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        assert_eq!(client.get(format!("{}?ticket={}", url, ticket)).send().await.unwrap().status(), 401);
+
+        let mut received = String::new();
+        while !received.contains("event: ready\n") {
+            received.push_str(&String::from_utf8_lossy(&response.chunk().await.unwrap().unwrap()));
+        }
+
+        sessions.revoke(&user, &session.id).await.unwrap();
+        while let Some(chunk) = response.chunk().await.unwrap() {
+            received.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        let revoked = json!({ "type": "session_revoked", "session_id": session.id });
+        assert!(received.contains(&format!("event: session_revoked\ndata: {}\n\n", revoked)), "{}", received);
+    }
+
+    #[tokio::test]
+    async fn ends_when_the_access_token_expires() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let session = SessionService::new(db.clone()).start(&user, "test").await.unwrap();
+        let tokens = TokenService::new(&[7; 32], Some(Duration::seconds(2))).unwrap();
+        let token = tokens.issue(&session).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/events", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let client = reqwest::Client::new();
+
+        let ticket: Value = client.post(format!("{}/tickets", url)).bearer_auth(&token).send().await.unwrap().json().await.unwrap();
+        for request in [client.get(&url).bearer_auth(&token), client.get(format!("{}?ticket={}", url, ticket["ticket"].as_str().unwrap()))] {
+            let mut response = request.send().await.unwrap();
+            assert_eq!(response.status(), 200);
+            let ended = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+                while response.chunk().await.unwrap().is_some() {}
+            });
+            assert!(ended.await.is_ok(), "the stream outlived its access token");
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::auth::Authenticated;
+use super::error::ApiResult;
+use super::ApiState;
+use crate::errors::Result;
+use crate::models::role::Permission;
+use axum::extract::State;
+use axum::Json;
+use chrono::{Duration, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Stats {
+    pub total_users: i64,
+    /// Only when SMS is enabled.
+    pub sms_last_30_days: Option<SmsStats>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SmsStats {
+    pub messages: i64,
+    pub cost_usd: f64,
+}
+
+/// `GET /stats`: the admin menu's statistics, for roles that may view reports.
+pub async fn show(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated) -> ApiResult<Json<Stats>> {
+    Ok(Json(collect(&state, &caller.user_id).await?))
+}
+
+pub(super) async fn collect(state: &ApiState, user_id: &Uuid) -> Result<Stats> {
+    state.admin_service.authorize(user_id, Permission::ViewReports).await?;
+
+    let sms_last_30_days = if state.sms_service.is_enabled() {
+        let usage = state.sms_service.usage_since(Utc::now() - Duration::days(30)).await?;
+        Some(SmsStats { messages: usage.messages, cost_usd: usage.cost_usd })
+    } else {
+        None
+    };
+
+    Ok(Stats {
+        total_users: state.user_service.get_user_count().await?,
+        sms_last_30_days,
+    })
+}
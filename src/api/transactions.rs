@@ -0,0 +1,48 @@
+use super::auth::Authenticated;
+use super::error::ApiResult;
+use super::graphql::wallets;
+use super::v1::dto::Transaction;
+use super::ApiState;
+use crate::errors::{AppError, Result};
+use crate::models::transaction::WalletTransaction;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const DEFAULT_LIMIT: u32 = 20;
+pub const MAX_LIMIT: u32 = 200;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TransactionsQuery {
+    /// At most 200; 20 when left out.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// `GET /wallets/{address}/transactions`: the wallet's latest payments.
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    Path(address): Path<String>,
+    Query(query): Query<TransactionsQuery>,
+) -> ApiResult<Json<Vec<Transaction>>> {
+    let transactions = recent(&state, &caller.user_id, &address, query.limit).await?;
+    Ok(Json(transactions.into_iter().map(Into::into).collect()))
+}
+
+/// Payments to or from one of the user's wallets, newest first, brought up
+/// to date from Horizon when it can be reached.
+pub(super) async fn recent(state: &ApiState, user_id: &Uuid, address: &str, limit: Option<u32>) -> Result<Vec<WalletTransaction>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(AppError::ValidationError(format!("'limit' must be between 1 and {}", MAX_LIMIT)));
+    }
+    if !wallets(state, user_id).await?.iter().any(|wallet| wallet.address == address) {
+        return Err(AppError::ValidationError(format!("{} isn't one of your wallets", address)));
+    }
+
+    Ok(state.history_service.recent(address, limit).await?.transactions)
+}
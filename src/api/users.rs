@@ -0,0 +1,19 @@
+use super::auth::Authenticated;
+use super::error::ApiResult;
+use super::v1::dto::User;
+use super::ApiState;
+use crate::errors::AppError;
+use axum::extract::State;
+use axum::Json;
+use std::sync::Arc;
+
+/// `GET /users/me`: the account the access token belongs to.
+pub async fn me(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated) -> ApiResult<Json<User>> {
+    let user = state
+        .user_service
+        .get_profile(&caller.user_id)
+        .await?
+        .ok_or_else(|| AppError::AuthenticationError("Invalid or expired access token".to_string()))?;
+
+    Ok(Json(user.into()))
+}
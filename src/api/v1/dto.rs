@@ -0,0 +1,144 @@
+//! What `/v1` sends, decoupled from the models the services pass around, so
+//! a model can grow or change without changing these.
+
+use crate::api::auth::Login;
+use crate::models::role::Role;
+use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+use crate::models::user::UserResponse;
+use crate::models::wallet_event::WalletEvent;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct User {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub is_verified: bool,
+    pub stellar_public_key: Option<String>,
+    /// On a login, the one before it.
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub login_count: i64,
+    pub role: Role,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<UserResponse> for User {
+    fn from(user: UserResponse) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            is_verified: user.is_verified,
+            stellar_public_key: user.stellar_public_key,
+            last_login_at: user.last_login_at,
+            login_count: user.login_count,
+            role: user.role,
+            disabled_at: user.disabled_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// One payment operation as seen from `account`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Transaction {
+    pub account: String,
+    pub hash: String,
+    pub operation_index: i64,
+    pub direction: TransactionDirection,
+    pub asset_code: String,
+    pub amount_stroops: i64,
+    pub counterparty: String,
+    pub memo: Option<String>,
+    pub status: TransactionStatus,
+    pub ledger: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<WalletTransaction> for Transaction {
+    fn from(transaction: WalletTransaction) -> Self {
+        Self {
+            account: transaction.account,
+            hash: transaction.hash,
+            operation_index: transaction.operation_index,
+            direction: transaction.direction,
+            asset_code: transaction.asset_code,
+            amount_stroops: transaction.amount_stroops,
+            counterparty: transaction.counterparty,
+            memo: transaction.memo,
+            status: transaction.status,
+            ledger: transaction.ledger,
+            error: transaction.error,
+            created_at: transaction.created_at,
+            updated_at: transaction.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LoginResponse {
+    pub access_token: String,
+    /// Always `Bearer`.
+    pub token_type: &'static str,
+    /// When the access token stops working; refresh it before then.
+    pub expires_at: DateTime<Utc>,
+    /// Trade it at `/v1/auth/refresh` for the next access token. Each one
+    /// works once; a reused one ends the session.
+    pub refresh_token: String,
+    /// What the access token may be used for, e.g. `read:account`.
+    pub scopes: Vec<String>,
+    pub user: User,
+}
+
+impl From<Login> for LoginResponse {
+    fn from(login: Login) -> Self {
+        Self {
+            access_token: login.access_token,
+            token_type: "Bearer",
+            expires_at: login.expires_at,
+            refresh_token: login.refresh_token,
+            scopes: login.scopes.iter().map(|scope| scope.as_str().to_string()).collect(),
+            user: login.user.into(),
+        }
+    }
+}
+
+/// An account event on `/v1/ws` and `/v1/events`, e.g.
+/// `{"type": "payment_received", "transaction": {...}}`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    PaymentReceived { transaction: Transaction },
+    TransactionConfirmed { transaction: Transaction },
+    TransactionFailed { transaction: Transaction },
+    AccountVerified { verified_at: DateTime<Utc> },
+    SessionRevoked { session_id: Uuid },
+    ApiKeyRevoked { key_id: Uuid },
+    AccountDisabled { disabled_at: DateTime<Utc> },
+}
+
+impl From<WalletEvent> for Event {
+    fn from(event: WalletEvent) -> Self {
+        match event {
+            WalletEvent::PaymentReceived { transaction } => Event::PaymentReceived {
+                transaction: transaction.into(),
+            },
+            WalletEvent::TransactionConfirmed { transaction } => Event::TransactionConfirmed {
+                transaction: transaction.into(),
+            },
+            WalletEvent::TransactionFailed { transaction } => Event::TransactionFailed {
+                transaction: transaction.into(),
+            },
+            WalletEvent::AccountVerified { verified_at } => Event::AccountVerified { verified_at },
+            WalletEvent::SessionRevoked { session_id } => Event::SessionRevoked { session_id },
+            WalletEvent::ApiKeyRevoked { key_id } => Event::ApiKeyRevoked { key_id },
+            WalletEvent::AccountDisabled { disabled_at } => Event::AccountDisabled { disabled_at },
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Version 1 of the REST API, served under `/v1`.
+//!
+//! A breaking change to a request or response ships as a new version: a
+//! `v2` module with its own routes and DTOs, nested under `/v2` next to
+//! this one, reusing the handlers that didn't change. Adding an optional
+//! request field or a response field isn't breaking. Handlers answer with
+//! the types in [`dto`], never with models, so `/v1` keeps its shape however
+//! the models change.
+//!
+//! Authenticated routes say which scope an access token or API key needs
+//! with an `Extension<ApiScope>`. Without one a route needs every scope, and
+//! API keys can't call it.
+//!
+//! The same routes are also served without a prefix for clients from before
+//! versioning, marked with a `Deprecation` header pointing them at `/v1`.
+
+pub mod dto;
+
+use super::{accounts, auth, events, passkeys, payments, sse, stats, transactions, users, webhooks, ApiState};
+use crate::models::api_key::ApiScope;
+use axum::http::header::LINK;
+use axum::http::{HeaderName, HeaderValue};
+use axum::response::Response;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Router};
+use std::sync::Arc;
+
+pub fn routes() -> Router<Arc<ApiState>> {
+    Router::new()
+        .route("/accounts", post(accounts::create))
+        .route("/accounts/batch", post(accounts::create_batch).layer(Extension(ApiScope::Users)))
+        .route("/auth/login", post(auth::login))
+        .route("/auth/refresh", post(auth::refresh))
+        .route("/auth/logout", post(auth::logout))
+        .route("/auth/oauth/{provider}", get(auth::oauth_start))
+        .route("/auth/oauth/{provider}/callback", post(auth::oauth_callback))
+        .route("/auth/passkey/options", post(auth::passkey_options))
+        .route("/auth/passkey", post(auth::passkey_login))
+        .route("/passkeys/registration-options", post(passkeys::registration_options))
+        .route("/passkeys", post(passkeys::create).get(passkeys::list))
+        .route("/passkeys/{id}", delete(passkeys::remove))
+        .route("/users/me", get(users::me).layer(Extension(ApiScope::Read)))
+        .route("/payments", post(payments::create).layer(Extension(ApiScope::Payments)))
+        .route("/wallets/{address}/transactions", get(transactions::list).layer(Extension(ApiScope::Read)))
+        .route("/stats", get(stats::show).layer(Extension(ApiScope::Reports)))
+        .route("/webhooks", post(webhooks::create).get(webhooks::list).layer(Extension(ApiScope::Webhooks)))
+        .route("/webhooks/test", post(webhooks::test).layer(Extension(ApiScope::AdminWebhooks)))
+        .route("/webhooks/{id}", delete(webhooks::remove).layer(Extension(ApiScope::Webhooks)))
+        .route("/webhooks/{id}/deliveries", get(webhooks::deliveries).layer(Extension(ApiScope::Webhooks)))
+        .route("/ws", get(events::connect))
+        .route("/events", get(sse::stream))
+        .route("/events/tickets", post(sse::ticket).layer(Extension(ApiScope::Read)))
+}
+
+/// [`routes`] without the `/v1` prefix, as they were before versioning.
+pub fn unversioned_routes() -> Router<Arc<ApiState>> {
+    routes().layer(axum::middleware::map_response(deprecated))
+}
+
+async fn deprecated(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    headers.insert(LINK, HeaderValue::from_static("</v1>; rel=\"successor-version\""));
+    response
+}
@@ -0,0 +1,164 @@
+use super::auth::{LoginRequest, OAuthCallbackRequest, PasskeyLoginRequest, RefreshRequest};
+use super::error::ApiError;
+use super::payments::PaymentRequest;
+use crate::errors::{AppError, Result};
+use crate::models::user::CreateUserRequest;
+use crate::utils::validation::Validator;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use secrecy::ExposeSecret;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Field-by-field checks for a request body, run before any handler sees it.
+pub trait Validate {
+    /// Records a message in `errors` for each field that fails its checks.
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+/// Messages for the fields of a request that failed validation.
+#[derive(Debug, Default, PartialEq)]
+pub struct FieldErrors(BTreeMap<&'static str, Vec<String>>);
+
+impl FieldErrors {
+    /// Records the message of a failed `check` against `field`.
+    pub fn check(&mut self, field: &'static str, check: Result<()>) {
+        if let Err(e) = check {
+            self.0.entry(field).or_default().push(e.message().to_string());
+        }
+    }
+
+    pub fn require(&mut self, field: &'static str, value: &str) {
+        if value.trim().is_empty() {
+            self.0.entry(field).or_default().push(format!("'{}' can't be empty", field));
+        }
+    }
+
+    /// A validation error naming every field, with the messages by field as
+    /// `details.fields` for clients that show them next to their inputs.
+    pub fn into_result(self) -> std::result::Result<(), ApiError> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let summary = self
+            .0
+            .iter()
+            .map(|(field, messages)| format!("{}: {}", field, messages.join(", ")))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Err(ApiError::from(AppError::ValidationError(summary)).with_details(json!({ "fields": self.0 })))
+    }
+}
+
+/// Checks `request` the way [`Valid`] does, for callers that don't receive it
+/// as a JSON body.
+pub fn validate(request: &impl Validate) -> std::result::Result<(), ApiError> {
+    let mut errors = FieldErrors::default();
+    request.validate(&mut errors);
+    errors.into_result()
+}
+
+/// A JSON request body that passed its [`Validate`] checks. Malformed bodies
+/// and failed checks are rejected with the usual error response.
+pub struct Valid<T>(pub T);
+
+impl<S, T> FromRequest<S> for Valid<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> std::result::Result<Self, ApiError> {
+        let Json(body) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| AppError::ValidationError(rejection.body_text()))?;
+
+        validate(&body)?;
+        Ok(Self(body))
+    }
+}
+
+impl Validate for CreateUserRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.check("email", Validator::validate_email(&self.email));
+        errors.check("username", Validator::validate_username(&self.username));
+        errors.check("password", Validator::validate_password(self.password.expose_secret()));
+    }
+}
+
+impl Validate for LoginRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.require("identifier", &self.identifier);
+        errors.require("password", self.password.expose_secret());
+        errors.check("scopes", self.requested_scopes().map(drop));
+    }
+}
+
+impl Validate for OAuthCallbackRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.require("state", &self.state);
+        errors.require("authorization_code", &self.authorization_code);
+        errors.check("scopes", self.requested_scopes().map(drop));
+    }
+}
+
+impl Validate for PasskeyLoginRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.require("passkey", &self.passkey.id);
+        errors.check("scopes", self.requested_scopes().map(drop));
+    }
+}
+
+impl Validate for RefreshRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.require("refresh_token", self.refresh_token.expose_secret());
+    }
+}
+
+impl Validate for PaymentRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.check("destination", Validator::validate_stellar_address(self.destination.trim()));
+        errors.check("amount", Validator::validate_amount(self.amount.trim()));
+        if let Some(memo) = &self.memo {
+            errors.check("memo", Validator::validate_memo(memo.trim()));
+        }
+        errors.require("password", self.password.expose_secret());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_every_failing_field() {
+        let request = CreateUserRequest {
+            email: "not-an-email".to_string(),
+            username: "ok_name".to_string(),
+            password: "short".to_string().into(),
+        };
+
+        let (status, body) = validate(&request).unwrap_err().public();
+        assert_eq!(status, 400);
+        assert!(body.message.starts_with("email: Invalid email format; password: "));
+        let fields = &body.details.unwrap()["fields"];
+        assert_eq!(fields["email"], json!(["Invalid email format"]));
+        assert_eq!(fields["password"], json!(["Password must be at least 8 characters long"]));
+        assert!(fields.get("username").is_none());
+    }
+
+    #[test]
+    fn passes_valid_requests() {
+        let request = LoginRequest {
+            identifier: "otter".to_string(),
+            password: "Velvet-Otter-92!".to_string().into(),
+            code: None,
+            passkey: None,
+            scopes: Some(vec!["read:account".to_string()]),
+        };
+        assert!(validate(&request).is_ok());
+    }
+}
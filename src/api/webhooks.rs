@@ -0,0 +1,161 @@
+use super::auth::Authenticated;
+use super::error::ApiResult;
+use super::validation::{FieldErrors, Valid, Validate};
+use super::ApiState;
+use crate::errors::AppError;
+use crate::models::role::Permission;
+use crate::models::webhook::{WebhookDelivery, WebhookEndpoint, WebhookEventType};
+use crate::services::webhook_endpoint_service::NewWebhook;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, Serializer};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 200;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateWebhookRequest {
+    /// HTTPS, or HTTP to localhost while developing a receiver.
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    /// Signs each request's `X-Wallet-Signature`, at least 16 characters.
+    /// Generated when left out.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub secret: Option<SecretString>,
+    /// Receive every account's events instead of your own. Admins only.
+    #[serde(default)]
+    pub all_accounts: bool,
+}
+
+impl Validate for CreateWebhookRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.require("url", &self.url);
+        if self.events.is_empty() {
+            errors.check("events", Err(AppError::ValidationError("Choose at least one event".to_string())));
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    pub all_accounts: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookEndpoint> for WebhookResponse {
+    fn from(endpoint: WebhookEndpoint) -> Self {
+        Self {
+            id: endpoint.id,
+            url: endpoint.url,
+            events: endpoint.event_types,
+            all_accounts: endpoint.user_id.is_none(),
+            created_at: endpoint.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: WebhookResponse,
+    /// Only shown here; verify requests with it.
+    #[serde(serialize_with = "expose")]
+    #[schemars(with = "String")]
+    pub secret: SecretString,
+}
+
+/// Writes the secret into the response, the one place it's shown; `Debug`
+/// keeps it out of logs.
+fn expose<S: Serializer>(secret: &SecretString, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(secret.expose_secret())
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WebhooksQuery {
+    /// List the endpoints for every account instead of your own. Admins only.
+    #[serde(default)]
+    pub all_accounts: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeliveriesQuery {
+    /// At most 200; 20 when left out.
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// `POST /webhooks`: registers an endpoint for signed event notifications.
+pub async fn create(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    Valid(request): Valid<CreateWebhookRequest>,
+) -> ApiResult<(StatusCode, Json<CreatedWebhook>)> {
+    let webhook = NewWebhook {
+        url: request.url,
+        event_types: request.events,
+        secret: request.secret,
+        all_accounts: request.all_accounts,
+    };
+    let (endpoint, secret) = state.webhook_endpoint_service.register(&caller.user_id, webhook).await?;
+
+    let created = CreatedWebhook {
+        webhook: endpoint.into(),
+        secret,
+    };
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// `GET /webhooks`: the account's endpoints.
+pub async fn list(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    Query(query): Query<WebhooksQuery>,
+) -> ApiResult<Json<Vec<WebhookResponse>>> {
+    let endpoints = state.webhook_endpoint_service.list(&caller.user_id, query.all_accounts).await?;
+    Ok(Json(endpoints.into_iter().map(Into::into).collect()))
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TestWebhookResponse {
+    /// The sample event's id, as the endpoint received it.
+    pub event_id: String,
+}
+
+/// `POST /webhooks/test`: sends a signed sample `webhook.test` event to the
+/// operator's endpoint, `WEBHOOK_URL`, to check its receiver. Admins only.
+pub async fn test(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated) -> ApiResult<Json<TestWebhookResponse>> {
+    state.admin_service.authorize(&caller.user_id, Permission::ManageWebhooks).await?;
+    let event_id = state.webhook_service.send_test().await?;
+    Ok(Json(TestWebhookResponse { event_id }))
+}
+
+/// `DELETE /webhooks/{id}`: stops deliveries to an endpoint and drops its log.
+pub async fn remove(State(state): State<Arc<ApiState>>, Authenticated(caller): Authenticated, Path(id): Path<Uuid>) -> ApiResult<StatusCode> {
+    state.webhook_endpoint_service.remove(&caller.user_id, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `GET /webhooks/{id}/deliveries`: the endpoint's delivery log, newest first.
+pub async fn deliveries(
+    State(state): State<Arc<ApiState>>,
+    Authenticated(caller): Authenticated,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeliveriesQuery>,
+) -> ApiResult<Json<Vec<WebhookDelivery>>> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(AppError::ValidationError(format!("'limit' must be between 1 and {}", MAX_LIMIT)).into());
+    }
+
+    Ok(Json(state.webhook_endpoint_service.deliveries(&caller.user_id, &id, limit).await?))
+}
@@ -0,0 +1,317 @@
+use super::output::OutputFormat;
+use super::CLI;
+use crate::config;
+use crate::errors::{AppError, Result};
+use clap::error::ErrorKind;
+use clap::{Args as ClapArgs, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use secrecy::SecretString;
+use std::io::{self, IsTerminal, Write};
+
+/// A Stellar wallet. Without a command it opens the interactive menu; the
+/// commands do one thing each without prompting, for scripts and cron jobs.
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Args {
+    /// Keep everything in memory and lose it on exit.
+    #[arg(long)]
+    pub ephemeral: bool,
+
+    /// Record the session to FILE, with passwords and keys masked.
+    #[arg(long, value_name = "FILE", conflicts_with = "serve")]
+    pub record: Option<String>,
+
+    /// Serve the JSON API on ADDRESS, API_LISTEN_ADDR or 127.0.0.1:8080
+    /// instead of opening the menu.
+    #[arg(long, value_name = "ADDRESS", num_args = 0..=1)]
+    pub serve: Option<Option<String>>,
+
+    /// Work through the REST API of the wallet deployed at URL instead of a
+    /// local database. Only what the API offers is available.
+    #[arg(long, value_name = "URL", conflicts_with_all = ["ephemeral", "serve"])]
+    pub remote: Option<String>,
+
+    /// How commands print their results: `table` for people, `json` or
+    /// `plain` for scripts, with messages on stderr.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Args {
+    /// Parses the command line, exiting with the usage if it doesn't make
+    /// sense.
+    pub fn read() -> Self {
+        let args = Self::parse();
+        if let Err(e) = args.check() {
+            e.exit();
+        }
+        args
+    }
+
+    /// The menu's options don't apply to commands, and `--output` only
+    /// applies to them.
+    fn check(&self) -> std::result::Result<(), clap::Error> {
+        if self.command.is_some() && (self.ephemeral || self.record.is_some() || self.serve.is_some()) {
+            let message = "--ephemeral, --record and --serve can't be used with a command";
+            return Err(Self::command().error(ErrorKind::ArgumentConflict, message));
+        }
+        if self.command.is_none() && self.output.is_machine_readable() {
+            return Err(Self::command().error(ErrorKind::ArgumentConflict, "--output only applies to commands"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage accounts.
+    #[command(subcommand)]
+    Account(AccountCommand),
+    /// Log in and save the login for `pay`, `stats` and `logout`.
+    Login {
+        /// Email or username.
+        #[arg(long)]
+        identifier: String,
+        #[command(flatten)]
+        password: PasswordSource,
+        /// An authenticator or recovery code, for accounts with 2FA on.
+        #[arg(long)]
+        code: Option<String>,
+        /// Limit the login to SCOPE, e.g. `write:payments`; repeat for more.
+        /// Without any it may do everything.
+        #[arg(long = "scope", value_name = "SCOPE")]
+        scopes: Vec<String>,
+    },
+    /// End the saved login.
+    Logout,
+    /// Send XLM from the logged-in account's wallet address. The password
+    /// unlocks its key; a signing PIN is read from WALLET_PIN.
+    Pay {
+        /// The recipient's Stellar address.
+        #[arg(long)]
+        to: String,
+        /// In XLM, e.g. 12.5.
+        #[arg(long)]
+        amount: String,
+        #[arg(long)]
+        memo: Option<String>,
+        #[command(flatten)]
+        password: PasswordSource,
+        /// An authenticator or recovery code, for accounts with 2FA on.
+        #[arg(long)]
+        code: Option<String>,
+        /// Send even if the same payment went out in the last few minutes.
+        #[arg(long)]
+        allow_duplicate: bool,
+    },
+    /// Show user and SMS statistics, for roles that may view reports.
+    Stats,
+    /// Inspect schema migrations.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Manage the keys that encrypt stored customer data.
+    #[command(subcommand)]
+    CustomerKeys(CustomerKeysCommand),
+    /// Maintain the database file.
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Check webhook delivery.
+    #[command(subcommand)]
+    Webhooks(WebhooksCommand),
+    /// Check the emails the wallet sends.
+    #[command(subcommand)]
+    Email(EmailCommand),
+    /// Compare an account's local history with Horizon and offer to fix it.
+    Reconcile {
+        /// The Stellar address to reconcile.
+        address: String,
+        /// Add missing records and remove duplicates without asking.
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Manage users.
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// Check the configuration, database and Horizon.
+    Doctor,
+    /// Print a completion script for SHELL, e.g.
+    /// `stellar-wallet completions bash > /etc/bash_completion.d/stellar-wallet`.
+    Completions {
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountCommand {
+    /// Sign up a new account.
+    Create {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        username: String,
+        #[command(flatten)]
+        password: PasswordSource,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// List every migration and whether this database has it, applying none.
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CustomerKeysCommand {
+    /// Rewrap stored data keys under the first key in CUSTOMER_DATA_KEYS.
+    Rotate,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Replace the database with an SQLCipher-encrypted copy.
+    Encrypt,
+    /// Run an integrity check, VACUUM and ANALYZE.
+    Maintain,
+    /// Move transactions older than MONTHS whole months into an archive file.
+    Archive {
+        months: u32,
+    },
+    /// Append transactions added or changed since the last export to Parquet
+    /// files under EXPORT_DIR, one directory per month.
+    Export,
+    /// Copy user accounts into the MySQL or MariaDB database at MYSQL_URL,
+    /// creating its users table. Accounts already there are skipped.
+    CopyUsers,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WebhooksCommand {
+    /// Send a signed sample event to WEBHOOK_URL.
+    Test,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EmailCommand {
+    /// Render TEMPLATE, e.g. `email_change_old`, with sample values from
+    /// EMAIL_TEMPLATE_DIR as it would be sent.
+    Preview {
+        template: String,
+        /// Render for LOCALE, e.g. `pt-BR`, instead of EMAIL_LOCALE.
+        #[arg(long)]
+        locale: Option<String>,
+        /// Also write the HTML variant to a file to open in a browser.
+        #[arg(long)]
+        html: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UsersCommand {
+    /// Set a user's role, e.g. to make the first admin.
+    Role {
+        /// Email or username.
+        user: String,
+        /// user, support or admin.
+        role: String,
+    },
+}
+
+/// Writes the completion script for `shell`, generated from the commands
+/// above so it never falls behind them.
+pub fn write_completions(shell: Shell, out: &mut dyn Write) -> Result<()> {
+    let mut command = Args::command();
+    let name = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    out.write_all(&script)
+        .map_err(|e| AppError::InternalError(format!("Failed to write the completion script: {}", e)))
+}
+
+/// Where a command gets a password from, since one on the command line
+/// would end up in shell history and the process list.
+#[derive(Debug, ClapArgs)]
+pub struct PasswordSource {
+    /// Read the password from the first line of stdin instead of
+    /// WALLET_PASSWORD.
+    #[arg(long)]
+    password_stdin: bool,
+}
+
+impl PasswordSource {
+    /// The password from stdin if asked, else `WALLET_PASSWORD`, else a
+    /// prompt when there's a terminal to ask at.
+    pub fn read(&self) -> Result<SecretString> {
+        if self.password_stdin {
+            let mut line = String::new();
+            io::stdin()
+                .read_line(&mut line)
+                .map_err(|e| AppError::InternalError(format!("Failed to read the password from stdin: {}", e)))?;
+            return Ok(SecretString::from(line.trim_end_matches(['\r', '\n']).to_string()));
+        }
+        if let Some(password) = config::var("WALLET_PASSWORD") {
+            return Ok(SecretString::from(password));
+        }
+        if io::stdin().is_terminal() {
+            return CLI::get_password("🔒 Enter your password:");
+        }
+        Err(AppError::ValidationError(
+            "No password; set WALLET_PASSWORD or pass --password-stdin".to_string(),
+        ))
+    }
+}
+
+/// The signing PIN in `WALLET_PIN`, for accounts that have one.
+pub fn signing_pin() -> Option<SecretString> {
+    config::var("WALLET_PIN").map(SecretString::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subcommands_and_rejects_mixing_them_with_menu_options() {
+        Args::command().debug_assert();
+
+        let args = Args::try_parse_from(["wallet", "pay", "--to", "GABC", "--amount", "1.5", "--password-stdin"]).unwrap();
+        let Some(Command::Pay { to, amount, memo, password, allow_duplicate, .. }) = args.command else {
+            panic!("expected pay");
+        };
+        assert_eq!((to.as_str(), amount.as_str(), memo, allow_duplicate), ("GABC", "1.5", None, false));
+        assert!(password.password_stdin);
+
+        let args = Args::try_parse_from(["wallet", "--ephemeral", "--serve"]).unwrap();
+        assert_eq!(args.serve, Some(None));
+        assert!(args.ephemeral && args.command.is_none());
+
+        let args = Args::try_parse_from(["wallet", "stats", "--output", "json"]).unwrap();
+        assert_eq!(args.output, OutputFormat::Json);
+        let args = Args::try_parse_from(["wallet", "--output", "plain", "migrate", "status"]).unwrap();
+        assert_eq!(args.output, OutputFormat::Plain);
+
+        assert!(Args::try_parse_from(["wallet", "--ephemeral", "doctor"]).unwrap().check().is_err());
+        assert!(Args::try_parse_from(["wallet", "--output", "json"]).unwrap().check().is_err());
+        assert!(Args::try_parse_from(["wallet", "--serve", "--record", "session.log"]).is_err());
+        assert!(Args::try_parse_from(["wallet", "db", "archive", "six"]).is_err());
+
+        let args = Args::try_parse_from(["wallet", "--remote", "https://wallet.example.com", "stats"]).unwrap();
+        assert_eq!(args.remote.as_deref(), Some("https://wallet.example.com"));
+        assert!(args.check().is_ok());
+        assert!(Args::try_parse_from(["wallet", "--remote", "https://wallet.example.com", "--serve"]).is_err());
+    }
+
+    #[test]
+    fn completes_every_command() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut script = Vec::new();
+            write_completions(shell, &mut script).unwrap();
+            let script = String::from_utf8(script).unwrap();
+            for command in ["account", "reconcile", "customer-keys", "completions", "output"] {
+                assert!(script.contains(command), "{:?} completions lack {}", shell, command);
+            }
+        }
+    }
+}
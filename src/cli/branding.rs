@@ -0,0 +1,120 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use colored::{Color, ColoredString, Colorize};
+use std::fs;
+use std::sync::OnceLock;
+
+pub const DEFAULT_PRODUCT_NAME: &str = "Stellar Wallet";
+
+static BRANDING: OnceLock<Branding> = OnceLock::new();
+
+/// Operator branding shown by the CLI, so the same build can ship under
+/// different names. Configured once at startup from the environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branding {
+    pub product_name: String,
+    /// Replaces the generated main-menu title, e.g. ASCII art from `BRAND_BANNER_FILE`.
+    pub banner: Option<String>,
+    /// Rules and borders.
+    pub primary_color: Color,
+    /// Titles.
+    pub accent_color: Color,
+    /// Shown on the main menu and with errors, e.g. an email address or URL.
+    pub support_contact: Option<String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            product_name: DEFAULT_PRODUCT_NAME.to_string(),
+            banner: None,
+            primary_color: Color::BrightBlue,
+            accent_color: Color::BrightYellow,
+            support_contact: None,
+        }
+    }
+}
+
+impl Branding {
+    /// Reads `BRAND_NAME`, `BRAND_BANNER_FILE`, `BRAND_PRIMARY_COLOR`,
+    /// `BRAND_ACCENT_COLOR` and `BRAND_SUPPORT_CONTACT`. Unset values keep the
+    /// defaults.
+    pub fn from_env() -> Result<Self> {
+        let var = config::var;
+        let defaults = Self::default();
+
+        let banner = match var("BRAND_BANNER_FILE") {
+            Some(path) => Some(
+                fs::read_to_string(&path)
+                    .map_err(|e| AppError::ValidationError(format!("Can't read BRAND_BANNER_FILE {}: {}", path, e)))?
+                    .trim_end()
+                    .to_string(),
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            product_name: var("BRAND_NAME").unwrap_or(defaults.product_name),
+            banner,
+            primary_color: var("BRAND_PRIMARY_COLOR").map(|c| parse_color(&c)).transpose()?.unwrap_or(defaults.primary_color),
+            accent_color: var("BRAND_ACCENT_COLOR").map(|c| parse_color(&c)).transpose()?.unwrap_or(defaults.accent_color),
+            support_contact: var("BRAND_SUPPORT_CONTACT"),
+        })
+    }
+
+    /// Makes this the branding returned by [`Branding::current`]. Only the first
+    /// call has any effect.
+    pub fn install(self) {
+        let _ = BRANDING.set(self);
+    }
+
+    /// The installed branding, or the defaults if none was installed.
+    pub fn current() -> &'static Branding {
+        BRANDING.get_or_init(Branding::default)
+    }
+
+    pub fn rule(&self, width: usize) -> ColoredString {
+        "=".repeat(width).color(self.primary_color)
+    }
+
+    pub fn title(&self, text: &str) -> ColoredString {
+        text.color(self.accent_color).bold()
+    }
+
+    /// A sentence pointing users at support, if a contact is configured.
+    pub fn support_line(&self) -> Option<String> {
+        self.support_contact.as_ref().map(|contact| format!("Need help? Contact {}", contact))
+    }
+}
+
+/// Accepts terminal color names (`bright blue`, `bright_blue`) and `#rrggbb`.
+fn parse_color(value: &str) -> Result<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        if let (6, Some(r), Some(g), Some(b)) = (hex.len(), channel(0), channel(2), channel(4)) {
+            return Ok(Color::TrueColor { r, g, b });
+        }
+    } else if let Ok(color) = value.replace('_', " ").parse() {
+        return Ok(color);
+    }
+
+    Err(AppError::ValidationError(format!(
+        "Unknown color '{}'; use a name like 'bright blue' or a hex code like '#1e90ff'",
+        value
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_and_hex_colors() {
+        assert_eq!(parse_color("bright_blue").unwrap(), Color::BrightBlue);
+        assert_eq!(parse_color("Magenta").unwrap(), Color::Magenta);
+        assert_eq!(parse_color("#1e90ff").unwrap(), Color::TrueColor { r: 0x1e, g: 0x90, b: 0xff });
+        assert!(parse_color("#1e90f").is_err());
+        assert!(parse_color("#gg0000").is_err());
+        assert!(parse_color("mauve").is_err());
+    }
+}
@@ -1,46 +1,99 @@
+pub mod args;
+pub mod branding;
+pub mod output;
+pub mod theme;
+pub mod transcript;
+
 use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::utils::password_strength::PasswordStrength;
+use branding::Branding;
 use colored::Colorize;
-use std::io::{self, Write};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use output::OutputFormat;
+use secrecy::SecretString;
+use theme::Themed;
+use transcript::Transcript;
+use std::io::{self, IsTerminal, Write};
 
 pub struct CLI;
 
 impl CLI {
     pub fn print_header() {
-        println!("{}", "=".repeat(50).bright_blue());
-        println!("{}", "    🌟 Stellar Wallet - Account Creation    ".bright_yellow().bold());
-        println!("{}", "=".repeat(50).bright_blue());
+        let branding = Branding::current();
+        println!("{}", branding.rule(50));
+        println!("{}", branding.title(&format!("    🌟 {} - Account Creation    ", branding.product_name)));
+        println!("{}", branding.rule(50));
         println!();
     }
 
     pub fn print_success(message: &str) {
-        println!("{} {}", "✅".green(), message.green());
+        Transcript::record("success", message);
+        output::status(&format!("{} {}", "✅".success(), message.success()));
     }
 
     pub fn print_error(message: &str) {
-        println!("{} {}", "❌".red(), message.red());
+        Transcript::record("error", message);
+        output::status(&format!("{} {}", "❌".error(), message.error()));
     }
 
     pub fn print_info(message: &str) {
-        println!("{} {}", "ℹ️".blue(), message.blue());
+        Transcript::record("info", message);
+        output::status(&format!("{} {}", "ℹ️".info(), message.info()));
     }
 
     pub fn get_input(prompt: &str) -> Result<String> {
-        print!("{} ", prompt.cyan());
+        print!("{} ", prompt.prompt());
         io::stdout().flush().map_err(|e| AppError::InternalError(format!("IO error: {}", e)))?;
         
         let mut input = String::new();
         io::stdin().read_line(&mut input)
             .map_err(|e| AppError::InternalError(format!("Failed to read input: {}", e)))?;
         
-        Ok(input.trim().to_string())
+        let input = input.trim().to_string();
+        Transcript::record_input(prompt, &input);
+        Ok(input)
     }
 
-    pub fn get_password(prompt: &str) -> Result<String> {
-        print!("{} ", prompt.cyan());
+    /// Like [`CLI::get_input`], except that one of `shortcuts` pressed as the
+    /// first key is returned at once, without waiting for Enter. Reads whole
+    /// lines as usual when stdin isn't a terminal.
+    pub fn get_choice(prompt: &str, shortcuts: &[char]) -> Result<String> {
+        if !io::stdin().is_terminal() {
+            return Self::get_input(prompt);
+        }
+
+        print!("{} ", prompt.prompt());
         io::stdout().flush().map_err(|e| AppError::InternalError(format!("IO error: {}", e)))?;
+
+        terminal::enable_raw_mode().map_err(|e| AppError::InternalError(format!("Failed to read input: {}", e)))?;
+        let choice = read_choice(shortcuts);
+        let _ = terminal::disable_raw_mode();
+        println!();
+
+        let Some(choice) = choice? else {
+            // Raw mode swallows Ctrl+C, so honour it the way the terminal would have.
+            std::process::exit(130);
+        };
+        Transcript::record_input(prompt, &choice);
+        Ok(choice)
+    }
+
+    /// Reads a line without echoing it. The result is wiped from memory when
+    /// dropped and prints as `[REDACTED]` if it ever reaches `Debug` output.
+    pub fn get_password(prompt: &str) -> Result<SecretString> {
+        if OutputFormat::current().is_machine_readable() {
+            eprint!("{} ", prompt.prompt());
+        } else {
+            print!("{} ", prompt.prompt());
+            io::stdout().flush().map_err(|e| AppError::InternalError(format!("IO error: {}", e)))?;
+        }
         
-        rpassword::read_password()
-            .map_err(|e| AppError::InternalError(format!("Failed to read password: {}", e)))
+        let password = rpassword::read_password()
+            .map_err(|e| AppError::InternalError(format!("Failed to read password: {}", e)))?;
+        Transcript::record("input", &format!("{} [redacted]", prompt));
+        Ok(SecretString::from(password))
     }
 
     pub fn confirm_action(prompt: &str) -> Result<bool> {
@@ -54,13 +107,93 @@ impl CLI {
         }
     }
 
+    pub fn wait_for_enter() {
+        let _ = Self::get_input("Press Enter to continue...");
+    }
+
+    pub fn clear_screen() {
+        print!("\x1B[2J\x1B[1;1H");
+    }
+
     pub fn display_password_requirements() {
-        println!("{}", "Password Requirements:".yellow().bold());
+        println!("{}", "Password Requirements:".warning().bold());
         println!("  • At least 8 characters long");
         println!("  • Contains uppercase letter (A-Z)");
         println!("  • Contains lowercase letter (a-z)");
         println!("  • Contains at least one digit (0-9)");
         println!("  • Contains special character (!@#$%^&*()_+-=[]{{}}|;:,.<>?)");
+        println!("  • Rated at least \"fair\": not built from common words, sequences, keyboard rows or years");
         println!();
     }
+
+    /// What a new login or API key will be able to do with `scopes`, and
+    /// what not, so the user knows what they are handing over.
+    pub fn print_grants(scopes: &[ApiScope]) {
+        println!("{}", "It will be able to:".warning().bold());
+        for scope in scopes {
+            println!("  • {} {}", scope.description(), format!("({})", scope.as_str()).muted());
+        }
+        let withheld: Vec<_> = ApiScope::ALL.into_iter().filter(|scope| !scopes.contains(scope)).collect();
+        if !withheld.is_empty() {
+            println!("{}", "It won't be able to:".muted());
+            for scope in withheld {
+                println!("{}", format!("  • {}", scope.description()).muted());
+            }
+        }
+        println!();
+    }
+
+    /// A strength meter for a password that was just typed, with tips for
+    /// making it harder to guess.
+    pub fn print_password_strength(strength: &PasswordStrength) {
+        let filled = usize::from(strength.score.min(4));
+        let meter = format!("[{}{}] {}", "█".repeat(filled), "░".repeat(4 - filled), strength.label());
+        let meter = match strength.score {
+            0 | 1 => meter.error(),
+            2 => meter.warning(),
+            _ => meter.success(),
+        };
+        println!("   Strength: {}", meter);
+
+        if let Some(warning) = strength.warning {
+            println!("   {}", warning.warning());
+        }
+        for suggestion in &strength.suggestions {
+            println!("   {}", format!("• {}", suggestion).muted());
+        }
+    }
+}
+
+/// Echoes keys until Enter or a shortcut. `None` means Ctrl+C.
+fn read_choice(shortcuts: &[char]) -> Result<Option<String>> {
+    let echo = |text: &str| {
+        print!("{}", text);
+        let _ = io::stdout().flush();
+    };
+    let mut typed = String::new();
+
+    loop {
+        let key = match event::read().map_err(|e| AppError::InternalError(format!("Failed to read input: {}", e)))? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Enter => return Ok(Some(typed.trim().to_string())),
+            KeyCode::Backspace if !typed.is_empty() => {
+                typed.pop();
+                echo("\x08 \x08");
+            }
+            KeyCode::Char(c) if typed.is_empty() && shortcuts.contains(&c.to_ascii_lowercase()) => {
+                echo(&c.to_string());
+                return Ok(Some(c.to_ascii_lowercase().to_string()));
+            }
+            KeyCode::Char(c) => {
+                typed.push(c);
+                echo(&c.to_string());
+            }
+            _ => {}
+        }
+    }
 }
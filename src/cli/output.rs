@@ -0,0 +1,122 @@
+use crate::errors::{AppError, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+static OUTPUT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// How commands print their results (`--output`). `table` is laid out for
+/// people. `json` and `plain` are for scripts: stdout carries only the
+/// result, and progress and error messages go to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    /// One JSON document per command.
+    Json,
+    /// Tab-separated lines without colour or decoration.
+    Plain,
+}
+
+impl OutputFormat {
+    /// Makes this the format returned by [`OutputFormat::current`]. Only the
+    /// first call has any effect.
+    pub fn install(self) {
+        let _ = OUTPUT.set(self);
+    }
+
+    /// The installed format, or `table` if none was installed.
+    pub fn current() -> Self {
+        *OUTPUT.get_or_init(Self::default)
+    }
+
+    pub fn is_machine_readable(self) -> bool {
+        self != Self::Table
+    }
+}
+
+/// A progress note from anywhere in the wallet, e.g. which database file it
+/// opened. Kept off stdout when stdout carries a result.
+pub fn status(message: &str) {
+    if OutputFormat::current().is_machine_readable() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Prints a command's `result` in the installed format, calling `table` to
+/// lay it out for people.
+pub fn emit<T: Serialize>(result: &T, table: impl FnOnce(&T)) -> Result<()> {
+    match OutputFormat::current() {
+        OutputFormat::Table => table(result),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(result).map_err(serialize_error)?),
+        OutputFormat::Plain => {
+            for line in plain_lines(&serde_json::to_value(result).map_err(serialize_error)?) {
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn serialize_error(e: serde_json::Error) -> AppError {
+    AppError::InternalError(format!("Failed to serialize the result: {}", e))
+}
+
+/// `value` as lines for `cut` and `awk`: a list gives a line per item with
+/// its fields tab-separated, anything else a `name<TAB>value` line per field,
+/// with nested names joined by dots.
+fn plain_lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().map(|item| fields(item).into_iter().map(|(_, value)| value).collect::<Vec<_>>().join("\t")).collect(),
+        Value::Object(_) => fields(value).into_iter().map(|(name, value)| format!("{}\t{}", name, value)).collect(),
+        scalar => vec![scalar_text(scalar)],
+    }
+}
+
+/// The scalars in `value` with their dotted paths, ordered by name.
+fn fields(value: &Value) -> Vec<(String, String)> {
+    fn walk(path: String, value: &Value, out: &mut Vec<(String, String)>) {
+        let join = |name: &str| if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) };
+        match value {
+            Value::Object(map) => map.iter().for_each(|(name, value)| walk(join(name), value, out)),
+            Value::Array(items) => items.iter().enumerate().for_each(|(i, value)| walk(join(&i.to_string()), value, out)),
+            scalar => out.push((path, scalar_text(scalar))),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(String::new(), value, &mut out);
+    out
+}
+
+/// Strings unquoted and null as empty, so fields can be used as they are.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.replace(['\t', '\n'], " "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn plain_output_flattens_records_and_lists() {
+        let user = json!({ "username": "heron", "role": "admin", "sms": { "messages": 3 }, "disabled_at": null });
+        assert_eq!(plain_lines(&user), ["disabled_at\t", "role\tadmin", "sms.messages\t3", "username\theron"]);
+
+        let migrations = json!([
+            { "version": 1, "description": "users", "installed_on": "2025-01-01" },
+            { "version": 2, "description": "two\twords", "installed_on": null },
+        ]);
+        assert_eq!(plain_lines(&migrations), ["users\t2025-01-01\t1", "two words\t\t2"]);
+
+        assert_eq!(plain_lines(&json!("evt_1")), ["evt_1"]);
+    }
+}
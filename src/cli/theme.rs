@@ -0,0 +1,212 @@
+use crate::errors::{AppError, Result};
+use colored::{Color, ColoredString, Colorize};
+use std::sync::RwLock;
+
+static ACTIVE: RwLock<ThemeName> = RwLock::new(ThemeName::Default);
+
+/// The color schemes users can pick in Settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeName {
+    Default,
+    /// Brighter colors for dark terminal backgrounds.
+    Dark,
+    /// Avoids yellow and dim text, which wash out on light backgrounds.
+    Light,
+    HighContrast,
+    /// No colors at all, only bold and dim.
+    Monochrome,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 5] = [
+        ThemeName::Default,
+        ThemeName::Dark,
+        ThemeName::Light,
+        ThemeName::HighContrast,
+        ThemeName::Monochrome,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemeName::Default => "default",
+            ThemeName::Dark => "dark",
+            ThemeName::Light => "light",
+            ThemeName::HighContrast => "high-contrast",
+            ThemeName::Monochrome => "monochrome",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim().to_lowercase().replace('_', "-");
+
+        Self::ALL.into_iter().find(|theme| theme.as_str() == value).ok_or_else(|| {
+            let names: Vec<_> = Self::ALL.iter().map(ThemeName::as_str).collect();
+            AppError::ValidationError(format!("Unknown theme '{}'; choose one of {}", value, names.join(", ")))
+        })
+    }
+}
+
+/// Switches every themed string printed from now on.
+pub fn apply(theme: ThemeName) {
+    if let Ok(mut active) = ACTIVE.write() {
+        *active = theme;
+    }
+}
+
+pub fn active() -> ThemeName {
+    ACTIVE.read().map(|active| *active).unwrap_or(ThemeName::Default)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+}
+
+const fn style(color: Option<Color>, bold: bool, dimmed: bool) -> Style {
+    Style { color, bold, dimmed }
+}
+
+impl Style {
+    fn paint(self, text: &str) -> ColoredString {
+        let mut painted = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        painted
+    }
+}
+
+/// The style of each role text can play.
+struct Palette {
+    heading: Style,
+    prompt: Style,
+    success: Style,
+    error: Style,
+    info: Style,
+    warning: Style,
+    muted: Style,
+}
+
+fn palette(theme: ThemeName) -> Palette {
+    use Color::*;
+
+    match theme {
+        ThemeName::Default => Palette {
+            heading: style(Some(Cyan), true, false),
+            prompt: style(Some(Cyan), false, false),
+            success: style(Some(Green), false, false),
+            error: style(Some(Red), false, false),
+            info: style(Some(Blue), false, false),
+            warning: style(Some(Yellow), false, false),
+            muted: style(None, false, true),
+        },
+        ThemeName::Dark => Palette {
+            heading: style(Some(BrightCyan), true, false),
+            prompt: style(Some(BrightCyan), false, false),
+            success: style(Some(BrightGreen), false, false),
+            error: style(Some(BrightRed), false, false),
+            info: style(Some(BrightBlue), false, false),
+            warning: style(Some(BrightYellow), false, false),
+            muted: style(Some(BrightBlack), false, false),
+        },
+        ThemeName::Light => Palette {
+            heading: style(Some(Blue), true, false),
+            prompt: style(Some(Blue), false, false),
+            success: style(Some(Green), false, false),
+            error: style(Some(Red), false, false),
+            info: style(Some(Blue), false, false),
+            warning: style(Some(Magenta), false, false),
+            muted: style(Some(BrightBlack), false, false),
+        },
+        ThemeName::HighContrast => Palette {
+            heading: style(Some(BrightWhite), true, false),
+            prompt: style(Some(BrightWhite), true, false),
+            success: style(Some(BrightGreen), true, false),
+            error: style(Some(BrightRed), true, false),
+            info: style(Some(BrightCyan), true, false),
+            warning: style(Some(BrightYellow), true, false),
+            muted: style(None, false, false),
+        },
+        ThemeName::Monochrome => Palette {
+            heading: style(None, true, false),
+            prompt: style(None, false, false),
+            success: style(None, false, false),
+            error: style(None, true, false),
+            info: style(None, false, false),
+            warning: style(None, true, false),
+            muted: style(None, false, true),
+        },
+    }
+}
+
+/// Styles text by what it means rather than by color, using the active theme.
+pub trait Themed {
+    fn heading(self) -> ColoredString;
+    fn prompt(self) -> ColoredString;
+    fn success(self) -> ColoredString;
+    fn error(self) -> ColoredString;
+    fn info(self) -> ColoredString;
+    fn warning(self) -> ColoredString;
+    fn muted(self) -> ColoredString;
+}
+
+impl Themed for &str {
+    fn heading(self) -> ColoredString {
+        palette(active()).heading.paint(self)
+    }
+
+    fn prompt(self) -> ColoredString {
+        palette(active()).prompt.paint(self)
+    }
+
+    fn success(self) -> ColoredString {
+        palette(active()).success.paint(self)
+    }
+
+    fn error(self) -> ColoredString {
+        palette(active()).error.paint(self)
+    }
+
+    fn info(self) -> ColoredString {
+        palette(active()).info.paint(self)
+    }
+
+    fn warning(self) -> ColoredString {
+        palette(active()).warning.paint(self)
+    }
+
+    fn muted(self) -> ColoredString {
+        palette(active()).muted.paint(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_theme_names() {
+        assert_eq!(ThemeName::parse(" High_Contrast ").unwrap(), ThemeName::HighContrast);
+        for theme in ThemeName::ALL {
+            assert_eq!(ThemeName::parse(theme.as_str()).unwrap(), theme);
+        }
+        assert!(ThemeName::parse("solarized").is_err());
+    }
+
+    #[test]
+    fn monochrome_uses_no_colors() {
+        let monochrome = palette(ThemeName::Monochrome);
+        for role in [monochrome.heading, monochrome.prompt, monochrome.success, monochrome.error, monochrome.info, monochrome.warning, monochrome.muted] {
+            assert_eq!(role.paint("text").fgcolor, None);
+        }
+        assert_eq!(palette(ThemeName::Default).error.paint("text").fgcolor, Some(Color::Red));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::errors::{AppError, Result};
+use bip39::Language;
+use chrono::Utc;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+static TRANSCRIPT: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Prompts whose answers are never written down, whatever they look like.
+const SECRET_PROMPT_WORDS: &[&str] = &["password", "secret", "seed", "phrase", "mnemonic", "code"];
+
+/// Opt-in log of an interactive session (`--record <path>`) so support can see
+/// what a user did. Passwords, secret keys and recovery phrases are masked.
+pub struct Transcript;
+
+impl Transcript {
+    /// Starts appending to `path`. Only the first call has any effect.
+    pub fn start(path: &str) -> Result<()> {
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // Transcripts name users and addresses, so keep them private.
+            options.mode(0o600);
+        }
+
+        let file = options
+            .open(path)
+            .map_err(|e| AppError::InternalError(format!("Can't open transcript {}: {}", path, e)))?;
+        let _ = TRANSCRIPT.set(Mutex::new(file));
+
+        Self::record("session", &format!("recording started (v{})", env!("CARGO_PKG_VERSION")));
+        Ok(())
+    }
+
+    pub fn is_recording() -> bool {
+        TRANSCRIPT.get().is_some()
+    }
+
+    /// Appends a timestamped line. Recording is best effort: a full disk must
+    /// not interrupt the session being recorded.
+    pub fn record(kind: &str, text: &str) {
+        let Some(file) = TRANSCRIPT.get() else {
+            return;
+        };
+
+        if let Ok(mut file) = file.lock() {
+            let _ = writeln!(file, "{} [{}] {}", Utc::now().to_rfc3339(), kind, text.replace('\n', " / "));
+        }
+    }
+
+    /// Records a prompt and what was typed, masking anything secret.
+    pub fn record_input(prompt: &str, response: &str) {
+        if Self::is_recording() {
+            Self::record("input", &format!("{} {}", prompt, redact(prompt, response)));
+        }
+    }
+}
+
+/// What to write down for `response`: the response itself, or a placeholder
+/// if the prompt asks for a secret or the response looks like one.
+pub fn redact(prompt: &str, response: &str) -> String {
+    let prompt = prompt.to_lowercase();
+    let trimmed = response.trim();
+
+    if trimmed.is_empty() {
+        return "(empty)".to_string();
+    }
+    if SECRET_PROMPT_WORDS.iter().any(|word| prompt.contains(word)) {
+        return "[redacted]".to_string();
+    }
+    if stellar_strkey::ed25519::PrivateKey::from_string(trimmed).is_ok() {
+        return "[redacted secret key]".to_string();
+    }
+    if looks_like_recovery_phrase(trimmed) {
+        return "[redacted recovery phrase]".to_string();
+    }
+
+    trimmed.to_string()
+}
+
+fn looks_like_recovery_phrase(text: &str) -> bool {
+    let words: Vec<_> = text.split_whitespace().collect();
+    words.len() >= 12 && words.iter().all(|word| Language::English.find_word(&word.to_lowercase()).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_secrets_but_keeps_ordinary_answers() {
+        assert_eq!(redact("Enter your choice:", " 2 "), "2");
+        assert_eq!(redact("🔒 Enter your password:", "hunter2"), "[redacted]");
+        assert_eq!(redact("📱 Code:", "123456"), "[redacted]");
+        let secret = stellar_strkey::ed25519::PrivateKey([7; 32]).to_string();
+        assert_eq!(redact("Import key:", &secret), "[redacted secret key]");
+        assert_eq!(
+            redact("Words:", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"),
+            "[redacted recovery phrase]"
+        );
+        assert_eq!(redact("Memo:", "rent for may"), "rent for may");
+        assert_eq!(redact("Memo:", ""), "(empty)");
+    }
+}
@@ -0,0 +1,690 @@
+//! A typed client for the wallet's REST API (`/v1`), for Rust frontends and
+//! other programs that talk to a hosted deployment.
+//!
+//! Each method is one endpoint. Authenticated ones send the access token from
+//! the last [`Client::login`] or [`Client::refresh`], or one given to
+//! [`Client::with_access_token`]; an API key (`swk_...`) works there too, on
+//! the routes its scopes cover. Error responses come back as [`Error::Api`]
+//! with the server's code and message.
+//!
+//! The event streams (`/v1/ws`, `/v1/events`), GraphQL and gRPC aren't
+//! wrapped: they have clients of their own. [`Client::stream_ticket`] gets a
+//! browser into `/v1/events` without its access token. Passkey challenges and
+//! credentials are passed through as the browser's WebAuthn calls take and
+//! return them.
+
+use chrono::{DateTime, Utc};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The base URL isn't an `http://` or `https://` URL.
+    InvalidUrl(String),
+    /// An authenticated endpoint was called before logging in.
+    NotLoggedIn,
+    /// The server couldn't be reached, or its reply couldn't be read.
+    Transport(reqwest::Error),
+    /// The server answered with an error.
+    Api(Box<ApiError>),
+}
+
+/// An error response from the server.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiError {
+    /// The HTTP status.
+    #[serde(skip)]
+    pub status: u16,
+    /// E.g. `unauthenticated` or `validation_failed`.
+    pub code: String,
+    pub message: String,
+    pub details: Option<Value>,
+    /// The request's `X-Request-Id`, to quote when reporting a problem.
+    pub request_id: Option<String>,
+    /// From `Retry-After`, on rate-limited requests.
+    #[serde(skip)]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidUrl(url) => write!(f, "'{}' isn't an http:// or https:// URL", url),
+            Error::NotLoggedIn => write!(f, "log in first"),
+            Error::Transport(e) => write!(f, "couldn't reach the wallet API: {}", e),
+            Error::Api(e) => write!(f, "{}", e.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Transport(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Support,
+    Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub is_verified: bool,
+    pub stellar_public_key: Option<String>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub login_count: i64,
+    pub role: Role,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Login {
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Trade it with [`Client::refresh`] for the next access token; works once.
+    pub refresh_token: String,
+    /// What the access token may be used for, e.g. `read:account`.
+    pub scopes: Vec<String>,
+    pub user: User,
+}
+
+/// What confirms a login for accounts with 2FA or a passkey.
+#[derive(Debug, Clone, Copy)]
+pub enum SecondFactor<'a> {
+    /// An authenticator or recovery code.
+    Code(&'a str),
+    /// One of the account's passkeys answering [`Client::passkey_options`].
+    Passkey(&'a PasskeyAssertion),
+}
+
+/// A signed challenge from `navigator.credentials.get()`, as its `toJSON()`
+/// sends it: binary fields base64url-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasskeyAssertion {
+    /// The id of the credential that signed.
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    #[serde(default)]
+    pub user_handle: Option<String>,
+}
+
+/// A new credential from `navigator.credentials.create()`, as its
+/// `toJSON()` sends it: binary fields base64url-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasskeyRegistration {
+    /// The credential id.
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Passkey {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Opens `/v1/events?ticket=` once, until `expires_at`.
+#[derive(Clone, Deserialize)]
+pub struct StreamTicket {
+    pub ticket: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Leaves the ticket out: it opens the stream as the user.
+impl fmt::Debug for StreamTicket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamTicket").field("ticket", &"[redacted]").field("expires_at", &self.expires_at).finish()
+    }
+}
+
+/// Leaves the tokens out, so logging a login doesn't leak it.
+impl fmt::Debug for Login {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Login")
+            .field("access_token", &"[redacted]")
+            .field("expires_at", &self.expires_at)
+            .field("refresh_token", &"[redacted]")
+            .field("scopes", &self.scopes)
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
+/// Not `Debug`, so the password can't end up in a log. It's wiped from
+/// memory once dropped.
+#[derive(Clone, Serialize)]
+pub struct NewAccount {
+    pub email: String,
+    pub username: String,
+    pub password: Zeroizing<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchResult {
+    pub created: usize,
+    pub failed: usize,
+    pub results: Vec<BatchAccountResult>,
+}
+
+/// Either `user` or `error` is set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchAccountResult {
+    pub index: usize,
+    pub user: Option<User>,
+    pub error: Option<String>,
+}
+
+/// Not `Debug`, so the password and PIN can't end up in a log. They're wiped
+/// from memory once dropped.
+#[derive(Clone, Default, Serialize)]
+pub struct Payment {
+    pub destination: String,
+    /// In XLM, e.g. `"12.5"`.
+    pub amount: String,
+    pub memo: Option<String>,
+    pub password: Zeroizing<String>,
+    pub pin: Option<Zeroizing<String>>,
+    /// An authenticator or recovery code, for accounts with 2FA on.
+    pub code: Option<String>,
+    /// Send even if the same payment went out in the last few minutes.
+    pub allow_duplicate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentReceipt {
+    pub hash: String,
+    pub ledger: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionStatus {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    pub account: String,
+    pub hash: String,
+    pub operation_index: i64,
+    pub direction: Direction,
+    pub asset_code: String,
+    pub amount_stroops: i64,
+    pub counterparty: String,
+    pub memo: Option<String>,
+    pub status: TransactionStatus,
+    pub ledger: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    pub total_users: i64,
+    pub sms_last_30_days: Option<SmsStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsStats {
+    pub messages: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "payment.received")]
+    PaymentReceived,
+    #[serde(rename = "account.verified")]
+    AccountVerified,
+    #[serde(rename = "transaction.failed")]
+    TransactionFailed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewWebhook {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// Generated by the server when left out.
+    pub secret: Option<String>,
+    /// Every account's events instead of your own. Admins only.
+    pub all_accounts: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub all_accounts: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    /// Only returned here; verify requests with [`crate::webhook::verify`].
+    pub secret: String,
+}
+
+/// Leaves the secret out, so logging the response doesn't leak it.
+impl fmt::Debug for CreatedWebhook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreatedWebhook").field("webhook", &self.webhook).field("secret", &"[redacted]").finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: String,
+    pub event_type: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub request_id: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: Check,
+    pub horizon: Check,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Check {
+    pub ok: bool,
+    pub latency_ms: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    pub git_hash: String,
+    pub built_at: Option<DateTime<Utc>>,
+    pub network: String,
+    pub network_passphrase: String,
+    pub horizon_url: String,
+}
+
+/// A connection to one deployment of the wallet API.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    access_token: Option<String>,
+}
+
+/// Leaves the access token or API key out.
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("access_token", &self.access_token.as_ref().map(|_| "[redacted]"))
+            .finish()
+    }
+}
+
+impl Client {
+    /// A client for the deployment at `base_url`, e.g. `https://wallet.example.com`.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let base_url = base_url.trim().trim_end_matches('/');
+        if !(base_url.starts_with("https://") || base_url.starts_with("http://")) {
+            return Err(Error::InvalidUrl(base_url.to_string()));
+        }
+
+        // Redirects are left for the caller, so `oauth_url` can read one.
+        let http = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build()?;
+        Ok(Self {
+            http,
+            base_url: base_url.to_string(),
+            access_token: None,
+        })
+    }
+
+    /// Authenticates with `token`, an access token or an API key.
+    pub fn with_access_token(mut self, token: &str) -> Self {
+        self.access_token = Some(token.to_string());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn access_token(&self) -> Option<&str> {
+        self.access_token.as_deref()
+    }
+
+    /// `GET /health`: whether the server is up.
+    pub async fn health(&self) -> Result<bool> {
+        let health: Value = self.send(self.request(Method::GET, "/health")).await?;
+        Ok(health["status"] == "ok")
+    }
+
+    /// `GET /ready`: whether its database and Horizon can be reached. A
+    /// server that isn't ready answers 503 with the same body.
+    pub async fn ready(&self) -> Result<Readiness> {
+        let response = self.request(Method::GET, "/ready").send().await?;
+        if response.status().as_u16() == 503 {
+            return Ok(response.json().await?);
+        }
+        read(response).await
+    }
+
+    /// `GET /version`: the build and the network it's configured for.
+    pub async fn version(&self) -> Result<VersionInfo> {
+        self.send(self.request(Method::GET, "/version")).await
+    }
+
+    /// `POST /v1/accounts`. Retries with the same `idempotency_key` get the
+    /// first signup's result.
+    pub async fn create_account(&self, account: &NewAccount, idempotency_key: Option<&str>) -> Result<User> {
+        let request = self.request(Method::POST, "/v1/accounts").json(account);
+        self.send(with_idempotency_key(request, idempotency_key)).await
+    }
+
+    /// `POST /v1/accounts/batch`: up to 100 signups at once. Admins only.
+    pub async fn create_accounts(&self, accounts: &[NewAccount]) -> Result<BatchResult> {
+        let request = self.authenticated(Method::POST, "/v1/accounts/batch")?;
+        self.send(request.json(&json!({ "accounts": accounts }))).await
+    }
+
+    /// `POST /v1/auth/login`, asking for `scopes`, or every scope if empty.
+    /// Accounts with 2FA or a passkey need `second_factor`. Later calls use
+    /// the new access token.
+    pub async fn login(&mut self, identifier: &str, password: &str, second_factor: Option<SecondFactor<'_>>, scopes: &[&str]) -> Result<Login> {
+        let body = login_body(json!({ "identifier": identifier, "password": password }), second_factor, scopes);
+        self.start(self.request(Method::POST, "/v1/auth/login").json(&body)).await
+    }
+
+    /// `GET /v1/auth/oauth/{provider}`: where to send the browser to log in
+    /// with `google` or `github`. The provider sends it back to the
+    /// deployment's redirect URL with what [`Client::oauth_callback`] needs.
+    pub async fn oauth_url(&self, provider: &str) -> Result<String> {
+        let response = self.request(Method::GET, &format!("/v1/auth/oauth/{}", urlencoding::encode(provider))).send().await?;
+        if !response.status().is_redirection() {
+            return Err(api_error(response).await);
+        }
+        Ok(response
+            .headers()
+            .get("location")
+            .and_then(|location| location.to_str().ok())
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// `POST /v1/auth/oauth/{provider}/callback`: finishes an OAuth login
+    /// with the `state` and `code` query parameters the provider sent back.
+    /// Later calls use the new access token.
+    pub async fn oauth_callback(
+        &mut self,
+        provider: &str,
+        state: &str,
+        authorization_code: &str,
+        second_factor: Option<SecondFactor<'_>>,
+        scopes: &[&str],
+    ) -> Result<Login> {
+        let body = login_body(json!({ "state": state, "authorization_code": authorization_code }), second_factor, scopes);
+        let path = format!("/v1/auth/oauth/{}/callback", urlencoding::encode(provider));
+        self.start(self.request(Method::POST, &path).json(&body)).await
+    }
+
+    /// `POST /v1/auth/passkey/options`: a challenge for
+    /// `navigator.credentials.get()`, to log in with a passkey or to confirm
+    /// a password or OAuth login with one.
+    pub async fn passkey_options(&self) -> Result<Value> {
+        self.send(self.request(Method::POST, "/v1/auth/passkey/options")).await
+    }
+
+    /// `POST /v1/auth/passkey`: logs in with only a passkey. Later calls use
+    /// the new access token.
+    pub async fn passkey_login(&mut self, passkey: &PasskeyAssertion, scopes: &[&str]) -> Result<Login> {
+        let body = login_body(json!({}), Some(SecondFactor::Passkey(passkey)), scopes);
+        self.start(self.request(Method::POST, "/v1/auth/passkey").json(&body)).await
+    }
+
+    /// `POST /v1/auth/refresh`: the next access token for the same session.
+    /// Later calls use it.
+    pub async fn refresh(&mut self, refresh_token: &str) -> Result<Login> {
+        let body = json!({ "refresh_token": refresh_token });
+        self.start(self.request(Method::POST, "/v1/auth/refresh").json(&body)).await
+    }
+
+    /// `POST /v1/auth/logout`: ends the session and forgets its access token.
+    pub async fn logout(&mut self) -> Result<()> {
+        let request = self.authenticated(Method::POST, "/v1/auth/logout")?;
+        self.send_empty(request).await?;
+        self.access_token = None;
+        Ok(())
+    }
+
+    /// `GET /v1/users/me`.
+    pub async fn me(&self) -> Result<User> {
+        self.send(self.authenticated(Method::GET, "/v1/users/me")?).await
+    }
+
+    /// `POST /v1/payments`: sends XLM from the account's wallet address.
+    /// Retrying with the same `idempotency_key` never pays twice.
+    pub async fn send_payment(&self, payment: &Payment, idempotency_key: Option<&str>) -> Result<PaymentReceipt> {
+        let request = self.authenticated(Method::POST, "/v1/payments")?.json(payment);
+        self.send(with_idempotency_key(request, idempotency_key)).await
+    }
+
+    /// `GET /v1/wallets/{address}/transactions`: newest first, 20 unless `limit` says otherwise.
+    pub async fn transactions(&self, address: &str, limit: Option<u32>) -> Result<Vec<Transaction>> {
+        let path = format!("/v1/wallets/{}/transactions", urlencoding::encode(address));
+        let mut request = self.authenticated(Method::GET, &path)?;
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// `POST /v1/passkeys/registration-options`: the options for
+    /// `navigator.credentials.create()` to add a passkey to the account.
+    pub async fn passkey_registration_options(&self) -> Result<Value> {
+        self.send(self.authenticated(Method::POST, "/v1/passkeys/registration-options")?).await
+    }
+
+    /// `POST /v1/passkeys`: saves the passkey the browser created, named
+    /// "Passkey" unless `name` says otherwise.
+    pub async fn create_passkey(&self, name: Option<&str>, credential: &PasskeyRegistration) -> Result<Passkey> {
+        let request = self.authenticated(Method::POST, "/v1/passkeys")?;
+        self.send(request.json(&json!({ "name": name, "credential": credential }))).await
+    }
+
+    /// `GET /v1/passkeys`: the account's passkeys, oldest first.
+    pub async fn passkeys(&self) -> Result<Vec<Passkey>> {
+        self.send(self.authenticated(Method::GET, "/v1/passkeys")?).await
+    }
+
+    /// `DELETE /v1/passkeys/{id}`.
+    pub async fn remove_passkey(&self, id: &Uuid) -> Result<()> {
+        self.send_empty(self.authenticated(Method::DELETE, &format!("/v1/passkeys/{}", id))?).await
+    }
+
+    /// `POST /v1/events/tickets`: a ticket for a browser to open
+    /// `/v1/events` with, so the access token never goes in a URL.
+    pub async fn stream_ticket(&self) -> Result<StreamTicket> {
+        self.send(self.authenticated(Method::POST, "/v1/events/tickets")?).await
+    }
+
+    /// `GET /v1/stats`. Support staff and admins only.
+    pub async fn stats(&self) -> Result<Stats> {
+        self.send(self.authenticated(Method::GET, "/v1/stats")?).await
+    }
+
+    /// `POST /v1/webhooks`.
+    pub async fn create_webhook(&self, webhook: &NewWebhook) -> Result<CreatedWebhook> {
+        self.send(self.authenticated(Method::POST, "/v1/webhooks")?.json(webhook)).await
+    }
+
+    /// `GET /v1/webhooks`: your endpoints, or every account's for admins.
+    pub async fn webhooks(&self, all_accounts: bool) -> Result<Vec<Webhook>> {
+        let request = self.authenticated(Method::GET, "/v1/webhooks")?.query(&[("all_accounts", all_accounts)]);
+        self.send(request).await
+    }
+
+    /// `POST /v1/webhooks/test`: sends a sample event to the operator's
+    /// webhook, returning its id. Admins only.
+    pub async fn test_webhook(&self) -> Result<String> {
+        let sent: Value = self.send(self.authenticated(Method::POST, "/v1/webhooks/test")?).await?;
+        Ok(sent["event_id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// `DELETE /v1/webhooks/{id}`.
+    pub async fn remove_webhook(&self, id: &Uuid) -> Result<()> {
+        self.send_empty(self.authenticated(Method::DELETE, &format!("/v1/webhooks/{}", id))?).await
+    }
+
+    /// `GET /v1/webhooks/{id}/deliveries`: newest first, 20 unless `limit` says otherwise.
+    pub async fn webhook_deliveries(&self, id: &Uuid, limit: Option<u32>) -> Result<Vec<WebhookDelivery>> {
+        let mut request = self.authenticated(Method::GET, &format!("/v1/webhooks/{}/deliveries", id))?;
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, format!("{}{}", self.base_url, path))
+    }
+
+    fn authenticated(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        let token = self.access_token.as_deref().ok_or(Error::NotLoggedIn)?;
+        Ok(self.request(method, path).bearer_auth(token))
+    }
+
+    /// Sends a login and uses its access token from then on.
+    async fn start(&mut self, request: RequestBuilder) -> Result<Login> {
+        let login: Login = self.send(request).await?;
+        self.access_token = Some(login.access_token.clone());
+        Ok(login)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        read(request.send().await?).await
+    }
+
+    async fn send_empty(&self, request: RequestBuilder) -> Result<()> {
+        let response = request.send().await?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+        Err(api_error(response).await)
+    }
+}
+
+/// `body` with the second factor and the scopes asked for, or every scope if
+/// there are none.
+fn login_body(mut body: Value, second_factor: Option<SecondFactor<'_>>, scopes: &[&str]) -> Value {
+    match second_factor {
+        Some(SecondFactor::Code(code)) => body["code"] = json!(code),
+        Some(SecondFactor::Passkey(passkey)) => body["passkey"] = json!(passkey),
+        None => {}
+    }
+    if !scopes.is_empty() {
+        body["scopes"] = json!(scopes);
+    }
+    body
+}
+
+fn with_idempotency_key(request: RequestBuilder, key: Option<&str>) -> RequestBuilder {
+    match key {
+        Some(key) => request.header("Idempotency-Key", key),
+        None => request,
+    }
+}
+
+async fn read<T: DeserializeOwned>(response: Response) -> Result<T> {
+    if response.status().is_success() {
+        return Ok(response.json().await?);
+    }
+    Err(api_error(response).await)
+}
+
+async fn api_error(response: Response) -> Error {
+    let status = response.status().as_u16();
+    let retry_after_secs = response
+        .headers()
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    match response.json::<ApiError>().await {
+        Ok(error) => Error::Api(Box::new(ApiError {
+            status,
+            retry_after_secs,
+            ..error
+        })),
+        Err(_) => Error::Api(Box::new(ApiError {
+            status,
+            code: "unknown".to_string(),
+            message: format!("The wallet API answered {}", status),
+            details: None,
+            request_id: None,
+            retry_after_secs,
+        })),
+    }
+}
@@ -0,0 +1,190 @@
+//! Layered settings: each component's built-in defaults, then `config.toml`,
+//! then environment variables. The file groups the same settings the
+//! environment variables carry, so `[database] path = "..."` and
+//! `DATABASE_PATH=...` are the same setting and the variable wins.
+//!
+//! Secrets (database keys, token signing keys, API tokens, webhook secrets)
+//! are only read from the environment or the OS keyring and are rejected in
+//! the file.
+
+use crate::errors::{AppError, Result};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::sync::OnceLock;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Read from the working directory when `WALLET_CONFIG` isn't set.
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Each `section.key` the file accepts and the variable it stands for.
+const KEYS: &[(&str, &str)] = &[
+    ("database.path", "DATABASE_PATH"),
+    ("database.max_connections", "DATABASE_MAX_CONNECTIONS"),
+    ("database.acquire_timeout_secs", "DATABASE_ACQUIRE_TIMEOUT_SECS"),
+    ("database.busy_timeout_ms", "DATABASE_BUSY_TIMEOUT_MS"),
+    ("database.journal_mode", "DATABASE_JOURNAL_MODE"),
+    ("database.synchronous", "DATABASE_SYNCHRONOUS"),
+    ("database.key_source", "DATABASE_KEY_SOURCE"),
+    ("database.archive_dir", "ARCHIVE_DIR"),
+    ("database.export_dir", "EXPORT_DIR"),
+    ("stellar.network", "STELLAR_NETWORK"),
+    ("stellar.horizon_url", "HORIZON_URL"),
+    ("stellar.passphrase", "STELLAR_NETWORK_PASSPHRASE"),
+    ("security.session_ttl_hours", "SESSION_TTL_HOURS"),
+    ("security.sms_code_ttl_minutes", "SMS_CODE_TTL_MINUTES"),
+    ("security.events_sink", "SECURITY_EVENTS_SINK"),
+    ("security.access_token_ttl_minutes", "ACCESS_TOKEN_TTL_MINUTES"),
+    ("security.session_file", "WALLET_SESSION_FILE"),
+    ("security.session_store", "WALLET_SESSION_STORE"),
+    ("security.login_max_failures", "LOGIN_MAX_FAILURES"),
+    ("security.password_hash_memory_kib", "PASSWORD_HASH_MEMORY_KIB"),
+    ("security.password_hash_iterations", "PASSWORD_HASH_ITERATIONS"),
+    ("security.password_hash_parallelism", "PASSWORD_HASH_PARALLELISM"),
+    ("security.password_breach_check", "PASSWORD_BREACH_CHECK"),
+    ("security.password_breach_api_url", "PASSWORD_BREACH_API_URL"),
+    ("sms.provider", "SMS_PROVIDER"),
+    ("sms.twilio_api_url", "TWILIO_API_URL"),
+    ("sms.country_hourly_limit", "SMS_COUNTRY_HOURLY_LIMIT"),
+    ("sms.country_limits", "SMS_COUNTRY_LIMITS"),
+    ("sms.cost_per_message", "SMS_COST_PER_MESSAGE"),
+    ("email.provider", "EMAIL_PROVIDER"),
+    ("email.from", "EMAIL_FROM"),
+    ("email.postmark_api_url", "POSTMARK_API_URL"),
+    ("email.template_dir", "EMAIL_TEMPLATE_DIR"),
+    ("email.locale", "EMAIL_LOCALE"),
+    ("prices.api_url", "PRICE_API_URL"),
+    ("hooks.dir", "WALLET_HOOKS_DIR"),
+    ("webhooks.url", "WEBHOOK_URL"),
+    ("webhooks.allow_local", "WEBHOOKS_ALLOW_LOCAL"),
+    ("events.publisher", "EVENT_PUBLISHER"),
+    ("events.subject_prefix", "EVENT_SUBJECT_PREFIX"),
+    ("branding.name", "BRAND_NAME"),
+    ("branding.banner_file", "BRAND_BANNER_FILE"),
+    ("branding.primary_color", "BRAND_PRIMARY_COLOR"),
+    ("branding.accent_color", "BRAND_ACCENT_COLOR"),
+    ("branding.support_contact", "BRAND_SUPPORT_CONTACT"),
+    ("api.listen_addr", "API_LISTEN_ADDR"),
+    ("api.tls_cert", "API_TLS_CERT"),
+    ("api.tls_key", "API_TLS_KEY"),
+    ("api.cors_allowed_origins", "API_CORS_ALLOWED_ORIGINS"),
+    ("api.cors_allowed_methods", "API_CORS_ALLOWED_METHODS"),
+    ("api.cors_allowed_headers", "API_CORS_ALLOWED_HEADERS"),
+    ("api.rate_limit_store", "API_RATE_LIMIT_STORE"),
+    ("api.rate_limit_auth_per_minute", "API_RATE_LIMIT_AUTH_PER_MINUTE"),
+    ("api.rate_limit_payments_per_minute", "API_RATE_LIMIT_PAYMENTS_PER_MINUTE"),
+    ("api.account_cache", "API_ACCOUNT_CACHE"),
+    ("api.account_cache_ttl_secs", "API_ACCOUNT_CACHE_TTL_SECS"),
+    ("oauth.redirect_url", "OAUTH_REDIRECT_URL"),
+    ("oauth.google_client_id", "OAUTH_GOOGLE_CLIENT_ID"),
+    ("oauth.github_client_id", "OAUTH_GITHUB_CLIENT_ID"),
+    ("webauthn.rp_id", "WEBAUTHN_RP_ID"),
+    ("webauthn.origin", "WEBAUTHN_ORIGIN"),
+];
+
+/// Settings read from the config file, by variable name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    file: HashMap<&'static str, String>,
+}
+
+impl Config {
+    /// Reads `WALLET_CONFIG`, or `config.toml` if it exists. A file named by
+    /// `WALLET_CONFIG` must exist.
+    pub fn load() -> Result<Self> {
+        let (path, required) = match env::var("WALLET_CONFIG").ok().filter(|path| !path.is_empty()) {
+            Some(path) => (path, true),
+            None => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map_err(|e| AppError::ValidationError(format!("{}: {}", path, e))),
+            Err(e) if e.kind() == ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(AppError::ValidationError(format!("Can't read config file {}: {}", path, e))),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let table: toml::Table = text.parse().map_err(|e| AppError::ValidationError(format!("Invalid config: {}", e)))?;
+        let mut file = HashMap::new();
+
+        for (section, values) in &table {
+            let values = values
+                .as_table()
+                .ok_or_else(|| AppError::ValidationError(format!("'{}' must be a [section]", section)))?;
+
+            for (key, value) in values {
+                let setting = format!("{}.{}", section, key);
+                let name = KEYS
+                    .iter()
+                    .find(|(file_key, _)| *file_key == setting)
+                    .map(|(_, name)| *name)
+                    .ok_or_else(|| AppError::ValidationError(format!("Unknown setting '{}'", setting)))?;
+
+                let value = match value {
+                    toml::Value::String(text) => text.clone(),
+                    toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => value.to_string(),
+                    _ => return Err(AppError::ValidationError(format!("'{}' must be a string, number or boolean", setting))),
+                };
+                file.insert(name, value);
+            }
+        }
+
+        Ok(Self { file })
+    }
+
+    /// Makes this the configuration [`var`] reads. Only the first call has
+    /// any effect.
+    pub fn install(self) {
+        let _ = CONFIG.set(self);
+    }
+
+    fn lookup(&self, name: &str, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+        env(name)
+            .or_else(|| self.file.get(name).cloned())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// The setting called `name` in the environment, else in the installed config
+/// file. Blank values count as unset.
+pub fn var(name: &str) -> Option<String> {
+    CONFIG.get_or_init(Config::default).lookup(name, |name| env::var(name).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environment_overrides_the_file() {
+        let config = Config::parse(
+            r#"
+            [database]
+            path = "/var/lib/wallet.db"
+            max_connections = 4
+
+            [stellar]
+            network = "public"
+            "#,
+        )
+        .unwrap();
+        let env = |name: &str| (name == "STELLAR_NETWORK").then(|| "testnet".to_string());
+
+        assert_eq!(config.lookup("DATABASE_PATH", env).as_deref(), Some("/var/lib/wallet.db"));
+        assert_eq!(config.lookup("DATABASE_MAX_CONNECTIONS", env).as_deref(), Some("4"));
+        assert_eq!(config.lookup("STELLAR_NETWORK", env).as_deref(), Some("testnet"));
+        assert_eq!(config.lookup("HORIZON_URL", env), None);
+    }
+
+    #[test]
+    fn rejects_unknown_settings_and_secrets() {
+        assert!(Config::parse("[database]\npth = \"x\"").is_err());
+        assert!(Config::parse("[database]\nkey = \"hunter2\"").is_err());
+        assert!(Config::parse("path = \"x\"").is_err());
+        assert!(Config::parse("[stellar]\nnetwork = [\"public\"]").is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::activity::{ActivitySummary, CounterpartyActivity, MonthlyVolume};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+/// Confirmed payments in one asset since a point in time. Shared by every
+/// activity query so they all count the same rows.
+const ACTIVITY_SCOPE: &str =
+    "account = ?1 AND asset_code = ?2 AND created_at >= ?3 AND status = 'confirmed'";
+
+impl SqliteDatabase {
+    pub async fn get_activity_summary(
+        &self,
+        account: &str,
+        asset_code: &str,
+        since: DateTime<Utc>,
+        top_counterparties: u32,
+    ) -> Result<ActivitySummary> {
+        let since_text = since.to_rfc3339();
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to load wallet activity: {}", e));
+
+        let totals = sqlx::query(&format!(
+            "SELECT COUNT(*) AS payments, COALESCE(SUM(amount_stroops), 0) AS volume FROM transactions WHERE {}",
+            ACTIVITY_SCOPE
+        ))
+        .bind(account)
+        .bind(asset_code)
+        .bind(&since_text)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let payments: i64 = totals.get("payments");
+        let volume: i64 = totals.get("volume");
+
+        // Timestamps are stored as UTC RFC 3339, so the hour is characters 12-13.
+        let cells = sqlx::query(&format!(
+            "SELECT CAST(strftime('%w', substr(created_at, 1, 10)) AS INTEGER) AS weekday,
+                    CAST(substr(created_at, 12, 2) AS INTEGER) AS hour,
+                    COUNT(*) AS payments
+             FROM transactions WHERE {} GROUP BY weekday, hour",
+            ACTIVITY_SCOPE
+        ))
+        .bind(account)
+        .bind(asset_code)
+        .bind(&since_text)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let mut heatmap = [[0; 24]; 7];
+        for row in cells {
+            let (weekday, hour): (i64, i64) = (row.get("weekday"), row.get("hour"));
+            if let Some(cell) = heatmap.get_mut(weekday as usize).and_then(|day| day.get_mut(hour as usize)) {
+                *cell = row.get("payments");
+            }
+        }
+
+        let counterparties = sqlx::query(&format!(
+            "SELECT counterparty, COUNT(*) AS payments, SUM(amount_stroops) AS volume
+             FROM transactions WHERE {} GROUP BY counterparty ORDER BY payments DESC, volume DESC LIMIT ?4",
+            ACTIVITY_SCOPE
+        ))
+        .bind(account)
+        .bind(asset_code)
+        .bind(&since_text)
+        .bind(top_counterparties)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        let monthly = sqlx::query(&format!(
+            "SELECT substr(created_at, 1, 7) AS month, COUNT(*) AS payments,
+                    COALESCE(SUM(CASE WHEN direction = 'incoming' THEN amount_stroops END), 0) AS incoming,
+                    COALESCE(SUM(CASE WHEN direction = 'outgoing' THEN amount_stroops END), 0) AS outgoing
+             FROM transactions WHERE {} GROUP BY month ORDER BY month",
+            ACTIVITY_SCOPE
+        ))
+        .bind(account)
+        .bind(asset_code)
+        .bind(&since_text)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(map_err)?;
+
+        Ok(ActivitySummary {
+            account: account.to_string(),
+            asset_code: asset_code.to_string(),
+            since,
+            payments,
+            average_payment_stroops: if payments == 0 { 0 } else { volume / payments },
+            heatmap,
+            top_counterparties: counterparties
+                .into_iter()
+                .map(|row| CounterpartyActivity {
+                    counterparty: row.get("counterparty"),
+                    payments: row.get("payments"),
+                    volume_stroops: row.get("volume"),
+                })
+                .collect(),
+            monthly: monthly
+                .into_iter()
+                .map(|row| MonthlyVolume {
+                    month: row.get("month"),
+                    payments: row.get("payments"),
+                    incoming_stroops: row.get("incoming"),
+                    outgoing_stroops: row.get("outgoing"),
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+    use chrono::{Duration, TimeZone};
+    use uuid::Uuid;
+
+    fn payment(hash: &str, at: DateTime<Utc>, direction: TransactionDirection, counterparty: &str, amount_stroops: i64) -> WalletTransaction {
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: "GME".to_string(),
+            hash: hash.to_string(),
+            operation_index: 0,
+            direction,
+            asset_code: "XLM".to_string(),
+            amount_stroops,
+            counterparty: counterparty.to_string(),
+            memo: None,
+            status: TransactionStatus::Confirmed,
+            ledger: Some(1),
+            error: None,
+            request_id: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[tokio::test]
+    async fn summarises_confirmed_payments_in_one_asset() {
+        let db = SqliteDatabase::in_memory().await;
+        // 2025-01-06 was a Monday.
+        let monday_nine = Utc.with_ymd_and_hms(2025, 1, 6, 9, 15, 0).unwrap();
+
+        db.upsert_transaction(&payment("a", monday_nine, TransactionDirection::Outgoing, "GBOB", 30)).await.unwrap();
+        db.upsert_transaction(&payment("b", monday_nine + Duration::minutes(5), TransactionDirection::Outgoing, "GBOB", 10)).await.unwrap();
+        db.upsert_transaction(&payment("c", monday_nine + Duration::days(30), TransactionDirection::Incoming, "GEVE", 20)).await.unwrap();
+
+        let mut failed = payment("d", monday_nine, TransactionDirection::Outgoing, "GBOB", 1_000);
+        failed.status = TransactionStatus::Failed;
+        db.upsert_transaction(&failed).await.unwrap();
+        let mut usdc = payment("e", monday_nine, TransactionDirection::Outgoing, "GBOB", 1_000);
+        usdc.asset_code = "USDC".to_string();
+        db.upsert_transaction(&usdc).await.unwrap();
+
+        let since = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let summary = db.get_activity_summary("GME", "XLM", since, 5).await.unwrap();
+
+        assert_eq!(summary.payments, 3);
+        assert_eq!(summary.average_payment_stroops, 20);
+        assert_eq!(summary.heatmap[1][9], 2);
+        assert_eq!(summary.heatmap[3][9], 1);
+        assert_eq!(summary.peak(), 2);
+        assert_eq!(
+            summary.top_counterparties[0],
+            CounterpartyActivity { counterparty: "GBOB".to_string(), payments: 2, volume_stroops: 40 }
+        );
+        assert_eq!(
+            summary.monthly,
+            vec![
+                MonthlyVolume { month: "2025-01".to_string(), payments: 2, incoming_stroops: 0, outgoing_stroops: 40 },
+                MonthlyVolume { month: "2025-02".to_string(), payments: 1, incoming_stroops: 20, outgoing_stroops: 0 },
+            ]
+        );
+
+        let later = db.get_activity_summary("GME", "XLM", since + Duration::days(40), 5).await.unwrap();
+        assert_eq!((later.payments, later.average_payment_stroops, later.peak()), (0, 0, 0));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::{ApiKey, ApiScope};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_api_key(&self, key: &ApiKey) -> Result<()> {
+        let query = r#"
+            INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, expires_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+        sqlx::query(query)
+            .bind(key.id.to_string())
+            .bind(key.user_id.to_string())
+            .bind(&key.name)
+            .bind(&key.prefix)
+            .bind(&key.key_hash)
+            .bind(join_scopes(&key.scopes))
+            .bind(key.expires_at.map(|expires_at| expires_at.to_rfc3339()))
+            .bind(key.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save API key: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// `user_id`'s keys that haven't been revoked, newest first. Expired ones
+    /// are included so users can see why an integration stopped working.
+    pub async fn get_api_keys_by_user(&self, user_id: &Uuid) -> Result<Vec<ApiKey>> {
+        let query = "SELECT * FROM api_keys WHERE user_id = ?1 AND revoked_at IS NULL ORDER BY created_at DESC";
+
+        sqlx::query_as::<_, ApiKey>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch API keys: {}", e)))
+    }
+
+    pub async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE key_hash = ?1")
+            .bind(key_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up API key: {}", e)))
+    }
+
+    pub async fn touch_api_key(&self, key_id: &Uuid, now: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = ?2 WHERE id = ?1")
+            .bind(key_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update API key: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Keys revoked after `since`, oldest first.
+    pub async fn get_api_keys_revoked_since(&self, since: DateTime<Utc>) -> Result<Vec<ApiKey>> {
+        sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE revoked_at > ?1 ORDER BY revoked_at")
+            .bind(since.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch revoked API keys: {}", e)))
+    }
+
+    /// Revokes one of `user_id`'s keys. Returns whether an unrevoked one was found.
+    pub async fn revoke_api_key(&self, user_id: &Uuid, key_id: &Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let query = "UPDATE api_keys SET revoked_at = ?3 WHERE id = ?1 AND user_id = ?2 AND revoked_at IS NULL";
+
+        let result = sqlx::query(query)
+            .bind(key_id.to_string())
+            .bind(user_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to revoke API key: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for ApiKey {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(ApiKey {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            name: row.try_get("name")?,
+            prefix: row.try_get("prefix")?,
+            key_hash: row.try_get("key_hash")?,
+            scopes: split_scopes(&row.try_get::<String, _>("scopes")?)?,
+            expires_at: rows::optional_timestamp(row, "expires_at")?,
+            last_used_at: rows::optional_timestamp(row, "last_used_at")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            revoked_at: rows::optional_timestamp(row, "revoked_at")?,
+        })
+    }
+}
+
+/// Scopes as stored: their names, comma-separated.
+pub(super) fn join_scopes(scopes: &[ApiScope]) -> String {
+    scopes.iter().map(ApiScope::as_str).collect::<Vec<_>>().join(",")
+}
+
+pub(super) fn split_scopes(scopes: &str) -> sqlx::Result<Vec<ApiScope>> {
+    ApiScope::parse_list(scopes.split(',').filter(|scope| !scope.is_empty()))
+        .map_err(|scope| rows::decode_error("scopes", format!("unknown scope '{}'", scope)))
+}
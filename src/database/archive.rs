@@ -0,0 +1,128 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::archive::TransactionRollup;
+use crate::models::transaction::{TransactionDirection, WalletTransaction};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+
+impl SqliteDatabase {
+    /// Every account's transactions created before `cutoff`, oldest first.
+    pub async fn get_transactions_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<WalletTransaction>> {
+        let query = "SELECT * FROM transactions WHERE created_at < ?1 ORDER BY account, created_at, operation_index";
+
+        sqlx::query_as::<_, WalletTransaction>(query)
+            .bind(cutoff.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch old transactions: {}", e)))
+    }
+
+    /// In one transaction: adds `rollups` to the monthly totals, deletes the
+    /// archived transactions and their tags, and records `cutoff` for each of
+    /// their accounts.
+    pub async fn replace_with_rollups(&self, archived: &[WalletTransaction], rollups: &[TransactionRollup], cutoff: DateTime<Utc>) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to archive transactions: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        for rollup in rollups {
+            let query = r#"
+                INSERT INTO transaction_rollups (account, month, asset_code, direction, payment_count, total_stroops)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT (account, month, asset_code, direction) DO UPDATE SET
+                    payment_count = transaction_rollups.payment_count + excluded.payment_count,
+                    total_stroops = transaction_rollups.total_stroops + excluded.total_stroops
+            "#;
+            sqlx::query(query)
+                .bind(&rollup.account)
+                .bind(&rollup.month)
+                .bind(&rollup.asset_code)
+                .bind(rollup.direction.as_str())
+                .bind(rollup.payment_count)
+                .bind(rollup.total_stroops)
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+
+        for transaction in archived {
+            sqlx::query("DELETE FROM transactions WHERE id = ?1")
+                .bind(transaction.id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+            sqlx::query(
+                "DELETE FROM transaction_tags WHERE account = ?1 AND hash = ?2 \
+                 AND NOT EXISTS (SELECT 1 FROM transactions WHERE account = ?1 AND hash = ?2)",
+            )
+            .bind(&transaction.account)
+            .bind(&transaction.hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        }
+
+        let mut accounts: Vec<&str> = archived.iter().map(|transaction| transaction.account.as_str()).collect();
+        accounts.dedup();
+        for account in accounts {
+            let query = r#"
+                INSERT INTO transaction_archive_cutoffs (account, archived_before) VALUES (?1, ?2)
+                ON CONFLICT (account) DO UPDATE SET
+                    archived_before = MAX(transaction_archive_cutoffs.archived_before, excluded.archived_before)
+            "#;
+            sqlx::query(query)
+                .bind(account)
+                .bind(cutoff.to_rfc3339())
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+
+        tx.commit().await.map_err(map_err)
+    }
+
+    /// `account`'s monthly totals, newest month first.
+    pub async fn get_transaction_rollups(&self, account: &str) -> Result<Vec<TransactionRollup>> {
+        let query = r#"
+            SELECT * FROM transaction_rollups
+            WHERE account = ?1
+            ORDER BY month DESC, asset_code, direction
+        "#;
+
+        sqlx::query_as::<_, TransactionRollup>(query)
+            .bind(account)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch monthly summaries: {}", e)))
+    }
+
+    /// The date before which `account`'s history was archived, if it was.
+    pub async fn get_archive_cutoff(&self, account: &str) -> Result<Option<DateTime<Utc>>> {
+        let row = sqlx::query("SELECT archived_before FROM transaction_archive_cutoffs WHERE account = ?1")
+            .bind(account)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up archive cutoff: {}", e)))?;
+
+        row.map(|row| rows::timestamp(&row, "archived_before"))
+            .transpose()
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up archive cutoff: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for TransactionRollup {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let direction: String = row.try_get("direction")?;
+
+        Ok(TransactionRollup {
+            account: row.try_get("account")?,
+            month: row.try_get("month")?,
+            asset_code: row.try_get("asset_code")?,
+            direction: TransactionDirection::parse(&direction)
+                .ok_or_else(|| rows::decode_error("direction", format!("unknown transaction direction '{}'", direction)))?,
+            payment_count: row.try_get("payment_count")?,
+            total_stroops: row.try_get("total_stroops")?,
+        })
+    }
+}
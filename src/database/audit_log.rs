@@ -0,0 +1,90 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::{AuditEntry, AuditFilter};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+
+impl SqliteDatabase {
+    pub async fn create_audit_entry(&self, entry: &AuditEntry) -> Result<()> {
+        let query = r#"
+            INSERT INTO audit_log (id, user_id, event_type, details, request_id, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#;
+
+        sqlx::query(query)
+            .bind(entry.id.to_string())
+            .bind(entry.user_id.map(|id| id.to_string()))
+            .bind(&entry.event_type)
+            .bind(&entry.details)
+            .bind(&entry.request_id)
+            .bind(entry.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to write audit entry: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Matching entries, newest first.
+    pub async fn get_audit_entries(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let query = r#"
+            SELECT * FROM audit_log
+            WHERE (?1 IS NULL OR user_id = ?1)
+              AND (?2 IS NULL OR created_at >= ?2)
+              AND (?3 IS NULL OR created_at < ?3)
+            ORDER BY created_at DESC
+            LIMIT ?4
+        "#;
+
+        sqlx::query_as::<_, AuditEntry>(query)
+            .bind(filter.user_id.map(|id| id.to_string()))
+            .bind(filter.since.map(|at| at.to_rfc3339()))
+            .bind(filter.until.map(|at| at.to_rfc3339()))
+            .bind(filter.limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch audit entries: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for AuditEntry {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(AuditEntry {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::optional_uuid(row, "user_id")?,
+            event_type: row.try_get("event_type")?,
+            details: row.try_get("details")?,
+            request_id: row.try_get("request_id")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn entries_cannot_be_edited_or_deleted() {
+        let db = SqliteDatabase::in_memory().await;
+        db.create_audit_entry(&AuditEntry {
+            id: Uuid::new_v4(),
+            user_id: None,
+            event_type: "login_failed".to_string(),
+            details: "x".to_string(),
+            request_id: None,
+            created_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+        assert!(sqlx::query("UPDATE audit_log SET details = 'y'").execute(&db.pool).await.is_err());
+        assert!(sqlx::query("DELETE FROM audit_log").execute(&db.pool).await.is_err());
+
+        let filter = AuditFilter { limit: 10, ..AuditFilter::default() };
+        assert_eq!(db.get_audit_entries(&filter).await.unwrap()[0].details, "x");
+    }
+}
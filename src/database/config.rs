@@ -0,0 +1,119 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Connection pool and SQLite settings. The defaults suit a single CLI user;
+/// raise the pool size and busy timeout when serving concurrent requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    pub max_connections: u32,
+    /// How long to wait for a free pooled connection.
+    pub acquire_timeout: Duration,
+    /// How long SQLite retries a locked database before failing with SQLITE_BUSY.
+    pub busy_timeout: Duration,
+    pub journal_mode: SqliteJournalMode,
+    pub synchronous: SqliteSynchronous,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            acquire_timeout: Duration::from_secs(30),
+            busy_timeout: Duration::from_secs(5),
+            // WAL lets readers carry on while a payment is being written.
+            journal_mode: SqliteJournalMode::Wal,
+            synchronous: SqliteSynchronous::Full,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Reads `DATABASE_MAX_CONNECTIONS`, `DATABASE_ACQUIRE_TIMEOUT_SECS`,
+    /// `DATABASE_BUSY_TIMEOUT_MS`, `DATABASE_JOURNAL_MODE` and
+    /// `DATABASE_SYNCHRONOUS`. Unset values keep the defaults.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(config::var)
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let defaults = Self::default();
+        let setting = |name: &str| var(name).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        let number = |name: &str| -> Result<Option<u64>> {
+            setting(name)
+                .map(|value| {
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| AppError::ValidationError(format!("{} must be a whole number, got '{}'", name, value)))
+                })
+                .transpose()
+        };
+
+        let max_connections = match number("DATABASE_MAX_CONNECTIONS")? {
+            Some(0) => return Err(AppError::ValidationError("DATABASE_MAX_CONNECTIONS must be at least 1".to_string())),
+            Some(n) => u32::try_from(n)
+                .map_err(|_| AppError::ValidationError("DATABASE_MAX_CONNECTIONS is too large".to_string()))?,
+            None => defaults.max_connections,
+        };
+
+        let journal_mode = match setting("DATABASE_JOURNAL_MODE") {
+            Some(value) => SqliteJournalMode::from_str(&value)
+                .map_err(|_| AppError::ValidationError(format!("Unknown DATABASE_JOURNAL_MODE '{}'", value)))?,
+            None => defaults.journal_mode,
+        };
+
+        let synchronous = match setting("DATABASE_SYNCHRONOUS") {
+            Some(value) => SqliteSynchronous::from_str(&value)
+                .map_err(|_| AppError::ValidationError(format!("Unknown DATABASE_SYNCHRONOUS '{}'", value)))?,
+            None => defaults.synchronous,
+        };
+
+        Ok(Self {
+            max_connections,
+            acquire_timeout: number("DATABASE_ACQUIRE_TIMEOUT_SECS")?.map_or(defaults.acquire_timeout, Duration::from_secs),
+            busy_timeout: number("DATABASE_BUSY_TIMEOUT_MS")?.map_or(defaults.busy_timeout, Duration::from_millis),
+            journal_mode,
+            synchronous,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(vars: &[(&str, &str)]) -> Result<DatabaseConfig> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        DatabaseConfig::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn reads_pool_and_pragma_settings() {
+        assert_eq!(config(&[]).unwrap(), DatabaseConfig::default());
+
+        let tuned = config(&[
+            ("DATABASE_MAX_CONNECTIONS", "32"),
+            ("DATABASE_ACQUIRE_TIMEOUT_SECS", "3"),
+            ("DATABASE_BUSY_TIMEOUT_MS", "250"),
+            ("DATABASE_JOURNAL_MODE", "delete"),
+            ("DATABASE_SYNCHRONOUS", "NORMAL"),
+        ])
+        .unwrap();
+        assert_eq!(tuned.max_connections, 32);
+        assert_eq!(tuned.acquire_timeout, Duration::from_secs(3));
+        assert_eq!(tuned.busy_timeout, Duration::from_millis(250));
+        assert_eq!(tuned.journal_mode, SqliteJournalMode::Delete);
+        assert_eq!(tuned.synchronous, SqliteSynchronous::Normal);
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        assert!(config(&[("DATABASE_MAX_CONNECTIONS", "0")]).is_err());
+        assert!(config(&[("DATABASE_BUSY_TIMEOUT_MS", "-1")]).is_err());
+        assert!(config(&[("DATABASE_JOURNAL_MODE", "fast")]).is_err());
+        assert!(config(&[("DATABASE_SYNCHRONOUS", "sometimes")]).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::contact::Contact;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_contact(&self, contact: &Contact) -> Result<()> {
+        let query = r#"
+            INSERT INTO contacts (id, user_id, name, address, memo, federation_name, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+
+        sqlx::query(query)
+            .bind(contact.id.to_string())
+            .bind(contact.user_id.to_string())
+            .bind(&contact.name)
+            .bind(&contact.address)
+            .bind(&contact.memo)
+            .bind(&contact.federation_name)
+            .bind(contact.created_at.to_rfc3339())
+            .bind(contact.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ValidationError("A contact with this name already exists".to_string())
+                } else {
+                    AppError::DatabaseError(format!("Failed to create contact: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    pub async fn get_contacts_by_user(&self, user_id: &Uuid) -> Result<Vec<Contact>> {
+        let query = "SELECT * FROM contacts WHERE user_id = ?1 ORDER BY name COLLATE NOCASE";
+
+        sqlx::query_as::<_, Contact>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch contacts: {}", e)))
+    }
+
+    pub async fn delete_contact(&self, user_id: &Uuid, contact_id: &Uuid) -> Result<bool> {
+        let query = "DELETE FROM contacts WHERE id = ?1 AND user_id = ?2";
+
+        let result = sqlx::query(query)
+            .bind(contact_id.to_string())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to delete contact: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Contact {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(Contact {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            name: row.try_get("name")?,
+            address: row.try_get("address")?,
+            memo: row.try_get("memo")?,
+            federation_name: row.try_get("federation_name")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,92 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::customer_field::CustomerField;
+use crate::utils::crypto::Envelope;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn upsert_customer_field(&self, field: &CustomerField) -> Result<()> {
+        let query = r#"
+            INSERT INTO customer_fields (id, user_id, field_name, ciphertext, nonce, wrapped_key, key_nonce, key_id, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (user_id, field_name) DO UPDATE SET
+                ciphertext = excluded.ciphertext,
+                nonce = excluded.nonce,
+                wrapped_key = excluded.wrapped_key,
+                key_nonce = excluded.key_nonce,
+                key_id = excluded.key_id,
+                updated_at = excluded.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(field.id.to_string())
+            .bind(field.user_id.to_string())
+            .bind(&field.field_name)
+            .bind(&field.envelope.ciphertext)
+            .bind(&field.envelope.nonce)
+            .bind(&field.envelope.wrapped_key)
+            .bind(&field.envelope.key_nonce)
+            .bind(&field.envelope.key_id)
+            .bind(field.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save customer field: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_customer_fields(&self, user_id: &Uuid) -> Result<Vec<CustomerField>> {
+        let query = "SELECT * FROM customer_fields WHERE user_id = ?1 ORDER BY field_name";
+
+        sqlx::query_as::<_, CustomerField>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch customer fields: {}", e)))
+    }
+
+    /// Fields whose data key is wrapped by anything other than `key_id`.
+    pub async fn get_customer_fields_not_under_key(&self, key_id: &str) -> Result<Vec<CustomerField>> {
+        let query = "SELECT * FROM customer_fields WHERE key_id != ?1";
+
+        sqlx::query_as::<_, CustomerField>(query)
+            .bind(key_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch customer fields: {}", e)))
+    }
+
+    pub async fn delete_customer_field(&self, user_id: &Uuid, field_name: &str) -> Result<bool> {
+        let query = "DELETE FROM customer_fields WHERE user_id = ?1 AND field_name = ?2";
+
+        let result = sqlx::query(query)
+            .bind(user_id.to_string())
+            .bind(field_name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to delete customer field: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for CustomerField {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(CustomerField {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            field_name: row.try_get("field_name")?,
+            envelope: Envelope {
+                ciphertext: row.try_get("ciphertext")?,
+                nonce: row.try_get("nonce")?,
+                wrapped_key: row.try_get("wrapped_key")?,
+                key_nonce: row.try_get("key_nonce")?,
+                key_id: row.try_get("key_id")?,
+            },
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::derived_account::{DerivedAccount, PhraseRecoveryCode, RecoveryPhrase};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_recovery_phrase(&self, phrase: &RecoveryPhrase) -> Result<()> {
+        let query = r#"
+            INSERT INTO recovery_phrases (user_id, encrypted_phrase, salt, nonce, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        "#;
+
+        sqlx::query(query)
+            .bind(phrase.user_id.to_string())
+            .bind(&phrase.encrypted_phrase)
+            .bind(&phrase.salt)
+            .bind(&phrase.nonce)
+            .bind(phrase.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ValidationError("This account already has a recovery phrase".to_string())
+                } else {
+                    AppError::DatabaseError(format!("Failed to store recovery phrase: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    pub async fn get_recovery_phrase(&self, user_id: &Uuid) -> Result<Option<RecoveryPhrase>> {
+        let query = "SELECT * FROM recovery_phrases WHERE user_id = ?1";
+
+        sqlx::query_as::<_, RecoveryPhrase>(query)
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch recovery phrase: {}", e)))
+    }
+
+    /// Replaces all of the user's recovery codes, used or not, with `codes`.
+    pub async fn replace_phrase_recovery_codes(&self, user_id: &Uuid, codes: &[PhraseRecoveryCode]) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to store recovery codes: {}", e));
+        let query = r#"
+            INSERT INTO phrase_recovery_codes (id, user_id, encrypted_phrase, salt, nonce, used_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#;
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        sqlx::query("DELETE FROM phrase_recovery_codes WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+
+        for code in codes {
+            sqlx::query(query)
+                .bind(code.id.to_string())
+                .bind(code.user_id.to_string())
+                .bind(&code.encrypted_phrase)
+                .bind(&code.salt)
+                .bind(&code.nonce)
+                .bind(code.used_at.map(|at| at.to_rfc3339()))
+                .bind(code.created_at.to_rfc3339())
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+
+        tx.commit().await.map_err(map_err)
+    }
+
+    pub async fn get_unused_phrase_recovery_codes(&self, user_id: &Uuid) -> Result<Vec<PhraseRecoveryCode>> {
+        let query = "SELECT * FROM phrase_recovery_codes WHERE user_id = ?1 AND used_at IS NULL ORDER BY created_at";
+
+        sqlx::query_as::<_, PhraseRecoveryCode>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch recovery codes: {}", e)))
+    }
+
+    pub async fn create_derived_account(&self, account: &DerivedAccount) -> Result<()> {
+        let query = r#"
+            INSERT INTO derived_accounts (id, user_id, account_index, public_key, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        "#;
+
+        sqlx::query(query)
+            .bind(account.id.to_string())
+            .bind(account.user_id.to_string())
+            .bind(account.account_index as i64)
+            .bind(&account.public_key)
+            .bind(account.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to store derived account: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_derived_accounts_by_user(&self, user_id: &Uuid) -> Result<Vec<DerivedAccount>> {
+        let query = "SELECT * FROM derived_accounts WHERE user_id = ?1 ORDER BY account_index";
+
+        sqlx::query_as::<_, DerivedAccount>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch derived accounts: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for RecoveryPhrase {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(RecoveryPhrase {
+            user_id: rows::uuid(row, "user_id")?,
+            encrypted_phrase: row.try_get("encrypted_phrase")?,
+            salt: row.try_get("salt")?,
+            nonce: row.try_get("nonce")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for PhraseRecoveryCode {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(PhraseRecoveryCode {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            encrypted_phrase: row.try_get("encrypted_phrase")?,
+            salt: row.try_get("salt")?,
+            nonce: row.try_get("nonce")?,
+            used_at: rows::optional_timestamp(row, "used_at")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for DerivedAccount {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(DerivedAccount {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            account_index: row.try_get::<i64, _>("account_index")? as u32,
+            public_key: row.try_get("public_key")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
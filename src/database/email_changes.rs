@@ -0,0 +1,106 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::email_change::EmailChange;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    /// Saves a pending change, replacing any earlier one for the user.
+    pub async fn upsert_email_change(&self, change: &EmailChange) -> Result<()> {
+        let query = r#"
+            INSERT INTO email_changes (user_id, new_email, old_code_hash, new_code_hash, failures, expires_at, created_at, used_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (user_id) DO UPDATE SET
+                new_email = excluded.new_email,
+                old_code_hash = excluded.old_code_hash,
+                new_code_hash = excluded.new_code_hash,
+                failures = excluded.failures,
+                expires_at = excluded.expires_at,
+                created_at = excluded.created_at,
+                used_at = excluded.used_at
+        "#;
+
+        sqlx::query(query)
+            .bind(change.user_id.to_string())
+            .bind(&change.new_email)
+            .bind(&change.old_code_hash)
+            .bind(&change.new_code_hash)
+            .bind(change.failures)
+            .bind(change.expires_at.to_rfc3339())
+            .bind(change.created_at.to_rfc3339())
+            .bind(change.used_at.map(|at| at.to_rfc3339()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save email change: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_email_change(&self, user_id: &Uuid) -> Result<Option<EmailChange>> {
+        sqlx::query_as::<_, EmailChange>("SELECT * FROM email_changes WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch email change: {}", e)))
+    }
+
+    pub async fn delete_email_change(&self, user_id: &Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM email_changes WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to remove email change: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Switches the user to the pending change's address and marks the change
+    /// used, in one transaction, so the email index never holds both addresses
+    /// or neither and the codes can't be accepted twice. The new address counts
+    /// as verified: it just received a code.
+    pub async fn apply_email_change(&self, user_id: &Uuid, new_email: &str, now: DateTime<Utc>) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to change email: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        sqlx::query("UPDATE users SET email = ?1, is_verified = 1, verified_at = ?2, updated_at = ?2 WHERE id = ?3")
+            .bind(new_email)
+            .bind(now.to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ValidationError("Email already exists".to_string())
+                } else {
+                    map_err(e)
+                }
+            })?;
+        sqlx::query("UPDATE email_changes SET used_at = ?1 WHERE user_id = ?2")
+            .bind(now.to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(())
+    }
+}
+
+impl FromRow<'_, SqliteRow> for EmailChange {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(EmailChange {
+            user_id: rows::uuid(row, "user_id")?,
+            new_email: row.try_get("new_email")?,
+            old_code_hash: row.try_get("old_code_hash")?,
+            new_code_hash: row.try_get("new_code_hash")?,
+            failures: row.try_get("failures")?,
+            expires_at: rows::timestamp(row, "expires_at")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            used_at: rows::optional_timestamp(row, "used_at")?,
+        })
+    }
+}
@@ -0,0 +1,184 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use sqlx::Row;
+use std::env;
+use std::path::Path;
+
+/// Keyring service the wallet's entries are stored under.
+#[cfg(feature = "keyring")]
+pub const KEYRING_SERVICE: &str = "stellar-wallet";
+/// Keyring entry holding the database key when `DATABASE_KEY_SOURCE=keyring`.
+#[cfg(feature = "sqlcipher")]
+pub const KEYRING_USER: &str = "database-key";
+
+/// Where the SQLCipher key comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// The database is stored in plaintext.
+    None,
+    /// `DATABASE_KEY` holds the passphrase.
+    Env(String),
+    /// The OS keyring holds the passphrase.
+    Keyring,
+}
+
+impl KeySource {
+    /// Reads `DATABASE_KEY` and `DATABASE_KEY_SOURCE` (`env`, the default, or `keyring`).
+    pub fn from_env() -> Result<Self> {
+        let key = env::var("DATABASE_KEY").ok().filter(|key| !key.is_empty());
+
+        match config::var("DATABASE_KEY_SOURCE").unwrap_or_default().to_lowercase().as_str() {
+            "" | "env" => Ok(key.map_or(KeySource::None, KeySource::Env)),
+            "keyring" if key.is_some() => Err(AppError::ValidationError(
+                "DATABASE_KEY must not be set when DATABASE_KEY_SOURCE=keyring".to_string(),
+            )),
+            "keyring" => Ok(KeySource::Keyring),
+            other => Err(AppError::ValidationError(format!("Unknown DATABASE_KEY_SOURCE '{}'", other))),
+        }
+    }
+
+    /// The key to open the database with, if it is encrypted.
+    pub fn key(&self) -> Result<Option<String>> {
+        match self {
+            KeySource::None => Ok(None),
+            KeySource::Env(key) => Ok(Some(key.clone())),
+            KeySource::Keyring => keyring_key(false),
+        }
+    }
+
+    /// Like [`KeySource::key`], but stores a fresh random key in the keyring if
+    /// it has none yet. Used when encrypting an existing database.
+    pub fn key_or_create(&self) -> Result<Option<String>> {
+        match self {
+            KeySource::Keyring => keyring_key(true),
+            _ => self.key(),
+        }
+    }
+}
+
+#[cfg(feature = "sqlcipher")]
+fn keyring_key(create: bool) -> Result<Option<String>> {
+    let keyring_error = |e: keyring::Error| AppError::InternalError(format!("OS keyring error: {}", e));
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(keyring_error)?;
+
+    match entry.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) if create => {
+            let key = random_key();
+            entry.set_password(&key).map_err(keyring_error)?;
+            Ok(Some(key))
+        }
+        Err(keyring::Error::NoEntry) => Err(AppError::ValidationError(format!(
+            "No database key in the OS keyring ({}/{}); run `stellar-wallet db encrypt` first",
+            KEYRING_SERVICE, KEYRING_USER
+        ))),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+fn keyring_key(_create: bool) -> Result<Option<String>> {
+    Err(not_built_with_sqlcipher())
+}
+
+pub fn not_built_with_sqlcipher() -> AppError {
+    AppError::ValidationError(
+        "This build has no database encryption; rebuild with `--features sqlcipher` to use a database key".to_string(),
+    )
+}
+
+#[cfg(feature = "sqlcipher")]
+fn random_key() -> String {
+    use rand_core::{OsRng, RngCore};
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// A key as a quoted SQL string literal for `PRAGMA key` and `ATTACH ... KEY`.
+pub fn quote_key(key: &str) -> String {
+    format!("'{}'", key.replace('\'', "''"))
+}
+
+impl SqliteDatabase {
+    /// Row counts per table, used to check a copy of the database is complete.
+    pub async fn table_row_counts(&self) -> Result<Vec<(String, i64)>> {
+        let tables = sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to list tables: {}", e)))?;
+
+        let mut counts = Vec::new();
+        for table in tables {
+            let name: String = table.get("name");
+            // Names come from sqlite_master, not from users.
+            let count: i64 = sqlx::query(&format!("SELECT COUNT(*) AS n FROM \"{}\"", name.replace('"', "\"\"")))
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| AppError::DatabaseError(format!("Failed to count {}: {}", name, e)))?
+                .get("n");
+            counts.push((name, count));
+        }
+
+        Ok(counts)
+    }
+
+    /// Writes an encrypted copy of this plaintext database to `target` with
+    /// SQLCipher's `sqlcipher_export`.
+    pub async fn export_encrypted(&self, target: &Path, key: &str) -> Result<()> {
+        if !cfg!(feature = "sqlcipher") {
+            return Err(not_built_with_sqlcipher());
+        }
+
+        // Connections are opened without SQLITE_OPEN_CREATE, and ATTACH inherits that.
+        std::fs::File::create(target)
+            .map_err(|e| AppError::DatabaseError(format!("Failed to create {}: {}", target.display(), e)))?;
+
+        let export_error = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to encrypt database: {}", e));
+        // ATTACH, export and DETACH must all run on the same connection.
+        let mut conn = self.pool.acquire().await.map_err(export_error)?;
+
+        sqlx::query(&format!("ATTACH DATABASE ?1 AS encrypted KEY {}", quote_key(key)))
+            .bind(target.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await
+            .map_err(export_error)?;
+        sqlx::query("SELECT sqlcipher_export('encrypted')")
+            .execute(&mut *conn)
+            .await
+            .map_err(export_error)?;
+        sqlx::query("DETACH DATABASE encrypted")
+            .execute(&mut *conn)
+            .await
+            .map_err(export_error)?;
+
+        Ok(())
+    }
+
+    pub async fn close(self) {
+        self.pool.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_keys_as_sql_strings() {
+        assert_eq!(quote_key("correct horse"), "'correct horse'");
+        assert_eq!(quote_key("it's"), "'it''s'");
+    }
+
+    #[tokio::test]
+    async fn counts_rows_in_every_table() {
+        let db = SqliteDatabase::in_memory().await;
+        db.insert_test_user().await;
+
+        let counts = db.table_row_counts().await.unwrap();
+        assert!(counts.contains(&("users".to_string(), 1)));
+        assert!(counts.iter().any(|(name, _)| name == "_sqlx_migrations"));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::outbox::OutboxEvent;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+
+impl SqliteDatabase {
+    /// Queues an event unless it already is, dropping the ones published
+    /// before `published_before`. Returns whether it was queued.
+    pub async fn insert_outbox_event(&self, event: &OutboxEvent, published_before: DateTime<Utc>) -> Result<bool> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to queue event: {}", e));
+
+        sqlx::query("DELETE FROM event_outbox WHERE published_at < ?1")
+            .bind(published_before.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+        let query = r#"
+            INSERT OR IGNORE INTO event_outbox (event_id, event_type, subject, payload, attempts, last_error,
+                                               next_attempt_at, published_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#;
+        let inserted = sqlx::query(query)
+            .bind(&event.event_id)
+            .bind(&event.event_type)
+            .bind(&event.subject)
+            .bind(&event.payload)
+            .bind(event.attempts)
+            .bind(&event.last_error)
+            .bind(event.next_attempt_at.map(|at| at.to_rfc3339()))
+            .bind(event.published_at.map(|at| at.to_rfc3339()))
+            .bind(event.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?
+            .rows_affected();
+
+        Ok(inserted > 0)
+    }
+
+    /// Unpublished events due by `now`, oldest first.
+    pub async fn get_due_outbox_events(&self, now: DateTime<Utc>, limit: u32) -> Result<Vec<OutboxEvent>> {
+        sqlx::query_as::<_, OutboxEvent>("SELECT * FROM event_outbox WHERE next_attempt_at <= ?1 ORDER BY next_attempt_at LIMIT ?2")
+            .bind(now.to_rfc3339())
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch queued events: {}", e)))
+    }
+
+    /// Moves a due event's next attempt to `until`, so nobody else publishes
+    /// it meanwhile. Returns false if someone already has.
+    pub async fn claim_outbox_event(&self, event: &OutboxEvent, until: DateTime<Utc>) -> Result<bool> {
+        let claimed = sqlx::query("UPDATE event_outbox SET next_attempt_at = ?3 WHERE event_id = ?1 AND next_attempt_at IS ?2")
+            .bind(&event.event_id)
+            .bind(event.next_attempt_at.map(|at| at.to_rfc3339()))
+            .bind(until.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to claim queued event: {}", e)))?
+            .rows_affected();
+
+        Ok(claimed > 0)
+    }
+
+    /// Records how an attempt to publish went.
+    pub async fn update_outbox_event(&self, event: &OutboxEvent) -> Result<()> {
+        let query = r#"
+            UPDATE event_outbox SET attempts = ?2, last_error = ?3, next_attempt_at = ?4, published_at = ?5
+            WHERE event_id = ?1
+        "#;
+
+        sqlx::query(query)
+            .bind(&event.event_id)
+            .bind(event.attempts)
+            .bind(&event.last_error)
+            .bind(event.next_attempt_at.map(|at| at.to_rfc3339()))
+            .bind(event.published_at.map(|at| at.to_rfc3339()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update queued event: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+impl FromRow<'_, SqliteRow> for OutboxEvent {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(OutboxEvent {
+            event_id: row.try_get("event_id")?,
+            event_type: row.try_get("event_type")?,
+            subject: row.try_get("subject")?,
+            payload: row.try_get("payload")?,
+            attempts: row.try_get("attempts")?,
+            last_error: row.try_get("last_error")?,
+            next_attempt_at: rows::optional_timestamp(row, "next_attempt_at")?,
+            published_at: rows::optional_timestamp(row, "published_at")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,115 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::idempotency::IdempotencyKey;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    /// Records a key unless its scope already has it. Returns whether it was
+    /// recorded, which makes this request the one that runs.
+    pub async fn insert_idempotency_key(&self, record: &IdempotencyKey) -> Result<bool> {
+        let query = r#"
+            INSERT OR IGNORE INTO idempotency_keys (scope, key, fingerprint, user_id, response_status, response_body, pending_hash, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+
+        let inserted = sqlx::query(query)
+            .bind(&record.scope)
+            .bind(&record.key)
+            .bind(&record.fingerprint)
+            .bind(record.user_id.map(|id| id.to_string()))
+            .bind(record.response_status)
+            .bind(&record.response_body)
+            .bind(&record.pending_hash)
+            .bind(record.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save idempotency key: {}", e)))?
+            .rows_affected();
+
+        Ok(inserted > 0)
+    }
+
+    pub async fn get_idempotency_key(&self, scope: &str, key: &str) -> Result<Option<IdempotencyKey>> {
+        sqlx::query_as::<_, IdempotencyKey>("SELECT * FROM idempotency_keys WHERE scope = ?1 AND key = ?2")
+            .bind(scope)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch idempotency key: {}", e)))
+    }
+
+    /// Saves the response to replay for the key.
+    pub async fn complete_idempotency_key(&self, scope: &str, key: &str, user_id: Option<&Uuid>, status: u16, body: &str) -> Result<()> {
+        let query = r#"
+            UPDATE idempotency_keys SET user_id = ?3, response_status = ?4, response_body = ?5, pending_hash = NULL
+            WHERE scope = ?1 AND key = ?2
+        "#;
+
+        sqlx::query(query)
+            .bind(scope)
+            .bind(key)
+            .bind(user_id.map(|id| id.to_string()))
+            .bind(status)
+            .bind(body)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save idempotent response: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Notes the transaction the key's request left pending.
+    pub async fn hold_idempotency_key(&self, scope: &str, key: &str, hash: &str) -> Result<()> {
+        sqlx::query("UPDATE idempotency_keys SET pending_hash = ?3 WHERE scope = ?1 AND key = ?2")
+            .bind(scope)
+            .bind(key)
+            .bind(hash)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save pending transaction: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn delete_idempotency_key(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = ?1 AND key = ?2")
+            .bind(scope)
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to remove idempotency key: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Forgets keys first used before `cutoff`. Returns how many there were.
+    pub async fn delete_idempotency_keys_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let deleted = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?1")
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to remove old idempotency keys: {}", e)))?
+            .rows_affected();
+
+        Ok(deleted)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for IdempotencyKey {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(IdempotencyKey {
+            scope: row.try_get("scope")?,
+            key: row.try_get("key")?,
+            fingerprint: row.try_get("fingerprint")?,
+            user_id: rows::optional_uuid(row, "user_id")?,
+            response_status: row.try_get("response_status")?,
+            response_body: row.try_get("response_body")?,
+            pending_hash: row.try_get("pending_hash")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,138 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::keystore::KeystoreEntry;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_keystore_entry(&self, entry: &KeystoreEntry) -> Result<()> {
+        let query = r#"
+            INSERT INTO keystore (id, user_id, public_key, encrypted_secret, salt, nonce, label, signs_for, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#;
+
+        sqlx::query(query)
+            .bind(entry.id.to_string())
+            .bind(entry.user_id.to_string())
+            .bind(&entry.public_key)
+            .bind(&entry.encrypted_secret)
+            .bind(&entry.salt)
+            .bind(&entry.nonce)
+            .bind(&entry.label)
+            .bind(&entry.signs_for)
+            .bind(entry.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ValidationError("This key is already in the keystore".to_string())
+                } else {
+                    AppError::DatabaseError(format!("Failed to store key: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    pub async fn get_keystore_entry(&self, user_id: &Uuid, public_key: &str) -> Result<Option<KeystoreEntry>> {
+        let query = "SELECT * FROM keystore WHERE user_id = ?1 AND public_key = ?2";
+
+        sqlx::query_as::<_, KeystoreEntry>(query)
+            .bind(user_id.to_string())
+            .bind(public_key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch keystore entry: {}", e)))
+    }
+
+    /// The key that currently signs for `account`: the newest one rotated in
+    /// for it, or else the account's own key.
+    pub async fn get_signing_key_entry(&self, user_id: &Uuid, account: &str) -> Result<Option<KeystoreEntry>> {
+        let query = r#"
+            SELECT * FROM keystore
+            WHERE user_id = ?1 AND (signs_for = ?2 OR public_key = ?2)
+            ORDER BY signs_for IS NULL, created_at DESC
+            LIMIT 1
+        "#;
+
+        sqlx::query_as::<_, KeystoreEntry>(query)
+            .bind(user_id.to_string())
+            .bind(account)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch keystore entry: {}", e)))
+    }
+
+    pub async fn delete_keystore_entry(&self, entry_id: &Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM keystore WHERE id = ?1")
+            .bind(entry_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to delete keystore entry: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_keystore_entries_by_user(&self, user_id: &Uuid) -> Result<Vec<KeystoreEntry>> {
+        let query = "SELECT * FROM keystore WHERE user_id = ?1 ORDER BY created_at";
+
+        sqlx::query_as::<_, KeystoreEntry>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch keystore entries: {}", e)))
+    }
+
+    /// Every user's own addresses: keystore keys that aren't rotated-in
+    /// signers, and primary addresses held elsewhere (a Ledger).
+    pub async fn get_wallet_addresses(&self) -> Result<Vec<(Uuid, String)>> {
+        let query = r#"
+            SELECT user_id, public_key FROM keystore WHERE signs_for IS NULL
+            UNION
+            SELECT id AS user_id, stellar_public_key AS public_key FROM users WHERE stellar_public_key IS NOT NULL
+        "#;
+
+        let rows = sqlx::query(query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch wallet addresses: {}", e)))?;
+
+        rows.iter()
+            .map(|row| Ok((rows::uuid(row, "user_id")?, row.try_get("public_key")?)))
+            .collect::<sqlx::Result<_>>()
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch wallet addresses: {}", e)))
+    }
+
+    /// The user `address` is one of the own addresses of, as in
+    /// [`SqliteDatabase::get_wallet_addresses`].
+    pub async fn get_wallet_owner(&self, address: &str) -> Result<Option<Uuid>> {
+        let query = r#"
+            SELECT user_id FROM keystore WHERE signs_for IS NULL AND public_key = ?1
+            UNION
+            SELECT id AS user_id FROM users WHERE stellar_public_key = ?1
+            LIMIT 1
+        "#;
+
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to look up a wallet address: {}", e));
+        let row = sqlx::query(query).bind(address).fetch_optional(&self.pool).await.map_err(map_err)?;
+        row.map(|row| rows::uuid(&row, "user_id")).transpose().map_err(map_err)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for KeystoreEntry {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(KeystoreEntry {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            public_key: row.try_get("public_key")?,
+            encrypted_secret: row.try_get("encrypted_secret")?,
+            salt: row.try_get("salt")?,
+            nonce: row.try_get("nonce")?,
+            label: row.try_get("label")?,
+            signs_for: row.try_get("signs_for")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::ledger_account::LedgerAccount;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_ledger_account(&self, account: &LedgerAccount) -> Result<()> {
+        let query = r#"
+            INSERT INTO ledger_accounts (id, user_id, public_key, account_index, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (user_id, public_key) DO NOTHING
+        "#;
+
+        sqlx::query(query)
+            .bind(account.id.to_string())
+            .bind(account.user_id.to_string())
+            .bind(&account.public_key)
+            .bind(account.account_index as i64)
+            .bind(account.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to store Ledger account: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_ledger_account(&self, user_id: &Uuid, public_key: &str) -> Result<Option<LedgerAccount>> {
+        let query = "SELECT * FROM ledger_accounts WHERE user_id = ?1 AND public_key = ?2";
+
+        sqlx::query_as::<_, LedgerAccount>(query)
+            .bind(user_id.to_string())
+            .bind(public_key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch Ledger account: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for LedgerAccount {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(LedgerAccount {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            public_key: row.try_get("public_key")?,
+            account_index: row.try_get::<i64, _>("account_index")? as u32,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::login_attempt::LoginAttempt;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_login_attempt(&self, attempt: &LoginAttempt) -> Result<()> {
+        let query = r#"
+            INSERT INTO login_attempts (id, user_id, succeeded, failure_reason, source, user_agent, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#;
+
+        sqlx::query(query)
+            .bind(attempt.id.to_string())
+            .bind(attempt.user_id.to_string())
+            .bind(attempt.succeeded)
+            .bind(&attempt.failure_reason)
+            .bind(&attempt.source)
+            .bind(&attempt.user_agent)
+            .bind(attempt.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to record login attempt: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// `user_id`'s most recent login attempts, newest first.
+    pub async fn get_login_attempts(&self, user_id: &Uuid, limit: i64) -> Result<Vec<LoginAttempt>> {
+        let query = "SELECT * FROM login_attempts WHERE user_id = ?1 ORDER BY created_at DESC LIMIT ?2";
+
+        sqlx::query_as::<_, LoginAttempt>(query)
+            .bind(user_id.to_string())
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch login attempts: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for LoginAttempt {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(LoginAttempt {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            succeeded: row.try_get("succeeded")?,
+            failure_reason: row.try_get("failure_reason")?,
+            source: row.try_get("source")?,
+            user_agent: row.try_get("user_agent")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
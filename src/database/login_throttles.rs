@@ -0,0 +1,60 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::login_throttle::LoginThrottle;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+
+impl SqliteDatabase {
+    pub async fn get_login_throttle(&self, key: &str) -> Result<Option<LoginThrottle>> {
+        sqlx::query_as::<_, LoginThrottle>("SELECT * FROM login_throttles WHERE key = ?1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch login throttle: {}", e)))
+    }
+
+    /// Saves the failure count and lock for a scope.
+    pub async fn upsert_login_throttle(&self, throttle: &LoginThrottle) -> Result<()> {
+        let query = r#"
+            INSERT INTO login_throttles (key, failures, locked_until, last_failure_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (key) DO UPDATE SET
+                failures = excluded.failures,
+                locked_until = excluded.locked_until,
+                last_failure_at = excluded.last_failure_at
+        "#;
+
+        sqlx::query(query)
+            .bind(&throttle.key)
+            .bind(throttle.failures)
+            .bind(throttle.locked_until.map(|until| until.to_rfc3339()))
+            .bind(throttle.last_failure_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save login throttle: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn delete_login_throttle(&self, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM login_throttles WHERE key = ?1")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to clear login throttle: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+impl FromRow<'_, SqliteRow> for LoginThrottle {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(LoginThrottle {
+            key: row.try_get("key")?,
+            failures: row.try_get("failures")?,
+            locked_until: rows::optional_timestamp(row, "locked_until")?,
+            last_failure_at: rows::timestamp(row, "last_failure_at")?,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use sqlx::Row;
+
+impl SqliteDatabase {
+    /// Problems reported by `PRAGMA integrity_check`, or none if it says `ok`.
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to check database integrity: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| row.get::<String, _>(0))
+            .filter(|line| line != "ok")
+            .collect())
+    }
+
+    /// Size of the database in bytes, from its page count.
+    pub async fn size_bytes(&self) -> Result<i64> {
+        let row = sqlx::query("SELECT page_count * page_size AS size FROM pragma_page_count(), pragma_page_size()")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to read database size: {}", e)))?;
+
+        Ok(row.get("size"))
+    }
+
+    /// Rebuilds the file without free pages, then truncates the WAL so the
+    /// space goes back to the filesystem.
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to vacuum database: {}", e)))?;
+
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to checkpoint database: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Refreshes the statistics the query planner uses to pick indexes.
+    pub async fn analyze(&self) -> Result<()> {
+        sqlx::query("ANALYZE")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to analyze database: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn vacuum_reclaims_deleted_rows() {
+        let db = SqliteDatabase::in_memory().await;
+        for _ in 0..200 {
+            db.insert_test_user().await;
+        }
+        sqlx::query("DELETE FROM users").execute(&db.pool).await.unwrap();
+        let before = db.size_bytes().await.unwrap();
+
+        assert!(db.integrity_check().await.unwrap().is_empty());
+        db.vacuum().await.unwrap();
+        db.analyze().await.unwrap();
+
+        assert!(db.size_bytes().await.unwrap() < before);
+    }
+}
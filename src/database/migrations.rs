@@ -0,0 +1,85 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::migration::MigrationStatus;
+use sqlx::migrate::Migrator;
+use sqlx::Row;
+use std::collections::HashMap;
+
+/// Versioned schema changes from `migrations/`, embedded at compile time. Add
+/// a new file for every schema change; never edit one that has shipped, since
+/// sqlx refuses to run against a database whose applied checksum differs.
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+impl SqliteDatabase {
+    pub async fn run_migrations(&self) -> Result<()> {
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to run migrations: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Every known migration alongside when (if ever) it was applied here.
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let tracked: i64 = sqlx::query("SELECT COUNT(*) AS count FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to read migration status: {}", e)))?
+            .get("count");
+
+        let mut applied = HashMap::new();
+        if tracked > 0 {
+            let rows = sqlx::query("SELECT version, installed_on FROM _sqlx_migrations WHERE success ORDER BY version")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| AppError::DatabaseError(format!("Failed to read migration status: {}", e)))?;
+
+            for row in rows {
+                applied.insert(row.get::<i64, _>("version"), row.get::<String, _>("installed_on"));
+            }
+        }
+
+        Ok(MIGRATOR
+            .iter()
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                installed_on: applied.remove(&migration.version),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fresh_databases_are_fully_migrated() {
+        let db = SqliteDatabase::in_memory().await;
+
+        let status = db.migration_status().await.unwrap();
+        assert!(!status.is_empty());
+        assert!(status.iter().all(MigrationStatus::is_applied));
+        assert_eq!(status[0].description, "initial schema");
+
+        // Running again is a no-op.
+        db.run_migrations().await.unwrap();
+        assert_eq!(db.get_user_count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn unmigrated_databases_report_everything_pending() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = SqliteDatabase { pool };
+
+        let status = db.migration_status().await.unwrap();
+        assert_eq!(status.len(), MIGRATOR.iter().count());
+        assert!(status.iter().all(|migration| !migration.is_applied()));
+    }
+}
@@ -1 +1,39 @@
+pub mod activity;
+pub mod api_keys;
+pub mod archive;
+pub mod audit_log;
+pub mod config;
+pub mod contacts;
+pub mod customer_fields;
+pub mod derived_accounts;
+pub mod email_changes;
+pub mod encryption;
+pub mod event_outbox;
+pub mod idempotency_keys;
+pub mod keystore;
+pub mod ledger_accounts;
+pub mod login_attempts;
+pub mod login_throttles;
+pub mod maintenance;
+pub mod migrations;
+#[cfg(feature = "mysql")]
+pub mod mysql;
+pub mod oauth;
+pub mod offline;
+pub mod passkeys;
+pub mod password_changes;
+pub mod payment_filters;
+pub mod payment_notes;
+pub mod policies;
+pub mod reports;
+pub mod rows;
+pub mod sessions;
+pub mod signing_pins;
+pub mod sms_messages;
 pub mod sqlite;
+pub mod stream_tickets;
+pub mod transactions;
+pub mod two_factor;
+pub mod user_repository;
+pub mod user_settings;
+pub mod webhooks;
\ No newline at end of file
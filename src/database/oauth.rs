@@ -0,0 +1,125 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::oauth::{LinkedIdentity, OAuthProvider};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+
+impl SqliteDatabase {
+    /// Remembers a login sent to `provider`, dropping the ones that expired
+    /// without coming back.
+    pub async fn create_oauth_state(
+        &self,
+        state_hash: &str,
+        provider: OAuthProvider,
+        code_verifier: &str,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to save OAuth state: {}", e));
+
+        sqlx::query("DELETE FROM oauth_states WHERE expires_at < ?1")
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+        sqlx::query("INSERT INTO oauth_states (state_hash, provider, code_verifier, expires_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+            .bind(state_hash)
+            .bind(provider.as_str())
+            .bind(code_verifier)
+            .bind(expires_at.to_rfc3339())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+
+        Ok(())
+    }
+
+    /// Deletes the state and returns its PKCE code verifier, if it was issued
+    /// for `provider` and hasn't expired. A second call for the same state
+    /// finds nothing.
+    pub async fn take_oauth_state(&self, state_hash: &str, provider: OAuthProvider, now: DateTime<Utc>) -> Result<Option<String>> {
+        let row = sqlx::query("DELETE FROM oauth_states WHERE state_hash = ?1 RETURNING provider, code_verifier, expires_at")
+            .bind(state_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to use OAuth state: {}", e)))?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let issued_for: String = row.get("provider");
+        let expires_at = rows::timestamp(&row, "expires_at").map_err(|e| AppError::DatabaseError(format!("Failed to use OAuth state: {}", e)))?;
+        Ok((issued_for == provider.as_str() && expires_at > now).then(|| row.get("code_verifier")))
+    }
+
+    pub async fn get_oauth_identity(&self, provider: OAuthProvider, subject: &str) -> Result<Option<LinkedIdentity>> {
+        sqlx::query_as::<_, LinkedIdentity>("SELECT * FROM oauth_identities WHERE provider = ?1 AND subject = ?2")
+            .bind(provider.as_str())
+            .bind(subject)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch OAuth identity: {}", e)))
+    }
+
+    pub async fn insert_oauth_identity(&self, identity: &LinkedIdentity) -> Result<()> {
+        sqlx::query("INSERT INTO oauth_identities (provider, subject, user_id, email, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+            .bind(identity.provider.as_str())
+            .bind(&identity.subject)
+            .bind(identity.user_id.to_string())
+            .bind(&identity.email)
+            .bind(identity.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ConflictError(format!(
+                        "This account already has another {} account linked",
+                        identity.provider.display_name()
+                    ))
+                } else {
+                    AppError::DatabaseError(format!("Failed to link OAuth identity: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+}
+
+impl FromRow<'_, SqliteRow> for LinkedIdentity {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let provider: String = row.try_get("provider")?;
+
+        Ok(LinkedIdentity {
+            provider: OAuthProvider::parse(&provider).ok_or_else(|| rows::decode_error("provider", format!("unknown provider '{}'", provider)))?,
+            subject: row.try_get("subject")?,
+            user_id: rows::uuid(row, "user_id")?,
+            email: row.try_get("email")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn states_work_once_for_their_provider_until_they_expire() {
+        let db = SqliteDatabase::in_memory().await;
+        let now = Utc::now();
+
+        db.create_oauth_state("a", OAuthProvider::Google, "verifier-a", now + Duration::minutes(10), now).await.unwrap();
+        db.create_oauth_state("b", OAuthProvider::Google, "verifier-b", now + Duration::minutes(10), now).await.unwrap();
+        db.create_oauth_state("c", OAuthProvider::GitHub, "verifier-c", now + Duration::minutes(1), now).await.unwrap();
+
+        assert_eq!(db.take_oauth_state("a", OAuthProvider::Google, now).await.unwrap().as_deref(), Some("verifier-a"));
+        assert_eq!(db.take_oauth_state("a", OAuthProvider::Google, now).await.unwrap(), None);
+        assert_eq!(db.take_oauth_state("b", OAuthProvider::GitHub, now).await.unwrap(), None);
+        assert_eq!(db.take_oauth_state("b", OAuthProvider::Google, now).await.unwrap(), None);
+        assert_eq!(db.take_oauth_state("c", OAuthProvider::GitHub, now + Duration::minutes(2)).await.unwrap(), None);
+    }
+}
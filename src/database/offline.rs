@@ -0,0 +1,120 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::offline::{BalanceSnapshot, QueuedPayment};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    /// Saves an account's balances, replacing the previous snapshot.
+    pub async fn upsert_balance_snapshot(&self, snapshot: &BalanceSnapshot) -> Result<()> {
+        let balances = serde_json::to_string(&snapshot.balances)
+            .map_err(|e| AppError::InternalError(format!("Failed to serialize balances: {}", e)))?;
+        let query = r#"
+            INSERT INTO balance_snapshots (network, account, balances, fetched_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (network, account) DO UPDATE SET
+                balances = excluded.balances,
+                fetched_at = excluded.fetched_at
+        "#;
+
+        sqlx::query(query)
+            .bind(&snapshot.network)
+            .bind(&snapshot.account)
+            .bind(balances)
+            .bind(snapshot.fetched_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save balances: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_balance_snapshot(&self, network: &str, account: &str) -> Result<Option<BalanceSnapshot>> {
+        sqlx::query_as::<_, BalanceSnapshot>("SELECT * FROM balance_snapshots WHERE network = ?1 AND account = ?2")
+            .bind(network)
+            .bind(account)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch saved balances: {}", e)))
+    }
+
+    pub async fn create_queued_payment(&self, payment: &QueuedPayment) -> Result<()> {
+        let query = r#"
+            INSERT INTO queued_payments (id, user_id, network, source, destination, amount, memo, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+
+        sqlx::query(query)
+            .bind(payment.id.to_string())
+            .bind(payment.user_id.to_string())
+            .bind(&payment.network)
+            .bind(&payment.source)
+            .bind(&payment.destination)
+            .bind(&payment.amount)
+            .bind(&payment.memo)
+            .bind(payment.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to queue payment: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// The user's queued payments on `network`, oldest first.
+    pub async fn get_queued_payments(&self, user_id: &Uuid, network: &str) -> Result<Vec<QueuedPayment>> {
+        let query = r#"
+            SELECT * FROM queued_payments
+            WHERE user_id = ?1 AND network = ?2
+            ORDER BY created_at
+        "#;
+
+        sqlx::query_as::<_, QueuedPayment>(query)
+            .bind(user_id.to_string())
+            .bind(network)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch queued payments: {}", e)))
+    }
+
+    /// Removes a queued payment. Returns whether it was still queued.
+    pub async fn delete_queued_payment(&self, user_id: &Uuid, id: &Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM queued_payments WHERE id = ?1 AND user_id = ?2")
+            .bind(id.to_string())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to remove queued payment: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for BalanceSnapshot {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let balances: String = row.try_get("balances")?;
+
+        Ok(BalanceSnapshot {
+            network: row.try_get("network")?,
+            account: row.try_get("account")?,
+            balances: serde_json::from_str(&balances).map_err(|e| rows::decode_error("balances", e))?,
+            fetched_at: rows::timestamp(row, "fetched_at")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for QueuedPayment {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(QueuedPayment {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            network: row.try_get("network")?,
+            source: row.try_get("source")?,
+            destination: row.try_get("destination")?,
+            amount: row.try_get("amount")?,
+            memo: row.try_get("memo")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::passkey::{ChallengePurpose, Passkey};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn insert_passkey(&self, passkey: &Passkey) -> Result<()> {
+        let query = r#"
+            INSERT INTO passkeys (id, user_id, credential_id, public_key, sign_count, name, created_at, last_used_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+
+        sqlx::query(query)
+            .bind(passkey.id.to_string())
+            .bind(passkey.user_id.to_string())
+            .bind(&passkey.credential_id)
+            .bind(&passkey.public_key)
+            .bind(passkey.sign_count)
+            .bind(&passkey.name)
+            .bind(passkey.created_at.to_rfc3339())
+            .bind(passkey.last_used_at.map(|at| at.to_rfc3339()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ConflictError("This passkey is already registered".to_string())
+                } else {
+                    AppError::DatabaseError(format!("Failed to save passkey: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    /// `user_id`'s passkeys, oldest first.
+    pub async fn get_passkeys(&self, user_id: &Uuid) -> Result<Vec<Passkey>> {
+        sqlx::query_as::<_, Passkey>("SELECT * FROM passkeys WHERE user_id = ?1 ORDER BY created_at")
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch passkeys: {}", e)))
+    }
+
+    pub async fn get_passkey_by_credential_id(&self, credential_id: &str) -> Result<Option<Passkey>> {
+        sqlx::query_as::<_, Passkey>("SELECT * FROM passkeys WHERE credential_id = ?1")
+            .bind(credential_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up passkey: {}", e)))
+    }
+
+    /// Records a login with the passkey and the authenticator's new counter.
+    pub async fn record_passkey_use(&self, id: &Uuid, sign_count: u32, now: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE passkeys SET sign_count = ?2, last_used_at = ?3 WHERE id = ?1")
+            .bind(id.to_string())
+            .bind(sign_count)
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update passkey: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Removes one of `user_id`'s passkeys. Returns whether it existed.
+    pub async fn delete_passkey(&self, user_id: &Uuid, id: &Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM passkeys WHERE id = ?1 AND user_id = ?2")
+            .bind(id.to_string())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to remove passkey: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remembers a challenge, dropping the ones that expired unanswered.
+    pub async fn create_webauthn_challenge(
+        &self,
+        challenge_hash: &str,
+        purpose: ChallengePurpose,
+        user_id: Option<&Uuid>,
+        expires_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to save passkey challenge: {}", e));
+
+        sqlx::query("DELETE FROM webauthn_challenges WHERE expires_at < ?1")
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+        sqlx::query("INSERT INTO webauthn_challenges (challenge_hash, purpose, user_id, expires_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")
+            .bind(challenge_hash)
+            .bind(purpose.as_str())
+            .bind(user_id.map(|id| id.to_string()))
+            .bind(expires_at.to_rfc3339())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+
+        Ok(())
+    }
+
+    /// Deletes the challenge and returns whether it was issued for `purpose`
+    /// and `user_id` and hasn't expired. A second call for the same challenge
+    /// returns false.
+    pub async fn take_webauthn_challenge(
+        &self,
+        challenge_hash: &str,
+        purpose: ChallengePurpose,
+        user_id: Option<&Uuid>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let row = sqlx::query("DELETE FROM webauthn_challenges WHERE challenge_hash = ?1 RETURNING purpose, user_id, expires_at")
+            .bind(challenge_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to use passkey challenge: {}", e)))?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+
+        let decode_error = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to use passkey challenge: {}", e));
+        let issued_for: String = row.get("purpose");
+        let owner = rows::optional_uuid(&row, "user_id").map_err(decode_error)?;
+        let expires_at = rows::timestamp(&row, "expires_at").map_err(decode_error)?;
+        Ok(issued_for == purpose.as_str() && owner.as_ref() == user_id && expires_at > now)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Passkey {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(Passkey {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            credential_id: row.try_get("credential_id")?,
+            public_key: row.try_get("public_key")?,
+            sign_count: row.try_get("sign_count")?,
+            name: row.try_get("name")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            last_used_at: rows::optional_timestamp(row, "last_used_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn challenges_work_once_for_their_purpose_and_user() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let now = Utc::now();
+        let later = now + Duration::minutes(5);
+
+        db.create_webauthn_challenge("a", ChallengePurpose::Registration, Some(&user), later, now).await.unwrap();
+        db.create_webauthn_challenge("b", ChallengePurpose::Registration, Some(&user), later, now).await.unwrap();
+        db.create_webauthn_challenge("c", ChallengePurpose::Authentication, None, later, now).await.unwrap();
+
+        assert!(db.take_webauthn_challenge("a", ChallengePurpose::Registration, Some(&user), now).await.unwrap());
+        assert!(!db.take_webauthn_challenge("a", ChallengePurpose::Registration, Some(&user), now).await.unwrap());
+        assert!(!db.take_webauthn_challenge("b", ChallengePurpose::Registration, Some(&Uuid::new_v4()), now).await.unwrap());
+        assert!(!db.take_webauthn_challenge("c", ChallengePurpose::Authentication, None, later).await.unwrap());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::password_change::PasswordUpdate;
+use chrono::{DateTime, Utc};
+
+impl SqliteDatabase {
+    /// Saves a new password with the keys and phrase encrypted for it, spends
+    /// the recovery code, if any, and ends the user's other sessions, all in
+    /// one transaction. Returns how many sessions were ended, or `None`, with
+    /// nothing changed, if the recovery code had been used already.
+    pub async fn apply_password_update(&self, update: &PasswordUpdate, now: DateTime<Utc>) -> Result<Option<u64>> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to save new password: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        if let Some(code_id) = update.recovery_code {
+            let used = sqlx::query("UPDATE phrase_recovery_codes SET used_at = ?2 WHERE id = ?1 AND used_at IS NULL")
+                .bind(code_id.to_string())
+                .bind(now.to_rfc3339())
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?
+                .rows_affected();
+            if used == 0 {
+                return Ok(None);
+            }
+        }
+        for entry in &update.keystore_entries {
+            sqlx::query("UPDATE keystore SET encrypted_secret = ?2, salt = ?3, nonce = ?4 WHERE id = ?1")
+                .bind(entry.id.to_string())
+                .bind(&entry.encrypted_secret)
+                .bind(&entry.salt)
+                .bind(&entry.nonce)
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+        if let Some(phrase) = &update.recovery_phrase {
+            sqlx::query("UPDATE recovery_phrases SET encrypted_phrase = ?2, salt = ?3, nonce = ?4 WHERE user_id = ?1")
+                .bind(phrase.user_id.to_string())
+                .bind(&phrase.encrypted_phrase)
+                .bind(&phrase.salt)
+                .bind(&phrase.nonce)
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+        sqlx::query("UPDATE users SET password_hash = ?1 WHERE id = ?2")
+            .bind(&update.password_hash)
+            .bind(update.user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        let ended = sqlx::query("UPDATE sessions SET revoked_at = ?3 WHERE user_id = ?1 AND id IS NOT ?2 AND revoked_at IS NULL")
+            .bind(update.user_id.to_string())
+            .bind(update.keep_session.map(|id| id.to_string()))
+            .bind(now.to_rfc3339())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?
+            .rows_affected();
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(Some(ended))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::session_service::SessionService;
+    use uuid::Uuid;
+
+    fn update(user_id: Uuid, password_hash: &str) -> PasswordUpdate {
+        PasswordUpdate {
+            user_id,
+            password_hash: password_hash.to_string(),
+            keystore_entries: Vec::new(),
+            recovery_phrase: None,
+            recovery_code: None,
+            keep_session: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn a_spent_recovery_code_changes_nothing() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let sessions = SessionService::new(db.clone());
+        let here = sessions.start(&user, "here").await.unwrap();
+        sessions.start(&user, "elsewhere").await.unwrap();
+
+        let spent = PasswordUpdate {
+            recovery_code: Some(Uuid::new_v4()),
+            ..update(user, "new hash")
+        };
+        assert_eq!(db.apply_password_update(&spent, Utc::now()).await.unwrap(), None);
+        assert_eq!(db.get_user_by_id(&user).await.unwrap().unwrap().password_hash, "");
+
+        let change = PasswordUpdate {
+            keep_session: Some(here.id),
+            ..update(user, "new hash")
+        };
+        assert_eq!(db.apply_password_update(&change, Utc::now()).await.unwrap(), Some(1));
+        assert_eq!(db.get_user_by_id(&user).await.unwrap().unwrap().password_hash, "new hash");
+        assert!(sessions.validate(&here.id).await.is_ok());
+    }
+}
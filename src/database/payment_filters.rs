@@ -0,0 +1,52 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::payment_filter::PaymentFilterSettings;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn upsert_payment_filter_settings(&self, settings: &PaymentFilterSettings) -> Result<()> {
+        let query = r#"
+            INSERT INTO payment_filter_settings (user_id, dust_threshold_stroops, hide_scam_memos, updated_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (user_id) DO UPDATE SET
+                dust_threshold_stroops = excluded.dust_threshold_stroops,
+                hide_scam_memos = excluded.hide_scam_memos,
+                updated_at = excluded.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(settings.user_id.to_string())
+            .bind(settings.dust_threshold_stroops)
+            .bind(settings.hide_scam_memos)
+            .bind(settings.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save payment filter settings: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_payment_filter_settings(&self, user_id: &Uuid) -> Result<Option<PaymentFilterSettings>> {
+        let query = "SELECT * FROM payment_filter_settings WHERE user_id = ?1";
+
+        sqlx::query_as::<_, PaymentFilterSettings>(query)
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch payment filter settings: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for PaymentFilterSettings {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(PaymentFilterSettings {
+            user_id: rows::uuid(row, "user_id")?,
+            dust_threshold_stroops: row.try_get("dust_threshold_stroops")?,
+            hide_scam_memos: row.try_get("hide_scam_memos")?,
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,79 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::payment_note::PaymentNote;
+use crate::utils::crypto::EncryptedNote;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_payment_note(&self, note: &PaymentNote) -> Result<()> {
+        let query = r#"
+            INSERT INTO payment_notes (id, transaction_hash, sender_user_id, sender_public_key, recipient_user_id, recipient_public_key, ciphertext, nonce, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#;
+
+        sqlx::query(query)
+            .bind(note.id.to_string())
+            .bind(&note.transaction_hash)
+            .bind(note.sender_user_id.to_string())
+            .bind(&note.sender_public_key)
+            .bind(note.recipient_user_id.to_string())
+            .bind(&note.recipient_public_key)
+            .bind(&note.note.ciphertext)
+            .bind(&note.note.nonce)
+            .bind(note.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save payment note: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Notes the user sent or received, newest first.
+    pub async fn get_payment_notes_for_user(&self, user_id: &Uuid) -> Result<Vec<PaymentNote>> {
+        let query = r#"
+            SELECT * FROM payment_notes
+            WHERE sender_user_id = ?1 OR recipient_user_id = ?1
+            ORDER BY created_at DESC
+        "#;
+
+        sqlx::query_as::<_, PaymentNote>(query)
+            .bind(user_id.to_string())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch payment notes: {}", e)))
+    }
+
+    /// The user whose keystore holds the secret for `public_key`, if any.
+    pub async fn get_keystore_owner(&self, public_key: &str) -> Result<Option<Uuid>> {
+        let row = sqlx::query("SELECT user_id FROM keystore WHERE public_key = ?1")
+            .bind(public_key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up key owner: {}", e)))?;
+
+        row.map(|row| rows::uuid(&row, "user_id"))
+            .transpose()
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up key owner: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for PaymentNote {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(PaymentNote {
+            id: rows::uuid(row, "id")?,
+            transaction_hash: row.try_get("transaction_hash")?,
+            sender_user_id: rows::uuid(row, "sender_user_id")?,
+            sender_public_key: row.try_get("sender_public_key")?,
+            recipient_user_id: rows::uuid(row, "recipient_user_id")?,
+            recipient_public_key: row.try_get("recipient_public_key")?,
+            note: EncryptedNote {
+                ciphertext: row.try_get("ciphertext")?,
+                nonce: row.try_get("nonce")?,
+            },
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::policy::{AllowlistEntry, PolicyRule, TransactionPolicy};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+const KIND_MAX_AMOUNT: &str = "max_amount";
+const KIND_ALLOWLIST_ONLY: &str = "allowlist_only";
+
+impl SqliteDatabase {
+    pub async fn create_policy(&self, policy: &TransactionPolicy) -> Result<()> {
+        let query = r#"
+            INSERT INTO transaction_policies (id, user_id, kind, asset_code, max_amount_stroops, network, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#;
+
+        let (kind, asset_code, max_amount_stroops, network) = match &policy.rule {
+            PolicyRule::MaxAmount { asset_code, max_stroops } => (KIND_MAX_AMOUNT, Some(asset_code.clone()), Some(*max_stroops), None),
+            PolicyRule::AllowlistOnly { network } => (KIND_ALLOWLIST_ONLY, None, None, network.clone()),
+        };
+
+        sqlx::query(query)
+            .bind(policy.id.to_string())
+            .bind(policy.user_id.map(|id| id.to_string()))
+            .bind(kind)
+            .bind(asset_code)
+            .bind(max_amount_stroops)
+            .bind(network)
+            .bind(policy.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to create policy: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Policies that apply to `user_id`: their own plus global ones. Pass `None`
+    /// for global policies only.
+    pub async fn get_policies(&self, user_id: Option<&Uuid>) -> Result<Vec<TransactionPolicy>> {
+        let query = "SELECT * FROM transaction_policies WHERE user_id IS NULL OR user_id = ?1 ORDER BY created_at";
+
+        sqlx::query_as::<_, TransactionPolicy>(query)
+            .bind(user_id.map(|id| id.to_string()))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch policies: {}", e)))
+    }
+
+    /// Deletes a policy owned by `user_id` (`None` for a global policy).
+    pub async fn delete_policy(&self, user_id: Option<&Uuid>, policy_id: &Uuid) -> Result<bool> {
+        let query = "DELETE FROM transaction_policies WHERE id = ?1 AND user_id IS ?2";
+
+        let result = sqlx::query(query)
+            .bind(policy_id.to_string())
+            .bind(user_id.map(|id| id.to_string()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to delete policy: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn create_allowlist_entry(&self, entry: &AllowlistEntry) -> Result<()> {
+        let query = r#"
+            INSERT INTO policy_allowlist (id, user_id, address, label, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+        "#;
+
+        sqlx::query(query)
+            .bind(entry.id.to_string())
+            .bind(entry.user_id.map(|id| id.to_string()))
+            .bind(&entry.address)
+            .bind(&entry.label)
+            .bind(entry.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to add allowlist entry: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Allowlisted destinations for `user_id` plus global ones.
+    pub async fn get_allowlist(&self, user_id: Option<&Uuid>) -> Result<Vec<AllowlistEntry>> {
+        let query = "SELECT * FROM policy_allowlist WHERE user_id IS NULL OR user_id = ?1 ORDER BY created_at";
+
+        sqlx::query_as::<_, AllowlistEntry>(query)
+            .bind(user_id.map(|id| id.to_string()))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch allowlist: {}", e)))
+    }
+
+    pub async fn delete_allowlist_entry(&self, user_id: Option<&Uuid>, entry_id: &Uuid) -> Result<bool> {
+        let query = "DELETE FROM policy_allowlist WHERE id = ?1 AND user_id IS ?2";
+
+        let result = sqlx::query(query)
+            .bind(entry_id.to_string())
+            .bind(user_id.map(|id| id.to_string()))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to delete allowlist entry: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for TransactionPolicy {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let kind: String = row.try_get("kind")?;
+
+        let rule = match kind.as_str() {
+            KIND_MAX_AMOUNT => PolicyRule::MaxAmount {
+                asset_code: row.try_get("asset_code")?,
+                max_stroops: row.try_get("max_amount_stroops")?,
+            },
+            KIND_ALLOWLIST_ONLY => PolicyRule::AllowlistOnly {
+                network: row.try_get("network")?,
+            },
+            other => return Err(rows::decode_error("kind", format!("unknown policy kind '{}'", other))),
+        };
+
+        Ok(TransactionPolicy {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::optional_uuid(row, "user_id")?,
+            rule,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for AllowlistEntry {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(AllowlistEntry {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::optional_uuid(row, "user_id")?,
+            address: row.try_get("address")?,
+            label: row.try_get("label")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::report::{ReportAggregate, ReportFilter, ReportGroup, ReportQuery, ReportRow, ReportSource};
+use sqlx::Row;
+
+/// SQL for a [`ReportQuery`]. Only fixed fragments are interpolated; every
+/// user-supplied value is a bind parameter.
+#[derive(Debug)]
+struct CompiledReport {
+    sql: String,
+    binds: Vec<String>,
+}
+
+impl SqliteDatabase {
+    pub async fn run_report(&self, report: &ReportQuery) -> Result<Vec<ReportRow>> {
+        let compiled = compile(report)?;
+
+        let mut query = sqlx::query(&compiled.sql);
+        for bind in &compiled.binds {
+            query = query.bind(bind);
+        }
+
+        let rows = query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to run report: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReportRow {
+                group: row.get("grp"),
+                value: row.get("value"),
+            })
+            .collect())
+    }
+}
+
+fn compile(report: &ReportQuery) -> Result<CompiledReport> {
+    let source = report.source;
+    let table = source.as_str();
+
+    let group = match (report.group_by, source) {
+        (None, _) => "'all'",
+        (Some(ReportGroup::Day), _) => "substr(created_at, 1, 10)",
+        (Some(ReportGroup::Month), _) => "substr(created_at, 1, 7)",
+        (Some(ReportGroup::Status), ReportSource::Users) => "CASE WHEN is_verified THEN 'verified' ELSE 'unverified' END",
+        (Some(ReportGroup::Status), ReportSource::Transactions) => "status",
+        (Some(ReportGroup::Wallet), ReportSource::Users) => "CASE WHEN stellar_public_key IS NULL THEN 'no wallet' ELSE 'wallet linked' END",
+        (Some(ReportGroup::Asset), ReportSource::Transactions) => "asset_code",
+        (Some(group), source) => {
+            return Err(AppError::ValidationError(format!("Report syntax: {} can't be grouped by {}", source.as_str(), group.as_str())));
+        }
+    };
+
+    let aggregate = match report.aggregate {
+        ReportAggregate::Count => "COUNT(*)",
+    };
+
+    let mut conditions = Vec::new();
+    let mut binds = Vec::new();
+    let only_for = |name: &str, wanted: ReportSource| {
+        AppError::ValidationError(format!("Report syntax: '{}' only applies to {}", name, wanted.as_str()))
+    };
+
+    for filter in &report.filters {
+        match (filter, source) {
+            (ReportFilter::Verified(true), ReportSource::Users) => conditions.push("is_verified".to_string()),
+            (ReportFilter::Verified(false), ReportSource::Users) => conditions.push("NOT is_verified".to_string()),
+            (ReportFilter::WalletLinked(true), ReportSource::Users) => conditions.push("stellar_public_key IS NOT NULL".to_string()),
+            (ReportFilter::WalletLinked(false), ReportSource::Users) => conditions.push("stellar_public_key IS NULL".to_string()),
+            (ReportFilter::Verified(_) | ReportFilter::WalletLinked(_), _) => return Err(only_for("verified/linked", ReportSource::Users)),
+            (ReportFilter::Status(status), ReportSource::Transactions) => {
+                binds.push(status.as_str().to_string());
+                conditions.push(format!("status = ?{}", binds.len()));
+            }
+            (ReportFilter::Direction(direction), ReportSource::Transactions) => {
+                binds.push(direction.as_str().to_string());
+                conditions.push(format!("direction = ?{}", binds.len()));
+            }
+            (ReportFilter::Asset(code), ReportSource::Transactions) => {
+                binds.push(code.to_uppercase());
+                conditions.push(format!("asset_code = ?{}", binds.len()));
+            }
+            (ReportFilter::Status(_) | ReportFilter::Direction(_) | ReportFilter::Asset(_), _) => {
+                return Err(only_for("status/direction/asset", ReportSource::Transactions));
+            }
+            (ReportFilter::Since(date), _) => {
+                binds.push(date.format("%Y-%m-%d").to_string());
+                conditions.push(format!("created_at >= ?{}", binds.len()));
+            }
+            (ReportFilter::Until(date), _) => {
+                // Timestamps are RFC 3339, so anything on `date` sorts below the next day.
+                let next_day = date.succ_opt().unwrap_or(*date);
+                binds.push(next_day.format("%Y-%m-%d").to_string());
+                conditions.push(format!("created_at < ?{}", binds.len()));
+            }
+        }
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    Ok(CompiledReport {
+        sql: format!(
+            "SELECT {group} AS grp, {aggregate} AS value FROM {table}{where_clause} GROUP BY grp ORDER BY grp"
+        ),
+        binds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+    use chrono::NaiveDate;
+    use uuid::Uuid;
+
+    fn users(filters: Vec<ReportFilter>, group_by: Option<ReportGroup>) -> ReportQuery {
+        ReportQuery {
+            source: ReportSource::Users,
+            filters,
+            group_by,
+            aggregate: ReportAggregate::Count,
+        }
+    }
+
+    #[test]
+    fn compiles_an_ungrouped_count() {
+        let compiled = compile(&users(vec![], None)).unwrap();
+
+        assert_eq!(compiled.sql, "SELECT 'all' AS grp, COUNT(*) AS value FROM users GROUP BY grp ORDER BY grp");
+        assert!(compiled.binds.is_empty());
+    }
+
+    #[test]
+    fn dates_are_bound_not_interpolated() {
+        let since = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        let compiled = compile(&users(
+            vec![ReportFilter::Verified(true), ReportFilter::Since(since), ReportFilter::Until(until)],
+            Some(ReportGroup::Month),
+        ))
+        .unwrap();
+
+        assert!(compiled.sql.contains("WHERE is_verified AND created_at >= ?1 AND created_at < ?2"));
+        assert!(compiled.sql.starts_with("SELECT substr(created_at, 1, 7) AS grp"));
+        assert_eq!(compiled.binds, vec!["2025-01-01".to_string(), "2025-02-01".to_string()]);
+    }
+
+    fn transactions(filters: Vec<ReportFilter>, group_by: Option<ReportGroup>) -> ReportQuery {
+        ReportQuery {
+            source: ReportSource::Transactions,
+            ..users(filters, group_by)
+        }
+    }
+
+    fn payment(asset_code: &str, status: TransactionStatus, direction: TransactionDirection, day: u32) -> WalletTransaction {
+        let at = NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc();
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: "GACCOUNT".to_string(),
+            hash: Uuid::new_v4().simple().to_string(),
+            operation_index: 1,
+            direction,
+            asset_code: asset_code.to_string(),
+            amount_stroops: 10_000_000,
+            counterparty: "GCOUNTERPARTY".to_string(),
+            memo: None,
+            status,
+            ledger: None,
+            error: None,
+            request_id: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    fn rows(rows: &[(&str, i64)]) -> Vec<ReportRow> {
+        rows.iter().map(|(group, value)| ReportRow { group: group.to_string(), value: *value }).collect()
+    }
+
+    #[tokio::test]
+    async fn reports_on_transactions_by_date_asset_and_status() {
+        let db = SqliteDatabase::in_memory().await;
+        for transaction in [
+            payment("XLM", TransactionStatus::Confirmed, TransactionDirection::Outgoing, 1),
+            payment("XLM", TransactionStatus::Failed, TransactionDirection::Outgoing, 1),
+            payment("USDC", TransactionStatus::Confirmed, TransactionDirection::Incoming, 2),
+            payment("XLM", TransactionStatus::Pending, TransactionDirection::Outgoing, 3),
+        ] {
+            db.upsert_transaction(&transaction).await.unwrap();
+        }
+
+        let by_asset = db.run_report(&transactions(vec![], Some(ReportGroup::Asset))).await.unwrap();
+        assert_eq!(by_asset, rows(&[("USDC", 1), ("XLM", 3)]));
+
+        let by_status = db.run_report(&transactions(vec![ReportFilter::Asset("xlm".to_string())], Some(ReportGroup::Status))).await.unwrap();
+        assert_eq!(by_status, rows(&[("confirmed", 1), ("failed", 1), ("pending", 1)]));
+
+        let until = NaiveDate::from_ymd_opt(2025, 3, 2).unwrap();
+        let by_day = db
+            .run_report(&transactions(vec![ReportFilter::Status(TransactionStatus::Confirmed), ReportFilter::Until(until)], Some(ReportGroup::Day)))
+            .await
+            .unwrap();
+        assert_eq!(by_day, rows(&[("2025-03-01", 1), ("2025-03-02", 1)]));
+
+        let outgoing = db.run_report(&transactions(vec![ReportFilter::Direction(TransactionDirection::Outgoing)], None)).await.unwrap();
+        assert_eq!(outgoing, rows(&[("all", 3)]));
+    }
+
+    #[tokio::test]
+    async fn rejects_filters_and_groups_of_the_other_source() {
+        let db = SqliteDatabase::in_memory().await;
+
+        assert!(db.run_report(&transactions(vec![ReportFilter::Verified(true)], None)).await.is_err());
+        assert!(db.run_report(&transactions(vec![], Some(ReportGroup::Wallet))).await.is_err());
+        assert!(db.run_report(&users(vec![ReportFilter::Asset("XLM".to_string())], None)).await.is_err());
+        assert!(db.run_report(&users(vec![], Some(ReportGroup::Asset))).await.is_err());
+        assert_eq!(db.run_report(&users(vec![], None)).await.unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,62 @@
+//! Readers for the columns SQLite stores as TEXT: ids and RFC 3339
+//! timestamps. Used from `FromRow` impls so a malformed value fails that query
+//! with a decode error instead of panicking.
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::Row;
+use uuid::Uuid;
+
+pub(super) fn uuid(row: &SqliteRow, column: &str) -> sqlx::Result<Uuid> {
+    parse_uuid(column, &row.try_get::<String, _>(column)?)
+}
+
+pub(super) fn optional_uuid(row: &SqliteRow, column: &str) -> sqlx::Result<Option<Uuid>> {
+    row.try_get::<Option<String>, _>(column)?.map(|id| parse_uuid(column, &id)).transpose()
+}
+
+pub(super) fn timestamp(row: &SqliteRow, column: &str) -> sqlx::Result<DateTime<Utc>> {
+    parse_timestamp(column, &row.try_get::<String, _>(column)?)
+}
+
+pub(super) fn optional_timestamp(row: &SqliteRow, column: &str) -> sqlx::Result<Option<DateTime<Utc>>> {
+    row.try_get::<Option<String>, _>(column)?.map(|at| parse_timestamp(column, &at)).transpose()
+}
+
+fn parse_uuid(column: &str, value: &str) -> sqlx::Result<Uuid> {
+    Uuid::parse_str(value).map_err(|e| decode_error(column, e))
+}
+
+fn parse_timestamp(column: &str, value: &str) -> sqlx::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).map(|at| at.with_timezone(&Utc)).map_err(|e| decode_error(column, e))
+}
+
+/// A column whose value can't be turned into the field it backs.
+pub(super) fn decode_error(column: &str, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> sqlx::Error {
+    sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: source.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::errors::AppError;
+
+    #[tokio::test]
+    async fn malformed_rows_are_errors_not_panics() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        sqlx::query("UPDATE users SET created_at = 'yesterday' WHERE id = ?1")
+            .bind(user.to_string())
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        match db.get_user_by_id(&user).await {
+            Err(AppError::DatabaseError(message)) => assert!(message.contains("created_at"), "{}", message),
+            other => panic!("expected a database error, got {:?}", other.map(|user| user.map(|user| user.id))),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::database::api_keys::{join_scopes, split_scopes};
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::models::session::Session;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_session(&self, session: &Session) -> Result<()> {
+        let query = r#"
+            INSERT INTO sessions (id, user_id, device_label, created_at, expires_at, last_seen_at, revoked_at, scopes)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+
+        sqlx::query(query)
+            .bind(session.id.to_string())
+            .bind(session.user_id.to_string())
+            .bind(&session.device_label)
+            .bind(session.created_at.to_rfc3339())
+            .bind(session.expires_at.to_rfc3339())
+            .bind(session.last_seen_at.to_rfc3339())
+            .bind(session.revoked_at.map(|at| at.to_rfc3339()))
+            .bind(join_scopes(&session.scopes))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to create session: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_session(&self, session_id: &Uuid) -> Result<Option<Session>> {
+        let query = "SELECT * FROM sessions WHERE id = ?1";
+
+        sqlx::query_as::<_, Session>(query)
+            .bind(session_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch session: {}", e)))
+    }
+
+    /// Sessions that are neither revoked nor expired as of `now`, newest first.
+    pub async fn get_active_sessions(&self, user_id: &Uuid, now: DateTime<Utc>) -> Result<Vec<Session>> {
+        let query = r#"
+            SELECT * FROM sessions
+            WHERE user_id = ?1 AND revoked_at IS NULL AND expires_at > ?2
+            ORDER BY created_at DESC
+        "#;
+
+        sqlx::query_as::<_, Session>(query)
+            .bind(user_id.to_string())
+            .bind(now.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch sessions: {}", e)))
+    }
+
+    /// Sessions revoked after `since`, oldest revocation first.
+    pub async fn get_sessions_revoked_since(&self, since: DateTime<Utc>) -> Result<Vec<Session>> {
+        let query = "SELECT * FROM sessions WHERE revoked_at > ?1 ORDER BY revoked_at";
+
+        sqlx::query_as::<_, Session>(query)
+            .bind(since.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch sessions: {}", e)))
+    }
+
+    pub async fn touch_session(&self, session_id: &Uuid, now: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE sessions SET last_seen_at = ?2 WHERE id = ?1")
+            .bind(session_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update session: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Revokes one of `user_id`'s sessions. Returns whether an active one was found.
+    pub async fn revoke_session(&self, user_id: &Uuid, session_id: &Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let query = "UPDATE sessions SET revoked_at = ?3 WHERE id = ?1 AND user_id = ?2 AND revoked_at IS NULL";
+
+        let result = sqlx::query(query)
+            .bind(session_id.to_string())
+            .bind(user_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to revoke session: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revokes every active session of `user_id`. Returns how many there were.
+    pub async fn revoke_user_sessions(&self, user_id: &Uuid, now: DateTime<Utc>) -> Result<u64> {
+        let query = "UPDATE sessions SET revoked_at = ?2 WHERE user_id = ?1 AND revoked_at IS NULL";
+
+        let result = sqlx::query(query)
+            .bind(user_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to revoke sessions: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Revokes every active session of `user_id` except `keep`. Returns how many there were.
+    pub async fn revoke_other_sessions(&self, user_id: &Uuid, keep: &Uuid, now: DateTime<Utc>) -> Result<u64> {
+        let query = "UPDATE sessions SET revoked_at = ?3 WHERE user_id = ?1 AND id != ?2 AND revoked_at IS NULL";
+
+        let result = sqlx::query(query)
+            .bind(user_id.to_string())
+            .bind(keep.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to revoke sessions: {}", e)))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn create_refresh_token(&self, session_id: &Uuid, token_hash: &str, now: DateTime<Utc>) -> Result<()> {
+        sqlx::query("INSERT INTO refresh_tokens (token_hash, session_id, created_at) VALUES (?1, ?2, ?3)")
+            .bind(token_hash)
+            .bind(session_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to create refresh token: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// The session a refresh token was issued for, used or not.
+    pub async fn get_refresh_token_session(&self, token_hash: &str) -> Result<Option<Uuid>> {
+        let row = sqlx::query("SELECT session_id FROM refresh_tokens WHERE token_hash = ?1")
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch refresh token: {}", e)))?;
+
+        row.map(|row| rows::uuid(&row, "session_id"))
+            .transpose()
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch refresh token: {}", e)))
+    }
+
+    /// Marks a refresh token used. Returns false if it already was, so two
+    /// requests racing with the same token can't both succeed.
+    pub async fn mark_refresh_token_used(&self, token_hash: &str, now: DateTime<Utc>) -> Result<bool> {
+        let result = sqlx::query("UPDATE refresh_tokens SET used_at = ?2 WHERE token_hash = ?1 AND used_at IS NULL")
+            .bind(token_hash)
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update refresh token: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Session {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(Session {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            device_label: row.try_get("device_label")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            expires_at: rows::timestamp(row, "expires_at")?,
+            last_seen_at: rows::timestamp(row, "last_seen_at")?,
+            revoked_at: rows::optional_timestamp(row, "revoked_at")?,
+            scopes: match row.try_get::<Option<String>, _>("scopes")? {
+                Some(scopes) => split_scopes(&scopes)?,
+                None => ApiScope::ALL.to_vec(),
+            },
+        })
+    }
+}
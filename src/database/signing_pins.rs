@@ -0,0 +1,66 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::signing_pin::SigningPin;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    /// Saves a PIN and its failure count, replacing any earlier one for the user.
+    pub async fn upsert_signing_pin(&self, pin: &SigningPin) -> Result<()> {
+        let query = r#"
+            INSERT INTO signing_pins (user_id, pin_hash, failures, locked_until, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                pin_hash = excluded.pin_hash,
+                failures = excluded.failures,
+                locked_until = excluded.locked_until,
+                created_at = excluded.created_at
+        "#;
+
+        sqlx::query(query)
+            .bind(pin.user_id.to_string())
+            .bind(&pin.pin_hash)
+            .bind(pin.failures)
+            .bind(pin.locked_until.map(|until| until.to_rfc3339()))
+            .bind(pin.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save signing PIN: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_signing_pin(&self, user_id: &Uuid) -> Result<Option<SigningPin>> {
+        sqlx::query_as::<_, SigningPin>("SELECT * FROM signing_pins WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch signing PIN: {}", e)))
+    }
+
+    /// Returns whether the user had a PIN.
+    pub async fn delete_signing_pin(&self, user_id: &Uuid) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM signing_pins WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to remove signing PIN: {}", e)))?
+            .rows_affected();
+
+        Ok(deleted > 0)
+    }
+}
+
+impl FromRow<'_, SqliteRow> for SigningPin {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(SigningPin {
+            user_id: rows::uuid(row, "user_id")?,
+            pin_hash: row.try_get("pin_hash")?,
+            failures: row.try_get("failures")?,
+            locked_until: rows::optional_timestamp(row, "locked_until")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::sms::{SmsMessage, SmsPurpose, SmsUsage};
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn create_sms_message(&self, message: &SmsMessage) -> Result<()> {
+        let query = r#"
+            INSERT INTO sms_messages (id, user_id, country_code, purpose, provider, provider_message_id, cost_usd, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#;
+
+        sqlx::query(query)
+            .bind(message.id.to_string())
+            .bind(message.user_id.map(|id| id.to_string()))
+            .bind(&message.country_code)
+            .bind(message.purpose.as_str())
+            .bind(&message.provider)
+            .bind(&message.provider_message_id)
+            .bind(message.cost_usd)
+            .bind(message.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to record SMS: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Messages sent to numbers in `country_code` since `since`.
+    pub async fn count_sms_messages_since(&self, country_code: &str, since: DateTime<Utc>) -> Result<i64> {
+        let query = "SELECT COUNT(*) AS count FROM sms_messages WHERE country_code = ?1 AND created_at >= ?2";
+
+        let row = sqlx::query(query)
+            .bind(country_code)
+            .bind(since.to_rfc3339())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to count SMS messages: {}", e)))?;
+
+        Ok(row.get("count"))
+    }
+
+    /// Texts sent to `user_id` for `purpose` since `since`.
+    pub async fn count_user_sms_messages_since(&self, user_id: &Uuid, purpose: SmsPurpose, since: DateTime<Utc>) -> Result<i64> {
+        let query = "SELECT COUNT(*) AS count FROM sms_messages WHERE user_id = ?1 AND purpose = ?2 AND created_at >= ?3";
+
+        let row = sqlx::query(query)
+            .bind(user_id.to_string())
+            .bind(purpose.as_str())
+            .bind(since.to_rfc3339())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to count SMS messages: {}", e)))?;
+
+        Ok(row.get("count"))
+    }
+
+    pub async fn get_sms_usage_since(&self, since: DateTime<Utc>) -> Result<SmsUsage> {
+        let query = "SELECT COUNT(*) AS messages, COALESCE(SUM(cost_usd), 0.0) AS cost_usd FROM sms_messages WHERE created_at >= ?1";
+
+        let row = sqlx::query(query)
+            .bind(since.to_rfc3339())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to sum SMS costs: {}", e)))?;
+
+        Ok(SmsUsage {
+            messages: row.get("messages"),
+            cost_usd: row.get("cost_usd"),
+        })
+    }
+}
@@ -1,15 +1,94 @@
+use crate::cli::output;
+use crate::config;
+use crate::database::config::DatabaseConfig;
+use crate::database::encryption::{not_built_with_sqlcipher, quote_key, KeySource};
+use crate::database::rows;
 use crate::errors::{AppError, Result};
+use crate::models::role::Role;
 use crate::models::user::User;
-use sqlx::{SqlitePool, Row};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
+use sqlx::{FromRow, SqlitePool, Row};
 use uuid::Uuid;
+use std::env;
 use std::path::Path;
+use std::str::FromStr;
 
+/// Database path for a private in-memory database that is gone when the process exits.
+pub const MEMORY_PATH: &str = ":memory:";
+
+#[derive(Clone)]
 pub struct SqliteDatabase {
-    pool: SqlitePool,
+    pub(super) pool: SqlitePool,
 }
 
 impl SqliteDatabase {
+    /// Opens the database at [`SqliteDatabase::default_path`].
+    pub async fn open_default() -> Result<Self> {
+        Self::new(&Self::default_path()?).await
+    }
+
+    /// An empty, fully migrated database that never touches the filesystem,
+    /// for demos and integration tests.
+    pub async fn ephemeral() -> Result<Self> {
+        Self::new(MEMORY_PATH).await
+    }
+
+    /// `DATABASE_PATH` if set (`:memory:` or `sqlite::memory:` for an in-memory
+    /// database), otherwise `stellar_wallet.db` in the current working directory.
+    pub fn default_path() -> Result<String> {
+        if let Some(path) = config::var("DATABASE_PATH") {
+            let path = if is_memory_path(&path) { MEMORY_PATH.to_string() } else { path };
+            output::status(&format!("📂 Database path: {}", path));
+            return Ok(path);
+        }
+
+        let current_dir = env::current_dir()
+            .map_err(|e| AppError::DatabaseError(format!("Failed to get current directory: {}", e)))?;
+        
+        let db_path = current_dir.join("stellar_wallet.db");
+        let db_path_str = db_path.to_string_lossy().to_string();
+        
+        output::status(&format!("📂 Database path: {}", db_path_str));
+        
+        Ok(db_path_str)
+    }
+
+    /// Connects and brings the schema up to date.
     pub async fn new(database_path: &str) -> Result<Self> {
+        let db = Self::connect(database_path).await?;
+        db.run_migrations().await?;
+        
+        output::status(&format!("✅ Connected to SQLite database: {}", database_path));
+        Ok(db)
+    }
+
+    /// Connects without running migrations, creating the file if needed.
+    /// Encrypted databases are opened with the key from [`KeySource::from_env`].
+    pub async fn connect(database_path: &str) -> Result<Self> {
+        let key = KeySource::from_env()?.key()?;
+        Self::connect_with_key(database_path, key.as_deref()).await
+    }
+
+    /// Connects to a plaintext database, or an SQLCipher one if `key` is given.
+    pub async fn connect_with_key(database_path: &str, key: Option<&str>) -> Result<Self> {
+        if key.is_some() && !cfg!(feature = "sqlcipher") {
+            return Err(not_built_with_sqlcipher());
+        }
+
+        if is_memory_path(database_path) {
+            // Every in-memory connection is its own database, so keep exactly one
+            // and never let the pool retire it.
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect("sqlite::memory:")
+                .await
+                .map_err(|e| AppError::DatabaseError(format!("Failed to open in-memory database: {}", e)))?;
+
+            return Ok(Self { pool });
+        }
+
         // Ensure the directory exists
         if let Some(parent) = Path::new(database_path).parent() {
             std::fs::create_dir_all(parent)
@@ -20,65 +99,179 @@ impl SqliteDatabase {
         if !Path::new(database_path).exists() {
             std::fs::File::create(database_path)
                 .map_err(|e| AppError::DatabaseError(format!("Failed to create database file: {}", e)))?;
-            println!("📁 Created new database file: {}", database_path);
+            output::status(&format!("📁 Created new database file: {}", database_path));
         }
 
+        Self::connect_file(database_path, key, false).await
+    }
+
+    /// Connects without writing anything, for commands that only report on
+    /// the database. `None` if the file doesn't exist; it isn't created.
+    pub async fn connect_read_only(database_path: &str) -> Result<Option<Self>> {
+        let key = KeySource::from_env()?.key()?;
+        if key.is_some() && !cfg!(feature = "sqlcipher") {
+            return Err(not_built_with_sqlcipher());
+        }
+        if is_memory_path(database_path) {
+            return Self::connect_with_key(database_path, key.as_deref()).await.map(Some);
+        }
+        if !Path::new(database_path).exists() {
+            return Ok(None);
+        }
+
+        Self::connect_file(database_path, key.as_deref(), true).await.map(Some)
+    }
+
+    async fn connect_file(database_path: &str, key: Option<&str>, read_only: bool) -> Result<Self> {
         let database_url = format!("sqlite:{}", database_path);
-        
-        let pool = SqlitePool::connect(&database_url)
+        let config = DatabaseConfig::from_env()?;
+        let mut options = SqliteConnectOptions::from_str(&database_url)
+            .map_err(|e| AppError::DatabaseError(format!("Invalid database path: {}", e)))?
+            .busy_timeout(config.busy_timeout)
+            .synchronous(config.synchronous);
+        // Switching the journal mode writes to the file.
+        options = if read_only { options.read_only(true) } else { options.journal_mode(config.journal_mode) };
+        if let Some(key) = key {
+            // sqlx issues `key` before any other pragma, as SQLCipher requires.
+            options = options.pragma("key", quote_key(key));
+        }
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .connect_with(options)
             .await
             .map_err(|e| AppError::DatabaseError(format!("Failed to connect to database: {}", e)))?;
 
-        let db = Self { pool };
-        
-        // Create tables if they don't exist
-        db.create_tables().await?;
-        
-        println!("✅ Connected to SQLite database: {}", database_path);
-        Ok(db)
-    }
+        // A wrong key (or a key for a plaintext file) only shows up on the first read.
+        sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| match key {
+                Some(_) => AppError::DatabaseError(format!(
+                    "Can't read the database with this key; check DATABASE_KEY or run `stellar-wallet db encrypt` ({})",
+                    e
+                )),
+                None => AppError::DatabaseError(format!("Can't read the database; is it encrypted? ({})", e)),
+            })?;
 
-    async fn create_tables(&self) -> Result<()> {
-        let query = r#"
-            CREATE TABLE IF NOT EXISTS users (
-                id TEXT PRIMARY KEY,
-                email TEXT UNIQUE NOT NULL,
-                username TEXT UNIQUE NOT NULL,
-                password_hash TEXT NOT NULL,
-                is_verified BOOLEAN DEFAULT FALSE,
-                stellar_public_key TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
-            CREATE INDEX IF NOT EXISTS idx_users_username ON users(username);
-        "#;
+        Ok(Self { pool })
+    }
 
-        sqlx::query(query)
+    /// Runs a trivial query, to check the database can be reached and read.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
             .execute(&self.pool)
             .await
-            .map_err(|e| AppError::DatabaseError(format!("Failed to create tables: {}", e)))?;
-
-        println!("📋 Database tables created/verified");
+            .map_err(|e| AppError::DatabaseError(format!("Failed to reach the database: {}", e)))?;
         Ok(())
     }
 
+    /// A private, fully migrated in-memory database for tests.
+    #[cfg(test)]
+    pub async fn in_memory() -> Self {
+        Self::ephemeral().await.unwrap()
+    }
+
+    /// Inserts a throwaway user so rows with a `user_id` foreign key can be saved.
+    #[cfg(test)]
+    pub async fn insert_test_user(&self) -> Uuid {
+        let id = Uuid::new_v4();
+        let now = chrono::Utc::now();
+
+        self.create_user(&User {
+            id,
+            email: format!("{}@example.com", id.simple()),
+            username: format!("user_{}", &id.simple().to_string()[..8]),
+            password_hash: String::new(),
+            is_verified: false,
+            stellar_public_key: None,
+            last_login_at: None,
+            login_count: 0,
+            role: Role::User,
+            disabled_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .await
+        .unwrap();
+
+        id
+    }
+
     pub async fn create_user(&self, user: &User) -> Result<()> {
+        insert_user(&self.pool, user).await?;
+
+        output::status(&format!("💾 User '{}' saved to database", user.username));
+        Ok(())
+    }
+
+    /// Inserts `users` in one transaction, with a result for each. A taken
+    /// email or username only fails that user, including one taken earlier
+    /// in the same batch; any other error rolls the whole batch back.
+    pub async fn create_users(&self, users: &[User]) -> Result<Vec<Result<()>>> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to create users: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        let mut results = Vec::with_capacity(users.len());
+        for user in users {
+            match insert_user(&mut *tx, user).await {
+                Err(AppError::DatabaseError(e)) => return Err(AppError::DatabaseError(e)),
+                result => results.push(result),
+            }
+        }
+        tx.commit().await.map_err(map_err)?;
+
+        let saved = results.iter().filter(|result| result.is_ok()).count();
+        output::status(&format!("💾 {} of {} users saved to database", saved, users.len()));
+        Ok(results)
+    }
+
+    pub async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>> {
+        let query = "SELECT * FROM users WHERE id = ?1";
+
+        sqlx::query_as::<_, User>(query)
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch user: {}", e)))
+    }
+
+    pub async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        let query = "SELECT * FROM users WHERE email = ?1";
+
+        sqlx::query_as::<_, User>(query)
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch user by email: {}", e)))
+    }
+
+    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        let query = "SELECT * FROM users WHERE username = ?1";
+
+        sqlx::query_as::<_, User>(query)
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch user by username: {}", e)))
+    }
+
+    /// Saves a changed email, username, verification flag or linked address.
+    pub async fn update_user(&self, user: &User) -> Result<()> {
         let query = r#"
-            INSERT INTO users (id, email, username, password_hash, is_verified, stellar_public_key, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            UPDATE users SET email = ?1, username = ?2, is_verified = ?3, stellar_public_key = ?4, updated_at = ?5,
+                             verified_at = CASE WHEN ?3 AND NOT is_verified THEN ?5 ELSE verified_at END
+            WHERE id = ?6
         "#;
 
         sqlx::query(query)
-            .bind(user.id.to_string())
             .bind(&user.email)
             .bind(&user.username)
-            .bind(&user.password_hash)
             .bind(user.is_verified)
             .bind(&user.stellar_public_key)
-            .bind(user.created_at.to_rfc3339())
             .bind(user.updated_at.to_rfc3339())
+            .bind(user.id.to_string())
             .execute(&self.pool)
             .await
             .map_err(|e| {
@@ -88,65 +281,129 @@ impl SqliteDatabase {
                     } else if e.to_string().contains("username") {
                         AppError::ValidationError("Username already exists".to_string())
                     } else {
-                        AppError::ValidationError("User already exists".to_string())
+                        AppError::ValidationError("This Stellar account is already linked to another user".to_string())
                     }
                 } else {
-                    AppError::DatabaseError(format!("Failed to create user: {}", e))
+                    AppError::DatabaseError(format!("Failed to update user: {}", e))
                 }
             })?;
 
-        println!("💾 User '{}' saved to database", user.username);
         Ok(())
     }
 
-    pub async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
-        let query = "SELECT * FROM users WHERE email = ?1";
+    /// Deletes a user and everything stored for them, in one transaction: keys,
+    /// contacts, settings, sessions, event stream tickets, login history, API
+    /// keys, linked OAuth accounts, passkeys, KYC fields, 2FA secrets, signing
+    /// PINs, recovery codes, pending email changes, webhooks and their
+    /// deliveries, idempotency keys, payment notes, queued payments and the
+    /// history, monthly summaries and cached balances of their addresses.
+    /// Audit entries are append-only and stay, and SMS cost records are kept
+    /// without the user id. Returns whether the user existed.
+    pub async fn delete_user(&self, user_id: &Uuid) -> Result<bool> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to delete user: {}", e));
+        let id = user_id.to_string();
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
 
-        let row = sqlx::query(query)
-            .bind(email)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch user by email: {}", e)))?;
-
-        if let Some(row) = row {
-            Ok(Some(User {
-                id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
-                email: row.get("email"),
-                username: row.get("username"),
-                password_hash: row.get("password_hash"),
-                is_verified: row.get("is_verified"),
-                stellar_public_key: row.get("stellar_public_key"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at")).unwrap().with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at")).unwrap().with_timezone(&chrono::Utc),
-            }))
-        } else {
-            Ok(None)
+        // Addresses that only this user can hold; Ledger accounts may be shared.
+        let addresses = r#"
+            SELECT stellar_public_key FROM users WHERE id = ?1 AND stellar_public_key IS NOT NULL
+            UNION SELECT public_key FROM keystore WHERE user_id = ?1
+            UNION SELECT public_key FROM derived_accounts WHERE user_id = ?1
+        "#;
+        let statements = [
+            format!("DELETE FROM transaction_tags WHERE account IN ({})", addresses),
+            format!("DELETE FROM transactions WHERE account IN ({})", addresses),
+            format!("DELETE FROM balance_snapshots WHERE account IN ({})", addresses),
+            format!("DELETE FROM transaction_rollups WHERE account IN ({})", addresses),
+            format!("DELETE FROM transaction_archive_cutoffs WHERE account IN ({})", addresses),
+            "DELETE FROM payment_notes WHERE sender_user_id = ?1 OR recipient_user_id = ?1".to_string(),
+            "UPDATE sms_messages SET user_id = NULL WHERE user_id = ?1".to_string(),
+            "DELETE FROM refresh_tokens WHERE session_id IN (SELECT id FROM sessions WHERE user_id = ?1)".to_string(),
+            "DELETE FROM sessions WHERE user_id = ?1".to_string(),
+            "DELETE FROM login_throttles WHERE key = 'account:' || ?1".to_string(),
+            "DELETE FROM login_attempts WHERE user_id = ?1".to_string(),
+            "DELETE FROM api_keys WHERE user_id = ?1".to_string(),
+            "DELETE FROM oauth_identities WHERE user_id = ?1".to_string(),
+            "DELETE FROM passkeys WHERE user_id = ?1".to_string(),
+            "DELETE FROM webauthn_challenges WHERE user_id = ?1".to_string(),
+            "DELETE FROM stream_tickets WHERE user_id = ?1".to_string(),
+            "DELETE FROM queued_payments WHERE user_id = ?1".to_string(),
+            "DELETE FROM customer_fields WHERE user_id = ?1".to_string(),
+            "DELETE FROM recovery_codes WHERE user_id = ?1".to_string(),
+            "DELETE FROM totp_secrets WHERE user_id = ?1".to_string(),
+            "DELETE FROM signing_pins WHERE user_id = ?1".to_string(),
+            "DELETE FROM email_changes WHERE user_id = ?1".to_string(),
+            "DELETE FROM policy_allowlist WHERE user_id = ?1".to_string(),
+            "DELETE FROM transaction_policies WHERE user_id = ?1".to_string(),
+            "DELETE FROM payment_filter_settings WHERE user_id = ?1".to_string(),
+            "DELETE FROM user_settings WHERE user_id = ?1".to_string(),
+            "DELETE FROM ledger_accounts WHERE user_id = ?1".to_string(),
+            "DELETE FROM derived_accounts WHERE user_id = ?1".to_string(),
+            "DELETE FROM phrase_recovery_codes WHERE user_id = ?1".to_string(),
+            "DELETE FROM recovery_phrases WHERE user_id = ?1".to_string(),
+            "DELETE FROM contacts WHERE user_id = ?1".to_string(),
+            "DELETE FROM webhook_deliveries WHERE user_id = ?1 OR endpoint_id IN (SELECT id FROM webhook_endpoints WHERE user_id = ?1)"
+                .to_string(),
+            "DELETE FROM webhook_endpoints WHERE user_id = ?1".to_string(),
+            "DELETE FROM idempotency_keys WHERE user_id = ?1".to_string(),
+            "DELETE FROM keystore WHERE user_id = ?1".to_string(),
+        ];
+        for statement in &statements {
+            sqlx::query(statement).bind(&id).execute(&mut *tx).await.map_err(map_err)?;
         }
+
+        let deleted = sqlx::query("DELETE FROM users WHERE id = ?1")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?
+            .rows_affected();
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(deleted > 0)
     }
 
-    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
-        let query = "SELECT * FROM users WHERE username = ?1";
+    pub async fn get_user_by_stellar_public_key(&self, public_key: &str) -> Result<Option<User>> {
+        let query = "SELECT * FROM users WHERE stellar_public_key = ?1";
 
-        let row = sqlx::query(query)
-            .bind(username)
+        sqlx::query_as::<_, User>(query)
+            .bind(public_key)
             .fetch_optional(&self.pool)
             .await
-            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch user by username: {}", e)))?;
-
-        if let Some(row) = row {
-            Ok(Some(User {
-                id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
-                email: row.get("email"),
-                username: row.get("username"),
-                password_hash: row.get("password_hash"),
-                is_verified: row.get("is_verified"),
-                stellar_public_key: row.get("stellar_public_key"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at")).unwrap().with_timezone(&chrono::Utc),
-                updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at")).unwrap().with_timezone(&chrono::Utc),
-            }))
-        } else {
-            Ok(None)
-        }
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch user by Stellar public key: {}", e)))
+    }
+
+    pub async fn update_user_stellar_public_key(&self, user_id: &Uuid, public_key: &str) -> Result<()> {
+        let query = "UPDATE users SET stellar_public_key = ?1, updated_at = ?2 WHERE id = ?3";
+
+        sqlx::query(query)
+            .bind(public_key)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    AppError::ValidationError("This Stellar account is already linked to another user".to_string())
+                } else {
+                    AppError::DatabaseError(format!("Failed to update Stellar public key: {}", e))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    pub async fn record_user_login(&self, user_id: &Uuid, at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        let query = "UPDATE users SET last_login_at = ?1, login_count = login_count + 1 WHERE id = ?2";
+
+        sqlx::query(query)
+            .bind(at.to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to record login: {}", e)))?;
+
+        Ok(())
     }
 
     pub async fn get_user_count(&self) -> Result<i64> {
@@ -159,4 +416,227 @@ impl SqliteDatabase {
 
         Ok(row.get("count"))
     }
+
+    /// Replaces the stored hash, e.g. with one made with current settings.
+    pub async fn update_user_password_hash(&self, user_id: &Uuid, password_hash: &str) -> Result<()> {
+        sqlx::query("UPDATE users SET password_hash = ?1 WHERE id = ?2")
+            .bind(password_hash)
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update password: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Every user, by username, for the admin user list.
+    pub async fn get_users(&self) -> Result<Vec<User>> {
+        sqlx::query_as::<_, User>("SELECT * FROM users ORDER BY username")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch users: {}", e)))
+    }
+
+    pub async fn update_user_role(&self, user_id: &Uuid, role: Role, at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        sqlx::query("UPDATE users SET role = ?1, updated_at = ?2 WHERE id = ?3")
+            .bind(role.as_str())
+            .bind(at.to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update role: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Disables the account as of `disabled_at`, or enables it again with `None`.
+    pub async fn update_user_disabled(&self, user_id: &Uuid, disabled_at: Option<chrono::DateTime<chrono::Utc>>, at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        sqlx::query("UPDATE users SET disabled_at = ?1, updated_at = ?2 WHERE id = ?3")
+            .bind(disabled_at.map(|disabled_at| disabled_at.to_rfc3339()))
+            .bind(at.to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update account status: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Users who signed up after `since`, oldest first.
+    pub async fn get_users_created_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<User>> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE created_at > ?1 ORDER BY created_at")
+            .bind(since.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch new users: {}", e)))
+    }
+
+    /// Users whose email address was verified after `since`, with when,
+    /// oldest first.
+    pub async fn get_users_verified_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<(Uuid, chrono::DateTime<chrono::Utc>)>> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to fetch verified users: {}", e));
+        let rows = sqlx::query("SELECT id, verified_at FROM users WHERE verified_at > ?1 ORDER BY verified_at")
+            .bind(since.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(map_err)?;
+
+        rows.iter()
+            .map(|row| Ok((rows::uuid(row, "id")?, rows::timestamp(row, "verified_at")?)))
+            .collect::<sqlx::Result<_>>()
+            .map_err(map_err)
+    }
+
+    /// Users disabled after `since`, with when, oldest first.
+    pub async fn get_users_disabled_since(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<(Uuid, chrono::DateTime<chrono::Utc>)>> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to fetch disabled users: {}", e));
+        let rows = sqlx::query("SELECT id, disabled_at FROM users WHERE disabled_at > ?1 ORDER BY disabled_at")
+            .bind(since.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(map_err)?;
+
+        rows.iter()
+            .map(|row| Ok((rows::uuid(row, "id")?, rows::timestamp(row, "disabled_at")?)))
+            .collect::<sqlx::Result<_>>()
+            .map_err(map_err)
+    }
+
+    pub async fn get_users_with_stellar_public_key(&self) -> Result<Vec<User>> {
+        let query = "SELECT * FROM users WHERE stellar_public_key IS NOT NULL ORDER BY username";
+
+        sqlx::query_as::<_, User>(query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch users with wallets: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for User {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let role: String = row.try_get("role")?;
+
+        Ok(User {
+            id: rows::uuid(row, "id")?,
+            email: row.try_get("email")?,
+            username: row.try_get("username")?,
+            password_hash: row.try_get("password_hash")?,
+            is_verified: row.try_get("is_verified")?,
+            stellar_public_key: row.try_get("stellar_public_key")?,
+            last_login_at: rows::optional_timestamp(row, "last_login_at")?,
+            login_count: row.try_get("login_count")?,
+            role: Role::parse(&role).ok_or_else(|| rows::decode_error("role", format!("unknown role '{}'", role)))?,
+            disabled_at: rows::optional_timestamp(row, "disabled_at")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+/// Inserts `user` with `executor`, so it can run inside a transaction.
+async fn insert_user<'e>(executor: impl sqlx::Executor<'e, Database = sqlx::Sqlite>, user: &User) -> Result<()> {
+    let query = r#"
+        INSERT INTO users (id, email, username, password_hash, is_verified, stellar_public_key, role, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+    "#;
+
+    sqlx::query(query)
+        .bind(user.id.to_string())
+        .bind(&user.email)
+        .bind(&user.username)
+        .bind(&user.password_hash)
+        .bind(user.is_verified)
+        .bind(&user.stellar_public_key)
+        .bind(user.role.as_str())
+        .bind(user.created_at.to_rfc3339())
+        .bind(user.updated_at.to_rfc3339())
+        .execute(executor)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("UNIQUE constraint failed") {
+                if e.to_string().contains("email") {
+                    AppError::ValidationError("Email already exists".to_string())
+                } else if e.to_string().contains("username") {
+                    AppError::ValidationError("Username already exists".to_string())
+                } else {
+                    AppError::ValidationError("User already exists".to_string())
+                }
+            } else {
+                AppError::DatabaseError(format!("Failed to create user: {}", e))
+            }
+        })?;
+
+    Ok(())
+}
+
+fn is_memory_path(path: &str) -> bool {
+    matches!(path, MEMORY_PATH | "sqlite::memory:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_stellar_key_belongs_to_at_most_one_user() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let bob = db.insert_test_user().await;
+
+        db.update_user_stellar_public_key(&alice, "GALICE").await.unwrap();
+        assert_eq!(db.get_user_by_stellar_public_key("GALICE").await.unwrap().unwrap().id, alice);
+        assert!(db.get_user_by_stellar_public_key("GBOB").await.unwrap().is_none());
+
+        let err = db.update_user_stellar_public_key(&bob, "GALICE").await.unwrap_err();
+        assert!(matches!(err, AppError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn deleting_a_user_removes_their_rows_only() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let bob = db.insert_test_user().await;
+
+        for (user, address) in [(alice, "GALICE"), (bob, "GBOB")] {
+            db.update_user_stellar_public_key(&user, address).await.unwrap();
+            sqlx::query("INSERT INTO contacts (id, user_id, name, address, created_at, updated_at) VALUES (?1, ?2, 'x', 'G', '', '')")
+                .bind(Uuid::new_v4().to_string())
+                .bind(user.to_string())
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO transactions (id, account, hash, operation_index, direction, asset_code, amount_stroops, counterparty, status, created_at, updated_at) \
+                 VALUES (?1, ?2, 'h', 1, 'incoming', 'XLM', 1, 'G', 'confirmed', '', '')",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(address)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        assert!(db.delete_user(&alice).await.unwrap());
+        assert!(!db.delete_user(&alice).await.unwrap());
+        assert!(db.get_user_by_id(&alice).await.unwrap().is_none());
+        assert!(db.get_user_by_id(&bob).await.unwrap().is_some());
+
+        let count = |sql: &'static str| async { sqlx::query_scalar::<_, i64>(sql).fetch_one(&db.pool).await.unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM contacts").await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM transactions WHERE account = 'GBOB'").await, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM transactions").await, 1);
+    }
+
+    #[tokio::test]
+    async fn read_only_connections_never_create_or_change_the_file() {
+        let path = std::env::temp_dir().join(format!("wallet-read-only-{}.db", Uuid::new_v4())).to_string_lossy().into_owned();
+        assert!(SqliteDatabase::connect_read_only(&path).await.unwrap().is_none());
+        assert!(!Path::new(&path).exists());
+
+        SqliteDatabase::connect_with_key(&path, None).await.unwrap().run_migrations().await.unwrap();
+        let db = SqliteDatabase::connect_read_only(&path).await.unwrap().unwrap();
+        assert!(db.migration_status().await.unwrap().iter().all(|migration| migration.is_applied()));
+        assert!(sqlx::query("CREATE TABLE scratch (x)").execute(&db.pool).await.is_err());
+        let _ = std::fs::remove_file(&path);
+    }
 }
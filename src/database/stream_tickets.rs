@@ -0,0 +1,85 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::stream_ticket::StreamTicket;
+use chrono::{DateTime, Utc};
+
+impl SqliteDatabase {
+    /// Remembers a ticket, dropping the ones that expired unused.
+    pub async fn create_stream_ticket(&self, ticket_hash: &str, ticket: &StreamTicket, expires_at: DateTime<Utc>, now: DateTime<Utc>) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to save stream ticket: {}", e));
+
+        sqlx::query("DELETE FROM stream_tickets WHERE expires_at < ?1")
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+        let query = r#"
+            INSERT INTO stream_tickets (ticket_hash, user_id, session_id, api_key_id, stream_expires_at, expires_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#;
+        sqlx::query(query)
+            .bind(ticket_hash)
+            .bind(ticket.user_id.to_string())
+            .bind(ticket.session_id.map(|id| id.to_string()))
+            .bind(ticket.api_key_id.map(|id| id.to_string()))
+            .bind(ticket.stream_expires_at.map(|at| at.to_rfc3339()))
+            .bind(expires_at.to_rfc3339())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(map_err)?;
+
+        Ok(())
+    }
+
+    /// Deletes the ticket and returns it, if it hasn't expired. A second call
+    /// for the same ticket finds nothing.
+    pub async fn take_stream_ticket(&self, ticket_hash: &str, now: DateTime<Utc>) -> Result<Option<StreamTicket>> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to use stream ticket: {}", e));
+        let row = sqlx::query("DELETE FROM stream_tickets WHERE ticket_hash = ?1 RETURNING user_id, session_id, api_key_id, stream_expires_at, expires_at")
+            .bind(ticket_hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(map_err)?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        if rows::timestamp(&row, "expires_at").map_err(map_err)? <= now {
+            return Ok(None);
+        }
+        Ok(Some(StreamTicket {
+            user_id: rows::uuid(&row, "user_id").map_err(map_err)?,
+            session_id: rows::optional_uuid(&row, "session_id").map_err(map_err)?,
+            api_key_id: rows::optional_uuid(&row, "api_key_id").map_err(map_err)?,
+            stream_expires_at: rows::optional_timestamp(&row, "stream_expires_at").map_err(map_err)?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn tickets_work_once_until_they_expire() {
+        let db = SqliteDatabase::in_memory().await;
+        let user_id = db.insert_test_user().await;
+        let now = Utc::now();
+        let ticket = StreamTicket {
+            user_id,
+            session_id: None,
+            api_key_id: Some(Uuid::new_v4()),
+            stream_expires_at: Some(now + Duration::minutes(15)),
+        };
+
+        db.create_stream_ticket("a", &ticket, now + Duration::seconds(30), now).await.unwrap();
+        db.create_stream_ticket("b", &ticket, now + Duration::seconds(30), now).await.unwrap();
+        assert_eq!(db.take_stream_ticket("a", now).await.unwrap(), Some(ticket));
+        assert_eq!(db.take_stream_ticket("a", now).await.unwrap(), None);
+        assert_eq!(db.take_stream_ticket("b", now + Duration::minutes(1)).await.unwrap(), None);
+    }
+}
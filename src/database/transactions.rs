@@ -0,0 +1,487 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::transaction::{TransactionDirection, TransactionSearch, TransactionStatus, WalletTransaction};
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A bind parameter for a compiled search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SearchBind {
+    Text(String),
+    Integer(i64),
+}
+
+impl SqliteDatabase {
+    /// Inserts a transaction, or updates the status of one already recorded for
+    /// the same account and operation. The original memo and timestamps are kept.
+    pub async fn upsert_transaction(&self, tx: &WalletTransaction) -> Result<()> {
+        let query = r#"
+            INSERT INTO transactions (id, account, hash, operation_index, direction, asset_code, amount_stroops,
+                                      counterparty, memo, status, ledger, error, request_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+            ON CONFLICT (account, hash, operation_index) DO UPDATE SET
+                status = excluded.status,
+                ledger = COALESCE(excluded.ledger, transactions.ledger),
+                error = excluded.error,
+                memo = COALESCE(transactions.memo, excluded.memo),
+                request_id = COALESCE(transactions.request_id, excluded.request_id),
+                updated_at = excluded.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(tx.id.to_string())
+            .bind(&tx.account)
+            .bind(&tx.hash)
+            .bind(tx.operation_index)
+            .bind(tx.direction.as_str())
+            .bind(&tx.asset_code)
+            .bind(tx.amount_stroops)
+            .bind(&tx.counterparty)
+            .bind(&tx.memo)
+            .bind(tx.status.as_str())
+            .bind(tx.ledger)
+            .bind(&tx.error)
+            .bind(&tx.request_id)
+            .bind(tx.created_at.to_rfc3339())
+            .bind(tx.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save transaction: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn update_transaction_status(
+        &self,
+        account: &str,
+        hash: &str,
+        status: TransactionStatus,
+        ledger: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let query = r#"
+            UPDATE transactions SET status = ?3, ledger = ?4, error = ?5, updated_at = ?6
+            WHERE account = ?1 AND hash = ?2
+        "#;
+
+        sqlx::query(query)
+            .bind(account)
+            .bind(hash)
+            .bind(status.as_str())
+            .bind(ledger)
+            .bind(error)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update transaction: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Transactions that failed after `since`, oldest first, with the user
+    /// whose wallet sent each.
+    pub async fn get_transactions_failed_since(&self, since: DateTime<Utc>) -> Result<Vec<(Uuid, WalletTransaction)>> {
+        let query = r#"
+            SELECT transactions.*, wallets.user_id AS owner_id FROM transactions
+            JOIN (
+                SELECT user_id, public_key FROM keystore WHERE signs_for IS NULL
+                UNION
+                SELECT id AS user_id, stellar_public_key AS public_key FROM users WHERE stellar_public_key IS NOT NULL
+            ) AS wallets ON wallets.public_key = transactions.account
+            WHERE transactions.status = 'failed' AND transactions.updated_at > ?1
+            ORDER BY transactions.updated_at
+        "#;
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to fetch failed transactions: {}", e));
+
+        let rows = sqlx::query(query).bind(since.to_rfc3339()).fetch_all(&self.pool).await.map_err(map_err)?;
+        rows.iter()
+            .map(|row| Ok((rows::uuid(row, "owner_id")?, WalletTransaction::from_row(row)?)))
+            .collect::<sqlx::Result<_>>()
+            .map_err(map_err)
+    }
+
+    /// Up to `limit` transactions inserted or changed after change number
+    /// `after`, in the order they changed, each with its change number.
+    pub async fn get_transactions_changed_after(&self, after: i64, limit: u32) -> Result<Vec<(i64, WalletTransaction)>> {
+        let query = "SELECT * FROM transactions WHERE change_seq > ?1 ORDER BY change_seq LIMIT ?2";
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to fetch changed transactions: {}", e));
+
+        let rows = sqlx::query(query).bind(after).bind(limit).fetch_all(&self.pool).await.map_err(map_err)?;
+        rows.iter()
+            .map(|row| Ok((row.try_get("change_seq")?, WalletTransaction::from_row(row)?)))
+            .collect::<sqlx::Result<_>>()
+            .map_err(map_err)
+    }
+
+    /// `account`'s record of the operation, if it has one.
+    pub async fn get_transaction(&self, account: &str, hash: &str, operation_index: i64) -> Result<Option<WalletTransaction>> {
+        sqlx::query_as::<_, WalletTransaction>("SELECT * FROM transactions WHERE account = ?1 AND hash = ?2 AND operation_index = ?3")
+            .bind(account)
+            .bind(hash)
+            .bind(operation_index)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch transaction: {}", e)))
+    }
+
+    /// The payment this wallet sent as `hash`, if it recorded one.
+    pub async fn get_outgoing_transaction(&self, hash: &str) -> Result<Option<WalletTransaction>> {
+        sqlx::query_as::<_, WalletTransaction>("SELECT * FROM transactions WHERE hash = ?1 AND direction = 'outgoing' LIMIT 1")
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch transaction: {}", e)))
+    }
+
+    /// When `account` last sent `amount_stroops` XLM to `counterparty` with
+    /// `memo`, if it did after `since` and the payment hasn't failed. Pending
+    /// payments count: they may still go through.
+    pub async fn last_matching_payment(
+        &self,
+        account: &str,
+        counterparty: &str,
+        amount_stroops: i64,
+        memo: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let query = r#"
+            SELECT created_at FROM transactions
+            WHERE account = ?1 AND direction = 'outgoing' AND asset_code = 'XLM' AND counterparty = ?2
+              AND amount_stroops = ?3 AND memo IS ?4 AND status != 'failed' AND created_at > ?5
+            ORDER BY created_at DESC LIMIT 1
+        "#;
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to look for a repeated payment: {}", e));
+
+        let row = sqlx::query(query)
+            .bind(account)
+            .bind(counterparty)
+            .bind(amount_stroops)
+            .bind(memo)
+            .bind(since.to_rfc3339())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(map_err)?;
+        row.map(|row| rows::timestamp(&row, "created_at")).transpose().map_err(map_err)
+    }
+
+    /// The newest `limit` transactions for `account`.
+    pub async fn get_transactions(&self, account: &str, limit: u32) -> Result<Vec<WalletTransaction>> {
+        let query = "SELECT * FROM transactions WHERE account = ?1 ORDER BY created_at DESC, operation_index DESC LIMIT ?2";
+
+        sqlx::query_as::<_, WalletTransaction>(query)
+            .bind(account)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch transactions: {}", e)))
+    }
+
+    /// The newest `limit` of `account`'s transactions matching `search`.
+    pub async fn search_transactions(&self, account: &str, search: &TransactionSearch, limit: u32) -> Result<Vec<WalletTransaction>> {
+        let (sql, binds) = compile_search(account, search, limit);
+
+        let mut query = sqlx::query_as::<_, WalletTransaction>(&sql);
+        for bind in binds {
+            query = match bind {
+                SearchBind::Text(value) => query.bind(value),
+                SearchBind::Integer(value) => query.bind(value),
+            };
+        }
+
+        query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to search transactions: {}", e)))
+    }
+
+    pub async fn add_transaction_tag(&self, account: &str, hash: &str, tag: &str) -> Result<()> {
+        let query = "INSERT OR IGNORE INTO transaction_tags (account, hash, tag, created_at) VALUES (?1, ?2, ?3, ?4)";
+
+        sqlx::query(query)
+            .bind(account)
+            .bind(hash)
+            .bind(tag)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to tag transaction: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Removes one local record, e.g. a duplicate found by reconciliation.
+    pub async fn delete_transaction(&self, account: &str, hash: &str, operation_index: i64) -> Result<()> {
+        sqlx::query("DELETE FROM transactions WHERE account = ?1 AND hash = ?2 AND operation_index = ?3")
+            .bind(account)
+            .bind(hash)
+            .bind(operation_index)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to delete transaction: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Returns whether the tag was there.
+    pub async fn remove_transaction_tag(&self, account: &str, hash: &str, tag: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM transaction_tags WHERE account = ?1 AND hash = ?2 AND tag = ?3")
+            .bind(account)
+            .bind(hash)
+            .bind(tag)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to untag transaction: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Every tag on `account`'s transactions, keyed by transaction hash.
+    pub async fn get_transaction_tags(&self, account: &str) -> Result<HashMap<String, Vec<String>>> {
+        let rows = sqlx::query("SELECT hash, tag FROM transaction_tags WHERE account = ?1 ORDER BY tag")
+            .bind(account)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch transaction tags: {}", e)))?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            tags.entry(row.get("hash")).or_default().push(row.get("tag"));
+        }
+        Ok(tags)
+    }
+
+    /// Distinct hashes of `account`'s transactions starting with `prefix`.
+    pub async fn find_transaction_hashes(&self, account: &str, prefix: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT DISTINCT hash FROM transactions WHERE account = ?1 AND hash GLOB ?2 LIMIT 2")
+            .bind(account)
+            .bind(format!("{}*", prefix))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to look up transaction: {}", e)))?;
+
+        Ok(rows.iter().map(|row| row.get("hash")).collect())
+    }
+}
+
+impl FromRow<'_, SqliteRow> for WalletTransaction {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let direction: String = row.try_get("direction")?;
+        let status: String = row.try_get("status")?;
+
+        Ok(WalletTransaction {
+            id: rows::uuid(row, "id")?,
+            account: row.try_get("account")?,
+            hash: row.try_get("hash")?,
+            operation_index: row.try_get("operation_index")?,
+            direction: TransactionDirection::parse(&direction)
+                .ok_or_else(|| rows::decode_error("direction", format!("unknown transaction direction '{}'", direction)))?,
+            asset_code: row.try_get("asset_code")?,
+            amount_stroops: row.try_get("amount_stroops")?,
+            counterparty: row.try_get("counterparty")?,
+            memo: row.try_get("memo")?,
+            status: TransactionStatus::parse(&status)
+                .ok_or_else(|| rows::decode_error("status", format!("unknown transaction status '{}'", status)))?,
+            ledger: row.try_get("ledger")?,
+            error: row.try_get("error")?,
+            request_id: row.try_get("request_id")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
+
+/// SQL for a search. Only fixed fragments are interpolated; every user-supplied
+/// value is a bind parameter. Prefix matches use GLOB, which SQLite can answer
+/// from the `(account, ...)` indexes; callers keep GLOB metacharacters out.
+fn compile_search(account: &str, search: &TransactionSearch, limit: u32) -> (String, Vec<SearchBind>) {
+    let mut conditions = vec!["t.account = ?1".to_string()];
+    let mut binds = vec![SearchBind::Text(account.to_string())];
+
+    let mut push = |condition: &str, bind: SearchBind| {
+        binds.push(bind);
+        conditions.push(condition.replace('?', &format!("?{}", binds.len())));
+    };
+
+    if let Some(prefix) = &search.hash_prefix {
+        push("t.hash GLOB ?", SearchBind::Text(format!("{}*", prefix)));
+    }
+    if let Some(prefix) = &search.counterparty {
+        push("t.counterparty GLOB ?", SearchBind::Text(format!("{}*", prefix)));
+    }
+    if let Some(text) = &search.memo {
+        let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        push("t.memo LIKE ? ESCAPE '\\'", SearchBind::Text(format!("%{}%", escaped)));
+    }
+    if let Some(min) = search.min_amount_stroops {
+        push("t.amount_stroops >= ?", SearchBind::Integer(min));
+    }
+    if let Some(max) = search.max_amount_stroops {
+        push("t.amount_stroops <= ?", SearchBind::Integer(max));
+    }
+    if let Some(asset_code) = &search.asset_code {
+        push("t.asset_code = ?", SearchBind::Text(asset_code.clone()));
+    }
+    if let Some(since) = search.since {
+        push("t.created_at >= ?", SearchBind::Text(since.format("%Y-%m-%d").to_string()));
+    }
+    if let Some(until) = search.until {
+        // Timestamps are RFC 3339, so anything on `until` sorts below the next day.
+        let next_day = until.succ_opt().unwrap_or(until);
+        push("t.created_at < ?", SearchBind::Text(next_day.format("%Y-%m-%d").to_string()));
+    }
+    if let Some(tag) = &search.tag {
+        push(
+            "EXISTS (SELECT 1 FROM transaction_tags g WHERE g.account = t.account AND g.hash = t.hash AND g.tag = ?)",
+            SearchBind::Text(tag.clone()),
+        );
+    }
+
+    binds.push(SearchBind::Integer(limit as i64));
+    let sql = format!(
+        "SELECT t.* FROM transactions t WHERE {} ORDER BY t.created_at DESC, t.operation_index DESC LIMIT ?{}",
+        conditions.join(" AND "),
+        binds.len()
+    );
+
+    (sql, binds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn outgoing(hash: &str, memo: Option<&str>, status: TransactionStatus) -> WalletTransaction {
+        let now = Utc::now();
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: "GME".to_string(),
+            hash: hash.to_string(),
+            operation_index: 1,
+            direction: TransactionDirection::Outgoing,
+            asset_code: "XLM".to_string(),
+            amount_stroops: 10_000_000,
+            counterparty: "GTHEM".to_string(),
+            memo: memo.map(str::to_string),
+            status,
+            ledger: None,
+            error: None,
+            request_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn records_and_updates_submitted_payments() {
+        let db = SqliteDatabase::in_memory().await;
+        db.upsert_transaction(&outgoing("aa", Some("rent"), TransactionStatus::Pending)).await.unwrap();
+        db.update_transaction_status("GME", "aa", TransactionStatus::Confirmed, Some(42), None).await.unwrap();
+
+        let stored = db.get_transactions("GME", 10).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].status, stored[0].ledger), (TransactionStatus::Confirmed, Some(42)));
+        assert!(db.get_transactions("GTHEM", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn backfill_merges_with_local_rows() {
+        let db = SqliteDatabase::in_memory().await;
+        db.upsert_transaction(&outgoing("aa", Some("rent"), TransactionStatus::Pending)).await.unwrap();
+
+        // The same operation arriving from Horizon confirms the local row rather
+        // than duplicating it, and keeps the memo we recorded.
+        let mut backfilled = outgoing("aa", None, TransactionStatus::Confirmed);
+        backfilled.ledger = Some(7);
+        db.upsert_transaction(&backfilled).await.unwrap();
+
+        let stored = db.get_transactions("GME", 10).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].status, TransactionStatus::Confirmed);
+        assert_eq!(stored[0].ledger, Some(7));
+        assert_eq!(stored[0].memo.as_deref(), Some("rent"));
+    }
+
+    #[tokio::test]
+    async fn numbers_inserts_and_real_changes_in_order() {
+        let db = SqliteDatabase::in_memory().await;
+        db.upsert_transaction(&outgoing("aa", None, TransactionStatus::Pending)).await.unwrap();
+        db.upsert_transaction(&outgoing("bb", None, TransactionStatus::Pending)).await.unwrap();
+        db.update_transaction_status("GME", "aa", TransactionStatus::Confirmed, Some(42), None).await.unwrap();
+
+        let changed = db.get_transactions_changed_after(0, 10).await.unwrap();
+        let order: Vec<(i64, &str)> = changed.iter().map(|(seq, tx)| (*seq, tx.hash.as_str())).collect();
+        assert_eq!(order, [(2, "bb"), (3, "aa")]);
+
+        // Backfilling what is already recorded changes nothing.
+        let mut backfilled = outgoing("aa", None, TransactionStatus::Confirmed);
+        backfilled.ledger = Some(42);
+        db.upsert_transaction(&backfilled).await.unwrap();
+        assert!(db.get_transactions_changed_after(3, 10).await.unwrap().is_empty());
+        assert_eq!(db.get_transactions_changed_after(0, 1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn searches_by_every_field() {
+        let db = SqliteDatabase::in_memory().await;
+        let mut rent = outgoing("aa11", Some("Rent for May"), TransactionStatus::Confirmed);
+        rent.amount_stroops = 500_000_000;
+        let mut coffee = outgoing("bb22", Some("coffee_50%"), TransactionStatus::Confirmed);
+        coffee.counterparty = "GCAFE".to_string();
+        coffee.asset_code = "USDC".to_string();
+        db.upsert_transaction(&rent).await.unwrap();
+        db.upsert_transaction(&coffee).await.unwrap();
+        db.add_transaction_tag("GME", "aa11", "housing").await.unwrap();
+
+        let hashes = |found: Vec<WalletTransaction>| found.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+        let search = |search: TransactionSearch| {
+            let db = db.clone();
+            async move { hashes(db.search_transactions("GME", &search, 10).await.unwrap()) }
+        };
+
+        assert_eq!(search(TransactionSearch::default()).await.len(), 2);
+        assert_eq!(search(TransactionSearch { hash_prefix: Some("aa".into()), ..Default::default() }).await, ["aa11"]);
+        assert_eq!(search(TransactionSearch { counterparty: Some("GCA".into()), ..Default::default() }).await, ["bb22"]);
+        assert_eq!(search(TransactionSearch { memo: Some("rent".into()), ..Default::default() }).await, ["aa11"]);
+        assert_eq!(search(TransactionSearch { memo: Some("_50%".into()), ..Default::default() }).await, ["bb22"]);
+        assert!(search(TransactionSearch { memo: Some("f_e".into()), ..Default::default() }).await.is_empty());
+        assert_eq!(search(TransactionSearch { min_amount_stroops: Some(100_000_000), ..Default::default() }).await, ["aa11"]);
+        assert_eq!(search(TransactionSearch { max_amount_stroops: Some(100_000_000), ..Default::default() }).await, ["bb22"]);
+        assert_eq!(search(TransactionSearch { asset_code: Some("USDC".into()), ..Default::default() }).await, ["bb22"]);
+        assert_eq!(search(TransactionSearch { tag: Some("housing".into()), ..Default::default() }).await, ["aa11"]);
+
+        let today = Utc::now().date_naive();
+        assert_eq!(search(TransactionSearch { since: Some(today), until: Some(today), ..Default::default() }).await.len(), 2);
+        assert!(search(TransactionSearch { since: today.succ_opt(), ..Default::default() }).await.is_empty());
+        assert!(search(TransactionSearch { until: today.pred_opt(), ..Default::default() }).await.is_empty());
+
+        assert!(db.search_transactions("GTHEM", &TransactionSearch::default(), 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn searches_use_the_account_indexes() {
+        let db = SqliteDatabase::in_memory().await;
+        let search = TransactionSearch {
+            counterparty: Some("GCA".into()),
+            ..Default::default()
+        };
+        let (sql, _) = compile_search("GME", &search, 10);
+
+        let plan: Vec<String> = sqlx::query(&format!("EXPLAIN QUERY PLAN {}", sql))
+            .bind("GME")
+            .bind("GCA*")
+            .bind(10)
+            .fetch_all(&db.pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("detail"))
+            .collect();
+
+        assert!(plan.iter().any(|step| step.contains("USING INDEX")), "{:?}", plan);
+    }
+}
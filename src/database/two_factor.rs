@@ -0,0 +1,175 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::two_factor::TotpSecret;
+use crate::utils::crypto::Envelope;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    /// Saves a secret, replacing any earlier one for the user.
+    pub async fn upsert_totp_secret(&self, secret: &TotpSecret) -> Result<()> {
+        let query = r#"
+            INSERT INTO totp_secrets (user_id, ciphertext, nonce, wrapped_key, key_nonce, key_id, confirmed_at, last_used_step, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            ON CONFLICT (user_id) DO UPDATE SET
+                ciphertext = excluded.ciphertext,
+                nonce = excluded.nonce,
+                wrapped_key = excluded.wrapped_key,
+                key_nonce = excluded.key_nonce,
+                key_id = excluded.key_id,
+                confirmed_at = excluded.confirmed_at,
+                last_used_step = excluded.last_used_step,
+                created_at = excluded.created_at
+        "#;
+
+        sqlx::query(query)
+            .bind(secret.user_id.to_string())
+            .bind(&secret.envelope.ciphertext)
+            .bind(&secret.envelope.nonce)
+            .bind(&secret.envelope.wrapped_key)
+            .bind(&secret.envelope.key_nonce)
+            .bind(&secret.envelope.key_id)
+            .bind(secret.confirmed_at.map(|at| at.to_rfc3339()))
+            .bind(secret.last_used_step)
+            .bind(secret.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save TOTP secret: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_totp_secret(&self, user_id: &Uuid) -> Result<Option<TotpSecret>> {
+        sqlx::query_as::<_, TotpSecret>("SELECT * FROM totp_secrets WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch TOTP secret: {}", e)))
+    }
+
+    /// Secrets whose data key is wrapped by anything other than `key_id`.
+    pub async fn get_totp_secrets_not_under_key(&self, key_id: &str) -> Result<Vec<TotpSecret>> {
+        sqlx::query_as::<_, TotpSecret>("SELECT * FROM totp_secrets WHERE key_id != ?1")
+            .bind(key_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch TOTP secrets: {}", e)))
+    }
+
+    /// Records `step` as used unless it is no later than the last one, so each
+    /// code works once. Returns whether the step was accepted.
+    pub async fn record_totp_step(&self, user_id: &Uuid, step: i64) -> Result<bool> {
+        let query = r#"
+            UPDATE totp_secrets SET last_used_step = ?2
+            WHERE user_id = ?1 AND (last_used_step IS NULL OR last_used_step < ?2)
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(user_id.to_string())
+            .bind(step)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update TOTP secret: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn confirm_totp_secret(&self, user_id: &Uuid, now: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE totp_secrets SET confirmed_at = ?2 WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to confirm TOTP secret: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Removes the user's secret and recovery codes. Returns whether a secret existed.
+    pub async fn delete_two_factor(&self, user_id: &Uuid) -> Result<bool> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to turn off two-factor authentication: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        let deleted = sqlx::query("DELETE FROM totp_secrets WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?
+            .rows_affected();
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Replaces all of the user's recovery codes with `code_hashes`.
+    pub async fn replace_recovery_codes(&self, user_id: &Uuid, code_hashes: &[String], now: DateTime<Utc>) -> Result<()> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to save recovery codes: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        for code_hash in code_hashes {
+            sqlx::query("INSERT INTO recovery_codes (code_hash, user_id, created_at) VALUES (?1, ?2, ?3)")
+                .bind(code_hash)
+                .bind(user_id.to_string())
+                .bind(now.to_rfc3339())
+                .execute(&mut *tx)
+                .await
+                .map_err(map_err)?;
+        }
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(())
+    }
+
+    /// Deletes one of the user's recovery codes. Returns whether it existed.
+    pub async fn use_recovery_code(&self, user_id: &Uuid, code_hash: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?1 AND code_hash = ?2")
+            .bind(user_id.to_string())
+            .bind(code_hash)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to use recovery code: {}", e)))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn count_recovery_codes(&self, user_id: &Uuid) -> Result<i64> {
+        let row = sqlx::query("SELECT COUNT(*) AS n FROM recovery_codes WHERE user_id = ?1")
+            .bind(user_id.to_string())
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to count recovery codes: {}", e)))?;
+
+        Ok(row.get("n"))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for TotpSecret {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(TotpSecret {
+            user_id: rows::uuid(row, "user_id")?,
+            envelope: Envelope {
+                ciphertext: row.try_get("ciphertext")?,
+                nonce: row.try_get("nonce")?,
+                wrapped_key: row.try_get("wrapped_key")?,
+                key_nonce: row.try_get("key_nonce")?,
+                key_id: row.try_get("key_id")?,
+            },
+            confirmed_at: rows::optional_timestamp(row, "confirmed_at")?,
+            last_used_step: row.try_get("last_used_step")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
@@ -0,0 +1,238 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::role::Role;
+use crate::models::user::User;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Storage for user accounts, so `UserService` doesn't depend on SQLite directly.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create_user(&self, user: &User) -> Result<()>;
+    /// Saves `users` all at once, with a result for each; see
+    /// [`SqliteDatabase::create_users`].
+    async fn create_users(&self, users: &[User]) -> Result<Vec<Result<()>>>;
+    async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>>;
+    /// The user for showing, which may be a few seconds old and, from a
+    /// cache, has no password hash. Don't check passwords or save changes
+    /// with it.
+    async fn get_user_profile(&self, user_id: &Uuid) -> Result<Option<User>> {
+        self.get_user_by_id(user_id).await
+    }
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>>;
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>>;
+    async fn get_user_by_stellar_public_key(&self, public_key: &str) -> Result<Option<User>>;
+    async fn update_user_stellar_public_key(&self, user_id: &Uuid, public_key: &str) -> Result<()>;
+    async fn record_user_login(&self, user_id: &Uuid, at: DateTime<Utc>) -> Result<()>;
+    async fn update_user(&self, user: &User) -> Result<()>;
+    async fn update_user_password_hash(&self, user_id: &Uuid, password_hash: &str) -> Result<()>;
+    async fn get_users(&self) -> Result<Vec<User>>;
+    async fn update_user_role(&self, user_id: &Uuid, role: Role, at: DateTime<Utc>) -> Result<()>;
+    async fn update_user_disabled(&self, user_id: &Uuid, disabled_at: Option<DateTime<Utc>>, at: DateTime<Utc>) -> Result<()>;
+    async fn delete_user(&self, user_id: &Uuid) -> Result<bool>;
+    async fn get_user_count(&self) -> Result<i64>;
+}
+
+#[async_trait]
+impl UserRepository for SqliteDatabase {
+    async fn create_user(&self, user: &User) -> Result<()> {
+        SqliteDatabase::create_user(self, user).await
+    }
+
+    async fn create_users(&self, users: &[User]) -> Result<Vec<Result<()>>> {
+        SqliteDatabase::create_users(self, users).await
+    }
+
+    async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>> {
+        SqliteDatabase::get_user_by_id(self, user_id).await
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        SqliteDatabase::get_user_by_email(self, email).await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        SqliteDatabase::get_user_by_username(self, username).await
+    }
+
+    async fn get_user_by_stellar_public_key(&self, public_key: &str) -> Result<Option<User>> {
+        SqliteDatabase::get_user_by_stellar_public_key(self, public_key).await
+    }
+
+    async fn update_user_stellar_public_key(&self, user_id: &Uuid, public_key: &str) -> Result<()> {
+        SqliteDatabase::update_user_stellar_public_key(self, user_id, public_key).await
+    }
+
+    async fn record_user_login(&self, user_id: &Uuid, at: DateTime<Utc>) -> Result<()> {
+        SqliteDatabase::record_user_login(self, user_id, at).await
+    }
+
+    async fn update_user(&self, user: &User) -> Result<()> {
+        SqliteDatabase::update_user(self, user).await
+    }
+
+    async fn update_user_password_hash(&self, user_id: &Uuid, password_hash: &str) -> Result<()> {
+        SqliteDatabase::update_user_password_hash(self, user_id, password_hash).await
+    }
+
+    async fn get_users(&self) -> Result<Vec<User>> {
+        SqliteDatabase::get_users(self).await
+    }
+
+    async fn update_user_role(&self, user_id: &Uuid, role: Role, at: DateTime<Utc>) -> Result<()> {
+        SqliteDatabase::update_user_role(self, user_id, role, at).await
+    }
+
+    async fn update_user_disabled(&self, user_id: &Uuid, disabled_at: Option<DateTime<Utc>>, at: DateTime<Utc>) -> Result<()> {
+        SqliteDatabase::update_user_disabled(self, user_id, disabled_at, at).await
+    }
+
+    async fn delete_user(&self, user_id: &Uuid) -> Result<bool> {
+        SqliteDatabase::delete_user(self, user_id).await
+    }
+
+    async fn get_user_count(&self) -> Result<i64> {
+        SqliteDatabase::get_user_count(self).await
+    }
+}
+
+/// A `UserRepository` backed by a `Vec`, for tests that don't need a database.
+#[cfg(test)]
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    users: std::sync::Mutex<Vec<User>>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create_user(&self, user: &User) -> Result<()> {
+        use crate::errors::AppError;
+
+        let mut users = self.users.lock().unwrap();
+
+        if users.iter().any(|existing| existing.email == user.email) {
+            return Err(AppError::ValidationError("Email already exists".to_string()));
+        }
+        if users.iter().any(|existing| existing.username == user.username) {
+            return Err(AppError::ValidationError("Username already exists".to_string()));
+        }
+
+        users.push(user.clone());
+        Ok(())
+    }
+
+    async fn create_users(&self, users: &[User]) -> Result<Vec<Result<()>>> {
+        let mut results = Vec::with_capacity(users.len());
+        for user in users {
+            results.push(self.create_user(user).await);
+        }
+        Ok(results)
+    }
+
+    async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>> {
+        Ok(self.users.lock().unwrap().iter().find(|user| user.id == *user_id).cloned())
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        Ok(self.users.lock().unwrap().iter().find(|user| user.email == email).cloned())
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        Ok(self.users.lock().unwrap().iter().find(|user| user.username == username).cloned())
+    }
+
+    async fn get_user_by_stellar_public_key(&self, public_key: &str) -> Result<Option<User>> {
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|user| user.stellar_public_key.as_deref() == Some(public_key))
+            .cloned())
+    }
+
+    async fn update_user_stellar_public_key(&self, user_id: &Uuid, public_key: &str) -> Result<()> {
+        use crate::errors::AppError;
+
+        let mut users = self.users.lock().unwrap();
+
+        if users
+            .iter()
+            .any(|user| user.id != *user_id && user.stellar_public_key.as_deref() == Some(public_key))
+        {
+            return Err(AppError::ValidationError("This Stellar account is already linked to another user".to_string()));
+        }
+        if let Some(user) = users.iter_mut().find(|user| user.id == *user_id) {
+            user.stellar_public_key = Some(public_key.to_string());
+            user.updated_at = chrono::Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn record_user_login(&self, user_id: &Uuid, at: DateTime<Utc>) -> Result<()> {
+        if let Some(user) = self.users.lock().unwrap().iter_mut().find(|user| user.id == *user_id) {
+            user.last_login_at = Some(at);
+            user.login_count += 1;
+        }
+        Ok(())
+    }
+
+    async fn update_user(&self, user: &User) -> Result<()> {
+        use crate::errors::AppError;
+
+        let mut users = self.users.lock().unwrap();
+
+        if users.iter().any(|existing| existing.id != user.id && existing.email == user.email) {
+            return Err(AppError::ValidationError("Email already exists".to_string()));
+        }
+        if users.iter().any(|existing| existing.id != user.id && existing.username == user.username) {
+            return Err(AppError::ValidationError("Username already exists".to_string()));
+        }
+        if let Some(existing) = users.iter_mut().find(|existing| existing.id == user.id) {
+            *existing = user.clone();
+        }
+        Ok(())
+    }
+
+    async fn update_user_password_hash(&self, user_id: &Uuid, password_hash: &str) -> Result<()> {
+        if let Some(user) = self.users.lock().unwrap().iter_mut().find(|user| user.id == *user_id) {
+            user.password_hash = password_hash.to_string();
+        }
+        Ok(())
+    }
+
+    async fn get_users(&self) -> Result<Vec<User>> {
+        let mut users = self.users.lock().unwrap().clone();
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(users)
+    }
+
+    async fn update_user_role(&self, user_id: &Uuid, role: Role, at: DateTime<Utc>) -> Result<()> {
+        if let Some(user) = self.users.lock().unwrap().iter_mut().find(|user| user.id == *user_id) {
+            user.role = role;
+            user.updated_at = at;
+        }
+        Ok(())
+    }
+
+    async fn update_user_disabled(&self, user_id: &Uuid, disabled_at: Option<DateTime<Utc>>, at: DateTime<Utc>) -> Result<()> {
+        if let Some(user) = self.users.lock().unwrap().iter_mut().find(|user| user.id == *user_id) {
+            user.disabled_at = disabled_at;
+            user.updated_at = at;
+        }
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: &Uuid) -> Result<bool> {
+        let mut users = self.users.lock().unwrap();
+        let before = users.len();
+        users.retain(|user| user.id != *user_id);
+        Ok(users.len() != before)
+    }
+
+    async fn get_user_count(&self) -> Result<i64> {
+        Ok(self.users.lock().unwrap().len() as i64)
+    }
+}
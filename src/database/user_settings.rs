@@ -0,0 +1,58 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::user_settings::UserSettings;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn upsert_user_settings(&self, settings: &UserSettings) -> Result<()> {
+        let query = r#"
+            INSERT INTO user_settings (user_id, fiat_currency, sms_phone_number, sms_verification_enabled, theme, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (user_id) DO UPDATE SET
+                fiat_currency = excluded.fiat_currency,
+                sms_phone_number = excluded.sms_phone_number,
+                sms_verification_enabled = excluded.sms_verification_enabled,
+                theme = excluded.theme,
+                updated_at = excluded.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(settings.user_id.to_string())
+            .bind(&settings.fiat_currency)
+            .bind(&settings.sms_phone_number)
+            .bind(settings.sms_verification_enabled)
+            .bind(&settings.theme)
+            .bind(settings.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save settings: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_user_settings(&self, user_id: &Uuid) -> Result<Option<UserSettings>> {
+        let query = "SELECT * FROM user_settings WHERE user_id = ?1";
+
+        sqlx::query_as::<_, UserSettings>(query)
+            .bind(user_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch settings: {}", e)))
+    }
+}
+
+impl FromRow<'_, SqliteRow> for UserSettings {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(UserSettings {
+            user_id: rows::uuid(row, "user_id")?,
+            fiat_currency: row.try_get("fiat_currency")?,
+            sms_phone_number: row.try_get("sms_phone_number")?,
+            sms_verification_enabled: row.try_get("sms_verification_enabled")?,
+            theme: row.try_get("theme")?,
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
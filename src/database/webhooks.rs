@@ -0,0 +1,288 @@
+use crate::database::rows;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::webhook::{DeliveryStatus, WebhookDelivery, WebhookEndpoint, WebhookEventType};
+use crate::utils::crypto::Envelope;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{FromRow, Row};
+use uuid::Uuid;
+
+impl SqliteDatabase {
+    pub async fn insert_webhook_endpoint(&self, endpoint: &WebhookEndpoint) -> Result<()> {
+        let query = r#"
+            INSERT INTO webhook_endpoints (id, user_id, url, event_types, ciphertext, nonce, wrapped_key, key_nonce, key_id,
+                                           created_by, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        "#;
+
+        sqlx::query(query)
+            .bind(endpoint.id.to_string())
+            .bind(endpoint.user_id.map(|id| id.to_string()))
+            .bind(&endpoint.url)
+            .bind(event_types(&endpoint.event_types))
+            .bind(&endpoint.envelope.ciphertext)
+            .bind(&endpoint.envelope.nonce)
+            .bind(&endpoint.envelope.wrapped_key)
+            .bind(&endpoint.envelope.key_nonce)
+            .bind(&endpoint.envelope.key_id)
+            .bind(endpoint.created_by.to_string())
+            .bind(endpoint.created_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save webhook endpoint: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn get_webhook_endpoint(&self, id: &Uuid) -> Result<Option<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>("SELECT * FROM webhook_endpoints WHERE id = ?1")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch webhook endpoint: {}", e)))
+    }
+
+    /// `user_id`'s own endpoints, or the ones for every account when `None`,
+    /// oldest first.
+    pub async fn get_webhook_endpoints(&self, user_id: Option<&Uuid>) -> Result<Vec<WebhookEndpoint>> {
+        let query = "SELECT * FROM webhook_endpoints WHERE user_id IS ?1 ORDER BY created_at";
+
+        sqlx::query_as::<_, WebhookEndpoint>(query)
+            .bind(user_id.map(|id| id.to_string()))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch webhook endpoints: {}", e)))
+    }
+
+    /// The endpoints an event about `user_id` goes to: theirs and the ones
+    /// for every account, if they subscribe to `event_type`.
+    pub async fn get_webhook_endpoints_for_event(&self, user_id: &Uuid, event_type: WebhookEventType) -> Result<Vec<WebhookEndpoint>> {
+        let query = r#"
+            SELECT * FROM webhook_endpoints
+            WHERE (user_id = ?1 OR user_id IS NULL) AND instr(',' || event_types || ',', ',' || ?2 || ',') > 0
+        "#;
+
+        sqlx::query_as::<_, WebhookEndpoint>(query)
+            .bind(user_id.to_string())
+            .bind(event_type.as_str())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch webhook endpoints: {}", e)))
+    }
+
+    pub async fn count_webhook_endpoints(&self, user_id: Option<&Uuid>) -> Result<i64> {
+        let row = sqlx::query("SELECT COUNT(*) AS n FROM webhook_endpoints WHERE user_id IS ?1")
+            .bind(user_id.map(|id| id.to_string()))
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to count webhook endpoints: {}", e)))?;
+
+        Ok(row.get("n"))
+    }
+
+    /// Removes an endpoint and its delivery log. Returns whether it existed.
+    pub async fn delete_webhook_endpoint(&self, id: &Uuid) -> Result<bool> {
+        let map_err = |e: sqlx::Error| AppError::DatabaseError(format!("Failed to remove webhook endpoint: {}", e));
+        let mut tx = self.pool.begin().await.map_err(map_err)?;
+
+        sqlx::query("DELETE FROM webhook_deliveries WHERE endpoint_id = ?1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?;
+        let deleted = sqlx::query("DELETE FROM webhook_endpoints WHERE id = ?1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(map_err)?
+            .rows_affected();
+        tx.commit().await.map_err(map_err)?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Endpoints whose secret's data key is wrapped by anything other than `key_id`.
+    pub async fn get_webhook_endpoints_not_under_key(&self, key_id: &str) -> Result<Vec<WebhookEndpoint>> {
+        sqlx::query_as::<_, WebhookEndpoint>("SELECT * FROM webhook_endpoints WHERE key_id != ?1")
+            .bind(key_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch webhook endpoints: {}", e)))
+    }
+
+    pub async fn update_webhook_endpoint_envelope(&self, id: &Uuid, envelope: &Envelope) -> Result<()> {
+        let query = r#"
+            UPDATE webhook_endpoints SET ciphertext = ?2, nonce = ?3, wrapped_key = ?4, key_nonce = ?5, key_id = ?6
+            WHERE id = ?1
+        "#;
+
+        sqlx::query(query)
+            .bind(id.to_string())
+            .bind(&envelope.ciphertext)
+            .bind(&envelope.nonce)
+            .bind(&envelope.wrapped_key)
+            .bind(&envelope.key_nonce)
+            .bind(&envelope.key_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update webhook endpoint: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Queues a delivery unless the endpoint already has this event. Returns
+    /// whether it was queued.
+    pub async fn insert_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<bool> {
+        let query = r#"
+            INSERT OR IGNORE INTO webhook_deliveries (id, endpoint_id, user_id, event_id, event_type, payload, status, attempts,
+                                                      response_status, last_error, request_id, next_attempt_at, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+        "#;
+
+        let inserted = sqlx::query(query)
+            .bind(delivery.id.to_string())
+            .bind(delivery.endpoint_id.to_string())
+            .bind(delivery.user_id.to_string())
+            .bind(&delivery.event_id)
+            .bind(delivery.event_type.as_str())
+            .bind(&delivery.payload)
+            .bind(delivery.status.as_str())
+            .bind(delivery.attempts)
+            .bind(delivery.response_status)
+            .bind(&delivery.last_error)
+            .bind(&delivery.request_id)
+            .bind(delivery.next_attempt_at.map(|at| at.to_rfc3339()))
+            .bind(delivery.created_at.to_rfc3339())
+            .bind(delivery.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to save webhook delivery: {}", e)))?
+            .rows_affected();
+
+        Ok(inserted > 0)
+    }
+
+    /// Moves a due delivery's next attempt to `until`, so nobody else picks it
+    /// up while it's being sent. Returns false if someone already has.
+    pub async fn claim_webhook_delivery(&self, delivery: &WebhookDelivery, until: DateTime<Utc>) -> Result<bool> {
+        let query = r#"
+            UPDATE webhook_deliveries SET next_attempt_at = ?3
+            WHERE id = ?1 AND status = 'pending' AND next_attempt_at IS ?2
+        "#;
+
+        let claimed = sqlx::query(query)
+            .bind(delivery.id.to_string())
+            .bind(delivery.next_attempt_at.map(|at| at.to_rfc3339()))
+            .bind(until.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to claim webhook delivery: {}", e)))?
+            .rows_affected();
+
+        Ok(claimed > 0)
+    }
+
+    /// Records how an attempt went.
+    pub async fn update_webhook_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let query = r#"
+            UPDATE webhook_deliveries
+            SET status = ?2, attempts = ?3, response_status = ?4, last_error = ?5, next_attempt_at = ?6, updated_at = ?7
+            WHERE id = ?1
+        "#;
+
+        sqlx::query(query)
+            .bind(delivery.id.to_string())
+            .bind(delivery.status.as_str())
+            .bind(delivery.attempts)
+            .bind(delivery.response_status)
+            .bind(&delivery.last_error)
+            .bind(delivery.next_attempt_at.map(|at| at.to_rfc3339()))
+            .bind(delivery.updated_at.to_rfc3339())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to update webhook delivery: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Pending deliveries whose next attempt is due by `now`, oldest first.
+    pub async fn get_due_webhook_deliveries(&self, now: DateTime<Utc>, limit: u32) -> Result<Vec<WebhookDelivery>> {
+        let query = r#"
+            SELECT * FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= ?1
+            ORDER BY next_attempt_at LIMIT ?2
+        "#;
+
+        sqlx::query_as::<_, WebhookDelivery>(query)
+            .bind(now.to_rfc3339())
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch webhook deliveries: {}", e)))
+    }
+
+    /// The endpoint's newest `limit` deliveries.
+    pub async fn get_webhook_deliveries(&self, endpoint_id: &Uuid, limit: u32) -> Result<Vec<WebhookDelivery>> {
+        let query = "SELECT * FROM webhook_deliveries WHERE endpoint_id = ?1 ORDER BY created_at DESC LIMIT ?2";
+
+        sqlx::query_as::<_, WebhookDelivery>(query)
+            .bind(endpoint_id.to_string())
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(format!("Failed to fetch webhook deliveries: {}", e)))
+    }
+}
+
+fn event_types(types: &[WebhookEventType]) -> String {
+    types.iter().map(WebhookEventType::as_str).collect::<Vec<_>>().join(",")
+}
+
+fn event_type(column: &str, value: &str) -> sqlx::Result<WebhookEventType> {
+    WebhookEventType::parse(value).ok_or_else(|| rows::decode_error(column, format!("unknown event type '{}'", value)))
+}
+
+impl FromRow<'_, SqliteRow> for WebhookEndpoint {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let types: String = row.try_get("event_types")?;
+
+        Ok(WebhookEndpoint {
+            id: rows::uuid(row, "id")?,
+            user_id: rows::optional_uuid(row, "user_id")?,
+            url: row.try_get("url")?,
+            event_types: types.split(',').map(|value| event_type("event_types", value)).collect::<sqlx::Result<_>>()?,
+            envelope: Envelope {
+                ciphertext: row.try_get("ciphertext")?,
+                nonce: row.try_get("nonce")?,
+                wrapped_key: row.try_get("wrapped_key")?,
+                key_nonce: row.try_get("key_nonce")?,
+                key_id: row.try_get("key_id")?,
+            },
+            created_by: rows::uuid(row, "created_by")?,
+            created_at: rows::timestamp(row, "created_at")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for WebhookDelivery {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let status: String = row.try_get("status")?;
+
+        Ok(WebhookDelivery {
+            id: rows::uuid(row, "id")?,
+            endpoint_id: rows::uuid(row, "endpoint_id")?,
+            user_id: rows::uuid(row, "user_id")?,
+            event_id: row.try_get("event_id")?,
+            event_type: event_type("event_type", &row.try_get::<String, _>("event_type")?)?,
+            payload: row.try_get("payload")?,
+            status: DeliveryStatus::parse(&status).ok_or_else(|| rows::decode_error("status", format!("unknown status '{}'", status)))?,
+            attempts: row.try_get("attempts")?,
+            response_status: row.try_get("response_status")?,
+            last_error: row.try_get("last_error")?,
+            request_id: row.try_get("request_id")?,
+            next_attempt_at: rows::optional_timestamp(row, "next_attempt_at")?,
+            created_at: rows::timestamp(row, "created_at")?,
+            updated_at: rows::timestamp(row, "updated_at")?,
+        })
+    }
+}
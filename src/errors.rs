@@ -1,3 +1,5 @@
+use schemars::JsonSchema;
+use serde::Serialize;
 use std::fmt;
 
 #[derive(Debug, Clone)]
@@ -6,6 +8,12 @@ pub enum AppError {
     DatabaseError(String),
     AuthenticationError(String),
     StellarError(String),
+    RateLimitError(String),
+    ConflictError(String),
+    /// A signed transaction was sent but Horizon didn't say whether it went
+    /// through, e.g. it timed out. It may still be applied, so it must be
+    /// looked up by `hash`, never signed again.
+    UnconfirmedError { hash: String, message: String },
     InternalError(String),
 }
 
@@ -16,6 +24,9 @@ impl fmt::Display for AppError {
             AppError::DatabaseError(msg) => write!(f, "Database Error: {}", msg),
             AppError::AuthenticationError(msg) => write!(f, "Authentication Error: {}", msg),
             AppError::StellarError(msg) => write!(f, "Stellar Error: {}", msg),
+            AppError::RateLimitError(msg) => write!(f, "Rate Limit Error: {}", msg),
+            AppError::ConflictError(msg) => write!(f, "Conflict Error: {}", msg),
+            AppError::UnconfirmedError { message, .. } => write!(f, "Unconfirmed Transaction: {}", message),
             AppError::InternalError(msg) => write!(f, "Internal Error: {}", msg),
         }
     }
@@ -23,4 +34,115 @@ impl fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
+impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::ValidationError(_) => ErrorCode::ValidationFailed,
+            AppError::DatabaseError(_) => ErrorCode::DatabaseError,
+            AppError::AuthenticationError(_) => ErrorCode::Unauthenticated,
+            AppError::StellarError(_) => ErrorCode::StellarError,
+            AppError::RateLimitError(_) => ErrorCode::RateLimited,
+            AppError::ConflictError(_) => ErrorCode::Conflict,
+            AppError::UnconfirmedError { .. } => ErrorCode::Unconfirmed,
+            AppError::InternalError(_) => ErrorCode::InternalError,
+        }
+    }
+
+    /// The message without the kind `Display` puts in front of it.
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::ValidationError(msg)
+            | AppError::DatabaseError(msg)
+            | AppError::AuthenticationError(msg)
+            | AppError::StellarError(msg)
+            | AppError::RateLimitError(msg)
+            | AppError::ConflictError(msg)
+            | AppError::InternalError(msg) => msg,
+            AppError::UnconfirmedError { message, .. } => message,
+        }
+    }
+}
+
+/// A stable name for each kind of [`AppError`], for API clients to branch on
+/// instead of matching messages, which may change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request was malformed or not allowed; the message says why.
+    ValidationFailed,
+    /// Missing or wrong credentials, tokens or codes, or a locked-out account.
+    Unauthenticated,
+    /// Horizon rejected a transaction or couldn't be reached.
+    StellarError,
+    /// Too many requests; retry after the `Retry-After` header's seconds.
+    RateLimited,
+    /// Clashes with a request that is still being handled; retry shortly.
+    Conflict,
+    /// A transaction was sent but not confirmed in time; `details.hash`
+    /// names it. Retry with the same `Idempotency-Key` to learn how it ended.
+    Unconfirmed,
+    DatabaseError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::Unauthenticated => "unauthenticated",
+            ErrorCode::StellarError => "stellar_error",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::Unconfirmed => "unconfirmed",
+            ErrorCode::DatabaseError => "database_error",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+
+    /// The HTTP status the API answers with.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCode::ValidationFailed => 400,
+            ErrorCode::Unauthenticated => 401,
+            ErrorCode::StellarError => 502,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::Conflict => 409,
+            ErrorCode::Unconfirmed => 504,
+            ErrorCode::DatabaseError | ErrorCode::InternalError => 500,
+        }
+    }
+
+    /// Whether the message may be shown to API clients. Database and
+    /// internal errors can reveal how the server is put together.
+    pub fn is_public(&self) -> bool {
+        !matches!(self, ErrorCode::DatabaseError | ErrorCode::InternalError)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, AppError>;
+
+/// Errors from a remote wallet's API, as the error the remote side raised.
+impl From<stellar_wallet::client::Error> for AppError {
+    fn from(e: stellar_wallet::client::Error) -> Self {
+        use stellar_wallet::client::Error;
+
+        let e = match e {
+            Error::InvalidUrl(_) => return AppError::ValidationError(e.to_string()),
+            Error::NotLoggedIn => return AppError::AuthenticationError("Not logged in; run `login` first".to_string()),
+            Error::Transport(_) => return AppError::InternalError(e.to_string()),
+            Error::Api(e) => e,
+        };
+        match e.code.as_str() {
+            "validation_failed" => AppError::ValidationError(e.message),
+            "unauthenticated" => AppError::AuthenticationError(e.message),
+            "stellar_error" => AppError::StellarError(e.message),
+            "rate_limited" => AppError::RateLimitError(e.message),
+            "conflict" => AppError::ConflictError(e.message),
+            "unconfirmed" => AppError::UnconfirmedError {
+                hash: e.details.as_ref().and_then(|details| details["hash"].as_str()).unwrap_or_default().to_string(),
+                message: e.message,
+            },
+            _ => AppError::InternalError(e.message),
+        }
+    }
+}
@@ -1,23 +1,133 @@
+use crate::cli::branding::Branding;
 use crate::cli::CLI;
-use crate::errors::Result;
-use crate::models::user::CreateUserRequest;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::handlers::password_handler::PasswordHandler;
+use crate::handlers::sms_handler::SmsHandler;
+use crate::handlers::two_factor_handler::TwoFactorHandler;
+use crate::models::login_throttle::LoginScope;
+use crate::models::session::{SavedLogin, Session};
+use crate::models::sms::SmsPurpose;
+use crate::models::user::{CreateUserRequest, UserResponse};
+use crate::services::account_recovery_service::AccountRecoveryService;
+use crate::services::audit_service::AuditService;
+use crate::services::breach_check_service::BreachCheckService;
+use crate::services::hook_service::HookService;
+use crate::services::login_history_service::LoginHistoryService;
+use crate::services::login_throttle_service::LoginThrottleService;
+use crate::services::session_service::{self, SessionService};
+use crate::services::token_service::{SessionStore, TokenService};
 use crate::services::user_service::UserService;
 use crate::utils::validation::Validator;
 use colored::Colorize;
+use std::sync::Arc;
+use secrecy::ExposeSecret;
 
 pub struct AccountHandler {
     user_service: UserService,
+    session_service: SessionService,
+    audit_service: AuditService,
+    login_throttle_service: LoginThrottleService,
+    login_history_service: LoginHistoryService,
+    account_recovery_service: AccountRecoveryService,
+    sms_handler: SmsHandler,
+    two_factor_handler: TwoFactorHandler,
+    tokens: Option<TokenService>,
+    password_handler: PasswordHandler,
+    session_store: SessionStore,
 }
 
 impl AccountHandler {
-    pub async fn new() -> Result<Self> {
-        let user_service = UserService::new().await?;
-        Ok(Self { user_service })
+    pub fn new(db: SqliteDatabase, hooks: Arc<HookService>) -> Self {
+        Self {
+            user_service: UserService::new(db.clone()).with_hooks(hooks),
+            session_service: SessionService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            login_throttle_service: LoginThrottleService::new(db.clone()),
+            login_history_service: LoginHistoryService::new(db.clone()),
+            account_recovery_service: AccountRecoveryService::new(db.clone()),
+            sms_handler: SmsHandler::new(db.clone()),
+            two_factor_handler: TwoFactorHandler::new(db),
+            tokens: None,
+            password_handler: PasswordHandler::new(None),
+            session_store: SessionStore::file(),
+        }
+    }
+
+    /// Offers "stay logged in" after each login, saving a signed token for
+    /// [`AccountHandler::resume_session`] to pick up on the next start.
+    pub fn with_tokens(mut self, tokens: TokenService) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Checks new passwords against known breaches before accepting them.
+    pub fn with_breach_check(mut self, breach_check: BreachCheckService) -> Self {
+        self.password_handler = PasswordHandler::new(Some(breach_check));
+        self
+    }
+
+    /// Keeps the saved login somewhere other than the default session file.
+    pub fn with_session_store(mut self, session_store: SessionStore) -> Self {
+        self.session_store = session_store;
+        self
+    }
+
+    /// The login saved by "stay logged in", if its session is still active.
+    /// An expired access token is renewed with the saved refresh token. A
+    /// saved login that has ended is removed.
+    pub async fn resume_session(&self) -> Result<Option<(UserResponse, Session)>> {
+        let Some(tokens) = &self.tokens else {
+            return Ok(None);
+        };
+        let Some(saved) = self.session_store.load()?.filter(|saved| saved.remote.is_none()) else {
+            return Ok(None);
+        };
+
+        let resumed = match tokens.authenticate(&saved.access_token, &self.session_service).await {
+            Ok(session) => Ok(session),
+            Err(AppError::AuthenticationError(_)) => self.renew(tokens, &saved.refresh_token).await,
+            Err(e) => Err(e),
+        };
+        let session = match resumed {
+            Ok(session) => session,
+            Err(AppError::AuthenticationError(_)) => {
+                self.session_store.clear()?;
+                CLI::print_info("Your saved login has ended; please log in again.");
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let Some(user) = self.user_service.get_user(&session.user_id).await? else {
+            self.session_store.clear()?;
+            return Ok(None);
+        };
+
+        CLI::print_success(&format!("Welcome back, {}! Resuming your saved login.", user.username));
+        Ok(Some((user, session)))
+    }
+
+    /// Trades the refresh token for a fresh pair and saves them.
+    async fn renew(&self, tokens: &TokenService, refresh_token: &str) -> Result<Session> {
+        let (session, refresh_token) = self.session_service.rotate_refresh_token(refresh_token).await?;
+        self.session_store.save(&SavedLogin {
+            access_token: tokens.issue(&session)?,
+            refresh_token,
+            remote: None,
+        })?;
+        Ok(session)
+    }
+
+    /// Removes the saved login once its session is over.
+    pub fn forget_session(&self) -> Result<()> {
+        self.session_store.clear()
     }
 
     pub async fn create_account_interactive(&self) -> Result<()> {
         CLI::print_header();
-        CLI::print_info("Let's create your Stellar Wallet account!");
+        CLI::print_info(&format!("Let's create your {} account!", Branding::current().product_name));
         println!();
 
         // Get email
@@ -57,43 +167,14 @@ impl AccountHandler {
         };
 
         // Get password with confirmation
-        println!();
-        CLI::display_password_requirements();
-        
-        let password = loop {
-            let password = CLI::get_password("🔒 Enter your password:")?;
-            
-            if password.is_empty() {
-                CLI::print_error("Password cannot be empty");
-                continue;
-            }
-
-            // Validate password strength
-            match Validator::validate_password(&password) {
-                Ok(()) => {
-                    // Confirm password
-                    let confirm_password = CLI::get_password("🔒 Confirm your password:")?;
-                    
-                    if password != confirm_password {
-                        CLI::print_error("Passwords do not match. Please try again.");
-                        continue;
-                    }
-                    
-                    break password;
-                }
-                Err(e) => {
-                    CLI::print_error(&e.to_string());
-                    continue;
-                }
-            }
-        };
+        let password = self.password_handler.new_password_interactive().await?;
 
         // Display summary and confirm
         println!();
-        println!("{}", "Account Summary:".yellow().bold());
+        println!("{}", "Account Summary:".warning().bold());
         println!("📧 Email: {}", email);
         println!("👤 Username: {}", username);
-        println!("🔒 Password: {}", "*".repeat(password.len()));
+        println!("🔒 Password: {}", "*".repeat(password.expose_secret().len()));
         println!();
 
         if !CLI::confirm_action("Do you want to create this account?")? {
@@ -108,12 +189,12 @@ impl AccountHandler {
             password,
         };
 
-        match self.user_service.create_user(create_request).await {
+        match self.sign_up(create_request).await {
             Ok(user) => {
                 println!();
                 CLI::print_success("🎉 Account created successfully!");
                 println!();
-                println!("{}", "Account Details:".green().bold());
+                println!("{}", "Account Details:".success().bold());
                 println!("🆔 User ID: {}", user.id);
                 println!("📧 Email: {}", user.email);
                 println!("👤 Username: {}", user.username);
@@ -131,7 +212,23 @@ impl AccountHandler {
         Ok(())
     }
 
-    pub async fn login_interactive(&self) -> Result<()> {
+    /// Signs up without prompting, for `account create`. The password goes
+    /// through the breach check a typed one would.
+    pub async fn create_account(&self, request: CreateUserRequest) -> Result<UserResponse> {
+        self.password_handler.check_breaches(request.password.expose_secret()).await?;
+        self.sign_up(request).await
+    }
+
+    async fn sign_up(&self, request: CreateUserRequest) -> Result<UserResponse> {
+        let user = self.user_service.create_user(request).await?;
+        self.audit_service
+            .record(Some(&user.id), AuditEvent::AccountCreated, &format!("username '{}'", user.username))
+            .await?;
+        Ok(user)
+    }
+
+    /// Authenticates the user and issues a session for this device.
+    pub async fn login_interactive(&self) -> Result<Option<(UserResponse, Session)>> {
         CLI::print_header();
         CLI::print_info("Welcome back! Please log in to your account.");
         println!();
@@ -151,42 +248,190 @@ impl AccountHandler {
         // Get password
         let password = CLI::get_password("🔒 Enter your password:")?;
 
-        if password.is_empty() {
+        if password.expose_secret().is_empty() {
             CLI::print_error("Password cannot be empty");
-            return Ok(());
+            return Ok(None);
+        }
+
+        // Failures count against this machine and, if it exists, the account.
+        let device = session_service::device_label();
+        let user_id = self.user_service.find_user(&identifier).await?.map(|user| user.id);
+        let mut scopes = vec![LoginScope::Source(device.clone())];
+        scopes.extend(user_id.map(LoginScope::Account));
+
+        if let Err(e) = self.login_throttle_service.check(&scopes, chrono::Utc::now()).await {
+            self.audit_service
+                .record(user_id.as_ref(), AuditEvent::LoginFailed, &format!("identifier '{}': locked out", identifier))
+                .await?;
+            if let Some(user_id) = &user_id {
+                self.login_history_service.record(user_id, &device, Some("locked out")).await?;
+            }
+            CLI::print_error(&format!("Login failed: {}", e));
+            return Err(e);
         }
 
         // Attempt login
-        match self.user_service.authenticate_user(&identifier, &password).await {
+        match self.user_service.authenticate_user(&identifier, password.expose_secret()).await {
             Ok(user) => {
+                if !self.sms_handler.require_code_interactive(&user.id, SmsPurpose::LoginCode).await? {
+                    self.audit_service
+                        .record(Some(&user.id), AuditEvent::LoginFailed, "SMS code not confirmed")
+                        .await?;
+                    self.login_history_service.record(&user.id, &device, Some("SMS code not confirmed")).await?;
+                    CLI::print_error("Login failed: SMS code not confirmed");
+                    self.record_failed_login(&scopes).await?;
+                    return Ok(None);
+                }
+                if !self.two_factor_handler.require_code_interactive(&user.id).await? {
+                    self.audit_service
+                        .record(Some(&user.id), AuditEvent::LoginFailed, "2FA code not confirmed")
+                        .await?;
+                    self.login_history_service.record(&user.id, &device, Some("2FA code not confirmed")).await?;
+                    CLI::print_error("Login failed: 2FA code not confirmed");
+                    self.record_failed_login(&scopes).await?;
+                    return Ok(None);
+                }
+                self.login_throttle_service.record_success(&user.id).await?;
+
+                let session = self.session_service.start(&user.id, &device).await?;
+                self.audit_service
+                    .record(Some(&user.id), AuditEvent::LoginSucceeded, &format!("session {} ({})", session.id, session.device_label))
+                    .await?;
+                self.login_history_service.record(&user.id, &device, None).await?;
+
                 println!();
                 CLI::print_success("🎉 Login successful!");
                 println!();
-                println!("{}", "Welcome back!".green().bold());
+                println!("{}", "Welcome back!".success().bold());
                 println!("👤 Username: {}", user.username);
                 println!("📧 Email: {}", user.email);
-                println!("📅 Last login: {}", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"));
+                match user.last_login_at {
+                    Some(at) => println!("📅 Last login: {} ({} logins)", at.format("%Y-%m-%d %H:%M:%S UTC"), user.login_count),
+                    None => println!("📅 First login, welcome!"),
+                }
+                println!("🔑 Session valid until {}", session.expires_at.format("%Y-%m-%d %H:%M UTC"));
                 println!();
-                
-                CLI::print_info("Login feature completed! Dashboard coming soon...");
+
+                if let Some(tokens) = &self.tokens {
+                    if CLI::confirm_action("Stay logged in on this device?")? {
+                        self.session_store.save(&SavedLogin {
+                            access_token: tokens.issue(&session)?,
+                            refresh_token: self.session_service.issue_refresh_token(&session).await?,
+                            remote: None,
+                        })?;
+                        CLI::print_info("You'll be logged in automatically until you log out or the session ends.");
+                    }
+                }
+
+                Ok(Some((user, session)))
             }
             Err(e) => {
+                self.audit_service
+                    .record(user_id.as_ref(), AuditEvent::LoginFailed, &format!("identifier '{}'", identifier))
+                    .await?;
+                if let Some(user_id) = &user_id {
+                    let reason = match &e {
+                        AppError::AuthenticationError(reason) => reason.clone(),
+                        other => other.to_string(),
+                    };
+                    self.login_history_service.record(user_id, &device, Some(&reason)).await?;
+                    self.sms_handler.sms_service().alert(user_id, "someone just failed to log in to your account.").await;
+                }
+
                 CLI::print_error(&format!("Login failed: {}", e));
+                self.record_failed_login(&scopes).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Sets a new password with one of the recovery codes issued with the
+    /// recovery phrase, for users who can't log in any more. Failed codes
+    /// count as failed logins.
+    pub async fn recover_account_interactive(&self) -> Result<()> {
+        CLI::print_header();
+        CLI::print_info("Forgot your password? A recovery code from when you created your recovery phrase sets a new one.");
+        println!();
+
+        let identifier = CLI::get_input("📧 Enter your email or username:")?;
+        let device = session_service::device_label();
+        let user = self.user_service.find_user(&identifier).await?;
+        let mut scopes = vec![LoginScope::Source(device.clone())];
+        scopes.extend(user.as_ref().map(|user| LoginScope::Account(user.id)));
+        self.login_throttle_service.check(&scopes, chrono::Utc::now()).await?;
+
+        let code = CLI::get_password("🛟 Enter a recovery code:")?;
+        let Some(user) = user else {
+            self.record_failed_login(&scopes).await?;
+            return Err(AppError::AuthenticationError("Invalid recovery code".to_string()));
+        };
+        if !self.two_factor_handler.require_code_interactive(&user.id).await? {
+            self.login_history_service.record(&user.id, &device, Some("2FA code not confirmed")).await?;
+            self.record_failed_login(&scopes).await?;
+            return Err(AppError::AuthenticationError("2FA code not confirmed".to_string()));
+        }
+
+        println!();
+        let password = self.password_handler.new_password_interactive().await?;
+        let recovery = match self.account_recovery_service.recover(&user.id, code.expose_secret(), password.expose_secret()).await {
+            Ok(recovery) => recovery,
+            Err(e) => {
+                if let AppError::AuthenticationError(reason) = &e {
+                    self.login_history_service.record(&user.id, &device, Some(reason)).await?;
+                    self.record_failed_login(&scopes).await?;
+                }
                 return Err(e);
             }
+        };
+        self.login_throttle_service.record_success(&user.id).await?;
+        self.sms_handler
+            .sms_service()
+            .alert(&user.id, "your password was reset with a recovery code.")
+            .await;
+
+        println!();
+        CLI::print_success("🎉 Your password has been changed. Log in with the new one.");
+        CLI::print_info(&format!(
+            "{} key(s) from your recovery phrase were restored; {} recovery code(s) left.",
+            recovery.restored_keys, recovery.codes_left
+        ));
+        if !recovery.lost_keys.is_empty() {
+            println!(
+                "{}",
+                "These keys were only protected by your old password and can't be unlocked any more:".warning()
+            );
+            for public_key in &recovery.lost_keys {
+                println!("  {}", public_key);
+            }
+            println!("{}", "Restore them from a keystore backup under Settings → My Data if you have one.".muted());
         }
+        Ok(())
+    }
 
+    async fn record_failed_login(&self, scopes: &[LoginScope]) -> Result<()> {
+        for (scope, until) in self.login_throttle_service.record_failure(scopes, chrono::Utc::now()).await? {
+            let what = match scope {
+                LoginScope::Account(_) => "This account",
+                LoginScope::Source(_) => "Logins from this device",
+            };
+            CLI::print_error(&format!("{} is locked until {} after too many failed attempts.", what, until.format("%H:%M UTC")));
+        }
         Ok(())
     }
 
     pub async fn show_stats(&self) -> Result<()> {
         let user_count = self.user_service.get_user_count().await?;
-        
+
         println!();
-        println!("{}", "📊 Database Statistics:".cyan().bold());
+        println!("{}", "📊 Database Statistics:".heading());
         println!("👥 Total Users: {}", user_count);
+        let sms_service = self.sms_handler.sms_service();
+        if sms_service.is_enabled() {
+            let usage = sms_service.usage_since(chrono::Utc::now() - chrono::Duration::days(30)).await?;
+            println!("📱 SMS sent (30 days): {} (${:.2})", usage.messages, usage.cost_usd);
+        }
         println!();
-        
+
         Ok(())
     }
 }
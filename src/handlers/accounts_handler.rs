@@ -0,0 +1,280 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::handlers::signing_handler::SigningHandler;
+use crate::handlers::sms_handler::SmsHandler;
+use crate::handlers::two_factor_handler::TwoFactorHandler;
+use crate::models::audit::AuditEvent;
+use crate::models::policy::PaymentIntent;
+use crate::models::sms::SmsPurpose;
+use crate::models::user::UserResponse;
+use crate::services::audit_service::AuditService;
+use crate::services::hd_wallet_service::HdWalletService;
+use crate::services::hook_service::HookService;
+use crate::services::policy_service::PolicyService;
+use crate::services::transaction_service::TransactionService;
+use crate::services::user_service::UserService;
+use crate::stellar::amount::parse_stroops;
+use crate::stellar::network::Network;
+use crate::utils::validation::Validator;
+use colored::Colorize;
+use std::sync::Arc;
+use secrecy::{ExposeSecret, SecretString};
+
+pub struct AccountsHandler {
+    hd_wallet_service: HdWalletService,
+    user_service: UserService,
+    policy_service: PolicyService,
+    audit_service: AuditService,
+    sms_handler: SmsHandler,
+    two_factor_handler: TwoFactorHandler,
+    transaction_service: TransactionService,
+}
+
+impl AccountsHandler {
+    pub fn new(db: SqliteDatabase, network: Network, hooks: Arc<HookService>) -> Self {
+        Self {
+            hd_wallet_service: HdWalletService::new(db.clone(), &network),
+            user_service: UserService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            sms_handler: SmsHandler::new(db.clone()),
+            two_factor_handler: TwoFactorHandler::new(db.clone()),
+            policy_service: PolicyService::new(db.clone()),
+            transaction_service: TransactionService::new(network).with_hooks(hooks).with_db(db),
+        }
+    }
+
+    pub async fn manage_accounts_interactive(&self, user: &mut UserResponse, signing: &SigningHandler) -> Result<()> {
+        if !self.hd_wallet_service.has_recovery_phrase(&user.id).await? {
+            return self.create_recovery_phrase_interactive(user).await;
+        }
+
+        loop {
+            println!();
+            println!("{}", "🗂️  Derived Accounts:".heading());
+            println!("  1. 📋 List Accounts & Balances");
+            println!("  2. ➕ Derive Next Account");
+            println!("  3. ⚡ Activate an Account");
+            println!("  4. 🛟 New Recovery Codes");
+            println!("  5. ↩️  Back");
+            println!();
+
+            let choice = CLI::get_input("Enter your choice:")?;
+
+            let result = match choice.as_str() {
+                "1" => self.list_accounts_interactive(user).await,
+                "2" => self.derive_account_interactive(user).await,
+                "3" => self.activate_account_interactive(user, signing).await,
+                "4" => self.regenerate_recovery_codes_interactive(user).await,
+                "5" => return Ok(()),
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    continue;
+                }
+            };
+
+            if let Err(e) = result {
+                CLI::print_error(&format!("Error: {}", e));
+            }
+        }
+    }
+
+    async fn create_recovery_phrase_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        println!();
+        CLI::print_info("A recovery phrase lets you back up many accounts with a single set of 24 words.");
+
+        if !CLI::confirm_action("Create your recovery phrase now?")? {
+            return Ok(());
+        }
+
+        let password = self.verified_password(user).await?;
+        let (phrase, codes, account) = self.hd_wallet_service.create_recovery_phrase(&user.id, password.expose_secret()).await?;
+
+        println!();
+        println!("{}", "Your Recovery Phrase:".warning().bold());
+        for (number, word) in phrase.split_whitespace().enumerate() {
+            print!("{:>4}. {:<10}", number + 1, word);
+            if (number + 1) % 4 == 0 {
+                println!();
+            }
+        }
+        println!();
+        CLI::print_error("Write these words down and keep them offline. They will not be shown again.");
+        println!();
+        print_recovery_codes(&codes);
+        self.audit_service
+            .record(Some(&user.id), AuditEvent::KeyExported, "recovery phrase shown at creation")
+            .await?;
+        self.sms_handler
+            .sms_service()
+            .alert(&user.id, "a recovery phrase was created and shown for your account.")
+            .await;
+
+        if user.stellar_public_key.is_none() {
+            self.user_service.link_stellar_public_key(&user.id, &account.public_key).await?;
+            user.stellar_public_key = Some(account.public_key.clone());
+        }
+
+        CLI::print_success(&format!("Account #0 derived: {}", account.public_key));
+        Ok(())
+    }
+
+    /// Replaces the recovery codes, e.g. after some were used or the list was lost.
+    async fn regenerate_recovery_codes_interactive(&self, user: &UserResponse) -> Result<()> {
+        let remaining = self.hd_wallet_service.remaining_recovery_codes(&user.id).await?;
+        println!();
+        CLI::print_info(&format!("You have {} unused recovery code(s).", remaining));
+        if !CLI::confirm_action("Replace them with a new set? The old ones will stop working.")? {
+            return Ok(());
+        }
+
+        let password = self.verified_password(user).await?;
+        let codes = self.hd_wallet_service.regenerate_recovery_codes(&user.id, password.expose_secret()).await?;
+        print_recovery_codes(&codes);
+        self.audit_service
+            .record(Some(&user.id), AuditEvent::KeyExported, "recovery phrase codes regenerated")
+            .await
+    }
+
+    async fn list_accounts_interactive(&self, user: &UserResponse) -> Result<()> {
+        let accounts = self.hd_wallet_service.list_accounts(&user.id).await?;
+
+        println!();
+        for (account, record) in accounts {
+            let primary = user.stellar_public_key.as_deref() == Some(account.public_key.as_str());
+            println!(
+                "  #{} {}{}",
+                account.account_index,
+                account.public_key,
+                if primary { " ⭐" } else { "" }
+            );
+            match record {
+                Some(record) => println!("     💰 {} XLM", record.native_balance()),
+                None => println!("     {}", "⚪ Not activated".muted()),
+            }
+        }
+        println!();
+
+        Ok(())
+    }
+
+    async fn derive_account_interactive(&self, user: &UserResponse) -> Result<()> {
+        let password = self.verified_password(user).await?;
+        let account = self.hd_wallet_service.derive_next_account(&user.id, password.expose_secret()).await?;
+
+        CLI::print_success(&format!("Account #{} derived: {}", account.account_index, account.public_key));
+        CLI::print_info("Activate it (option 3) before it can receive non-XLM assets or send payments.");
+        Ok(())
+    }
+
+    async fn activate_account_interactive(&self, user: &UserResponse, signing: &SigningHandler) -> Result<()> {
+        let source = user.stellar_public_key.as_deref().ok_or_else(|| {
+            AppError::StellarError("Link a funded primary address before activating accounts".to_string())
+        })?;
+
+        let inactive: Vec<_> = self
+            .hd_wallet_service
+            .list_accounts(&user.id)
+            .await?
+            .into_iter()
+            .filter(|(_, record)| record.is_none())
+            .map(|(account, _)| account)
+            .collect();
+
+        if inactive.is_empty() {
+            CLI::print_info("All derived accounts are already active.");
+            return Ok(());
+        }
+
+        println!();
+        for account in &inactive {
+            println!("  #{} {}", account.account_index, account.public_key);
+        }
+        println!();
+
+        let target = loop {
+            let choice = CLI::get_input("Account number to activate (empty to cancel):")?;
+
+            if choice.is_empty() {
+                return Ok(());
+            }
+
+            match choice.parse::<u32>() {
+                Ok(index) => match inactive.iter().find(|account| account.account_index == index) {
+                    Some(account) => break account,
+                    None => CLI::print_error("That account isn't in the list"),
+                },
+                Err(_) => CLI::print_error("Please enter an account number"),
+            }
+        };
+
+        let amount = loop {
+            let amount = CLI::get_input("💰 Starting balance in XLM (at least 1):")?;
+
+            match Validator::validate_amount(&amount) {
+                Ok(()) => break amount,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        println!();
+        println!("🌐 Network: {}", self.transaction_service.network().name);
+        println!("🏦 Funding from: {}", source);
+        println!("🎯 Activating: #{} {}", target.account_index, target.public_key);
+        println!("💰 Starting balance: {} XLM", amount);
+        println!();
+
+        let intent = PaymentIntent {
+            destination: target.public_key.clone(),
+            asset_code: "XLM".to_string(),
+            amount_stroops: parse_stroops(&amount)?,
+            network: self.transaction_service.network().name.clone(),
+        };
+        self.policy_service.check(&user.id, &intent).await?;
+
+        if !CLI::confirm_action("Activate this account?")? {
+            CLI::print_info("Activation cancelled.");
+            return Ok(());
+        }
+
+        if !self.sms_handler.require_code_interactive(&user.id, SmsPurpose::PaymentConfirmation).await? {
+            CLI::print_error("Activation cancelled: SMS code not confirmed.");
+            return Ok(());
+        }
+
+        if !self.two_factor_handler.require_code_interactive(&user.id).await? {
+            CLI::print_error("Activation cancelled: 2FA code not confirmed.");
+            return Ok(());
+        }
+
+        let signer = signing.unlock_signer_interactive(user, source).await?;
+        let result = self
+            .transaction_service
+            .send_payment(signer.as_ref(), &target.public_key, &amount, None)
+            .await;
+        self.audit_service.record_payment(&user.id, &target.public_key, &amount, &result).await?;
+        let result = result?;
+
+        CLI::print_success(&format!("Account #{} activated in transaction {}", target.account_index, result.hash));
+        Ok(())
+    }
+
+    async fn verified_password(&self, user: &UserResponse) -> Result<SecretString> {
+        let password = CLI::get_password("🔒 Enter your password:")?;
+        self.user_service.authenticate_user(&user.username, password.expose_secret()).await?;
+        Ok(password)
+    }
+}
+
+fn print_recovery_codes(codes: &[String]) {
+    println!("{}", "Recovery codes".heading());
+    println!(
+        "{}",
+        "If you forget your password, any one of these sets a new one from the main menu. Each works once; store them apart from your password.".warning()
+    );
+    for code in codes {
+        println!("  {}", code);
+    }
+    println!();
+}
@@ -0,0 +1,143 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::activity::ActivitySummary;
+use crate::services::activity_service::ActivityService;
+use crate::stellar::amount::format_stroops;
+use colored::Colorize;
+
+const DEFAULT_DAYS: i64 = 365;
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+/// Heatmap shades from no payments to the busiest hour.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+const BAR_WIDTH: i64 = 30;
+
+pub struct ActivityHandler {
+    activity_service: ActivityService,
+}
+
+impl ActivityHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            activity_service: ActivityService::new(db),
+        }
+    }
+
+    pub async fn activity_interactive(&self, address: &str) -> Result<()> {
+        let asset = CLI::get_input("Asset code (Enter for XLM):")?;
+        let asset = if asset.is_empty() { "XLM".to_string() } else { asset };
+
+        let days = loop {
+            let input = CLI::get_input(&format!("Days to look back (Enter for {}):", DEFAULT_DAYS))?;
+            if input.is_empty() {
+                break DEFAULT_DAYS;
+            }
+            match input.parse::<i64>() {
+                Ok(days) if days > 0 => break days,
+                _ => CLI::print_error("Enter a whole number of days."),
+            }
+        };
+
+        let summary = self.activity_service.summary(address, &asset, days).await?;
+
+        if summary.payments == 0 {
+            CLI::print_info(&format!("No confirmed {} payments in the last {} days.", summary.asset_code, days));
+            return Ok(());
+        }
+
+        print_summary(&summary, days);
+
+        if CLI::confirm_action("Print as JSON?")? {
+            println!("{}", ActivityService::to_json(&summary)?);
+        }
+
+        Ok(())
+    }
+}
+
+fn print_summary(summary: &ActivitySummary, days: i64) {
+    println!();
+    println!("{}", format!("📊 {} activity, last {} days", summary.asset_code, days).heading());
+    println!();
+    println!("🔢 Payments: {}", summary.payments);
+    println!("⚖️  Average payment: {} {}", format_stroops(summary.average_payment_stroops), summary.asset_code);
+
+    println!();
+    println!("{}", "🗓️  When you transact (UTC)".bold());
+    println!("      {}", "0     6     12    18   23".muted());
+    let peak = summary.peak();
+    for (weekday, hours) in summary.heatmap.iter().enumerate() {
+        let row: String = hours.iter().map(|&count| shade(count, peak)).collect();
+        println!("  {} {}", WEEKDAYS[weekday], row);
+    }
+
+    println!();
+    println!("{}", "👥 Top counterparties".bold());
+    for party in &summary.top_counterparties {
+        println!(
+            "  {}  {:>4} payments  {:>18} {}",
+            party.counterparty,
+            party.payments,
+            format_stroops(party.volume_stroops),
+            summary.asset_code
+        );
+    }
+
+    println!();
+    println!("{}", format!("📈 Monthly volume ({} in, {} out)", "▇".success(), "▇".error()).bold());
+    let largest = summary
+        .monthly
+        .iter()
+        .map(|month| month.incoming_stroops.max(month.outgoing_stroops))
+        .max()
+        .unwrap_or(0);
+    for month in &summary.monthly {
+        println!(
+            "  {}  {} {}",
+            month.month,
+            bar(month.incoming_stroops, largest).success(),
+            format_stroops(month.incoming_stroops).muted()
+        );
+        println!(
+            "  {:7}  {} {}",
+            "",
+            bar(month.outgoing_stroops, largest).error(),
+            format_stroops(month.outgoing_stroops).muted()
+        );
+    }
+    println!();
+}
+
+fn shade(count: i64, peak: i64) -> char {
+    if count == 0 || peak == 0 {
+        return SHADES[0];
+    }
+    // Rounded up, so any activity gets at least the lightest shade.
+    let darkest = SHADES.len() as i64 - 1;
+    let level = (count * darkest + peak - 1) / peak;
+    SHADES[level.min(darkest) as usize]
+}
+
+fn bar(value: i64, largest: i64) -> String {
+    if largest == 0 {
+        return String::new();
+    }
+    let width = (value as i128 * BAR_WIDTH as i128 / largest as i128) as usize;
+    "▇".repeat(width.max(usize::from(value > 0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shades_and_bars_scale_to_the_peak() {
+        assert_eq!(shade(0, 10), '·');
+        assert_eq!(shade(1, 10), '░');
+        assert_eq!(shade(10, 10), '█');
+        assert_eq!(bar(0, 100), "");
+        assert_eq!(bar(1, 1_000_000).chars().count(), 1);
+        assert_eq!(bar(100, 100).chars().count(), BAR_WIDTH as usize);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::handlers::audit_handler::AuditHandler;
+use crate::handlers::health_handler::HealthHandler;
+use crate::handlers::policies_handler::PoliciesHandler;
+use crate::handlers::reports_handler::ReportsHandler;
+use crate::models::role::{Permission, Role};
+use crate::models::user::UserResponse;
+use crate::services::admin_service::AdminService;
+use crate::services::sms_service::SmsService;
+use crate::services::user_service::UserService;
+use crate::stellar::network::Network;
+use colored::Colorize;
+
+/// The dashboard's administration menu, for support and admin accounts.
+/// Every entry is checked against the user's current role before it runs.
+pub struct AdminHandler {
+    admin_service: AdminService,
+    user_service: UserService,
+    sms_service: SmsService,
+    audit_handler: AuditHandler,
+    reports_handler: ReportsHandler,
+    health_handler: HealthHandler,
+    policies_handler: PoliciesHandler,
+}
+
+impl AdminHandler {
+    pub fn new(db: SqliteDatabase, network: Network) -> Self {
+        Self {
+            admin_service: AdminService::new(db.clone()),
+            user_service: UserService::new(db.clone()),
+            sms_service: SmsService::new(db.clone()),
+            audit_handler: AuditHandler::new(db.clone()),
+            reports_handler: ReportsHandler::new(db.clone()),
+            health_handler: HealthHandler::new(db.clone(), network),
+            policies_handler: PoliciesHandler::new(db),
+        }
+    }
+
+    pub async fn admin_interactive(&self, user: &UserResponse) -> Result<()> {
+        const ENTRIES: [(&str, &str, Permission); 7] = [
+            ("1", "👥 Users", Permission::ViewUsers),
+            ("2", "🎭 Change a Role", Permission::ManageUsers),
+            ("3", "⛔ Disable or Enable an Account", Permission::ManageUsers),
+            ("4", "🧾 Audit Log", Permission::ViewAuditLog),
+            ("5", "📊 Database Stats & Reports", Permission::ViewReports),
+            ("6", "🩺 Wallet Health Sweep", Permission::RunHealthSweep),
+            ("7", "🛡️  Operator Policies", Permission::ManagePolicies),
+        ];
+
+        loop {
+            println!();
+            println!("{}", format!("🛠️  Administration ({})", user.role.as_str()).heading());
+            println!();
+            for (key, label, permission) in ENTRIES {
+                let line = format!("  {}. {}", key, label);
+                if user.role.allows(permission) {
+                    println!("{}", line);
+                } else {
+                    println!("{}", format!("{} (admins only)", line).muted());
+                }
+            }
+            println!("  8. ↩️  Back");
+            println!();
+
+            let choice = CLI::get_input("Enter your choice:")?;
+            if choice == "8" {
+                return Ok(());
+            }
+            let Some((_, _, permission)) = ENTRIES.iter().find(|(key, _, _)| *key == choice) else {
+                CLI::print_error("Invalid choice. Please try again.");
+                continue;
+            };
+
+            if let Err(e) = self.run(&choice, *permission, user).await {
+                CLI::print_error(&format!("Error: {}", e));
+            }
+            if choice != "5" && choice != "7" {
+                CLI::wait_for_enter();
+            }
+        }
+    }
+
+    async fn run(&self, choice: &str, permission: Permission, user: &UserResponse) -> Result<()> {
+        self.admin_service.authorize(&user.id, permission).await?;
+
+        match choice {
+            "1" => self.list_users(user).await,
+            "2" => self.change_role_interactive(user).await,
+            "3" => self.toggle_disabled_interactive(user).await,
+            "4" => self.audit_handler.audit_log_interactive().await,
+            "5" => {
+                self.show_stats().await?;
+                self.reports_handler.reports_interactive().await
+            }
+            "6" => self.health_handler.sweep_interactive().await,
+            _ => self.policies_handler.manage_policies_interactive(None).await,
+        }
+    }
+
+    async fn list_users(&self, user: &UserResponse) -> Result<()> {
+        let users = self.admin_service.users(&user.id).await?;
+
+        println!();
+        println!("{}", "👥 Users".heading());
+        println!();
+        for user in &users {
+            let status = match user.disabled_at {
+                Some(at) => format!("disabled {}", at.format("%Y-%m-%d %H:%M UTC")).error(),
+                None => "active".success(),
+            };
+            println!("  {:<20} {:<8} {}  {}", user.username.bold(), user.role.as_str(), status, user.email.muted());
+        }
+        println!();
+        CLI::print_info(&format!("{} user(s)", users.len()));
+        Ok(())
+    }
+
+    async fn change_role_interactive(&self, user: &UserResponse) -> Result<()> {
+        let target = self.target_interactive().await?;
+        let roles: Vec<_> = Role::ALL.iter().map(Role::as_str).collect();
+        let input = CLI::get_input(&format!("🎭 New role for {} ({}, now {}):", target.username, roles.join("/"), target.role.as_str()))?;
+        let role = Role::parse(&input.to_lowercase())
+            .ok_or_else(|| AppError::ValidationError(format!("Unknown role '{}'", input)))?;
+
+        let updated = self.admin_service.set_role(&user.id, &target.id, role).await?;
+        CLI::print_success(&format!("{} is now {}.", updated.username, updated.role.as_str()));
+        Ok(())
+    }
+
+    async fn toggle_disabled_interactive(&self, user: &UserResponse) -> Result<()> {
+        let target = self.target_interactive().await?;
+        let disable = target.disabled_at.is_none();
+        let prompt = if disable {
+            format!("Disable {}? They will be logged out everywhere and can't log in again until enabled.", target.username)
+        } else {
+            format!("Enable {} again?", target.username)
+        };
+        if !CLI::confirm_action(&prompt)? {
+            CLI::print_info("Cancelled.");
+            return Ok(());
+        }
+
+        let updated = self.admin_service.set_disabled(&user.id, &target.id, disable).await?;
+        if updated.disabled_at.is_some() {
+            CLI::print_success(&format!("{} is disabled.", updated.username));
+        } else {
+            CLI::print_success(&format!("{} can log in again.", updated.username));
+        }
+        Ok(())
+    }
+
+    async fn show_stats(&self) -> Result<()> {
+        let user_count = self.user_service.get_user_count().await?;
+
+        println!();
+        println!("{}", "📊 Database Statistics:".heading());
+        println!("👥 Total Users: {}", user_count);
+        if self.sms_service.is_enabled() {
+            let usage = self.sms_service.usage_since(chrono::Utc::now() - chrono::Duration::days(30)).await?;
+            println!("📱 SMS sent (30 days): {} (${:.2})", usage.messages, usage.cost_usd);
+        }
+        println!();
+
+        Ok(())
+    }
+
+    async fn target_interactive(&self) -> Result<UserResponse> {
+        let identifier = CLI::get_input("👤 Email or username:")?;
+        self.user_service
+            .find_user(&identifier)
+            .await?
+            .ok_or_else(|| AppError::ValidationError(format!("No user '{}'", identifier)))
+    }
+}
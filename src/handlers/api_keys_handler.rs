@@ -0,0 +1,160 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::{ApiKey, ApiScope};
+use crate::models::user::UserResponse;
+use crate::services::api_key_service::{ApiKeyService, DEFAULT_API_KEY_DAYS};
+use chrono::{Duration, Utc};
+use colored::Colorize;
+use secrecy::ExposeSecret;
+
+pub struct ApiKeysHandler {
+    api_key_service: ApiKeyService,
+}
+
+impl ApiKeysHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            api_key_service: ApiKeyService::new(db),
+        }
+    }
+
+    pub async fn manage_api_keys_interactive(&self, user: &UserResponse) -> Result<()> {
+        loop {
+            let keys = self.api_key_service.keys(&user.id).await?;
+
+            println!();
+            println!("{}", "🗝️  API Keys".heading());
+            if keys.is_empty() {
+                CLI::print_info("No API keys yet.");
+            }
+            for (index, key) in keys.iter().enumerate() {
+                print_key(index + 1, key);
+            }
+            println!();
+            println!("  1. ➕ Create a key");
+            println!("  2. 🗑️  Revoke a key");
+            println!("  3. 🔎 Check a key");
+            println!("  4. ↩️  Back");
+            println!();
+
+            let result = match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => self.create_interactive(user).await,
+                "2" => self.revoke_interactive(user, &keys).await,
+                "3" => self.check_interactive(user).await,
+                "4" => return Ok(()),
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    continue;
+                }
+            };
+            if let Err(e) = result {
+                CLI::print_error(&e.to_string());
+            }
+        }
+    }
+
+    async fn create_interactive(&self, user: &UserResponse) -> Result<()> {
+        let name = CLI::get_input("🏷️  Name, e.g. the integration using it:")?;
+
+        println!("{}", "Scopes:".muted());
+        for scope in ApiScope::ALL {
+            println!("{}", format!("  {:<15} {}", scope.as_str(), scope.description()).muted());
+        }
+        let input = CLI::get_input("🔒 Scopes, comma-separated (Enter for read:account):")?;
+        let scopes = if input.is_empty() {
+            vec![ApiScope::Read]
+        } else {
+            let names: Vec<_> = input.split(',').map(|scope| scope.trim().to_lowercase()).collect();
+            ApiScope::parse_list(names.iter().map(String::as_str))
+                .map_err(|scope| AppError::ValidationError(format!("Unknown scope '{}'", scope)))?
+        };
+
+        let input = CLI::get_input(&format!("⏳ Expires in how many days (Enter for {}, 0 for never):", DEFAULT_API_KEY_DAYS))?;
+        let days = if input.is_empty() {
+            DEFAULT_API_KEY_DAYS
+        } else {
+            input
+                .parse::<i64>()
+                .ok()
+                .filter(|days| *days >= 0)
+                .ok_or_else(|| AppError::ValidationError(format!("Invalid number of days '{}'", input)))?
+        };
+        let expires_in = (days > 0).then(|| Duration::days(days));
+
+        println!();
+        CLI::print_grants(&scopes);
+        if !CLI::confirm_action(&format!("Create '{}' with these scopes?", name.trim()))? {
+            return Ok(());
+        }
+
+        let (key, token) = self.api_key_service.create(&user.id, &name, &scopes, expires_in).await?;
+        println!();
+        CLI::print_success(&format!("Created '{}'. Copy the key now; it won't be shown again:", key.name));
+        println!();
+        println!("  {}", token.bold());
+        println!();
+        Ok(())
+    }
+
+    async fn revoke_interactive(&self, user: &UserResponse, keys: &[ApiKey]) -> Result<()> {
+        if keys.is_empty() {
+            CLI::print_info("There are no keys to revoke.");
+            return Ok(());
+        }
+
+        let choice = CLI::get_input("Key number to revoke:")?;
+        let key = match choice.parse::<usize>() {
+            Ok(index) if (1..=keys.len()).contains(&index) => &keys[index - 1],
+            _ => return Err(AppError::ValidationError("Please enter one of the listed numbers".to_string())),
+        };
+        if !CLI::confirm_action(&format!("Revoke '{}'? Anything using it will stop working.", key.name))? {
+            return Ok(());
+        }
+
+        self.api_key_service.revoke(&user.id, key).await?;
+        CLI::print_success(&format!("Revoked '{}'.", key.name));
+        Ok(())
+    }
+
+    /// Tries a key the way an integration would, to see whether it still works.
+    async fn check_interactive(&self, user: &UserResponse) -> Result<()> {
+        let token = CLI::get_password("🗝️  API key:")?;
+        let input = CLI::get_input("🔒 Scope to check (Enter for read:account):")?;
+        let scope = if input.is_empty() {
+            ApiScope::Read
+        } else {
+            ApiScope::parse(&input.to_lowercase()).ok_or_else(|| AppError::ValidationError(format!("Unknown scope '{}'", input)))?
+        };
+
+        let key = self.api_key_service.authenticate(token.expose_secret(), scope).await?;
+        if key.user_id == user.id {
+            CLI::print_success(&format!("'{}' works for {}.", key.name, scope.as_str()));
+        } else {
+            CLI::print_success(&format!("The key works for {}, but it belongs to another account.", scope.as_str()));
+        }
+        Ok(())
+    }
+}
+
+fn print_key(number: usize, key: &ApiKey) {
+    let scopes: Vec<_> = key.scopes.iter().map(ApiScope::as_str).collect();
+    let expired = !key.is_active(Utc::now());
+    let title = format!("  {}. {} {}… [{}]", number, key.name.bold(), key.prefix, scopes.join(", "));
+    if expired {
+        println!("{} {}", title, "(expired)".error());
+    } else {
+        println!("{}", title);
+    }
+
+    let expires = match key.expires_at {
+        Some(at) => format!("expires {}", at.format("%Y-%m-%d %H:%M UTC")),
+        None => "never expires".to_string(),
+    };
+    let used = match key.last_used_at {
+        Some(at) => format!("last used {}", at.format("%Y-%m-%d %H:%M UTC")),
+        None => "never used".to_string(),
+    };
+    println!("     {}", format!("Created {} · {} · {}", key.created_at.format("%Y-%m-%d %H:%M UTC"), used, expires).muted());
+}
@@ -0,0 +1,99 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditFilter;
+use crate::services::audit_service::AuditService;
+use crate::services::user_service::UserService;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use colored::Colorize;
+
+const AUDIT_VIEW_LIMIT: u32 = 100;
+
+/// Operator view of the audit log.
+pub struct AuditHandler {
+    audit_service: AuditService,
+    user_service: UserService,
+}
+
+impl AuditHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            user_service: UserService::new(db),
+        }
+    }
+
+    pub async fn audit_log_interactive(&self) -> Result<()> {
+        println!();
+        println!("{}", "🧾 Audit Log".heading());
+        println!();
+
+        let identifier = CLI::get_input("👤 Email or username (empty for everyone):")?;
+        let user_id = if identifier.is_empty() {
+            None
+        } else {
+            let user = self
+                .user_service
+                .find_user(&identifier)
+                .await?
+                .ok_or_else(|| AppError::ValidationError(format!("No user '{}'", identifier)))?;
+            Some(user.id)
+        };
+
+        let since = day_start(&CLI::get_input("📅 From date, YYYY-MM-DD (optional):")?)?;
+        // The end date is inclusive, so stop at the start of the following day.
+        let until = day_start(&CLI::get_input("📅 To date, YYYY-MM-DD (optional):")?)?
+            .map(|day| day.checked_add_days(Days::new(1)).unwrap_or(day));
+
+        let entries = self
+            .audit_service
+            .entries(&AuditFilter {
+                user_id,
+                since,
+                until,
+                limit: AUDIT_VIEW_LIMIT,
+            })
+            .await?;
+
+        println!();
+        if entries.is_empty() {
+            CLI::print_info("No matching events.");
+            return Ok(());
+        }
+
+        for entry in &entries {
+            let user = entry.user_id.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string());
+            println!(
+                "  {}  {:<16} {}",
+                entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+                entry.event_type.bold(),
+                user.muted()
+            );
+            match &entry.request_id {
+                Some(request_id) => {
+                    let request = format!("[request {}]", request_id);
+                    println!("{:>23}{} {}", "", entry.details, request.as_str().muted());
+                }
+                None => println!("{:>23}{}", "", entry.details),
+            }
+        }
+        println!();
+        if entries.len() as u32 == AUDIT_VIEW_LIMIT {
+            CLI::print_info(&format!("Showing the newest {} events; narrow the filter to see older ones.", AUDIT_VIEW_LIMIT));
+        }
+
+        Ok(())
+    }
+}
+
+fn day_start(input: &str) -> Result<Option<DateTime<Utc>>> {
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    let day = NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map_err(|_| AppError::ValidationError(format!("Invalid date '{}', expected YYYY-MM-DD", input)))?;
+
+    Ok(Some(day.and_hms_opt(0, 0, 0).unwrap().and_utc()))
+}
@@ -0,0 +1,114 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::errors::{AppError, Result};
+use crate::models::user::UserResponse;
+use crate::database::sqlite::SqliteDatabase;
+use crate::services::asset_metadata_service::AssetMetadataService;
+use crate::services::offline_service::{Balances, OfflineService};
+use crate::services::price_service::PriceService;
+use crate::services::settings_service::SettingsService;
+use crate::stellar::horizon::BalanceRecord;
+use crate::stellar::network::Network;
+use colored::Colorize;
+
+pub struct BalancesHandler {
+    offline_service: OfflineService,
+    asset_metadata_service: AssetMetadataService,
+    settings_service: SettingsService,
+    price_service: PriceService,
+}
+
+impl BalancesHandler {
+    pub fn new(db: SqliteDatabase, network: Network, price_service: PriceService) -> Self {
+        Self {
+            asset_metadata_service: AssetMetadataService::new(&network),
+            offline_service: OfflineService::new(db.clone(), &network),
+            settings_service: SettingsService::new(db),
+            price_service,
+        }
+    }
+
+    pub async fn show_balances_interactive(&self, user: &UserResponse) -> Result<()> {
+        let address = user.stellar_public_key.as_deref().ok_or_else(|| {
+            AppError::StellarError("No Stellar address is linked to this account yet".to_string())
+        })?;
+
+        println!();
+        println!("{}", "💰 Balances".heading());
+        println!("📍 {}", address);
+        println!();
+
+        let balances = match self.offline_service.balances(address).await? {
+            Balances::Live(balances) => balances,
+            Balances::Unfunded => {
+                CLI::print_info("This address isn't funded yet. Receive at least 1 XLM to activate it.");
+                return Ok(());
+            }
+            Balances::Saved { snapshot, error } => {
+                println!(
+                    "{}",
+                    format!(
+                        "📴 Offline: showing balances saved {}; couldn't reach Horizon ({})",
+                        snapshot.fetched_at.format("%Y-%m-%d %H:%M UTC"),
+                        error
+                    )
+                    .warning()
+                );
+                println!();
+                snapshot.balances
+            }
+        };
+
+        let currency = self.settings_service.settings(&user.id).await?.fiat_currency;
+
+        for balance in &balances {
+            self.print_balance(balance, &currency).await;
+        }
+        println!();
+
+        Ok(())
+    }
+
+    async fn print_balance(&self, balance: &BalanceRecord, currency: &str) {
+        let (code, issuer) = match (&balance.asset_code, &balance.asset_issuer) {
+            (Some(code), Some(issuer)) => (code, issuer),
+            _ if balance.asset_type == "native" => {
+                let fiat = self.fiat_suffix(&balance.balance, "XLM", currency).await;
+                println!("  {} XLM (Stellar Lumens){}", format!("{:>20}", balance.balance).bold(), fiat);
+                return;
+            }
+            _ => {
+                println!("  {} {}", format!("{:>20}", balance.balance).bold(), balance.asset_type);
+                return;
+            }
+        };
+
+        let fiat = self.fiat_suffix(&balance.balance, code, currency).await;
+        println!("  {} {}{}", format!("{:>20}", balance.balance).bold(), code, fiat);
+
+        match self.asset_metadata_service.resolve(code, issuer).await {
+            Ok(Some(metadata)) => {
+                let issuer_name = metadata.org_name.as_deref().unwrap_or(&metadata.home_domain);
+                println!("{:>24}✅ {} · {} ({})", "", metadata.display_name().success(), issuer_name, metadata.home_domain);
+                if let Some(image) = &metadata.image {
+                    println!("{:>24}🖼️  {}", "", image.muted());
+                }
+                if let Some(server) = metadata.transfer_server_sep0024.as_ref().or(metadata.transfer_server.as_ref()) {
+                    println!("{:>24}🏦 Anchor: {}", "", server.muted());
+                }
+                if let Some(auth) = &metadata.web_auth_endpoint {
+                    println!("{:>24}🔐 Web auth: {}", "", auth.muted());
+                }
+            }
+            Ok(None) => println!("{:>24}{} {}", "", "⚠️  Unverified issuer".warning(), issuer.muted()),
+            Err(e) => println!("{:>24}{} ({})", "", "⚠️  Could not verify issuer".warning(), e),
+        }
+    }
+
+    async fn fiat_suffix(&self, amount: &str, asset_code: &str, currency: &str) -> String {
+        match self.price_service.fiat_label(amount, asset_code, currency).await {
+            Some(label) => format!("  {}", label.muted()),
+            None => String::new(),
+        }
+    }
+}
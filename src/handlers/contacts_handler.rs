@@ -0,0 +1,291 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::contact::{Contact, CreateContactRequest};
+use crate::models::contact_import::{ContactField, FieldMapping, ImportFormat, ImportTable};
+use crate::models::undo::LocalAction;
+use crate::models::user::UserResponse;
+use crate::services::contact_import_service::{guess_column, ContactImportService};
+use crate::services::contact_service::ContactService;
+use crate::services::undo_service::UndoService;
+use crate::utils::validation::Validator;
+use colored::Colorize;
+use std::fs;
+use std::rc::Rc;
+
+pub struct ContactsHandler {
+    contact_service: ContactService,
+    contact_import_service: ContactImportService,
+    undo_service: Rc<UndoService>,
+}
+
+impl ContactsHandler {
+    pub fn new(db: SqliteDatabase, undo_service: Rc<UndoService>) -> Self {
+        Self {
+            contact_service: ContactService::new(db.clone()),
+            contact_import_service: ContactImportService::new(db),
+            undo_service,
+        }
+    }
+
+    pub async fn manage_contacts_interactive(&self, user: &UserResponse) -> Result<()> {
+        loop {
+            println!();
+            println!("{}", "📒 Contacts:".heading());
+            println!("  1. 📋 List Contacts");
+            println!("  2. ➕ Add Contact");
+            println!("  3. 🗑️  Delete Contact");
+            println!("  4. 📥 Import from CSV / vCard");
+            println!("  5. ↩️  Back");
+            println!();
+
+            let choice = CLI::get_input("Enter your choice:")?;
+
+            let result = match choice.as_str() {
+                "1" => self.list_contacts_interactive(user).await,
+                "2" => self.add_contact_interactive(user).await,
+                "3" => self.delete_contact_interactive(user).await,
+                "4" => self.import_contacts_interactive(user).await,
+                "5" => return Ok(()),
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    continue;
+                }
+            };
+
+            if let Err(e) = result {
+                CLI::print_error(&format!("Error: {}", e));
+            }
+        }
+    }
+
+    async fn list_contacts_interactive(&self, user: &UserResponse) -> Result<()> {
+        let contacts = self.contact_service.list_contacts(&user.id).await?;
+
+        if contacts.is_empty() {
+            CLI::print_info("Your address book is empty.");
+            return Ok(());
+        }
+
+        Self::print_contacts(&contacts);
+        Ok(())
+    }
+
+    async fn add_contact_interactive(&self, user: &UserResponse) -> Result<()> {
+        println!();
+
+        let name = loop {
+            let name = CLI::get_input("👤 Contact name:")?;
+
+            match Validator::validate_contact_name(&name) {
+                Ok(()) => break name,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let address = loop {
+            let address = CLI::get_input("🏦 Stellar address (G...):")?;
+
+            match Validator::validate_stellar_address(&address) {
+                Ok(()) => break address,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let memo = loop {
+            let memo = CLI::get_input("📝 Default memo (optional):")?;
+
+            if memo.is_empty() {
+                break None;
+            }
+
+            match Validator::validate_memo(&memo) {
+                Ok(()) => break Some(memo),
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let federation_name = loop {
+            let federation_name = CLI::get_input("🌐 Federation name, e.g. bob*example.com (optional):")?;
+
+            if federation_name.is_empty() {
+                break None;
+            }
+
+            match Validator::validate_federation_name(&federation_name) {
+                Ok(()) => break Some(federation_name),
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let request = CreateContactRequest {
+            name,
+            address,
+            memo,
+            federation_name,
+        };
+
+        let contact = self.contact_service.add_contact(&user.id, request).await?;
+        CLI::print_success(&format!("Contact '{}' added.", contact.name));
+        self.undo_service.record(LocalAction::ContactAdded(contact));
+        Ok(())
+    }
+
+    async fn delete_contact_interactive(&self, user: &UserResponse) -> Result<()> {
+        let Some(contact) = self.pick_contact(user).await? else {
+            return Ok(());
+        };
+
+        if !CLI::confirm_action(&format!("Delete contact '{}'?", contact.name))? {
+            CLI::print_info("Nothing was deleted.");
+            return Ok(());
+        }
+
+        self.contact_service.delete_contact(&user.id, &contact.id).await?;
+        CLI::print_success(&format!("Contact '{}' deleted.", contact.name));
+        self.undo_service.record(LocalAction::ContactDeleted(contact));
+        Ok(())
+    }
+
+    async fn import_contacts_interactive(&self, user: &UserResponse) -> Result<()> {
+        let path = CLI::get_input("📄 CSV or vCard file to import:")?;
+        let text = fs::read_to_string(&path).map_err(|e| AppError::ValidationError(format!("Can't read {}: {}", path, e)))?;
+        let format = ImportFormat::detect(&path, &text);
+        let table = ContactImportService::parse(format, &text)?;
+
+        if table.rows.is_empty() {
+            CLI::print_info("The file has no contacts in it.");
+            return Ok(());
+        }
+
+        println!();
+        println!("{}", format!("{} file with {} row(s). Columns:", format.as_str(), table.rows.len()).heading());
+        for (index, column) in table.columns.iter().enumerate() {
+            let sample = table.rows.iter().map(|row| table.cell(row, index)).find(|value| !value.is_empty()).unwrap_or("");
+            println!("  {}. {} {}", index + 1, column, format!("(e.g. {})", sample).muted());
+        }
+        println!();
+
+        let mapping = FieldMapping {
+            name: Self::pick_column(&table, ContactField::Name)?.unwrap_or_default(),
+            address: Self::pick_column(&table, ContactField::Address)?.unwrap_or_default(),
+            memo: Self::pick_column(&table, ContactField::Memo)?,
+            federation_name: Self::pick_column(&table, ContactField::FederationName)?,
+        };
+
+        let plan = self.contact_import_service.plan(&user.id, &table, &mapping).await?;
+
+        println!();
+        println!("{}", "🔎 Preview (nothing saved yet)".heading());
+        for request in &plan.to_add {
+            println!("  {} {} {}", "+".success(), request.name, request.address.muted());
+        }
+        for duplicate in &plan.duplicates {
+            println!(
+                "  {} {} {}",
+                "=".muted(),
+                duplicate.name,
+                format!("already saved as '{}'", duplicate.existing_name).muted()
+            );
+        }
+        for (row, reason) in &plan.invalid {
+            println!("  {} row {}: {}", "!".warning(), row, reason);
+        }
+        println!();
+        println!(
+            "{} to add, {} duplicate(s) skipped, {} invalid row(s) skipped",
+            plan.to_add.len(),
+            plan.duplicates.len(),
+            plan.invalid.len()
+        );
+
+        if plan.to_add.is_empty() {
+            CLI::print_info("Nothing new to import.");
+            return Ok(());
+        }
+
+        if !CLI::confirm_action(&format!("Import {} contact(s)?", plan.to_add.len()))? {
+            CLI::print_info("Dry run only; nothing was imported.");
+            return Ok(());
+        }
+
+        let added = self.contact_import_service.import(&user.id, plan).await?;
+        CLI::print_success(&format!("Imported {} contact(s).", added.len()));
+        Ok(())
+    }
+
+    /// Asks which column holds `field`, offering the guessed one as the
+    /// default. Optional fields can be left unmapped with `-`.
+    fn pick_column(table: &ImportTable, field: ContactField) -> Result<Option<usize>> {
+        let guess = guess_column(table, field);
+
+        loop {
+            let default = match guess {
+                Some(column) => format!("{}", column + 1),
+                None if field.is_required() => String::new(),
+                None => "-".to_string(),
+            };
+            let prompt = match (field.is_required(), default.is_empty()) {
+                (true, true) => format!("{} column:", field.label()),
+                (true, false) => format!("{} column [{}]:", field.label(), default),
+                (false, _) => format!("{} column, or - for none [{}]:", field.label(), default),
+            };
+
+            let choice = CLI::get_input(&prompt)?;
+            let choice = if choice.is_empty() { default } else { choice };
+
+            if choice == "-" && !field.is_required() {
+                return Ok(None);
+            }
+
+            match choice.parse::<usize>() {
+                Ok(column) if (1..=table.columns.len()).contains(&column) => return Ok(Some(column - 1)),
+                _ => CLI::print_error("Please enter one of the listed column numbers"),
+            }
+        }
+    }
+
+    /// Lets the user choose one of their contacts by number. Returns `None` if
+    /// they have no contacts or leave the choice empty.
+    pub async fn pick_contact(&self, user: &UserResponse) -> Result<Option<Contact>> {
+        let mut contacts = self.contact_service.list_contacts(&user.id).await?;
+
+        if contacts.is_empty() {
+            CLI::print_info("Your address book is empty.");
+            return Ok(None);
+        }
+
+        Self::print_contacts(&contacts);
+
+        loop {
+            let choice = CLI::get_input("Choose a contact number (empty to cancel):")?;
+
+            if choice.is_empty() {
+                return Ok(None);
+            }
+
+            match choice.parse::<usize>() {
+                Ok(index) if (1..=contacts.len()).contains(&index) => {
+                    return Ok(Some(contacts.swap_remove(index - 1)));
+                }
+                _ => CLI::print_error("Please enter one of the listed numbers"),
+            }
+        }
+    }
+
+    fn print_contacts(contacts: &[Contact]) {
+        println!();
+        for (index, contact) in contacts.iter().enumerate() {
+            println!("  {}. {} {}", index + 1, "👤".bold(), contact.name.bold());
+            println!("     🏦 {}", contact.address);
+            if let Some(federation_name) = &contact.federation_name {
+                println!("     🌐 {}", federation_name);
+            }
+            if let Some(memo) = &contact.memo {
+                println!("     📝 {}", memo);
+            }
+        }
+        println!();
+    }
+}
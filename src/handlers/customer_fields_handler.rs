@@ -0,0 +1,104 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::customer_field::SEP9_FIELDS;
+use crate::models::user::UserResponse;
+use crate::services::customer_field_service::CustomerFieldService;
+
+pub struct CustomerFieldsHandler {
+    customer_field_service: CustomerFieldService,
+}
+
+impl CustomerFieldsHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            customer_field_service: CustomerFieldService::new(db),
+        }
+    }
+
+    pub async fn manage_customer_fields_interactive(&self, user: &UserResponse) -> Result<()> {
+        loop {
+            println!();
+            println!("{}", "🪪 KYC Details (SEP-9):".heading());
+            println!("  1. 📋 List Stored Fields");
+            println!("  2. ✏️  Set a Field");
+            println!("  3. 🗑️  Remove a Field");
+            println!("  4. 🔍 Preview an Anchor Request");
+            println!("  5. ↩️  Back");
+            println!();
+
+            let result = match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => self.list_interactive(user).await,
+                "2" => self.set_interactive(user).await,
+                "3" => self.remove_interactive(user).await,
+                "4" => self.preview_interactive(user).await,
+                "5" => return Ok(()),
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    continue;
+                }
+            };
+
+            if let Err(e) = result {
+                CLI::print_error(&format!("Error: {}", e));
+            }
+        }
+    }
+
+    async fn list_interactive(&self, user: &UserResponse) -> Result<()> {
+        let fields = self.customer_field_service.stored_fields(&user.id).await?;
+
+        if fields.is_empty() {
+            CLI::print_info("No KYC details stored yet.");
+            return Ok(());
+        }
+
+        // Values stay encrypted; only names are shown here.
+        println!();
+        for field in fields {
+            println!("  🔒 {}", field);
+        }
+        println!();
+        Ok(())
+    }
+
+    async fn set_interactive(&self, user: &UserResponse) -> Result<()> {
+        println!("{}", format!("Fields: {}", SEP9_FIELDS.join(", ")).muted());
+        let field_name = CLI::get_input("🏷️  Field name:")?;
+        let value = CLI::get_input("✏️  Value:")?;
+
+        self.customer_field_service.set_field(&user.id, &field_name, &value).await?;
+        CLI::print_success(&format!("'{}' saved encrypted.", field_name.trim().to_lowercase()));
+        Ok(())
+    }
+
+    async fn remove_interactive(&self, user: &UserResponse) -> Result<()> {
+        let field_name = CLI::get_input("🏷️  Field name to remove:")?;
+
+        self.customer_field_service.remove_field(&user.id, &field_name).await?;
+        CLI::print_success(&format!("'{}' removed.", field_name.trim()));
+        Ok(())
+    }
+
+    /// Shows exactly what would be shared for an anchor's list of required fields.
+    async fn preview_interactive(&self, user: &UserResponse) -> Result<()> {
+        let required: Vec<String> = CLI::get_input("📨 Fields the anchor requires (comma separated):")?
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        let disclosure = self.customer_field_service.disclose(&user.id, &required).await?;
+
+        println!();
+        for (name, value) in &disclosure.provided {
+            println!("  ✅ {}: {}", name, value);
+        }
+        for name in &disclosure.missing {
+            println!("  {} {}", "❌".error(), format!("{} (not stored)", name).muted());
+        }
+        println!();
+        Ok(())
+    }
+}
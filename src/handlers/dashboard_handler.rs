@@ -0,0 +1,491 @@
+use crate::cli::branding::Branding;
+use crate::cli::CLI;
+use crate::cli::theme::{self, ThemeName, Themed};
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::handlers::accounts_handler::AccountsHandler;
+use crate::handlers::admin_handler::AdminHandler;
+use crate::handlers::balances_handler::BalancesHandler;
+use crate::handlers::contacts_handler::ContactsHandler;
+use crate::handlers::history_handler::HistoryHandler;
+use crate::handlers::payment_handler::PaymentHandler;
+use crate::handlers::payment_notes_handler::PaymentNotesHandler;
+use crate::handlers::settings_handler::SettingsHandler;
+use crate::handlers::signing_handler::SigningHandler;
+use crate::models::session::Session;
+use crate::models::user::UserResponse;
+use crate::services::hook_service::HookService;
+use crate::services::keystore_service::KeystoreService;
+use crate::services::offline_service::OfflineService;
+use crate::services::price_service::PriceService;
+use crate::services::session_service::SessionService;
+use crate::services::undo_service::UndoService;
+use crate::services::user_service::UserService;
+use crate::stellar::network::Network;
+use crate::stellar::sep7::PaymentRequest;
+use crate::stellar::vanity::{VanityGenerator, VanityProgress, MAX_SUFFIX_LEN};
+use crate::utils::qr::QrRenderer;
+use colored::Colorize;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use secrecy::ExposeSecret;
+
+/// Keys that open a dashboard entry on their own, with the entry they stand
+/// for and the name shown in the legend.
+const SHORTCUTS: [(char, &str, &str); 7] = [
+    ('r', "1", "receive"),
+    ('s', "4", "send"),
+    ('b', "5", "balances"),
+    ('h', "8", "history"),
+    ('q', "11", "logout"),
+    ('u', "12", "undo"),
+    ('y', "13", "redo"),
+];
+
+pub struct DashboardHandler {
+    user: UserResponse,
+    session: Session,
+    user_service: UserService,
+    session_service: SessionService,
+    keystore_service: KeystoreService,
+    contacts_handler: ContactsHandler,
+    payment_handler: PaymentHandler,
+    accounts_handler: AccountsHandler,
+    balances_handler: BalancesHandler,
+    signing_handler: SigningHandler,
+    history_handler: HistoryHandler,
+    payment_notes_handler: PaymentNotesHandler,
+    settings_handler: SettingsHandler,
+    admin_handler: AdminHandler,
+    undo_service: Rc<UndoService>,
+    offline_service: OfflineService,
+    /// Payments queued while offline, as of the last menu refresh.
+    queued_payments: usize,
+    /// Whether the user was already asked about the queue since reconnecting.
+    queue_offered: bool,
+    network: Network,
+}
+
+impl DashboardHandler {
+    pub fn new(user: UserResponse, session: Session, db: SqliteDatabase, network: Network, hooks: Arc<HookService>) -> Self {
+        let price_service = PriceService::from_env();
+        // Lives only as long as this login; nothing is kept across sessions.
+        let undo_service = Rc::new(UndoService::new(db.clone()));
+
+        Self {
+            user,
+            session,
+            user_service: UserService::new(db.clone()),
+            session_service: SessionService::new(db.clone()),
+            keystore_service: KeystoreService::new(db.clone()),
+            offline_service: OfflineService::new(db.clone(), &network),
+            contacts_handler: ContactsHandler::new(db.clone(), undo_service.clone()),
+            payment_handler: PaymentHandler::new(db.clone(), network.clone(), price_service.clone(), hooks.clone()),
+            accounts_handler: AccountsHandler::new(db.clone(), network.clone(), hooks),
+            balances_handler: BalancesHandler::new(db.clone(), network.clone(), price_service),
+            signing_handler: SigningHandler::new(db.clone(), network.clone()),
+            history_handler: HistoryHandler::new(db.clone(), network.clone(), undo_service.clone()),
+            payment_notes_handler: PaymentNotesHandler::new(db.clone()),
+            admin_handler: AdminHandler::new(db.clone(), network.clone()),
+            settings_handler: SettingsHandler::new(db, undo_service.clone()),
+            undo_service,
+            queued_payments: 0,
+            queue_offered: false,
+            network,
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        self.settings_handler.apply_saved_theme(&self.user.id).await;
+        let result = self.menu_loop().await;
+        // The next person at the login screen shouldn't inherit this user's colors.
+        theme::apply(ThemeName::Default);
+        result
+    }
+
+    async fn menu_loop(&mut self) -> Result<()> {
+        loop {
+            // Sessions can expire or be revoked from another device mid-use.
+            self.session = match self.session_service.validate(&self.session.id).await {
+                Ok(session) => session,
+                Err(e) => {
+                    CLI::print_info(&e.to_string());
+                    return Ok(());
+                }
+            };
+
+            if let Err(e) = self.offer_queued_payments().await {
+                CLI::print_error(&format!("Error: {}", e));
+                CLI::wait_for_enter();
+            }
+
+            self.display_menu();
+
+            let keys = SHORTCUTS.map(|(key, _, _)| key);
+            let mut choice = CLI::get_choice("Enter your choice:", &keys)?;
+            if let Some((_, entry, _)) = SHORTCUTS.iter().find(|(key, _, _)| choice == key.to_string()) {
+                choice = entry.to_string();
+            }
+
+            match choice.as_str() {
+                "1" if self.user.stellar_public_key.is_none() => {
+                    CLI::print_info("Generate a wallet address first (option 2) to receive payments.");
+                    CLI::wait_for_enter();
+                }
+                "1" => {
+                    if let Err(e) = self.receive_interactive() {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "2" => {
+                    if let Err(e) = self.vanity_interactive().await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "3" => {
+                    if let Err(e) = self.contacts_handler.manage_contacts_interactive(&self.user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                }
+                "4" if self.user.stellar_public_key.is_none() => {
+                    CLI::print_info("Generate a wallet address first (option 2) to send payments.");
+                    CLI::wait_for_enter();
+                }
+                "4" => {
+                    if let Err(e) = self
+                        .payment_handler
+                        .send_payment_interactive(&self.user, &self.contacts_handler, &self.signing_handler)
+                        .await
+                    {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "5" if self.user.stellar_public_key.is_none() => {
+                    CLI::print_info("Generate a wallet address first (option 2) to see balances.");
+                    CLI::wait_for_enter();
+                }
+                "5" => {
+                    if let Err(e) = self.balances_handler.show_balances_interactive(&self.user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "6" => {
+                    if let Err(e) = self
+                        .accounts_handler
+                        .manage_accounts_interactive(&mut self.user, &self.signing_handler)
+                        .await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "7" => {
+                    if let Err(e) = self.signing_handler.manage_signing_device_interactive(&mut self.user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "8" if self.user.stellar_public_key.is_none() => {
+                    CLI::print_info("Generate a wallet address first (option 2) to see payment history.");
+                    CLI::wait_for_enter();
+                }
+                "8" => {
+                    if let Err(e) = self.history_handler.history_interactive(&self.user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                        CLI::wait_for_enter();
+                    }
+                }
+                "9" => {
+                    if let Err(e) = self.settings_handler.settings_interactive(&mut self.user, &self.session).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                        CLI::wait_for_enter();
+                    }
+                }
+                "10" => {
+                    if let Err(e) = self.payment_notes_handler.notes_interactive(&self.user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "11" => {
+                    self.session_service.logout(&self.user.id, &self.session.id).await?;
+                    CLI::print_info(&format!("👋 Logged out {}.", self.user.username));
+                    return Ok(());
+                }
+                "12" | "undo" => {
+                    match self.undo_service.undo().await {
+                        Ok(Some(done)) => CLI::print_success(&format!("Undid: {}", done)),
+                        Ok(None) => CLI::print_info("Nothing to undo."),
+                        Err(e) => CLI::print_error(&format!("Couldn't undo: {}", e)),
+                    }
+                    self.settings_handler.apply_saved_theme(&self.user.id).await;
+                    CLI::wait_for_enter();
+                }
+                "13" | "redo" => {
+                    match self.undo_service.redo().await {
+                        Ok(Some(done)) => CLI::print_success(&format!("Redid: {}", done)),
+                        Ok(None) => CLI::print_info("Nothing to redo."),
+                        Err(e) => CLI::print_error(&format!("Couldn't redo: {}", e)),
+                    }
+                    self.settings_handler.apply_saved_theme(&self.user.id).await;
+                    CLI::wait_for_enter();
+                }
+                "14" => {
+                    if let Err(e) = self.payment_handler.review_queue_interactive(&self.user, &self.signing_handler).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "15" if self.user.role.is_staff() => {
+                    if let Err(e) = self.admin_handler.admin_interactive(&self.user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                        CLI::wait_for_enter();
+                    }
+                }
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    CLI::wait_for_enter();
+                }
+            }
+        }
+    }
+
+    /// Once Horizon can be reached again, asks once whether to review and send
+    /// the payments queued while offline.
+    async fn offer_queued_payments(&mut self) -> Result<()> {
+        self.queued_payments = self.offline_service.queued_payments(&self.user.id).await?.len();
+        if self.queued_payments == 0 {
+            return Ok(());
+        }
+        if !self.offline_service.is_online().await {
+            self.queue_offered = false;
+            return Ok(());
+        }
+        if self.queue_offered {
+            return Ok(());
+        }
+
+        self.queue_offered = true;
+        println!();
+        let prompt = format!(
+            "🌐 Back online with {} queued payment(s). Review and send them now?",
+            self.queued_payments
+        );
+        if CLI::confirm_action(&prompt)? {
+            self.payment_handler.review_queue_interactive(&self.user, &self.signing_handler).await?;
+            CLI::wait_for_enter();
+            self.queued_payments = self.offline_service.queued_payments(&self.user.id).await?.len();
+        }
+        Ok(())
+    }
+
+    fn display_menu(&self) {
+        CLI::clear_screen();
+        let branding = Branding::current();
+        println!("{}", branding.rule(60));
+        println!("{}", branding.title(&format!("           🌟 Welcome, {} 🌟           ", self.user.username)));
+        println!("{}", branding.rule(60));
+        println!();
+        println!("🌐 Network: {}", self.network.name);
+        println!();
+        println!("{}", "Dashboard:".heading());
+        if self.user.stellar_public_key.is_some() {
+            println!("  1. 📥 Receive Payment");
+        } else {
+            println!("{}", "  1. 📥 Receive Payment (no wallet address yet)".muted());
+        }
+        println!("  2. ✨ Generate Vanity Address");
+        println!("  3. 📒 Contacts");
+        if self.user.stellar_public_key.is_some() {
+            println!("  4. 📤 Send Payment");
+        } else {
+            println!("{}", "  4. 📤 Send Payment (no wallet address yet)".muted());
+        }
+        if self.user.stellar_public_key.is_some() {
+            println!("  5. 💰 Balances");
+        } else {
+            println!("{}", "  5. 💰 Balances (no wallet address yet)".muted());
+        }
+        println!("  6. 🗂️  Derived Accounts");
+        println!("  7. 🔌 Signing Device");
+        if self.user.stellar_public_key.is_some() {
+            println!("  8. 📜 Payment History");
+        } else {
+            println!("{}", "  8. 📜 Payment History (no wallet address yet)".muted());
+        }
+        println!("  9. ⚙️  Settings");
+        println!(" 10. 💬 Payment Notes");
+        println!(" 11. 🚪 Logout");
+        match self.undo_service.next_undo() {
+            Some(action) => println!(" 12. ↶ Undo ({})", action),
+            None => println!("{}", " 12. ↶ Undo (nothing to undo)".muted()),
+        }
+        match self.undo_service.next_redo() {
+            Some(action) => println!(" 13. ↷ Redo ({})", action),
+            None => println!("{}", " 13. ↷ Redo (nothing to redo)".muted()),
+        }
+        match self.queued_payments {
+            0 => println!("{}", " 14. 🕒 Queued Payments (none)".muted()),
+            count => println!(" 14. 🕒 Queued Payments ({})", format!("{} pending, not submitted", count).warning()),
+        }
+        if self.user.role.is_staff() {
+            println!(" 15. 🛠️  Administration");
+        }
+        println!();
+        let legend: Vec<_> = SHORTCUTS.iter().map(|(key, _, name)| format!("[{}] {}", key, name)).collect();
+        println!("{}", format!("Shortcuts: {}", legend.join("  ")).muted());
+        println!();
+    }
+
+    pub fn receive_interactive(&self) -> Result<()> {
+        let address = self.user.stellar_public_key.as_deref().ok_or_else(|| {
+            AppError::StellarError("No Stellar address is linked to this account yet".to_string())
+        })?;
+
+        println!();
+        println!("{}", "📥 Receive Payment".heading());
+        CLI::print_info("Leave the amount or memo empty to let the sender choose.");
+        println!();
+
+        let mut request = PaymentRequest::new(address);
+
+        request = loop {
+            let amount = CLI::get_input("💰 Amount in XLM (optional):")?;
+
+            if amount.is_empty() {
+                break request;
+            }
+
+            match request.clone().with_amount(&amount) {
+                Ok(request) => break request,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        request = loop {
+            let memo = CLI::get_input("📝 Memo (optional):")?;
+
+            if memo.is_empty() {
+                break request;
+            }
+
+            match request.clone().with_memo(&memo) {
+                Ok(request) => break request,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let uri = request.to_uri()?;
+        let qr = QrRenderer::render_terminal(&uri)?;
+
+        println!();
+        println!("{}", "Scan with a SEP-7 compatible wallet:".success().bold());
+        println!("{}", qr);
+        println!("🏦 Address: {}", address);
+        println!("🔗 Payment URI: {}", uri);
+        println!();
+
+        Ok(())
+    }
+
+    pub async fn vanity_interactive(&mut self) -> Result<()> {
+        println!();
+        println!("{}", "✨ Vanity Address Generator".heading());
+        CLI::print_info(&format!(
+            "Pick up to {} characters (A-Z, 2-7) for your address to end with.",
+            MAX_SUFFIX_LEN
+        ));
+        println!();
+
+        let generator = loop {
+            let suffix = CLI::get_input("🔤 Desired suffix:")?;
+
+            match VanityGenerator::new(&suffix) {
+                Ok(generator) => break generator,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        println!();
+        println!("🎯 Suffix: {}", generator.suffix());
+        println!("🧮 Expected attempts: ~{}", generator.expected_attempts());
+        println!("🖥️  CPU cores: {}", generator.threads());
+        println!();
+
+        if !CLI::confirm_action("Start searching?")? {
+            CLI::print_info("Vanity search cancelled.");
+            return Ok(());
+        }
+
+        // Checked up front: a mistyped password must never cost the key a long
+        // search just found.
+        let password = loop {
+            let password = CLI::get_password("🔒 Enter your password to encrypt the new key (empty to cancel):")?;
+
+            if password.expose_secret().is_empty() {
+                CLI::print_info("Vanity search cancelled.");
+                return Ok(());
+            }
+
+            match self.user_service.authenticate_user(&self.user.username, password.expose_secret()).await {
+                Ok(_) => break password,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let keypair = generator.search(print_vanity_progress)?;
+        println!();
+        println!();
+        CLI::print_success(&format!("Found {}", keypair.public_key()));
+        println!();
+
+        let label = Some(format!("vanity-{}", generator.suffix()));
+        let entry = self.keystore_service.store_keypair(&self.user.id, &keypair, password.expose_secret(), label).await?;
+
+        if self.user.stellar_public_key.is_none()
+            || CLI::confirm_action("Use this as your primary wallet address?")?
+        {
+            self.user_service.link_stellar_public_key(&self.user.id, &entry.public_key).await?;
+            self.user.stellar_public_key = Some(entry.public_key.clone());
+            CLI::print_success("Primary wallet address updated.");
+        }
+
+        println!();
+        println!("{}", "Vanity Key Details:".success().bold());
+        println!("🏦 Address: {}", entry.public_key);
+        println!("🏷️  Label: {}", entry.label.as_deref().unwrap_or("-"));
+        println!("📅 Stored: {}", entry.created_at.format("%Y-%m-%d %H:%M:%S UTC"));
+        println!();
+
+        Ok(())
+    }
+}
+
+fn print_vanity_progress(progress: &VanityProgress) {
+    let eta = progress
+        .eta()
+        .map(format_duration)
+        .unwrap_or_else(|| "estimating...".to_string());
+
+    print!(
+        "\r⏳ {} keys tried · {:.0} keys/s · ETA ~{}        ",
+        progress.attempts,
+        progress.rate(),
+        eta
+    );
+    let _ = io::stdout().flush();
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
@@ -0,0 +1,180 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::user::UserResponse;
+use crate::services::data_export_service::DataExportService;
+use crate::services::keystore_backup_service::KeystoreBackupService;
+use crate::utils::validation::Validator;
+use std::fs;
+use secrecy::ExposeSecret;
+
+pub struct DataHandler {
+    data_export_service: DataExportService,
+    keystore_backup_service: KeystoreBackupService,
+}
+
+impl DataHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            data_export_service: DataExportService::new(db.clone()),
+            keystore_backup_service: KeystoreBackupService::new(db),
+        }
+    }
+
+    pub async fn manage_data_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        println!();
+        println!("{}", "📦 My Data".heading());
+        println!("  1. 📤 Export to a JSON file");
+        println!("  2. 📥 Import from a JSON file");
+        println!("  3. 🔐 Back up a secret key");
+        println!("  4. 🗝️  Restore a secret key backup");
+        println!("  5. ↩️  Back");
+        println!();
+
+        let result = match CLI::get_input("Enter your choice:")?.as_str() {
+            "1" => self.export_interactive(user).await,
+            "2" => self.import_interactive(user).await,
+            "3" => self.backup_key_interactive(user).await,
+            "4" => self.restore_key_interactive(user).await,
+            "5" => return Ok(()),
+            _ => {
+                CLI::print_error("Invalid choice. Please try again.");
+                return Ok(());
+            }
+        };
+
+        if let Err(e) = result {
+            CLI::print_error(&e.to_string());
+        }
+        Ok(())
+    }
+
+    async fn export_interactive(&self, user: &UserResponse) -> Result<()> {
+        let default_path = format!("{}-export.json", user.username);
+        let path = CLI::get_input(&format!("📄 File to write [{}]:", default_path))?;
+        let path = if path.is_empty() { default_path } else { path };
+
+        let export = self.data_export_service.export(user).await?;
+        fs::write(&path, DataExportService::to_json(&export)?)
+            .map_err(|e| AppError::InternalError(format!("Can't write {}: {}", path, e)))?;
+
+        CLI::print_success(&format!(
+            "Exported your profile, settings, {} contact(s) and {} transaction(s) to {}",
+            export.contacts.len(),
+            export.transactions.len(),
+            path
+        ));
+        CLI::print_info("Secret keys are not included; use the keystore backup to move them.");
+        Ok(())
+    }
+
+    async fn import_interactive(&self, user: &UserResponse) -> Result<()> {
+        let path = CLI::get_input("📄 File to import:")?;
+        let json = fs::read_to_string(&path).map_err(|e| AppError::ValidationError(format!("Can't read {}: {}", path, e)))?;
+        let export = DataExportService::from_json(&json)?;
+
+        println!(
+            "  From {} ({}), exported {}: {} contact(s), {} transaction(s)",
+            export.profile.username,
+            export.profile.email,
+            export.exported_at.format("%Y-%m-%d %H:%M UTC"),
+            export.contacts.len(),
+            export.transactions.len()
+        );
+        if !CLI::confirm_action("Merge this into your account?")? {
+            return Ok(());
+        }
+
+        let summary = self.data_export_service.import(user, &export).await?;
+        CLI::print_success(&format!(
+            "Added {} contact(s) and {} transaction(s)",
+            summary.contacts_added, summary.transactions_imported
+        ));
+        if summary.contacts_skipped > 0 {
+            CLI::print_info(&format!("Skipped {} contact(s) whose names you already use.", summary.contacts_skipped));
+        }
+        if summary.transactions_skipped > 0 {
+            CLI::print_info(&format!(
+                "Skipped {} transaction(s) of a Stellar account not linked to this one.",
+                summary.transactions_skipped
+            ));
+        }
+        if export.settings.sms_phone_number.is_some() {
+            CLI::print_info("SMS numbers aren't imported; set yours again under SMS Verification.");
+        }
+        Ok(())
+    }
+
+    async fn backup_key_interactive(&self, user: &UserResponse) -> Result<()> {
+        let keys = self.keystore_backup_service.keys(&user.id).await?;
+        if keys.is_empty() {
+            CLI::print_info("Your keystore is empty. Generate an address from the dashboard first.");
+            return Ok(());
+        }
+
+        println!();
+        for (number, key) in keys.iter().enumerate() {
+            let note = match (&key.signs_for, &key.label) {
+                (Some(account), _) => format!("(signs for {})", account),
+                (None, Some(label)) => format!("({})", label),
+                (None, None) => String::new(),
+            };
+            println!("  {}. {} {}", number + 1, key.public_key, note.muted());
+        }
+        println!();
+
+        let choice = CLI::get_input("Key number:")?;
+        let entry = match choice.parse::<usize>() {
+            Ok(index) if (1..=keys.len()).contains(&index) => &keys[index - 1],
+            _ => return Err(AppError::ValidationError("Please enter one of the listed numbers".to_string())),
+        };
+
+        let password = CLI::get_password("🔒 Your login password:")?;
+        println!("{}", "The backup gets its own passphrase; anyone with the file and the passphrase controls the key.".muted());
+        let passphrase = CLI::get_password("🔑 Backup passphrase:")?;
+        if Validator::validate_password(passphrase.expose_secret()).is_err() {
+            return Err(AppError::ValidationError(
+                "Choose a passphrase that would pass as a login password; the file can be attacked offline".to_string(),
+            ));
+        }
+        if passphrase.expose_secret() != CLI::get_password("🔑 Confirm the passphrase:")?.expose_secret() {
+            return Err(AppError::ValidationError("Passphrases do not match".to_string()));
+        }
+
+        let default_path = format!("{}-keystore.json", &entry.public_key[..8]);
+        let path = CLI::get_input(&format!("📄 File to write [{}]:", default_path))?;
+        let path = if path.is_empty() { default_path } else { path };
+
+        let backup = self.keystore_backup_service.export(&user.id, entry, password.expose_secret(), passphrase.expose_secret()).await?;
+        fs::write(&path, KeystoreBackupService::to_json(&backup)?)
+            .map_err(|e| AppError::InternalError(format!("Can't write {}: {}", path, e)))?;
+
+        CLI::print_success(&format!("Backed up {} to {}", entry.public_key, path));
+        CLI::print_info("Keep the file and the passphrase in separate places.");
+        Ok(())
+    }
+
+    async fn restore_key_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        let path = CLI::get_input("📄 Backup file:")?;
+        let json = fs::read_to_string(&path).map_err(|e| AppError::ValidationError(format!("Can't read {}: {}", path, e)))?;
+        let backup = KeystoreBackupService::from_json(&json)?;
+
+        println!("  {} backed up {}", backup.public_key, backup.exported_at.format("%Y-%m-%d %H:%M UTC"));
+        if let Some(account) = &backup.signs_for {
+            println!("  {}", format!("Signs for {}", account).muted());
+        }
+        let passphrase = CLI::get_password("🔑 Backup passphrase:")?;
+        let password = CLI::get_password("🔒 Your login password, to encrypt the key in this wallet:")?;
+
+        let entry = self.keystore_backup_service.import(user, &backup, passphrase.expose_secret(), password.expose_secret()).await?;
+        CLI::print_success(&format!("Restored {} into your keystore", entry.public_key));
+
+        if user.stellar_public_key.is_none() {
+            let address = backup.signs_for.unwrap_or(backup.public_key);
+            CLI::print_info(&format!("{} is now your wallet address.", address));
+            user.stellar_public_key = Some(address);
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
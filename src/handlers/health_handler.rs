@@ -0,0 +1,120 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::wallet_health::{WalletHealth, WalletStatus};
+use crate::services::wallet_health_service::WalletHealthService;
+use crate::stellar::amount::{format_stroops, to_stroops};
+use crate::stellar::keypair::Keypair;
+use crate::stellar::network::Network;
+use colored::Colorize;
+use std::env;
+use zeroize::Zeroizing;
+
+const DEFAULT_THRESHOLD_XLM: &str = "2";
+
+pub struct HealthHandler {
+    wallet_health_service: WalletHealthService,
+    network: Network,
+}
+
+impl HealthHandler {
+    pub fn new(db: SqliteDatabase, network: Network) -> Self {
+        Self {
+            wallet_health_service: WalletHealthService::new(db, network.clone()),
+            network,
+        }
+    }
+
+    pub async fn sweep_interactive(&self) -> Result<()> {
+        println!();
+        println!("{}", "🩺 Wallet Health Sweep".heading());
+        println!("🌐 Network: {}", self.network.name);
+        println!();
+
+        let threshold_stroops = loop {
+            let input = CLI::get_input(&format!("Minimum spendable XLM per wallet (Enter for {}):", DEFAULT_THRESHOLD_XLM))?;
+            let input = if input.is_empty() { DEFAULT_THRESHOLD_XLM.to_string() } else { input };
+
+            match to_stroops(&input) {
+                Ok(stroops) => break stroops,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        CLI::print_info("Checking wallets on the network...");
+        let report = self.wallet_health_service.sweep(threshold_stroops).await?;
+
+        if report.is_empty() {
+            CLI::print_info("No users have a linked Stellar address yet.");
+            return Ok(());
+        }
+
+        println!();
+        for wallet in &report {
+            print_wallet(wallet);
+        }
+
+        let unfunded = report.iter().filter(|w| w.status == WalletStatus::Unfunded).count();
+        let low: Vec<&WalletHealth> = report
+            .iter()
+            .filter(|w| matches!(w.status, WalletStatus::LowReserve { .. }))
+            .collect();
+        let orphaned: usize = report.iter().map(|w| w.orphaned_trustlines.len()).sum();
+
+        println!();
+        println!("{}", "Summary:".heading());
+        println!("  👛 Wallets checked: {}", report.len());
+        println!("  ⚪ Unfunded: {}", unfunded);
+        println!("  🟡 Low reserve: {}", low.len());
+        println!("  🧹 Orphaned trustlines: {}", orphaned);
+        println!();
+
+        if low.is_empty() {
+            return Ok(());
+        }
+
+        self.remediate_interactive(&low, threshold_stroops).await
+    }
+
+    async fn remediate_interactive(&self, low: &[&WalletHealth], threshold_stroops: i64) -> Result<()> {
+        let secret = Zeroizing::new(env::var("STELLAR_FUNDING_SECRET").unwrap_or_default());
+        if secret.is_empty() {
+            CLI::print_info("Set STELLAR_FUNDING_SECRET to top up low-reserve wallets automatically.");
+            return Ok(());
+        }
+
+        let funding = Keypair::from_secret(&secret)?;
+        println!("🏦 Funding account: {}", funding.public_key());
+
+        if !CLI::confirm_action(&format!("Top up {} low-reserve wallet(s) to {} XLM of headroom?", low.len(), format_stroops(threshold_stroops)))? {
+            CLI::print_info("No top-ups sent.");
+            return Ok(());
+        }
+
+        for wallet in low {
+            match self.wallet_health_service.top_up(&funding, wallet, threshold_stroops).await {
+                Ok(Some(response)) => CLI::print_success(&format!("Topped up {} in transaction {}", wallet.username, response.hash)),
+                Ok(None) => {}
+                Err(e) => CLI::print_error(&format!("Failed to top up {}: {}", wallet.username, e)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_wallet(wallet: &WalletHealth) {
+    let status = match wallet.status {
+        WalletStatus::Unfunded => "⚪ Unfunded".muted().to_string(),
+        WalletStatus::LowReserve { headroom_stroops } => format!("🟡 Low reserve ({} XLM spendable)", format_stroops(headroom_stroops)).warning().to_string(),
+        WalletStatus::Healthy { headroom_stroops } => format!("🟢 Healthy ({} XLM spendable)", format_stroops(headroom_stroops)).success().to_string(),
+    };
+
+    println!("  {} {}", wallet.username.bold(), wallet.public_key);
+    println!("     {}", status);
+
+    if !wallet.orphaned_trustlines.is_empty() {
+        println!("     🧹 Zero-balance trustlines: {}", wallet.orphaned_trustlines.join(", "));
+    }
+}
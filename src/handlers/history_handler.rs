@@ -0,0 +1,278 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::handlers::activity_handler::ActivityHandler;
+use crate::models::payment_filter::HiddenReason;
+use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+use crate::models::undo::LocalAction;
+use crate::models::user::UserResponse;
+use crate::services::archive_service::ArchiveService;
+use crate::services::history_service::{parse_search, HistoryService, SEARCH_HELP};
+use crate::services::payment_filter_service::PaymentFilterService;
+use crate::services::undo_service::UndoService;
+use crate::stellar::amount::{format_stroops, to_stroops};
+use crate::stellar::network::Network;
+use std::rc::Rc;
+
+const HISTORY_LIMIT: u32 = 50;
+
+pub struct HistoryHandler {
+    history_service: HistoryService,
+    archive_service: ArchiveService,
+    activity_handler: ActivityHandler,
+    payment_filter_service: PaymentFilterService,
+    undo_service: Rc<UndoService>,
+}
+
+impl HistoryHandler {
+    pub fn new(db: SqliteDatabase, network: Network, undo_service: Rc<UndoService>) -> Self {
+        Self {
+            history_service: HistoryService::new(db.clone(), &network),
+            archive_service: ArchiveService::new(db.clone()),
+            activity_handler: ActivityHandler::new(db.clone()),
+            payment_filter_service: PaymentFilterService::new(db),
+            undo_service,
+        }
+    }
+
+    pub async fn history_interactive(&self, user: &UserResponse) -> Result<()> {
+        let address = user.stellar_public_key.as_deref().ok_or_else(|| {
+            AppError::StellarError("No Stellar address is linked to this account yet".to_string())
+        })?;
+
+        let mut show_hidden = false;
+
+        loop {
+            let history = self.history_service.recent(address, HISTORY_LIMIT).await?;
+            let tags = self.history_service.tags(address).await?;
+            let filter = self.payment_filter_service.filter(&user.id).await?;
+            let payments: Vec<_> = history
+                .transactions
+                .iter()
+                .map(|tx| (tx, filter.hidden_reason(tx)))
+                .collect();
+
+            println!();
+            println!("{}", "📜 Payment History".heading());
+            println!();
+
+            if let Some(e) = &history.backfill_error {
+                println!("{}", format!("📴 Showing saved history only; couldn't reach Horizon ({})", e).warning());
+                println!();
+            }
+
+            if payments.is_empty() {
+                CLI::print_info("No payments yet.");
+            }
+
+            for (payment, reason) in &payments {
+                match reason {
+                    None => print_payment(payment, None, tags.get(&payment.hash)),
+                    Some(reason) if show_hidden => print_payment(payment, Some(*reason), tags.get(&payment.hash)),
+                    Some(_) => {}
+                }
+            }
+
+            let dust = payments.iter().filter(|(_, r)| *r == Some(HiddenReason::Dust)).count();
+            let scam = payments.iter().filter(|(_, r)| *r == Some(HiddenReason::ScamMemo)).count();
+            if dust + scam > 0 && !show_hidden {
+                println!();
+                println!("{}", format!("🙈 {} hidden ({} dust, {} suspicious memo)", dust + scam, dust, scam).muted());
+            }
+
+            println!();
+            println!("  1. {} hidden payments", if show_hidden { "🙈 Hide" } else { "👀 Show" });
+            println!("  2. 🔍 Search");
+            println!("  3. 🏷️  Tag a payment");
+            println!("  4. 📊 Activity");
+            println!("  5. ⚙️  Spam Filter Settings");
+            println!("  6. 📦 Archived Months");
+            println!("  7. ↩️  Back");
+            println!();
+
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => show_hidden = !show_hidden,
+                "2" => {
+                    if let Err(e) = self.search_interactive(address).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                }
+                "3" => {
+                    if let Err(e) = self.tag_interactive(address).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                }
+                "4" => {
+                    if let Err(e) = self.activity_handler.activity_interactive(address).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "5" => {
+                    if let Err(e) = self.settings_interactive(user).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                }
+                "6" => {
+                    if let Err(e) = self.archived_months_interactive(address).await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "7" => return Ok(()),
+                _ => CLI::print_error("Invalid choice. Please try again."),
+            }
+        }
+    }
+
+    /// Monthly totals for history moved out by `db archive`.
+    async fn archived_months_interactive(&self, address: &str) -> Result<()> {
+        let rollups = self.archive_service.rollups(address).await?;
+
+        println!();
+        println!("{}", "📦 Archived Months".heading());
+        if rollups.is_empty() {
+            CLI::print_info("No history has been archived.");
+            return Ok(());
+        }
+
+        for rollup in &rollups {
+            let (arrow, sign) = match rollup.direction {
+                TransactionDirection::Incoming => ("📥", "+"),
+                TransactionDirection::Outgoing => ("📤", "-"),
+            };
+            println!(
+                "  {} {} {}{} {} ({} payment(s))",
+                rollup.month,
+                arrow,
+                sign,
+                format_stroops(rollup.total_stroops),
+                rollup.asset_code,
+                rollup.payment_count
+            );
+        }
+        println!();
+        println!("{}", "Full records are in the archive files written by `db archive`.".muted());
+        Ok(())
+    }
+
+    async fn search_interactive(&self, address: &str) -> Result<()> {
+        println!();
+        println!("{}", SEARCH_HELP.muted());
+        let search = parse_search(&CLI::get_input("🔍 Search:")?)?;
+        if search.is_empty() {
+            return Ok(());
+        }
+
+        let results = self.history_service.search(address, &search, HISTORY_LIMIT).await?;
+        let tags = self.history_service.tags(address).await?;
+
+        println!();
+        if results.is_empty() {
+            CLI::print_info("No matching payments.");
+        }
+        for payment in &results {
+            print_payment(payment, None, tags.get(&payment.hash));
+        }
+        if results.len() == HISTORY_LIMIT as usize {
+            println!();
+            println!("{}", format!("Showing the first {} matches; narrow the search to see more.", HISTORY_LIMIT).muted());
+        }
+
+        CLI::wait_for_enter();
+        Ok(())
+    }
+
+    async fn tag_interactive(&self, address: &str) -> Result<()> {
+        let hash = CLI::get_input("Transaction hash (the first few characters are enough):")?;
+        let tag = CLI::get_input("🏷️  Tag (e.g. rent, payroll):")?;
+
+        let action = if CLI::confirm_action("Remove this tag instead of adding it?")? {
+            let hash = self.history_service.untag(address, &hash, &tag).await?;
+            let tag = tag.trim().to_lowercase();
+            CLI::print_success(&format!("Removed tag '{}'", tag));
+            LocalAction::TagRemoved { account: address.to_string(), hash, tag }
+        } else {
+            let hash = self.history_service.tag(address, &hash, &tag).await?;
+            let tag = tag.trim().to_lowercase();
+            CLI::print_success(&format!("Tagged {}… '{}'", &hash[..12.min(hash.len())], tag));
+            LocalAction::TagAdded { account: address.to_string(), hash, tag }
+        };
+
+        self.undo_service.record(action);
+
+        Ok(())
+    }
+
+    async fn settings_interactive(&self, user: &UserResponse) -> Result<()> {
+        let settings = self.payment_filter_service.settings(&user.id).await?;
+
+        println!();
+        println!("🧹 Dust threshold: {} XLM", format_stroops(settings.dust_threshold_stroops));
+        println!("🎣 Hide suspicious memos: {}", if settings.hide_scam_memos { "yes" } else { "no" });
+        println!();
+
+        let dust_threshold_stroops = loop {
+            let input = CLI::get_input("New dust threshold in XLM (Enter to keep, 0 to disable):")?;
+
+            if input.is_empty() {
+                break settings.dust_threshold_stroops;
+            }
+            if input == "0" {
+                break 0;
+            }
+            match to_stroops(&input) {
+                Ok(stroops) => break stroops,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let hide_scam_memos = CLI::confirm_action("Hide payments with suspicious memos (links, airdrop bait)?")?;
+
+        self.payment_filter_service
+            .update_settings(&user.id, dust_threshold_stroops, hide_scam_memos)
+            .await?;
+
+        CLI::print_success("Spam filter settings saved");
+        Ok(())
+    }
+}
+
+fn print_payment(tx: &WalletTransaction, hidden: Option<HiddenReason>, tags: Option<&Vec<String>>) {
+    let incoming = tx.direction == TransactionDirection::Incoming;
+    let arrow = if incoming { "📥" } else { "📤" };
+
+    let date = tx.created_at.format("%Y-%m-%d");
+    let line = format!(
+        "  {} {}  {} {} {} {}",
+        arrow,
+        date,
+        if incoming { "+" } else { "-" },
+        format_stroops(tx.amount_stroops),
+        tx.asset_code,
+        tx.counterparty
+    );
+
+    let status = match tx.status {
+        TransactionStatus::Confirmed => String::new(),
+        TransactionStatus::Pending => format!(" {}", "[pending]".warning()),
+        TransactionStatus::Failed => format!(" {}", "[failed]".error()),
+    };
+
+    match hidden {
+        None => println!("{}{}", line, status),
+        Some(HiddenReason::Dust) => println!("{} {}", line.muted(), "[dust]".warning()),
+        Some(HiddenReason::ScamMemo) => println!("{} {}", line.muted(), "[suspicious memo]".warning()),
+    }
+
+    if let Some(memo) = &tx.memo {
+        println!("{:>17}📝 {}", "", memo.muted());
+    }
+    if let Some(error) = &tx.error {
+        println!("{:>17}⚠️  {}", "", error.muted());
+    }
+    if let Some(tags) = tags.filter(|tags| !tags.is_empty()) {
+        println!("{:>17}🏷️  {}  {}", "", tags.join(", ").prompt(), tx.hash.get(..12).unwrap_or(&tx.hash).muted());
+    }
+}
@@ -0,0 +1,58 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::user::UserResponse;
+use crate::services::login_history_service::LoginHistoryService;
+use colored::Colorize;
+
+pub struct LoginHistoryHandler {
+    login_history_service: LoginHistoryService,
+}
+
+impl LoginHistoryHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            login_history_service: LoginHistoryService::new(db),
+        }
+    }
+
+    /// Shows the latest login attempts on the user's account, so they can spot
+    /// any that weren't theirs.
+    pub async fn recent_activity_interactive(&self, user: &UserResponse) -> Result<()> {
+        let attempts = self.login_history_service.recent(&user.id).await?;
+
+        println!();
+        println!("{}", "🕵️  Recent Activity".heading());
+        println!();
+        for attempt in &attempts {
+            let outcome = match &attempt.failure_reason {
+                None => "✅ login".success(),
+                Some(reason) => format!("❌ failed: {}", reason).error(),
+            };
+            println!("  {}  {}", attempt.created_at.format("%Y-%m-%d %H:%M UTC"), outcome);
+
+            let mut from = attempt.source.clone();
+            if let Some(user_agent) = &attempt.user_agent {
+                from.push_str(&format!(" · {}", user_agent));
+            }
+            println!("     {}", from.muted());
+        }
+
+        let failures = attempts.iter().filter(|attempt| !attempt.succeeded).count();
+        println!();
+        if attempts.is_empty() {
+            CLI::print_info("No logins recorded yet.");
+        } else if failures == 0 {
+            CLI::print_info(&format!("Your last {} login attempt(s) all succeeded.", attempts.len()));
+        } else {
+            println!(
+                "{}",
+                format!("⚠️  {} failed attempt(s). If any weren't you, change your password and revoke other sessions.", failures)
+                    .warning()
+                    .bold()
+            );
+        }
+        Ok(())
+    }
+}
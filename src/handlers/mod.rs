@@ -1 +1,26 @@
-pub mod account_handler;
\ No newline at end of file
+pub mod account_handler;
+pub mod accounts_handler;
+pub mod activity_handler;
+pub mod admin_handler;
+pub mod api_keys_handler;
+pub mod audit_handler;
+pub mod balances_handler;
+pub mod contacts_handler;
+pub mod customer_fields_handler;
+pub mod dashboard_handler;
+pub mod data_handler;
+pub mod health_handler;
+pub mod history_handler;
+pub mod login_history_handler;
+pub mod password_handler;
+pub mod payment_handler;
+pub mod payment_notes_handler;
+pub mod policies_handler;
+pub mod profile_handler;
+pub mod remote_handler;
+pub mod reports_handler;
+pub mod sessions_handler;
+pub mod settings_handler;
+pub mod signing_handler;
+pub mod sms_handler;
+pub mod two_factor_handler;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::errors::{AppError, Result};
+use crate::services::breach_check_service::{BreachCheckService, BreachPolicy};
+use crate::utils::password_strength;
+use crate::utils::validation::Validator;
+use secrecy::{ExposeSecret, SecretString};
+
+/// Prompts for new passwords, wherever one is being set.
+pub struct PasswordHandler {
+    breach_check: Option<BreachCheckService>,
+}
+
+impl PasswordHandler {
+    pub fn new(breach_check: Option<BreachCheckService>) -> Self {
+        Self { breach_check }
+    }
+
+    /// Asks for a new password until one passes the strength and breach
+    /// checks and is typed the same twice.
+    pub async fn new_password_interactive(&self) -> Result<SecretString> {
+        println!();
+        CLI::display_password_requirements();
+
+        loop {
+            let secret = CLI::get_password("🔒 Enter your password:")?;
+            let password = secret.expose_secret();
+
+            if password.is_empty() {
+                CLI::print_error("Password cannot be empty");
+                continue;
+            }
+
+            // Validate password strength
+            CLI::print_password_strength(&password_strength::estimate(password));
+            if let Err(e) = Validator::validate_password(password) {
+                CLI::print_error(&e.to_string());
+                continue;
+            }
+            if !self.breach_check_interactive(password).await? {
+                continue;
+            }
+
+            // Confirm password
+            let confirm_password = CLI::get_password("🔒 Confirm your password:")?;
+
+            if password != confirm_password.expose_secret() {
+                CLI::print_error("Passwords do not match. Please try again.");
+                continue;
+            }
+
+            return Ok(secret);
+        }
+    }
+
+    /// The breach check for a password given without a prompt, as by
+    /// `account create`: rejected under the `reject` policy, only warned
+    /// about under `warn`, and skipped if the check can't run.
+    pub async fn check_breaches(&self, password: &str) -> Result<()> {
+        let Some(breach_check) = &self.breach_check else {
+            return Ok(());
+        };
+
+        let times = match breach_check.times_breached(password).await {
+            Ok(times) => times,
+            Err(e) => {
+                CLI::print_info(&format!("Skipping the breach check: {}", e));
+                return Ok(());
+            }
+        };
+        if times == 0 {
+            return Ok(());
+        }
+
+        let message = format!("This password has appeared in {} known data breaches.", times);
+        match breach_check.policy() {
+            BreachPolicy::Reject => Err(AppError::ValidationError(format!("{} Please choose another one.", message))),
+            BreachPolicy::Warn => {
+                println!("{}", format!("⚠️  {} Attackers try these first.", message).warning());
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether `password` may be used as far as the breach check goes. If the
+    /// check can't run, e.g. offline, it is skipped rather than blocking.
+    async fn breach_check_interactive(&self, password: &str) -> Result<bool> {
+        let Some(breach_check) = &self.breach_check else {
+            return Ok(true);
+        };
+
+        let times = match breach_check.times_breached(password).await {
+            Ok(times) => times,
+            Err(e) => {
+                CLI::print_info(&format!("Skipping the breach check: {}", e));
+                return Ok(true);
+            }
+        };
+        if times == 0 {
+            return Ok(true);
+        }
+
+        let message = format!("This password has appeared in {} known data breaches.", times);
+        match breach_check.policy() {
+            BreachPolicy::Reject => {
+                CLI::print_error(&format!("{} Please choose another one.", message));
+                Ok(false)
+            }
+            BreachPolicy::Warn => {
+                println!("{}", format!("⚠️  {} Attackers try these first.", message).warning());
+                CLI::confirm_action("Use it anyway?")
+            }
+        }
+    }
+}
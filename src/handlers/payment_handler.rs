@@ -0,0 +1,311 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::errors::{AppError, Result};
+use crate::handlers::contacts_handler::ContactsHandler;
+use crate::handlers::payment_notes_handler::PaymentNotesHandler;
+use crate::handlers::signing_handler::SigningHandler;
+use crate::handlers::sms_handler::SmsHandler;
+use crate::handlers::two_factor_handler::TwoFactorHandler;
+use crate::database::sqlite::SqliteDatabase;
+use crate::models::offline::QueuedPayment;
+use crate::models::policy::PaymentIntent;
+use crate::models::sms::SmsPurpose;
+use crate::models::user::UserResponse;
+use crate::services::audit_service::AuditService;
+use crate::services::hook_service::HookService;
+use crate::services::offline_service::OfflineService;
+use crate::services::policy_service::PolicyService;
+use crate::services::price_service::PriceService;
+use crate::services::settings_service::SettingsService;
+use crate::services::transaction_service::TransactionService;
+use crate::services::user_service::UserService;
+use crate::stellar::amount::parse_stroops;
+use crate::stellar::network::Network;
+use crate::utils::validation::Validator;
+use colored::Colorize;
+use std::sync::Arc;
+
+pub struct PaymentHandler {
+    transaction_service: TransactionService,
+    settings_service: SettingsService,
+    policy_service: PolicyService,
+    audit_service: AuditService,
+    user_service: UserService,
+    sms_handler: SmsHandler,
+    two_factor_handler: TwoFactorHandler,
+    payment_notes_handler: PaymentNotesHandler,
+    offline_service: OfflineService,
+    price_service: PriceService,
+}
+
+impl PaymentHandler {
+    pub fn new(db: SqliteDatabase, network: Network, price_service: PriceService, hooks: Arc<HookService>) -> Self {
+        Self {
+            offline_service: OfflineService::new(db.clone(), &network),
+            transaction_service: TransactionService::new(network).with_hooks(hooks).with_db(db.clone()),
+            settings_service: SettingsService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            user_service: UserService::new(db.clone()),
+            sms_handler: SmsHandler::new(db.clone()),
+            two_factor_handler: TwoFactorHandler::new(db.clone()),
+            payment_notes_handler: PaymentNotesHandler::new(db.clone()),
+            policy_service: PolicyService::new(db),
+            price_service,
+        }
+    }
+
+    pub async fn send_payment_interactive(&self, user: &UserResponse, contacts: &ContactsHandler, signing: &SigningHandler) -> Result<()> {
+        let source = user.stellar_public_key.as_deref().ok_or_else(|| {
+            AppError::StellarError("No Stellar address is linked to this account yet".to_string())
+        })?;
+        let network = self.transaction_service.network();
+
+        println!();
+        println!("{}", "📤 Send Payment".heading());
+        println!("🌐 Network: {}", network.name);
+        println!();
+
+        let online = self.offline_service.is_online().await;
+        if !online {
+            println!("{}", "📴 Horizon can't be reached. You can queue this payment and send it once you're back online.".warning());
+            println!();
+        }
+
+        let contact = if CLI::confirm_action("Pay one of your saved contacts?")? {
+            contacts.pick_contact(user).await?
+        } else {
+            None
+        };
+
+        let (destination, saved_memo) = match &contact {
+            Some(contact) => {
+                CLI::print_info(&format!("Paying {} ({})", contact.name, contact.address));
+                (contact.address.clone(), contact.memo.clone())
+            }
+            None => {
+                let address = loop {
+                    let address = CLI::get_input("🏦 Destination address (G...):")?;
+
+                    match Validator::validate_stellar_address(&address) {
+                        Ok(()) => break address,
+                        Err(e) => CLI::print_error(&e.to_string()),
+                    }
+                };
+                (address, None)
+            }
+        };
+
+        if online && !self.transaction_service.account_exists(&destination).await? {
+            CLI::print_info("This address isn't funded yet; the payment will create it (minimum 1 XLM).");
+        }
+
+        let amount = loop {
+            let amount = CLI::get_input("💰 Amount in XLM:")?;
+
+            match Validator::validate_amount(&amount) {
+                Ok(()) => break amount,
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        };
+
+        let memo_prompt = match &saved_memo {
+            Some(memo) => format!("📝 Memo (Enter keeps '{}', '-' for none):", memo),
+            None => "📝 Memo (optional):".to_string(),
+        };
+
+        let memo = loop {
+            let memo = CLI::get_input(&memo_prompt)?;
+
+            match memo.as_str() {
+                "" => break saved_memo.clone(),
+                "-" => break None,
+                _ => match Validator::validate_memo(&memo) {
+                    Ok(()) => break Some(memo),
+                    Err(e) => CLI::print_error(&e.to_string()),
+                },
+            }
+        };
+
+        if !online {
+            return self.queue_interactive(user, source, &destination, &amount, memo.as_deref()).await;
+        }
+
+        self.confirm_and_send(user, signing, source, &destination, &amount, memo.as_deref()).await?;
+        Ok(())
+    }
+
+    /// Offers to submit each payment queued while offline, oldest first. Each
+    /// one goes through the same summary and checks as a new payment, and is
+    /// signed with the account's current sequence number.
+    pub async fn review_queue_interactive(&self, user: &UserResponse, signing: &SigningHandler) -> Result<()> {
+        let queued = self.offline_service.queued_payments(&user.id).await?;
+
+        println!();
+        println!("{}", "🕒 Queued Payments".heading());
+        if queued.is_empty() {
+            CLI::print_info("No payments are waiting to be sent.");
+            return Ok(());
+        }
+        if !self.offline_service.is_online().await {
+            CLI::print_info("Still offline; queued payments stay pending, not submitted.");
+            return Ok(());
+        }
+
+        for payment in &queued {
+            println!();
+            Self::print_queued(payment);
+            println!("  1. 📤 Review and send");
+            println!("  2. ⏭️  Keep for later");
+            println!("  3. 🗑️  Discard");
+
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" if user.stellar_public_key.as_deref() != Some(payment.source.as_str()) => {
+                    CLI::print_error("This payment was queued from an address you no longer use; kept in the queue.");
+                }
+                "1" => match self
+                    .confirm_and_send(user, signing, &payment.source, &payment.destination, &payment.amount, payment.memo.as_deref())
+                    .await
+                {
+                    Ok(true) => self.offline_service.remove_queued(&user.id, &payment.id).await?,
+                    Ok(false) => CLI::print_info("Kept in the queue."),
+                    Err(e) => CLI::print_error(&format!("Not sent, kept in the queue: {}", e)),
+                },
+                "3" => {
+                    self.offline_service.remove_queued(&user.id, &payment.id).await?;
+                    CLI::print_info("Discarded.");
+                }
+                _ => CLI::print_info("Kept in the queue."),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn queue_interactive(&self, user: &UserResponse, source: &str, destination: &str, amount: &str, memo: Option<&str>) -> Result<()> {
+        println!();
+        println!("🎯 To: {}", destination);
+        println!("💰 Amount: {} XLM", amount);
+        println!("📝 Memo: {}", memo.unwrap_or("-"));
+        println!();
+
+        if !CLI::confirm_action("Queue this payment to send later?")? {
+            CLI::print_info("Payment cancelled.");
+            return Ok(());
+        }
+
+        self.offline_service.queue_payment(&user.id, source, destination, amount, memo).await?;
+        println!("{}", "🕒 Queued: pending, not submitted.".warning().bold());
+        CLI::print_info("You'll be asked to review and send it once Horizon can be reached again.");
+        Ok(())
+    }
+
+    fn print_queued(payment: &QueuedPayment) {
+        println!(
+            "{} {} XLM to {} (queued {})",
+            "🕒 Pending, not submitted:".warning(),
+            payment.amount,
+            payment.destination,
+            payment.created_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        if let Some(memo) = &payment.memo {
+            println!("   📝 Memo: {}", memo);
+        }
+    }
+
+    /// Shows the summary, runs every check and asks for confirmation before
+    /// signing and submitting. Returns whether the payment was sent.
+    async fn confirm_and_send(
+        &self,
+        user: &UserResponse,
+        signing: &SigningHandler,
+        source: &str,
+        destination: &str,
+        amount: &str,
+        memo: Option<&str>,
+    ) -> Result<bool> {
+        let network = self.transaction_service.network();
+        let destination_exists = self.transaction_service.account_exists(destination).await?;
+
+        println!();
+        println!("{}", "Payment Summary:".warning().bold());
+        if network.is_public() {
+            println!("🌐 Network: {}", "public (real funds)".error().bold());
+        } else {
+            println!("🌐 Network: {}", network.name);
+        }
+        println!("🏦 From: {}", source);
+        println!("🎯 To: {}{}", destination, if destination_exists { "" } else { " (new account)" });
+        let recipient = self.user_service.find_user_by_stellar_public_key(destination).await?;
+        if let Some(recipient) = &recipient {
+            println!("👤 Wallet user: {}", recipient.username.bold());
+        }
+        let currency = self.settings_service.settings(&user.id).await?.fiat_currency;
+        match self.price_service.fiat_label(amount, "XLM", &currency).await {
+            Some(fiat) => println!("💰 Amount: {} XLM ({})", amount, fiat),
+            None => println!("💰 Amount: {} XLM", amount),
+        }
+        println!("📝 Memo: {}", memo.unwrap_or("-"));
+        println!();
+
+        let intent = PaymentIntent {
+            destination: destination.to_string(),
+            asset_code: "XLM".to_string(),
+            amount_stroops: parse_stroops(amount)?,
+            network: network.name.clone(),
+        };
+        self.policy_service.check(&user.id, &intent).await?;
+
+        if let Some(ago) = self.transaction_service.recent_duplicate(source, destination, amount, memo).await? {
+            println!(
+                "{}",
+                format!("⚠️  You sent this exact payment {}s ago. It may be an accidental repeat.", ago.as_secs())
+                    .warning()
+                    .bold()
+            );
+            if !CLI::confirm_action("Send it again anyway?")? {
+                CLI::print_info("Payment cancelled.");
+                return Ok(false);
+            }
+        }
+
+        let prompt = match &recipient {
+            Some(recipient) => format!("Send this payment to {}?", recipient.username),
+            None => "Send this payment?".to_string(),
+        };
+        if !CLI::confirm_action(&prompt)? {
+            CLI::print_info("Payment cancelled.");
+            return Ok(false);
+        }
+
+        if !self.sms_handler.require_code_interactive(&user.id, SmsPurpose::PaymentConfirmation).await? {
+            CLI::print_error("Payment cancelled: SMS code not confirmed.");
+            return Ok(false);
+        }
+
+        if !self.two_factor_handler.require_code_interactive(&user.id).await? {
+            CLI::print_error("Payment cancelled: 2FA code not confirmed.");
+            return Ok(false);
+        }
+
+        let signer = signing.unlock_signer_interactive(user, source).await?;
+
+        let result = self
+            .transaction_service
+            .send_payment(signer.as_ref(), destination, amount, memo)
+            .await;
+        self.audit_service.record_payment(&user.id, destination, amount, &result).await?;
+        let result = result?;
+
+        println!();
+        CLI::print_success("🎉 Payment sent!");
+        println!("🧾 Transaction: {}", result.hash);
+        println!("📦 Ledger: {}", result.ledger);
+
+        self.payment_notes_handler
+            .attach_note_interactive(user, signer.as_ref(), destination, &result.hash)
+            .await?;
+        println!();
+
+        Ok(true)
+    }
+}
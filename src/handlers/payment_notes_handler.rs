@@ -0,0 +1,93 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::payment_note::MAX_NOTE_LENGTH;
+use crate::models::user::UserResponse;
+use crate::services::payment_note_service::PaymentNoteService;
+use crate::stellar::keypair::Keypair;
+use crate::stellar::signer::Signer;
+use std::collections::HashMap;
+use secrecy::ExposeSecret;
+
+pub struct PaymentNotesHandler {
+    payment_note_service: PaymentNoteService,
+}
+
+impl PaymentNotesHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            payment_note_service: PaymentNoteService::new(db),
+        }
+    }
+
+    /// Offers to attach a private note when the payment went to another wallet
+    /// user. The payment has already gone through, so failures are only reported.
+    pub async fn attach_note_interactive(&self, user: &UserResponse, signer: &dyn Signer, destination: &str, transaction_hash: &str) -> Result<()> {
+        let Some(recipient) = self.payment_note_service.recipient(&user.id, destination).await? else {
+            return Ok(());
+        };
+
+        println!();
+        CLI::print_info("The recipient also uses this wallet. You can send them an end-to-end encrypted note.");
+        let text = CLI::get_input(&format!("💬 Private note (optional, up to {} characters):", MAX_NOTE_LENGTH))?;
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        match self
+            .payment_note_service
+            .attach(&user.id, signer, &recipient, destination, transaction_hash, &text)
+            .await
+        {
+            Ok(_) => CLI::print_success("Encrypted note attached."),
+            Err(e) => CLI::print_error(&format!("Note not attached: {}", e)),
+        }
+
+        Ok(())
+    }
+
+    pub async fn notes_interactive(&self, user: &UserResponse) -> Result<()> {
+        let notes = self.payment_note_service.notes(&user.id).await?;
+
+        println!();
+        println!("{}", "💬 Payment Notes".heading());
+        if notes.is_empty() {
+            CLI::print_info("No notes yet. Notes can be attached when paying another wallet user.");
+            return Ok(());
+        }
+
+        let password = CLI::get_password("🔒 Enter your password to decrypt notes:")?;
+        let mut keys: HashMap<String, Result<Keypair>> = HashMap::new();
+        println!();
+
+        for note in &notes {
+            let (ours, theirs) = note.addresses_for(&user.id);
+            if !keys.contains_key(ours) {
+                let keypair = self.payment_note_service.unlock(&user.id, ours, password.expose_secret()).await;
+                keys.insert(ours.to_string(), keypair);
+            }
+
+            let direction = if note.sender_user_id == user.id { "→ to" } else { "← from" };
+            println!(
+                "  {}  {} {}  {}",
+                note.created_at.format("%Y-%m-%d %H:%M"),
+                direction,
+                theirs,
+                format!("tx {}", &note.transaction_hash[..note.transaction_hash.len().min(12)]).muted()
+            );
+
+            let text = match &keys[ours] {
+                Ok(keypair) => self.payment_note_service.read(note, keypair),
+                Err(e) => Err(e.clone()),
+            };
+            match text {
+                Ok(text) => println!("     {}", text),
+                Err(e) => println!("     {}", format!("🔒 {}", e).error()),
+            }
+        }
+        println!();
+
+        Ok(())
+    }
+}
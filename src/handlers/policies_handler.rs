@@ -0,0 +1,170 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::policy::{PolicyRule, TransactionPolicy};
+use crate::services::policy_service::PolicyService;
+use crate::stellar::amount::format_stroops;
+use colored::Colorize;
+use uuid::Uuid;
+
+/// Manages payment policies. With an owner these are the user's own rules;
+/// without one they are operator-wide rules that apply to every user.
+pub struct PoliciesHandler {
+    policy_service: PolicyService,
+}
+
+impl PoliciesHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            policy_service: PolicyService::new(db),
+        }
+    }
+
+    pub async fn manage_policies_interactive(&self, owner: Option<&Uuid>) -> Result<()> {
+        loop {
+            println!();
+            match owner {
+                Some(_) => println!("{}", "🛡️  Payment Policies:".heading()),
+                None => println!("{}", "🛡️  Operator Policies (all users):".heading()),
+            }
+            println!("  1. 📋 List Policies & Allowlist");
+            println!("  2. 💰 Add Amount Limit");
+            println!("  3. 🎯 Require Allowlisted Destinations");
+            println!("  4. ➕ Allow a Destination");
+            println!("  5. 🗑️  Remove Policy or Destination");
+            println!("  6. ↩️  Back");
+            println!();
+
+            let result = match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => self.list_interactive(owner).await,
+                "2" => self.add_max_amount_interactive(owner).await,
+                "3" => self.add_allowlist_only_interactive(owner).await,
+                "4" => self.allow_destination_interactive(owner).await,
+                "5" => self.remove_interactive(owner).await,
+                "6" => return Ok(()),
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    continue;
+                }
+            };
+
+            if let Err(e) = result {
+                CLI::print_error(&format!("Error: {}", e));
+            }
+        }
+    }
+
+    async fn list_interactive(&self, owner: Option<&Uuid>) -> Result<()> {
+        let policies = self.policy_service.policies(owner).await?;
+        let allowlist = self.policy_service.allowlist(owner).await?;
+
+        println!();
+        if policies.is_empty() {
+            CLI::print_info("No policies are set.");
+        }
+        for (index, policy) in policies.iter().enumerate() {
+            println!("  P{}. {}{}", index + 1, describe(policy), scope_label(owner, policy.user_id));
+        }
+
+        if !allowlist.is_empty() {
+            println!();
+            println!("{}", "Allowed destinations:".bold());
+        }
+        for (index, entry) in allowlist.iter().enumerate() {
+            let label = entry.label.as_deref().map(|l| format!(" ({})", l)).unwrap_or_default();
+            println!("  A{}. {}{}{}", index + 1, entry.address, label, scope_label(owner, entry.user_id));
+        }
+        println!();
+
+        Ok(())
+    }
+
+    async fn add_max_amount_interactive(&self, owner: Option<&Uuid>) -> Result<()> {
+        let asset_code = CLI::get_input("🪙 Asset code (e.g. XLM, USDC):")?;
+        let max_amount = CLI::get_input("💰 Largest allowed payment:")?;
+
+        let policy = self.policy_service.add_max_amount(owner, &asset_code, &max_amount).await?;
+        CLI::print_success(&format!("Added: {}", describe(&policy)));
+        Ok(())
+    }
+
+    async fn add_allowlist_only_interactive(&self, owner: Option<&Uuid>) -> Result<()> {
+        let network = CLI::get_input("🌐 Only on network (testnet/public, empty for all):")?;
+
+        let policy = self
+            .policy_service
+            .add_allowlist_only(owner, Some(network.as_str()))
+            .await?;
+        CLI::print_success(&format!("Added: {}", describe(&policy)));
+        Ok(())
+    }
+
+    async fn allow_destination_interactive(&self, owner: Option<&Uuid>) -> Result<()> {
+        let address = CLI::get_input("🏦 Destination address (G...):")?;
+        let label = CLI::get_input("🏷️  Label (optional):")?;
+
+        let entry = self.policy_service.allow_destination(owner, &address, Some(label.as_str())).await?;
+        CLI::print_success(&format!("{} is now allowed.", entry.address));
+        Ok(())
+    }
+
+    async fn remove_interactive(&self, owner: Option<&Uuid>) -> Result<()> {
+        self.list_interactive(owner).await?;
+
+        // Only rules at this level can be removed; users can't drop operator rules.
+        let policies = self.policy_service.policies(owner).await?;
+        let allowlist = self.policy_service.allowlist(owner).await?;
+
+        let choice = CLI::get_input("Entry to remove, e.g. P1 or A2 (empty to cancel):")?.to_uppercase();
+        if choice.is_empty() {
+            return Ok(());
+        }
+
+        let pick = |prefix: char, len: usize| -> Option<usize> {
+            let index = choice.strip_prefix(prefix)?.parse::<usize>().ok()?;
+            (1..=len).contains(&index).then(|| index - 1)
+        };
+
+        if let Some(index) = pick('P', policies.len()) {
+            let policy = &policies[index];
+            if policy.user_id.as_ref() != owner {
+                CLI::print_error("That policy is set by the operator and can't be removed here.");
+                return Ok(());
+            }
+            self.policy_service.remove_policy(owner, &policy.id).await?;
+            CLI::print_success("Policy removed.");
+        } else if let Some(index) = pick('A', allowlist.len()) {
+            let entry = &allowlist[index];
+            if entry.user_id.as_ref() != owner {
+                CLI::print_error("That destination is allowed by the operator and can't be removed here.");
+                return Ok(());
+            }
+            self.policy_service.remove_destination(owner, &entry.id).await?;
+            CLI::print_success("Destination removed.");
+        } else {
+            CLI::print_error("Please enter one of the listed entries");
+        }
+
+        Ok(())
+    }
+}
+
+fn describe(policy: &TransactionPolicy) -> String {
+    match &policy.rule {
+        PolicyRule::MaxAmount { asset_code, max_stroops } => {
+            format!("Block {} payments above {}", asset_code, format_stroops(*max_stroops))
+        }
+        PolicyRule::AllowlistOnly { network: Some(network) } => {
+            format!("Only allowlisted destinations on {}", network)
+        }
+        PolicyRule::AllowlistOnly { network: None } => "Only allowlisted destinations".to_string(),
+    }
+}
+
+fn scope_label(owner: Option<&Uuid>, user_id: Option<Uuid>) -> String {
+    match (owner, user_id) {
+        (Some(_), None) => format!(" {}", "[operator]".muted()),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,173 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::handlers::password_handler::PasswordHandler;
+use crate::models::audit::AuditEvent;
+use crate::models::session::Session;
+use crate::models::user::UserResponse;
+use crate::services::audit_service::AuditService;
+use crate::services::breach_check_service::BreachCheckService;
+use crate::services::email_change_service::EmailChangeService;
+use crate::services::password_change_service::PasswordChangeService;
+use crate::services::user_service::UserService;
+use chrono::Utc;
+use colored::Colorize;
+use secrecy::ExposeSecret;
+
+pub struct ProfileHandler {
+    user_service: UserService,
+    audit_service: AuditService,
+    password_change_service: PasswordChangeService,
+    email_change_service: EmailChangeService,
+    password_handler: PasswordHandler,
+}
+
+impl ProfileHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            user_service: UserService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            password_change_service: PasswordChangeService::new(db.clone()),
+            email_change_service: EmailChangeService::new(db),
+            // A bad setting has already stopped the app at startup.
+            password_handler: PasswordHandler::new(BreachCheckService::from_env().unwrap_or_default()),
+        }
+    }
+
+    /// Edits the username, email and password, or deletes the account.
+    /// Returns `true` once the account is gone.
+    pub async fn manage_profile_interactive(&self, user: &mut UserResponse, session: &Session) -> Result<bool> {
+        loop {
+            println!();
+            println!("{}", "👤 Profile & Account".heading());
+            println!("  1. 🏷️  Username ({})", user.username);
+            println!("  2. 📧 Email ({})", user.email);
+            println!("  3. 🔒 Change password");
+            println!("  4. 🗑️  Delete my account");
+            println!("  5. ↩️  Back");
+            println!();
+
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => {
+                    let username = CLI::get_input("🏷️  New username:")?;
+
+                    match self.user_service.change_username(&user.id, &username).await {
+                        Ok(updated) => {
+                            self.audit_service
+                                .record(Some(&user.id), AuditEvent::ProfileUpdated, &format!("username '{}' -> '{}'", user.username, updated.username))
+                                .await?;
+                            CLI::print_success(&format!("You are now {}", updated.username));
+                            *user = updated;
+                        }
+                        Err(e) => CLI::print_error(&e.to_string()),
+                    }
+                }
+                "2" => {
+                    if let Err(e) = self.change_email_interactive(user).await {
+                        CLI::print_error(&e.to_string());
+                    }
+                }
+                "3" => {
+                    if let Err(e) = self.change_password_interactive(user, session).await {
+                        CLI::print_error(&e.to_string());
+                    }
+                }
+                "4" => {
+                    if self.delete_account_interactive(user).await? {
+                        return Ok(true);
+                    }
+                }
+                "5" => return Ok(false),
+                _ => CLI::print_error("Invalid choice. Please try again."),
+            }
+        }
+    }
+
+    async fn change_email_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        if !self.email_change_service.is_enabled() {
+            CLI::print_error("Changing your email needs email delivery; ask the operator to set EMAIL_PROVIDER.");
+            return Ok(());
+        }
+
+        let email = CLI::get_input("📧 New email:")?;
+        let password = CLI::get_password("🔒 Confirm with your password:")?;
+        let expires_at = self.email_change_service.start(&user.id, password.expose_secret(), &email, Utc::now()).await?;
+        CLI::print_info(&format!(
+            "We've emailed a code to {} and another to {}. They expire at {}.",
+            user.email,
+            email.trim(),
+            expires_at.format("%H:%M UTC")
+        ));
+
+        loop {
+            let old_code = CLI::get_input(&format!("🔑 Code sent to {} (blank to cancel):", user.email))?;
+            if old_code.is_empty() {
+                CLI::print_info("Your email was not changed.");
+                return Ok(());
+            }
+            let new_code = CLI::get_input(&format!("🔑 Code sent to {}:", email.trim()))?;
+
+            match self.email_change_service.confirm(&user.id, &old_code, &new_code, Utc::now()).await {
+                Ok(new_email) => {
+                    CLI::print_success(&format!("Your email is now {}", new_email));
+                    if let Some(updated) = self.user_service.get_user(&user.id).await? {
+                        *user = updated;
+                    }
+                    return Ok(());
+                }
+                Err(AppError::AuthenticationError(message)) if !message.starts_with("Too many") => CLI::print_error(&message),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn change_password_interactive(&self, user: &UserResponse, session: &Session) -> Result<()> {
+        let current = CLI::get_password("🔒 Current password:")?;
+        self.user_service.verify_password(&user.id, current.expose_secret()).await?;
+        let password = self.password_handler.new_password_interactive().await?;
+
+        let change = self.password_change_service.change(&user.id, &session.id, current.expose_secret(), password.expose_secret()).await?;
+        CLI::print_success("Your password has been changed.");
+        CLI::print_info(&format!(
+            "{} key(s) re-encrypted with the new password; {} other session(s) logged out.",
+            change.reencrypted_keys, change.revoked_sessions
+        ));
+        if !change.skipped_keys.is_empty() {
+            println!("{}", "These keys couldn't be opened with your old password and were left as they were:".warning());
+            for public_key in &change.skipped_keys {
+                println!("  {}", public_key);
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_account_interactive(&self, user: &UserResponse) -> Result<bool> {
+        println!();
+        println!("{}", "⚠️  This permanently deletes your account, contacts, settings and history,".error().bold());
+        println!("{}", "   and the encrypted secret keys stored here. Funds stay on the network, but".error().bold());
+        println!("{}", "   you can only reach them with a recovery phrase or key you have kept.".error().bold());
+        println!();
+
+        let confirmation = CLI::get_input(&format!("Type your username ({}) to confirm:", user.username))?;
+        if confirmation != user.username {
+            CLI::print_info("Username didn't match; nothing was deleted.");
+            return Ok(false);
+        }
+        let password = CLI::get_password("🔒 Password:")?;
+
+        match self.user_service.delete_user(&user.id, password.expose_secret()).await {
+            Ok(()) => {
+                self.audit_service
+                    .record(Some(&user.id), AuditEvent::AccountDeleted, &format!("username '{}'", user.username))
+                    .await?;
+                CLI::print_success("Your account has been deleted.");
+                Ok(true)
+            }
+            Err(e) => {
+                CLI::print_error(&e.to_string());
+                Ok(false)
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::cli::branding::Branding;
+use crate::cli::theme::Themed;
+use crate::cli::CLI;
+use crate::errors::Result;
+use crate::services::remote_service::RemoteService;
+use crate::stellar::amount::format_stroops;
+use secrecy::ExposeSecret;
+use stellar_wallet::client::{Direction, NewAccount, Payment, Role, User};
+use zeroize::Zeroizing;
+
+/// The interactive wallet for `--remote`: the menus for what a deployment's
+/// REST API offers, signing up, logging in, the account, payments, history
+/// and statistics. Everything else the local wallet does needs its database.
+pub struct RemoteHandler {
+    remote: RemoteService,
+}
+
+impl RemoteHandler {
+    pub fn new(remote: RemoteService) -> Self {
+        Self { remote }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        CLI::print_info(&format!("🌍 Remote mode: everything goes through {}.", self.remote.base_url()));
+        if let Ok(user) = self.remote.me().await {
+            CLI::print_success(&format!("Welcome back, {}! Resuming your saved login.", user.username));
+            self.dashboard(user).await?;
+        }
+
+        loop {
+            self.display_main_menu();
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => {
+                    if let Err(e) = self.create_account_interactive().await {
+                        CLI::print_error(&format!("Error: {}", e));
+                    }
+                    CLI::wait_for_enter();
+                }
+                "2" => match self.login_interactive().await {
+                    Ok(user) => self.dashboard(user).await?,
+                    Err(e) => {
+                        CLI::print_error(&format!("Error: {}", e));
+                        CLI::wait_for_enter();
+                    }
+                },
+                "3" => {
+                    CLI::print_info(&format!("👋 Thank you for using {}! Goodbye!", Branding::current().product_name));
+                    return Ok(());
+                }
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    CLI::wait_for_enter();
+                }
+            }
+        }
+    }
+
+    fn display_main_menu(&self) {
+        let branding = Branding::current();
+
+        CLI::clear_screen();
+        println!("{}", branding.rule(60));
+        println!("{}", branding.title(&format!("  🌍 {} @ {}", branding.product_name, self.remote.base_url())));
+        println!("{}", branding.rule(60));
+        println!();
+        println!("{}", "Main Menu:".heading());
+        println!("  1. 📝 Create New Account");
+        println!("  2. 🔐 Login to Account");
+        println!("  3. 🚪 Exit");
+        println!();
+    }
+
+    async fn create_account_interactive(&self) -> Result<()> {
+        let email = CLI::get_input("📧 Enter your email address:")?;
+        let username = CLI::get_input("👤 Choose a username:")?;
+        CLI::display_password_requirements();
+        let password = CLI::get_password("🔒 Choose a password:")?;
+
+        let account = NewAccount {
+            email,
+            username,
+            password: Zeroizing::new(password.expose_secret().to_owned()),
+        };
+        let user = self.remote.create_account(&account).await?;
+        CLI::print_success(&format!("Created account {}; you can log in now.", user.username));
+        Ok(())
+    }
+
+    async fn login_interactive(&self) -> Result<User> {
+        let identifier = CLI::get_input("📧 Enter your email or username:")?;
+        let password = CLI::get_password("🔒 Enter your password:")?;
+        let code = CLI::get_input("🔢 Authenticator or recovery code (leave empty if 2FA is off):")?;
+
+        let code = Some(code.as_str()).filter(|code| !code.is_empty());
+        let login = self.remote.login(&identifier, password.expose_secret(), code, &[]).await?;
+        CLI::print_success(&format!("Welcome, {}! The login is saved until you log out.", login.user.username));
+        Ok(login.user)
+    }
+
+    async fn dashboard(&self, mut user: User) -> Result<()> {
+        loop {
+            self.display_dashboard(&user);
+            let result = match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => self.remote.me().await.map(|me| {
+                    user = me;
+                    self.show_account(&user);
+                }),
+                "2" => self.send_interactive().await,
+                "3" => self.history_interactive(&user).await,
+                "4" if user.role != Role::User => self.stats().await,
+                "5" => {
+                    self.remote.logout().await?;
+                    CLI::print_success("Logged out.");
+                    return Ok(());
+                }
+                _ => {
+                    CLI::print_error("Invalid choice. Please try again.");
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                CLI::print_error(&format!("Error: {}", e));
+            }
+            CLI::wait_for_enter();
+        }
+    }
+
+    fn display_dashboard(&self, user: &User) {
+        CLI::clear_screen();
+        println!("{}", Branding::current().rule(60));
+        println!("{}", format!("  👤 {} @ {}", user.username, self.remote.base_url()).as_str().heading());
+        println!("{}", Branding::current().rule(60));
+        println!();
+        println!("  1. 🪪 Account Details");
+        println!("  2. 💸 Send Payment");
+        println!("  3. 📜 Transaction History");
+        if user.role != Role::User {
+            println!("  4. 📊 Statistics");
+        }
+        println!("  5. 🚪 Logout");
+        println!();
+    }
+
+    fn show_account(&self, user: &User) {
+        println!();
+        println!("{}", "🪪 Account:".heading());
+        println!("  Username: {}", user.username);
+        println!("  Email:    {}{}", user.email, if user.is_verified { " ✅" } else { " (unverified)" });
+        println!("  Role:     {:?}", user.role);
+        match &user.stellar_public_key {
+            Some(address) => println!("  Wallet:   {}", address),
+            None => println!("  Wallet:   {}", "none yet; generate one from the local wallet".muted()),
+        }
+        println!();
+    }
+
+    async fn send_interactive(&self) -> Result<()> {
+        let destination = CLI::get_input("🏦 Destination address (G...):")?;
+        let amount = CLI::get_input("💰 Amount in XLM:")?;
+        let memo = CLI::get_input("📝 Memo (optional):")?;
+        if !CLI::confirm_action(&format!("Send {} XLM to {}?", amount, destination))? {
+            CLI::print_info("Payment cancelled.");
+            return Ok(());
+        }
+        let password = CLI::get_password("🔒 Enter your password to sign:")?;
+        let pin = CLI::get_password("🔢 Signing PIN (leave empty if you have none):")?;
+        let code = CLI::get_input("🔢 Authenticator or recovery code (leave empty if 2FA is off):")?;
+
+        let payment = Payment {
+            destination: destination.clone(),
+            amount: amount.clone(),
+            memo: Some(memo).filter(|memo| !memo.is_empty()),
+            password: Zeroizing::new(password.expose_secret().to_owned()),
+            pin: Some(Zeroizing::new(pin.expose_secret().to_owned())).filter(|pin| !pin.is_empty()),
+            code: Some(code).filter(|code| !code.is_empty()),
+            allow_duplicate: false,
+        };
+        let receipt = self.remote.pay(&payment).await?;
+        CLI::print_success(&format!("Sent {} XLM to {} in ledger {} ({}).", amount, destination, receipt.ledger, receipt.hash));
+        Ok(())
+    }
+
+    async fn history_interactive(&self, user: &User) -> Result<()> {
+        let Some(address) = &user.stellar_public_key else {
+            CLI::print_info("This account has no wallet address yet.");
+            return Ok(());
+        };
+
+        let transactions = self.remote.transactions(address, None).await?;
+        println!();
+        println!("{}", "📜 Latest payments:".heading());
+        if transactions.is_empty() {
+            println!("  {}", "No payments yet.".muted());
+        }
+        for tx in transactions {
+            let arrow = match tx.direction {
+                Direction::Incoming => "⬅️",
+                Direction::Outgoing => "➡️",
+            };
+            println!(
+                "  {} {} {} {} {:?} {}",
+                tx.created_at.format("%Y-%m-%d %H:%M"),
+                arrow,
+                format_stroops(tx.amount_stroops),
+                tx.asset_code,
+                tx.status,
+                tx.counterparty.as_str().muted()
+            );
+        }
+        println!();
+        Ok(())
+    }
+
+    async fn stats(&self) -> Result<()> {
+        let stats = self.remote.stats().await?;
+
+        println!();
+        println!("{}", "📊 Database Statistics:".heading());
+        println!("👥 Total Users: {}", stats.total_users);
+        if let Some(sms) = &stats.sms_last_30_days {
+            println!("📱 SMS sent (30 days): {} (${:.2})", sms.messages, sms.cost_usd);
+        }
+        println!();
+        Ok(())
+    }
+}
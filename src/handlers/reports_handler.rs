@@ -0,0 +1,56 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::report::ReportRow;
+use crate::services::report_service::{ReportService, REPORT_SYNTAX};
+use colored::Colorize;
+
+pub struct ReportsHandler {
+    report_service: ReportService,
+}
+
+impl ReportsHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            report_service: ReportService::new(db),
+        }
+    }
+
+    pub async fn reports_interactive(&self) -> Result<()> {
+        println!("{}", "📈 Custom Reports".heading());
+        println!("{}", REPORT_SYNTAX.muted());
+        println!("{}", "  e.g. users where verified and since 2025-01-01 by month".muted());
+        println!("{}", "       transactions where failed and asset xlm by day".muted());
+        println!();
+
+        loop {
+            let query = CLI::get_input("Report (empty to go back):")?;
+
+            if query.is_empty() {
+                return Ok(());
+            }
+
+            match self.report_service.run(&query).await {
+                Ok(rows) => print_rows(&rows),
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        }
+    }
+}
+
+fn print_rows(rows: &[ReportRow]) {
+    println!();
+
+    if rows.is_empty() {
+        CLI::print_info("No matching rows.");
+        return;
+    }
+
+    let width = rows.iter().map(|row| row.group.len()).max().unwrap_or(0).max(5);
+    for row in rows {
+        println!("  {:<width$}  {:>8}", row.group, row.value, width = width);
+    }
+    println!("  {:<width$}  {:>8}", "total".bold(), rows.iter().map(|row| row.value).sum::<i64>(), width = width);
+    println!();
+}
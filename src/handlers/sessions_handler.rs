@@ -0,0 +1,81 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::session::Session;
+use crate::models::user::UserResponse;
+use crate::services::session_service::SessionService;
+use colored::Colorize;
+
+pub struct SessionsHandler {
+    session_service: SessionService,
+}
+
+impl SessionsHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            session_service: SessionService::new(db),
+        }
+    }
+
+    /// Lists the user's sessions and lets them revoke any. Returns `true` if
+    /// they revoked the current one.
+    pub async fn manage_sessions_interactive(&self, user: &UserResponse, current: &Session) -> Result<bool> {
+        loop {
+            let sessions = self.session_service.active_sessions(&user.id).await?;
+
+            println!();
+            println!("{}", "🔑 Active Sessions:".heading());
+            for (index, session) in sessions.iter().enumerate() {
+                let this = if session.id == current.id { " (this session)".success().to_string() } else { String::new() };
+                println!("  {}. {}{}", index + 1, session.device_label.bold(), this);
+                println!(
+                    "     {}",
+                    format!(
+                        "Signed in {} · last seen {} · expires {}",
+                        session.created_at.format("%Y-%m-%d %H:%M"),
+                        session.last_seen_at.format("%Y-%m-%d %H:%M"),
+                        session.expires_at.format("%Y-%m-%d %H:%M UTC")
+                    )
+                    .muted()
+                );
+            }
+            println!();
+
+            let choice = CLI::get_input("Session number to revoke, 'a' for all others (empty to go back):")?;
+            if choice.is_empty() {
+                return Ok(false);
+            }
+
+            if choice.eq_ignore_ascii_case("a") {
+                if sessions.len() < 2 {
+                    CLI::print_info("This is your only session.");
+                } else if CLI::confirm_action("Log out every other device?")? {
+                    match self.session_service.revoke_others(&user.id, &current.id).await {
+                        Ok(revoked) => CLI::print_success(&format!("Revoked {} other session(s).", revoked)),
+                        Err(e) => CLI::print_error(&e.to_string()),
+                    }
+                }
+                continue;
+            }
+
+            let session = match choice.parse::<usize>() {
+                Ok(index) if (1..=sessions.len()).contains(&index) => &sessions[index - 1],
+                _ => {
+                    CLI::print_error("Please enter one of the listed numbers");
+                    continue;
+                }
+            };
+
+            if session.id == current.id && !CLI::confirm_action("This is your current session; revoking it logs you out. Continue?")? {
+                continue;
+            }
+
+            match self.session_service.revoke(&user.id, &session.id).await {
+                Ok(()) if session.id == current.id => return Ok(true),
+                Ok(()) => CLI::print_success(&format!("Revoked '{}'.", session.device_label)),
+                Err(e) => CLI::print_error(&e.to_string()),
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::cli::CLI;
+use crate::cli::theme::{self, ThemeName, Themed};
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::handlers::api_keys_handler::ApiKeysHandler;
+use crate::handlers::customer_fields_handler::CustomerFieldsHandler;
+use crate::handlers::data_handler::DataHandler;
+use crate::handlers::login_history_handler::LoginHistoryHandler;
+use crate::handlers::policies_handler::PoliciesHandler;
+use crate::handlers::profile_handler::ProfileHandler;
+use crate::handlers::sessions_handler::SessionsHandler;
+use crate::handlers::sms_handler::SmsHandler;
+use crate::handlers::two_factor_handler::TwoFactorHandler;
+use crate::models::session::Session;
+use crate::models::undo::{LocalAction, UndoableSetting};
+use crate::models::user::UserResponse;
+use crate::models::user_settings::SUPPORTED_FIAT_CURRENCIES;
+use crate::services::settings_service::SettingsService;
+use crate::services::undo_service::UndoService;
+use std::rc::Rc;
+use uuid::Uuid;
+
+pub struct SettingsHandler {
+    settings_service: SettingsService,
+    policies_handler: PoliciesHandler,
+    customer_fields_handler: CustomerFieldsHandler,
+    sessions_handler: SessionsHandler,
+    login_history_handler: LoginHistoryHandler,
+    sms_handler: SmsHandler,
+    two_factor_handler: TwoFactorHandler,
+    api_keys_handler: ApiKeysHandler,
+    data_handler: DataHandler,
+    profile_handler: ProfileHandler,
+    undo_service: Rc<UndoService>,
+}
+
+impl SettingsHandler {
+    pub fn new(db: SqliteDatabase, undo_service: Rc<UndoService>) -> Self {
+        Self {
+            settings_service: SettingsService::new(db.clone()),
+            policies_handler: PoliciesHandler::new(db.clone()),
+            customer_fields_handler: CustomerFieldsHandler::new(db.clone()),
+            sessions_handler: SessionsHandler::new(db.clone()),
+            login_history_handler: LoginHistoryHandler::new(db.clone()),
+            sms_handler: SmsHandler::new(db.clone()),
+            two_factor_handler: TwoFactorHandler::new(db.clone()),
+            api_keys_handler: ApiKeysHandler::new(db.clone()),
+            data_handler: DataHandler::new(db.clone()),
+            profile_handler: ProfileHandler::new(db),
+            undo_service,
+        }
+    }
+
+    pub async fn settings_interactive(&self, user: &mut UserResponse, session: &Session) -> Result<()> {
+        loop {
+            let settings = self.settings_service.settings(&user.id).await?;
+
+            println!();
+            println!("{}", "⚙️  Settings".heading());
+            println!("  1. 💱 Display currency ({})", settings.fiat_currency.to_uppercase());
+            println!("  2. 🛡️  Payment Policies");
+            println!("  3. 🪪 KYC Details");
+            println!("  4. 🔑 Active Sessions");
+            println!("  5. 📱 SMS Verification");
+            println!("  6. 📦 Export / Import My Data");
+            println!("  7. 👤 Profile & Account");
+            println!("  8. 🎨 Theme ({})", settings.theme);
+            println!("  9. 🔐 Two-Factor Authentication");
+            println!("  10. 🗝️  API Keys");
+            println!("  11. 🕵️  Recent Activity");
+            println!("  12. ↩️  Back");
+            println!();
+
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => {
+                    println!("{}", format!("Available: {}", SUPPORTED_FIAT_CURRENCIES.join(", ")).muted());
+                    let currency = CLI::get_input("💱 Currency code:")?;
+
+                    match self.settings_service.set_fiat_currency(&user.id, &currency).await {
+                        Ok(updated) => {
+                            CLI::print_success(&format!("Fiat values will be shown in {}", updated.fiat_currency.to_uppercase()));
+                            self.record_change(&user.id, UndoableSetting::FiatCurrency, settings.fiat_currency, updated.fiat_currency);
+                        }
+                        Err(e) => CLI::print_error(&e.to_string()),
+                    }
+                }
+                "2" => self.policies_handler.manage_policies_interactive(Some(&user.id)).await?,
+                "3" => self.customer_fields_handler.manage_customer_fields_interactive(user).await?,
+                "4" => {
+                    if self.sessions_handler.manage_sessions_interactive(user, session).await? {
+                        return Ok(());
+                    }
+                }
+                "5" => self.sms_handler.manage_sms_interactive(user).await?,
+                "6" => self.data_handler.manage_data_interactive(user).await?,
+                "7" => {
+                    if self.profile_handler.manage_profile_interactive(user, session).await? {
+                        return Ok(());
+                    }
+                }
+                "8" => self.choose_theme_interactive(&user.id, settings.theme).await?,
+                "9" => self.two_factor_handler.manage_two_factor_interactive(user).await?,
+                "10" => self.api_keys_handler.manage_api_keys_interactive(user).await?,
+                "11" => {
+                    self.login_history_handler.recent_activity_interactive(user).await?;
+                    CLI::wait_for_enter();
+                }
+                "12" => return Ok(()),
+                _ => CLI::print_error("Invalid choice. Please try again."),
+            }
+        }
+    }
+    /// Switches to the theme saved for `user_id`. A name this build doesn't
+    /// know falls back to the default rather than blocking the login.
+    pub async fn apply_saved_theme(&self, user_id: &Uuid) {
+        let saved = self.settings_service.settings(user_id).await.ok().and_then(|settings| ThemeName::parse(&settings.theme).ok());
+        theme::apply(saved.unwrap_or(ThemeName::Default));
+    }
+
+    async fn choose_theme_interactive(&self, user_id: &Uuid, before: String) -> Result<()> {
+        println!();
+        for (i, name) in ThemeName::ALL.iter().enumerate() {
+            let marker = if *name == theme::active() { " (current)" } else { "" };
+            println!("  {}. {}{}", i + 1, name.as_str(), marker);
+        }
+
+        let choice = CLI::get_input("🎨 Theme number or name:")?;
+        let picked = match choice.parse::<usize>() {
+            Ok(n) if (1..=ThemeName::ALL.len()).contains(&n) => Ok(ThemeName::ALL[n - 1]),
+            _ => ThemeName::parse(&choice),
+        };
+
+        match picked {
+            Ok(name) => {
+                self.settings_service.set_theme(user_id, name).await?;
+                theme::apply(name);
+                CLI::print_success(&format!("Theme set to {}", name.as_str()));
+                self.record_change(user_id, UndoableSetting::Theme, before, name.as_str().to_string());
+            }
+            Err(e) => CLI::print_error(&e.to_string()),
+        }
+        Ok(())
+    }
+
+    /// Offers the change for undo unless it left the value as it was.
+    fn record_change(&self, user_id: &Uuid, setting: UndoableSetting, before: String, after: String) {
+        if before != after {
+            self.undo_service.record(LocalAction::SettingChanged {
+                user_id: *user_id,
+                setting,
+                before,
+                after,
+            });
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::user::UserResponse;
+use crate::services::key_rotation_service::KeyRotationService;
+use crate::services::signer_service::{SignerKind, SignerService};
+use crate::services::signing_pin_service::SigningPinService;
+use crate::stellar::network::Network;
+use crate::stellar::signer::Signer;
+use chrono::Utc;
+use secrecy::ExposeSecret;
+
+pub struct SigningHandler {
+    signer_service: SignerService,
+    key_rotation_service: KeyRotationService,
+    signing_pin_service: SigningPinService,
+}
+
+impl SigningHandler {
+    pub fn new(db: SqliteDatabase, network: Network) -> Self {
+        Self {
+            signer_service: SignerService::new(db.clone()),
+            key_rotation_service: KeyRotationService::new(db.clone(), network),
+            signing_pin_service: SigningPinService::new(db),
+        }
+    }
+
+    /// Gets a signer for `public_key` once the signing PIN, if any, checks out:
+    /// asks for the password for keystore keys, or connects to the Ledger for
+    /// device-held ones.
+    pub async fn unlock_signer_interactive(&self, user: &UserResponse, public_key: &str) -> Result<Box<dyn Signer>> {
+        self.require_pin_interactive(user).await?;
+
+        match self.signer_service.signer_kind(&user.id, public_key).await? {
+            SignerKind::Software => {
+                let password = CLI::get_password("🔒 Enter your password to sign:")?;
+                self.signer_service.unlock_software(&user.id, public_key, password.expose_secret()).await
+            }
+            SignerKind::Ledger { account_index } => {
+                CLI::print_info("🔌 Connect your Ledger, open the Stellar app and review the transaction on the device.");
+                self.signer_service.connect_ledger(public_key, account_index).await
+            }
+        }
+    }
+
+    pub async fn manage_signing_device_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        println!();
+        println!("{}", "🔌 Signing Device".heading());
+
+        match user.stellar_public_key.as_deref() {
+            Some(address) => match self.signer_service.signer_kind(&user.id, address).await? {
+                SignerKind::Software => println!("Current: 🔑 software key for {}", address),
+                SignerKind::Ledger { account_index } => println!("Current: 🔌 Ledger account #{} ({})", account_index, address),
+            },
+            None => println!("Current: {}", "no wallet address yet".muted()),
+        }
+
+        println!();
+        println!("  1. 🔌 Use a Ledger account");
+        println!("  2. 🔑 Use a software key from your keystore");
+        println!("  3. 🔄 Rotate your signing key");
+        println!("  4. 🔢 Signing PIN");
+        println!("  5. ↩️  Back");
+        println!();
+
+        match CLI::get_input("Enter your choice:")?.as_str() {
+            "1" => self.use_ledger_interactive(user).await,
+            "2" => self.use_software_key_interactive(user).await,
+            "3" => self.rotate_key_interactive(user).await,
+            "4" => self.manage_pin_interactive(user).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Moves the wallet address over to a freshly generated keystore key, e.g.
+    /// when the old secret may have leaked.
+    async fn rotate_key_interactive(&self, user: &UserResponse) -> Result<()> {
+        let Some(account) = user.stellar_public_key.as_deref() else {
+            CLI::print_info("Generate a wallet address from the dashboard first.");
+            return Ok(());
+        };
+
+        println!();
+        println!("{}", "A new key will be generated and made the only one that can sign for your address.".muted());
+        println!("{}", "Your address, balances and history stay the same; the old key stops working.".muted());
+        if !CLI::confirm_action(&format!("Rotate the signing key for {}?", account))? {
+            CLI::print_info("Cancelled.");
+            return Ok(());
+        }
+
+        self.require_pin_interactive(user).await?;
+        let password = CLI::get_password("🔒 Enter your password to sign:")?;
+        let rotation = self.key_rotation_service.rotate(&user.id, account, password.expose_secret()).await?;
+
+        CLI::print_success(&format!("{} now signs for {}", rotation.new_signer, account));
+        CLI::print_info(&format!("{} was removed as a signer in transaction {}", rotation.old_signer, rotation.hash));
+        Ok(())
+    }
+
+    /// Asks for the signing PIN if the user has one.
+    async fn require_pin_interactive(&self, user: &UserResponse) -> Result<()> {
+        if !self.signing_pin_service.is_enabled(&user.id).await? {
+            return Ok(());
+        }
+        let pin = CLI::get_password("🔢 Enter your signing PIN:")?;
+        self.signing_pin_service.verify(&user.id, pin.expose_secret(), Utc::now()).await
+    }
+
+    async fn manage_pin_interactive(&self, user: &UserResponse) -> Result<()> {
+        println!();
+        println!("{}", "A signing PIN is asked for before every transaction, on top of your password or Ledger.".muted());
+        println!("{}", "It keeps a logged-in terminal left unattended from being used to send funds.".muted());
+
+        if self.signing_pin_service.is_enabled(&user.id).await? {
+            println!();
+            println!("  1. 🔢 Change your PIN");
+            println!("  2. 🗑️  Remove your PIN");
+            println!("  3. ↩️  Back");
+            println!();
+
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => self.set_pin_interactive(user).await,
+                "2" => {
+                    let password = CLI::get_password("🔒 Enter your password to remove the PIN:")?;
+                    self.signing_pin_service.remove(&user.id, password.expose_secret()).await?;
+                    CLI::print_success("Signing PIN removed.");
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        } else if CLI::confirm_action("Set a signing PIN?")? {
+            self.set_pin_interactive(user).await
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn set_pin_interactive(&self, user: &UserResponse) -> Result<()> {
+        let pin = CLI::get_password("🔢 New PIN (4 to 8 digits):")?;
+        if CLI::get_password("🔢 Repeat the PIN:")?.expose_secret() != pin.expose_secret() {
+            CLI::print_error("The PINs don't match.");
+            return Ok(());
+        }
+        let password = CLI::get_password("🔒 Enter your password to confirm:")?;
+
+        self.signing_pin_service.set(&user.id, password.expose_secret(), pin.expose_secret()).await?;
+        CLI::print_success("Signing PIN set. You'll be asked for it before each transaction.");
+        Ok(())
+    }
+
+    async fn use_ledger_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        let account_index = loop {
+            let input = CLI::get_input("Ledger account number (Enter for 0):")?;
+
+            if input.is_empty() {
+                break 0;
+            }
+            match input.parse::<u32>() {
+                Ok(index) if index < 0x8000_0000 => break index,
+                _ => CLI::print_error("Please enter an account number"),
+            }
+        };
+
+        CLI::print_info("🔌 Connect your Ledger, open the Stellar app and approve the address on the device.");
+        let public_key = self.signer_service.link_ledger_account(&user.id, account_index).await?;
+        user.stellar_public_key = Some(public_key.clone());
+
+        CLI::print_success(&format!("Payments from {} will now be signed on your Ledger", public_key));
+        Ok(())
+    }
+
+    async fn use_software_key_interactive(&self, user: &mut UserResponse) -> Result<()> {
+        let keys = self.signer_service.software_keys(&user.id).await?;
+
+        if keys.is_empty() {
+            CLI::print_info("Your keystore is empty. Generate an address from the dashboard first.");
+            return Ok(());
+        }
+
+        println!();
+        for (number, key) in keys.iter().enumerate() {
+            println!(
+                "  {}. {} {}",
+                number + 1,
+                key.public_key,
+                key.label.as_deref().map(|label| format!("({})", label)).unwrap_or_default().muted()
+            );
+        }
+        println!();
+
+        let key = loop {
+            let choice = CLI::get_input("Key number (empty to cancel):")?;
+
+            if choice.is_empty() {
+                return Ok(());
+            }
+            match choice.parse::<usize>().ok().and_then(|n| n.checked_sub(1)).and_then(|i| keys.get(i)) {
+                Some(key) => break key,
+                None => CLI::print_error("That key isn't in the list"),
+            }
+        };
+
+        self.signer_service.link_software_key(&user.id, &key.public_key).await?;
+        user.stellar_public_key = Some(key.public_key.clone());
+
+        CLI::print_success(&format!("Payments from {} will be signed with your keystore key", key.public_key));
+        Ok(())
+    }
+}
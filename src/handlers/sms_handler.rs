@@ -0,0 +1,154 @@
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::sms::SmsPurpose;
+use crate::models::user::UserResponse;
+use crate::services::settings_service::SettingsService;
+use crate::services::sms_service::{calling_code, SmsService};
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Wrong codes allowed before the action is refused.
+const MAX_CODE_ATTEMPTS: usize = 3;
+
+pub struct SmsHandler {
+    sms_service: SmsService,
+    settings_service: SettingsService,
+}
+
+impl SmsHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            sms_service: SmsService::new(db.clone()),
+            settings_service: SettingsService::new(db),
+        }
+    }
+
+    pub fn sms_service(&self) -> &SmsService {
+        &self.sms_service
+    }
+
+    /// Asks for a texted code if the user turned SMS verification on. Returns
+    /// whether the action may go ahead.
+    pub async fn require_code_interactive(&self, user_id: &Uuid, purpose: SmsPurpose) -> Result<bool> {
+        let settings = self.settings_service.settings(user_id).await?;
+        let Some(phone) = settings.sms_verification_number() else {
+            return Ok(true);
+        };
+
+        if !self.sms_service.is_enabled() {
+            return Err(AppError::AuthenticationError(
+                "SMS verification is on for this account but SMS is not available; contact support".to_string(),
+            ));
+        }
+
+        self.verify_code_interactive(user_id, phone, purpose).await
+    }
+
+    async fn verify_code_interactive(&self, user_id: &Uuid, phone: &str, purpose: SmsPurpose) -> Result<bool> {
+        let code = self.sms_service.send_code(user_id, phone, purpose).await?;
+        CLI::print_info(&format!("We texted a code to {}.", mask_phone(phone)));
+
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            let input = CLI::get_input("📱 Code:")?;
+
+            if code.matches(&input, Utc::now()) {
+                return Ok(true);
+            }
+            if Utc::now() >= code.expires_at {
+                CLI::print_error("That code has expired.");
+                return Ok(false);
+            }
+            CLI::print_error("Incorrect code.");
+        }
+
+        Ok(false)
+    }
+
+    pub async fn manage_sms_interactive(&self, user: &UserResponse) -> Result<()> {
+        if !self.sms_service.is_enabled() {
+            CLI::print_info("SMS is not enabled on this wallet.");
+            return Ok(());
+        }
+
+        loop {
+            let settings = self.settings_service.settings(&user.id).await?;
+
+            println!();
+            println!("{}", "📱 SMS Verification".heading());
+            match &settings.sms_phone_number {
+                Some(phone) => println!("  Phone: {}", mask_phone(phone)),
+                None => println!("  Phone: {}", "none".muted()),
+            }
+            println!(
+                "  Codes at login and before payments: {}",
+                if settings.sms_verification_enabled { "on".success() } else { "off".muted() }
+            );
+            println!();
+            println!("  1. ☎️  Set phone number");
+            println!("  2. 🔁 Turn verification {}", if settings.sms_verification_enabled { "off" } else { "on" });
+            println!("  3. 🗑️  Remove phone number");
+            println!("  4. ↩️  Back");
+            println!();
+
+            match CLI::get_input("Enter your choice:")?.as_str() {
+                "1" => {
+                    // With verification on, moving it to another number needs the current one.
+                    if !self.require_code_interactive(&user.id, SmsPurpose::PhoneConfirmation).await? {
+                        CLI::print_error("Phone number kept.");
+                        continue;
+                    }
+
+                    let phone = CLI::get_input("☎️  Phone number (e.g. +254712345678):")?;
+
+                    match self.verify_code_interactive(&user.id, &phone, SmsPurpose::PhoneConfirmation).await {
+                        Ok(true) => {
+                            self.settings_service.set_sms_phone_number(&user.id, Some(&phone)).await?;
+                            CLI::print_success("Phone number confirmed. Security alerts will be texted to it.");
+                        }
+                        Ok(false) => CLI::print_error("Phone number not confirmed."),
+                        Err(e) => CLI::print_error(&e.to_string()),
+                    }
+                }
+                "2" => {
+                    let enabled = !settings.sms_verification_enabled;
+
+                    if !enabled && !self.require_code_interactive(&user.id, SmsPurpose::PhoneConfirmation).await? {
+                        CLI::print_error("SMS verification left on.");
+                        continue;
+                    }
+
+                    match self.settings_service.set_sms_verification(&user.id, enabled).await {
+                        Ok(_) if enabled => CLI::print_success("You'll be asked for a texted code at login and before payments."),
+                        Ok(_) => CLI::print_success("SMS verification turned off."),
+                        Err(e) => CLI::print_error(&e.to_string()),
+                    }
+                }
+                "3" => {
+                    if !self.require_code_interactive(&user.id, SmsPurpose::PhoneConfirmation).await? {
+                        CLI::print_error("Phone number kept.");
+                        continue;
+                    }
+
+                    self.settings_service.set_sms_phone_number(&user.id, None).await?;
+                    CLI::print_success("Phone number removed and SMS verification turned off.");
+                }
+                "4" => return Ok(()),
+                _ => CLI::print_error("Invalid choice. Please try again."),
+            }
+        }
+    }
+}
+
+/// Shows only the last few digits, e.g. `+254•••••5678`.
+fn mask_phone(phone: &str) -> String {
+    let visible = phone.len().saturating_sub(4);
+    let prefix_len = calling_code(phone).map_or(1, |code| code.len() + 1);
+
+    if visible <= prefix_len {
+        return phone.to_string();
+    }
+
+    format!("{}{}{}", &phone[..prefix_len], "•".repeat(visible - prefix_len), &phone[visible..])
+}
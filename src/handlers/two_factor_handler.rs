@@ -0,0 +1,143 @@
+use crate::cli::branding::Branding;
+use crate::cli::CLI;
+use crate::cli::theme::Themed;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::user::UserResponse;
+use crate::services::two_factor_service::{SecondFactor, TwoFactorService};
+use crate::utils::qr::QrRenderer;
+use chrono::Utc;
+use colored::Colorize;
+use uuid::Uuid;
+
+/// Wrong codes allowed before the action is refused.
+const MAX_CODE_ATTEMPTS: usize = 3;
+
+pub struct TwoFactorHandler {
+    two_factor_service: TwoFactorService,
+}
+
+impl TwoFactorHandler {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            two_factor_service: TwoFactorService::new(db),
+        }
+    }
+
+    /// Asks for an authenticator or recovery code if the user turned 2FA on.
+    /// Returns whether the action may go ahead.
+    pub async fn require_code_interactive(&self, user_id: &Uuid) -> Result<bool> {
+        if !self.two_factor_service.is_enabled(user_id).await? {
+            return Ok(true);
+        }
+
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            let input = CLI::get_input("🔐 Authenticator code (or a recovery code):")?;
+
+            match self.two_factor_service.verify(user_id, &input, Utc::now()).await? {
+                SecondFactor::Code => return Ok(true),
+                SecondFactor::RecoveryCode { remaining } => {
+                    CLI::print_info(&format!("Recovery code used; {} left.", remaining));
+                    if remaining <= 2 {
+                        println!("{}", "Generate new recovery codes under Settings → Two-Factor Authentication.".warning());
+                    }
+                    return Ok(true);
+                }
+                SecondFactor::Rejected => CLI::print_error("Incorrect or already used code."),
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn manage_two_factor_interactive(&self, user: &UserResponse) -> Result<()> {
+        if !self.two_factor_service.is_available() {
+            CLI::print_info("Two-factor authentication is not enabled on this wallet.");
+            return Ok(());
+        }
+
+        println!();
+        println!("{}", "🔐 Two-Factor Authentication".heading());
+
+        if !self.two_factor_service.is_enabled(&user.id).await? {
+            println!("Status: {}", "off".warning());
+            println!("With it on, logins and payments also need a code from an authenticator app.");
+            if CLI::confirm_action("Turn on two-factor authentication?")? {
+                self.enroll_interactive(user).await?;
+            }
+            return Ok(());
+        }
+
+        let remaining = self.two_factor_service.remaining_recovery_codes(&user.id).await?;
+        println!("Status: {} ({} recovery code(s) left)", "on".success(), remaining);
+        println!("  1. 🔄 New recovery codes");
+        println!("  2. 🚫 Turn off");
+        println!("  3. ↩️  Back");
+        println!();
+
+        match CLI::get_input("Enter your choice:")?.as_str() {
+            "1" => {
+                if !self.require_code_interactive(&user.id).await? {
+                    CLI::print_error("Code not confirmed; your recovery codes are unchanged.");
+                    return Ok(());
+                }
+                let codes = self.two_factor_service.regenerate_recovery_codes(&user.id).await?;
+                Self::print_recovery_codes(&codes);
+            }
+            "2" => {
+                if !self.require_code_interactive(&user.id).await? {
+                    CLI::print_error("Code not confirmed; two-factor authentication stays on.");
+                    return Ok(());
+                }
+                self.two_factor_service.disable(&user.id).await?;
+                CLI::print_success("Two-factor authentication is off.");
+            }
+            "3" => {}
+            _ => CLI::print_error("Invalid choice. Please try again."),
+        }
+        Ok(())
+    }
+
+    async fn enroll_interactive(&self, user: &UserResponse) -> Result<()> {
+        let enrollment = self
+            .two_factor_service
+            .begin_enrollment(&user.id, &user.username, &Branding::current().product_name)
+            .await?;
+
+        println!();
+        CLI::print_info("Scan this with your authenticator app:");
+        println!("{}", QrRenderer::render_terminal(&enrollment.otpauth_url)?);
+        println!("Or enter this key by hand: {}", enrollment.secret_base32.bold());
+        println!();
+
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            let code = CLI::get_input("🔐 Code shown in the app (empty to cancel):")?;
+            if code.is_empty() {
+                break;
+            }
+
+            match self.two_factor_service.confirm_enrollment(&user.id, &code, Utc::now()).await? {
+                Some(codes) => {
+                    CLI::print_success("Two-factor authentication is on.");
+                    Self::print_recovery_codes(&codes);
+                    return Ok(());
+                }
+                None => CLI::print_error("That code doesn't match; check the app's clock and try again."),
+            }
+        }
+
+        CLI::print_info("Two-factor authentication was not turned on.");
+        Ok(())
+    }
+
+    fn print_recovery_codes(codes: &[String]) {
+        println!();
+        println!("{}", "Recovery codes".heading());
+        println!("{}", "Each works once if you lose your authenticator. Store them somewhere safe; they won't be shown again.".warning());
+        for pair in codes.chunks(2) {
+            println!("  {}", pair.join("    "));
+        }
+        println!();
+        CLI::wait_for_enter();
+    }
+}
@@ -0,0 +1,5 @@
+//! Pieces of the wallet backend that other programs can depend on. The wallet
+//! itself is the `stellar-wallet` binary.
+
+pub mod client;
+pub mod webhook;
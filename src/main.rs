@@ -1,24 +1,141 @@
+mod api;
 mod cli;
+mod config;
 mod database;
 mod errors;
 mod handlers;
 mod models;
 mod services;
+mod stellar;
 mod utils;
 
+use api::cors::CorsPolicy;
+use api::tls::{TlsConfig, TlsListener};
+use api::auth::LoginRequest;
+use api::payments::PaymentRequest;
+use api::scripted::Scripted;
+use api::ApiState;
+use cli::args::{self, AccountCommand, Args, Command, CustomerKeysCommand, DbCommand, EmailCommand, MigrateCommand, PasswordSource, UsersCommand, WebhooksCommand};
+use cli::branding::Branding;
+use cli::output::{self, OutputFormat};
+use cli::transcript::Transcript;
+use cli::theme::Themed;
 use cli::CLI;
-use colored::Colorize;
+use config::Config;
+use database::encryption::KeySource;
+use database::sqlite::SqliteDatabase;
+use database::user_repository::UserRepository;
+use errors::AppError;
 use handlers::account_handler::AccountHandler;
+use handlers::dashboard_handler::DashboardHandler;
+use handlers::remote_handler::RemoteHandler;
+use models::api_key::ApiScope;
+use models::audit::AuditEvent;
+use models::email::EmailTemplate;
+use models::role::Role;
+use models::user::CreateUserRequest;
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use serde_json::json;
+use services::account_cache_service::AccountCacheService;
+use services::archive_service::ArchiveService;
+use services::audit_service::AuditService;
+use services::breach_check_service::BreachCheckService;
+use services::customer_field_service::CustomerFieldService;
+use services::email_template_service::{self, EmailTemplateService};
+use services::event_outbox_service::EventOutboxService;
+use services::health_service::{Check, HealthService, VersionInfo};
+use services::hook_service::HookService;
+use services::maintenance_service::MaintenanceService;
+use services::oauth_service::OAuthService;
+use services::passkey_service::PasskeyService;
+use services::rate_limit_service::RateLimitService;
+use services::reconciliation_service::ReconciliationService;
+use services::remote_service::RemoteService;
+use services::security_event_sink::SecurityEventSink;
+use services::token_service::{SessionStore, TokenService};
+use services::transaction_export_service::TransactionExportService;
+use services::two_factor_service::TwoFactorService;
+use services::user_service::UserService;
+use services::webhook_endpoint_service::WebhookEndpointService;
+use services::webhook_service::WebhookService;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use stellar::amount::format_stroops;
+use stellar::horizon::HorizonClient;
+use stellar::network::Network;
+use stellar_wallet::client::{NewAccount, Payment as RemotePayment};
+use tokio::net::TcpListener;
+use utils::validation::Validator;
+use zeroize::Zeroizing;
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = run().await {
+    let args = Args::read();
+    if let Err(e) = run(args).await {
         CLI::print_error(&format!("Application error: {}", e));
+        if let Some(support) = Branding::current().support_line() {
+            CLI::print_info(&support);
+        }
+        std::process::exit(1);
     }
 }
 
-async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let account_handler = AccountHandler::new().await?;
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    // Before anything reads the configuration, so a broken config file or
+    // SIEM setting can't stop the shell from loading its completions.
+    if let Some(Command::Completions { shell }) = args.command {
+        return Ok(args::write_completions(shell, &mut std::io::stdout())?);
+    }
+
+    Config::load()?.install();
+    Branding::from_env()?.install();
+    if let Some(sink) = SecurityEventSink::from_env()? {
+        sink.install();
+    }
+
+    args.output.install();
+    if let Some(command) = args.command {
+        return match &args.remote {
+            Some(url) => run_remote_command(url, command).await,
+            None => run_command(command).await,
+        };
+    }
+    if let Some(addr) = args.serve {
+        return serve_api(addr, args.ephemeral).await;
+    }
+    if let Some(path) = &args.record {
+        Transcript::start(path)?;
+        CLI::print_info(&format!("📼 Recording this session to {} (passwords and keys are masked).", path));
+    }
+    if let Some(url) = &args.remote {
+        RemoteHandler::new(RemoteService::new(url, SessionStore::from_env()?)?).run().await?;
+        return Ok(());
+    }
+
+    let network = Network::from_env()?;
+    let db = if args.ephemeral {
+        CLI::print_info("🧪 Ephemeral mode: everything is kept in memory and lost on exit.");
+        SqliteDatabase::ephemeral().await?
+    } else {
+        SqliteDatabase::open_default().await?
+    };
+    let hooks = Arc::new(HookService::from_env()?);
+    let mut account_handler = AccountHandler::new(db.clone(), hooks.clone()).with_session_store(SessionStore::from_env()?);
+    if let Some(tokens) = TokenService::from_env()? {
+        account_handler = account_handler.with_tokens(tokens);
+    }
+    if let Some(breach_check) = BreachCheckService::from_env()? {
+        account_handler = account_handler.with_breach_check(breach_check);
+    }
+
+    if let Some((user, session)) = account_handler.resume_session().await? {
+        if let Err(e) = DashboardHandler::new(user, session, db.clone(), network.clone(), hooks.clone()).run().await {
+            CLI::print_error(&format!("Error: {}", e));
+        }
+        account_handler.forget_session()?;
+    }
 
     loop {
         display_main_menu();
@@ -30,27 +147,40 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
                 if let Err(e) = account_handler.create_account_interactive().await {
                     CLI::print_error(&format!("Error: {}", e));
                 }
-                wait_for_enter();
+                CLI::wait_for_enter();
             }
             "2" => {
-                if let Err(e) = account_handler.login_interactive().await {
-                    CLI::print_error(&format!("Error: {}", e));
+                match account_handler.login_interactive().await {
+                    Ok(Some((user, session))) => {
+                        if let Err(e) = DashboardHandler::new(user, session, db.clone(), network.clone(), hooks.clone()).run().await {
+                            CLI::print_error(&format!("Error: {}", e));
+                        }
+                        account_handler.forget_session()?;
+                    }
+                    Ok(None) => {}
+                    Err(e) => CLI::print_error(&format!("Error: {}", e)),
                 }
-                wait_for_enter();
+                CLI::wait_for_enter();
             }
             "3" => {
                 if let Err(e) = account_handler.show_stats().await {
                     CLI::print_error(&format!("Error: {}", e));
                 }
-                wait_for_enter();
+                CLI::wait_for_enter();
             }
             "4" => {
-                CLI::print_info("👋 Thank you for using Stellar Wallet! Goodbye!");
+                if let Err(e) = account_handler.recover_account_interactive().await {
+                    CLI::print_error(&format!("Error: {}", e));
+                }
+                CLI::wait_for_enter();
+            }
+            "5" => {
+                CLI::print_info(&format!("👋 Thank you for using {}! Goodbye!", Branding::current().product_name));
                 break;
             }
             _ => {
                 CLI::print_error("Invalid choice. Please try again.");
-                wait_for_enter();
+                CLI::wait_for_enter();
             }
         }
     }
@@ -58,24 +188,697 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+async fn run_command(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Account(AccountCommand::Create { email, username, password }) => create_account(email, username, &password).await,
+        Command::Login { identifier, password, code, scopes } => {
+            let request = LoginRequest {
+                identifier,
+                password: password.read()?,
+                code,
+                passkey: None,
+                scopes: (!scopes.is_empty()).then_some(scopes),
+            };
+            login(request).await
+        }
+        Command::Logout => logout().await,
+        Command::Pay { to, amount, memo, password, code, allow_duplicate } => {
+            let request = PaymentRequest {
+                destination: to,
+                amount,
+                memo,
+                password: password.read()?,
+                pin: args::signing_pin(),
+                code,
+                allow_duplicate,
+            };
+            pay(request).await
+        }
+        Command::Stats => stats().await,
+        Command::Migrate(MigrateCommand::Status) => migrate_status().await,
+        Command::CustomerKeys(CustomerKeysCommand::Rotate) => rotate_customer_keys().await,
+        Command::Db(DbCommand::Encrypt) => encrypt_database().await,
+        Command::Db(DbCommand::Maintain) => maintain_database().await,
+        Command::Db(DbCommand::Archive { months }) => archive_transactions(months).await,
+        Command::Db(DbCommand::Export) => export_transactions().await,
+        Command::Db(DbCommand::CopyUsers) => copy_users_to_mysql().await,
+        Command::Webhooks(WebhooksCommand::Test) => send_test_webhook().await,
+        Command::Email(EmailCommand::Preview { template, locale, html }) => preview_email(&template, locale, html),
+        Command::Reconcile { address, repair } => reconcile(&address, repair).await,
+        Command::Users(UsersCommand::Role { user, role }) => set_user_role(&user, &role).await,
+        Command::Doctor => doctor().await,
+        Command::Completions { shell } => Ok(args::write_completions(shell, &mut std::io::stdout())?),
+    }
+}
+
+/// The commands with `--remote`: those the deployment's REST API offers, run
+/// as the login saved for it.
+async fn run_remote_command(url: &str, command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    let remote = RemoteService::new(url, SessionStore::from_env()?)?;
+    match command {
+        Command::Account(AccountCommand::Create { email, username, password }) => {
+            let account = NewAccount {
+                email,
+                username,
+                password: Zeroizing::new(password.read()?.expose_secret().to_owned()),
+            };
+            let user = remote.create_account(&account).await?;
+            output::emit(&user, |user| CLI::print_success(&format!("Created account {} ({}).", user.username, user.id)))?;
+        }
+        Command::Login { identifier, password, code, scopes } => {
+            let scopes = scopes.iter().map(String::as_str).collect::<Vec<_>>();
+            let login = remote.login(&identifier, password.read()?.expose_secret(), code.as_deref(), &scopes).await?;
+            output::emit(&login.user, |user| {
+                CLI::print_success(&format!("Logged in to {} as {}; the login is saved until you log out.", url, user.username));
+                let granted = login.scopes.iter().filter_map(|scope| ApiScope::parse(scope)).collect::<Vec<_>>();
+                if granted.len() < ApiScope::ALL.len() {
+                    CLI::print_grants(&granted);
+                }
+            })?;
+        }
+        Command::Logout => {
+            remote.logout().await?;
+            output::emit(&json!({ "logged_out": true }), |_| CLI::print_success("Logged out."))?;
+        }
+        Command::Pay { to, amount, memo, password, code, allow_duplicate } => {
+            let payment = RemotePayment {
+                destination: to.clone(),
+                amount: amount.clone(),
+                memo,
+                password: Zeroizing::new(password.read()?.expose_secret().to_owned()),
+                pin: args::signing_pin().map(|pin| Zeroizing::new(pin.expose_secret().to_owned())),
+                code,
+                allow_duplicate,
+            };
+            let sent = remote.pay(&payment).await?;
+            output::emit(&sent, |sent| {
+                CLI::print_success(&format!("Sent {} XLM to {} in ledger {} ({}).", amount, to, sent.ledger, sent.hash))
+            })?;
+        }
+        Command::Stats => {
+            let stats = remote.stats().await?;
+            output::emit(&stats, |stats| {
+                println!();
+                println!("{}", "📊 Database Statistics:".heading());
+                println!("👥 Total Users: {}", stats.total_users);
+                if let Some(sms) = &stats.sms_last_30_days {
+                    println!("📱 SMS sent (30 days): {} (${:.2})", sms.messages, sms.cost_usd);
+                }
+                println!();
+            })?;
+        }
+        _ => {
+            return Err(AppError::ValidationError(
+                "That command works on this wallet's own database; with --remote there are account create, login, logout, pay and stats".to_string(),
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
+/// Signs up without prompting, with the password from `password`.
+async fn create_account(email: String, username: String, password: &PasswordSource) -> Result<(), Box<dyn std::error::Error>> {
+    let password = password.read()?;
+    let db = SqliteDatabase::open_default().await?;
+    let mut account_handler = AccountHandler::new(db, Arc::new(HookService::from_env()?));
+    if let Some(breach_check) = BreachCheckService::from_env()? {
+        account_handler = account_handler.with_breach_check(breach_check);
+    }
+
+    let user = account_handler.create_account(CreateUserRequest { email, username, password }).await?;
+    output::emit(&user, |user| CLI::print_success(&format!("Created account {} ({}).", user.username, user.id)))?;
+    Ok(())
+}
+
+/// The API's services over the wallet's database, acting as the saved login.
+async fn scripted() -> Result<Scripted, Box<dyn std::error::Error>> {
+    let tokens = TokenService::from_env()?.ok_or_else(|| {
+        AppError::ValidationError("Set JWT_SIGNING_KEY; logins are saved as signed access tokens".to_string())
+    })?;
+    let state = ApiState::new(SqliteDatabase::open_default().await?, &Network::from_env()?, tokens)
+        .with_hooks(Arc::new(HookService::from_env()?));
+    Ok(Scripted::new(state, SessionStore::from_env()?))
+}
+
+async fn login(request: LoginRequest) -> Result<(), Box<dyn std::error::Error>> {
+    let login = scripted().await?.login(request).await?;
+    output::emit(&login.user, |user| {
+        CLI::print_success(&format!("Logged in as {}; the login is saved until you log out.", user.username));
+        if login.scopes.len() < ApiScope::ALL.len() {
+            CLI::print_grants(&login.scopes);
+        }
+    })?;
+    Ok(())
+}
+
+async fn logout() -> Result<(), Box<dyn std::error::Error>> {
+    scripted().await?.logout().await?;
+    output::emit(&json!({ "logged_out": true }), |_| CLI::print_success("Logged out."))?;
+    Ok(())
+}
+
+async fn pay(request: PaymentRequest) -> Result<(), Box<dyn std::error::Error>> {
+    let destination = request.destination.clone();
+    let amount = request.amount.clone();
+    let sent = scripted().await?.pay(request).await?;
+    output::emit(&sent, |sent| {
+        CLI::print_success(&format!("Sent {} XLM to {} in ledger {} ({}).", amount, destination, sent.ledger, sent.hash))
+    })?;
+    Ok(())
+}
+
+async fn stats() -> Result<(), Box<dyn std::error::Error>> {
+    let stats = scripted().await?.stats().await?;
+
+    output::emit(&stats, |stats| {
+        println!();
+        println!("{}", "📊 Database Statistics:".heading());
+        println!("👥 Total Users: {}", stats.total_users);
+        if let Some(sms) = &stats.sms_last_30_days {
+            println!("📱 SMS sent (30 days): {} (${:.2})", sms.messages, sms.cost_usd);
+        }
+        println!();
+    })?;
+    Ok(())
+}
+
+/// Serves the JSON API until Ctrl-C, on `addr`, `API_LISTEN_ADDR` or
+/// 127.0.0.1:8080, with the same database and hooks the CLI uses. Serves
+/// HTTPS when `API_TLS_CERT` and `API_TLS_KEY` are set.
+async fn serve_api(addr: Option<String>, ephemeral: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Warnings logged while handling requests, with the request's id.
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    let tokens = TokenService::from_env()?.ok_or_else(|| {
+        AppError::ValidationError("Set JWT_SIGNING_KEY; the API authenticates requests with access tokens".to_string())
+    })?;
+    let addr = addr
+        .or_else(|| config::var("API_LISTEN_ADDR"))
+        .unwrap_or_else(|| api::DEFAULT_LISTEN_ADDR.to_string());
+
+    let db = if ephemeral {
+        CLI::print_info("🧪 Ephemeral mode: everything is kept in memory and lost on exit.");
+        SqliteDatabase::ephemeral().await?
+    } else {
+        SqliteDatabase::open_default().await?
+    };
+    let oauth = OAuthService::from_env(db.clone())?;
+    let passkeys = PasskeyService::from_env(db.clone());
+    let event_outbox = EventOutboxService::from_env(db.clone())?;
+    let network = Network::from_env()?;
+    let mut state = ApiState::new(db, &network, tokens)
+        .with_hooks(Arc::new(HookService::from_env()?))
+        .with_rate_limits(RateLimitService::from_env()?)
+        .with_webhook(WebhookService::from_env()?)
+        .with_account_cache(AccountCacheService::from_env(HorizonClient::new(&network.horizon_url))?);
+    if let Some(breach_check) = BreachCheckService::from_env()? {
+        state = state.with_breach_check(breach_check);
+    }
+    if let Some(oauth) = oauth {
+        state = state.with_oauth(oauth);
+    }
+    if let Some(passkeys) = passkeys {
+        state = state.with_passkeys(passkeys);
+    }
+    if let Some(event_outbox) = event_outbox {
+        state = state.with_event_outbox(event_outbox);
+    }
+    if let Some(cors) = CorsPolicy::from_env()? {
+        state = state.with_cors(cors);
+    }
+
+    let listener = TcpListener::bind(&addr)
+        .await
+        .map_err(|e| AppError::ValidationError(format!("Can't listen on {}: {}", addr, e)))?;
+    let local_addr = listener.local_addr()?;
+    match TlsConfig::from_env()? {
+        Some(tls) => {
+            let listener = TlsListener::new(listener, tls)?;
+            CLI::print_success(&format!("🔒 Serving the API on https://{} (Ctrl-C to stop).", local_addr));
+            api::serve(listener, state).await?;
+        }
+        None => {
+            CLI::print_success(&format!("🌐 Serving the API on http://{} (Ctrl-C to stop).", local_addr));
+            api::serve(listener, state).await?;
+        }
+    }
+    Ok(())
+}
+
+/// One line of `doctor`'s report.
+#[derive(Serialize)]
+struct Finding {
+    check: &'static str,
+    status: FindingStatus,
+    message: String,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum FindingStatus {
+    Ok,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Finding {
+    fn new(check: &'static str, status: FindingStatus, message: impl Into<String>) -> Self {
+        Self { check, status, message: message.into() }
+    }
+
+    fn from_check(check: &'static str, name: &str, result: &Check) -> Self {
+        match &result.error {
+            None => Self::new(check, FindingStatus::Ok, format!("{} reachable ({} ms)", name, result.latency_ms)),
+            Some(error) => Self::new(check, FindingStatus::Error, format!("{}: {}", name, error)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Diagnosis {
+    version: VersionInfo,
+    findings: Vec<Finding>,
+}
+
+/// What `/ready` and `/version` report, plus why any check failed and what
+/// else would stop the wallet or the API from working. Applies no migrations
+/// and never creates or writes to the database.
+async fn doctor() -> Result<(), Box<dyn std::error::Error>> {
+    let network = Network::from_env()?;
+    let mut findings = Vec::new();
+
+    let path = SqliteDatabase::default_path()?;
+    match SqliteDatabase::connect_read_only(&path).await {
+        Ok(Some(db)) => {
+            let readiness = HealthService::new(db.clone(), &network).readiness().await;
+            findings.push(Finding::from_check("database", "Database", &readiness.database));
+            findings.push(Finding::from_check("horizon", "Horizon", &readiness.horizon));
+
+            let pending = db.migration_status().await?.iter().filter(|migration| !migration.is_applied()).count();
+            if pending > 0 {
+                let message = format!("{} pending migration(s); they run the next time the wallet starts", pending);
+                findings.push(Finding::new("migrations", FindingStatus::Warning, message));
+            }
+        }
+        result => {
+            findings.push(match result {
+                Err(e) => Finding::new("database", FindingStatus::Error, format!("Database: {}", e)),
+                _ => Finding::new("database", FindingStatus::Warning, format!("No database at {}; the wallet creates it when it starts", path)),
+            });
+            if HorizonClient::new(&network.horizon_url).is_reachable().await {
+                findings.push(Finding::new("horizon", FindingStatus::Ok, "Horizon reachable"));
+            } else {
+                let message = format!("Horizon: No answer from {}", network.horizon_url);
+                findings.push(Finding::new("horizon", FindingStatus::Error, message));
+            }
+        }
+    }
+
+    findings.push(match TokenService::from_env() {
+        Ok(Some(_)) => Finding::new("jwt_signing_key", FindingStatus::Ok, "JWT_SIGNING_KEY is set"),
+        Ok(None) => Finding::new("jwt_signing_key", FindingStatus::Warning, "JWT_SIGNING_KEY isn't set; --serve won't start"),
+        Err(e) => Finding::new("jwt_signing_key", FindingStatus::Error, format!("JWT_SIGNING_KEY: {}", e)),
+    });
+    findings.push(match TlsConfig::from_env().and_then(|tls| tls.map(|tls| tls.load()).transpose()) {
+        Ok(Some(_)) => Finding::new("tls", FindingStatus::Ok, "TLS certificate and key load; --serve uses HTTPS"),
+        Ok(None) => Finding::new("tls", FindingStatus::Info, "API_TLS_CERT and API_TLS_KEY aren't set; --serve uses plain HTTP"),
+        Err(e) => Finding::new("tls", FindingStatus::Error, format!("TLS: {}", e)),
+    });
+
+    let diagnosis = Diagnosis {
+        version: VersionInfo::new(&network),
+        findings,
+    };
+    output::emit(&diagnosis, |diagnosis| {
+        let version = &diagnosis.version;
+        let built_at = version.built_at.map_or("unknown time".to_string(), |at| at.format("%Y-%m-%d %H:%M UTC").to_string());
+
+        println!();
+        println!("{}", "🩺 Doctor:".heading());
+        println!("  Version:  {} ({}, built {})", version.version, version.git_hash, built_at);
+        println!("  Network:  {} via {}", version.network, version.horizon_url);
+        println!();
+        for finding in &diagnosis.findings {
+            match finding.status {
+                FindingStatus::Ok => println!("  ✅ {}", finding.message),
+                FindingStatus::Info => println!("  ℹ️  {}", finding.message),
+                FindingStatus::Warning => println!("  ⚠️  {}", finding.message.warning()),
+                FindingStatus::Error => println!("  ❌ {}", finding.message.error()),
+            }
+        }
+        println!();
+    })?;
+    Ok(())
+}
+
+/// Lists every migration and whether this database has it, without applying
+/// any or creating the database.
+async fn migrate_status() -> Result<(), Box<dyn std::error::Error>> {
+    let path = SqliteDatabase::default_path()?;
+    let db = SqliteDatabase::connect_read_only(&path).await?.ok_or_else(|| {
+        AppError::ValidationError(format!("No database at {}; the wallet creates it, fully migrated, when it starts", path))
+    })?;
+    let status = db.migration_status().await?;
+
+    output::emit(&status, |status| {
+        println!();
+        println!("{}", "🗃️  Schema Migrations:".heading());
+        for migration in status {
+            match &migration.installed_on {
+                Some(installed_on) => println!("  ✅ {} {} (applied {})", migration.version, migration.description, installed_on),
+                None => println!("  ⏳ {} {} {}", migration.version, migration.description, "(pending)".warning()),
+            }
+        }
+
+        let pending = status.iter().filter(|migration| !migration.is_applied()).count();
+        println!();
+        if pending == 0 {
+            CLI::print_success("Schema is up to date.");
+        } else {
+            CLI::print_info(&format!("{} pending migration(s) will run the next time the wallet starts.", pending));
+        }
+    })?;
+    Ok(())
+}
+
+/// Rewraps stored KYC data keys under the first key in `CUSTOMER_DATA_KEYS`.
+async fn rotate_customer_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let db = SqliteDatabase::open_default().await?;
+    let rewrapped = CustomerFieldService::new(db.clone()).rotate_keys().await?;
+    let secrets = TwoFactorService::new(db.clone()).rotate_keys().await?;
+    let webhooks = WebhookEndpointService::new(db).rotate_keys().await?;
+
+    let result = json!({ "fields": rewrapped, "two_factor_secrets": secrets, "webhook_secrets": webhooks });
+    output::emit(&result, |_| {
+        CLI::print_success(&format!(
+            "Rewrapped {} field(s), {} 2FA secret(s) and {} webhook secret(s); keys after the first can now be removed.",
+            rewrapped, secrets, webhooks
+        ))
+    })?;
+    Ok(())
+}
+
+/// Sets a user's role from the command line, e.g. to make the first admin.
+/// Anyone who can run this can already open the database.
+async fn set_user_role(identifier: &str, role: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let role = Role::parse(role).ok_or_else(|| AppError::ValidationError(format!("Unknown role '{}'; use user, support or admin", role)))?;
+    let db = SqliteDatabase::open_default().await?;
+    let user_service = UserService::new(db.clone());
+    let user = user_service
+        .find_user(identifier)
+        .await?
+        .ok_or_else(|| AppError::ValidationError(format!("No user '{}'", identifier)))?;
+
+    let updated = user_service.set_role(&user.id, role).await?;
+    let details = format!("{} -> {} from the command line", user.role.as_str(), role.as_str());
+    AuditService::new(db).record(Some(&user.id), AuditEvent::RoleChanged, &details).await?;
+    output::emit(&updated, |updated| CLI::print_success(&format!("{} is now {}.", updated.username, updated.role.as_str())))?;
+    Ok(())
+}
+
+/// Replaces the plaintext database with an SQLCipher-encrypted copy keyed from
+/// `DATABASE_KEY` or the OS keyring. The original is kept as a backup.
+async fn encrypt_database() -> Result<(), Box<dyn std::error::Error>> {
+    let source = KeySource::from_env()?;
+    let key = source.key_or_create()?.ok_or_else(|| {
+        AppError::ValidationError("Set DATABASE_KEY, or DATABASE_KEY_SOURCE=keyring, to choose the key".to_string())
+    })?;
+
+    let path = SqliteDatabase::default_path()?;
+    if !Path::new(&path).exists() {
+        return Err(AppError::ValidationError(format!("No database at {}", path)).into());
+    }
+    let encrypted_path = format!("{}.encrypting", path);
+    let backup_path = format!("{}.plaintext-backup", path);
+    let _ = fs::remove_file(&encrypted_path);
+
+    let plaintext = SqliteDatabase::connect_with_key(&path, None).await?;
+    let expected = plaintext.table_row_counts().await?;
+    plaintext.export_encrypted(Path::new(&encrypted_path), &key).await?;
+    plaintext.close().await;
+
+    let encrypted = SqliteDatabase::connect_with_key(&encrypted_path, Some(&key)).await?;
+    let copied = encrypted.table_row_counts().await?;
+    encrypted.close().await;
+
+    if copied != expected {
+        let _ = fs::remove_file(&encrypted_path);
+        return Err(AppError::DatabaseError("The encrypted copy doesn't match the original; nothing was changed".to_string()).into());
+    }
+
+    fs::rename(&path, &backup_path)?;
+    fs::rename(&encrypted_path, &path)?;
+
+    let result = json!({ "path": path, "tables": copied.len(), "backup_path": backup_path });
+    output::emit(&result, |_| {
+        CLI::print_success(&format!("Encrypted {} ({} tables).", path, copied.len()));
+        if source == KeySource::Keyring {
+            CLI::print_info("The key is stored in the OS keyring; keep DATABASE_KEY_SOURCE=keyring set.");
+        } else {
+            CLI::print_info("Keep DATABASE_KEY set; the wallet can't open the database without it.");
+        }
+        CLI::print_info(&format!("Check the wallet starts, then securely delete the plaintext copy at {}.", backup_path));
+    })?;
+    Ok(())
+}
+
+/// Runs an integrity check, VACUUM and ANALYZE, reporting the size change.
+async fn maintain_database() -> Result<(), Box<dyn std::error::Error>> {
+    let db = SqliteDatabase::open_default().await?;
+    let report = MaintenanceService::new(db).run().await?;
+
+    output::emit(&report, |report| {
+        println!();
+        println!("{}", "🧹 Database Maintenance:".heading());
+        if report.is_healthy() {
+            println!("  ✅ Integrity check passed");
+        } else {
+            println!("  {}", "❌ Integrity check failed:".error());
+            for problem in &report.integrity_problems {
+                println!("     {}", problem);
+            }
+            println!("  {}", "⏭️  Skipped VACUUM and ANALYZE; restore from a backup or investigate first.".warning());
+        }
+        println!("  📦 Size: {} KiB → {} KiB", report.size_before_bytes / 1024, report.size_after_bytes / 1024);
+        println!();
+
+        if report.vacuumed {
+            CLI::print_success(&format!("Reclaimed {} KiB and refreshed query statistics.", report.reclaimed_bytes().max(0) / 1024));
+        }
+    })?;
+    Ok(())
+}
+
+/// Moves transactions older than `months` whole months into an archive file,
+/// keeping monthly totals in the database.
+async fn archive_transactions(months: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let db = SqliteDatabase::open_default().await?;
+    let report = ArchiveService::new(db).archive(months, chrono::Utc::now()).await?;
+
+    output::emit(&report, |report| {
+        println!();
+        println!("{}", "📦 Transaction Archive:".heading());
+        println!("  📅 Before: {}", report.cutoff.format("%Y-%m-%d"));
+        match &report.path {
+            Some(path) => {
+                println!("  🗜️  File: {}", path);
+                println!("  📜 Archived: {} transaction(s) into {} monthly summary row(s)", report.archived, report.rollups);
+                println!();
+                CLI::print_success("Run `db maintain` to reclaim the space.");
+            }
+            None => {
+                println!();
+                CLI::print_info("Nothing is old enough to archive.");
+            }
+        }
+    })?;
+    Ok(())
+}
+
+async fn export_transactions() -> Result<(), Box<dyn std::error::Error>> {
+    let db = SqliteDatabase::open_default().await?;
+    let report = TransactionExportService::new(db).export(chrono::Utc::now()).await?;
+
+    output::emit(&report, |report| {
+        println!();
+        println!("{}", "📤 Transaction Export:".heading());
+        println!("  📂 Directory: {} (schema v{})", report.dir, report.schema_version);
+        if report.exported == 0 {
+            println!();
+            CLI::print_info("Nothing changed since the last export.");
+            return;
+        }
+        println!("  📜 Exported: {} transaction(s) into {} file(s)", report.exported, report.files.len());
+        println!("  🔖 Checkpoint: change {}", report.checkpoint.last_change_seq);
+        println!();
+    })?;
+    Ok(())
+}
+
+/// Copies every user account into the MySQL or MariaDB database at
+/// `MYSQL_URL`. Accounts already there are skipped, so it can be run again
+/// after more signups.
+async fn copy_users_to_mysql() -> Result<(), Box<dyn std::error::Error>> {
+    let url = std::env::var("MYSQL_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .ok_or_else(|| AppError::ValidationError("`db copy-users` needs MYSQL_URL".to_string()))?;
+    let mysql = mysql_users(&url).await?;
+    let users = SqliteDatabase::open_default().await?.get_users().await?;
+
+    let copied = mysql.create_users(&users).await?.iter().filter(|result| result.is_ok()).count();
+    let report = json!({ "copied": copied, "skipped": users.len() - copied, "total": mysql.get_user_count().await? });
+    output::emit(&report, |_| {
+        CLI::print_success(&format!(
+            "Copied {} of {} user(s) to MySQL; {} were already there.",
+            copied,
+            users.len(),
+            users.len() - copied
+        ))
+    })?;
+    Ok(())
+}
+
+#[cfg(feature = "mysql")]
+async fn mysql_users(url: &str) -> Result<Arc<dyn UserRepository>, AppError> {
+    Ok(Arc::new(database::mysql::MySqlDatabase::new(url).await?))
+}
+
+#[cfg(not(feature = "mysql"))]
+async fn mysql_users(_url: &str) -> Result<Arc<dyn UserRepository>, AppError> {
+    Err(AppError::ValidationError("`db copy-users` needs a build with the mysql feature".to_string()))
+}
+
+/// Sends a signed sample event to `WEBHOOK_URL`.
+async fn send_test_webhook() -> Result<(), Box<dyn std::error::Error>> {
+    let id = WebhookService::from_env()?.send_test().await?;
+
+    output::emit(&json!({ "event_id": id }), |_| CLI::print_success(&format!("Delivered test event {}.", id)))?;
+    Ok(())
+}
+
+/// Renders an email template with sample values. `html` also writes the
+/// HTML variant to a temporary file and prints where.
+fn preview_email(name: &str, locale: Option<String>, html: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let template = EmailTemplate::parse(name).ok_or_else(|| {
+        let names: Vec<_> = EmailTemplate::ALL.iter().map(EmailTemplate::as_str).collect();
+        AppError::ValidationError(format!("Unknown email template '{}'; pick one of {}", name, names.join(", ")))
+    })?;
+    let mut templates = EmailTemplateService::from_config();
+    if let Some(locale) = locale {
+        templates = templates.with_locale(locale);
+    }
+    let email = templates.render(template, email_template_service::sample_values(template))?;
+
+    let html_path = if html {
+        let path = std::env::temp_dir().join(format!("{}-preview.html", template.as_str()));
+        std::fs::write(&path, &email.html).map_err(|e| AppError::InternalError(format!("Failed to write {}: {}", path.display(), e)))?;
+        Some(path)
+    } else {
+        None
+    };
+
+    output::emit(&email, |email| {
+        println!();
+        println!("{}", format!("📧 {}", template.as_str()).heading());
+        println!("  🌐 Template: {}", email.locale.as_deref().unwrap_or("built-in"));
+        println!("  ✉️  Subject: {}", email.subject);
+        println!();
+        println!("{}", email.text);
+        println!();
+        if let Some(path) = &html_path {
+            CLI::print_info(&format!("Open file://{} in a browser to see the HTML version.", path.display()));
+        }
+    })?;
+    Ok(())
+}
+
+/// Compares an account's local history with Horizon and offers to fix it.
+/// `repair` fixes it without asking; scripts are never asked.
+async fn reconcile(address: &str, repair: bool) -> Result<(), Box<dyn std::error::Error>> {
+    Validator::validate_stellar_address(address)?;
+    let network = Network::from_env()?;
+    let service = ReconciliationService::new(SqliteDatabase::open_default().await?, &network);
+
+    CLI::print_info(&format!("Reading the full history of {} from {}...", address, network.name));
+    let report = service.reconcile(address).await?;
+
+    output::emit(&report, |report| {
+        println!();
+        println!("{}", "🧮 Reconciliation (payments only, fees excluded):".heading());
+        for asset in &report.assets {
+            let line = format!(
+                "  {:<12} network {:>20}  local {:>20}",
+                asset.asset_code,
+                format_stroops(asset.network_stroops),
+                format_stroops(asset.local_stroops)
+            );
+            match asset.difference() {
+                0 => println!("{}  ✅", line),
+                difference => println!("{}  {}", line, format!("off by {}", format_stroops(difference)).error()),
+            }
+        }
+        for (label, records) in [
+            ("➕ Missing locally", &report.missing),
+            ("♊ Duplicated locally", &report.duplicates),
+            ("❓ Unknown to Horizon", &report.unknown),
+        ] {
+            if records.is_empty() {
+                continue;
+            }
+            println!();
+            println!("{} ({}):", label, records.len());
+            for tx in records {
+                println!(
+                    "    {} #{} {} {} {} {}",
+                    tx.created_at.format("%Y-%m-%d"),
+                    tx.operation_index,
+                    tx.direction.as_str(),
+                    format_stroops(tx.amount_stroops),
+                    tx.asset_code,
+                    tx.hash.get(..16).unwrap_or(&tx.hash).muted()
+                );
+            }
+        }
+        println!();
+
+        if report.is_clean() {
+            CLI::print_success("Local history matches the network.");
+        }
+    })?;
+
+    let ask = || CLI::confirm_action("Add the missing records and remove the duplicates?");
+    if report.is_repairable() && (repair || (!OutputFormat::current().is_machine_readable() && ask()?)) {
+        let changed = service.repair(&report).await?;
+        CLI::print_success(&format!("Repaired {} record(s).", changed));
+    }
+    if !report.unknown.is_empty() {
+        CLI::print_info("Records unknown to Horizon were left alone; check whether they belong to another network.");
+    }
+    Ok(())
+}
+
 fn display_main_menu() {
-    clear_screen();
-    println!("{}", "=".repeat(60).bright_blue());
-    println!("{}", "           🌟 STELLAR WALLET BACKEND 🌟           ".bright_yellow().bold());
-    println!("{}", "=".repeat(60).bright_blue());
+    let branding = Branding::current();
+
+    CLI::clear_screen();
+    println!("{}", branding.rule(60));
+    match &branding.banner {
+        Some(banner) => println!("{}", branding.title(banner)),
+        None => println!("{}", branding.title(&format!("           🌟 {} 🌟           ", branding.product_name.to_uppercase()))),
+    }
+    println!("{}", branding.rule(60));
     println!();
-    println!("{}", "Main Menu:".cyan().bold());
+    println!("{}", "Main Menu:".heading());
     println!("  1. 📝 Create New Account");
     println!("  2. 🔐 Login to Account");
     println!("  3. 📊 Show Database Stats");
-    println!("  4. 🚪 Exit");
+    println!("  4. 🛟 Recover Account");
+    println!("  5. 🚪 Exit");
     println!();
-}
-
-fn wait_for_enter() {
-    let _ = CLI::get_input("Press Enter to continue...");
-}
-
-fn clear_screen() {
-    print!("\x1B[2J\x1B[1;1H");
+    if let Some(support) = branding.support_line() {
+        println!("{}", support.muted());
+        println!();
+    }
 }
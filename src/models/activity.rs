@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// How an account has been used, built from its locally recorded history.
+/// Only confirmed payments in one asset are counted so amounts add up.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActivitySummary {
+    pub account: String,
+    pub asset_code: String,
+    pub since: DateTime<Utc>,
+    pub payments: i64,
+    pub average_payment_stroops: i64,
+    /// Payment counts by UTC weekday (0 = Sunday) and hour.
+    pub heatmap: [[i64; 24]; 7],
+    pub top_counterparties: Vec<CounterpartyActivity>,
+    /// Oldest month first.
+    pub monthly: Vec<MonthlyVolume>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CounterpartyActivity {
+    pub counterparty: String,
+    pub payments: i64,
+    pub volume_stroops: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MonthlyVolume {
+    /// `YYYY-MM`.
+    pub month: String,
+    pub payments: i64,
+    pub incoming_stroops: i64,
+    pub outgoing_stroops: i64,
+}
+
+impl ActivitySummary {
+    /// The busiest weekday/hour cell, used to scale the heatmap.
+    pub fn peak(&self) -> i64 {
+        self.heatmap.iter().flatten().copied().max().unwrap_or(0)
+    }
+}
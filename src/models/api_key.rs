@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Starts every API key, so leaked ones are easy to search for.
+pub const API_KEY_PREFIX: &str = "swk_";
+
+/// What an access token or API key may be used for. Logins get every scope
+/// unless they ask for fewer; the user's role still decides what they can
+/// do within one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+    /// Balances, history and other account details.
+    Read,
+    /// Sending payments.
+    Payments,
+    /// Adding and removing contacts.
+    Contacts,
+    /// Registering and removing webhooks.
+    Webhooks,
+    /// User and SMS statistics, for roles that may view reports.
+    Reports,
+    /// Creating and managing other users' accounts, for admins.
+    Users,
+    /// Sending test events to the operator's webhook, for admins.
+    AdminWebhooks,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 7] = [
+        ApiScope::Read,
+        ApiScope::Payments,
+        ApiScope::Contacts,
+        ApiScope::Webhooks,
+        ApiScope::Reports,
+        ApiScope::Users,
+        ApiScope::AdminWebhooks,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "read:account",
+            ApiScope::Payments => "write:payments",
+            ApiScope::Contacts => "write:contacts",
+            ApiScope::Webhooks => "write:webhooks",
+            ApiScope::Reports => "admin:reports",
+            ApiScope::Users => "admin:users",
+            ApiScope::AdminWebhooks => "admin:webhooks",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == value)
+    }
+
+    /// What the scope allows, in words for the user granting it.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "see your profile, wallets, balances and payment history",
+            ApiScope::Payments => "send payments from your wallets",
+            ApiScope::Contacts => "add and remove your contacts",
+            ApiScope::Webhooks => "register webhooks that are told about your account's events",
+            ApiScope::Reports => "view user and SMS statistics, if your role may",
+            ApiScope::Users => "create and manage other users' accounts, if you are an admin",
+            ApiScope::AdminWebhooks => "send test events to the operator's webhook, if you are an admin",
+        }
+    }
+
+    /// Parses scope names in [`ApiScope::ALL`]'s order, without repeats.
+    /// Fails with the first name that isn't a scope.
+    pub fn parse_list<'a>(values: impl IntoIterator<Item = &'a str>) -> Result<Vec<Self>, &'a str> {
+        let scopes = values.into_iter().map(|value| Self::parse(value).ok_or(value)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::ALL.into_iter().filter(|scope| scopes.contains(scope)).collect())
+    }
+}
+
+/// A key a user generated for programmatic access. Only its hash is stored.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// The start of the key, shown so users can tell their keys apart.
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<ApiScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
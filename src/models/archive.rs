@@ -0,0 +1,38 @@
+use crate::models::transaction::TransactionDirection;
+use crate::models::user_export::ExportedTransaction;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The confirmed payments of one account in one month, for one asset and
+/// direction, after the records themselves were archived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRollup {
+    pub account: String,
+    /// `YYYY-MM`, in UTC.
+    pub month: String,
+    pub asset_code: String,
+    pub direction: TransactionDirection,
+    pub payment_count: i64,
+    pub total_stroops: i64,
+}
+
+/// One line of an archive file: a transaction as it was in the database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedTransaction {
+    pub account: String,
+    #[serde(flatten)]
+    pub transaction: ExportedTransaction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Outcome of a `db archive` run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchiveReport {
+    /// Transactions created before this were archived.
+    pub cutoff: DateTime<Utc>,
+    pub archived: usize,
+    pub rollups: usize,
+    /// The archive file, or `None` when there was nothing old enough to archive.
+    pub path: Option<String>,
+}
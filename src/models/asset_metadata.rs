@@ -0,0 +1,20 @@
+/// Display details for an issued asset, taken from its issuer's stellar.toml.
+/// Only produced when the file lists the exact code and issuer, so a present
+/// value means the asset is verified against the issuer's home domain.
+#[derive(Debug, Clone)]
+pub struct AssetMetadata {
+    pub code: String,
+    pub home_domain: String,
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub org_name: Option<String>,
+    pub transfer_server: Option<String>,
+    pub transfer_server_sep0024: Option<String>,
+    pub web_auth_endpoint: Option<String>,
+}
+
+impl AssetMetadata {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.code)
+    }
+}
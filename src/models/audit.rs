@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Security-relevant events written to the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEvent {
+    AccountCreated,
+    LoginSucceeded,
+    LoginFailed,
+    /// Too many failed logins in a row locked an account or source out.
+    LoginLockedOut,
+    Logout,
+    SessionRevoked,
+    /// A used refresh token was presented again, so its session was revoked.
+    RefreshTokenReused,
+    /// Secret key material was shown to the user, e.g. a recovery phrase.
+    KeyExported,
+    PaymentSent,
+    PaymentFailed,
+    PolicyViolation,
+    DataExported,
+    DataImported,
+    ProfileUpdated,
+    AccountDeleted,
+    /// A user tried an administrative action their role doesn't allow.
+    AccessDenied,
+    RoleChanged,
+    AccountDisabled,
+    AccountEnabled,
+    ApiKeyCreated,
+    ApiKeyRevoked,
+    SigningKeyRotated,
+    KeyImported,
+    /// A signing PIN was set, changed or removed.
+    SigningPinChanged,
+    /// Too many wrong signing PINs in a row locked signing.
+    SigningPinLockedOut,
+    /// A recovery code was used to set a new password without the old one.
+    AccountRecovered,
+    PasswordChanged,
+    /// The login email was changed after both the old and new address confirmed it.
+    EmailChanged,
+    /// Codes from an email change that was already confirmed were tried again.
+    EmailCodeReused,
+    /// A webhook endpoint was registered or removed.
+    WebhookAdded,
+    WebhookRemoved,
+    /// An OAuth provider's account was linked to a user through its email.
+    OAuthLinked,
+    /// A passkey was registered or removed.
+    PasskeyAdded,
+    PasskeyRemoved,
+}
+
+impl AuditEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEvent::AccountCreated => "account_created",
+            AuditEvent::LoginSucceeded => "login_succeeded",
+            AuditEvent::LoginFailed => "login_failed",
+            AuditEvent::LoginLockedOut => "login_locked_out",
+            AuditEvent::Logout => "logout",
+            AuditEvent::SessionRevoked => "session_revoked",
+            AuditEvent::RefreshTokenReused => "refresh_token_reused",
+            AuditEvent::KeyExported => "key_exported",
+            AuditEvent::PaymentSent => "payment_sent",
+            AuditEvent::PaymentFailed => "payment_failed",
+            AuditEvent::PolicyViolation => "policy_violation",
+            AuditEvent::DataExported => "data_exported",
+            AuditEvent::DataImported => "data_imported",
+            AuditEvent::ProfileUpdated => "profile_updated",
+            AuditEvent::AccountDeleted => "account_deleted",
+            AuditEvent::AccessDenied => "access_denied",
+            AuditEvent::RoleChanged => "role_changed",
+            AuditEvent::AccountDisabled => "account_disabled",
+            AuditEvent::AccountEnabled => "account_enabled",
+            AuditEvent::ApiKeyCreated => "api_key_created",
+            AuditEvent::ApiKeyRevoked => "api_key_revoked",
+            AuditEvent::SigningKeyRotated => "signing_key_rotated",
+            AuditEvent::KeyImported => "key_imported",
+            AuditEvent::SigningPinChanged => "signing_pin_changed",
+            AuditEvent::SigningPinLockedOut => "signing_pin_locked_out",
+            AuditEvent::AccountRecovered => "account_recovered",
+            AuditEvent::PasswordChanged => "password_changed",
+            AuditEvent::EmailChanged => "email_changed",
+            AuditEvent::EmailCodeReused => "email_code_reused",
+            AuditEvent::WebhookAdded => "webhook_added",
+            AuditEvent::WebhookRemoved => "webhook_removed",
+            AuditEvent::OAuthLinked => "oauth_linked",
+            AuditEvent::PasskeyAdded => "passkey_added",
+            AuditEvent::PasskeyRemoved => "passkey_removed",
+        }
+    }
+}
+
+/// An append-only record of a security-relevant event.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub event_type: String,
+    pub details: String,
+    /// The API request that caused it, if one did.
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Narrows the admin audit view. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub user_id: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: u32,
+}
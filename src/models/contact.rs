@@ -0,0 +1,26 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A saved payment recipient in a user's address book.
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Contact {
+    pub id: Uuid,
+    #[graphql(skip)]
+    pub user_id: Uuid,
+    pub name: String,
+    pub address: String,
+    pub memo: Option<String>,
+    pub federation_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateContactRequest {
+    pub name: String,
+    pub address: String,
+    pub memo: Option<String>,
+    pub federation_name: Option<String>,
+}
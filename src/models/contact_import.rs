@@ -0,0 +1,101 @@
+use crate::models::contact::CreateContactRequest;
+
+/// Address books the contacts importer reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    VCard,
+}
+
+impl ImportFormat {
+    /// Picks the format from the file's contents, falling back to its
+    /// extension: exports are often saved as `.txt`.
+    pub fn detect(path: &str, text: &str) -> Self {
+        if text.trim_start().to_uppercase().starts_with("BEGIN:VCARD") {
+            return ImportFormat::VCard;
+        }
+
+        let lower = path.to_lowercase();
+        if lower.ends_with(".vcf") || lower.ends_with(".vcard") {
+            ImportFormat::VCard
+        } else {
+            ImportFormat::Csv
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "CSV",
+            ImportFormat::VCard => "vCard",
+        }
+    }
+}
+
+/// A parsed address book before its columns are mapped to contact fields.
+/// For vCards each property name (`FN`, `NOTE`, `X-STELLAR`, ...) is a column
+/// and each card a row.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ImportTable {
+    /// The value in `column` of `row`, empty if the row is short.
+    pub fn cell<'a>(&self, row: &'a [String], column: usize) -> &'a str {
+        row.get(column).map(String::as_str).unwrap_or("")
+    }
+}
+
+/// Which column holds each contact field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldMapping {
+    pub name: usize,
+    pub address: usize,
+    pub memo: Option<usize>,
+    pub federation_name: Option<usize>,
+}
+
+/// What an import would do. Nothing has been written yet when this is built,
+/// so it doubles as the dry-run preview.
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    pub to_add: Vec<CreateContactRequest>,
+    pub duplicates: Vec<DuplicateContact>,
+    /// Rows that can't become a contact, with their 1-based row number.
+    pub invalid: Vec<(usize, String)>,
+}
+
+/// A row whose address is already in the address book or earlier in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateContact {
+    pub name: String,
+    pub address: String,
+    /// The name the address is already saved under.
+    pub existing_name: String,
+}
+
+/// The contact fields a column can be mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactField {
+    Name,
+    Address,
+    Memo,
+    FederationName,
+}
+
+impl ContactField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContactField::Name => "Name",
+            ContactField::Address => "Stellar address",
+            ContactField::Memo => "Default memo",
+            ContactField::FederationName => "Federation name",
+        }
+    }
+
+    /// Whether every imported contact needs a value for this field.
+    pub fn is_required(&self) -> bool {
+        matches!(self, ContactField::Name | ContactField::Address)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::utils::crypto::Envelope;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// SEP-9 natural person fields the wallet can hold for anchor KYC requests.
+pub const SEP9_FIELDS: &[&str] = &[
+    "first_name",
+    "last_name",
+    "additional_name",
+    "address_country_code",
+    "state_or_province",
+    "city",
+    "postal_code",
+    "address",
+    "mobile_number",
+    "email_address",
+    "birth_date",
+    "birth_place",
+    "birth_country_code",
+    "bank_account_number",
+    "bank_account_type",
+    "bank_number",
+    "bank_phone_number",
+    "bank_branch_number",
+    "tax_id",
+    "tax_id_name",
+    "occupation",
+    "employer_name",
+    "employer_address",
+    "language_code",
+    "id_type",
+    "id_country_code",
+    "id_issue_date",
+    "id_expiration_date",
+    "id_number",
+    "ip_address",
+    "sex",
+    "referral_id",
+];
+
+/// One encrypted KYC attribute. Only the field name is stored in the clear.
+#[derive(Debug, Clone)]
+pub struct CustomerField {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub field_name: String,
+    pub envelope: Envelope,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The fields an anchor asked for, split into what we can send and what's missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomerFieldDisclosure {
+    pub provided: Vec<(String, String)>,
+    pub missing: Vec<String>,
+}
@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Recovery codes issued alongside a new recovery phrase.
+pub const PHRASE_RECOVERY_CODE_COUNT: usize = 5;
+
+/// A user's BIP-39 recovery phrase, encrypted with their password.
+#[derive(Debug, Clone)]
+pub struct RecoveryPhrase {
+    pub user_id: Uuid,
+    pub encrypted_phrase: String,
+    pub salt: String,
+    pub nonce: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The recovery phrase encrypted with one of its recovery codes, which opens
+/// it once in place of the password.
+#[derive(Debug, Clone)]
+pub struct PhraseRecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub encrypted_phrase: String,
+    pub salt: String,
+    pub nonce: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An account derived from the user's recovery phrase at a SEP-5 index.
+#[derive(Debug, Clone)]
+pub struct DerivedAccount {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub account_index: u32,
+    pub public_key: String,
+    pub created_at: DateTime<Utc>,
+}
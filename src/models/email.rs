@@ -0,0 +1,42 @@
+use serde::Serialize;
+
+/// The emails the wallet sends. Each can be overridden per locale in
+/// `EMAIL_TEMPLATE_DIR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    /// Sent to the current address when a login email change starts.
+    EmailChangeOld,
+    /// Sent to the new address when a login email change starts.
+    EmailChangeNew,
+    /// Sent to the login address when codes from a confirmed change are
+    /// tried again.
+    EmailCodeReused,
+}
+
+impl EmailTemplate {
+    pub const ALL: [EmailTemplate; 3] = [EmailTemplate::EmailChangeOld, EmailTemplate::EmailChangeNew, EmailTemplate::EmailCodeReused];
+
+    /// The file name stem, e.g. `email_change_old` for `email_change_old.txt`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailTemplate::EmailChangeOld => "email_change_old",
+            EmailTemplate::EmailChangeNew => "email_change_new",
+            EmailTemplate::EmailCodeReused => "email_code_reused",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|template| template.as_str() == name)
+    }
+}
+
+/// A rendered email, ready for the provider.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Email {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+    /// The template directory the email came from, e.g. `pt-BR`, or `None`
+    /// for the built-in English one.
+    pub locale: Option<String>,
+}
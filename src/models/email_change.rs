@@ -0,0 +1,25 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// How long the codes for an email change can be used for.
+pub const EMAIL_CHANGE_TTL: Duration = Duration::minutes(30);
+
+/// How long a confirmed change's codes are remembered, so trying them again
+/// can be reported.
+pub const EMAIL_CHANGE_REUSE_WINDOW: Duration = Duration::days(7);
+
+/// An email change waiting for the codes sent to the old and new addresses.
+#[derive(Debug, Clone)]
+pub struct EmailChange {
+    pub user_id: Uuid,
+    pub new_email: String,
+    pub old_code_hash: String,
+    pub new_code_hash: String,
+    /// Wrong code pairs entered so far.
+    pub failures: i64,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    /// When both codes were accepted. The change is kept after that only to
+    /// spot the codes being used again.
+    pub used_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// How long a key's response is replayed for.
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::hours(24);
+
+/// A request made with an `Idempotency-Key` header, and its response once
+/// there is one.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    /// The operation and, for signed-in requests, who made it, e.g.
+    /// `payments:<user id>`. Keys only have to be unique within a scope.
+    pub scope: String,
+    pub key: String,
+    /// Hash of the request's non-secret fields, so a key reused for a
+    /// different request is turned away instead of answered with the wrong
+    /// response.
+    pub fingerprint: String,
+    /// The account the response is about, once known.
+    pub user_id: Option<Uuid>,
+    pub response_status: Option<u16>,
+    pub response_body: Option<String>,
+    /// A transaction the first request sent without learning whether it
+    /// went through.
+    pub pending_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl IdempotencyKey {
+    /// The stored response, or `None` while the first request is running.
+    pub fn response(&self) -> Option<(u16, &str)> {
+        Some((self.response_status?, self.response_body.as_deref()?))
+    }
+}
@@ -0,0 +1,18 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A Stellar secret key held on behalf of a user, encrypted with their password.
+#[derive(Debug, Clone)]
+pub struct KeystoreEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub public_key: String,
+    pub encrypted_secret: String,
+    pub salt: String,
+    pub nonce: String,
+    pub label: Option<String>,
+    /// The account this key signs for when that isn't its own address, i.e.
+    /// it replaced the account's key in a rotation.
+    pub signs_for: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
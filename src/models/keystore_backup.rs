@@ -0,0 +1,26 @@
+use crate::utils::crypto::SealedSecret;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the file layout changes incompatibly.
+pub const KEYSTORE_BACKUP_VERSION: u32 = 1;
+
+/// One Stellar secret key, encrypted with a passphrase of the user's choosing,
+/// as written to a backup file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreBackup {
+    pub version: u32,
+    pub public_key: String,
+    /// The account the key signs for when it replaced that account's own key.
+    pub signs_for: Option<String>,
+    pub label: Option<String>,
+    pub exported_at: DateTime<Utc>,
+    pub crypto: SealedSecret,
+}
+
+impl KeystoreBackup {
+    /// Bound to the ciphertext, so the key can't be relabelled as another one.
+    pub fn context(version: u32, public_key: &str, signs_for: Option<&str>) -> Vec<u8> {
+        format!("stellar-wallet keystore backup v{}|{}|{}", version, public_key, signs_for.unwrap_or_default()).into_bytes()
+    }
+}
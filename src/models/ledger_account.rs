@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// An address whose key lives on the user's Ledger at SEP-5 index `account_index`.
+/// Addresses without one of these are signed with a key from the keystore.
+#[derive(Debug, Clone)]
+pub struct LedgerAccount {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub public_key: String,
+    pub account_index: u32,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// One attempt to log in to an account, successful or not.
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub succeeded: bool,
+    /// Why it failed, e.g. "wrong password" or "2FA code not confirmed".
+    pub failure_reason: Option<String>,
+    /// Where it came from; the device label for CLI logins.
+    pub source: String,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Failed logins before an account is locked, unless `LOGIN_MAX_FAILURES` says otherwise.
+pub const DEFAULT_MAX_FAILURES: i64 = 5;
+
+/// What failed logins are counted against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginScope {
+    Account(Uuid),
+    /// Where the attempts come from, whichever account they target.
+    Source(String),
+}
+
+impl LoginScope {
+    pub fn key(&self) -> String {
+        match self {
+            LoginScope::Account(user_id) => format!("account:{}", user_id),
+            LoginScope::Source(source) => format!("source:{}", source),
+        }
+    }
+}
+
+/// Failed logins in a row for one scope.
+#[derive(Debug, Clone)]
+pub struct LoginThrottle {
+    pub key: String,
+    pub failures: i64,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub last_failure_at: DateTime<Utc>,
+}
@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+/// Outcome of a `db maintain` run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceReport {
+    /// Problems found by `PRAGMA integrity_check`; empty when the database is sound.
+    pub integrity_problems: Vec<String>,
+    pub size_before_bytes: i64,
+    pub size_after_bytes: i64,
+    /// False when integrity problems made it unsafe to rewrite the file.
+    pub vacuumed: bool,
+}
+
+impl MaintenanceReport {
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_problems.is_empty()
+    }
+
+    pub fn reclaimed_bytes(&self) -> i64 {
+        self.size_before_bytes - self.size_after_bytes
+    }
+}
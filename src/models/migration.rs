@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+/// One migration shipped with the binary and whether this database has it.
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: Option<String>,
+}
+
+impl MigrationStatus {
+    pub fn is_applied(&self) -> bool {
+        self.installed_on.is_some()
+    }
+}
@@ -1 +1,45 @@
+pub mod activity;
+pub mod api_key;
+pub mod archive;
+pub mod asset_metadata;
+pub mod audit;
+pub mod contact;
+pub mod contact_import;
+pub mod customer_field;
+pub mod derived_account;
+pub mod email;
+pub mod email_change;
+pub mod idempotency;
+pub mod keystore;
+pub mod keystore_backup;
+pub mod ledger_account;
+pub mod login_attempt;
+pub mod login_throttle;
+pub mod maintenance;
+pub mod migration;
+pub mod oauth;
+pub mod offline;
+pub mod outbox;
+pub mod passkey;
+pub mod password_change;
+pub mod payment_filter;
+pub mod payment_note;
+pub mod policy;
+pub mod reconciliation;
+pub mod report;
+pub mod role;
+pub mod security_event;
+pub mod session;
+pub mod signing_pin;
+pub mod sms;
+pub mod stream_ticket;
+pub mod transaction;
+pub mod transaction_export;
+pub mod two_factor;
+pub mod undo;
 pub mod user;
+pub mod user_export;
+pub mod user_settings;
+pub mod wallet_event;
+pub mod wallet_health;
+pub mod webhook;
\ No newline at end of file
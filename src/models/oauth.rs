@@ -0,0 +1,57 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// How long a user has to come back from the provider's consent page.
+pub const OAUTH_STATE_TTL: Duration = Duration::minutes(10);
+
+/// An identity provider users can log in through instead of a password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthProvider {
+    Google,
+    GitHub,
+}
+
+impl OAuthProvider {
+    pub const ALL: [OAuthProvider; 2] = [OAuthProvider::Google, OAuthProvider::GitHub];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "google",
+            OAuthProvider::GitHub => "github",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|provider| provider.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+
+    /// The name to show people, e.g. "GitHub".
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "Google",
+            OAuthProvider::GitHub => "GitHub",
+        }
+    }
+}
+
+/// Who the provider says just logged in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthIdentity {
+    pub provider: OAuthProvider,
+    /// The provider's stable id for the account; emails can change.
+    pub subject: String,
+    pub email: String,
+    /// Whether the provider checked that the account owns `email`.
+    pub email_verified: bool,
+}
+
+/// A provider account linked to a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedIdentity {
+    pub provider: OAuthProvider,
+    pub subject: String,
+    pub user_id: Uuid,
+    /// The address it was linked through.
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
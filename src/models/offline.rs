@@ -0,0 +1,26 @@
+use crate::stellar::horizon::BalanceRecord;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// An account's balances as Horizon last reported them.
+#[derive(Debug, Clone)]
+pub struct BalanceSnapshot {
+    pub network: String,
+    pub account: String,
+    pub balances: Vec<BalanceRecord>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// A payment written while offline: pending, not submitted. It is only
+/// built, signed and sent after the user reviews it back online.
+#[derive(Debug, Clone)]
+pub struct QueuedPayment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub network: String,
+    pub source: String,
+    pub destination: String,
+    pub amount: String,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
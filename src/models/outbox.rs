@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+
+/// A domain event queued for the message broker. `payload` is the same JSON
+/// envelope webhooks get: `{"id", "type", "created_at", "data"}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEvent {
+    pub event_id: String,
+    /// e.g. `payment.received`.
+    pub event_type: String,
+    /// Where it is published, e.g. `wallet.payment.received`.
+    pub subject: String,
+    pub payload: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    /// When to try next; `None` once published.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How long a browser has to answer a passkey challenge.
+pub const PASSKEY_CHALLENGE_TTL: Duration = Duration::minutes(5);
+
+/// A WebAuthn credential registered for API logins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passkey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// The authenticator's id for the credential, base64url-encoded.
+    pub credential_id: String,
+    /// The credential's COSE public key.
+    pub public_key: Vec<u8>,
+    /// The authenticator's signature counter at the last login; 0 for
+    /// authenticators that don't keep one.
+    pub sign_count: u32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// What a WebAuthn challenge was issued for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengePurpose {
+    /// Adding a passkey to the user it was issued to.
+    Registration,
+    /// Proving who is logging in with a passkey.
+    Authentication,
+}
+
+impl ChallengePurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChallengePurpose::Registration => "registration",
+            ChallengePurpose::Authentication => "authentication",
+        }
+    }
+}
+
+/// A new credential from `navigator.credentials.create()`, as its
+/// `toJSON()` sends it: binary fields base64url-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PasskeyRegistration {
+    /// The credential id.
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+}
+
+/// A signed challenge from `navigator.credentials.get()`, as its `toJSON()`
+/// sends it: binary fields base64url-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PasskeyAssertion {
+    /// The id of the credential that signed.
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    /// The user id the passkey was registered with.
+    #[serde(default)]
+    pub user_handle: Option<String>,
+}
@@ -0,0 +1,19 @@
+use crate::models::derived_account::RecoveryPhrase;
+use crate::models::keystore::KeystoreEntry;
+use uuid::Uuid;
+
+/// Everything a new password rewrites, saved together so that a change or
+/// recovery that fails part-way leaves the old password and keys as they were.
+#[derive(Debug, Clone)]
+pub struct PasswordUpdate {
+    pub user_id: Uuid,
+    pub password_hash: String,
+    /// Keystore entries encrypted again with the new password.
+    pub keystore_entries: Vec<KeystoreEntry>,
+    /// The recovery phrase encrypted again with the new password.
+    pub recovery_phrase: Option<RecoveryPhrase>,
+    /// The phrase recovery code a recovery spends.
+    pub recovery_code: Option<Uuid>,
+    /// The session to keep; every other session of the user is ended.
+    pub keep_session: Option<Uuid>,
+}
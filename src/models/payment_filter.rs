@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Default dust threshold: incoming payments below 0.001 XLM are hidden.
+pub const DEFAULT_DUST_THRESHOLD_STROOPS: i64 = 10_000;
+
+/// A user's preferences for hiding unsolicited incoming payments.
+#[derive(Debug, Clone)]
+pub struct PaymentFilterSettings {
+    pub user_id: Uuid,
+    /// Incoming native payments strictly below this are treated as dust. Zero disables it.
+    pub dust_threshold_stroops: i64,
+    pub hide_scam_memos: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PaymentFilterSettings {
+    pub fn defaults_for(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            dust_threshold_stroops: DEFAULT_DUST_THRESHOLD_STROOPS,
+            hide_scam_memos: true,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Why a payment was hidden from the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HiddenReason {
+    Dust,
+    ScamMemo,
+}
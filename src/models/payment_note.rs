@@ -0,0 +1,35 @@
+use crate::utils::crypto::EncryptedNote;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Longest note, in characters, that can be attached to a payment.
+pub const MAX_NOTE_LENGTH: usize = 280;
+
+/// An encrypted note from one wallet user to another about a payment.
+#[derive(Debug, Clone)]
+pub struct PaymentNote {
+    pub id: Uuid,
+    pub transaction_hash: String,
+    pub sender_user_id: Uuid,
+    pub sender_public_key: String,
+    pub recipient_user_id: Uuid,
+    pub recipient_public_key: String,
+    pub note: EncryptedNote,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PaymentNote {
+    /// Bound into the ciphertext so a note only opens on its own payment.
+    pub fn context(&self) -> Vec<u8> {
+        format!("{}:{}:{}", self.transaction_hash, self.sender_public_key, self.recipient_public_key).into_bytes()
+    }
+
+    /// Our own address on this note and the other party's, from `user_id`'s side.
+    pub fn addresses_for(&self, user_id: &Uuid) -> (&str, &str) {
+        if self.sender_user_id == *user_id {
+            (&self.sender_public_key, &self.recipient_public_key)
+        } else {
+            (&self.recipient_public_key, &self.sender_public_key)
+        }
+    }
+}
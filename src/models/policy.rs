@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A rule checked before any payment is signed. Global policies (`user_id` of
+/// `None`) are set by operators and apply to everyone.
+#[derive(Debug, Clone)]
+pub struct TransactionPolicy {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub rule: PolicyRule,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyRule {
+    /// Blocks payments of `asset_code` larger than `max_stroops`.
+    MaxAmount { asset_code: String, max_stroops: i64 },
+    /// Only allows destinations on the allowlist, optionally just on one network.
+    AllowlistOnly { network: Option<String> },
+}
+
+/// A destination permitted by `AllowlistOnly` policies.
+#[derive(Debug, Clone)]
+pub struct AllowlistEntry {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub address: String,
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The payment being checked.
+#[derive(Debug, Clone)]
+pub struct PaymentIntent {
+    pub destination: String,
+    pub asset_code: String,
+    pub amount_stroops: i64,
+    pub network: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+    pub policy_id: Uuid,
+    pub message: String,
+}
@@ -0,0 +1,46 @@
+use crate::models::transaction::WalletTransaction;
+use serde::Serialize;
+
+/// One asset's net movement as derived from each source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetReconciliation {
+    pub asset_code: String,
+    /// Credits minus debits (and the starting balance) from Horizon effects.
+    pub network_stroops: i64,
+    /// Incoming minus outgoing confirmed payments in the local history.
+    pub local_stroops: i64,
+}
+
+impl AssetReconciliation {
+    pub fn difference(&self) -> i64 {
+        self.local_stroops - self.network_stroops
+    }
+}
+
+/// How an account's local history compares with what Horizon reports.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconciliationReport {
+    pub assets: Vec<AssetReconciliation>,
+    /// On the network but not in the local history.
+    pub missing: Vec<WalletTransaction>,
+    /// Local copies of a payment already recorded under another operation index.
+    pub duplicates: Vec<WalletTransaction>,
+    /// Confirmed locally but unknown to Horizon; left alone for investigation.
+    pub unknown: Vec<WalletTransaction>,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty()
+            && self.duplicates.is_empty()
+            && self.unknown.is_empty()
+            && self.assets.iter().all(|asset| asset.difference() == 0)
+    }
+
+    /// Whether [`ReconciliationService::repair`] has anything to do.
+    ///
+    /// [`ReconciliationService::repair`]: crate::services::reconciliation_service::ReconciliationService::repair
+    pub fn is_repairable(&self) -> bool {
+        !self.missing.is_empty() || !self.duplicates.is_empty()
+    }
+}
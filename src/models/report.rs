@@ -0,0 +1,80 @@
+use crate::models::transaction::{TransactionDirection, TransactionStatus};
+use chrono::NaiveDate;
+
+/// An ad-hoc admin report: which rows to look at, how to bucket them and what
+/// to compute per bucket. Built by [`ReportService::parse`](crate::services::report_service::ReportService::parse)
+/// and compiled to SQL by the database layer, so callers never write SQL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportQuery {
+    pub source: ReportSource,
+    pub filters: Vec<ReportFilter>,
+    pub group_by: Option<ReportGroup>,
+    pub aggregate: ReportAggregate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportSource {
+    Users,
+    /// Payments in the local history of every wallet.
+    Transactions,
+}
+
+impl ReportSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportSource::Users => "users",
+            ReportSource::Transactions => "transactions",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReportFilter {
+    /// Users only.
+    Verified(bool),
+    /// Users only.
+    WalletLinked(bool),
+    /// Transactions only.
+    Status(TransactionStatus),
+    /// Transactions only.
+    Direction(TransactionDirection),
+    /// Transactions only: the asset code, e.g. `XLM`.
+    Asset(String),
+    Since(NaiveDate),
+    Until(NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportGroup {
+    Day,
+    Month,
+    /// Verified or not for users; pending, confirmed or failed for transactions.
+    Status,
+    /// Users only.
+    Wallet,
+    /// Transactions only.
+    Asset,
+}
+
+impl ReportGroup {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportGroup::Day => "day",
+            ReportGroup::Month => "month",
+            ReportGroup::Status => "status",
+            ReportGroup::Wallet => "wallet",
+            ReportGroup::Asset => "asset",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportAggregate {
+    Count,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportRow {
+    pub group: String,
+    pub value: i64,
+}
@@ -0,0 +1,88 @@
+use async_graphql::Enum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What an account may do beyond its own wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    /// Can look at accounts and the audit log, but not change anything.
+    Support,
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::User, Role::Support, Role::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Support => "support",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "user" => Some(Role::User),
+            "support" => Some(Role::Support),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        match self {
+            Role::User => false,
+            Role::Support => matches!(permission, Permission::ViewUsers | Permission::ViewAuditLog | Permission::ViewReports),
+            Role::Admin => true,
+        }
+    }
+
+    /// Whether the role allows any administrative action at all.
+    pub fn is_staff(&self) -> bool {
+        *self != Role::User
+    }
+}
+
+/// Administrative actions, each allowed to some roles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ViewUsers,
+    /// Changing roles and disabling or enabling accounts.
+    ManageUsers,
+    ViewAuditLog,
+    ViewReports,
+    RunHealthSweep,
+    ManagePolicies,
+    /// Registering webhooks that receive every account's events.
+    ManageWebhooks,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ViewUsers => "view_users",
+            Permission::ManageUsers => "manage_users",
+            Permission::ViewAuditLog => "view_audit_log",
+            Permission::ViewReports => "view_reports",
+            Permission::RunHealthSweep => "run_health_sweep",
+            Permission::ManagePolicies => "manage_policies",
+            Permission::ManageWebhooks => "manage_webhooks",
+        }
+    }
+
+    /// Completes "Your account isn't allowed to ...".
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Permission::ViewUsers => "list users",
+            Permission::ManageUsers => "manage users",
+            Permission::ViewAuditLog => "view the audit log",
+            Permission::ViewReports => "view reports",
+            Permission::RunHealthSweep => "run the wallet health sweep",
+            Permission::ManagePolicies => "manage operator policies",
+            Permission::ManageWebhooks => "manage webhooks for every account",
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::models::audit::{AuditEntry, AuditEvent};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Version of the Elastic Common Schema the fields below follow.
+pub const ECS_VERSION: &str = "8.11.0";
+
+/// An audit log entry in Elastic Common Schema layout, one JSON object per
+/// line, so Splunk, Elastic and most SIEMs can ingest it without a custom
+/// parser.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecurityEvent {
+    #[serde(rename = "@timestamp")]
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+    pub event: EventFields,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserFields>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpFields>,
+    pub service: ServiceFields,
+    pub ecs: EcsFields,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventFields {
+    /// The audit entry's id, for joining with the wallet's own audit view.
+    pub id: String,
+    pub kind: &'static str,
+    pub category: Vec<&'static str>,
+    #[serde(rename = "type")]
+    pub event_type: Vec<&'static str>,
+    /// The wallet's own name for the event, e.g. `login_failed`.
+    pub action: String,
+    /// `success` or `failure`.
+    pub outcome: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserFields {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpFields {
+    pub request: HttpRequestFields,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpRequestFields {
+    /// The API request's `X-Request-Id`.
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceFields {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EcsFields {
+    pub version: &'static str,
+}
+
+impl SecurityEvent {
+    pub fn from_audit(entry: &AuditEntry, event: AuditEvent) -> Self {
+        let (category, event_type, outcome) = classify(event);
+
+        Self {
+            timestamp: entry.created_at,
+            message: entry.details.clone(),
+            event: EventFields {
+                id: entry.id.to_string(),
+                kind: "event",
+                category,
+                event_type,
+                action: entry.event_type.clone(),
+                outcome,
+            },
+            user: entry.user_id.map(|id| UserFields { id: id.to_string() }),
+            http: entry.request_id.clone().map(|id| HttpFields {
+                request: HttpRequestFields { id },
+            }),
+            service: ServiceFields {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            ecs: EcsFields { version: ECS_VERSION },
+        }
+    }
+}
+
+/// ECS `event.category`, `event.type` and `event.outcome` for each audit event.
+fn classify(event: AuditEvent) -> (Vec<&'static str>, Vec<&'static str>, &'static str) {
+    match event {
+        AuditEvent::AccountCreated => (vec!["iam"], vec!["user", "creation"], "success"),
+        AuditEvent::LoginSucceeded => (vec!["authentication", "session"], vec!["start"], "success"),
+        AuditEvent::LoginFailed => (vec!["authentication"], vec!["start"], "failure"),
+        AuditEvent::LoginLockedOut => (vec!["authentication", "intrusion_detection"], vec!["denied"], "failure"),
+        AuditEvent::Logout => (vec!["authentication", "session"], vec!["end"], "success"),
+        AuditEvent::SessionRevoked => (vec!["session"], vec!["end"], "success"),
+        AuditEvent::RefreshTokenReused => (vec!["intrusion_detection", "session"], vec!["end"], "failure"),
+        AuditEvent::KeyExported => (vec!["iam"], vec!["access"], "success"),
+        AuditEvent::PaymentSent => (vec!["api"], vec!["info"], "success"),
+        AuditEvent::PaymentFailed => (vec!["api"], vec!["info"], "failure"),
+        AuditEvent::PolicyViolation => (vec!["intrusion_detection"], vec!["denied"], "failure"),
+        AuditEvent::DataExported => (vec!["database"], vec!["access"], "success"),
+        AuditEvent::DataImported => (vec!["database"], vec!["change"], "success"),
+        AuditEvent::ProfileUpdated => (vec!["iam"], vec!["user", "change"], "success"),
+        AuditEvent::AccountDeleted => (vec!["iam"], vec!["user", "deletion"], "success"),
+        AuditEvent::AccessDenied => (vec!["iam"], vec!["access", "denied"], "failure"),
+        AuditEvent::RoleChanged => (vec!["iam"], vec!["user", "change"], "success"),
+        AuditEvent::AccountDisabled => (vec!["iam"], vec!["user", "change"], "success"),
+        AuditEvent::AccountEnabled => (vec!["iam"], vec!["user", "change"], "success"),
+        AuditEvent::ApiKeyCreated => (vec!["iam"], vec!["creation"], "success"),
+        AuditEvent::ApiKeyRevoked => (vec!["iam"], vec!["deletion"], "success"),
+        AuditEvent::SigningKeyRotated => (vec!["iam"], vec!["change"], "success"),
+        AuditEvent::KeyImported => (vec!["iam"], vec!["creation"], "success"),
+        AuditEvent::SigningPinChanged => (vec!["iam"], vec!["change"], "success"),
+        AuditEvent::SigningPinLockedOut => (vec!["authentication", "intrusion_detection"], vec!["denied"], "failure"),
+        AuditEvent::AccountRecovered => (vec!["iam", "authentication"], vec!["user", "change"], "success"),
+        AuditEvent::PasswordChanged => (vec!["iam"], vec!["user", "change"], "success"),
+        AuditEvent::EmailChanged => (vec!["iam"], vec!["user", "change"], "success"),
+        AuditEvent::EmailCodeReused => (vec!["intrusion_detection", "iam"], vec!["denied"], "failure"),
+        AuditEvent::WebhookAdded => (vec!["configuration"], vec!["creation"], "success"),
+        AuditEvent::WebhookRemoved => (vec!["configuration"], vec!["deletion"], "success"),
+        AuditEvent::OAuthLinked => (vec!["iam", "authentication"], vec!["user", "change"], "success"),
+        AuditEvent::PasskeyAdded => (vec!["iam", "authentication"], vec!["creation"], "success"),
+        AuditEvent::PasskeyRemoved => (vec!["iam", "authentication"], vec!["deletion"], "success"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn failed_login_uses_ecs_field_names() {
+        let user_id = Uuid::new_v4();
+        let entry = AuditEntry {
+            id: Uuid::new_v4(),
+            user_id: Some(user_id),
+            event_type: AuditEvent::LoginFailed.as_str().to_string(),
+            details: "identifier 'alice'".to_string(),
+            request_id: Some("req-1".to_string()),
+            created_at: Utc::now(),
+        };
+
+        let json = serde_json::to_value(SecurityEvent::from_audit(&entry, AuditEvent::LoginFailed)).unwrap();
+
+        assert_eq!(json["@timestamp"], entry.created_at.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true));
+        assert_eq!(json["event"]["action"], "login_failed");
+        assert_eq!(json["event"]["category"], serde_json::json!(["authentication"]));
+        assert_eq!(json["event"]["type"], serde_json::json!(["start"]));
+        assert_eq!(json["event"]["outcome"], "failure");
+        assert_eq!(json["user"]["id"], user_id.to_string());
+        assert_eq!(json["message"], "identifier 'alice'");
+        assert_eq!(json["http"]["request"]["id"], "req-1");
+        assert_eq!(json["ecs"]["version"], ECS_VERSION);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::models::api_key::ApiScope;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// How long a login stays valid.
+pub const SESSION_TTL: Duration = Duration::hours(12);
+
+/// An authenticated login, from successful password check until logout,
+/// expiry or revocation.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub device_label: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// What the login's access tokens may be used for.
+    pub scopes: Vec<ApiScope>,
+}
+
+impl Session {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && now < self.expires_at
+    }
+}
+
+/// What a signed access token carries. The session id lets a revoked or
+/// expired session invalidate its tokens before they run out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessClaims {
+    /// The user id.
+    pub sub: Uuid,
+    /// The session the token was issued for.
+    pub sid: Uuid,
+    pub iat: i64,
+    pub exp: i64,
+    /// The session's scopes, space-separated. Tokens from before scopes
+    /// have none and get their session's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+/// A "stay logged in" login kept on disk between runs.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedLogin {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// The deployment it's a login to, for `--remote`; `None` for a login to
+    /// this wallet's own database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+}
+
+/// Leaves the tokens out, so logging a saved login doesn't leak it.
+impl fmt::Debug for SavedLogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SavedLogin")
+            .field("access_token", &"[redacted]")
+            .field("refresh_token", &"[redacted]")
+            .field("remote", &self.remote)
+            .finish()
+    }
+}
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A user's signing PIN, hashed like a password.
+#[derive(Debug, Clone)]
+pub struct SigningPin {
+    pub user_id: Uuid,
+    pub pin_hash: String,
+    /// Wrong PINs in a row since the last correct one or lockout.
+    pub failures: i64,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// How long an SMS verification code can be used for.
+pub const SMS_CODE_TTL: Duration = Duration::minutes(5);
+
+/// Why a text was sent, for the message log and cost reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmsPurpose {
+    /// Confirms the user owns a number before verification is switched on.
+    PhoneConfirmation,
+    LoginCode,
+    PaymentConfirmation,
+    SecurityAlert,
+}
+
+impl SmsPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmsPurpose::PhoneConfirmation => "phone_confirmation",
+            SmsPurpose::LoginCode => "login_code",
+            SmsPurpose::PaymentConfirmation => "payment_confirmation",
+            SmsPurpose::SecurityAlert => "security_alert",
+        }
+    }
+}
+
+/// A text handed to the SMS provider. The destination number is not kept, only
+/// its country calling code.
+#[derive(Debug, Clone)]
+pub struct SmsMessage {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    /// Country calling code without the `+`, e.g. `"254"`.
+    pub country_code: String,
+    pub purpose: SmsPurpose,
+    pub provider: String,
+    pub provider_message_id: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a provider reports back for an accepted message.
+#[derive(Debug, Clone, Default)]
+pub struct SmsReceipt {
+    pub provider_message_id: Option<String>,
+    pub cost_usd: Option<f64>,
+}
+
+/// A one-time code that was texted to the user.
+#[derive(Debug, Clone)]
+pub struct SmsCode {
+    pub code: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SmsCode {
+    pub fn matches(&self, input: &str, now: DateTime<Utc>) -> bool {
+        now < self.expires_at && input.trim() == self.code
+    }
+}
+
+/// Messages sent and what they cost, for the operator stats screen.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SmsUsage {
+    pub messages: i64,
+    pub cost_usd: f64,
+}
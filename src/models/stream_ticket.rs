@@ -0,0 +1,18 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// How long a ticket can wait before it opens a stream.
+pub const STREAM_TICKET_TTL: Duration = Duration::seconds(30);
+
+/// Who an event stream opened with a ticket is for, and until when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamTicket {
+    pub user_id: Uuid,
+    /// The session of the login that asked for it; `None` for API keys.
+    pub session_id: Option<Uuid>,
+    /// The API key that asked for it; `None` for logins.
+    pub api_key_id: Option<Uuid>,
+    /// When the stream ends: when the access token or API key that asked
+    /// for the ticket expires. `None` for API keys that don't.
+    pub stream_expires_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,112 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionDirection {
+    Incoming,
+    Outgoing,
+}
+
+impl TransactionDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionDirection::Incoming => "incoming",
+            TransactionDirection::Outgoing => "outgoing",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "incoming" => Some(TransactionDirection::Incoming),
+            "outgoing" => Some(TransactionDirection::Outgoing),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionStatus {
+    /// Signed and being submitted.
+    Pending,
+    /// In a closed ledger.
+    Confirmed,
+    /// Rejected by Horizon or never made it to the network.
+    Failed,
+}
+
+impl TransactionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::Confirmed => "confirmed",
+            TransactionStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(TransactionStatus::Pending),
+            "confirmed" => Some(TransactionStatus::Confirmed),
+            "failed" => Some(TransactionStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// One payment operation as seen from `account`, either submitted by the
+/// wallet or backfilled from Horizon.
+#[derive(Debug, Clone, Serialize, JsonSchema, SimpleObject)]
+#[graphql(name = "Transaction", complex)]
+pub struct WalletTransaction {
+    #[serde(skip)]
+    #[graphql(skip)]
+    pub id: Uuid,
+    pub account: String,
+    pub hash: String,
+    pub operation_index: i64,
+    pub direction: TransactionDirection,
+    pub asset_code: String,
+    #[graphql(skip)]
+    pub amount_stroops: i64,
+    pub counterparty: String,
+    pub memo: Option<String>,
+    pub status: TransactionStatus,
+    pub ledger: Option<i64>,
+    pub error: Option<String>,
+    /// The API request that submitted it, for payments sent through the API.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[graphql(skip)]
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What to look for in an account's history. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionSearch {
+    /// Start of the transaction hash, in lowercase hex.
+    pub hash_prefix: Option<String>,
+    /// Start of the counterparty's address.
+    pub counterparty: Option<String>,
+    /// Case-insensitive text anywhere in the memo.
+    pub memo: Option<String>,
+    pub min_amount_stroops: Option<i64>,
+    pub max_amount_stroops: Option<i64>,
+    pub asset_code: Option<String>,
+    /// First day included.
+    pub since: Option<NaiveDate>,
+    /// Last day included.
+    pub until: Option<NaiveDate>,
+    pub tag: Option<String>,
+}
+
+impl TransactionSearch {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the exported columns change. Each version is exported to
+/// its own directory from the start, so one directory never mixes schemas.
+pub const TRANSACTION_EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// How far `db export` got, kept next to the files it wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportCheckpoint {
+    /// The last change number written to a file.
+    pub last_change_seq: i64,
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of a `db export` run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionExportReport {
+    /// Where this schema version's files and checkpoint are.
+    pub dir: String,
+    pub schema_version: u32,
+    /// Transactions written, counting a changed one again each time.
+    pub exported: usize,
+    /// The files this run added, one per month per batch.
+    pub files: Vec<String>,
+    pub checkpoint: ExportCheckpoint,
+}
@@ -0,0 +1,43 @@
+use crate::utils::crypto::Envelope;
+use chrono::{DateTime, Utc};
+use std::fmt;
+use uuid::Uuid;
+
+/// Recovery codes issued each time 2FA is turned on or the codes are renewed.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// A user's TOTP secret, sealed under `CUSTOMER_DATA_KEYS`.
+#[derive(Debug, Clone)]
+pub struct TotpSecret {
+    pub user_id: Uuid,
+    pub envelope: Envelope,
+    /// Unset while enrollment waits for the first code.
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// The 30-second time step of the last accepted code.
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TotpSecret {
+    pub fn is_enabled(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+/// What the user needs to add the account to an authenticator app. Shown
+/// once, during enrollment.
+#[derive(Clone)]
+pub struct TotpEnrollment {
+    pub secret_base32: String,
+    pub otpauth_url: String,
+}
+
+/// Leaves the secret out, and the URL that carries it.
+impl fmt::Debug for TotpEnrollment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TotpEnrollment")
+            .field("secret_base32", &"[redacted]")
+            .field("otpauth_url", &"[redacted]")
+            .finish()
+    }
+}
@@ -0,0 +1,63 @@
+use crate::models::contact::Contact;
+use uuid::Uuid;
+
+/// A per-user setting that can be changed back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoableSetting {
+    FiatCurrency,
+    Theme,
+}
+
+/// A change to local data only, which can be reversed. Anything submitted to
+/// the network is never recorded: a payment can't be taken back.
+#[derive(Debug, Clone)]
+pub enum LocalAction {
+    ContactAdded(Contact),
+    ContactDeleted(Contact),
+    TagAdded { account: String, hash: String, tag: String },
+    TagRemoved { account: String, hash: String, tag: String },
+    SettingChanged { user_id: Uuid, setting: UndoableSetting, before: String, after: String },
+}
+
+impl LocalAction {
+    /// The action that cancels this one.
+    pub fn inverse(&self) -> Self {
+        match self {
+            LocalAction::ContactAdded(contact) => LocalAction::ContactDeleted(contact.clone()),
+            LocalAction::ContactDeleted(contact) => LocalAction::ContactAdded(contact.clone()),
+            LocalAction::TagAdded { account, hash, tag } => LocalAction::TagRemoved {
+                account: account.clone(),
+                hash: hash.clone(),
+                tag: tag.clone(),
+            },
+            LocalAction::TagRemoved { account, hash, tag } => LocalAction::TagAdded {
+                account: account.clone(),
+                hash: hash.clone(),
+                tag: tag.clone(),
+            },
+            LocalAction::SettingChanged { user_id, setting, before, after } => LocalAction::SettingChanged {
+                user_id: *user_id,
+                setting: *setting,
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+
+    /// What the action does, e.g. "add contact 'Bob'".
+    pub fn describe(&self) -> String {
+        match self {
+            LocalAction::ContactAdded(contact) => format!("add contact '{}'", contact.name),
+            LocalAction::ContactDeleted(contact) => format!("delete contact '{}'", contact.name),
+            LocalAction::TagAdded { hash, tag, .. } => format!("tag {}… '{}'", hash.get(..12).unwrap_or(hash), tag),
+            LocalAction::TagRemoved { hash, tag, .. } => format!("remove tag '{}' from {}…", tag, hash.get(..12).unwrap_or(hash)),
+            LocalAction::SettingChanged { setting, before, after, .. } => {
+                let name = match setting {
+                    UndoableSetting::FiatCurrency => "display currency",
+                    UndoableSetting::Theme => "theme",
+                };
+                format!("change {} from {} to {}", name, before, after)
+            }
+        }
+    }
+}
@@ -1,4 +1,8 @@
+use crate::models::role::Role;
+use async_graphql::SimpleObject;
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -10,24 +14,36 @@ pub struct User {
     pub password_hash: String,
     pub is_verified: bool,
     pub stellar_public_key: Option<String>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub login_count: i64,
+    pub role: Role,
+    /// When an admin disabled the account; disabled accounts can't log in.
+    pub disabled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateUserRequest {
     pub email: String,
     pub username: String,
-    pub password: String,
+    #[schemars(with = "String")]
+    pub password: SecretString,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema, SimpleObject)]
+#[graphql(name = "User")]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
     pub username: String,
     pub is_verified: bool,
     pub stellar_public_key: Option<String>,
+    /// From `authenticate_user`, the login before the current one.
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub login_count: i64,
+    pub role: Role,
+    pub disabled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -39,6 +55,10 @@ impl From<User> for UserResponse {
             username: user.username,
             is_verified: user.is_verified,
             stellar_public_key: user.stellar_public_key,
+            last_login_at: user.last_login_at,
+            login_count: user.login_count,
+            role: user.role,
+            disabled_at: user.disabled_at,
             created_at: user.created_at,
         }
     }
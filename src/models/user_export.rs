@@ -0,0 +1,127 @@
+use crate::models::contact::Contact;
+use crate::models::transaction::WalletTransaction;
+use crate::models::user::UserResponse;
+use crate::models::user_settings::UserSettings;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the file layout changes incompatibly.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Everything the wallet stores about a user that they can take elsewhere.
+/// Internal ids are left out so the file can be imported into any instance.
+/// Secret keys are not included; move them with the keystore export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserExport {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub profile: ExportedProfile,
+    pub settings: ExportedSettings,
+    pub contacts: Vec<ExportedContact>,
+    pub transactions: Vec<ExportedTransaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedProfile {
+    pub username: String,
+    pub email: String,
+    pub is_verified: bool,
+    pub stellar_public_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedSettings {
+    pub fiat_currency: String,
+    pub sms_phone_number: Option<String>,
+    pub sms_verification_enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedContact {
+    pub name: String,
+    pub address: String,
+    pub memo: Option<String>,
+    pub federation_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTransaction {
+    pub hash: String,
+    pub operation_index: i64,
+    /// `incoming` or `outgoing`.
+    pub direction: String,
+    pub asset_code: String,
+    pub amount_stroops: i64,
+    pub counterparty: String,
+    pub memo: Option<String>,
+    /// `pending`, `confirmed` or `failed`.
+    pub status: String,
+    pub ledger: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What an import changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub contacts_added: usize,
+    /// Contacts whose name the account already uses.
+    pub contacts_skipped: usize,
+    pub transactions_imported: usize,
+    /// Transactions of a Stellar account other than the importing user's.
+    pub transactions_skipped: usize,
+}
+
+impl From<&UserResponse> for ExportedProfile {
+    fn from(user: &UserResponse) -> Self {
+        Self {
+            username: user.username.clone(),
+            email: user.email.clone(),
+            is_verified: user.is_verified,
+            stellar_public_key: user.stellar_public_key.clone(),
+            created_at: user.created_at,
+        }
+    }
+}
+
+impl From<&UserSettings> for ExportedSettings {
+    fn from(settings: &UserSettings) -> Self {
+        Self {
+            fiat_currency: settings.fiat_currency.clone(),
+            sms_phone_number: settings.sms_phone_number.clone(),
+            sms_verification_enabled: settings.sms_verification_enabled,
+        }
+    }
+}
+
+impl From<&Contact> for ExportedContact {
+    fn from(contact: &Contact) -> Self {
+        Self {
+            name: contact.name.clone(),
+            address: contact.address.clone(),
+            memo: contact.memo.clone(),
+            federation_name: contact.federation_name.clone(),
+            created_at: contact.created_at,
+        }
+    }
+}
+
+impl From<&WalletTransaction> for ExportedTransaction {
+    fn from(tx: &WalletTransaction) -> Self {
+        Self {
+            hash: tx.hash.clone(),
+            operation_index: tx.operation_index,
+            direction: tx.direction.as_str().to_string(),
+            asset_code: tx.asset_code.clone(),
+            amount_stroops: tx.amount_stroops,
+            counterparty: tx.counterparty.clone(),
+            memo: tx.memo.clone(),
+            status: tx.status.as_str().to_string(),
+            ledger: tx.ledger,
+            error: tx.error.clone(),
+            created_at: tx.created_at,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub const DEFAULT_FIAT_CURRENCY: &str = "usd";
+pub const DEFAULT_THEME: &str = "default";
+
+/// Fiat currencies users can pick for display.
+pub const SUPPORTED_FIAT_CURRENCIES: &[&str] = &[
+    "usd", "eur", "gbp", "jpy", "cad", "aud", "chf", "cny", "inr", "brl", "mxn", "ngn", "kes", "zar",
+];
+
+/// Per-user display preferences.
+#[derive(Debug, Clone)]
+pub struct UserSettings {
+    pub user_id: Uuid,
+    /// Lowercase ISO 4217 code fiat equivalents are shown in.
+    pub fiat_currency: String,
+    /// E.164 number SMS codes and alerts go to, once confirmed.
+    pub sms_phone_number: Option<String>,
+    /// Whether logins and payments need a code sent to `sms_phone_number`.
+    pub sms_verification_enabled: bool,
+    /// CLI color theme name, e.g. `high-contrast`.
+    pub theme: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserSettings {
+    pub fn defaults_for(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            fiat_currency: DEFAULT_FIAT_CURRENCY.to_string(),
+            sms_phone_number: None,
+            sms_verification_enabled: false,
+            theme: DEFAULT_THEME.to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// The number to send verification codes to, if the user turned them on.
+    pub fn sms_verification_number(&self) -> Option<&str> {
+        self.sms_phone_number.as_deref().filter(|_| self.sms_verification_enabled)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::models::transaction::WalletTransaction;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Something that happened to a user's account, e.g.
+/// `{"type": "payment_received", "transaction": {...}}`. The API sends it as
+/// its own version's event type.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalletEvent {
+    PaymentReceived { transaction: WalletTransaction },
+    /// A payment from one of the user's wallets made it into a ledger.
+    TransactionConfirmed { transaction: WalletTransaction },
+    /// A payment from one of the user's wallets was rejected or never made it
+    /// to the network.
+    TransactionFailed { transaction: WalletTransaction },
+    /// The user's email address was verified.
+    AccountVerified { verified_at: DateTime<Utc> },
+    /// Logged out, revoked from another device, or ended by an admin.
+    SessionRevoked { session_id: Uuid },
+    /// One of the user's API keys was revoked.
+    ApiKeyRevoked { key_id: Uuid },
+    /// An admin disabled the account. Every credential stops working.
+    AccountDisabled { disabled_at: DateTime<Utc> },
+}
+
+/// A [`WalletEvent`] and the user it belongs to.
+#[derive(Debug, Clone)]
+pub struct UserEvent {
+    pub user_id: Uuid,
+    pub event: WalletEvent,
+}
@@ -0,0 +1,19 @@
+/// The outcome of checking one user's wallet against the network.
+#[derive(Debug, Clone)]
+pub struct WalletHealth {
+    pub username: String,
+    pub public_key: String,
+    pub status: WalletStatus,
+    /// Non-native trustlines holding a zero balance. They still lock up a base
+    /// reserve each, so they are worth removing.
+    pub orphaned_trustlines: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletStatus {
+    /// The address is linked but has never been funded.
+    Unfunded,
+    /// Spendable XLM above the minimum balance is below the sweep threshold.
+    LowReserve { headroom_stroops: i64 },
+    Healthy { headroom_stroops: i64 },
+}
@@ -0,0 +1,108 @@
+use crate::utils::crypto::Envelope;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Events endpoints can subscribe to, named as in each payload's `type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum WebhookEventType {
+    /// A payment arrived at one of the account's wallets.
+    #[serde(rename = "payment.received")]
+    PaymentReceived,
+    /// The account's email address was verified.
+    #[serde(rename = "account.verified")]
+    AccountVerified,
+    /// A payment the account sent was rejected or never reached the network.
+    #[serde(rename = "transaction.failed")]
+    TransactionFailed,
+}
+
+impl WebhookEventType {
+    pub const ALL: [WebhookEventType; 3] = [
+        WebhookEventType::PaymentReceived,
+        WebhookEventType::AccountVerified,
+        WebhookEventType::TransactionFailed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::PaymentReceived => "payment.received",
+            WebhookEventType::AccountVerified => "account.verified",
+            WebhookEventType::TransactionFailed => "transaction.failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event_type| event_type.as_str() == value)
+    }
+}
+
+/// A URL that gets signed notifications of an account's events, or of every
+/// account's when `user_id` is unset.
+#[derive(Debug, Clone)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    /// The signing secret, sealed under `CUSTOMER_DATA_KEYS`.
+    pub envelope: Envelope,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Not delivered yet; tried again at `next_attempt_at`.
+    Pending,
+    Delivered,
+    /// Given up on after the last retry.
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(DeliveryStatus::Pending),
+            "delivered" => Some(DeliveryStatus::Delivered),
+            "failed" => Some(DeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// One event on its way to one endpoint, and how the attempts went.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    /// The account the event is about.
+    #[serde(skip)]
+    pub user_id: Uuid,
+    /// The payload's `id`, the same on every attempt.
+    pub event_id: String,
+    pub event_type: WebhookEventType,
+    /// The JSON body, posted as is on every attempt.
+    #[serde(skip)]
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    /// The HTTP status of the last attempt, if the endpoint answered.
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    /// The API request behind the event, also sent as `X-Request-Id`.
+    pub request_id: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,567 @@
+use crate::config;
+use crate::database::user_repository::UserRepository;
+use crate::errors::{AppError, Result};
+use crate::models::role::Role;
+use crate::models::user::User;
+use crate::models::wallet_event::WalletEvent;
+use crate::services::event_bus::EventBus;
+use crate::stellar::horizon::{AccountRecord, HorizonClient};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long a Horizon account or user profile is served from the cache,
+/// unless `API_ACCOUNT_CACHE_TTL_SECS` says otherwise.
+const DEFAULT_TTL: Duration = Duration::from_secs(10);
+
+/// The in-memory store drops expired accounts once it holds this many.
+const MAX_MEMORY_ENTRIES: usize = 10_000;
+
+/// Where cached accounts and profiles are kept, as JSON.
+#[async_trait]
+pub trait AccountCacheStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+    async fn put(&self, key: &str, value: &str, ttl: Duration) -> Result<()>;
+    async fn remove(&self, key: &str) -> Result<()>;
+}
+
+/// Horizon account lookups for display, such as balances in the API, cached
+/// for a few seconds so busy clients don't each wait on Horizon. Accounts are
+/// dropped from the cache as soon as the API sends a payment from or to them
+/// or sees one arrive. Anything that signs still reads Horizon directly: a
+/// stale sequence number would fail the transaction. [`Self::users`] puts the
+/// same cache in front of user profiles.
+pub struct AccountCacheService {
+    horizon: HorizonClient,
+    cache: Option<Arc<Cache>>,
+}
+
+impl AccountCacheService {
+    /// Lookups that always go to Horizon.
+    pub fn new(horizon: HorizonClient) -> Self {
+        Self { horizon, cache: None }
+    }
+
+    /// Caches where `API_ACCOUNT_CACHE` says: `memory` or `redis`, at
+    /// `REDIS_URL`. Unset, nothing is cached. `API_ACCOUNT_CACHE_TTL_SECS`
+    /// sets how long an account is kept.
+    pub fn from_env(horizon: HorizonClient) -> Result<Self> {
+        let store: Arc<dyn AccountCacheStore> = match config::var("API_ACCOUNT_CACHE").unwrap_or_default().to_lowercase().as_str() {
+            "" => return Ok(Self::new(horizon)),
+            "memory" => Arc::new(MemoryStore::default()),
+            "redis" => redis_store()?,
+            other => {
+                return Err(AppError::ValidationError(format!(
+                    "Unknown API_ACCOUNT_CACHE '{}', expected memory or redis",
+                    other
+                )))
+            }
+        };
+        let ttl = match config::var("API_ACCOUNT_CACHE_TTL_SECS") {
+            Some(value) => value
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .ok_or_else(|| AppError::ValidationError("API_ACCOUNT_CACHE_TTL_SECS must be a whole number of seconds".to_string()))?,
+            None => DEFAULT_TTL,
+        };
+
+        Ok(Self::with_store(horizon, store, ttl))
+    }
+
+    pub fn with_store(horizon: HorizonClient, store: Arc<dyn AccountCacheStore>, ttl: Duration) -> Self {
+        Self {
+            horizon,
+            cache: Some(Arc::new(Cache {
+                store,
+                ttl,
+                in_flight: Mutex::default(),
+            })),
+        }
+    }
+
+    /// Like [`HorizonClient::get_account`], possibly a few seconds old. A
+    /// store that can't be reached is logged and skipped.
+    pub async fn get_account(&self, address: &str) -> Result<Option<AccountRecord>> {
+        match &self.cache {
+            Some(cache) => cache.get_or_fetch(&self.key(address), self.horizon.get_account(address)).await,
+            None => self.horizon.get_account(address).await,
+        }
+    }
+
+    /// Drops `addresses` from the cache after something changed them.
+    pub async fn invalidate(&self, addresses: &[&str]) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+        for address in addresses {
+            cache.invalidate(&self.key(address)).await;
+        }
+    }
+
+    /// `users` with [`UserRepository::get_user_profile`] served from this
+    /// cache, or `users` itself when nothing is cached.
+    pub fn users(&self, users: Arc<dyn UserRepository>) -> Arc<dyn UserRepository> {
+        match &self.cache {
+            Some(cache) => Arc::new(CachedUserRepository {
+                users,
+                cache: cache.clone(),
+            }),
+            None => users,
+        }
+    }
+
+    /// Drops accounts from the cache as payments to and from them show up
+    /// on `bus`, and profiles as accounts are disabled or verified, until
+    /// the bus closes.
+    pub async fn run(self: Arc<Self>, bus: EventBus) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+
+        let mut events = bus.subscribe_all();
+        while let Some(event) = events.next().await {
+            match &event.event {
+                WalletEvent::PaymentReceived { transaction }
+                | WalletEvent::TransactionConfirmed { transaction }
+                | WalletEvent::TransactionFailed { transaction } => self.invalidate(&[&transaction.account]).await,
+                WalletEvent::AccountDisabled { .. } | WalletEvent::AccountVerified { .. } => {
+                    cache.invalidate(&user_key(&event.user_id)).await
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Keys include Horizon's URL so testnet and mainnet can share a Redis.
+    fn key(&self, address: &str) -> String {
+        format!("account:{}:{}", self.horizon.base_url(), address)
+    }
+}
+
+fn user_key(user_id: &Uuid) -> String {
+    format!("user:{}", user_id)
+}
+
+/// Values kept in a store for `ttl`. A lookup that an invalidation overtook
+/// doesn't write its older value back.
+struct Cache {
+    store: Arc<dyn AccountCacheStore>,
+    ttl: Duration,
+    /// For each key with lookups in flight: how many, and a version that
+    /// each invalidation bumps.
+    in_flight: Mutex<HashMap<String, (usize, u64)>>,
+}
+
+impl Cache {
+    async fn get_or_fetch<T: Serialize + DeserializeOwned>(&self, key: &str, fetch: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.store.get(key).await {
+            Ok(Some(cached)) => match serde_json::from_str(&cached) {
+                Ok(value) => return Ok(value),
+                Err(e) => tracing::warn!("Ignoring unreadable cache entry {}: {}", key, e),
+            },
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Cache lookup for {} failed, reading it directly: {}", key, e),
+        }
+
+        let lookup = Lookup::start(self, key);
+        let value = fetch.await?;
+        let cached = serde_json::to_string(&value).map_err(|e| AppError::InternalError(format!("Failed to serialize {}: {}", key, e)))?;
+        if lookup.is_current() {
+            if let Err(e) = self.store.put(key, &cached, self.ttl).await {
+                tracing::warn!("Couldn't cache {}: {}", key, e);
+            }
+            // An invalidation while the value was being written may have
+            // removed it before it landed.
+            if !lookup.is_current() {
+                self.remove(key).await;
+            }
+        }
+        Ok(value)
+    }
+
+    async fn invalidate(&self, key: &str) {
+        if let Some((_, version)) = self.in_flight.lock().unwrap().get_mut(key) {
+            *version += 1;
+        }
+        self.remove(key).await;
+    }
+
+    async fn remove(&self, key: &str) {
+        if let Err(e) = self.store.remove(key).await {
+            tracing::warn!("Couldn't drop cached {}: {}", key, e);
+        }
+    }
+}
+
+/// A lookup of `key` in flight, until dropped, and the version it started at.
+struct Lookup<'a> {
+    cache: &'a Cache,
+    key: &'a str,
+    version: u64,
+}
+
+impl<'a> Lookup<'a> {
+    fn start(cache: &'a Cache, key: &'a str) -> Self {
+        let mut in_flight = cache.in_flight.lock().unwrap();
+        let (lookups, version) = in_flight.entry(key.to_string()).or_default();
+        *lookups += 1;
+        Self {
+            cache,
+            key,
+            version: *version,
+        }
+    }
+
+    /// Whether nothing invalidated the key since the lookup started.
+    fn is_current(&self) -> bool {
+        self.cache.in_flight.lock().unwrap().get(self.key).is_some_and(|(_, version)| *version == self.version)
+    }
+}
+
+impl Drop for Lookup<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.cache.in_flight.lock().unwrap();
+        if let Some((lookups, _)) = in_flight.get_mut(self.key) {
+            *lookups -= 1;
+            if *lookups == 0 {
+                in_flight.remove(self.key);
+            }
+        }
+    }
+}
+
+/// A [`UserRepository`] whose profile lookups come from the cache, kept
+/// without the password hash. Writes through it drop the user's profile.
+struct CachedUserRepository {
+    users: Arc<dyn UserRepository>,
+    cache: Arc<Cache>,
+}
+
+impl CachedUserRepository {
+    async fn changed(&self, user_id: &Uuid) {
+        self.cache.invalidate(&user_key(user_id)).await;
+    }
+}
+
+#[async_trait]
+impl UserRepository for CachedUserRepository {
+    async fn create_user(&self, user: &User) -> Result<()> {
+        self.users.create_user(user).await
+    }
+
+    async fn create_users(&self, users: &[User]) -> Result<Vec<Result<()>>> {
+        self.users.create_users(users).await
+    }
+
+    async fn get_user_by_id(&self, user_id: &Uuid) -> Result<Option<User>> {
+        self.users.get_user_by_id(user_id).await
+    }
+
+    async fn get_user_profile(&self, user_id: &Uuid) -> Result<Option<User>> {
+        let fetch = async {
+            let user = self.users.get_user_profile(user_id).await?;
+            Ok(user.map(|user| User {
+                password_hash: String::new(),
+                ..user
+            }))
+        };
+        self.cache.get_or_fetch(&user_key(user_id), fetch).await
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        self.users.get_user_by_email(email).await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        self.users.get_user_by_username(username).await
+    }
+
+    async fn get_user_by_stellar_public_key(&self, public_key: &str) -> Result<Option<User>> {
+        self.users.get_user_by_stellar_public_key(public_key).await
+    }
+
+    async fn update_user_stellar_public_key(&self, user_id: &Uuid, public_key: &str) -> Result<()> {
+        let result = self.users.update_user_stellar_public_key(user_id, public_key).await;
+        self.changed(user_id).await;
+        result
+    }
+
+    async fn record_user_login(&self, user_id: &Uuid, at: DateTime<Utc>) -> Result<()> {
+        let result = self.users.record_user_login(user_id, at).await;
+        self.changed(user_id).await;
+        result
+    }
+
+    async fn update_user(&self, user: &User) -> Result<()> {
+        let result = self.users.update_user(user).await;
+        self.changed(&user.id).await;
+        result
+    }
+
+    async fn update_user_password_hash(&self, user_id: &Uuid, password_hash: &str) -> Result<()> {
+        self.users.update_user_password_hash(user_id, password_hash).await
+    }
+
+    async fn get_users(&self) -> Result<Vec<User>> {
+        self.users.get_users().await
+    }
+
+    async fn update_user_role(&self, user_id: &Uuid, role: Role, at: DateTime<Utc>) -> Result<()> {
+        let result = self.users.update_user_role(user_id, role, at).await;
+        self.changed(user_id).await;
+        result
+    }
+
+    async fn update_user_disabled(&self, user_id: &Uuid, disabled_at: Option<DateTime<Utc>>, at: DateTime<Utc>) -> Result<()> {
+        let result = self.users.update_user_disabled(user_id, disabled_at, at).await;
+        self.changed(user_id).await;
+        result
+    }
+
+    async fn delete_user(&self, user_id: &Uuid) -> Result<bool> {
+        let result = self.users.delete_user(user_id).await;
+        self.changed(user_id).await;
+        result
+    }
+
+    async fn get_user_count(&self) -> Result<i64> {
+        self.users.get_user_count().await
+    }
+}
+
+/// Accounts for this process only.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+#[async_trait]
+impl AccountCacheStore for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .get(key)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(value, _)| value.clone()))
+    }
+
+    async fn put(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_MEMORY_ENTRIES {
+            entries.retain(|_, (_, expires)| *expires > now);
+        }
+        entries.insert(key.to_string(), (value.to_string(), now + ttl));
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<()> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_store() -> Result<Arc<dyn AccountCacheStore>> {
+    let url = std::env::var("REDIS_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .ok_or_else(|| AppError::ValidationError("API_ACCOUNT_CACHE=redis needs REDIS_URL".to_string()))?;
+    let client = redis::Client::open(url).map_err(|e| AppError::ValidationError(format!("Invalid REDIS_URL: {}", e)))?;
+
+    Ok(Arc::new(RedisStore {
+        client,
+        connection: tokio::sync::OnceCell::new(),
+    }))
+}
+
+#[cfg(not(feature = "redis"))]
+fn redis_store() -> Result<Arc<dyn AccountCacheStore>> {
+    Err(AppError::ValidationError(
+        "API_ACCOUNT_CACHE=redis needs a build with the redis feature".to_string(),
+    ))
+}
+
+/// Accounts shared by every API server using the same Redis, which expires
+/// them. Connects on first use and reconnects by itself.
+#[cfg(feature = "redis")]
+pub struct RedisStore {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::ConnectionManager>,
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    async fn connection(&self) -> Result<redis::aio::ConnectionManager> {
+        Ok(self
+            .connection
+            .get_or_try_init(|| redis::aio::ConnectionManager::new(self.client.clone()))
+            .await
+            .map_err(redis_error)?
+            .clone())
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_error(e: redis::RedisError) -> AppError {
+    AppError::InternalError(format!("Redis account cache failed: {}", e))
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl AccountCacheStore for RedisStore {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        redis::cmd("GET").arg(key).query_async(&mut self.connection().await?).await.map_err(redis_error)
+    }
+
+    async fn put(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("PX")
+            .arg(ttl.as_millis() as u64)
+            .query_async(&mut self.connection().await?)
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn remove(&self, key: &str) -> Result<()> {
+        redis::cmd("DEL").arg(key).query_async(&mut self.connection().await?).await.map_err(redis_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::user_repository::InMemoryUserRepository;
+    use axum::extract::State;
+    use axum::routing::get;
+    use axum::Router;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const ADDRESS: &str = "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ";
+
+    /// Serves one account and counts how often it's asked for.
+    async fn horizon() -> (HorizonClient, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/accounts/{address}",
+                get(|State(requests): State<Arc<AtomicUsize>>| async move {
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    axum::Json(json!({ "sequence": "42", "balances": [{ "balance": "10.0000000", "asset_type": "native" }] }))
+                }),
+            )
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (HorizonClient::new(&url), requests)
+    }
+
+    #[tokio::test]
+    async fn serves_accounts_from_the_cache_until_invalidated_or_expired() {
+        let (horizon, requests) = horizon().await;
+        let store = Arc::new(MemoryStore::default());
+        let cache = AccountCacheService::with_store(horizon.clone(), store.clone(), Duration::from_secs(60));
+
+        assert_eq!(cache.get_account(ADDRESS).await.unwrap().unwrap().native_balance(), "10.0000000");
+        cache.get_account(ADDRESS).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        cache.invalidate(&[ADDRESS]).await;
+        cache.get_account(ADDRESS).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        let short = AccountCacheService::with_store(horizon.clone(), store, Duration::from_millis(1));
+        short.invalidate(&[ADDRESS]).await;
+        short.get_account(ADDRESS).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        short.get_account(ADDRESS).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+
+        AccountCacheService::new(horizon).get_account(ADDRESS).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn lookups_overtaken_by_an_invalidation_are_not_cached() {
+        let (requests, release) = (Arc::new(AtomicUsize::new(0)), Arc::new(tokio::sync::Notify::new()));
+        let app = Router::new()
+            .route(
+                "/accounts/{address}",
+                get(|State((requests, release)): State<(Arc<AtomicUsize>, Arc<tokio::sync::Notify>)>| async move {
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    release.notified().await;
+                    axum::Json(json!({ "sequence": "42", "balances": [] }))
+                }),
+            )
+            .with_state((requests.clone(), release.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let cache = Arc::new(AccountCacheService::with_store(
+            HorizonClient::new(&url),
+            Arc::new(MemoryStore::default()),
+            Duration::from_secs(60),
+        ));
+
+        let lookup = tokio::spawn({
+            let cache = cache.clone();
+            async move { cache.get_account(ADDRESS).await }
+        });
+        while requests.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        cache.invalidate(&[ADDRESS]).await;
+        release.notify_one();
+        lookup.await.unwrap().unwrap();
+
+        release.notify_one();
+        cache.get_account(ADDRESS).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn caches_profiles_without_the_password_hash_until_they_change() {
+        let (horizon, _) = horizon().await;
+        let cache = AccountCacheService::with_store(horizon, Arc::new(MemoryStore::default()), Duration::from_secs(60));
+        let inner = Arc::new(InMemoryUserRepository::default());
+        let users = cache.users(inner.clone());
+        let user = User {
+            id: Uuid::new_v4(),
+            email: "ada@example.com".to_string(),
+            username: "ada".to_string(),
+            password_hash: "hash".to_string(),
+            is_verified: false,
+            stellar_public_key: None,
+            last_login_at: None,
+            login_count: 0,
+            role: Role::User,
+            disabled_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        users.create_user(&user).await.unwrap();
+
+        let profile = users.get_user_profile(&user.id).await.unwrap().unwrap();
+        assert_eq!((profile.role, profile.password_hash.as_str()), (Role::User, ""));
+        assert_eq!(users.get_user_by_id(&user.id).await.unwrap().unwrap().password_hash, "hash");
+
+        inner.update_user_role(&user.id, Role::Support, Utc::now()).await.unwrap();
+        assert_eq!(users.get_user_profile(&user.id).await.unwrap().unwrap().role, Role::User);
+
+        users.update_user_role(&user.id, Role::Admin, Utc::now()).await.unwrap();
+        assert_eq!(users.get_user_profile(&user.id).await.unwrap().unwrap().role, Role::Admin);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::password_change::PasswordUpdate;
+use crate::services::audit_service::AuditService;
+use crate::services::keystore_service::KeystoreService;
+use crate::stellar::sep5;
+use crate::utils::crypto::{EncryptedSecret, PasswordManager, RecoveryCode, SecretCipher};
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// What a recovery was able to bring back.
+pub struct AccountRecovery {
+    /// Keys derived from the recovery phrase, now encrypted with the new password.
+    pub restored_keys: usize,
+    /// Keystore keys that were only encrypted with the old password, e.g.
+    /// imported or rotated ones. They stay, but can't be unlocked any more.
+    pub lost_keys: Vec<String>,
+    pub codes_left: usize,
+}
+
+/// Sets a new password for a user who lost theirs, using one of the recovery
+/// codes issued with their recovery phrase. The code opens the phrase, and
+/// every key derived from it is encrypted again with the new password.
+pub struct AccountRecoveryService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+}
+
+impl AccountRecoveryService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Uses up `code` and replaces the user's password with `new_password`.
+    /// Every session of the user is ended.
+    pub async fn recover(&self, user_id: &Uuid, code: &str, new_password: &str) -> Result<AccountRecovery> {
+        Validator::validate_password(new_password)?;
+        let now = Utc::now();
+        let invalid = || AppError::AuthenticationError("Invalid recovery code".to_string());
+
+        let normalized = RecoveryCode::normalize(code);
+        let codes = self.db.get_unused_phrase_recovery_codes(user_id).await?;
+        let (used, phrase) = codes
+            .iter()
+            .find_map(|stored| {
+                let encrypted = EncryptedSecret {
+                    ciphertext: stored.encrypted_phrase.clone(),
+                    salt: stored.salt.clone(),
+                    nonce: stored.nonce.clone(),
+                };
+                SecretCipher::decrypt(&encrypted, &normalized).ok().map(|phrase| (stored, phrase))
+            })
+            .ok_or_else(invalid)?;
+        let phrase = std::str::from_utf8(&phrase)
+            .map(|phrase| Zeroizing::new(phrase.to_string()))
+            .map_err(|_| AppError::InternalError("Stored recovery phrase is corrupt".to_string()))?;
+
+        let stored = self
+            .db
+            .get_recovery_phrase(user_id)
+            .await?
+            .ok_or_else(|| AppError::ValidationError("No recovery phrase has been created yet".to_string()))?;
+        let recovery_phrase = KeystoreService::reencrypt_phrase(stored, &phrase, new_password)?;
+
+        let mut keystore_entries = Vec::new();
+        for account in self.db.get_derived_accounts_by_user(user_id).await? {
+            let keypair = sep5::derive_keypair(&phrase, account.account_index)?;
+            if let Some(entry) = self.db.get_keystore_entry(user_id, &keypair.public_key()).await? {
+                keystore_entries.push(KeystoreService::reencrypt(entry, &keypair, new_password)?);
+            }
+        }
+        let lost_keys: Vec<String> = self
+            .db
+            .get_keystore_entries_by_user(user_id)
+            .await?
+            .into_iter()
+            .map(|entry| entry.public_key)
+            .filter(|public_key| !keystore_entries.iter().any(|entry| entry.public_key == *public_key))
+            .collect();
+        let restored_keys = keystore_entries.len();
+
+        // The code is spent in the same transaction, so one that two
+        // recoveries race for only sets one password.
+        let update = PasswordUpdate {
+            user_id: *user_id,
+            password_hash: PasswordManager::hash_password(new_password)?,
+            keystore_entries,
+            recovery_phrase: Some(recovery_phrase),
+            recovery_code: Some(used.id),
+            keep_session: None,
+        };
+        if self.db.apply_password_update(&update, now).await?.is_none() {
+            return Err(invalid());
+        }
+
+        let codes_left = codes.len() - 1;
+        self.audit_service
+            .record(
+                Some(user_id),
+                AuditEvent::AccountRecovered,
+                &format!(
+                    "recovery code used, {} left; {} key(s) restored, {} unrecoverable",
+                    codes_left,
+                    restored_keys,
+                    lost_keys.len()
+                ),
+            )
+            .await?;
+
+        Ok(AccountRecovery {
+            restored_keys,
+            lost_keys,
+            codes_left,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::hd_wallet_service::HdWalletService;
+    use crate::stellar::keypair::Keypair;
+    use crate::stellar::network::Network;
+
+    #[tokio::test]
+    async fn a_recovery_code_sets_a_new_password_once() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let hd_wallet = HdWalletService::new(db.clone(), &Network::testnet());
+        let keystore = KeystoreService::new(db.clone());
+        let service = AccountRecoveryService::new(db.clone());
+
+        let (_, codes, account) = hd_wallet.create_recovery_phrase(&user, "forgotten").await.unwrap();
+        let imported = Keypair::random();
+        keystore.store_keypair(&user, &imported, "forgotten", None).await.unwrap();
+
+        assert!(service.recover(&user, "aaaaa-bbbbb-ccccc-ddddd", "Velvet-Otter-92!").await.is_err());
+        let recovery = service
+            .recover(&user, &codes[1].to_uppercase(), "Velvet-Otter-92!")
+            .await
+            .unwrap();
+        assert_eq!(recovery.restored_keys, 1);
+        assert_eq!(recovery.lost_keys, vec![imported.public_key()]);
+        assert_eq!(recovery.codes_left, codes.len() - 1);
+
+        assert!(keystore.unlock_keypair(&user, &account.public_key, "Velvet-Otter-92!").await.is_ok());
+        assert!(hd_wallet.derive_next_account(&user, "Velvet-Otter-92!").await.is_ok());
+        let stored = db.get_user_by_id(&user).await.unwrap().unwrap();
+        assert!(PasswordManager::verify_password("Velvet-Otter-92!", &stored.password_hash).unwrap());
+
+        assert!(service.recover(&user, &codes[1], "Another-Otter-93!").await.is_err());
+    }
+}
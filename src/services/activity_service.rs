@@ -0,0 +1,37 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::activity::ActivitySummary;
+use chrono::{Duration, Utc};
+
+/// Counterparties listed in a summary.
+pub const TOP_COUNTERPARTIES: u32 = 5;
+
+/// Usage analytics for one account from its local history. Nothing is fetched
+/// from Horizon, so the numbers cover what the wallet has recorded or backfilled.
+pub struct ActivityService {
+    db: SqliteDatabase,
+}
+
+impl ActivityService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Activity in `asset_code` over the last `days` days.
+    pub async fn summary(&self, address: &str, asset_code: &str, days: i64) -> Result<ActivitySummary> {
+        if days <= 0 {
+            return Err(AppError::ValidationError("The period must be at least one day".to_string()));
+        }
+
+        let since = Utc::now() - Duration::days(days);
+        self.db
+            .get_activity_summary(address, &asset_code.to_uppercase(), since, TOP_COUNTERPARTIES)
+            .await
+    }
+
+    /// The summary as JSON, for frontends that draw their own charts.
+    pub fn to_json(summary: &ActivitySummary) -> Result<String> {
+        serde_json::to_string_pretty(summary)
+            .map_err(|e| AppError::InternalError(format!("Failed to encode activity: {}", e)))
+    }
+}
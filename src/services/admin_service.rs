@@ -0,0 +1,195 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::role::{Permission, Role};
+use crate::models::user::{CreateUserRequest, UserResponse};
+use crate::services::audit_service::AuditService;
+use crate::services::user_service::UserService;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Most accounts [`AdminService::create_users`] takes at once; each password
+/// is hashed in turn, so a bigger batch would hold the request for long.
+pub const MAX_BATCH_SIGNUPS: usize = 100;
+
+/// Checks administrative actions against the acting user's role and carries
+/// out the user management ones. The role is looked up again on every check,
+/// so a demotion applies to sessions that are already open.
+pub struct AdminService {
+    db: SqliteDatabase,
+    user_service: UserService,
+    audit_service: AuditService,
+}
+
+impl AdminService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            user_service: UserService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Fails, and records the attempt, unless `actor` may take `permission`.
+    pub async fn authorize(&self, actor: &Uuid, permission: Permission) -> Result<()> {
+        let role = self
+            .user_service
+            .get_user(actor)
+            .await?
+            .filter(|user| user.disabled_at.is_none())
+            .map(|user| user.role);
+        if role.is_some_and(|role| role.allows(permission)) {
+            return Ok(());
+        }
+
+        let role = role.map_or("disabled account", |role| role.as_str());
+        self.audit_service
+            .record(Some(actor), AuditEvent::AccessDenied, &format!("{} as {}", permission.as_str(), role))
+            .await?;
+        Err(AppError::AuthenticationError(format!("Your account isn't allowed to {}", permission.describe())))
+    }
+
+    pub async fn users(&self, actor: &Uuid) -> Result<Vec<UserResponse>> {
+        self.authorize(actor, Permission::ViewUsers).await?;
+        self.user_service.list_users().await
+    }
+
+    /// Creates an account for each request, saving the valid ones together,
+    /// and records who provisioned them. See [`UserService::create_users`].
+    pub async fn create_users(&self, actor: &Uuid, requests: Vec<CreateUserRequest>) -> Result<Vec<Result<UserResponse>>> {
+        self.authorize(actor, Permission::ManageUsers).await?;
+        if requests.is_empty() || requests.len() > MAX_BATCH_SIGNUPS {
+            return Err(AppError::ValidationError(format!(
+                "Send between 1 and {} accounts at a time",
+                MAX_BATCH_SIGNUPS
+            )));
+        }
+
+        let results = self.user_service.create_users(requests).await?;
+        for user in results.iter().flatten() {
+            let details = format!("username '{}', provisioned by {}", user.username, actor);
+            self.audit_service.record(Some(&user.id), AuditEvent::AccountCreated, &details).await?;
+        }
+        Ok(results)
+    }
+
+    pub async fn set_role(&self, actor: &Uuid, target: &Uuid, role: Role) -> Result<UserResponse> {
+        self.authorize(actor, Permission::ManageUsers).await?;
+        // Otherwise the last admin could demote themselves and lock everyone out.
+        if actor == target {
+            return Err(AppError::ValidationError("You can't change your own role".to_string()));
+        }
+
+        let before = self.target(target).await?.role;
+        let user = self.user_service.set_role(target, role).await?;
+        let details = format!("{} -> {} by {}", before.as_str(), role.as_str(), actor);
+        self.audit_service.record(Some(target), AuditEvent::RoleChanged, &details).await?;
+        Ok(user)
+    }
+
+    /// Disables the account and ends its sessions, or enables it again.
+    pub async fn set_disabled(&self, actor: &Uuid, target: &Uuid, disabled: bool) -> Result<UserResponse> {
+        self.authorize(actor, Permission::ManageUsers).await?;
+        if actor == target {
+            return Err(AppError::ValidationError("You can't disable or enable your own account".to_string()));
+        }
+
+        self.target(target).await?;
+        let user = self.user_service.set_disabled(target, disabled).await?;
+        if disabled {
+            let ended = self.db.revoke_user_sessions(target, Utc::now()).await?;
+            let details = format!("by {}, {} session(s) ended", actor, ended);
+            self.audit_service.record(Some(target), AuditEvent::AccountDisabled, &details).await?;
+        } else {
+            self.audit_service.record(Some(target), AuditEvent::AccountEnabled, &format!("by {}", actor)).await?;
+        }
+        Ok(user)
+    }
+
+    async fn target(&self, user_id: &Uuid) -> Result<UserResponse> {
+        self.user_service
+            .get_user(user_id)
+            .await?
+            .ok_or_else(|| AppError::ValidationError("User not found".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::audit::AuditFilter;
+    use crate::services::session_service::SessionService;
+
+    #[tokio::test]
+    async fn only_admins_manage_users_and_support_can_look() {
+        let db = SqliteDatabase::in_memory().await;
+        let service = AdminService::new(db.clone());
+        let (admin, support, user) = (db.insert_test_user().await, db.insert_test_user().await, db.insert_test_user().await);
+        db.update_user_role(&admin, Role::Admin, Utc::now()).await.unwrap();
+        db.update_user_role(&support, Role::Support, Utc::now()).await.unwrap();
+
+        assert_eq!(service.users(&support).await.unwrap().len(), 3);
+        assert!(service.users(&user).await.is_err());
+        assert!(service.set_role(&support, &user, Role::Admin).await.is_err());
+        assert!(service.set_role(&admin, &admin, Role::User).await.is_err());
+
+        service.set_role(&admin, &support, Role::User).await.unwrap();
+        assert!(service.users(&support).await.is_err());
+        let entries = db.get_audit_entries(&AuditFilter { limit: 100, ..AuditFilter::default() }).await.unwrap();
+        assert_eq!(entries.iter().filter(|entry| entry.event_type == "access_denied").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn provisions_a_batch_with_a_result_for_each_account() {
+        let db = SqliteDatabase::in_memory().await;
+        let service = AdminService::new(db.clone());
+        let (admin, support) = (db.insert_test_user().await, db.insert_test_user().await);
+        db.update_user_role(&admin, Role::Admin, Utc::now()).await.unwrap();
+        db.update_user_role(&support, Role::Support, Utc::now()).await.unwrap();
+        let request = |email: &str, username: &str| CreateUserRequest {
+            email: email.to_string(),
+            username: username.to_string(),
+            password: "Velvet-Otter-92!".to_string().into(),
+        };
+        let batch = || {
+            vec![
+                request("otter@example.com", "otter"),
+                request("not-an-email", "heron"),
+                request("kingfisher@example.com", "otter"),
+                request("kingfisher@example.com", "kingfisher"),
+            ]
+        };
+
+        assert!(service.create_users(&support, batch()).await.is_err());
+        assert!(service.create_users(&admin, Vec::new()).await.is_err());
+
+        let results = service.create_users(&admin, batch()).await.unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().username, "otter");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap_err().message(), "Username already exists");
+        assert_eq!(results[3].as_ref().unwrap().username, "kingfisher");
+        assert_eq!(db.get_user_count().await.unwrap(), 4);
+
+        let entries = db.get_audit_entries(&AuditFilter { limit: 100, ..AuditFilter::default() }).await.unwrap();
+        assert_eq!(entries.iter().filter(|entry| entry.event_type == "account_created").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn disabling_an_account_ends_its_sessions() {
+        let db = SqliteDatabase::in_memory().await;
+        let service = AdminService::new(db.clone());
+        let sessions = SessionService::new(db.clone());
+        let (admin, user) = (db.insert_test_user().await, db.insert_test_user().await);
+        db.update_user_role(&admin, Role::Admin, Utc::now()).await.unwrap();
+        let session = sessions.start(&user, "CLI on laptop").await.unwrap();
+
+        service.set_disabled(&admin, &user, true).await.unwrap();
+        assert!(sessions.validate(&session.id).await.is_err());
+
+        db.update_user_role(&user, Role::Admin, Utc::now()).await.unwrap();
+        assert!(service.authorize(&user, Permission::ViewUsers).await.is_err());
+        assert!(service.set_disabled(&admin, &user, false).await.unwrap().disabled_at.is_none());
+        service.authorize(&user, Permission::ViewUsers).await.unwrap();
+    }
+}
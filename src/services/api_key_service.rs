@@ -0,0 +1,145 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::{ApiKey, ApiScope, API_KEY_PREFIX};
+use crate::models::audit::AuditEvent;
+use crate::services::audit_service::AuditService;
+use chrono::{Duration, Utc};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// How long a key lasts unless the user picks otherwise.
+pub const DEFAULT_API_KEY_DAYS: i64 = 90;
+
+const MAX_NAME_LEN: usize = 50;
+
+/// Characters of the key kept in the clear, including [`API_KEY_PREFIX`].
+const SHOWN_PREFIX_LEN: usize = 12;
+
+/// Named keys for server-to-server integrations, as an alternative to logging
+/// in. Each is limited to some scopes and may expire; only its hash is kept,
+/// so a lost key can't be shown again, only replaced.
+pub struct ApiKeyService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+}
+
+impl ApiKeyService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Generates a key for `user_id`. Returns it with the saved record; this
+    /// is the only time the key itself is available.
+    pub async fn create(&self, user_id: &Uuid, name: &str, scopes: &[ApiScope], expires_in: Option<Duration>) -> Result<(ApiKey, String)> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(AppError::ValidationError(format!("Key names must be 1 to {} characters", MAX_NAME_LEN)));
+        }
+        if self.keys(user_id).await?.iter().any(|key| key.name.eq_ignore_ascii_case(name)) {
+            return Err(AppError::ValidationError(format!("You already have a key named '{}'", name)));
+        }
+        let scopes = ApiScope::ALL.into_iter().filter(|scope| scopes.contains(scope)).collect::<Vec<_>>();
+        if scopes.is_empty() {
+            return Err(AppError::ValidationError("Choose at least one scope".to_string()));
+        }
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = format!("{}{}", API_KEY_PREFIX, hex::encode(bytes));
+        let now = Utc::now();
+        let key = ApiKey {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            name: name.to_string(),
+            prefix: token[..SHOWN_PREFIX_LEN].to_string(),
+            key_hash: hash_api_key(&token),
+            scopes,
+            expires_at: expires_in.map(|expires_in| now + expires_in),
+            last_used_at: None,
+            created_at: now,
+            revoked_at: None,
+        };
+
+        self.db.create_api_key(&key).await?;
+        self.audit_service
+            .record(Some(user_id), AuditEvent::ApiKeyCreated, &format!("'{}' ({})", key.name, key.prefix))
+            .await?;
+        Ok((key, token))
+    }
+
+    /// The user's unrevoked keys, newest first, including expired ones.
+    pub async fn keys(&self, user_id: &Uuid) -> Result<Vec<ApiKey>> {
+        self.db.get_api_keys_by_user(user_id).await
+    }
+
+    pub async fn revoke(&self, user_id: &Uuid, key: &ApiKey) -> Result<()> {
+        if !self.db.revoke_api_key(user_id, &key.id, Utc::now()).await? {
+            return Err(AppError::ValidationError("API key not found or already revoked".to_string()));
+        }
+        self.audit_service
+            .record(Some(user_id), AuditEvent::ApiKeyRevoked, &format!("'{}' ({})", key.name, key.prefix))
+            .await
+    }
+
+    /// The key `token` is, if it is active, grants `scope` and belongs to an
+    /// account that isn't disabled. Records the use.
+    pub async fn authenticate(&self, token: &str, scope: ApiScope) -> Result<ApiKey> {
+        let now = Utc::now();
+        let invalid = || AppError::AuthenticationError("Invalid or expired API key".to_string());
+        let key = self
+            .db
+            .get_api_key_by_hash(&hash_api_key(token.trim()))
+            .await?
+            .filter(|key| key.is_active(now))
+            .ok_or_else(invalid)?;
+        let owner = self.db.get_user_by_id(&key.user_id).await?;
+        if owner.is_none_or(|owner| owner.disabled_at.is_some()) {
+            return Err(invalid());
+        }
+        if !key.scopes.contains(&scope) {
+            return Err(AppError::AuthenticationError(format!("This API key doesn't have the '{}' scope", scope.as_str())));
+        }
+
+        self.db.touch_api_key(&key.id, now).await?;
+        Ok(ApiKey {
+            last_used_at: Some(now),
+            ..key
+        })
+    }
+}
+
+fn hash_api_key(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn keys_work_for_their_scopes_until_revoked_or_expired() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = ApiKeyService::new(db.clone());
+
+        let (key, token) = service.create(&user, "Accounting", &[ApiScope::Read], None).await.unwrap();
+        assert!(token.starts_with(&key.prefix) && key.prefix.starts_with(API_KEY_PREFIX));
+        assert!(service.create(&user, "accounting", &[ApiScope::Read], None).await.is_err());
+
+        assert_eq!(service.authenticate(&token, ApiScope::Read).await.unwrap().id, key.id);
+        assert!(service.authenticate(&token, ApiScope::Payments).await.is_err());
+        assert!(service.authenticate("swk_wrong", ApiScope::Read).await.is_err());
+        assert!(service.keys(&user).await.unwrap()[0].last_used_at.is_some());
+
+        service.revoke(&user, &key).await.unwrap();
+        assert!(service.authenticate(&token, ApiScope::Read).await.is_err());
+        assert!(service.keys(&user).await.unwrap().is_empty());
+
+        let (_, expired) = service.create(&user, "Old", &[ApiScope::Read], Some(Duration::seconds(-1))).await.unwrap();
+        assert!(service.authenticate(&expired, ApiScope::Read).await.is_err());
+    }
+}
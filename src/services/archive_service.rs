@@ -0,0 +1,212 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::archive::{ArchiveReport, ArchivedTransaction, TransactionRollup};
+use crate::models::transaction::{TransactionStatus, WalletTransaction};
+use chrono::{DateTime, Datelike, Months, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Where archive files go when `ARCHIVE_DIR` is unset.
+pub const DEFAULT_ARCHIVE_DIR: &str = "archives";
+
+/// Moves old transaction records out of the database. Each run writes the
+/// records to a gzipped JSON Lines file and keeps only monthly totals per
+/// asset and direction, so long-lived accounts don't slow history queries.
+pub struct ArchiveService {
+    db: SqliteDatabase,
+    dir: String,
+}
+
+impl ArchiveService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            db,
+            dir: config::var("ARCHIVE_DIR").unwrap_or_else(|| DEFAULT_ARCHIVE_DIR.to_string()),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_dir(db: SqliteDatabase, dir: &str) -> Self {
+        Self { db, dir: dir.to_string() }
+    }
+
+    /// Archives transactions from before the start of the month `months` ago,
+    /// so only whole months are rolled up. The file is written and synced
+    /// before anything is deleted.
+    pub async fn archive(&self, months: u32, now: DateTime<Utc>) -> Result<ArchiveReport> {
+        let cutoff = cutoff(months, now)?;
+        let archived = self.db.get_transactions_before(cutoff).await?;
+        if archived.is_empty() {
+            return Ok(ArchiveReport { cutoff, archived: 0, rollups: 0, path: None });
+        }
+
+        let path = Path::new(&self.dir).join(format!("transactions-{}.jsonl.gz", now.format("%Y%m%dT%H%M%SZ")));
+        let path = path.to_string_lossy().to_string();
+        self.write_archive(&path, &archived).await?;
+
+        let rollups = rollups(&archived);
+        self.db.replace_with_rollups(&archived, &rollups, cutoff).await?;
+
+        Ok(ArchiveReport {
+            cutoff,
+            archived: archived.len(),
+            rollups: rollups.len(),
+            path: Some(path),
+        })
+    }
+
+    /// `account`'s monthly totals for archived months, newest first.
+    pub async fn rollups(&self, account: &str) -> Result<Vec<TransactionRollup>> {
+        self.db.get_transaction_rollups(account).await
+    }
+
+    async fn write_archive(&self, path: &str, archived: &[WalletTransaction]) -> Result<()> {
+        let write_error = |e: std::io::Error| AppError::InternalError(format!("Can't write {}: {}", path, e));
+        fs::create_dir_all(&self.dir).map_err(write_error)?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut encoder = GzEncoder::new(options.open(path).map_err(write_error)?, Compression::default());
+
+        let mut tags: HashMap<&str, HashMap<String, Vec<String>>> = HashMap::new();
+        for transaction in archived {
+            if !tags.contains_key(transaction.account.as_str()) {
+                tags.insert(&transaction.account, self.db.get_transaction_tags(&transaction.account).await?);
+            }
+            let line = ArchivedTransaction {
+                account: transaction.account.clone(),
+                transaction: transaction.into(),
+                tags: tags[transaction.account.as_str()].get(&transaction.hash).cloned().unwrap_or_default(),
+            };
+            serde_json::to_writer(&mut encoder, &line)
+                .map_err(|e| AppError::InternalError(format!("Failed to encode archived transaction: {}", e)))?;
+            encoder.write_all(b"\n").map_err(write_error)?;
+        }
+
+        encoder.finish().and_then(|file| file.sync_all()).map_err(write_error)
+    }
+}
+
+/// Midnight UTC on the first day of the month `months` before `now`'s.
+fn cutoff(months: u32, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if months == 0 {
+        return Err(AppError::ValidationError("Keep at least 1 month of history".to_string()));
+    }
+
+    now.date_naive()
+        .with_day(1)
+        .and_then(|first| first.checked_sub_months(Months::new(months)))
+        .and_then(|first| first.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+        .ok_or_else(|| AppError::ValidationError(format!("Can't go back {} months", months)))
+}
+
+/// Monthly totals of the confirmed transactions; pending and failed ones
+/// moved no funds and are only kept in the file.
+fn rollups(archived: &[WalletTransaction]) -> Vec<TransactionRollup> {
+    let mut totals: BTreeMap<(&str, String, &str, &str), TransactionRollup> = BTreeMap::new();
+
+    for transaction in archived.iter().filter(|transaction| transaction.status == TransactionStatus::Confirmed) {
+        let month = transaction.created_at.format("%Y-%m").to_string();
+        let key = (transaction.account.as_str(), month.clone(), transaction.asset_code.as_str(), transaction.direction.as_str());
+        let rollup = totals.entry(key).or_insert_with(|| TransactionRollup {
+            account: transaction.account.clone(),
+            month,
+            asset_code: transaction.asset_code.clone(),
+            direction: transaction.direction,
+            payment_count: 0,
+            total_stroops: 0,
+        });
+        rollup.payment_count += 1;
+        rollup.total_stroops += transaction.amount_stroops;
+    }
+
+    totals.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::TransactionDirection;
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use uuid::Uuid;
+
+    fn transaction(account: &str, hash: &str, at: DateTime<Utc>, stroops: i64, status: TransactionStatus) -> WalletTransaction {
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: account.to_string(),
+            hash: hash.to_string(),
+            operation_index: 1,
+            direction: TransactionDirection::Incoming,
+            asset_code: "XLM".to_string(),
+            amount_stroops: stroops,
+            counterparty: "GOTHER".to_string(),
+            memo: None,
+            status,
+            ledger: None,
+            error: None,
+            request_id: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn cutoff_is_the_start_of_a_whole_month() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 31, 18, 0, 0).unwrap();
+
+        assert_eq!(cutoff(1, now).unwrap(), Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap());
+        assert_eq!(cutoff(12, now).unwrap(), Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+        assert!(cutoff(0, now).is_err());
+    }
+
+    #[tokio::test]
+    async fn archives_old_records_to_a_file_and_keeps_monthly_totals() {
+        let db = SqliteDatabase::in_memory().await;
+        let dir = std::env::temp_dir().join(format!("wallet-archive-{}", Uuid::new_v4()));
+        let service = ArchiveService::with_dir(db.clone(), &dir.to_string_lossy());
+        let now = Utc.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap();
+        let at = |month, day| Utc.with_ymd_and_hms(2025, month, day, 9, 0, 0).unwrap();
+
+        for tx in [
+            transaction("GA", "aa", at(1, 3), 10_000_000, TransactionStatus::Confirmed),
+            transaction("GA", "bb", at(1, 20), 5_000_000, TransactionStatus::Confirmed),
+            transaction("GA", "cc", at(1, 21), 7_000_000, TransactionStatus::Failed),
+            transaction("GA", "dd", at(5, 2), 1_000_000, TransactionStatus::Confirmed),
+        ] {
+            db.upsert_transaction(&tx).await.unwrap();
+        }
+        db.add_transaction_tag("GA", "aa", "rent").await.unwrap();
+
+        let report = service.archive(2, now).await.unwrap();
+        assert_eq!((report.archived, report.rollups), (3, 1));
+        assert_eq!(db.get_transactions("GA", 10).await.unwrap().len(), 1);
+        assert!(db.get_transaction_tags("GA").await.unwrap().is_empty());
+        assert_eq!(db.get_archive_cutoff("GA").await.unwrap(), Some(report.cutoff));
+
+        let rollups = service.rollups("GA").await.unwrap();
+        assert_eq!((rollups[0].month.as_str(), rollups[0].payment_count, rollups[0].total_stroops), ("2025-01", 2, 15_000_000));
+
+        let mut text = String::new();
+        GzDecoder::new(fs::File::open(report.path.unwrap()).unwrap()).read_to_string(&mut text).unwrap();
+        let lines: Vec<ArchivedTransaction> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].tags, ["rent"]);
+        assert_eq!(lines[2].transaction.status, "failed");
+
+        assert_eq!(service.archive(2, now).await.unwrap().path, None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
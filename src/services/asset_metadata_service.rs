@@ -0,0 +1,89 @@
+use crate::errors::Result;
+use crate::models::asset_metadata::AssetMetadata;
+use crate::stellar::horizon::HorizonClient;
+use crate::stellar::network::Network;
+use crate::stellar::stellar_toml::StellarToml;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a fetched (or missing) stellar.toml is reused before asking again.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct CachedToml {
+    home_domain: Option<String>,
+    toml: Option<StellarToml>,
+    fetched_at: Instant,
+}
+
+/// Resolves asset names and anchor endpoints from issuers' stellar.toml files,
+/// caching one lookup per issuer.
+pub struct AssetMetadataService {
+    horizon: HorizonClient,
+    http: reqwest::Client,
+    cache: Mutex<HashMap<String, CachedToml>>,
+}
+
+impl AssetMetadataService {
+    pub fn new(network: &Network) -> Self {
+        Self {
+            horizon: HorizonClient::new(&network.horizon_url),
+            http: reqwest::Client::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Metadata for `code` issued by `issuer`, or `None` if the issuer has no
+    /// home domain or its stellar.toml doesn't list the asset.
+    pub async fn resolve(&self, code: &str, issuer: &str) -> Result<Option<AssetMetadata>> {
+        let mut cache = self.cache.lock().await;
+
+        let stale = cache
+            .get(issuer)
+            .map(|entry| entry.fetched_at.elapsed() > CACHE_TTL)
+            .unwrap_or(true);
+
+        if stale {
+            let entry = self.fetch(issuer).await?;
+            cache.insert(issuer.to_string(), entry);
+        }
+
+        let entry = &cache[issuer];
+        let (Some(home_domain), Some(toml)) = (&entry.home_domain, &entry.toml) else {
+            return Ok(None);
+        };
+
+        Ok(toml.currency(code, issuer).map(|currency| AssetMetadata {
+            code: code.to_string(),
+            home_domain: home_domain.clone(),
+            name: currency.name.clone(),
+            image: currency.image.clone(),
+            org_name: toml.org_name().map(str::to_string),
+            transfer_server: toml.transfer_server.clone(),
+            transfer_server_sep0024: toml.transfer_server_sep0024.clone(),
+            web_auth_endpoint: toml.web_auth_endpoint.clone(),
+        }))
+    }
+
+    async fn fetch(&self, issuer: &str) -> Result<CachedToml> {
+        let home_domain = self
+            .horizon
+            .get_account(issuer)
+            .await?
+            .and_then(|account| account.home_domain)
+            .filter(|domain| !domain.is_empty());
+
+        // An unreachable or malformed stellar.toml just means the asset can't be
+        // verified; cache that rather than failing the whole screen.
+        let toml = match &home_domain {
+            Some(domain) => StellarToml::fetch(&self.http, domain).await.ok(),
+            None => None,
+        };
+
+        Ok(CachedToml {
+            home_domain,
+            toml,
+            fetched_at: Instant::now(),
+        })
+    }
+}
@@ -0,0 +1,96 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::audit::{AuditEntry, AuditEvent, AuditFilter};
+use crate::models::security_event::SecurityEvent;
+use crate::services::security_event_sink::SecurityEventSink;
+use crate::stellar::horizon::SubmitTransactionResponse;
+use crate::utils::request_id;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Writes security-relevant events to the append-only audit log.
+#[derive(Clone)]
+pub struct AuditService {
+    db: SqliteDatabase,
+}
+
+impl AuditService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Saves the entry, with the current API request's id, and mirrors it to
+    /// the SIEM sink, if one is configured.
+    pub async fn record(&self, user_id: Option<&Uuid>, event: AuditEvent, details: &str) -> Result<()> {
+        let entry = AuditEntry {
+            id: Uuid::new_v4(),
+            user_id: user_id.copied(),
+            event_type: event.as_str().to_string(),
+            details: details.to_string(),
+            request_id: request_id::current(),
+            created_at: Utc::now(),
+        };
+
+        self.db.create_audit_entry(&entry).await?;
+        SecurityEventSink::emit(&SecurityEvent::from_audit(&entry, event));
+        Ok(())
+    }
+
+    /// Records whether a payment the user signed went through.
+    pub async fn record_payment(
+        &self,
+        user_id: &Uuid,
+        destination: &str,
+        amount: &str,
+        outcome: &Result<SubmitTransactionResponse>,
+    ) -> Result<()> {
+        match outcome {
+            Ok(response) => {
+                let details = format!("{} XLM to {} in {}", amount, destination, response.hash);
+                self.record(Some(user_id), AuditEvent::PaymentSent, &details).await
+            }
+            Err(e) => {
+                let details = format!("{} XLM to {}: {}", amount, destination, e);
+                self.record(Some(user_id), AuditEvent::PaymentFailed, &details).await
+            }
+        }
+    }
+
+    pub async fn entries(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        self.db.get_audit_entries(filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn filters_by_user_and_time_range() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let bob = db.insert_test_user().await;
+        let audit = AuditService::new(db);
+
+        audit.record(Some(&alice), AuditEvent::LoginSucceeded, "CLI").await.unwrap();
+        audit.record(Some(&bob), AuditEvent::LoginFailed, "bad password").await.unwrap();
+        audit.record(None, AuditEvent::LoginFailed, "unknown user 'eve'").await.unwrap();
+
+        let everything = AuditFilter { limit: 10, ..AuditFilter::default() };
+        assert_eq!(audit.entries(&everything).await.unwrap().len(), 3);
+
+        let bobs = audit
+            .entries(&AuditFilter { user_id: Some(bob), ..everything.clone() })
+            .await
+            .unwrap();
+        assert_eq!(bobs.len(), 1);
+        assert_eq!(bobs[0].event_type, "login_failed");
+
+        let future = AuditFilter { since: Some(Utc::now() + Duration::hours(1)), ..everything.clone() };
+        assert!(audit.entries(&future).await.unwrap().is_empty());
+
+        let past = AuditFilter { until: Some(Utc::now() - Duration::hours(1)), ..everything };
+        assert!(audit.entries(&past).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use sha1::{Digest, Sha1};
+use std::time::Duration;
+
+pub const DEFAULT_BREACH_API_URL: &str = "https://api.pwnedpasswords.com";
+
+/// Long enough for a slow link, short enough not to stall sign-up when offline.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What to do with a password that appears in a known breach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreachPolicy {
+    /// Tell the user and let them keep it if they insist.
+    Warn,
+    Reject,
+}
+
+/// Looks passwords up in the Have I Been Pwned range API
+/// (`PASSWORD_BREACH_API_URL`). Only the first five hex characters of the
+/// password's SHA-1 leave the machine; the match happens locally.
+pub struct BreachCheckService {
+    base_url: String,
+    http: reqwest::Client,
+    policy: BreachPolicy,
+}
+
+impl BreachCheckService {
+    /// Set up from `PASSWORD_BREACH_CHECK` (`warn` or `reject`); `None` when
+    /// it is unset or `off`.
+    pub fn from_env() -> Result<Option<Self>> {
+        let policy = match config::var("PASSWORD_BREACH_CHECK").unwrap_or_default().to_lowercase().as_str() {
+            "" | "off" => return Ok(None),
+            "warn" => BreachPolicy::Warn,
+            "reject" => BreachPolicy::Reject,
+            other => {
+                return Err(AppError::ValidationError(format!(
+                    "Unknown PASSWORD_BREACH_CHECK '{}', expected off, warn or reject",
+                    other
+                )))
+            }
+        };
+        let base_url = config::var("PASSWORD_BREACH_API_URL").unwrap_or_else(|| DEFAULT_BREACH_API_URL.to_string());
+
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Some(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+            policy,
+        }))
+    }
+
+    pub fn policy(&self) -> BreachPolicy {
+        self.policy
+    }
+
+    /// How many times `password` has been seen in breaches; 0 if never.
+    pub async fn times_breached(&self, password: &str) -> Result<u64> {
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(5);
+
+        let response = self
+            .http
+            .get(format!("{}/range/{}", self.base_url, prefix))
+            // Pads the reply so its size doesn't hint at the prefix.
+            .header("Add-Padding", "true")
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Breach check failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::InternalError(format!("Breach check returned HTTP {}", response.status())));
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Breach check failed: {}", e)))?;
+        Ok(count_in_range(&body, suffix))
+    }
+}
+
+/// The count on the `SUFFIX:COUNT` line matching `suffix`. Padding lines have
+/// a count of 0.
+fn count_in_range(body: &str, suffix: &str) -> u64 {
+    body.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_matching_suffix_in_a_range() {
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        let body = "003D68EB55068C33ACE09247EE4C639306B:3\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD9:0";
+
+        assert_eq!(count_in_range(body, "1E4C9B93F3F0682250B6CF8331B7EE68FD8"), 9659365);
+        assert_eq!(count_in_range(body, "1E4C9B93F3F0682250B6CF8331B7EE68FD9"), 0);
+        assert_eq!(count_in_range(body, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"), 0);
+    }
+}
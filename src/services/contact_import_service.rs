@@ -0,0 +1,272 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::contact::{Contact, CreateContactRequest};
+use crate::models::contact_import::{ContactField, DuplicateContact, FieldMapping, ImportFormat, ImportPlan, ImportTable};
+use crate::services::contact_service::ContactService;
+use crate::utils::validation::Validator;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Brings contacts in from another wallet's CSV or vCard export. Building a
+/// plan writes nothing; only [`ContactImportService::import`] does.
+pub struct ContactImportService {
+    contact_service: ContactService,
+}
+
+impl ContactImportService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            contact_service: ContactService::new(db),
+        }
+    }
+
+    pub fn parse(format: ImportFormat, text: &str) -> Result<ImportTable> {
+        match format {
+            ImportFormat::Csv => parse_csv(text),
+            ImportFormat::VCard => parse_vcard(text),
+        }
+    }
+
+    /// Sorts every row into contacts to add, duplicates and rows that can't be
+    /// imported. A row is a duplicate when its address is already saved, under
+    /// any name, or appeared earlier in the file.
+    pub async fn plan(&self, user_id: &Uuid, table: &ImportTable, mapping: &FieldMapping) -> Result<ImportPlan> {
+        let mut known: HashMap<String, String> = self
+            .contact_service
+            .list_contacts(user_id)
+            .await?
+            .into_iter()
+            .map(|contact| (contact.address, contact.name))
+            .collect();
+        let mut plan = ImportPlan::default();
+
+        for (index, row) in table.rows.iter().enumerate() {
+            let optional = |column: Option<usize>| {
+                column.map(|column| table.cell(row, column).to_string()).filter(|value| !value.is_empty())
+            };
+            let request = CreateContactRequest {
+                name: table.cell(row, mapping.name).to_string(),
+                address: table.cell(row, mapping.address).to_uppercase(),
+                memo: optional(mapping.memo),
+                federation_name: optional(mapping.federation_name),
+            };
+
+            if let Err(e) = validate(&request) {
+                plan.invalid.push((index + 1, e.to_string()));
+                continue;
+            }
+
+            if let Some(existing_name) = known.get(&request.address) {
+                plan.duplicates.push(DuplicateContact {
+                    name: request.name,
+                    address: request.address,
+                    existing_name: existing_name.clone(),
+                });
+                continue;
+            }
+
+            known.insert(request.address.clone(), request.name.clone());
+            plan.to_add.push(request);
+        }
+
+        Ok(plan)
+    }
+
+    /// Saves the contacts a plan would add.
+    pub async fn import(&self, user_id: &Uuid, plan: ImportPlan) -> Result<Vec<Contact>> {
+        let mut added = Vec::with_capacity(plan.to_add.len());
+        for request in plan.to_add {
+            added.push(self.contact_service.add_contact(user_id, request).await?);
+        }
+        Ok(added)
+    }
+}
+
+fn validate(request: &CreateContactRequest) -> Result<()> {
+    Validator::validate_contact_name(&request.name)?;
+    Validator::validate_stellar_address(&request.address)?;
+    if let Some(memo) = &request.memo {
+        Validator::validate_memo(memo)?;
+    }
+    if let Some(federation_name) = &request.federation_name {
+        Validator::validate_federation_name(federation_name)?;
+    }
+    Ok(())
+}
+
+/// The column most likely to hold `field`, by header name. The address column
+/// is found by its contents first, since wallets label it in many ways.
+pub fn guess_column(table: &ImportTable, field: ContactField) -> Option<usize> {
+    let headers: Vec<String> = table.columns.iter().map(|column| column.to_lowercase()).collect();
+    let named = |words: &[&str]| headers.iter().position(|header| words.iter().any(|word| header.contains(word)));
+
+    match field {
+        ContactField::Name => headers
+            .iter()
+            .position(|header| header == "fn" || header == "name")
+            .or_else(|| headers.iter().position(|header| header.contains("name") && !header.contains("federation"))),
+        ContactField::Address => (0..table.columns.len())
+            .find(|&column| {
+                table
+                    .rows
+                    .iter()
+                    .any(|row| Validator::validate_stellar_address(&table.cell(row, column).to_uppercase()).is_ok())
+            })
+            .or_else(|| named(&["address", "stellar", "account", "public"])),
+        ContactField::Memo => named(&["memo", "note"]),
+        ContactField::FederationName => named(&["federation"]),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<ImportTable> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let invalid = |e: csv::Error| AppError::ValidationError(format!("Invalid CSV: {}", e));
+
+    let columns = reader.headers().map_err(invalid)?.iter().map(str::to_string).collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+
+    Ok(ImportTable { columns, rows })
+}
+
+/// Reads vCard 3.0 and 4.0 cards. Parameters and group prefixes are dropped
+/// (`item1.X-STELLAR;TYPE=work` becomes `X-STELLAR`) and only the first value
+/// of a repeated property is kept.
+fn parse_vcard(text: &str) -> Result<ImportTable> {
+    // Lines starting with a space or tab continue the previous one.
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut table = ImportTable::default();
+    let mut card: Option<HashMap<usize, String>> = None;
+
+    for line in lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(AppError::ValidationError(format!("Invalid vCard line '{}'", line)));
+        };
+        let property = key.split(';').next().unwrap_or(key);
+        let property = property.rsplit('.').next().unwrap_or(property).to_uppercase();
+
+        match (property.as_str(), card.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VCARD") => card = Some(HashMap::new()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                let values = card.take().unwrap_or_default();
+                let mut row = vec![String::new(); table.columns.len()];
+                for (column, value) in values {
+                    row[column] = value;
+                }
+                table.rows.push(row);
+            }
+            ("BEGIN" | "END", _) => return Err(AppError::ValidationError("Cards in the vCard file are not properly nested".to_string())),
+            (_, Some(values)) => {
+                let column = match table.columns.iter().position(|column| *column == property) {
+                    Some(column) => column,
+                    None => {
+                        table.columns.push(property);
+                        table.columns.len() - 1
+                    }
+                };
+                values.entry(column).or_insert_with(|| unescape(value).trim().to_string());
+            }
+            (_, None) => return Err(AppError::ValidationError(format!("'{}' is outside a BEGIN:VCARD block", line))),
+        }
+    }
+
+    if card.is_some() {
+        return Err(AppError::ValidationError("The last card has no END:VCARD".to_string()));
+    }
+    Ok(table)
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(escaped) => out.push(escaped),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> String {
+        stellar_strkey::ed25519::PublicKey([byte; 32]).to_string()
+    }
+
+    #[test]
+    fn reads_folded_vcards_and_guesses_columns() {
+        let text = format!(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Bob\r\nitem1.X-STELLAR;TYPE=work:{}\r\nNOTE:rent\\, monthly\r\nEND:VCARD\r\n\
+             BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Carol\r\n  Smith\r\nX-STELLAR:{}\r\nEND:VCARD\r\n",
+            address(1),
+            address(2)
+        );
+        let table = ContactImportService::parse(ImportFormat::detect("contacts.txt", &text), &text).unwrap();
+
+        assert_eq!(table.columns, ["VERSION", "FN", "X-STELLAR", "NOTE"]);
+        assert_eq!(table.rows[0], ["3.0", "Bob", &address(1), "rent, monthly"]);
+        assert_eq!(table.rows[1], ["4.0", "Carol Smith", &address(2), ""]);
+        assert_eq!(guess_column(&table, ContactField::Name), Some(1));
+        assert_eq!(guess_column(&table, ContactField::Address), Some(2));
+        assert_eq!(guess_column(&table, ContactField::Memo), Some(3));
+        assert_eq!(guess_column(&table, ContactField::FederationName), None);
+    }
+
+    #[tokio::test]
+    async fn plan_skips_known_addresses_and_invalid_rows_without_writing() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = ContactImportService::new(db.clone());
+        ContactService::new(db.clone())
+            .add_contact(&user, CreateContactRequest { name: "Bobby".into(), address: address(1), memo: None, federation_name: None })
+            .await
+            .unwrap();
+
+        let text = format!(
+            "Label,Account,Memo\nBob,{},\nCarol,{},lunch\n\"Carol, again\",{},\nDave,GNOTANADDRESS,\n",
+            address(1),
+            address(2),
+            address(2)
+        );
+        let table = ContactImportService::parse(ImportFormat::Csv, &text).unwrap();
+        let mapping = FieldMapping { name: 0, address: 1, memo: Some(2), federation_name: None };
+        let plan = service.plan(&user, &table, &mapping).await.unwrap();
+
+        assert_eq!(plan.to_add.len(), 1);
+        assert_eq!(plan.to_add[0].memo.as_deref(), Some("lunch"));
+        assert_eq!(
+            plan.duplicates.iter().map(|dup| (dup.name.as_str(), dup.existing_name.as_str())).collect::<Vec<_>>(),
+            [("Bob", "Bobby"), ("Carol, again", "Carol")]
+        );
+        assert_eq!(plan.invalid.iter().map(|(row, _)| *row).collect::<Vec<_>>(), [4]);
+        assert_eq!(db.get_contacts_by_user(&user).await.unwrap().len(), 1);
+
+        let added = service.import(&user, plan).await.unwrap();
+        assert_eq!(added[0].name, "Carol");
+        assert_eq!(db.get_contacts_by_user(&user).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::cli::output;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::contact::{Contact, CreateContactRequest};
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+
+pub struct ContactService {
+    db: SqliteDatabase,
+}
+
+impl ContactService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    pub async fn add_contact(&self, user_id: &Uuid, request: CreateContactRequest) -> Result<Contact> {
+        Validator::validate_contact_name(&request.name)?;
+        Validator::validate_stellar_address(&request.address)?;
+
+        if let Some(memo) = &request.memo {
+            Validator::validate_memo(memo)?;
+        }
+
+        if let Some(federation_name) = &request.federation_name {
+            Validator::validate_federation_name(federation_name)?;
+        }
+
+        let now = Utc::now();
+        let contact = Contact {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            name: request.name.trim().to_string(),
+            address: request.address,
+            memo: request.memo,
+            federation_name: request.federation_name,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.db.create_contact(&contact).await?;
+
+        output::status(&format!("💾 Contact '{}' saved", contact.name));
+        Ok(contact)
+    }
+
+    pub async fn list_contacts(&self, user_id: &Uuid) -> Result<Vec<Contact>> {
+        self.db.get_contacts_by_user(user_id).await
+    }
+
+    pub async fn delete_contact(&self, user_id: &Uuid, contact_id: &Uuid) -> Result<()> {
+        if !self.db.delete_contact(user_id, contact_id).await? {
+            return Err(AppError::ValidationError("Contact not found".to_string()));
+        }
+
+        Ok(())
+    }
+}
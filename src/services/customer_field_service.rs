@@ -0,0 +1,190 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::customer_field::{CustomerField, CustomerFieldDisclosure};
+use crate::utils::crypto::{EnvelopeCipher, KeyRing};
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Stores SEP-9 KYC attributes, each encrypted under its own data key wrapped by
+/// a master key from `CUSTOMER_DATA_KEYS`.
+pub struct CustomerFieldService {
+    db: SqliteDatabase,
+    keys: std::result::Result<Option<KeyRing>, AppError>,
+}
+
+impl CustomerFieldService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            db,
+            keys: KeyRing::from_env(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_keys(db: SqliteDatabase, keys: KeyRing) -> Self {
+        Self { db, keys: Ok(Some(keys)) }
+    }
+
+    fn keys(&self) -> Result<&KeyRing> {
+        match &self.keys {
+            Ok(Some(keys)) => Ok(keys),
+            Ok(None) => Err(AppError::ValidationError(
+                "KYC storage is disabled; set CUSTOMER_DATA_KEYS to enable it".to_string(),
+            )),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// Names of the fields stored for the user. Nothing is decrypted.
+    pub async fn stored_fields(&self, user_id: &Uuid) -> Result<Vec<String>> {
+        Ok(self
+            .db
+            .get_customer_fields(user_id)
+            .await?
+            .into_iter()
+            .map(|field| field.field_name)
+            .collect())
+    }
+
+    pub async fn set_field(&self, user_id: &Uuid, field_name: &str, value: &str) -> Result<()> {
+        let field_name = field_name.trim().to_lowercase();
+        let value = value.trim();
+        Validator::validate_customer_field(&field_name, value)?;
+
+        let envelope = EnvelopeCipher::seal(value.as_bytes(), &context(user_id, &field_name), self.keys()?)?;
+
+        self.db
+            .upsert_customer_field(&CustomerField {
+                id: Uuid::new_v4(),
+                user_id: *user_id,
+                field_name,
+                envelope,
+                updated_at: Utc::now(),
+            })
+            .await
+    }
+
+    pub async fn remove_field(&self, user_id: &Uuid, field_name: &str) -> Result<()> {
+        if !self.db.delete_customer_field(user_id, field_name.trim()).await? {
+            return Err(AppError::ValidationError(format!("No '{}' field is stored", field_name.trim())));
+        }
+        Ok(())
+    }
+
+    /// Decrypts just the fields an anchor asked for, reporting any the user
+    /// hasn't provided. Other fields stay sealed.
+    pub async fn disclose(&self, user_id: &Uuid, required: &[String]) -> Result<CustomerFieldDisclosure> {
+        let keys = self.keys()?;
+        let stored = self.db.get_customer_fields(user_id).await?;
+        let mut disclosure = CustomerFieldDisclosure::default();
+
+        for name in required {
+            match stored.iter().find(|field| &field.field_name == name) {
+                Some(field) => {
+                    let value = EnvelopeCipher::open(&field.envelope, &context(user_id, name), keys)?;
+                    let value = std::str::from_utf8(&value)
+                        .map(str::to_string)
+                        .map_err(|_| AppError::InternalError(format!("Stored '{}' is not valid text", name)))?;
+                    disclosure.provided.push((name.clone(), value));
+                }
+                None => disclosure.missing.push(name.clone()),
+            }
+        }
+
+        Ok(disclosure)
+    }
+
+    /// Rewraps every data key not yet under the active master key, after which
+    /// older master keys can be removed. Returns how many fields were rewrapped.
+    pub async fn rotate_keys(&self) -> Result<usize> {
+        let keys = self.keys()?;
+        let stale = self.db.get_customer_fields_not_under_key(keys.active_id()).await?;
+
+        for field in &stale {
+            let rewrapped = CustomerField {
+                envelope: EnvelopeCipher::rewrap(&field.envelope, keys)?,
+                ..field.clone()
+            };
+            self.db.upsert_customer_field(&rewrapped).await?;
+        }
+
+        Ok(stale.len())
+    }
+}
+
+/// Binds each ciphertext to its owner and field, so rows can't be swapped.
+fn context(user_id: &Uuid, field_name: &str) -> Vec<u8> {
+    format!("{}/{}", user_id, field_name).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_KEY: &str = "v1:0101010101010101010101010101010101010101010101010101010101010101";
+    const NEW_KEY: &str = "v2:0202020202020202020202020202020202020202020202020202020202020202";
+
+    fn required(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn discloses_only_requested_fields() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = CustomerFieldService::with_keys(db, KeyRing::parse(OLD_KEY).unwrap());
+
+        service.set_field(&user, "first_name", "Ada").await.unwrap();
+        service.set_field(&user, "birth_date", "1815-12-10").await.unwrap();
+        service.set_field(&user, "first_name", "Augusta").await.unwrap();
+
+        assert_eq!(service.stored_fields(&user).await.unwrap(), vec!["birth_date", "first_name"]);
+
+        let disclosure = service.disclose(&user, &required(&["first_name", "last_name"])).await.unwrap();
+        assert_eq!(disclosure.provided, vec![("first_name".to_string(), "Augusta".to_string())]);
+        assert_eq!(disclosure.missing, vec!["last_name".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn values_are_not_stored_in_the_clear() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = CustomerFieldService::with_keys(db.clone(), KeyRing::parse(OLD_KEY).unwrap());
+
+        service.set_field(&user, "tax_id", "123-45-6789").await.unwrap();
+
+        let stored = db.get_customer_fields(&user).await.unwrap();
+        assert!(!stored[0].envelope.ciphertext.contains(&hex::encode("123-45-6789")));
+        assert_eq!(stored[0].envelope.key_id, "v1");
+    }
+
+    #[tokio::test]
+    async fn rotation_rewraps_under_the_new_key() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+
+        let before = CustomerFieldService::with_keys(db.clone(), KeyRing::parse(OLD_KEY).unwrap());
+        before.set_field(&user, "city", "London").await.unwrap();
+
+        let rotating = CustomerFieldService::with_keys(db.clone(), KeyRing::parse(&format!("{},{}", NEW_KEY, OLD_KEY)).unwrap());
+        assert_eq!(rotating.rotate_keys().await.unwrap(), 1);
+        assert_eq!(rotating.rotate_keys().await.unwrap(), 0);
+
+        let after = CustomerFieldService::with_keys(db, KeyRing::parse(NEW_KEY).unwrap());
+        let disclosure = after.disclose(&user, &required(&["city"])).await.unwrap();
+        assert_eq!(disclosure.provided, vec![("city".to_string(), "London".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_fields_and_missing_keys() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+
+        let service = CustomerFieldService::with_keys(db.clone(), KeyRing::parse(OLD_KEY).unwrap());
+        assert!(service.set_field(&user, "shoe_size", "9").await.is_err());
+
+        let disabled = CustomerFieldService { db, keys: Ok(None) };
+        assert!(disabled.set_field(&user, "city", "London").await.is_err());
+    }
+}
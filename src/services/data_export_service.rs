@@ -0,0 +1,225 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::contact::CreateContactRequest;
+use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+use crate::models::user::UserResponse;
+use crate::models::user_export::{ImportSummary, UserExport, EXPORT_FORMAT_VERSION};
+use crate::services::audit_service::AuditService;
+use crate::services::contact_service::ContactService;
+use crate::services::settings_service::SettingsService;
+use chrono::Utc;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Exports a user's data as JSON and imports such a file into an account, for
+/// moving between instances and answering data-portability requests.
+pub struct DataExportService {
+    db: SqliteDatabase,
+    contact_service: ContactService,
+    settings_service: SettingsService,
+    audit_service: AuditService,
+}
+
+impl DataExportService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            contact_service: ContactService::new(db.clone()),
+            settings_service: SettingsService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    pub async fn export(&self, user: &UserResponse) -> Result<UserExport> {
+        let settings = self.settings_service.settings(&user.id).await?;
+        let contacts = self.contact_service.list_contacts(&user.id).await?;
+        let transactions = match &user.stellar_public_key {
+            Some(address) => self.db.get_transactions(address, u32::MAX).await?,
+            None => Vec::new(),
+        };
+
+        self.audit_service
+            .record(Some(&user.id), AuditEvent::DataExported, &format!("{} contacts, {} transactions", contacts.len(), transactions.len()))
+            .await?;
+
+        Ok(UserExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            profile: user.into(),
+            settings: (&settings).into(),
+            contacts: contacts.iter().map(Into::into).collect(),
+            transactions: transactions.iter().map(Into::into).collect(),
+        })
+    }
+
+    pub fn to_json(export: &UserExport) -> Result<String> {
+        serde_json::to_string_pretty(export)
+            .map_err(|e| AppError::InternalError(format!("Failed to encode export: {}", e)))
+    }
+
+    pub fn from_json(json: &str) -> Result<UserExport> {
+        let export: UserExport = serde_json::from_str(json)
+            .map_err(|e| AppError::ValidationError(format!("Not a wallet data export: {}", e)))?;
+
+        if export.format_version != EXPORT_FORMAT_VERSION {
+            return Err(AppError::ValidationError(format!(
+                "Export format {} is not supported (expected {})",
+                export.format_version, EXPORT_FORMAT_VERSION
+            )));
+        }
+
+        Ok(export)
+    }
+
+    /// Merges an export into `user`'s account. The profile itself is not
+    /// copied, since the account already has its own. An SMS number must be
+    /// confirmed again, so it isn't imported either. Transaction history is
+    /// only taken if it belongs to the account's linked Stellar address.
+    pub async fn import(&self, user: &UserResponse, export: &UserExport) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+
+        self.settings_service
+            .set_fiat_currency(&user.id, &export.settings.fiat_currency)
+            .await?;
+
+        let mut names: HashSet<String> = self
+            .contact_service
+            .list_contacts(&user.id)
+            .await?
+            .into_iter()
+            .map(|contact| contact.name)
+            .collect();
+
+        for contact in &export.contacts {
+            if !names.insert(contact.name.trim().to_string()) {
+                summary.contacts_skipped += 1;
+                continue;
+            }
+
+            self.contact_service
+                .add_contact(
+                    &user.id,
+                    CreateContactRequest {
+                        name: contact.name.clone(),
+                        address: contact.address.clone(),
+                        memo: contact.memo.clone(),
+                        federation_name: contact.federation_name.clone(),
+                    },
+                )
+                .await?;
+            summary.contacts_added += 1;
+        }
+
+        match &user.stellar_public_key {
+            Some(address) if export.profile.stellar_public_key.as_ref() == Some(address) => {
+                for tx in &export.transactions {
+                    let direction = TransactionDirection::parse(&tx.direction)
+                        .ok_or_else(|| AppError::ValidationError(format!("Unknown direction '{}'", tx.direction)))?;
+                    let status = TransactionStatus::parse(&tx.status)
+                        .ok_or_else(|| AppError::ValidationError(format!("Unknown status '{}'", tx.status)))?;
+
+                    self.db
+                        .upsert_transaction(&WalletTransaction {
+                            id: Uuid::new_v4(),
+                            account: address.clone(),
+                            hash: tx.hash.clone(),
+                            operation_index: tx.operation_index,
+                            direction,
+                            asset_code: tx.asset_code.clone(),
+                            amount_stroops: tx.amount_stroops,
+                            counterparty: tx.counterparty.clone(),
+                            memo: tx.memo.clone(),
+                            status,
+                            ledger: tx.ledger,
+                            error: tx.error.clone(),
+                            request_id: None,
+                            created_at: tx.created_at,
+                            updated_at: Utc::now(),
+                        })
+                        .await?;
+                    summary.transactions_imported += 1;
+                }
+            }
+            _ => summary.transactions_skipped = export.transactions.len(),
+        }
+
+        self.audit_service
+            .record(
+                Some(&user.id),
+                AuditEvent::DataImported,
+                &format!(
+                    "from {}: {} contacts, {} transactions",
+                    export.profile.username, summary.contacts_added, summary.transactions_imported
+                ),
+            )
+            .await?;
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::User;
+
+    async fn user(db: &SqliteDatabase, address: Option<&str>) -> UserResponse {
+        let id = db.insert_test_user().await;
+        if let Some(address) = address {
+            db.update_user_stellar_public_key(&id, address).await.unwrap();
+        }
+        let user: User = db.get_user_by_email(&format!("{}@example.com", id.simple())).await.unwrap().unwrap();
+        user.into()
+    }
+
+    #[tokio::test]
+    async fn round_trips_contacts_settings_and_history() {
+        let db = SqliteDatabase::in_memory().await;
+        let service = DataExportService::new(db.clone());
+        let address = stellar_strkey::ed25519::PublicKey([1; 32]).to_string();
+        let alice = user(&db, Some(&address)).await;
+
+        SettingsService::new(db.clone()).set_fiat_currency(&alice.id, "eur").await.unwrap();
+        ContactService::new(db.clone())
+            .add_contact(&alice.id, CreateContactRequest { name: "Bob".into(), address: address.clone(), memo: None, federation_name: None })
+            .await
+            .unwrap();
+        let now = Utc::now();
+        db.upsert_transaction(&WalletTransaction {
+            id: Uuid::new_v4(),
+            account: address.clone(),
+            hash: "aa".to_string(),
+            operation_index: 0,
+            direction: TransactionDirection::Incoming,
+            asset_code: "XLM".to_string(),
+            amount_stroops: 10,
+            counterparty: "GBOB".to_string(),
+            memo: Some("hi".to_string()),
+            status: TransactionStatus::Confirmed,
+            ledger: Some(1),
+            error: None,
+            request_id: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .await
+        .unwrap();
+
+        let json = DataExportService::to_json(&service.export(&alice).await.unwrap()).unwrap();
+        let export = DataExportService::from_json(&json).unwrap();
+        assert_eq!((export.contacts.len(), export.transactions.len()), (1, 1));
+
+        // Re-importing into the same account skips what is already there.
+        let summary = service.import(&alice, &export).await.unwrap();
+        assert_eq!(summary, ImportSummary { contacts_skipped: 1, transactions_imported: 1, ..Default::default() });
+
+        // Another account gets the contacts and settings but not someone else's history.
+        let carol = user(&db, None).await;
+        let summary = service.import(&carol, &export).await.unwrap();
+        assert_eq!(summary, ImportSummary { contacts_added: 1, transactions_skipped: 1, ..Default::default() });
+        assert_eq!(SettingsService::new(db.clone()).settings(&carol.id).await.unwrap().fiat_currency, "eur");
+
+        assert!(DataExportService::from_json(&json.replace("\"format_version\": 1", "\"format_version\": 9")).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use crate::cli::output;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::email::EmailTemplate;
+use crate::models::email_change::{EmailChange, EMAIL_CHANGE_REUSE_WINDOW, EMAIL_CHANGE_TTL};
+use crate::services::audit_service::AuditService;
+use crate::services::email_service::EmailService;
+use crate::services::user_service::UserService;
+use crate::utils::crypto::RecoveryCode;
+use crate::utils::validation::Validator;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Wrong code pairs allowed before the change has to be started again.
+const MAX_CODE_FAILURES: i64 = 5;
+
+/// Groups of five characters in each emailed code.
+const CODE_GROUPS: usize = 2;
+
+/// Changes the login email in two steps. Starting a change emails one code to
+/// the current address and another to the new one; the email only changes once
+/// both are typed back. Whoever controls the login email can take over the
+/// account, so a stolen session or password alone isn't enough to move it, and
+/// a typo can't lock the user out.
+pub struct EmailChangeService {
+    db: SqliteDatabase,
+    user_service: UserService,
+    email_service: EmailService,
+    audit_service: AuditService,
+}
+
+impl EmailChangeService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self::with_email(db, EmailService::from_config())
+    }
+
+    pub fn with_email(db: SqliteDatabase, email_service: EmailService) -> Self {
+        Self {
+            user_service: UserService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            email_service,
+            db,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.email_service.is_enabled()
+    }
+
+    /// Emails the confirmation codes, replacing any change already waiting.
+    /// Returns when the codes expire. Whether another account already uses
+    /// `new_email` is only checked on confirmation, which takes the code sent
+    /// there, so this can't be used to find out which emails have accounts.
+    pub async fn start(&self, user_id: &Uuid, password: &str, new_email: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+        let new_email = new_email.trim();
+        Validator::validate_email(new_email)?;
+        self.user_service.verify_password(user_id, password).await?;
+        let user = self
+            .user_service
+            .get_user(user_id)
+            .await?
+            .ok_or_else(|| AppError::ValidationError("User not found".to_string()))?;
+        if user.email.eq_ignore_ascii_case(new_email) {
+            return Err(AppError::ValidationError("That is already your email".to_string()));
+        }
+
+        let old_code = RecoveryCode::generate(CODE_GROUPS);
+        let new_code = RecoveryCode::generate(CODE_GROUPS);
+        let expires_at = now + EMAIL_CHANGE_TTL;
+        self.email_service
+            .send(
+                &user.email,
+                EmailTemplate::EmailChangeOld,
+                &[("new_email", new_email), ("code", &old_code)],
+            )
+            .await?;
+        self.email_service
+            .send(new_email, EmailTemplate::EmailChangeNew, &[("code", &new_code)])
+            .await?;
+
+        self.db
+            .upsert_email_change(&EmailChange {
+                user_id: *user_id,
+                new_email: new_email.to_string(),
+                old_code_hash: hash_code(&old_code),
+                new_code_hash: hash_code(&new_code),
+                failures: 0,
+                expires_at,
+                created_at: now,
+                used_at: None,
+            })
+            .await?;
+
+        Ok(expires_at)
+    }
+
+    /// Applies the waiting change if both codes match. Returns the new email.
+    /// Codes from a change confirmed in the last [`EMAIL_CHANGE_REUSE_WINDOW`]
+    /// are refused, and the first such attempt is audited and emailed to the
+    /// user, since only someone who read one of the emails has them.
+    pub async fn confirm(&self, user_id: &Uuid, old_code: &str, new_code: &str, now: DateTime<Utc>) -> Result<String> {
+        let nothing_waiting = || AppError::ValidationError("No email change is waiting to be confirmed".to_string());
+        let pending = self.db.get_email_change(user_id).await?.ok_or_else(nothing_waiting)?;
+        if let Some(used_at) = pending.used_at {
+            let reused = hash_code(old_code) == pending.old_code_hash || hash_code(new_code) == pending.new_code_hash;
+            if !reused || used_at + EMAIL_CHANGE_REUSE_WINDOW <= now {
+                return Err(nothing_waiting());
+            }
+            self.report_reuse(user_id, &pending, used_at).await?;
+            return Err(AppError::AuthenticationError("These codes have already been used".to_string()));
+        }
+        if pending.expires_at <= now {
+            self.db.delete_email_change(user_id).await?;
+            return Err(AppError::ValidationError("The codes have expired; please start the change again".to_string()));
+        }
+
+        if hash_code(old_code) != pending.old_code_hash || hash_code(new_code) != pending.new_code_hash {
+            let failures = pending.failures + 1;
+            if failures >= MAX_CODE_FAILURES {
+                self.db.delete_email_change(user_id).await?;
+                return Err(AppError::AuthenticationError(
+                    "Too many wrong codes; please start the change again".to_string(),
+                ));
+            }
+            self.db.upsert_email_change(&EmailChange { failures, ..pending }).await?;
+            return Err(AppError::AuthenticationError(format!(
+                "Incorrect codes; {} attempt(s) left",
+                MAX_CODE_FAILURES - failures
+            )));
+        }
+
+        let old_email = self.user_service.get_user(user_id).await?.map(|user| user.email).unwrap_or_default();
+        self.db.apply_email_change(user_id, &pending.new_email, now).await?;
+        self.audit_service
+            .record(
+                Some(user_id),
+                AuditEvent::EmailChanged,
+                &format!("'{}' -> '{}', confirmed by both addresses", old_email, pending.new_email),
+            )
+            .await?;
+
+        Ok(pending.new_email)
+    }
+
+    /// Records and emails a reuse of `change`'s codes, then forgets the codes
+    /// so repeated tries can't flood the user's inbox.
+    async fn report_reuse(&self, user_id: &Uuid, change: &EmailChange, used_at: DateTime<Utc>) -> Result<()> {
+        self.db.delete_email_change(user_id).await?;
+        self.audit_service
+            .record(
+                Some(user_id),
+                AuditEvent::EmailCodeReused,
+                &format!("codes for the change to '{}', confirmed {}", change.new_email, used_at.to_rfc3339()),
+            )
+            .await?;
+
+        // Best effort: the attempt is refused and audited either way.
+        let used_at = used_at.format("%Y-%m-%d %H:%M UTC").to_string();
+        if let Some(user) = self.user_service.get_user(user_id).await? {
+            if let Err(e) = self
+                .email_service
+                .send(&user.email, EmailTemplate::EmailCodeReused, &[("used_at", &used_at)])
+                .await
+            {
+                output::status(&format!("⚠️  Could not send email alert: {}", e));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hashes a code, ignoring case, spaces and dashes.
+fn hash_code(code: &str) -> String {
+    hex::encode(Sha256::digest(RecoveryCode::normalize(code).as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::audit::AuditFilter;
+    use crate::models::email::Email;
+    use crate::services::email_service::EmailSender;
+    use crate::utils::crypto::PasswordManager;
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    /// Keeps sent emails so the test can read the codes.
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<(String, String)>>);
+
+    impl Outbox {
+        fn code_sent_to(&self, to: &str) -> String {
+            let sent = self.0.lock().unwrap();
+            let (_, body) = sent.iter().rev().find(|(address, _)| address == to).unwrap();
+            body.split_whitespace().find(|word| word.len() == 11 && word.contains('-')).unwrap().to_string()
+        }
+    }
+
+    #[async_trait]
+    impl EmailSender for Outbox {
+        async fn send(&self, to: &str, email: &Email) -> Result<()> {
+            self.0.lock().unwrap().push((to.to_string(), email.text.clone()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn changes_the_email_only_with_both_codes() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        db.update_user_password_hash(&user, &PasswordManager::hash_password("Velvet-Otter-92!").unwrap())
+            .await
+            .unwrap();
+        let other = db.insert_test_user().await;
+        let old_email = db.get_user_by_id(&user).await.unwrap().unwrap().email;
+        let taken = db.get_user_by_id(&other).await.unwrap().unwrap().email;
+        let outbox = Arc::new(Outbox::default());
+        let service = EmailChangeService::with_email(db.clone(), EmailService::with_sender(outbox.clone()));
+        let now = Utc::now();
+
+        assert!(service.start(&user, "wrong", "new@example.com", now).await.is_err());
+        service.start(&user, "Velvet-Otter-92!", &taken, now).await.unwrap();
+        let old_code = outbox.code_sent_to(&old_email);
+        let new_code = outbox.code_sent_to(&taken);
+        assert!(service.confirm(&user, &old_code, &new_code, now).await.is_err());
+
+        service.start(&user, "Velvet-Otter-92!", "new@example.com", now).await.unwrap();
+        let old_code = outbox.code_sent_to(&old_email);
+        let new_code = outbox.code_sent_to("new@example.com");
+
+        // Each code only works for its own address.
+        assert!(service.confirm(&user, &new_code, &old_code, now).await.is_err());
+        let later = now + EMAIL_CHANGE_TTL + chrono::Duration::minutes(1);
+        assert!(service.confirm(&user, &old_code, &new_code, later).await.is_err());
+        assert!(db.get_email_change(&user).await.unwrap().is_none());
+
+        service.start(&user, "Velvet-Otter-92!", "new@example.com", now).await.unwrap();
+        let old_code = outbox.code_sent_to(&old_email);
+        let new_code = outbox.code_sent_to("new@example.com");
+        assert_eq!(
+            service.confirm(&user, &old_code.to_uppercase(), &new_code, now).await.unwrap(),
+            "new@example.com"
+        );
+
+        let stored = db.get_user_by_id(&user).await.unwrap().unwrap();
+        assert_eq!(stored.email, "new@example.com");
+        assert!(stored.is_verified);
+        assert!(db.get_user_by_email(&old_email).await.unwrap().is_none());
+
+        // Trying the used codes again is refused and reported once.
+        let error = service.confirm(&user, &old_code, &new_code, now).await.unwrap_err();
+        assert!(matches!(error, AppError::AuthenticationError(message) if message.contains("already been used")));
+        assert!(outbox.0.lock().unwrap().last().unwrap().1.contains("were just entered again"));
+        let filter = AuditFilter { user_id: Some(user), limit: 10, ..Default::default() };
+        let events: Vec<_> = db.get_audit_entries(&filter).await.unwrap().into_iter().map(|entry| entry.event_type).collect();
+        assert!(events.contains(&AuditEvent::EmailCodeReused.as_str().to_string()));
+        let sent = outbox.0.lock().unwrap().len();
+        assert!(service.confirm(&user, &old_code, &new_code, now).await.is_err());
+        assert_eq!(outbox.0.lock().unwrap().len(), sent);
+    }
+}
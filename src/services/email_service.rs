@@ -0,0 +1,125 @@
+use crate::cli::output;
+use crate::config;
+use crate::errors::{AppError, Result};
+use crate::models::email::{Email, EmailTemplate};
+use crate::services::email_template_service::EmailTemplateService;
+use async_trait::async_trait;
+use serde_json::json;
+use std::env;
+use std::sync::Arc;
+
+pub const DEFAULT_POSTMARK_API_URL: &str = "https://api.postmarkapp.com";
+
+/// Something that can deliver an email.
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    async fn send(&self, to: &str, email: &Email) -> Result<()>;
+}
+
+/// Prints emails to the terminal instead of sending them, for development.
+pub struct ConsoleEmailSender;
+
+#[async_trait]
+impl EmailSender for ConsoleEmailSender {
+    async fn send(&self, to: &str, email: &Email) -> Result<()> {
+        output::status(&format!("📧 Email to {} ({}): {}", to, email.subject, email.text));
+        Ok(())
+    }
+}
+
+/// Sends through Postmark's email API.
+pub struct PostmarkEmailSender {
+    base_url: String,
+    server_token: String,
+    from: String,
+    http: reqwest::Client,
+}
+
+impl PostmarkEmailSender {
+    /// Reads `POSTMARK_SERVER_TOKEN` and `EMAIL_FROM`.
+    pub fn from_env() -> Result<Self> {
+        let missing = |name: &str| AppError::ValidationError(format!("EMAIL_PROVIDER=postmark needs {} to be set", name));
+
+        let server_token = env::var("POSTMARK_SERVER_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| missing("POSTMARK_SERVER_TOKEN"))?;
+        let from = config::var("EMAIL_FROM").ok_or_else(|| missing("EMAIL_FROM"))?;
+        let base_url = config::var("POSTMARK_API_URL").unwrap_or_else(|| DEFAULT_POSTMARK_API_URL.to_string());
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            server_token,
+            from,
+            http: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for PostmarkEmailSender {
+    async fn send(&self, to: &str, email: &Email) -> Result<()> {
+        let response = self
+            .http
+            .post(format!("{}/email", self.base_url))
+            .header("X-Postmark-Server-Token", &self.server_token)
+            .json(&json!({
+                "From": self.from,
+                "To": to,
+                "Subject": email.subject,
+                "TextBody": email.text,
+                "HtmlBody": email.html,
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Email request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::InternalError(format!("Email provider returned HTTP {}", response.status())));
+        }
+        Ok(())
+    }
+}
+
+/// Sends email through the provider picked by `EMAIL_PROVIDER` (`postmark` or
+/// `console`), rendered from [`EmailTemplateService`]. Features that need
+/// email are unavailable when it's unset.
+pub struct EmailService {
+    sender: std::result::Result<Option<Arc<dyn EmailSender>>, AppError>,
+    templates: EmailTemplateService,
+}
+
+impl EmailService {
+    pub fn from_config() -> Self {
+        let sender: std::result::Result<Option<Arc<dyn EmailSender>>, AppError> =
+            match config::var("EMAIL_PROVIDER").unwrap_or_default().to_lowercase().as_str() {
+                "" => Ok(None),
+                "console" => Ok(Some(Arc::new(ConsoleEmailSender))),
+                "postmark" => PostmarkEmailSender::from_env().map(|sender| Some(Arc::new(sender) as Arc<dyn EmailSender>)),
+                other => Err(AppError::ValidationError(format!("Unknown EMAIL_PROVIDER '{}'", other))),
+            };
+
+        Self { sender, templates: EmailTemplateService::from_config() }
+    }
+
+    #[cfg(test)]
+    pub fn with_sender(sender: Arc<dyn EmailSender>) -> Self {
+        Self {
+            sender: Ok(Some(sender)),
+            templates: EmailTemplateService::new(None, None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.sender, Ok(Some(_)))
+    }
+
+    /// Renders `template` with `values` and sends it to `to`.
+    pub async fn send(&self, to: &str, template: EmailTemplate, values: &[(&str, &str)]) -> Result<()> {
+        match &self.sender {
+            Ok(Some(sender)) => sender.send(to, &self.templates.render(template, values)?).await,
+            Ok(None) => Err(AppError::ValidationError("Email is disabled; set EMAIL_PROVIDER to enable it".to_string())),
+            Err(e) => Err(e.clone()),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::cli::branding::Branding;
+use crate::config;
+use crate::errors::{AppError, Result};
+use crate::models::email::{Email, EmailTemplate};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Used when `EMAIL_LOCALE` is unset.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Built-in English subject, text and HTML for each template, used when
+/// `EMAIL_TEMPLATE_DIR` has nothing for the locale.
+fn built_in(template: EmailTemplate) -> (&'static str, &'static str, &'static str) {
+    match template {
+        EmailTemplate::EmailChangeOld => (
+            "Confirm your {{product_name}} email change",
+            "Someone asked to move your {{product_name}} login from this address to {{new_email}}. \
+             If that was you, enter {{code}} to confirm. If it wasn't, change your password now.",
+            "<p>Someone asked to move your {{product_name}} login from this address to {{new_email}}.</p>\n\
+             <p>If that was you, enter <strong>{{code}}</strong> to confirm. If it wasn't, change your password now.</p>",
+        ),
+        EmailTemplate::EmailChangeNew => (
+            "Confirm your {{product_name}} email change",
+            "Enter {{code}} to make this your {{product_name}} login email.",
+            "<p>Enter <strong>{{code}}</strong> to make this your {{product_name}} login email.</p>",
+        ),
+        EmailTemplate::EmailCodeReused => (
+            "Someone tried an old {{product_name}} email code",
+            "The codes that moved your {{product_name}} login to this address on {{used_at}} were just entered again. \
+             They no longer work, but whoever entered them may have read your email. \
+             Change your email password and your {{product_name}} password.",
+            "<p>The codes that moved your {{product_name}} login to this address on {{used_at}} were just entered again.</p>\n\
+             <p>They no longer work, but whoever entered them may have read your email. \
+             Change your email password and your {{product_name}} password.</p>",
+        ),
+    }
+}
+
+/// Made-up values for `email preview`.
+pub fn sample_values(template: EmailTemplate) -> &'static [(&'static str, &'static str)] {
+    match template {
+        EmailTemplate::EmailChangeOld => &[("new_email", "new@example.com"), ("code", "7KQ2M-XW9PD")],
+        EmailTemplate::EmailChangeNew => &[("code", "7KQ2M-XW9PD")],
+        EmailTemplate::EmailCodeReused => &[("used_at", "2025-02-03 14:05 UTC")],
+    }
+}
+
+/// Renders emails from the templates in `EMAIL_TEMPLATE_DIR`, read on every
+/// send so edits apply without a restart. A template for `pt-BR` lives in
+/// `pt-BR/<name>.subject.txt`, `<name>.txt` and optionally `<name>.html`;
+/// without one the `pt` directory is tried, then the built-in English text.
+/// `{{name}}` placeholders are filled with the email's values and
+/// `product_name` and `support_contact` from the branding, HTML-escaped in the
+/// HTML variant. `EMAIL_LOCALE` defaults to `en`.
+pub struct EmailTemplateService {
+    dir: Option<PathBuf>,
+    locale: Option<String>,
+}
+
+impl EmailTemplateService {
+    /// Reads `EMAIL_TEMPLATE_DIR` and `EMAIL_LOCALE`.
+    pub fn from_config() -> Self {
+        Self::new(config::var("EMAIL_TEMPLATE_DIR").map(PathBuf::from), config::var("EMAIL_LOCALE"))
+    }
+
+    pub fn new(dir: Option<PathBuf>, locale: Option<String>) -> Self {
+        Self { dir, locale }
+    }
+
+    pub fn with_locale(mut self, locale: String) -> Self {
+        self.locale = Some(locale);
+        self
+    }
+
+    pub fn render(&self, template: EmailTemplate, values: &[(&str, &str)]) -> Result<Email> {
+        let branding = Branding::current();
+        let mut values = values.to_vec();
+        values.push(("product_name", &branding.product_name));
+        values.push(("support_contact", branding.support_contact.as_deref().unwrap_or_default()));
+
+        let name = template.as_str();
+        let (subject, text, html, locale) = match self.find(template)? {
+            Some((dir, locale)) => {
+                let subject = read(&dir.join(format!("{}.subject.txt", name)))?.ok_or_else(|| {
+                    AppError::ValidationError(format!("Email template {}/{}.txt has no {}.subject.txt", locale, name, name))
+                })?;
+                let text = read(&dir.join(format!("{}.txt", name)))?.unwrap_or_default();
+                let html = match read(&dir.join(format!("{}.html", name)))? {
+                    Some(html) => html,
+                    None => text_to_html(&text),
+                };
+                (subject, text, html, Some(locale))
+            }
+            None => {
+                let (subject, text, html) = built_in(template);
+                (subject.to_string(), text.to_string(), html.to_string(), None)
+            }
+        };
+
+        Ok(Email {
+            subject: fill(name, subject.trim(), &values, |value| value.to_string())?,
+            text: fill(name, &text, &values, |value| value.to_string())?,
+            html: fill(name, &html, &values, escape_html)?,
+            locale,
+        })
+    }
+
+    /// The first directory in the locale's fallback chain with `template`.
+    fn find(&self, template: EmailTemplate) -> Result<Option<(PathBuf, String)>> {
+        let Some(dir) = &self.dir else {
+            return Ok(None);
+        };
+        for locale in fallback_chain(self.locale.as_deref().unwrap_or(DEFAULT_LOCALE))? {
+            let path = dir.join(&locale);
+            if path.join(format!("{}.txt", template.as_str())).is_file() {
+                return Ok(Some((path, locale)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// `pt_BR.UTF-8` and `pt-BR` both give `["pt-BR", "pt"]`.
+fn fallback_chain(locale: &str) -> Result<Vec<String>> {
+    let name = locale;
+    let locale = locale.split('.').next().unwrap_or_default().replace('_', "-");
+    if locale.is_empty() || !locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::ValidationError(format!("'{}' is not a locale like 'en' or 'pt-BR'", name)));
+    }
+
+    let mut chain = vec![locale.clone()];
+    if let Some((language, _)) = locale.split_once('-') {
+        chain.push(language.to_string());
+    }
+    Ok(chain)
+}
+
+fn read(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::ValidationError(format!("Can't read email template {}: {}", path.display(), e))),
+    }
+}
+
+/// Replaces each `{{name}}` with its value, passed through `escape`.
+fn fill(template: &str, source: &str, values: &[(&str, &str)], escape: impl Fn(&str) -> String) -> Result<String> {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let key = rest[start + 2..start + end].trim();
+        let (_, value) = values.iter().find(|(name, _)| *name == key).ok_or_else(|| {
+            AppError::ValidationError(format!("Email template {} uses unknown placeholder {{{{{}}}}}", template, key))
+        })?;
+        out.push_str(&rest[..start]);
+        out.push_str(&escape(value));
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// An HTML variant for templates that only have text: one paragraph per
+/// blank-line-separated block. Placeholders are left for [`fill`].
+fn text_to_html(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|block| !block.is_empty())
+        .map(|block| format!("<p>{}</p>", escape_html(block).replace('\n', "<br>\n")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn falls_back_from_locale_to_language_to_built_in() {
+        let dir = std::env::temp_dir().join(format!("wallet-email-{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("pt")).unwrap();
+        fs::write(dir.join("pt/email_change_new.subject.txt"), "Confirme o e-mail do {{product_name}}\n").unwrap();
+        fs::write(dir.join("pt/email_change_new.txt"), "Digite {{ code }}.\n\nObrigado!").unwrap();
+        let values = [("code", "<b>ABCDE-12345</b>")];
+
+        let email = EmailTemplateService::new(Some(dir.clone()), Some("pt_BR.UTF-8".to_string()))
+            .render(EmailTemplate::EmailChangeNew, &values)
+            .unwrap();
+        assert_eq!(email.locale.as_deref(), Some("pt"));
+        assert_eq!(email.subject, format!("Confirme o e-mail do {}", Branding::current().product_name));
+        assert_eq!(email.text, "Digite <b>ABCDE-12345</b>.\n\nObrigado!");
+        assert_eq!(email.html, "<p>Digite &lt;b&gt;ABCDE-12345&lt;/b&gt;.</p>\n<p>Obrigado!</p>");
+
+        let email = EmailTemplateService::new(Some(dir.clone()), Some("de-DE".to_string()))
+            .render(EmailTemplate::EmailChangeNew, &values)
+            .unwrap();
+        assert_eq!(email.locale, None);
+        assert!(email.text.starts_with("Enter <b>ABCDE-12345</b> to make this"));
+
+        fs::write(dir.join("pt/email_change_old.txt"), "{{code}} {{password}}").unwrap();
+        fs::write(dir.join("pt/email_change_old.subject.txt"), "Oi").unwrap();
+        let service = EmailTemplateService::new(Some(dir.clone()), Some("pt".to_string()));
+        assert!(service.render(EmailTemplate::EmailChangeOld, &values).is_err());
+        assert!(EmailTemplateService::new(Some(dir.clone()), Some("../pt".to_string()))
+            .render(EmailTemplate::EmailChangeNew, &values)
+            .is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use crate::models::wallet_event::{UserEvent, WalletEvent};
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+/// Events a slow listener can fall behind by before it starts missing some.
+const CAPACITY: usize = 256;
+
+/// Fans account events out to everyone listening in this process: `/ws`
+/// connections, GraphQL subscriptions and webhooks.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<UserEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+
+    /// Sends `event` to `user_id`'s listeners. Nobody listening is fine.
+    pub fn publish(&self, user_id: Uuid, event: WalletEvent) {
+        let _ = self.sender.send(UserEvent { user_id, event });
+    }
+
+    /// `user_id`'s events from now on. A listener that falls too far behind
+    /// skips what it missed rather than ending.
+    pub fn subscribe(&self, user_id: Uuid) -> BoxStream<'static, WalletEvent> {
+        stream::unfold(self.sender.subscribe(), move |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.user_id == user_id => return Some((event.event, receiver)),
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// Every user's events from now on, for listeners that act on all
+    /// accounts. Like [`EventBus::subscribe`], it skips what it falls behind on.
+    pub fn subscribe_all(&self) -> BoxStream<'static, UserEvent> {
+        stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn listeners_only_get_their_own_events() {
+        let bus = EventBus::new();
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut events = bus.subscribe(alice);
+
+        let (theirs, ours) = (Uuid::new_v4(), Uuid::new_v4());
+        bus.publish(bob, WalletEvent::SessionRevoked { session_id: theirs });
+        bus.publish(alice, WalletEvent::SessionRevoked { session_id: ours });
+
+        match events.next().await {
+            Some(WalletEvent::SessionRevoked { session_id }) => assert_eq!(session_id, ours),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
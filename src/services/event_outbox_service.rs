@@ -0,0 +1,328 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::outbox::OutboxEvent;
+use crate::models::user::User;
+use crate::models::wallet_event::UserEvent;
+use crate::services::event_bus::EventBus;
+use crate::services::webhook_endpoint_service::webhook_event;
+use crate::services::webhook_service::event_body;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Subjects start with this when `EVENT_SUBJECT_PREFIX` is unset.
+pub const DEFAULT_SUBJECT_PREFIX: &str = "wallet";
+
+/// The wait after the first failed attempt, doubling up to [`MAX_RETRY_SECS`].
+const FIRST_RETRY_SECS: i64 = 5;
+
+/// Events are never given up on; past this they are retried every 5 minutes.
+const MAX_RETRY_SECS: i64 = 300;
+
+/// How long an attempt may take before someone else may try the event.
+const PUBLISH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How often due events and new signups are looked for.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Events published per pass.
+const BATCH_SIZE: u32 = 100;
+
+/// How long published events are kept, so one seen again isn't queued twice.
+const KEEP_PUBLISHED_DAYS: i64 = 7;
+
+/// A message broker that domain events go to.
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    /// Returns once the broker has stored the message. Brokers that can use
+    /// `event_id` to drop a message they already have should.
+    async fn publish(&self, subject: &str, event_id: &str, payload: &[u8]) -> Result<()>;
+}
+
+/// Publishes domain events (`user.created`, `payment.received`,
+/// `transaction.failed` and `account.verified`) for downstream consumers.
+/// Events are queued in the database's outbox first and only marked
+/// published once the broker has them, so each is delivered at least once,
+/// even across restarts and broker outages. Payloads are the same JSON as
+/// webhooks, under the same event ids; consumers drop ids they've seen.
+/// Each goes to `<EVENT_SUBJECT_PREFIX>.<type>`, e.g. `wallet.user.created`.
+pub struct EventOutboxService {
+    db: SqliteDatabase,
+    publisher: Arc<dyn EventPublisher>,
+    subject_prefix: String,
+}
+
+impl EventOutboxService {
+    /// The publisher `EVENT_PUBLISHER` picks (`nats`), or `None` when unset.
+    pub fn from_env(db: SqliteDatabase) -> Result<Option<Self>> {
+        let publisher: Arc<dyn EventPublisher> = match config::var("EVENT_PUBLISHER").unwrap_or_default().to_lowercase().as_str() {
+            "" => return Ok(None),
+            "nats" => nats_publisher()?,
+            other => return Err(AppError::ValidationError(format!("Unknown EVENT_PUBLISHER '{}', expected nats", other))),
+        };
+        let subject_prefix = config::var("EVENT_SUBJECT_PREFIX").unwrap_or_else(|| DEFAULT_SUBJECT_PREFIX.to_string());
+
+        Ok(Some(Self::with_publisher(db, publisher, &subject_prefix)))
+    }
+
+    pub fn with_publisher(db: SqliteDatabase, publisher: Arc<dyn EventPublisher>, subject_prefix: &str) -> Self {
+        Self {
+            db,
+            publisher,
+            subject_prefix: subject_prefix.trim_end_matches('.').to_string(),
+        }
+    }
+
+    /// Queues a bus event, if it's one consumers get. Returns whether it was
+    /// queued; one already queued isn't again.
+    pub async fn enqueue_event(&self, event: &UserEvent, now: DateTime<Utc>) -> Result<bool> {
+        match webhook_event(event) {
+            Some((event_type, event_id, data)) => self.enqueue(event_type.as_str(), &event_id, data, now).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Queues `user.created` for a new account.
+    pub async fn enqueue_signup(&self, user: &User, now: DateTime<Utc>) -> Result<bool> {
+        let digest = Sha256::digest(format!("user.created:{}", user.id));
+        let event_id = format!("evt_{}", hex::encode(&digest[..16]));
+        let data = json!({ "user_id": user.id, "username": user.username, "created_at": user.created_at });
+
+        self.enqueue("user.created", &event_id, data, now).await
+    }
+
+    async fn enqueue(&self, event_type: &str, event_id: &str, data: Value, now: DateTime<Utc>) -> Result<bool> {
+        let event = OutboxEvent {
+            event_id: event_id.to_string(),
+            event_type: event_type.to_string(),
+            subject: format!("{}.{}", self.subject_prefix, event_type),
+            payload: event_body(event_id, event_type, now, data),
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: Some(now),
+            published_at: None,
+            created_at: now,
+        };
+        self.db.insert_outbox_event(&event, now - Duration::days(KEEP_PUBLISHED_DAYS)).await
+    }
+
+    /// Publishes every event due by `now`, oldest first. Returns how many
+    /// the broker took.
+    pub async fn publish_due(&self, now: DateTime<Utc>) -> Result<usize> {
+        let lease = now + Duration::from_std(PUBLISH_TIMEOUT).unwrap_or_default() * 2;
+        let mut published = 0;
+
+        for mut event in self.db.get_due_outbox_events(now, BATCH_SIZE).await? {
+            if !self.db.claim_outbox_event(&event, lease).await? {
+                continue;
+            }
+            let outcome = tokio::time::timeout(PUBLISH_TIMEOUT, self.publisher.publish(&event.subject, &event.event_id, event.payload.as_bytes()))
+                .await
+                .unwrap_or_else(|_| Err(AppError::InternalError("The broker didn't answer in time".to_string())));
+
+            event.attempts += 1;
+            match outcome {
+                Ok(()) => {
+                    event.last_error = None;
+                    event.next_attempt_at = None;
+                    event.published_at = Some(Utc::now());
+                    published += 1;
+                }
+                Err(e) => {
+                    event.last_error = Some(e.message().to_string());
+                    event.next_attempt_at = Some(now + retry_delay(event.attempts));
+                }
+            }
+            self.db.update_outbox_event(&event).await?;
+        }
+
+        Ok(published)
+    }
+
+    /// Runs until the process exits: queues the bus's events and new
+    /// signups as they happen and publishes whatever is due, including
+    /// events left over from a restart.
+    pub async fn run(self: Arc<Self>, bus: EventBus) {
+        let wake = Arc::new(Notify::new());
+        let publisher = {
+            let (service, wake) = (self.clone(), wake.clone());
+            async move {
+                let mut signups_since = Utc::now();
+                loop {
+                    if let Ok(users) = service.db.get_users_created_since(signups_since).await {
+                        for user in users {
+                            signups_since = signups_since.max(user.created_at);
+                            if let Err(e) = service.enqueue_signup(&user, Utc::now()).await {
+                                eprintln!("Couldn't queue event: {}", e);
+                            }
+                        }
+                    }
+                    if let Err(e) = service.publish_due(Utc::now()).await {
+                        eprintln!("Event publishing failed: {}", e);
+                    }
+                    tokio::select! {
+                        _ = wake.notified() => {}
+                        _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                    }
+                }
+            }
+        };
+        tokio::spawn(publisher);
+
+        let mut events = bus.subscribe_all();
+        while let Some(event) = events.next().await {
+            match self.enqueue_event(&event, Utc::now()).await {
+                Ok(false) => {}
+                Ok(true) => wake.notify_one(),
+                Err(e) => eprintln!("Couldn't queue event: {}", e),
+            }
+        }
+    }
+}
+
+/// How long to wait after the `attempts`th failed attempt.
+fn retry_delay(attempts: i64) -> Duration {
+    Duration::seconds((FIRST_RETRY_SECS << (attempts - 1).clamp(0, 16)).min(MAX_RETRY_SECS))
+}
+
+#[cfg(feature = "nats")]
+fn nats_publisher() -> Result<Arc<dyn EventPublisher>> {
+    let url = std::env::var("NATS_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .ok_or_else(|| AppError::ValidationError("EVENT_PUBLISHER=nats needs NATS_URL".to_string()))?;
+
+    Ok(Arc::new(NatsPublisher {
+        url,
+        context: tokio::sync::OnceCell::new(),
+    }))
+}
+
+#[cfg(not(feature = "nats"))]
+fn nats_publisher() -> Result<Arc<dyn EventPublisher>> {
+    Err(AppError::ValidationError("EVENT_PUBLISHER=nats needs a build with the nats feature".to_string()))
+}
+
+/// Publishes to NATS JetStream, which acknowledges once a stream has stored
+/// the message and drops repeats of a `Nats-Msg-Id` within its duplicate
+/// window. The operator creates a stream covering the subjects. Connects on
+/// first use and reconnects by itself.
+#[cfg(feature = "nats")]
+pub struct NatsPublisher {
+    url: String,
+    context: tokio::sync::OnceCell<async_nats::jetstream::Context>,
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl EventPublisher for NatsPublisher {
+    async fn publish(&self, subject: &str, event_id: &str, payload: &[u8]) -> Result<()> {
+        let failed = |e: String| AppError::InternalError(format!("Publishing to NATS failed: {}", e));
+        let context = self
+            .context
+            .get_or_try_init(|| async { async_nats::connect(&self.url).await.map(async_nats::jetstream::new) })
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert(async_nats::header::NATS_MESSAGE_ID, event_id);
+        context
+            .publish_with_headers(subject.to_string(), headers, payload.to_vec().into())
+            .await
+            .map_err(|e| failed(e.to_string()))?
+            .await
+            .map_err(|e| failed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+    use crate::models::wallet_event::WalletEvent;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// Keeps what it's given, failing while `down` is set.
+    #[derive(Default)]
+    struct Broker {
+        down: Mutex<bool>,
+        stored: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl EventPublisher for Broker {
+        async fn publish(&self, subject: &str, event_id: &str, _payload: &[u8]) -> Result<()> {
+            if *self.down.lock().unwrap() {
+                return Err(AppError::InternalError("connection refused".to_string()));
+            }
+            self.stored.lock().unwrap().push((subject.to_string(), event_id.to_string()));
+            Ok(())
+        }
+    }
+
+    fn payment(user_id: Uuid) -> UserEvent {
+        let now = Utc::now();
+        let transaction = WalletTransaction {
+            id: Uuid::new_v4(),
+            account: "GME".to_string(),
+            hash: "aa".to_string(),
+            operation_index: 1,
+            direction: TransactionDirection::Incoming,
+            asset_code: "XLM".to_string(),
+            amount_stroops: 10_000_000,
+            counterparty: "GTHEM".to_string(),
+            memo: None,
+            status: TransactionStatus::Confirmed,
+            ledger: Some(7),
+            error: None,
+            request_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        UserEvent {
+            user_id,
+            event: WalletEvent::PaymentReceived { transaction },
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_each_event_until_the_broker_has_it() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.get_user_by_id(&db.insert_test_user().await).await.unwrap().unwrap();
+        let broker = Arc::new(Broker::default());
+        let service = EventOutboxService::with_publisher(db.clone(), broker.clone(), "wallet.");
+        let now = Utc::now();
+
+        assert!(service.enqueue_event(&payment(user.id), now).await.unwrap());
+        assert!(!service.enqueue_event(&payment(user.id), now).await.unwrap());
+        assert!(service.enqueue_signup(&user, now).await.unwrap());
+        let revoked = UserEvent {
+            user_id: user.id,
+            event: WalletEvent::SessionRevoked { session_id: Uuid::new_v4() },
+        };
+        assert!(!service.enqueue_event(&revoked, now).await.unwrap());
+
+        *broker.down.lock().unwrap() = true;
+        assert_eq!(service.publish_due(now).await.unwrap(), 0);
+        let retry_at = now + retry_delay(1);
+        assert_eq!(db.get_due_outbox_events(retry_at, 10).await.unwrap()[0].last_error.as_deref(), Some("connection refused"));
+
+        *broker.down.lock().unwrap() = false;
+        assert_eq!(service.publish_due(now).await.unwrap(), 0);
+        assert_eq!(service.publish_due(retry_at).await.unwrap(), 2);
+        let mut subjects: Vec<String> = broker.stored.lock().unwrap().iter().map(|(subject, _)| subject.clone()).collect();
+        subjects.sort();
+        assert_eq!(subjects, ["wallet.payment.received", "wallet.user.created"]);
+
+        // Seen again after publishing, it still isn't queued twice.
+        assert!(!service.enqueue_event(&payment(user.id), retry_at).await.unwrap());
+        assert_eq!(service.publish_due(retry_at + Duration::hours(1)).await.unwrap(), 0);
+    }
+}
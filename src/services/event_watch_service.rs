@@ -0,0 +1,220 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::models::transaction::{TransactionDirection, WalletTransaction};
+use crate::models::wallet_event::WalletEvent;
+use crate::services::event_bus::EventBus;
+use crate::services::history_service::{self, HistoryService};
+use crate::stellar::network::Network;
+use chrono::Utc;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How often Horizon and the database are checked for something new.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Feeds the [`EventBus`] for the API server. It follows the network's
+/// payments on Horizon for every user's wallets and watches the sessions, API keys, transactions and users
+/// tables, so payments, failures, logouts, revoked keys, disabled accounts and
+/// verifications from the CLI or another device reach `/ws` listeners and
+/// webhooks too.
+#[derive(Clone)]
+pub struct EventWatchService {
+    db: SqliteDatabase,
+    network: Network,
+    bus: EventBus,
+}
+
+impl EventWatchService {
+    pub fn new(db: SqliteDatabase, network: &Network, bus: EventBus) -> Self {
+        Self {
+            db,
+            network: network.clone(),
+            bus,
+        }
+    }
+
+    /// Runs until the process exits. Wallets added while it runs are picked
+    /// up at the next check, and rotated or deleted ones stop being watched.
+    pub async fn run(self) {
+        let watched = Arc::new(RwLock::new(HashMap::new()));
+        tokio::spawn(self.clone().watch_payments(watched.clone()));
+        let mut revoked_since = Utc::now();
+        let mut failed_since = revoked_since;
+        let mut verified_since = revoked_since;
+        let mut keys_revoked_since = revoked_since;
+        let mut disabled_since = revoked_since;
+
+        loop {
+            if let Ok(addresses) = self.db.get_wallet_addresses().await {
+                *watched.write().unwrap() = addresses.into_iter().map(|(user_id, address)| (address, user_id)).collect();
+            }
+
+            if let Ok(sessions) = self.db.get_sessions_revoked_since(revoked_since).await {
+                for session in sessions {
+                    revoked_since = session.revoked_at.map_or(revoked_since, |at| at.max(revoked_since));
+                    self.bus.publish(session.user_id, WalletEvent::SessionRevoked { session_id: session.id });
+                }
+            }
+
+            if let Ok(keys) = self.db.get_api_keys_revoked_since(keys_revoked_since).await {
+                for key in keys {
+                    keys_revoked_since = key.revoked_at.map_or(keys_revoked_since, |at| at.max(keys_revoked_since));
+                    self.bus.publish(key.user_id, WalletEvent::ApiKeyRevoked { key_id: key.id });
+                }
+            }
+
+            if let Ok(disabled) = self.db.get_users_disabled_since(disabled_since).await {
+                for (user_id, disabled_at) in disabled {
+                    disabled_since = disabled_since.max(disabled_at);
+                    self.bus.publish(user_id, WalletEvent::AccountDisabled { disabled_at });
+                }
+            }
+
+            if let Ok(failures) = self.db.get_transactions_failed_since(failed_since).await {
+                for (user_id, transaction) in failures {
+                    failed_since = failed_since.max(transaction.updated_at);
+                    self.bus.publish(user_id, WalletEvent::TransactionFailed { transaction });
+                }
+            }
+
+            if let Ok(verified) = self.db.get_users_verified_since(verified_since).await {
+                for (user_id, verified_at) in verified {
+                    verified_since = verified_since.max(verified_at);
+                    self.bus.publish(user_id, WalletEvent::AccountVerified { verified_at });
+                }
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    }
+
+    /// Publishes new payments from or to the `watched` wallets, keyed by
+    /// address, saving each to the local history like a backfill does, which
+    /// confirms payments the wallet sent. One feed of the whole network
+    /// covers every wallet.
+    async fn watch_payments(self, watched: Arc<RwLock<HashMap<String, Uuid>>>) {
+        let mut payments = HistoryService::new(self.db.clone(), &self.network).watch_network_payments(CHECK_INTERVAL);
+
+        while let Some(payment) = payments.next().await {
+            let mut ours = Vec::new();
+            {
+                let watched = watched.read().unwrap();
+                for address in [payment.sender(), payment.recipient()].into_iter().flatten() {
+                    if let Some(user_id) = watched.get(address) {
+                        if !ours.iter().any(|(_, known)| known == address) {
+                            ours.push((*user_id, address.to_string()));
+                        }
+                    }
+                }
+            }
+            for (user_id, address) in ours {
+                if let Some(transaction) = history_service::from_payment(&address, &payment) {
+                    self.record(user_id, transaction).await;
+                }
+            }
+        }
+    }
+
+    async fn record(&self, user_id: Uuid, transaction: WalletTransaction) {
+        // Payments the API sent here were announced as they went out.
+        let announced = transaction.direction == TransactionDirection::Incoming
+            && matches!(
+                self.db.get_transaction(&transaction.account, &transaction.hash, transaction.operation_index).await,
+                Ok(Some(known)) if known.request_id.is_some()
+            );
+        if let Err(e) = self.db.upsert_transaction(&transaction).await {
+            eprintln!("Couldn't save payment {} for {}: {}", transaction.hash, transaction.account, e);
+        }
+        if announced {
+            return;
+        }
+        let event = match transaction.direction {
+            TransactionDirection::Incoming => WalletEvent::PaymentReceived { transaction },
+            TransactionDirection::Outgoing => WalletEvent::TransactionConfirmed { transaction },
+        };
+        self.bus.publish(user_id, event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Query, State};
+    use axum::routing::get;
+    use axum::Router;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    const OLD: &str = "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ";
+    const NEW: &str = "GCFXHS4GXL6BVUCXBWXGTITROWLVYXQKQLF4YH5O5JT3YZXCYPAFBJZB";
+    const THEM: &str = "GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6";
+
+    type Payments = Arc<Mutex<Vec<Value>>>;
+
+    fn payment(id: i64, to: &str) -> Value {
+        json!({
+            "id": id.to_string(),
+            "transaction_hash": format!("{:x}", id),
+            "created_at": "2025-03-01T10:00:00Z",
+            "from": THEM,
+            "to": to,
+            "amount": "1.0000000",
+            "asset_type": "native",
+        })
+    }
+
+    /// Serves the network's `/payments`: the newest is always "100", and
+    /// newer ones are whatever the test has queued.
+    async fn horizon(payments: Payments) -> Network {
+        let app = Router::new()
+            .route(
+                "/payments",
+                get(|State(payments): State<Payments>, Query(query): Query<HashMap<String, String>>| async move {
+                    let records = match query.get("cursor").and_then(|cursor| cursor.parse::<i64>().ok()) {
+                        Some(cursor) => payments
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .filter(|payment| payment["id"].as_str().unwrap().parse::<i64>().unwrap() > cursor)
+                            .cloned()
+                            .collect(),
+                        None => vec![payment(100, THEM)],
+                    };
+                    axum::Json(json!({ "_embedded": { "records": records } }))
+                }),
+            )
+            .with_state(payments);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let horizon_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Network {
+            horizon_url,
+            ..Network::testnet()
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_the_network_payments_for_the_wallets_it_watches() {
+        let payments = Payments::default();
+        let network = horizon(payments.clone()).await;
+        let db = SqliteDatabase::in_memory().await;
+        let user_id = db.insert_test_user().await;
+        db.update_user_stellar_public_key(&user_id, OLD).await.unwrap();
+        let bus = EventBus::new();
+        let mut events = bus.subscribe(user_id);
+        tokio::spawn(EventWatchService::new(db.clone(), &network, bus).run());
+
+        payments.lock().unwrap().push(payment(4294967297, OLD));
+        let event = tokio::time::timeout(Duration::from_secs(15), events.next()).await.unwrap().unwrap();
+        assert!(matches!(event, WalletEvent::PaymentReceived { transaction } if transaction.account == OLD));
+
+        // Once the address is rotated, payments to the old one aren't the user's.
+        db.update_user_stellar_public_key(&user_id, NEW).await.unwrap();
+        tokio::time::sleep(CHECK_INTERVAL + Duration::from_secs(1)).await;
+        payments.lock().unwrap().extend([payment(4294967298, OLD), payment(4294967299, NEW)]);
+        let event = tokio::time::timeout(Duration::from_secs(15), events.next()).await.unwrap().unwrap();
+        assert!(matches!(event, WalletEvent::PaymentReceived { transaction } if transaction.account == NEW));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::derived_account::{DerivedAccount, PhraseRecoveryCode, RecoveryPhrase, PHRASE_RECOVERY_CODE_COUNT};
+use crate::services::keystore_service::KeystoreService;
+use crate::stellar::horizon::{AccountRecord, HorizonClient};
+use crate::stellar::network::Network;
+use crate::stellar::sep5;
+use crate::utils::crypto::{RecoveryCode, SecretCipher};
+use chrono::Utc;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// Manages the accounts a user derives from their single recovery phrase (SEP-5).
+pub struct HdWalletService {
+    db: SqliteDatabase,
+    keystore_service: KeystoreService,
+    horizon: HorizonClient,
+}
+
+impl HdWalletService {
+    pub fn new(db: SqliteDatabase, network: &Network) -> Self {
+        Self {
+            keystore_service: KeystoreService::new(db.clone()),
+            horizon: HorizonClient::new(&network.horizon_url),
+            db,
+        }
+    }
+
+    pub async fn has_recovery_phrase(&self, user_id: &Uuid) -> Result<bool> {
+        Ok(self.db.get_recovery_phrase(user_id).await?.is_some())
+    }
+
+    /// Generates and stores a new recovery phrase and derives account 0 from it.
+    /// The phrase and its recovery codes are returned once so the user can
+    /// write them down.
+    pub async fn create_recovery_phrase(&self, user_id: &Uuid, password: &str) -> Result<(Zeroizing<String>, Vec<String>, DerivedAccount)> {
+        if self.has_recovery_phrase(user_id).await? {
+            return Err(AppError::ValidationError("This account already has a recovery phrase".to_string()));
+        }
+
+        let phrase = sep5::generate_mnemonic()?;
+        let encrypted = SecretCipher::encrypt(phrase.as_bytes(), password)?;
+
+        self.db
+            .create_recovery_phrase(&RecoveryPhrase {
+                user_id: *user_id,
+                encrypted_phrase: encrypted.ciphertext,
+                salt: encrypted.salt,
+                nonce: encrypted.nonce,
+                created_at: Utc::now(),
+            })
+            .await?;
+
+        let codes = self.issue_recovery_codes(user_id, &phrase).await?;
+        let account = self.store_derived_account(user_id, &phrase, 0, password).await?;
+        Ok((phrase, codes, account))
+    }
+
+    /// Replaces the recovery codes for the user's phrase with a new set.
+    pub async fn regenerate_recovery_codes(&self, user_id: &Uuid, password: &str) -> Result<Vec<String>> {
+        let phrase = self.unlock_phrase(user_id, password).await?;
+        self.issue_recovery_codes(user_id, &phrase).await
+    }
+
+    pub async fn remaining_recovery_codes(&self, user_id: &Uuid) -> Result<usize> {
+        Ok(self.db.get_unused_phrase_recovery_codes(user_id).await?.len())
+    }
+
+    /// Derives the next unused account index.
+    pub async fn derive_next_account(&self, user_id: &Uuid, password: &str) -> Result<DerivedAccount> {
+        let phrase = self.unlock_phrase(user_id, password).await?;
+
+        let next_index = self
+            .db
+            .get_derived_accounts_by_user(user_id)
+            .await?
+            .iter()
+            .map(|account| account.account_index + 1)
+            .max()
+            .unwrap_or(0);
+
+        self.store_derived_account(user_id, &phrase, next_index, password).await
+    }
+
+    /// Lists derived accounts along with their on-chain state; `None` means the
+    /// account hasn't been activated yet.
+    pub async fn list_accounts(&self, user_id: &Uuid) -> Result<Vec<(DerivedAccount, Option<AccountRecord>)>> {
+        let mut accounts = Vec::new();
+
+        for account in self.db.get_derived_accounts_by_user(user_id).await? {
+            let record = self.horizon.get_account(&account.public_key).await?;
+            accounts.push((account, record));
+        }
+
+        Ok(accounts)
+    }
+
+    async fn unlock_phrase(&self, user_id: &Uuid, password: &str) -> Result<Zeroizing<String>> {
+        self.keystore_service
+            .unlock_phrase(user_id, password)
+            .await?
+            .ok_or_else(|| AppError::ValidationError("No recovery phrase has been created yet".to_string()))
+    }
+
+    /// Each code gets its own copy of the phrase encrypted with the code, so
+    /// any one of them opens it without the password.
+    async fn issue_recovery_codes(&self, user_id: &Uuid, phrase: &str) -> Result<Vec<String>> {
+        let now = Utc::now();
+        let codes: Vec<String> = (0..PHRASE_RECOVERY_CODE_COUNT).map(|_| RecoveryCode::generate(4)).collect();
+
+        let sealed = codes
+            .iter()
+            .map(|code| {
+                let encrypted = SecretCipher::encrypt(phrase.as_bytes(), &RecoveryCode::normalize(code))?;
+                Ok(PhraseRecoveryCode {
+                    id: Uuid::new_v4(),
+                    user_id: *user_id,
+                    encrypted_phrase: encrypted.ciphertext,
+                    salt: encrypted.salt,
+                    nonce: encrypted.nonce,
+                    used_at: None,
+                    created_at: now,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.db.replace_phrase_recovery_codes(user_id, &sealed).await?;
+        Ok(codes)
+    }
+
+    async fn store_derived_account(&self, user_id: &Uuid, phrase: &str, index: u32, password: &str) -> Result<DerivedAccount> {
+        let keypair = sep5::derive_keypair(phrase, index)?;
+
+        // The key also goes into the keystore so every derived account can sign
+        // exactly like an imported or vanity key.
+        self.keystore_service
+            .store_keypair(user_id, &keypair, password, Some(format!("sep5-{}", index)))
+            .await?;
+
+        let account = DerivedAccount {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            account_index: index,
+            public_key: keypair.public_key(),
+            created_at: Utc::now(),
+        };
+
+        self.db.create_derived_account(&account).await?;
+        Ok(account)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::stellar::horizon::HorizonClient;
+use crate::stellar::network::Network;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::future::Future;
+use std::time::Instant;
+
+/// Which build this is and what network it talks to.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct VersionInfo {
+    pub version: &'static str,
+    /// The commit the binary was built from, or `unknown`.
+    pub git_hash: &'static str,
+    pub built_at: Option<DateTime<Utc>>,
+    /// `testnet`, `public` or a custom network's name.
+    pub network: String,
+    pub network_passphrase: String,
+    pub horizon_url: String,
+}
+
+impl VersionInfo {
+    pub fn new(network: &Network) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: env!("GIT_HASH"),
+            built_at: env!("BUILD_TIME").parse().ok().and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            network: network.name.clone(),
+            network_passphrase: network.passphrase.clone(),
+            horizon_url: network.horizon_url.clone(),
+        }
+    }
+}
+
+/// Whether the service can do its job right now.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Readiness {
+    /// Both dependencies are up.
+    pub ready: bool,
+    pub database: Check,
+    pub horizon: Check,
+}
+
+/// One dependency check.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Check {
+    pub ok: bool,
+    pub latency_ms: u64,
+    /// Why it failed. Left out of `/ready`, which anyone can call; `doctor`
+    /// shows it.
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+pub struct HealthService {
+    db: SqliteDatabase,
+    horizon: HorizonClient,
+    network: Network,
+}
+
+impl HealthService {
+    pub fn new(db: SqliteDatabase, network: &Network) -> Self {
+        Self {
+            db,
+            horizon: HorizonClient::new(&network.horizon_url),
+            network: network.clone(),
+        }
+    }
+
+    pub fn version(&self) -> VersionInfo {
+        VersionInfo::new(&self.network)
+    }
+
+    /// Checks the database and Horizon at the same time.
+    pub async fn readiness(&self) -> Readiness {
+        let (database, horizon) = tokio::join!(
+            timed(async { self.db.ping().await.map_err(|e| e.to_string()) }),
+            timed(async {
+                if self.horizon.is_reachable().await {
+                    Ok(())
+                } else {
+                    Err(format!("No answer from {}", self.network.horizon_url))
+                }
+            })
+        );
+
+        Readiness {
+            ready: database.ok && horizon.ok,
+            database,
+            horizon,
+        }
+    }
+}
+
+async fn timed(check: impl Future<Output = Result<(), String>>) -> Check {
+    let started = Instant::now();
+    let outcome = check.await;
+    Check {
+        ok: outcome.is_ok(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: outcome.err(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_an_unreachable_horizon_as_not_ready() {
+        let network = Network {
+            name: "local".to_string(),
+            // Nothing listens on the discard port.
+            horizon_url: "http://127.0.0.1:9".to_string(),
+            passphrase: "Local Network".to_string(),
+        };
+        let health = HealthService::new(SqliteDatabase::in_memory().await, &network);
+
+        let readiness = health.readiness().await;
+        assert!(readiness.database.ok);
+        assert!(!readiness.horizon.ok);
+        assert!(readiness.horizon.error.unwrap().contains("127.0.0.1:9"));
+        assert!(!readiness.ready);
+
+        let version = health.version();
+        assert_eq!(version.network, "local");
+        assert!(!version.git_hash.is_empty());
+        assert!(version.built_at.is_some());
+    }
+}
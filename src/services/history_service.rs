@@ -0,0 +1,302 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::transaction::{TransactionDirection, TransactionSearch, TransactionStatus, WalletTransaction};
+use crate::stellar::amount;
+use crate::stellar::horizon::{HorizonClient, PaymentRecord};
+use crate::stellar::network::Network;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Explains the `history search` syntax.
+pub const SEARCH_HELP: &str = "Words match the memo. Filters: hash:<prefix> party:<address prefix> asset:<code> \
+min:<amount> max:<amount> since:YYYY-MM-DD until:YYYY-MM-DD tag:<tag>";
+
+/// Payment history for an account, read from the local `transactions` table
+/// after backfilling it from Horizon.
+pub struct History {
+    pub transactions: Vec<WalletTransaction>,
+    /// Set when Horizon couldn't be reached; the local records are still returned.
+    pub backfill_error: Option<AppError>,
+}
+
+pub struct HistoryService {
+    db: SqliteDatabase,
+    horizon: HorizonClient,
+}
+
+impl HistoryService {
+    pub fn new(db: SqliteDatabase, network: &Network) -> Self {
+        Self {
+            db,
+            horizon: HorizonClient::new(&network.horizon_url),
+        }
+    }
+
+    pub async fn recent(&self, address: &str, limit: u32) -> Result<History> {
+        let backfill_error = self.backfill(address, limit).await.err();
+        let transactions = self.db.get_transactions(address, limit).await?;
+
+        Ok(History {
+            transactions,
+            backfill_error,
+        })
+    }
+
+    /// Searches the locally recorded history. Nothing is fetched from Horizon.
+    pub async fn search(&self, address: &str, search: &TransactionSearch, limit: u32) -> Result<Vec<WalletTransaction>> {
+        self.db.search_transactions(address, search, limit).await
+    }
+
+    /// Tags the transaction whose hash starts with `hash_prefix`, returning its full hash.
+    pub async fn tag(&self, address: &str, hash_prefix: &str, tag: &str) -> Result<String> {
+        let tag = parse_tag(tag)?;
+        let hash = self.resolve_hash(address, hash_prefix).await?;
+        self.db.add_transaction_tag(address, &hash, &tag).await?;
+        Ok(hash)
+    }
+
+    /// Removes a tag from the transaction whose hash starts with `hash_prefix`, returning its full hash.
+    pub async fn untag(&self, address: &str, hash_prefix: &str, tag: &str) -> Result<String> {
+        let tag = parse_tag(tag)?;
+        let hash = self.resolve_hash(address, hash_prefix).await?;
+        if !self.db.remove_transaction_tag(address, &hash, &tag).await? {
+            return Err(AppError::ValidationError(format!("That transaction isn't tagged '{}'", tag)));
+        }
+        Ok(hash)
+    }
+
+    /// Tags on the account's transactions, keyed by hash.
+    pub async fn tags(&self, address: &str) -> Result<HashMap<String, Vec<String>>> {
+        self.db.get_transaction_tags(address).await
+    }
+
+    async fn resolve_hash(&self, address: &str, hash_prefix: &str) -> Result<String> {
+        let prefix = parse_hash_prefix(hash_prefix)?;
+        let mut hashes = self.db.find_transaction_hashes(address, &prefix).await?;
+
+        match hashes.len() {
+            0 => Err(AppError::ValidationError(format!("No transaction starts with '{}'", prefix))),
+            1 => Ok(hashes.remove(0)),
+            _ => Err(AppError::ValidationError(format!("'{}' matches several transactions; type more of the hash", prefix))),
+        }
+    }
+
+    /// Every payment on the network as it gets there, oldest first, checking
+    /// Horizon every `every`: one feed to pick all watched wallets' payments
+    /// from, rather than a request per wallet. Payments from before the call
+    /// aren't included. A check that fails is picked up where it stopped at
+    /// the next one.
+    pub fn watch_network_payments(&self, every: Duration) -> BoxStream<'static, PaymentRecord> {
+        let horizon = self.horizon.clone();
+
+        let start = (None, false, VecDeque::new());
+        stream::unfold(start, move |(mut cursor, mut checked, mut arrived): (Option<String>, bool, VecDeque<PaymentRecord>)| {
+            let horizon = horizon.clone();
+            async move {
+                loop {
+                    if let Some(payment) = arrived.pop_front() {
+                        return Some((payment, (cursor, checked, arrived)));
+                    }
+                    if checked {
+                        tokio::time::sleep(every).await;
+                    }
+                    checked = true;
+
+                    let Some(after) = cursor.clone() else {
+                        // Start after the newest payment; with none yet, every one is new.
+                        let newest = horizon.stream::<PaymentRecord>("payments", &[]).take(1).try_collect::<Vec<_>>().await;
+                        cursor = newest.ok().map(|newest| newest.first().map_or_else(|| "0".to_string(), |payment| payment.id.clone()));
+                        continue;
+                    };
+                    let mut newer = horizon.stream_network_payments_after(&after);
+                    while let Ok(Some(payment)) = newer.try_next().await {
+                        cursor = Some(payment.id.clone());
+                        arrived.push_back(payment);
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// Copies the latest `limit` payments from Horizon into the local table,
+    /// confirming any the wallet submitted itself. Payments from before the
+    /// account's archive cutoff stay in the archive.
+    async fn backfill(&self, address: &str, limit: u32) -> Result<()> {
+        let cutoff = self.db.get_archive_cutoff(address).await?;
+        let mut payments = self.horizon.stream_payments(address).take(limit as usize);
+
+        while let Some(payment) = payments.try_next().await? {
+            if let Some(tx) = from_payment(address, &payment).filter(|tx| cutoff.is_none_or(|cutoff| tx.created_at >= cutoff)) {
+                self.db.upsert_transaction(&tx).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts a Horizon payment into a confirmed transaction as seen from
+/// `account`. Records missing the fields we need are skipped.
+pub fn from_payment(account: &str, payment: &PaymentRecord) -> Option<WalletTransaction> {
+    let (sender, recipient) = (payment.sender()?, payment.recipient()?);
+    let (direction, counterparty) = if recipient == account && sender != account {
+        (TransactionDirection::Incoming, sender)
+    } else {
+        (TransactionDirection::Outgoing, recipient)
+    };
+
+    let created_at = DateTime::parse_from_rfc3339(&payment.created_at).ok()?.with_timezone(&Utc);
+
+    Some(WalletTransaction {
+        id: Uuid::new_v4(),
+        account: account.to_string(),
+        hash: payment.transaction_hash.clone(),
+        operation_index: payment.operation_index()?,
+        direction,
+        asset_code: payment.asset().to_string(),
+        amount_stroops: amount::parse_stroops(payment.amount()?).ok()?,
+        counterparty: counterparty.to_string(),
+        memo: payment.memo().map(str::to_string),
+        status: TransactionStatus::Confirmed,
+        ledger: payment.ledger(),
+        error: None,
+        request_id: None,
+        created_at,
+        updated_at: Utc::now(),
+    })
+}
+
+/// Parses a search like `rent asset:USDC min:10 since:2025-01-01`.
+pub fn parse_search(input: &str) -> Result<TransactionSearch> {
+    let mut search = TransactionSearch::default();
+    let mut words = Vec::new();
+
+    for token in input.split_whitespace() {
+        let Some((key, value)) = token.split_once(':').filter(|(_, value)| !value.is_empty()) else {
+            words.push(token);
+            continue;
+        };
+
+        match key.to_lowercase().as_str() {
+            "hash" => search.hash_prefix = Some(parse_hash_prefix(value)?),
+            "party" => search.counterparty = Some(parse_address_prefix(value)?),
+            "asset" => search.asset_code = Some(value.to_uppercase()),
+            "min" => search.min_amount_stroops = Some(amount::to_stroops(value)?),
+            "max" => search.max_amount_stroops = Some(amount::to_stroops(value)?),
+            "since" => search.since = Some(parse_date(value)?),
+            "until" => search.until = Some(parse_date(value)?),
+            "tag" => search.tag = Some(parse_tag(value)?),
+            _ => words.push(token),
+        }
+    }
+
+    if !words.is_empty() {
+        search.memo = Some(words.join(" "));
+    }
+
+    Ok(search)
+}
+
+fn parse_hash_prefix(value: &str) -> Result<String> {
+    let prefix = value.trim().to_lowercase();
+    if prefix.is_empty() || prefix.len() > 64 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::ValidationError(format!("'{}' is not a transaction hash prefix", value)));
+    }
+    Ok(prefix)
+}
+
+fn parse_address_prefix(value: &str) -> Result<String> {
+    let prefix = value.trim().to_uppercase();
+    if prefix.is_empty() || prefix.len() > 56 || !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AppError::ValidationError(format!("'{}' is not an address prefix", value)));
+    }
+    Ok(prefix)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::ValidationError(format!("Invalid date '{}', expected YYYY-MM-DD", value)))
+}
+
+fn parse_tag(value: &str) -> Result<String> {
+    let tag = value.trim().to_lowercase();
+    if tag.is_empty() || tag.len() > 32 || !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(AppError::ValidationError(
+            "Tags are 1-32 letters, digits, hyphens or underscores".to_string(),
+        ));
+    }
+    Ok(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar::horizon::TransactionRecord;
+
+    #[test]
+    fn parses_search_filters_and_memo_words() {
+        let search = parse_search("rent May hash:AB12 party:gcafe asset:usdc min:1.5 max:10 since:2025-01-01 until:2025-01-31 tag:Housing").unwrap();
+
+        assert_eq!(search.memo.as_deref(), Some("rent May"));
+        assert_eq!(search.hash_prefix.as_deref(), Some("ab12"));
+        assert_eq!(search.counterparty.as_deref(), Some("GCAFE"));
+        assert_eq!(search.asset_code.as_deref(), Some("USDC"));
+        assert_eq!((search.min_amount_stroops, search.max_amount_stroops), (Some(15_000_000), Some(100_000_000)));
+        assert_eq!(search.since, NaiveDate::from_ymd_opt(2025, 1, 1));
+        assert_eq!(search.until, NaiveDate::from_ymd_opt(2025, 1, 31));
+        assert_eq!(search.tag.as_deref(), Some("housing"));
+
+        assert!(parse_search("").unwrap().is_empty());
+        assert!(parse_search("hash:xyz").is_err());
+        assert!(parse_search("party:G*").is_err());
+        assert!(parse_search("since:yesterday").is_err());
+    }
+
+    fn payment(from: &str, to: &str, amount: &str) -> PaymentRecord {
+        PaymentRecord {
+            id: "237044848320512001".to_string(),
+            transaction_hash: "aa11".to_string(),
+            created_at: "2025-03-01T10:00:00Z".to_string(),
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            amount: Some(amount.to_string()),
+            asset_type: Some("native".to_string()),
+            asset_code: None,
+            funder: None,
+            account: None,
+            starting_balance: None,
+            transaction: Some(TransactionRecord {
+                memo: Some("rent".to_string()),
+            }),
+        }
+    }
+
+    #[test]
+    fn converts_payments_from_the_accounts_point_of_view() {
+        let incoming = from_payment("GME", &payment("GTHEM", "GME", "1.5")).unwrap();
+        assert_eq!(incoming.direction, TransactionDirection::Incoming);
+        assert_eq!(incoming.counterparty, "GTHEM");
+        assert_eq!(incoming.amount_stroops, 15_000_000);
+        assert_eq!((incoming.ledger, incoming.operation_index), (Some(55191304), 1));
+        assert_eq!(incoming.memo.as_deref(), Some("rent"));
+
+        let outgoing = from_payment("GME", &payment("GME", "GTHEM", "2")).unwrap();
+        assert_eq!(outgoing.direction, TransactionDirection::Outgoing);
+        assert_eq!(outgoing.counterparty, "GTHEM");
+    }
+
+    #[test]
+    fn skips_records_it_cannot_read() {
+        let mut broken = payment("GTHEM", "GME", "1");
+        broken.amount = Some("lots".to_string());
+        assert!(from_payment("GME", &broken).is_none());
+
+        let mut no_id = payment("GTHEM", "GME", "1");
+        no_id.id = "paging-token".to_string();
+        assert!(from_payment("GME", &no_id).is_none());
+    }
+}
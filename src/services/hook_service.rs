@@ -0,0 +1,209 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_HOOKS_DIR: &str = "hooks";
+
+/// Upper bound on script work, so a runaway hook can't hang the wallet.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Points in the wallet where operator scripts run. Each loads from
+/// `<hooks dir>/<name>.rhai` if that file exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookPoint {
+    /// Before a payment is signed. A veto blocks the payment.
+    BeforePayment,
+    /// After the signup form is validated, before the account is saved. A veto
+    /// rejects the signup.
+    AfterSignup,
+}
+
+impl HookPoint {
+    const ALL: [HookPoint; 2] = [HookPoint::BeforePayment, HookPoint::AfterSignup];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookPoint::BeforePayment => "before_payment",
+            HookPoint::AfterSignup => "after_signup",
+        }
+    }
+}
+
+/// What a hook decided. Scripts call `veto("reason")` to stop the operation and
+/// `annotate("note")` to attach notes shown to the user.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HookOutcome {
+    pub veto: Option<String>,
+    pub annotations: Vec<String>,
+}
+
+/// Runs operator-provided Rhai scripts at hook points. Scripts see the event
+/// as a read-only `event` object map. One script runs at a time, since they
+/// all report through the same outcome.
+pub struct HookService {
+    engine: Engine,
+    outcome: Arc<Mutex<HookOutcome>>,
+    running: Mutex<()>,
+    scripts: HashMap<HookPoint, AST>,
+}
+
+impl HookService {
+    /// Loads scripts from `WALLET_HOOKS_DIR` (or `./hooks`). A missing
+    /// directory just means no hooks are configured.
+    pub fn from_env() -> Result<Self> {
+        let dir = config::var("WALLET_HOOKS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_HOOKS_DIR));
+
+        let mut service = Self::empty();
+
+        for hook in HookPoint::ALL {
+            let path = dir.join(format!("{}.rhai", hook.name()));
+            if path.is_file() {
+                service.load(hook, &path)?;
+            }
+        }
+
+        Ok(service)
+    }
+
+    fn empty() -> Self {
+        let outcome = Arc::new(Mutex::new(HookOutcome::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let veto = outcome.clone();
+        engine.register_fn("veto", move |reason: &str| {
+            veto.lock().unwrap().veto.get_or_insert_with(|| reason.to_string());
+        });
+        let annotate = outcome.clone();
+        engine.register_fn("annotate", move |note: &str| {
+            annotate.lock().unwrap().annotations.push(note.to_string());
+        });
+
+        Self {
+            engine,
+            outcome,
+            running: Mutex::new(()),
+            scripts: HashMap::new(),
+        }
+    }
+
+    fn load(&mut self, hook: HookPoint, path: &Path) -> Result<()> {
+        let ast = self
+            .engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| AppError::ValidationError(format!("Invalid {} hook ({}): {}", hook.name(), path.display(), e)))?;
+
+        self.scripts.insert(hook, ast);
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn with_script(hook: HookPoint, script: &str) -> Result<Self> {
+        let mut service = Self::empty();
+        let ast = service
+            .engine
+            .compile(script)
+            .map_err(|e| AppError::ValidationError(format!("Invalid {} hook: {}", hook.name(), e)))?;
+        service.scripts.insert(hook, ast);
+        Ok(service)
+    }
+
+    /// Runs the script for `hook`, if any. A script that fails to run counts as
+    /// a veto, so a broken rule never silently lets an operation through.
+    pub fn run(&self, hook: HookPoint, event: Map) -> Result<HookOutcome> {
+        let Some(ast) = self.scripts.get(&hook) else {
+            return Ok(HookOutcome::default());
+        };
+
+        let _running = self.running.lock().unwrap();
+        *self.outcome.lock().unwrap() = HookOutcome::default();
+
+        let mut scope = Scope::new();
+        scope.push_constant("event", event);
+
+        if let Err(e) = self.engine.run_ast_with_scope(&mut scope, ast) {
+            return Err(AppError::ValidationError(format!("{} hook failed: {}", hook.name(), e)));
+        }
+
+        let outcome = self.outcome.lock().unwrap().clone();
+        Ok(outcome)
+    }
+
+    /// Runs `hook` and turns a veto into a validation error, returning the
+    /// annotations otherwise.
+    pub fn check(&self, hook: HookPoint, event: Map) -> Result<Vec<String>> {
+        let outcome = self.run(hook, event)?;
+
+        match outcome.veto {
+            Some(reason) => Err(AppError::ValidationError(format!("Blocked by {} rule: {}", hook.name(), reason))),
+            None => Ok(outcome.annotations),
+        }
+    }
+}
+
+/// Builds the `event` map passed to a hook script.
+pub fn event(fields: &[(&str, Dynamic)]) -> Map {
+    fields
+        .iter()
+        .map(|(key, value)| ((*key).into(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment(amount: &str, destination: &str) -> Map {
+        event(&[("amount", amount.into()), ("destination", destination.into())])
+    }
+
+    #[test]
+    fn no_script_allows_everything() {
+        let service = HookService::empty();
+
+        assert_eq!(service.run(HookPoint::BeforePayment, payment("1", "GA")).unwrap(), HookOutcome::default());
+    }
+
+    #[test]
+    fn scripts_can_veto_and_annotate() {
+        let service = HookService::with_script(
+            HookPoint::BeforePayment,
+            r#"
+                if event.amount.parse_float() > 100.0 { veto("over 100 XLM needs approval"); }
+                annotate("checked " + event.destination);
+            "#,
+        )
+        .unwrap();
+
+        let small = service.check(HookPoint::BeforePayment, payment("5", "GA")).unwrap();
+        assert_eq!(small, vec!["checked GA".to_string()]);
+
+        let err = service.check(HookPoint::BeforePayment, payment("500", "GA")).unwrap_err();
+        assert!(err.to_string().contains("over 100 XLM needs approval"));
+
+        // Outcomes don't leak between runs.
+        assert!(service.check(HookPoint::BeforePayment, payment("5", "GB")).is_ok());
+    }
+
+    #[test]
+    fn hooks_only_run_at_their_own_point() {
+        let service = HookService::with_script(HookPoint::AfterSignup, r#"veto("closed");"#).unwrap();
+
+        assert!(service.check(HookPoint::BeforePayment, payment("5", "GA")).is_ok());
+        assert!(service.check(HookPoint::AfterSignup, Map::new()).is_err());
+    }
+
+    #[test]
+    fn runaway_and_broken_scripts_fail_closed() {
+        let looping = HookService::with_script(HookPoint::BeforePayment, "loop {}").unwrap();
+        assert!(looping.check(HookPoint::BeforePayment, payment("1", "GA")).is_err());
+
+        let broken = HookService::with_script(HookPoint::BeforePayment, "event.missing.field").unwrap();
+        assert!(broken.check(HookPoint::BeforePayment, payment("1", "GA")).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::idempotency::{IdempotencyKey, IDEMPOTENCY_KEY_TTL};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Longest key accepted; clients usually send a UUID.
+const MAX_KEY_LEN: usize = 255;
+
+/// What to do with a request that carries an idempotency key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Claim {
+    /// First time the key is seen: run the request, then
+    /// [`IdempotencyService::complete`] or [`IdempotencyService::release`] it.
+    Run,
+    /// Already answered: send this status and JSON body again.
+    Replay { status: u16, body: String },
+    /// The first request sent the transaction `hash` without learning how
+    /// it ended. Find out instead of running the request again, then
+    /// complete, hold or release the key as for [`Claim::Run`].
+    Pending { hash: String },
+}
+
+/// Makes retried signups and payments safe: the first request with a key
+/// runs, and later ones with the same key get its response instead of
+/// running again. A request that failed before changing anything frees its
+/// key, so the client can fix what was wrong and retry with it; one that
+/// sent a transaction without learning its fate holds the key until that is
+/// known. Keys are forgotten after a day.
+pub struct IdempotencyService {
+    db: SqliteDatabase,
+}
+
+impl IdempotencyService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Claims `key` in `scope` for a request whose non-secret fields are
+    /// `request`. Fails if the key was used for a different request, or the
+    /// first request with it hasn't finished yet.
+    pub async fn claim(&self, scope: &str, key: &str, request: &impl Serialize, now: DateTime<Utc>) -> Result<Claim> {
+        let key = key.trim();
+        if key.is_empty() || key.len() > MAX_KEY_LEN || !key.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(AppError::ValidationError(format!(
+                "Idempotency-Key must be 1 to {} printable characters without spaces",
+                MAX_KEY_LEN
+            )));
+        }
+        self.db.delete_idempotency_keys_before(now - IDEMPOTENCY_KEY_TTL).await?;
+
+        let record = IdempotencyKey {
+            scope: scope.to_string(),
+            key: key.to_string(),
+            fingerprint: fingerprint(request)?,
+            user_id: None,
+            response_status: None,
+            response_body: None,
+            pending_hash: None,
+            created_at: now,
+        };
+        if self.db.insert_idempotency_key(&record).await? {
+            return Ok(Claim::Run);
+        }
+
+        let Some(existing) = self.db.get_idempotency_key(scope, key).await? else {
+            // Expired or released between the insert and the read.
+            return Err(AppError::ConflictError("A request with this Idempotency-Key just finished; retry it".to_string()));
+        };
+        if existing.fingerprint != record.fingerprint {
+            return Err(AppError::ValidationError(
+                "This Idempotency-Key was already used for a different request; use a new key".to_string(),
+            ));
+        }
+        match (existing.response(), &existing.pending_hash) {
+            (Some((status, body)), _) => Ok(Claim::Replay {
+                status,
+                body: body.to_string(),
+            }),
+            (None, Some(hash)) => Ok(Claim::Pending { hash: hash.clone() }),
+            (None, None) => Err(AppError::ConflictError(
+                "A request with this Idempotency-Key is still being handled; retry shortly".to_string(),
+            )),
+        }
+    }
+
+    /// Keeps the response to replay for a claimed request, and the account
+    /// it is about if it succeeded.
+    pub async fn complete(&self, scope: &str, key: &str, user_id: Option<&Uuid>, status: u16, body: &str) -> Result<()> {
+        self.db.complete_idempotency_key(scope, key.trim(), user_id, status, body).await
+    }
+
+    /// Keeps a claimed request's key while the transaction `hash` it sent
+    /// is unconfirmed, so retries look it up instead of sending another.
+    pub async fn hold(&self, scope: &str, key: &str, hash: &str) -> Result<()> {
+        self.db.hold_idempotency_key(scope, key.trim(), hash).await
+    }
+
+    /// Frees the key of a claimed request that failed without changing
+    /// anything.
+    pub async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        self.db.delete_idempotency_key(scope, key.trim()).await
+    }
+}
+
+fn fingerprint(request: &impl Serialize) -> Result<String> {
+    let json = serde_json::to_vec(request).map_err(|e| AppError::InternalError(format!("Can't fingerprint the request: {}", e)))?;
+    Ok(hex::encode(Sha256::digest(json)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn replays_successes_and_turns_away_reuse() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = IdempotencyService::new(db);
+        let now = Utc::now();
+        let payment = json!({ "destination": "GABC", "amount": "5" });
+
+        assert_eq!(service.claim("payments", "retry-me", &payment, now).await.unwrap(), Claim::Run);
+        assert!(matches!(service.claim("payments", "retry-me", &payment, now).await, Err(AppError::ConflictError(_))));
+        service.complete("payments", "retry-me", Some(&user), 200, r#"{"hash":"abc"}"#).await.unwrap();
+
+        let replay = service.claim("payments", " retry-me ", &payment, now).await.unwrap();
+        assert_eq!(
+            replay,
+            Claim::Replay {
+                status: 200,
+                body: r#"{"hash":"abc"}"#.to_string()
+            }
+        );
+        let other = json!({ "destination": "GABC", "amount": "50" });
+        assert!(matches!(service.claim("payments", "retry-me", &other, now).await, Err(AppError::ValidationError(_))));
+        assert_eq!(service.claim("accounts", "retry-me", &other, now).await.unwrap(), Claim::Run);
+        assert_eq!(service.claim("payments", "retry-me", &other, now + Duration::days(2)).await.unwrap(), Claim::Run);
+
+        assert_eq!(service.claim("payments", "failed", &payment, now).await.unwrap(), Claim::Run);
+        service.release("payments", "failed").await.unwrap();
+        assert_eq!(service.claim("payments", "failed", &payment, now).await.unwrap(), Claim::Run);
+        assert!(service.claim("payments", "has spaces", &payment, now).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn holds_keys_of_unconfirmed_payments() {
+        let service = IdempotencyService::new(SqliteDatabase::in_memory().await);
+        let now = Utc::now();
+        let payment = json!({ "destination": "GABC", "amount": "5" });
+
+        assert_eq!(service.claim("payments", "timed-out", &payment, now).await.unwrap(), Claim::Run);
+        service.hold("payments", "timed-out", "ab12").await.unwrap();
+        let pending = Claim::Pending { hash: "ab12".to_string() };
+        assert_eq!(service.claim("payments", "timed-out", &payment, now).await.unwrap(), pending);
+        assert_eq!(service.claim("payments", "timed-out", &payment, now).await.unwrap(), pending);
+
+        service.complete("payments", "timed-out", None, 200, r#"{"hash":"ab12"}"#).await.unwrap();
+        assert!(matches!(service.claim("payments", "timed-out", &payment, now).await.unwrap(), Claim::Replay { status: 200, .. }));
+    }
+}
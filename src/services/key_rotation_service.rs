@@ -0,0 +1,105 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::services::audit_service::AuditService;
+use crate::services::keystore_service::KeystoreService;
+use crate::services::signer_service::{SignerKind, SignerService};
+use crate::stellar::horizon::HorizonClient;
+use crate::stellar::keypair::Keypair;
+use crate::stellar::network::Network;
+use crate::stellar::transaction::{sign_transaction, TransactionBuilder};
+use uuid::Uuid;
+
+/// What a completed rotation changed.
+pub struct KeyRotation {
+    pub old_signer: String,
+    pub new_signer: String,
+    pub hash: String,
+}
+
+pub struct KeyRotationService {
+    db: SqliteDatabase,
+    horizon: HorizonClient,
+    network: Network,
+    keystore_service: KeystoreService,
+    signer_service: SignerService,
+    audit_service: AuditService,
+}
+
+impl KeyRotationService {
+    pub fn new(db: SqliteDatabase, network: Network) -> Self {
+        Self {
+            horizon: HorizonClient::new(&network.horizon_url),
+            network,
+            keystore_service: KeystoreService::new(db.clone()),
+            signer_service: SignerService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Replaces the keystore key that signs for `account` with a new one. A
+    /// single transaction signed by the old key adds the new key as a signer
+    /// with the old key's weight and drops the old key's weight to 0, so the
+    /// address, balances and history stay as they are.
+    pub async fn rotate(&self, user_id: &Uuid, account: &str, password: &str) -> Result<KeyRotation> {
+        if self.signer_service.signer_kind(user_id, account).await? != SignerKind::Software {
+            return Err(AppError::ValidationError("Only keystore keys can be rotated here".to_string()));
+        }
+
+        let current = self.keystore_service.signing_key(user_id, account).await?;
+        let signer = self.keystore_service.unlock_signer(user_id, account, password).await?;
+        let record = self
+            .horizon
+            .get_account(account)
+            .await?
+            .ok_or_else(|| AppError::StellarError("Your account is not funded on the network yet".to_string()))?;
+        let weight = record
+            .signer_weight(&current.public_key)
+            .filter(|weight| *weight > 0)
+            .ok_or_else(|| AppError::StellarError(format!("{} no longer signs for {}", current.public_key, account)))?;
+
+        let new_key = Keypair::random();
+        let builder = TransactionBuilder::new(account, record.sequence_number()?)?.set_signer(&new_key.public_key(), weight)?;
+        let builder = if current.public_key == account {
+            builder.master_weight(0)
+        } else {
+            builder.set_signer(&current.public_key, 0)?
+        };
+        let signed = sign_transaction(builder.build()?, signer.as_ref(), &self.network)?;
+
+        // Saved before submitting, so the new key can't be lost once the
+        // network has accepted it.
+        let entry = self
+            .keystore_service
+            .store_signing_key(user_id, &new_key, account, password)
+            .await?;
+
+        if let Err(e) = self.horizon.submit_transaction(&signed).await {
+            match self.horizon.get_account(account).await {
+                // The submission went through after all, e.g. the response timed out.
+                Ok(Some(record)) if record.signer_weight(&new_key.public_key()).is_some_and(|weight| weight > 0) => {}
+                Ok(_) => {
+                    self.db.delete_keystore_entry(&entry.id).await?;
+                    return Err(e);
+                }
+                // Can't tell whether it went through; keep the key in case it did.
+                Err(_) => return Err(e),
+            }
+        }
+
+        self.audit_service
+            .record(
+                Some(user_id),
+                AuditEvent::SigningKeyRotated,
+                &format!("{}: {} → {}", account, current.public_key, new_key.public_key()),
+            )
+            .await?;
+
+        Ok(KeyRotation {
+            old_signer: current.public_key,
+            new_signer: new_key.public_key(),
+            hash: signed.hash,
+        })
+    }
+}
@@ -0,0 +1,139 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::keystore::KeystoreEntry;
+use crate::models::keystore_backup::{KeystoreBackup, KEYSTORE_BACKUP_VERSION};
+use crate::models::user::UserResponse;
+use crate::services::audit_service::AuditService;
+use crate::services::keystore_service::KeystoreService;
+use crate::services::user_service::UserService;
+use crate::stellar::keypair::Keypair;
+use crate::utils::crypto::PassphraseCipher;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Moves keystore keys between wallets as passphrase-encrypted JSON files.
+pub struct KeystoreBackupService {
+    db: SqliteDatabase,
+    keystore_service: KeystoreService,
+    user_service: UserService,
+    audit_service: AuditService,
+}
+
+impl KeystoreBackupService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            keystore_service: KeystoreService::new(db.clone()),
+            user_service: UserService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    pub async fn keys(&self, user_id: &Uuid) -> Result<Vec<KeystoreEntry>> {
+        self.db.get_keystore_entries_by_user(user_id).await
+    }
+
+    /// Decrypts `entry` with the login password and re-encrypts it with
+    /// `passphrase` for the backup file.
+    pub async fn export(&self, user_id: &Uuid, entry: &KeystoreEntry, password: &str, passphrase: &str) -> Result<KeystoreBackup> {
+        let keypair = self.keystore_service.unlock_keypair(user_id, &entry.public_key, password).await?;
+        let context = KeystoreBackup::context(KEYSTORE_BACKUP_VERSION, &entry.public_key, entry.signs_for.as_deref());
+
+        let backup = KeystoreBackup {
+            version: KEYSTORE_BACKUP_VERSION,
+            public_key: entry.public_key.clone(),
+            signs_for: entry.signs_for.clone(),
+            label: entry.label.clone(),
+            exported_at: Utc::now(),
+            crypto: PassphraseCipher::seal(keypair.seed_bytes().as_slice(), passphrase, &context)?,
+        };
+
+        self.audit_service
+            .record(Some(user_id), AuditEvent::KeyExported, &format!("keystore backup of {}", entry.public_key))
+            .await?;
+        Ok(backup)
+    }
+
+    pub fn to_json(backup: &KeystoreBackup) -> Result<String> {
+        serde_json::to_string_pretty(backup)
+            .map_err(|e| AppError::InternalError(format!("Failed to encode keystore backup: {}", e)))
+    }
+
+    pub fn from_json(json: &str) -> Result<KeystoreBackup> {
+        let backup: KeystoreBackup = serde_json::from_str(json)
+            .map_err(|e| AppError::ValidationError(format!("Not a keystore backup: {}", e)))?;
+
+        if backup.version != KEYSTORE_BACKUP_VERSION {
+            return Err(AppError::ValidationError(format!(
+                "Keystore backup version {} is not supported (expected {})",
+                backup.version, KEYSTORE_BACKUP_VERSION
+            )));
+        }
+
+        Ok(backup)
+    }
+
+    /// Opens `backup` with `passphrase` and stores the key for `user`,
+    /// encrypted with their login password. A user without an address yet gets
+    /// it as their address.
+    pub async fn import(&self, user: &UserResponse, backup: &KeystoreBackup, passphrase: &str, password: &str) -> Result<KeystoreEntry> {
+        let context = KeystoreBackup::context(backup.version, &backup.public_key, backup.signs_for.as_deref());
+        let seed = PassphraseCipher::open(&backup.crypto, passphrase, &context)?;
+        let keypair = Keypair::from_seed_bytes(&seed)?;
+        if keypair.public_key() != backup.public_key {
+            return Err(AppError::ValidationError("The backup's key doesn't match its address".to_string()));
+        }
+
+        // The login password is what the keystore encrypts with; check it
+        // before storing a key nobody could unlock.
+        self.user_service.verify_password(&user.id, password).await?;
+
+        let entry = match &backup.signs_for {
+            Some(account) => self.keystore_service.store_signing_key(&user.id, &keypair, account, password).await?,
+            None => self.keystore_service.store_keypair(&user.id, &keypair, password, backup.label.clone()).await?,
+        };
+        if user.stellar_public_key.is_none() {
+            let address = backup.signs_for.as_deref().unwrap_or(&backup.public_key);
+            self.user_service.link_stellar_public_key(&user.id, address).await?;
+        }
+
+        self.audit_service
+            .record(Some(&user.id), AuditEvent::KeyImported, &format!("keystore backup of {}", backup.public_key))
+            .await?;
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn backups_move_a_key_into_another_account() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let service = KeystoreBackupService::new(db.clone());
+        let keystore = KeystoreService::new(db.clone());
+
+        let keypair = Keypair::random();
+        let entry = keystore.store_keypair(&alice, &keypair, "password", Some("savings".to_string())).await.unwrap();
+        let backup = service.export(&alice, &entry, "password", "backup passphrase").await.unwrap();
+        let backup = KeystoreBackupService::from_json(&KeystoreBackupService::to_json(&backup).unwrap()).unwrap();
+        assert_eq!(backup.public_key, keypair.public_key());
+
+        let mut tampered = backup.clone();
+        tampered.signs_for = Some(Keypair::random().public_key());
+        let context = KeystoreBackup::context(tampered.version, &tampered.public_key, tampered.signs_for.as_deref());
+        assert!(PassphraseCipher::open(&tampered.crypto, "backup passphrase", &context).is_err());
+
+        let context = KeystoreBackup::context(backup.version, &backup.public_key, None);
+        assert!(PassphraseCipher::open(&backup.crypto, "wrong passphrase", &context).is_err());
+        let seed = PassphraseCipher::open(&backup.crypto, "backup passphrase", &context).unwrap();
+        assert_eq!(seed.as_slice(), keypair.seed_bytes().as_slice());
+
+        let mut newer = backup.clone();
+        newer.version += 1;
+        assert!(KeystoreBackupService::from_json(&KeystoreBackupService::to_json(&newer).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::cli::output;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::derived_account::RecoveryPhrase;
+use crate::models::keystore::KeystoreEntry;
+use crate::stellar::keypair::Keypair;
+use crate::stellar::signer::{DelegatedSigner, Signer};
+use crate::utils::crypto::{EncryptedSecret, SecretCipher};
+use chrono::Utc;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+pub struct KeystoreService {
+    db: SqliteDatabase,
+}
+
+impl KeystoreService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Encrypts the keypair's seed with `password` and stores it for the user.
+    pub async fn store_keypair(&self, user_id: &Uuid, keypair: &Keypair, password: &str, label: Option<String>) -> Result<KeystoreEntry> {
+        self.store(user_id, keypair, password, label, None).await
+    }
+
+    /// Stores a key that was made a signer of `account` in place of its own key.
+    pub async fn store_signing_key(&self, user_id: &Uuid, keypair: &Keypair, account: &str, password: &str) -> Result<KeystoreEntry> {
+        self.store(user_id, keypair, password, Some("rotated signing key".to_string()), Some(account.to_string()))
+            .await
+    }
+
+    async fn store(
+        &self,
+        user_id: &Uuid,
+        keypair: &Keypair,
+        password: &str,
+        label: Option<String>,
+        signs_for: Option<String>,
+    ) -> Result<KeystoreEntry> {
+        let encrypted = SecretCipher::encrypt(keypair.seed_bytes().as_slice(), password)?;
+
+        let entry = KeystoreEntry {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            public_key: keypair.public_key(),
+            encrypted_secret: encrypted.ciphertext,
+            salt: encrypted.salt,
+            nonce: encrypted.nonce,
+            label,
+            signs_for,
+            created_at: Utc::now(),
+        };
+
+        self.db.create_keystore_entry(&entry).await?;
+
+        output::status(&format!("🔐 Key {} stored in keystore", entry.public_key));
+        Ok(entry)
+    }
+
+    /// `entry` with its secret encrypted again with `password`, e.g. for a
+    /// new password, to save along with it. `keypair` must be the entry's own.
+    pub fn reencrypt(entry: KeystoreEntry, keypair: &Keypair, password: &str) -> Result<KeystoreEntry> {
+        if keypair.public_key() != entry.public_key {
+            return Err(AppError::InternalError(format!("Key doesn't match keystore entry {}", entry.public_key)));
+        }
+
+        let encrypted = SecretCipher::encrypt(keypair.seed_bytes().as_slice(), password)?;
+        Ok(KeystoreEntry {
+            encrypted_secret: encrypted.ciphertext,
+            salt: encrypted.salt,
+            nonce: encrypted.nonce,
+            ..entry
+        })
+    }
+
+    /// Decrypts the user's recovery phrase, if they have one.
+    pub async fn unlock_phrase(&self, user_id: &Uuid, password: &str) -> Result<Option<Zeroizing<String>>> {
+        let Some(stored) = self.db.get_recovery_phrase(user_id).await? else {
+            return Ok(None);
+        };
+
+        let phrase = SecretCipher::decrypt(
+            &EncryptedSecret {
+                ciphertext: stored.encrypted_phrase,
+                salt: stored.salt,
+                nonce: stored.nonce,
+            },
+            password,
+        )?;
+
+        std::str::from_utf8(&phrase)
+            .map(|phrase| Some(Zeroizing::new(phrase.to_string())))
+            .map_err(|_| AppError::InternalError("Stored recovery phrase is corrupt".to_string()))
+    }
+
+    /// The user's recovery phrase encrypted again with `password`.
+    pub fn reencrypt_phrase(stored: RecoveryPhrase, phrase: &str, password: &str) -> Result<RecoveryPhrase> {
+        let encrypted = SecretCipher::encrypt(phrase.as_bytes(), password)?;
+        Ok(RecoveryPhrase {
+            encrypted_phrase: encrypted.ciphertext,
+            salt: encrypted.salt,
+            nonce: encrypted.nonce,
+            ..stored
+        })
+    }
+
+    /// Decrypts the secret for `public_key` itself. Once the account's key has
+    /// been rotated this no longer signs for it, but still opens its payment notes.
+    pub async fn unlock_keypair(&self, user_id: &Uuid, public_key: &str, password: &str) -> Result<Keypair> {
+        let entry = self
+            .db
+            .get_keystore_entry(user_id, public_key)
+            .await?
+            .ok_or_else(|| AppError::StellarError(format!("No secret key stored for {}", public_key)))?;
+
+        decrypt(entry, password)
+    }
+
+    /// The entry for the key that currently signs for `account`.
+    pub async fn signing_key(&self, user_id: &Uuid, account: &str) -> Result<KeystoreEntry> {
+        self.db
+            .get_signing_key_entry(user_id, account)
+            .await?
+            .ok_or_else(|| AppError::StellarError(format!("No secret key stored for {}", account)))
+    }
+
+    /// Decrypts whichever key currently signs for `account`.
+    pub async fn unlock_signer(&self, user_id: &Uuid, account: &str, password: &str) -> Result<Box<dyn Signer>> {
+        let entry = self.signing_key(user_id, account).await?;
+        let rotated = entry.signs_for.is_some();
+        let keypair = decrypt(entry, password)?;
+
+        Ok(if rotated {
+            Box::new(DelegatedSigner::new(account, keypair))
+        } else {
+            Box::new(keypair)
+        })
+    }
+}
+
+fn decrypt(entry: KeystoreEntry, password: &str) -> Result<Keypair> {
+    let seed = SecretCipher::decrypt(
+        &EncryptedSecret {
+            ciphertext: entry.encrypted_secret,
+            salt: entry.salt,
+            nonce: entry.nonce,
+        },
+        password,
+    )?;
+
+    Keypair::from_seed_bytes(&seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rotated_key_signs_for_the_account_but_the_old_key_stays_readable() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let keystore = KeystoreService::new(db);
+
+        let account = Keypair::random();
+        let rotated = Keypair::random();
+        keystore.store_keypair(&user, &account, "password", None).await.unwrap();
+        let address = account.public_key();
+        assert_eq!(keystore.signing_key(&user, &address).await.unwrap().public_key, address);
+
+        keystore.store_signing_key(&user, &rotated, &address, "password").await.unwrap();
+        assert_eq!(keystore.signing_key(&user, &address).await.unwrap().public_key, rotated.public_key());
+
+        let signer = keystore.unlock_signer(&user, &address, "password").await.unwrap();
+        assert_eq!(signer.public_key(), address);
+        let hint = signer.sign_transaction(&[], &[7; 32]).unwrap().hint;
+        assert_eq!(hint, rotated.sign_decorated(&[7; 32]).unwrap().hint);
+        assert!(signer.shared_secret(&Keypair::random().public_key()).is_err());
+
+        let original = keystore.unlock_keypair(&user, &address, "password").await.unwrap();
+        assert_eq!(original.public_key(), address);
+    }
+}
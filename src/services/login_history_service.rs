@@ -0,0 +1,63 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::login_attempt::LoginAttempt;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// How many attempts the Recent Activity screen shows.
+pub const RECENT_ATTEMPTS: i64 = 20;
+
+/// Keeps every login attempt on an account so its owner can review them.
+/// Attempts on identifiers that match no account only go to the audit log.
+#[derive(Clone)]
+pub struct LoginHistoryService {
+    db: SqliteDatabase,
+}
+
+impl LoginHistoryService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Records an attempt from `source`; `failure` is why it failed, if it did.
+    pub async fn record(&self, user_id: &Uuid, source: &str, failure: Option<&str>) -> Result<()> {
+        self.db
+            .create_login_attempt(&LoginAttempt {
+                id: Uuid::new_v4(),
+                user_id: *user_id,
+                succeeded: failure.is_none(),
+                failure_reason: failure.map(str::to_string),
+                source: source.to_string(),
+                user_agent: None,
+                created_at: Utc::now(),
+            })
+            .await
+    }
+
+    pub async fn recent(&self, user_id: &Uuid) -> Result<Vec<LoginAttempt>> {
+        self.db.get_login_attempts(user_id, RECENT_ATTEMPTS).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn attempts_are_listed_newest_first_per_user() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let bob = db.insert_test_user().await;
+        let service = LoginHistoryService::new(db);
+
+        service.record(&alice, "CLI on laptop", None).await.unwrap();
+        service.record(&alice, "CLI on unknown", Some("Invalid email/username or password")).await.unwrap();
+        service.record(&bob, "CLI", None).await.unwrap();
+
+        let attempts = service.recent(&alice).await.unwrap();
+        assert_eq!(attempts.len(), 2);
+        assert!(!attempts[0].succeeded);
+        assert_eq!(attempts[0].source, "CLI on unknown");
+        assert!(attempts[1].succeeded && attempts[1].failure_reason.is_none());
+    }
+}
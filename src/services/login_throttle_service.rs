@@ -0,0 +1,159 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::login_throttle::{LoginScope, LoginThrottle, DEFAULT_MAX_FAILURES};
+use crate::services::audit_service::AuditService;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// A source may fail this many times more often than one account before it
+/// is locked, since several people can log in from the same machine.
+const SOURCE_FAILURE_FACTOR: i64 = 4;
+
+/// The first lockout. Each further failure once locked doubles it.
+const BASE_LOCKOUT_SECS: i64 = 60;
+
+const MAX_LOCKOUT_SECS: i64 = 24 * 60 * 60;
+
+/// Failures further apart than this don't count as being in a row.
+const FAILURE_WINDOW_HOURS: i64 = 24;
+
+/// Counts failed logins per account and per source and locks either out for
+/// a while once there are too many in a row, doubling the lock each time.
+/// While locked, passwords aren't checked at all.
+pub struct LoginThrottleService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+    max_failures: i64,
+}
+
+impl LoginThrottleService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+            max_failures: configured_max_failures(),
+        }
+    }
+
+    /// Fails while any of `scopes` is locked out.
+    pub async fn check(&self, scopes: &[LoginScope], now: DateTime<Utc>) -> Result<()> {
+        for scope in scopes {
+            let Some(throttle) = self.db.get_login_throttle(&scope.key()).await? else {
+                continue;
+            };
+            if let Some(until) = throttle.locked_until.filter(|until| *until > now) {
+                let minutes = ((until - now).num_seconds() + 59) / 60;
+                return Err(AppError::AuthenticationError(format!(
+                    "Too many failed logins; try again in {} minute(s)",
+                    minutes
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts a failed login against each scope, locking those that reached
+    /// their limit. Returns when each newly locked scope unlocks.
+    pub async fn record_failure(&self, scopes: &[LoginScope], now: DateTime<Utc>) -> Result<Vec<(LoginScope, DateTime<Utc>)>> {
+        let mut locked = Vec::new();
+
+        for scope in scopes {
+            let key = scope.key();
+            let previous = self
+                .db
+                .get_login_throttle(&key)
+                .await?
+                .filter(|throttle| throttle.last_failure_at > now - Duration::hours(FAILURE_WINDOW_HOURS))
+                .map_or(0, |throttle| throttle.failures);
+            let failures = previous + 1;
+
+            let limit = match scope {
+                LoginScope::Account(_) => self.max_failures,
+                LoginScope::Source(_) => self.max_failures * SOURCE_FAILURE_FACTOR,
+            };
+            let locked_until = (failures >= limit).then(|| now + lockout(failures - limit));
+
+            self.db
+                .upsert_login_throttle(&LoginThrottle {
+                    key,
+                    failures,
+                    locked_until,
+                    last_failure_at: now,
+                })
+                .await?;
+
+            if let Some(until) = locked_until {
+                let user_id = match scope {
+                    LoginScope::Account(user_id) => Some(user_id),
+                    LoginScope::Source(_) => None,
+                };
+                let details = format!("{} after {} failed logins in a row, until {}", scope.key(), failures, until.to_rfc3339());
+                self.audit_service.record(user_id, AuditEvent::LoginLockedOut, &details).await?;
+                locked.push((scope.clone(), until));
+            }
+        }
+
+        Ok(locked)
+    }
+
+    /// Clears the account's failures after a full login. The source keeps its
+    /// count, so logging into one's own account doesn't reset an attack on others.
+    pub async fn record_success(&self, user_id: &Uuid) -> Result<()> {
+        self.db.delete_login_throttle(&LoginScope::Account(*user_id).key()).await
+    }
+}
+
+/// How long to lock for the `extra`th failure past the limit.
+fn lockout(extra: i64) -> Duration {
+    let secs = BASE_LOCKOUT_SECS.saturating_mul(1 << extra.clamp(0, 20));
+    Duration::seconds(secs.min(MAX_LOCKOUT_SECS))
+}
+
+fn configured_max_failures() -> i64 {
+    config::var("LOGIN_MAX_FAILURES")
+        .and_then(|failures| failures.parse::<i64>().ok())
+        .filter(|failures| *failures > 0)
+        .unwrap_or(DEFAULT_MAX_FAILURES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockouts_double_up_to_a_day() {
+        assert_eq!(lockout(0), Duration::minutes(1));
+        assert_eq!(lockout(3), Duration::minutes(8));
+        assert_eq!(lockout(40), Duration::hours(24));
+    }
+
+    #[tokio::test]
+    async fn locks_an_account_after_repeated_failures_until_it_expires() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = LoginThrottleService::new(db.clone());
+        let scopes = [LoginScope::Account(user), LoginScope::Source("CLI on test".to_string())];
+        let now = Utc::now();
+
+        for _ in 1..DEFAULT_MAX_FAILURES {
+            assert!(service.record_failure(&scopes, now).await.unwrap().is_empty());
+        }
+        service.check(&scopes, now).await.unwrap();
+
+        let locked = service.record_failure(&scopes, now).await.unwrap();
+        assert_eq!(locked, [(LoginScope::Account(user), now + Duration::minutes(1))]);
+        assert!(service.check(&scopes, now).await.is_err());
+        service.check(&scopes, now + Duration::minutes(2)).await.unwrap();
+
+        // The next failure in a row locks for twice as long.
+        let later = now + Duration::minutes(2);
+        let locked = service.record_failure(&scopes, later).await.unwrap();
+        assert_eq!(locked[0].1, later + Duration::minutes(2));
+
+        service.record_success(&user).await.unwrap();
+        service.check(&scopes, later).await.unwrap();
+        assert_eq!(db.get_login_throttle("source:CLI on test").await.unwrap().unwrap().failures, DEFAULT_MAX_FAILURES + 1);
+    }
+}
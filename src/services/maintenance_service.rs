@@ -0,0 +1,35 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::maintenance::MaintenanceReport;
+
+/// Routine database upkeep. Safe to run on a schedule while the wallet is in
+/// use; SQLite waits for the busy timeout if another writer holds the lock.
+pub struct MaintenanceService {
+    db: SqliteDatabase,
+}
+
+impl MaintenanceService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Checks integrity, then vacuums and analyzes. A damaged database is left
+    /// untouched so it can be recovered from as it is.
+    pub async fn run(&self) -> Result<MaintenanceReport> {
+        let size_before_bytes = self.db.size_bytes().await?;
+        let integrity_problems = self.db.integrity_check().await?;
+
+        let vacuumed = integrity_problems.is_empty();
+        if vacuumed {
+            self.db.vacuum().await?;
+            self.db.analyze().await?;
+        }
+
+        Ok(MaintenanceReport {
+            integrity_problems,
+            size_before_bytes,
+            size_after_bytes: self.db.size_bytes().await?,
+            vacuumed,
+        })
+    }
+}
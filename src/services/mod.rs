@@ -1 +1,58 @@
-pub mod user_service;
\ No newline at end of file
+pub mod account_cache_service;
+pub mod account_recovery_service;
+pub mod activity_service;
+pub mod admin_service;
+pub mod api_key_service;
+pub mod archive_service;
+pub mod asset_metadata_service;
+pub mod audit_service;
+pub mod breach_check_service;
+pub mod contact_import_service;
+pub mod contact_service;
+pub mod customer_field_service;
+pub mod data_export_service;
+pub mod email_change_service;
+pub mod email_service;
+pub mod email_template_service;
+pub mod event_bus;
+pub mod event_outbox_service;
+pub mod event_watch_service;
+pub mod hd_wallet_service;
+pub mod health_service;
+pub mod history_service;
+pub mod hook_service;
+pub mod idempotency_service;
+pub mod key_rotation_service;
+pub mod keystore_backup_service;
+pub mod keystore_service;
+pub mod login_history_service;
+pub mod login_throttle_service;
+pub mod maintenance_service;
+pub mod oauth_service;
+pub mod offline_service;
+pub mod passkey_service;
+pub mod password_change_service;
+pub mod payment_filter_service;
+pub mod payment_note_service;
+pub mod policy_service;
+pub mod price_service;
+pub mod rate_limit_service;
+pub mod reconciliation_service;
+pub mod remote_service;
+pub mod report_service;
+pub mod security_event_sink;
+pub mod session_service;
+pub mod stream_ticket_service;
+pub mod settings_service;
+pub mod signer_service;
+pub mod signing_pin_service;
+pub mod sms_service;
+pub mod token_service;
+pub mod transaction_export_service;
+pub mod transaction_service;
+pub mod two_factor_service;
+pub mod undo_service;
+pub mod user_service;
+pub mod wallet_health_service;
+pub mod webhook_endpoint_service;
+pub mod webhook_service;
\ No newline at end of file
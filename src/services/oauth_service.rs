@@ -0,0 +1,423 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::oauth::{LinkedIdentity, OAuthIdentity, OAuthProvider, OAUTH_STATE_TTL};
+use crate::services::audit_service::AuditService;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use rand_core::{OsRng, RngCore};
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Long enough for a slow provider, short enough not to hold a login open.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An OAuth app registered with a provider, and where that provider's
+/// endpoints are.
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    pub provider: OAuthProvider,
+    pub client_id: String,
+    pub client_secret: SecretString,
+    pub authorize_url: String,
+    pub token_url: String,
+    /// Who an access token belongs to: Google's userinfo, GitHub's `/user`.
+    pub userinfo_url: String,
+}
+
+impl OAuthClient {
+    /// The app registered with `provider`, at the provider's own endpoints.
+    pub fn new(provider: OAuthProvider, client_id: &str, client_secret: SecretString) -> Self {
+        let (authorize_url, token_url, userinfo_url) = match provider {
+            OAuthProvider::Google => (
+                "https://accounts.google.com/o/oauth2/v2/auth",
+                "https://oauth2.googleapis.com/token",
+                "https://openidconnect.googleapis.com/v1/userinfo",
+            ),
+            OAuthProvider::GitHub => (
+                "https://github.com/login/oauth/authorize",
+                "https://github.com/login/oauth/access_token",
+                "https://api.github.com/user",
+            ),
+        };
+
+        Self {
+            provider,
+            client_id: client_id.to_string(),
+            client_secret,
+            authorize_url: authorize_url.to_string(),
+            token_url: token_url.to_string(),
+            userinfo_url: userinfo_url.to_string(),
+        }
+    }
+
+    /// Reads `OAUTH_<PROVIDER>_CLIENT_ID` and `OAUTH_<PROVIDER>_CLIENT_SECRET`,
+    /// which is never taken from the config file. `None` when the id is unset.
+    fn from_env(provider: OAuthProvider) -> Result<Option<Self>> {
+        let prefix = format!("OAUTH_{}", provider.as_str().to_uppercase());
+        let Some(client_id) = config::var(&format!("{}_CLIENT_ID", prefix)) else {
+            return Ok(None);
+        };
+        let client_secret = env::var(format!("{}_CLIENT_SECRET", prefix))
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .ok_or_else(|| AppError::ValidationError(format!("{}_CLIENT_ID is set, so set {}_CLIENT_SECRET too", prefix, prefix)))?;
+
+        Ok(Some(Self::new(provider, &client_id, SecretString::from(client_secret))))
+    }
+
+    /// What to ask the user to share: who they are and their email address.
+    fn scope(&self) -> &'static str {
+        match self.provider {
+            OAuthProvider::Google => "openid email",
+            OAuthProvider::GitHub => "read:user user:email",
+        }
+    }
+}
+
+/// Logins through Google or GitHub for API clients. The user is sent to the
+/// provider with a single-use state and a PKCE challenge, comes back to
+/// `OAUTH_REDIRECT_URL` with an authorization code, and the client trades
+/// both for a login. A provider account logs in as the user it is linked to.
+/// The first time, it is linked to the user with the same email address,
+/// but only if the provider has verified that address: OAuth doesn't sign
+/// anyone up, since a wallet's keys are encrypted with its password.
+pub struct OAuthService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+    http: reqwest::Client,
+    redirect_url: String,
+    clients: Vec<OAuthClient>,
+}
+
+impl OAuthService {
+    /// The providers with an app configured, returning to
+    /// `OAUTH_REDIRECT_URL`; `None` when no provider is configured.
+    pub fn from_env(db: SqliteDatabase) -> Result<Option<Self>> {
+        let mut clients = Vec::new();
+        for provider in OAuthProvider::ALL {
+            clients.extend(OAuthClient::from_env(provider)?);
+        }
+        if clients.is_empty() {
+            return Ok(None);
+        }
+        let redirect_url = config::var("OAUTH_REDIRECT_URL").ok_or_else(|| {
+            AppError::ValidationError("Set OAUTH_REDIRECT_URL to the page OAuth providers send users back to".to_string())
+        })?;
+
+        Self::new(db, &redirect_url, clients).map(Some)
+    }
+
+    pub fn new(db: SqliteDatabase, redirect_url: &str, clients: Vec<OAuthClient>) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            // GitHub's API turns away requests without one.
+            .user_agent(concat!("stellar-wallet/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+            http,
+            redirect_url: redirect_url.to_string(),
+            clients,
+        })
+    }
+
+    /// Where to send the user's browser to log in with `provider`.
+    pub async fn authorization_url(&self, provider: OAuthProvider) -> Result<String> {
+        let client = self.client(provider)?;
+        let state = random_token();
+        let code_verifier = random_token();
+        let now = Utc::now();
+        self.db
+            .create_oauth_state(&hash(&state), provider, &code_verifier, now + OAUTH_STATE_TTL, now)
+            .await?;
+
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+        Ok(format!(
+            "{}?response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&code_challenge={}&code_challenge_method=S256",
+            client.authorize_url,
+            urlencoding::encode(&client.client_id),
+            urlencoding::encode(&self.redirect_url),
+            urlencoding::encode(client.scope()),
+            state,
+            challenge
+        ))
+    }
+
+    /// The user that `authorization_code`, handed back with `state`, logs in
+    /// as. Each state works once, within [`OAUTH_STATE_TTL`].
+    pub async fn complete(&self, provider: OAuthProvider, state: &str, authorization_code: &str) -> Result<Uuid> {
+        let client = self.client(provider)?;
+        let code_verifier = self.db.take_oauth_state(&hash(state.trim()), provider, Utc::now()).await?.ok_or_else(|| {
+            AppError::AuthenticationError("This OAuth login has expired or was already used; start it again".to_string())
+        })?;
+
+        let access_token = self.exchange(client, authorization_code.trim(), &code_verifier).await?;
+        let identity = self.identity(client, &access_token).await?;
+        self.user_for(&identity).await
+    }
+
+    fn client(&self, provider: OAuthProvider) -> Result<&OAuthClient> {
+        self.clients
+            .iter()
+            .find(|client| client.provider == provider)
+            .ok_or_else(|| AppError::ValidationError(format!("Logging in with {} isn't set up here", provider.display_name())))
+    }
+
+    /// Trades the authorization code for the provider's access token.
+    async fn exchange(&self, client: &OAuthClient, authorization_code: &str, code_verifier: &str) -> Result<String> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", authorization_code),
+            ("redirect_uri", &self.redirect_url),
+            ("client_id", &client.client_id),
+            ("client_secret", client.client_secret.expose_secret()),
+            ("code_verifier", code_verifier),
+        ];
+        let response = self
+            .http
+            .post(&client.token_url)
+            .header("Accept", "application/json")
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| unreachable_provider(client.provider, e))?;
+        let body: Value = response.json().await.map_err(|e| unreachable_provider(client.provider, e))?;
+
+        // GitHub reports a bad code with 200 OK, so the body decides.
+        match body["access_token"].as_str() {
+            Some(token) => Ok(token.to_string()),
+            None => Err(AppError::AuthenticationError(format!(
+                "{} didn't accept the authorization code: {}",
+                client.provider.display_name(),
+                body["error_description"].as_str().or(body["error"].as_str()).unwrap_or("no reason given")
+            ))),
+        }
+    }
+
+    async fn identity(&self, client: &OAuthClient, access_token: &str) -> Result<OAuthIdentity> {
+        let user = self.get_json(client, &client.userinfo_url, access_token).await?;
+        match client.provider {
+            OAuthProvider::Google => google_identity(&user),
+            OAuthProvider::GitHub => {
+                let emails = self.get_json(client, &format!("{}/emails", client.userinfo_url), access_token).await?;
+                github_identity(&user, &emails)
+            }
+        }
+    }
+
+    async fn get_json(&self, client: &OAuthClient, url: &str, access_token: &str) -> Result<Value> {
+        let response = self
+            .http
+            .get(url)
+            .bearer_auth(access_token)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| unreachable_provider(client.provider, e))?;
+        if !response.status().is_success() {
+            return Err(AppError::AuthenticationError(format!(
+                "{} wouldn't say who logged in (HTTP {})",
+                client.provider.display_name(),
+                response.status()
+            )));
+        }
+        response.json().await.map_err(|e| unreachable_provider(client.provider, e))
+    }
+
+    /// The user `identity` is linked to, linking it by email the first time.
+    async fn user_for(&self, identity: &OAuthIdentity) -> Result<Uuid> {
+        if let Some(linked) = self.db.get_oauth_identity(identity.provider, &identity.subject).await? {
+            return Ok(linked.user_id);
+        }
+
+        let name = identity.provider.display_name();
+        if !identity.email_verified {
+            return Err(AppError::AuthenticationError(format!(
+                "{} hasn't verified {}, so it can't be linked to an account here",
+                name, identity.email
+            )));
+        }
+        let user = self.db.get_user_by_email(&identity.email).await?.ok_or_else(|| {
+            AppError::AuthenticationError(format!(
+                "No account here uses {}; sign up with it first, then log in with {} to link them",
+                identity.email, name
+            ))
+        })?;
+
+        self.db
+            .insert_oauth_identity(&LinkedIdentity {
+                provider: identity.provider,
+                subject: identity.subject.clone(),
+                user_id: user.id,
+                email: identity.email.clone(),
+                created_at: Utc::now(),
+            })
+            .await?;
+        self.audit_service
+            .record(
+                Some(&user.id),
+                AuditEvent::OAuthLinked,
+                &format!("{} account {} ({})", identity.provider.as_str(), identity.subject, identity.email),
+            )
+            .await?;
+        Ok(user.id)
+    }
+}
+
+/// Google's OpenID Connect userinfo.
+fn google_identity(user: &Value) -> Result<OAuthIdentity> {
+    let (Some(subject), Some(email)) = (user["sub"].as_str(), user["email"].as_str()) else {
+        return Err(AppError::AuthenticationError("Google didn't share the account's id and email".to_string()));
+    };
+
+    Ok(OAuthIdentity {
+        provider: OAuthProvider::Google,
+        subject: subject.to_string(),
+        email: email.to_string(),
+        email_verified: user["email_verified"].as_bool().unwrap_or(false),
+    })
+}
+
+/// GitHub's `/user`, with the primary address from `/user/emails`, since the
+/// profile's public email may be unset or unverified.
+fn github_identity(user: &Value, emails: &Value) -> Result<OAuthIdentity> {
+    let subject = user["id"].as_i64().ok_or_else(|| AppError::AuthenticationError("GitHub didn't share the account's id".to_string()))?;
+    let primary = emails
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|email| email["primary"].as_bool() == Some(true))
+        .ok_or_else(|| AppError::AuthenticationError("GitHub didn't share the account's email".to_string()))?;
+
+    Ok(OAuthIdentity {
+        provider: OAuthProvider::GitHub,
+        subject: subject.to_string(),
+        email: primary["email"].as_str().unwrap_or_default().to_string(),
+        email_verified: primary["verified"].as_bool().unwrap_or(false),
+    })
+}
+
+fn unreachable_provider(provider: OAuthProvider, e: reqwest::Error) -> AppError {
+    AppError::InternalError(format!("Couldn't reach {}: {}", provider.display_name(), e))
+}
+
+/// 32 random bytes, URL-safe: long enough for a PKCE verifier.
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn hash(state: &str) -> String {
+    hex::encode(Sha256::digest(state.as_bytes()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::extract::{Form, State};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    /// A stand-in for Google that accepts the code `good-code` and says the
+    /// user is whoever the returned mutex holds.
+    pub(crate) async fn fake_google() -> (OAuthClient, Arc<Mutex<Value>>) {
+        let user = Arc::new(Mutex::new(json!({})));
+        let router = Router::new()
+            .route(
+                "/token",
+                post(|Form(form): Form<HashMap<String, String>>| async move {
+                    let verified = form.get("code_verifier").is_some_and(|verifier| verifier.len() == 43);
+                    Json(match form.get("code").map(String::as_str) {
+                        Some("good-code") if verified => json!({ "access_token": "google-token" }),
+                        _ => json!({ "error": "invalid_grant" }),
+                    })
+                }),
+            )
+            .route("/userinfo", get(|State(user): State<Arc<Mutex<Value>>>| async move { Json(user.lock().unwrap().clone()) }))
+            .with_state(user.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = OAuthClient {
+            authorize_url: format!("{}/auth", base),
+            token_url: format!("{}/token", base),
+            userinfo_url: format!("{}/userinfo", base),
+            ..OAuthClient::new(OAuthProvider::Google, "wallet-app", SecretString::from("app-secret"))
+        };
+        (client, user)
+    }
+
+    /// The `state` parameter of an authorization URL.
+    pub(crate) fn state_of(url: &str) -> String {
+        url.split(['?', '&']).find_map(|pair| pair.strip_prefix("state=")).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn links_verified_emails_once_and_logs_in_by_provider_id_after() {
+        let db = SqliteDatabase::in_memory().await;
+        let user_id = db.insert_test_user().await;
+        let email = db.get_user_by_id(&user_id).await.unwrap().unwrap().email;
+        let (client, google) = fake_google().await;
+        let oauth = OAuthService::new(db.clone(), "https://app.example.com/oauth", vec![client]).unwrap();
+        assert!(oauth.authorization_url(OAuthProvider::GitHub).await.is_err());
+
+        *google.lock().unwrap() = json!({ "sub": "g-1", "email": email, "email_verified": false });
+        let url = oauth.authorization_url(OAuthProvider::Google).await.unwrap();
+        assert!(url.contains("code_challenge_method=S256") && url.contains("redirect_uri=https%3A%2F%2Fapp.example.com%2Foauth"));
+        let err = oauth.complete(OAuthProvider::Google, &state_of(&url), "good-code").await.unwrap_err();
+        assert!(err.to_string().contains("hasn't verified"));
+
+        *google.lock().unwrap() = json!({ "sub": "g-1", "email": email, "email_verified": true });
+        let state = state_of(&oauth.authorization_url(OAuthProvider::Google).await.unwrap());
+        assert!(oauth.complete(OAuthProvider::Google, &state, "bad-code").await.is_err());
+        let state = state_of(&oauth.authorization_url(OAuthProvider::Google).await.unwrap());
+        assert_eq!(oauth.complete(OAuthProvider::Google, &state, "good-code").await.unwrap(), user_id);
+        assert!(oauth.complete(OAuthProvider::Google, &state, "good-code").await.is_err());
+        assert_eq!(db.get_oauth_identity(OAuthProvider::Google, "g-1").await.unwrap().unwrap().user_id, user_id);
+
+        // Linked by id now, whatever the address.
+        *google.lock().unwrap() = json!({ "sub": "g-1", "email": "renamed@example.com", "email_verified": false });
+        let state = state_of(&oauth.authorization_url(OAuthProvider::Google).await.unwrap());
+        assert_eq!(oauth.complete(OAuthProvider::Google, &state, "good-code").await.unwrap(), user_id);
+
+        *google.lock().unwrap() = json!({ "sub": "g-2", "email": "stranger@example.com", "email_verified": true });
+        let state = state_of(&oauth.authorization_url(OAuthProvider::Google).await.unwrap());
+        let err = oauth.complete(OAuthProvider::Google, &state, "good-code").await.unwrap_err();
+        assert!(err.to_string().contains("sign up with it first"));
+
+        *google.lock().unwrap() = json!({ "sub": "g-3", "email": email, "email_verified": true });
+        let state = state_of(&oauth.authorization_url(OAuthProvider::Google).await.unwrap());
+        assert!(matches!(oauth.complete(OAuthProvider::Google, &state, "good-code").await, Err(AppError::ConflictError(_))));
+    }
+
+    #[test]
+    fn reads_github_users_by_their_primary_email() {
+        let user = json!({ "id": 583231, "login": "octocat", "email": null });
+        let emails = json!([
+            { "email": "old@example.com", "primary": false, "verified": true },
+            { "email": "octocat@example.com", "primary": true, "verified": true },
+        ]);
+
+        let identity = github_identity(&user, &emails).unwrap();
+        assert_eq!((identity.subject.as_str(), identity.email.as_str()), ("583231", "octocat@example.com"));
+        assert!(identity.email_verified);
+        assert!(github_identity(&user, &json!([])).is_err());
+        assert!(google_identity(&json!({ "email": "no-sub@example.com" })).is_err());
+    }
+}
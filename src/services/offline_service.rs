@@ -0,0 +1,158 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::offline::{BalanceSnapshot, QueuedPayment};
+use crate::stellar::horizon::{BalanceRecord, HorizonClient};
+use crate::stellar::network::Network;
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// An account's balances, live from Horizon when it can be reached.
+pub enum Balances {
+    Live(Vec<BalanceRecord>),
+    /// The account exists locally but hasn't been funded on the network.
+    Unfunded,
+    /// Horizon couldn't be reached; these are the last balances seen.
+    Saved { snapshot: BalanceSnapshot, error: AppError },
+}
+
+/// Keeps the wallet usable without a connection: the last balances seen for
+/// each account, and payments queued to be reviewed and sent later. Queued
+/// payments are never signed here, so their sequence number is only taken
+/// when they are finally sent.
+pub struct OfflineService {
+    db: SqliteDatabase,
+    horizon: HorizonClient,
+    network: String,
+}
+
+impl OfflineService {
+    pub fn new(db: SqliteDatabase, network: &Network) -> Self {
+        Self {
+            db,
+            horizon: HorizonClient::new(&network.horizon_url),
+            network: network.name.clone(),
+        }
+    }
+
+    pub async fn is_online(&self) -> bool {
+        self.horizon.is_reachable().await
+    }
+
+    /// Loads `account`'s balances from Horizon and saves them, falling back to
+    /// the saved copy when Horizon can't be reached.
+    pub async fn balances(&self, account: &str) -> Result<Balances> {
+        match self.horizon.get_account(account).await {
+            Ok(Some(record)) => {
+                self.db
+                    .upsert_balance_snapshot(&BalanceSnapshot {
+                        network: self.network.clone(),
+                        account: account.to_string(),
+                        balances: record.balances.clone(),
+                        fetched_at: Utc::now(),
+                    })
+                    .await?;
+                Ok(Balances::Live(record.balances))
+            }
+            Ok(None) => Ok(Balances::Unfunded),
+            Err(error) => match self.db.get_balance_snapshot(&self.network, account).await? {
+                Some(snapshot) => Ok(Balances::Saved { snapshot, error }),
+                None => Err(error),
+            },
+        }
+    }
+
+    /// Saves a payment to send later. It is checked the same way as one sent
+    /// right away, except for anything that needs Horizon.
+    pub async fn queue_payment(&self, user_id: &Uuid, source: &str, destination: &str, amount: &str, memo: Option<&str>) -> Result<QueuedPayment> {
+        Validator::validate_stellar_address(destination)?;
+        Validator::validate_amount(amount)?;
+        if let Some(memo) = memo {
+            Validator::validate_memo(memo)?;
+        }
+
+        let payment = QueuedPayment {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            network: self.network.clone(),
+            source: source.to_string(),
+            destination: destination.to_string(),
+            amount: amount.to_string(),
+            memo: memo.map(str::to_string),
+            created_at: Utc::now(),
+        };
+        self.db.create_queued_payment(&payment).await?;
+        Ok(payment)
+    }
+
+    /// The user's queued payments on this network, oldest first.
+    pub async fn queued_payments(&self, user_id: &Uuid) -> Result<Vec<QueuedPayment>> {
+        self.db.get_queued_payments(user_id, &self.network).await
+    }
+
+    /// Takes a payment off the queue, once sent or discarded.
+    pub async fn remove_queued(&self, user_id: &Uuid, id: &Uuid) -> Result<()> {
+        if !self.db.delete_queued_payment(user_id, id).await? {
+            return Err(AppError::ValidationError("That payment is no longer queued".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unreachable_network() -> Network {
+        Network {
+            horizon_url: "http://127.0.0.1:9".to_string(),
+            ..Network::testnet()
+        }
+    }
+
+    #[tokio::test]
+    async fn offline_shows_saved_balances_and_keeps_the_queue_per_network() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = OfflineService::new(db.clone(), &unreachable_network());
+        let account = stellar_strkey::ed25519::PublicKey([1; 32]).to_string();
+        let destination = stellar_strkey::ed25519::PublicKey([2; 32]).to_string();
+
+        assert!(!service.is_online().await);
+        assert!(service.balances(&account).await.is_err());
+
+        let fetched_at = Utc::now();
+        db.upsert_balance_snapshot(&BalanceSnapshot {
+            network: "testnet".to_string(),
+            account: account.clone(),
+            balances: vec![BalanceRecord {
+                balance: "12.5000000".to_string(),
+                asset_type: "native".to_string(),
+                asset_code: None,
+                asset_issuer: None,
+            }],
+            fetched_at,
+        })
+        .await
+        .unwrap();
+        match service.balances(&account).await.unwrap() {
+            Balances::Saved { snapshot, .. } => {
+                assert_eq!(snapshot.balances[0].balance, "12.5000000");
+                assert_eq!(snapshot.fetched_at.timestamp(), fetched_at.timestamp());
+            }
+            _ => panic!("expected the saved balances"),
+        }
+
+        assert!(service.queue_payment(&user, &account, "GNOTANADDRESS", "1", None).await.is_err());
+        let queued = service.queue_payment(&user, &account, &destination, "5", Some("rent")).await.unwrap();
+        let public = OfflineService::new(db, &Network::public());
+        assert!(public.queued_payments(&user).await.unwrap().is_empty());
+
+        let pending = service.queued_payments(&user).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].amount.as_str(), pending[0].memo.as_deref()), ("5", Some("rent")));
+        service.remove_queued(&user, &queued.id).await.unwrap();
+        assert!(service.remove_queued(&user, &queued.id).await.is_err());
+        assert!(service.queued_payments(&user).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::cli::branding::Branding;
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::passkey::{ChallengePurpose, Passkey, PasskeyAssertion, PasskeyRegistration, PASSKEY_CHALLENGE_TTL};
+use crate::services::audit_service::AuditService;
+use crate::utils::webauthn::{self, AuthenticatorData, ClientData, SUPPORTED_ALGORITHMS};
+use chrono::Utc;
+use rand_core::{OsRng, RngCore};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Passkeys (WebAuthn credentials) for API clients in a browser. Once an
+/// account has one, password logins through the API need it as their second
+/// factor, or the authenticator code if 2FA is also on; a passkey that
+/// verified its user with a PIN or biometric logs in without a password.
+/// Credentials are scoped to `WEBAUTHN_RP_ID`, the site's domain, and only
+/// answers from pages on `WEBAUTHN_ORIGIN` are accepted.
+pub struct PasskeyService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+    rp_id: String,
+    origin: String,
+}
+
+impl PasskeyService {
+    /// Passkeys for `WEBAUTHN_RP_ID`, answered from `WEBAUTHN_ORIGIN`
+    /// (`https://` and the id when unset); `None` when no id is set.
+    pub fn from_env(db: SqliteDatabase) -> Option<Self> {
+        let rp_id = config::var("WEBAUTHN_RP_ID")?;
+        let origin = config::var("WEBAUTHN_ORIGIN").unwrap_or_else(|| format!("https://{}", rp_id));
+        Some(Self::new(db, &rp_id, &origin))
+    }
+
+    pub fn new(db: SqliteDatabase, rp_id: &str, origin: &str) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+            rp_id: rp_id.to_string(),
+            origin: origin.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The `publicKey` options for `navigator.credentials.create()` to add a
+    /// passkey to `user_id`, with a challenge that works once.
+    pub async fn registration_options(&self, user_id: &Uuid) -> Result<Value> {
+        let user = self
+            .db
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::ValidationError(format!("No user {}", user_id)))?;
+        let challenge = self.challenge(ChallengePurpose::Registration, Some(user_id)).await?;
+        let existing = self.db.get_passkeys(user_id).await?;
+
+        Ok(json!({
+            "challenge": challenge,
+            "rp": { "id": self.rp_id, "name": Branding::current().product_name },
+            "user": { "id": webauthn::encode(user_id.as_bytes()), "name": user.email, "displayName": user.username },
+            "pubKeyCredParams": SUPPORTED_ALGORITHMS.map(|alg| json!({ "type": "public-key", "alg": alg })),
+            "timeout": PASSKEY_CHALLENGE_TTL.num_milliseconds(),
+            "attestation": "none",
+            "authenticatorSelection": { "residentKey": "preferred", "userVerification": "preferred" },
+            "excludeCredentials": existing.iter().map(|passkey| json!({ "type": "public-key", "id": passkey.credential_id })).collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Saves the credential the browser created for `user_id` in answer to
+    /// [`PasskeyService::registration_options`].
+    pub async fn register(&self, user_id: &Uuid, name: &str, registration: &PasskeyRegistration) -> Result<Passkey> {
+        let client_data_json = webauthn::decode("clientDataJSON", &registration.response.client_data_json)?;
+        self.check_client_data(&client_data_json, "webauthn.create", ChallengePurpose::Registration, Some(user_id)).await?;
+
+        let attestation_object = webauthn::decode("attestationObject", &registration.response.attestation_object)?;
+        let data = AuthenticatorData::parse(&webauthn::attested_authenticator_data(&attestation_object)?)?;
+        self.check_authenticator_data(&data, false)?;
+        let credential = data
+            .credential
+            .ok_or_else(|| AppError::ValidationError("The passkey's response has no credential in it".to_string()))?;
+        if webauthn::encode(&credential.id) != registration.id.trim_end_matches('=') {
+            return Err(AppError::ValidationError("The passkey's response is for a different credential".to_string()));
+        }
+        webauthn::check_public_key(&credential.public_key)?;
+
+        let now = Utc::now();
+        let passkey = Passkey {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            credential_id: webauthn::encode(&credential.id),
+            public_key: credential.public_key,
+            sign_count: data.sign_count,
+            name: name.to_string(),
+            created_at: now,
+            last_used_at: None,
+        };
+        self.db.insert_passkey(&passkey).await?;
+        self.audit_service
+            .record(Some(user_id), AuditEvent::PasskeyAdded, &format!("passkey {} ('{}')", passkey.id, passkey.name))
+            .await?;
+        Ok(passkey)
+    }
+
+    pub async fn passkeys(&self, user_id: &Uuid) -> Result<Vec<Passkey>> {
+        self.db.get_passkeys(user_id).await
+    }
+
+    pub async fn has_passkeys(&self, user_id: &Uuid) -> Result<bool> {
+        Ok(!self.db.get_passkeys(user_id).await?.is_empty())
+    }
+
+    pub async fn remove(&self, user_id: &Uuid, id: &Uuid) -> Result<()> {
+        if !self.db.delete_passkey(user_id, id).await? {
+            return Err(AppError::ValidationError(format!("No passkey {}", id)));
+        }
+        self.audit_service.record(Some(user_id), AuditEvent::PasskeyRemoved, &format!("passkey {}", id)).await
+    }
+
+    /// The `publicKey` options for `navigator.credentials.get()`. Any of the
+    /// site's passkeys may answer, so the browser can offer the user theirs
+    /// before anyone has said who is logging in.
+    pub async fn authentication_options(&self) -> Result<Value> {
+        let challenge = self.challenge(ChallengePurpose::Authentication, None).await?;
+        Ok(json!({
+            "challenge": challenge,
+            "rpId": self.rp_id,
+            "timeout": PASSKEY_CHALLENGE_TTL.num_milliseconds(),
+            "userVerification": "preferred",
+            "allowCredentials": [],
+        }))
+    }
+
+    /// The passkey that signed `assertion`, an answer to
+    /// [`PasskeyService::authentication_options`]. With `expected_user`, it
+    /// must be one of theirs; with `require_user_verification`, the
+    /// authenticator must have checked a PIN or biometric.
+    pub async fn authenticate(
+        &self,
+        assertion: &PasskeyAssertion,
+        expected_user: Option<&Uuid>,
+        require_user_verification: bool,
+    ) -> Result<Passkey> {
+        let unknown = || AppError::AuthenticationError("This passkey isn't registered here".to_string());
+        let passkey = self
+            .db
+            .get_passkey_by_credential_id(assertion.id.trim().trim_end_matches('='))
+            .await?
+            .ok_or_else(unknown)?;
+        if expected_user.is_some_and(|user_id| *user_id != passkey.user_id) {
+            return Err(unknown());
+        }
+        if let Some(handle) = &assertion.response.user_handle {
+            if webauthn::decode("userHandle", handle)? != passkey.user_id.as_bytes() {
+                return Err(AppError::AuthenticationError("The passkey's response is for a different user".to_string()));
+            }
+        }
+
+        let client_data_json = webauthn::decode("clientDataJSON", &assertion.response.client_data_json)?;
+        self.check_client_data(&client_data_json, "webauthn.get", ChallengePurpose::Authentication, None).await?;
+        let auth_data = webauthn::decode("authenticatorData", &assertion.response.authenticator_data)?;
+        let data = AuthenticatorData::parse(&auth_data)?;
+        self.check_authenticator_data(&data, require_user_verification)?;
+
+        let mut signed = auth_data;
+        signed.extend(Sha256::digest(&client_data_json));
+        webauthn::verify(&passkey.public_key, &signed, &webauthn::decode("signature", &assertion.response.signature)?)?;
+
+        // A counter that didn't go up means the key was copied.
+        if (data.sign_count != 0 || passkey.sign_count != 0) && data.sign_count <= passkey.sign_count {
+            return Err(AppError::AuthenticationError(
+                "This passkey's signature counter went backwards, so it may have been cloned; remove it and register it again".to_string(),
+            ));
+        }
+        self.db.record_passkey_use(&passkey.id, data.sign_count, Utc::now()).await?;
+        Ok(passkey)
+    }
+
+    async fn challenge(&self, purpose: ChallengePurpose, user_id: Option<&Uuid>) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let challenge = webauthn::encode(&bytes);
+
+        let now = Utc::now();
+        self.db
+            .create_webauthn_challenge(&hash(&challenge), purpose, user_id, now + PASSKEY_CHALLENGE_TTL, now)
+            .await?;
+        Ok(challenge)
+    }
+
+    /// Checks the browser's `clientDataJSON` is for `kind` from our origin,
+    /// and uses up the challenge it answers.
+    async fn check_client_data(&self, json: &[u8], kind: &str, purpose: ChallengePurpose, user_id: Option<&Uuid>) -> Result<()> {
+        let client_data = ClientData::parse(json)?;
+        if client_data.kind != kind {
+            return Err(AppError::AuthenticationError(format!("Expected a '{}' response from the passkey", kind)));
+        }
+        if client_data.origin != self.origin {
+            return Err(AppError::AuthenticationError(format!(
+                "The passkey answered for {}, not {}",
+                client_data.origin, self.origin
+            )));
+        }
+
+        let challenge = client_data.challenge.trim_end_matches('=');
+        if !self.db.take_webauthn_challenge(&hash(challenge), purpose, user_id, Utc::now()).await? {
+            return Err(AppError::AuthenticationError(
+                "This passkey challenge has expired or was already used; start again".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_authenticator_data(&self, data: &AuthenticatorData, require_user_verification: bool) -> Result<()> {
+        if data.rp_id_hash.as_slice() != Sha256::digest(self.rp_id.as_bytes()).as_slice() {
+            return Err(AppError::AuthenticationError(format!("The passkey isn't for {}", self.rp_id)));
+        }
+        if !data.user_present {
+            return Err(AppError::AuthenticationError("The passkey answered without the user present".to_string()));
+        }
+        if require_user_verification && !data.user_verified {
+            return Err(AppError::AuthenticationError(
+                "Logging in with only a passkey needs one that checks a PIN or biometric".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn hash(challenge: &str) -> String {
+    hex::encode(Sha256::digest(challenge.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::webauthn::tests::SoftAuthenticator;
+
+    const RP_ID: &str = "wallet.example.com";
+    const ORIGIN: &str = "https://wallet.example.com";
+
+    #[tokio::test]
+    async fn registers_passkeys_and_accepts_each_signed_challenge_once() {
+        let db = SqliteDatabase::in_memory().await;
+        let user_id = db.insert_test_user().await;
+        let passkeys = PasskeyService::new(db.clone(), RP_ID, ORIGIN);
+        let mut authenticator = SoftAuthenticator::new(RP_ID, ORIGIN);
+
+        // A challenge is only for the user it was issued to, and only once.
+        let registration = authenticator.register(&passkeys.registration_options(&user_id).await.unwrap());
+        assert!(passkeys.register(&Uuid::new_v4(), "stolen", &registration).await.is_err());
+        assert!(passkeys.register(&user_id, "laptop", &registration).await.is_err());
+
+        let options = passkeys.registration_options(&user_id).await.unwrap();
+        assert_eq!(options["user"]["id"], webauthn::encode(user_id.as_bytes()));
+        let passkey = passkeys.register(&user_id, "laptop", &authenticator.register(&options)).await.unwrap();
+        assert!(passkeys.has_passkeys(&user_id).await.unwrap());
+        let options = passkeys.registration_options(&user_id).await.unwrap();
+        assert_eq!(options["excludeCredentials"][0]["id"], passkey.credential_id);
+        let err = passkeys.register(&user_id, "again", &authenticator.register(&options)).await.unwrap_err();
+        assert!(matches!(err, AppError::ConflictError(_)));
+
+        let handle = webauthn::encode(user_id.as_bytes());
+        let options = passkeys.authentication_options().await.unwrap();
+        let assertion = authenticator.assert(&options, Some(&handle));
+        assert!(passkeys.authenticate(&assertion, Some(&Uuid::new_v4()), false).await.is_err());
+        let options = passkeys.authentication_options().await.unwrap();
+        let assertion = authenticator.assert(&options, Some(&handle));
+        assert_eq!(passkeys.authenticate(&assertion, Some(&user_id), true).await.unwrap().id, passkey.id);
+        assert!(passkeys.authenticate(&assertion, Some(&user_id), true).await.is_err());
+
+        authenticator.user_verified = false;
+        let options = passkeys.authentication_options().await.unwrap();
+        let assertion = authenticator.assert(&options, None);
+        assert!(passkeys.authenticate(&assertion, None, true).await.is_err());
+        let options = passkeys.authentication_options().await.unwrap();
+        let assertion = authenticator.assert(&options, None);
+        assert_eq!(passkeys.authenticate(&assertion, None, false).await.unwrap().user_id, user_id);
+
+        authenticator.sign_count = 1;
+        let options = passkeys.authentication_options().await.unwrap();
+        let err = passkeys.authenticate(&authenticator.assert(&options, None), None, false).await.unwrap_err();
+        assert!(err.to_string().contains("cloned"));
+
+        passkeys.remove(&user_id, &passkey.id).await.unwrap();
+        assert!(passkeys.remove(&user_id, &passkey.id).await.is_err());
+        assert!(!passkeys.has_passkeys(&user_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn refuses_answers_from_other_sites() {
+        let db = SqliteDatabase::in_memory().await;
+        let user_id = db.insert_test_user().await;
+        let passkeys = PasskeyService::new(db.clone(), RP_ID, ORIGIN);
+
+        let phished = SoftAuthenticator::new(RP_ID, "https://wallet.example.net");
+        let options = passkeys.registration_options(&user_id).await.unwrap();
+        assert!(passkeys.register(&user_id, "phished", &phished.register(&options)).await.is_err());
+
+        let elsewhere = SoftAuthenticator::new("example.net", ORIGIN);
+        let options = passkeys.registration_options(&user_id).await.unwrap();
+        assert!(passkeys.register(&user_id, "elsewhere", &elsewhere.register(&options)).await.is_err());
+        assert!(!passkeys.has_passkeys(&user_id).await.unwrap());
+    }
+}
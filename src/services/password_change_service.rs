@@ -0,0 +1,136 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::password_change::PasswordUpdate;
+use crate::services::audit_service::AuditService;
+use crate::services::keystore_service::KeystoreService;
+use crate::services::user_service::UserService;
+use crate::utils::crypto::PasswordManager;
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// What changing the password touched besides the password itself.
+pub struct PasswordChange {
+    /// Keystore keys encrypted again with the new password.
+    pub reencrypted_keys: usize,
+    /// Keys the current password couldn't open, e.g. ones left behind by an
+    /// account recovery. They are left as they are.
+    pub skipped_keys: Vec<String>,
+    pub revoked_sessions: u64,
+}
+
+/// Changes a logged-in user's password. Keystore keys and the recovery phrase
+/// are encrypted with the login password, so they are all encrypted again with
+/// the new one before it takes effect.
+pub struct PasswordChangeService {
+    db: SqliteDatabase,
+    user_service: UserService,
+    keystore_service: KeystoreService,
+    audit_service: AuditService,
+}
+
+impl PasswordChangeService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            user_service: UserService::new(db.clone()),
+            keystore_service: KeystoreService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Replaces the password and ends every session but `current_session`.
+    pub async fn change(&self, user_id: &Uuid, current_session: &Uuid, current: &str, new_password: &str) -> Result<PasswordChange> {
+        Validator::validate_password(new_password)?;
+        if new_password == current {
+            return Err(AppError::ValidationError("The new password must differ from the current one".to_string()));
+        }
+        self.user_service.verify_password(user_id, current).await?;
+
+        let mut keystore_entries = Vec::new();
+        let mut skipped_keys = Vec::new();
+        for entry in self.db.get_keystore_entries_by_user(user_id).await? {
+            match self.keystore_service.unlock_keypair(user_id, &entry.public_key, current).await {
+                Ok(keypair) => keystore_entries.push(KeystoreService::reencrypt(entry, &keypair, new_password)?),
+                Err(AppError::AuthenticationError(_)) => skipped_keys.push(entry.public_key),
+                Err(e) => return Err(e),
+            }
+        }
+        let recovery_phrase = match self.keystore_service.unlock_phrase(user_id, current).await? {
+            Some(phrase) => {
+                let stored = self
+                    .db
+                    .get_recovery_phrase(user_id)
+                    .await?
+                    .ok_or_else(|| AppError::InternalError("Recovery phrase disappeared".to_string()))?;
+                Some(KeystoreService::reencrypt_phrase(stored, &phrase, new_password)?)
+            }
+            None => None,
+        };
+        let reencrypted_keys = keystore_entries.len();
+
+        let update = PasswordUpdate {
+            user_id: *user_id,
+            password_hash: PasswordManager::hash_password(new_password)?,
+            keystore_entries,
+            recovery_phrase,
+            recovery_code: None,
+            keep_session: Some(*current_session),
+        };
+        let revoked_sessions = self.db.apply_password_update(&update, Utc::now()).await?.unwrap_or_default();
+
+        self.audit_service
+            .record(
+                Some(user_id),
+                AuditEvent::PasswordChanged,
+                &format!("{} key(s) re-encrypted, {} other session(s) ended", reencrypted_keys, revoked_sessions),
+            )
+            .await?;
+
+        Ok(PasswordChange {
+            reencrypted_keys,
+            skipped_keys,
+            revoked_sessions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::session_service::SessionService;
+    use crate::stellar::keypair::Keypair;
+
+    #[tokio::test]
+    async fn keys_and_sessions_follow_the_new_password() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        db.update_user_password_hash(&user, &PasswordManager::hash_password("Velvet-Otter-92!").unwrap())
+            .await
+            .unwrap();
+        let keystore = KeystoreService::new(db.clone());
+        let sessions = SessionService::new(db.clone());
+        let service = PasswordChangeService::new(db.clone());
+
+        let keypair = Keypair::random();
+        keystore.store_keypair(&user, &keypair, "Velvet-Otter-92!", None).await.unwrap();
+        let stale = Keypair::random();
+        keystore.store_keypair(&user, &stale, "an older password", None).await.unwrap();
+        let here = sessions.start(&user, "here").await.unwrap();
+        let elsewhere = sessions.start(&user, "elsewhere").await.unwrap();
+
+        assert!(service.change(&user, &here.id, "wrong", "Harbor-Lantern-37!").await.is_err());
+        assert!(service.change(&user, &here.id, "Velvet-Otter-92!", "weak").await.is_err());
+
+        let change = service.change(&user, &here.id, "Velvet-Otter-92!", "Harbor-Lantern-37!").await.unwrap();
+        assert_eq!(change.reencrypted_keys, 1);
+        assert_eq!(change.skipped_keys, vec![stale.public_key()]);
+        assert_eq!(change.revoked_sessions, 1);
+
+        assert!(keystore.unlock_keypair(&user, &keypair.public_key(), "Harbor-Lantern-37!").await.is_ok());
+        assert!(keystore.unlock_keypair(&user, &keypair.public_key(), "Velvet-Otter-92!").await.is_err());
+        assert!(sessions.validate(&here.id).await.is_ok());
+        assert!(sessions.validate(&elsewhere.id).await.is_err());
+    }
+}
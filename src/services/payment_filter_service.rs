@@ -0,0 +1,148 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::payment_filter::{HiddenReason, PaymentFilterSettings};
+use crate::models::transaction::{TransactionDirection, WalletTransaction};
+use chrono::Utc;
+use regex::Regex;
+use uuid::Uuid;
+
+/// Memo patterns used by airdrop and phishing spam: links and "claim your reward" bait.
+const SCAM_MEMO_PATTERN: &str =
+    r"(?i)(https?://|www\.|\.(com|io|net|org|xyz|app|finance|top|click)\b|airdrop|claim|reward|bonus|giveaway|free\s*xlm|gift)";
+
+pub struct PaymentFilterService {
+    db: SqliteDatabase,
+}
+
+impl PaymentFilterService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    pub async fn settings(&self, user_id: &Uuid) -> Result<PaymentFilterSettings> {
+        Ok(self
+            .db
+            .get_payment_filter_settings(user_id)
+            .await?
+            .unwrap_or_else(|| PaymentFilterSettings::defaults_for(*user_id)))
+    }
+
+    pub async fn update_settings(&self, user_id: &Uuid, dust_threshold_stroops: i64, hide_scam_memos: bool) -> Result<PaymentFilterSettings> {
+        let settings = PaymentFilterSettings {
+            user_id: *user_id,
+            dust_threshold_stroops,
+            hide_scam_memos,
+            updated_at: Utc::now(),
+        };
+
+        self.db.upsert_payment_filter_settings(&settings).await?;
+        Ok(settings)
+    }
+
+    /// The filter for the user's current settings.
+    pub async fn filter(&self, user_id: &Uuid) -> Result<PaymentFilter> {
+        PaymentFilter::new(&self.settings(user_id).await?)
+    }
+}
+
+/// Applies a user's filter settings to individual payments.
+pub struct PaymentFilter {
+    dust_threshold_stroops: i64,
+    scam_memo: Option<Regex>,
+}
+
+impl PaymentFilter {
+    pub fn new(settings: &PaymentFilterSettings) -> Result<Self> {
+        let scam_memo = if settings.hide_scam_memos {
+            Some(Regex::new(SCAM_MEMO_PATTERN).map_err(|e| AppError::InternalError(format!("Regex error: {}", e)))?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            dust_threshold_stroops: settings.dust_threshold_stroops,
+            scam_memo,
+        })
+    }
+
+    /// Only incoming payments are ever hidden; the user's own outgoing payments
+    /// always show.
+    pub fn hidden_reason(&self, tx: &WalletTransaction) -> Option<HiddenReason> {
+        if tx.direction != TransactionDirection::Incoming {
+            return None;
+        }
+
+        if let (Some(pattern), Some(memo)) = (&self.scam_memo, tx.memo.as_deref()) {
+            if pattern.is_match(memo) {
+                return Some(HiddenReason::ScamMemo);
+            }
+        }
+
+        let is_dust = tx.asset_code == "XLM" && tx.amount_stroops < self.dust_threshold_stroops;
+
+        is_dust.then_some(HiddenReason::Dust)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::TransactionStatus;
+    use crate::stellar::amount::parse_stroops;
+
+    fn payment(direction: TransactionDirection, amount: &str, memo: Option<&str>) -> WalletTransaction {
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: "GME".to_string(),
+            hash: "aa".to_string(),
+            operation_index: 1,
+            direction,
+            asset_code: "XLM".to_string(),
+            amount_stroops: parse_stroops(amount).unwrap(),
+            counterparty: "GTHEM".to_string(),
+            memo: memo.map(str::to_string),
+            status: TransactionStatus::Confirmed,
+            ledger: None,
+            error: None,
+            request_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn incoming(amount: &str, memo: Option<&str>) -> WalletTransaction {
+        payment(TransactionDirection::Incoming, amount, memo)
+    }
+
+    fn filter(dust: i64, hide_scam_memos: bool) -> PaymentFilter {
+        let mut settings = PaymentFilterSettings::defaults_for(Uuid::new_v4());
+        settings.dust_threshold_stroops = dust;
+        settings.hide_scam_memos = hide_scam_memos;
+        PaymentFilter::new(&settings).unwrap()
+    }
+
+    #[test]
+    fn hides_incoming_dust_below_threshold() {
+        let dust_filter = filter(10_000, true);
+
+        assert_eq!(dust_filter.hidden_reason(&incoming("0.0000100", None)), Some(HiddenReason::Dust));
+        assert_eq!(dust_filter.hidden_reason(&incoming("0.0010000", None)), None);
+        assert_eq!(filter(0, true).hidden_reason(&incoming("0.0000001", None)), None);
+    }
+
+    #[test]
+    fn hides_scam_memos_when_enabled() {
+        let spam = incoming("5", Some("Claim 500 XLM at stellar-gift.com"));
+
+        assert_eq!(filter(0, true).hidden_reason(&spam), Some(HiddenReason::ScamMemo));
+        assert_eq!(filter(0, false).hidden_reason(&spam), None);
+        assert_eq!(filter(0, true).hidden_reason(&incoming("5", Some("rent march"))), None);
+    }
+
+    #[test]
+    fn never_hides_outgoing_payments() {
+        let outgoing = payment(TransactionDirection::Outgoing, "0.0000001", Some("www.example.com"));
+
+        assert_eq!(filter(10_000, true).hidden_reason(&outgoing), None);
+    }
+}
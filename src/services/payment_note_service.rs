@@ -0,0 +1,143 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::payment_note::{PaymentNote, MAX_NOTE_LENGTH};
+use crate::services::keystore_service::KeystoreService;
+use crate::stellar::keypair::Keypair;
+use crate::stellar::signer::Signer;
+use crate::utils::crypto::{EncryptedNote, NoteCipher};
+use chrono::Utc;
+use uuid::Uuid;
+
+/// End-to-end encrypted notes between wallet users, attached to payments. The
+/// server only ever stores ciphertext; reading a note needs one of the two
+/// accounts' secret keys.
+pub struct PaymentNoteService {
+    db: SqliteDatabase,
+    keystore_service: KeystoreService,
+}
+
+impl PaymentNoteService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            keystore_service: KeystoreService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// The other wallet user who can read notes sent to `destination`, if any.
+    pub async fn recipient(&self, sender_user_id: &Uuid, destination: &str) -> Result<Option<Uuid>> {
+        Ok(self
+            .db
+            .get_keystore_owner(destination)
+            .await?
+            .filter(|owner| owner != sender_user_id))
+    }
+
+    /// Encrypts `text` for the recipient of an already-submitted payment.
+    pub async fn attach(
+        &self,
+        sender_user_id: &Uuid,
+        signer: &dyn Signer,
+        recipient_user_id: &Uuid,
+        recipient_public_key: &str,
+        transaction_hash: &str,
+        text: &str,
+    ) -> Result<PaymentNote> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_NOTE_LENGTH {
+            return Err(AppError::ValidationError(format!(
+                "Notes must be between 1 and {} characters",
+                MAX_NOTE_LENGTH
+            )));
+        }
+
+        let mut note = PaymentNote {
+            id: Uuid::new_v4(),
+            transaction_hash: transaction_hash.to_string(),
+            sender_user_id: *sender_user_id,
+            sender_public_key: signer.public_key(),
+            recipient_user_id: *recipient_user_id,
+            recipient_public_key: recipient_public_key.to_string(),
+            note: EncryptedNote {
+                ciphertext: String::new(),
+                nonce: String::new(),
+            },
+            created_at: Utc::now(),
+        };
+
+        let shared_secret = signer.shared_secret(recipient_public_key)?;
+        note.note = NoteCipher::seal(text.as_bytes(), &shared_secret, &note.context())?;
+
+        self.db.create_payment_note(&note).await?;
+        Ok(note)
+    }
+
+    pub async fn notes(&self, user_id: &Uuid) -> Result<Vec<PaymentNote>> {
+        self.db.get_payment_notes_for_user(user_id).await
+    }
+
+    /// Decrypts the keystore key that can open notes for `public_key`.
+    pub async fn unlock(&self, user_id: &Uuid, public_key: &str, password: &str) -> Result<Keypair> {
+        self.keystore_service.unlock_keypair(user_id, public_key, password).await
+    }
+
+    /// Decrypts a note with our side's key.
+    pub fn read(&self, note: &PaymentNote, keypair: &Keypair) -> Result<String> {
+        let other = if keypair.public_key() == note.sender_public_key {
+            &note.recipient_public_key
+        } else {
+            &note.sender_public_key
+        };
+
+        let plaintext = NoteCipher::open(&note.note, &*keypair.shared_secret(other)?, &note.context())?;
+        std::str::from_utf8(&plaintext).map(str::to_string).map_err(|_| AppError::InternalError("Note is not valid UTF-8".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_the_two_parties_can_read_a_note() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let bob = db.insert_test_user().await;
+        let keystore = KeystoreService::new(db.clone());
+        let notes = PaymentNoteService::new(db);
+
+        let alice_key = Keypair::random();
+        let bob_key = Keypair::random();
+        keystore.store_keypair(&bob, &bob_key, "Passw0rd!", None).await.unwrap();
+
+        assert_eq!(notes.recipient(&alice, &bob_key.public_key()).await.unwrap(), Some(bob));
+        assert_eq!(notes.recipient(&bob, &bob_key.public_key()).await.unwrap(), None);
+
+        notes
+            .attach(&alice, &alice_key, &bob, &bob_key.public_key(), "abc123", "rent for May")
+            .await
+            .unwrap();
+
+        let received = notes.notes(&bob).await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert!(!received[0].note.ciphertext.contains(&hex::encode("rent")));
+
+        let bob_unlocked = notes.unlock(&bob, &bob_key.public_key(), "Passw0rd!").await.unwrap();
+        assert_eq!(notes.read(&received[0], &bob_unlocked).unwrap(), "rent for May");
+        assert_eq!(notes.read(&received[0], &alice_key).unwrap(), "rent for May");
+        assert!(notes.read(&received[0], &Keypair::random()).is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_and_overlong_notes() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let bob = db.insert_test_user().await;
+        let notes = PaymentNoteService::new(db);
+        let bob_key = Keypair::random().public_key();
+
+        for text in ["  ", &"x".repeat(MAX_NOTE_LENGTH + 1)] {
+            assert!(notes.attach(&alice, &Keypair::random(), &bob, &bob_key, "abc", text).await.is_err());
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::policy::{AllowlistEntry, PaymentIntent, PolicyRule, PolicyViolation, TransactionPolicy};
+use crate::services::audit_service::AuditService;
+use crate::stellar::amount::{format_stroops, parse_stroops};
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Per-asset and per-destination rules checked before a payment is signed.
+pub struct PolicyService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+}
+
+impl PolicyService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// Policies in force for `owner` (global ones included), or just the global
+    /// ones when `owner` is `None`.
+    pub async fn policies(&self, owner: Option<&Uuid>) -> Result<Vec<TransactionPolicy>> {
+        self.db.get_policies(owner).await
+    }
+
+    pub async fn allowlist(&self, owner: Option<&Uuid>) -> Result<Vec<AllowlistEntry>> {
+        self.db.get_allowlist(owner).await
+    }
+
+    pub async fn add_max_amount(&self, owner: Option<&Uuid>, asset_code: &str, max_amount: &str) -> Result<TransactionPolicy> {
+        let asset_code = asset_code.trim().to_uppercase();
+        if asset_code.is_empty() || asset_code.len() > 12 || !asset_code.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(AppError::ValidationError("Asset code must be 1-12 letters or digits".to_string()));
+        }
+
+        let policy = TransactionPolicy {
+            id: Uuid::new_v4(),
+            user_id: owner.copied(),
+            rule: PolicyRule::MaxAmount {
+                asset_code,
+                max_stroops: parse_stroops(max_amount.trim())?,
+            },
+            created_at: Utc::now(),
+        };
+
+        self.db.create_policy(&policy).await?;
+        Ok(policy)
+    }
+
+    /// `network` limits the rule to one network (e.g. `public`); `None` applies it everywhere.
+    pub async fn add_allowlist_only(&self, owner: Option<&Uuid>, network: Option<&str>) -> Result<TransactionPolicy> {
+        let policy = TransactionPolicy {
+            id: Uuid::new_v4(),
+            user_id: owner.copied(),
+            rule: PolicyRule::AllowlistOnly {
+                network: network.map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty()),
+            },
+            created_at: Utc::now(),
+        };
+
+        self.db.create_policy(&policy).await?;
+        Ok(policy)
+    }
+
+    pub async fn remove_policy(&self, owner: Option<&Uuid>, policy_id: &Uuid) -> Result<()> {
+        if !self.db.delete_policy(owner, policy_id).await? {
+            return Err(AppError::ValidationError("Policy not found".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn allow_destination(&self, owner: Option<&Uuid>, address: &str, label: Option<&str>) -> Result<AllowlistEntry> {
+        let address = address.trim();
+        Validator::validate_stellar_address(address)?;
+
+        let entry = AllowlistEntry {
+            id: Uuid::new_v4(),
+            user_id: owner.copied(),
+            address: address.to_string(),
+            label: label.map(str::trim).filter(|l| !l.is_empty()).map(str::to_string),
+            created_at: Utc::now(),
+        };
+
+        self.db.create_allowlist_entry(&entry).await?;
+        Ok(entry)
+    }
+
+    pub async fn remove_destination(&self, owner: Option<&Uuid>, entry_id: &Uuid) -> Result<()> {
+        if !self.db.delete_allowlist_entry(owner, entry_id).await? {
+            return Err(AppError::ValidationError("Allowlist entry not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Checks `intent` against every policy that applies to `user_id`. Each
+    /// violation is written to the audit log, and any violation blocks the payment.
+    pub async fn check(&self, user_id: &Uuid, intent: &PaymentIntent) -> Result<()> {
+        let policies = self.db.get_policies(Some(user_id)).await?;
+        let allowlist = self.db.get_allowlist(Some(user_id)).await?;
+
+        let violations = evaluate(&policies, &allowlist, intent);
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        for violation in &violations {
+            self.audit_service
+                .record(
+                    Some(user_id),
+                    AuditEvent::PolicyViolation,
+                    &format!("policy {}: {}", violation.policy_id, violation.message),
+                )
+                .await?;
+        }
+
+        let messages: Vec<String> = violations.into_iter().map(|v| v.message).collect();
+        Err(AppError::ValidationError(format!("Blocked by policy: {}", messages.join("; "))))
+    }
+}
+
+/// Every rule in `policies` that `intent` breaks.
+pub fn evaluate(policies: &[TransactionPolicy], allowlist: &[AllowlistEntry], intent: &PaymentIntent) -> Vec<PolicyViolation> {
+    policies
+        .iter()
+        .filter_map(|policy| {
+            let message = match &policy.rule {
+                PolicyRule::MaxAmount { asset_code, max_stroops } => {
+                    if !asset_code.eq_ignore_ascii_case(&intent.asset_code) || intent.amount_stroops <= *max_stroops {
+                        return None;
+                    }
+                    format!(
+                        "{} {} is above the {} {} limit",
+                        format_stroops(intent.amount_stroops),
+                        intent.asset_code,
+                        format_stroops(*max_stroops),
+                        asset_code
+                    )
+                }
+                PolicyRule::AllowlistOnly { network } => {
+                    if network.as_deref().is_some_and(|n| !n.eq_ignore_ascii_case(&intent.network)) {
+                        return None;
+                    }
+                    if allowlist.iter().any(|entry| entry.address == intent.destination) {
+                        return None;
+                    }
+                    format!("{} is not on the destination allowlist for {}", intent.destination, intent.network)
+                }
+            };
+
+            Some(PolicyViolation {
+                policy_id: policy.id,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: &str = "GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7";
+    const OTHER: &str = "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H";
+
+    fn policy(rule: PolicyRule) -> TransactionPolicy {
+        TransactionPolicy {
+            id: Uuid::new_v4(),
+            user_id: None,
+            rule,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn allowed(address: &str) -> AllowlistEntry {
+        AllowlistEntry {
+            id: Uuid::new_v4(),
+            user_id: None,
+            address: address.to_string(),
+            label: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn intent(destination: &str, asset_code: &str, amount: &str, network: &str) -> PaymentIntent {
+        PaymentIntent {
+            destination: destination.to_string(),
+            asset_code: asset_code.to_string(),
+            amount_stroops: parse_stroops(amount).unwrap(),
+            network: network.to_string(),
+        }
+    }
+
+    #[test]
+    fn max_amount_only_applies_to_its_asset() {
+        let policies = vec![policy(PolicyRule::MaxAmount {
+            asset_code: "XLM".to_string(),
+            max_stroops: parse_stroops("100").unwrap(),
+        })];
+
+        assert!(evaluate(&policies, &[], &intent(OTHER, "XLM", "100", "testnet")).is_empty());
+        assert!(evaluate(&policies, &[], &intent(OTHER, "USDC", "500", "testnet")).is_empty());
+
+        let violations = evaluate(&policies, &[], &intent(OTHER, "xlm", "100.5", "testnet"));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].policy_id, policies[0].id);
+        assert!(violations[0].message.contains("above the 100.0000000 XLM limit"));
+    }
+
+    #[test]
+    fn allowlist_only_blocks_unknown_destinations() {
+        let policies = vec![policy(PolicyRule::AllowlistOnly { network: None })];
+        let allowlist = vec![allowed(ALLOWED)];
+
+        assert!(evaluate(&policies, &allowlist, &intent(ALLOWED, "XLM", "1", "testnet")).is_empty());
+
+        let violations = evaluate(&policies, &allowlist, &intent(OTHER, "XLM", "1", "testnet"));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].message.contains("not on the destination allowlist"));
+    }
+
+    #[test]
+    fn network_scoped_allowlist_ignores_other_networks() {
+        let policies = vec![policy(PolicyRule::AllowlistOnly {
+            network: Some("public".to_string()),
+        })];
+
+        assert!(evaluate(&policies, &[], &intent(OTHER, "XLM", "1", "testnet")).is_empty());
+        assert_eq!(evaluate(&policies, &[], &intent(OTHER, "XLM", "1", "public")).len(), 1);
+    }
+
+    #[test]
+    fn every_broken_rule_is_reported() {
+        let policies = vec![
+            policy(PolicyRule::MaxAmount {
+                asset_code: "XLM".to_string(),
+                max_stroops: parse_stroops("10").unwrap(),
+            }),
+            policy(PolicyRule::AllowlistOnly { network: None }),
+        ];
+
+        assert_eq!(evaluate(&policies, &[], &intent(OTHER, "XLM", "50", "public")).len(), 2);
+    }
+}
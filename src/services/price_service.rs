@@ -0,0 +1,130 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use crate::stellar::amount;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const DEFAULT_PRICE_API_URL: &str = "https://api.coingecko.com/api/v3";
+
+/// How long a fetched price is shown before asking the provider again.
+const PRICE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Cached prices keyed by `(coin id, currency)`.
+type PriceCache = HashMap<(String, String), (f64, Instant)>;
+
+/// Fetches asset prices from a CoinGecko-compatible `/simple/price` API
+/// (`PRICE_API_URL`, CoinGecko by default). Clones share one cache.
+#[derive(Clone)]
+pub struct PriceService {
+    base_url: String,
+    http: reqwest::Client,
+    cache: Arc<Mutex<PriceCache>>,
+}
+
+impl PriceService {
+    pub fn from_env() -> Self {
+        let base_url = config::var("PRICE_API_URL")
+            .unwrap_or_else(|| DEFAULT_PRICE_API_URL.to_string());
+
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Price of one unit of `asset_code` in `currency`, or `None` for assets the
+    /// provider doesn't track.
+    pub async fn price(&self, asset_code: &str, currency: &str) -> Result<Option<f64>> {
+        let Some(coin_id) = coin_id(asset_code) else {
+            return Ok(None);
+        };
+
+        let key = (coin_id.to_string(), currency.to_string());
+        let mut cache = self.cache.lock().await;
+
+        if let Some((price, fetched_at)) = cache.get(&key) {
+            if fetched_at.elapsed() < PRICE_TTL {
+                return Ok(Some(*price));
+            }
+        }
+
+        let price = self.fetch(coin_id, currency).await?;
+        if let Some(price) = price {
+            cache.insert(key, (price, Instant::now()));
+        }
+
+        Ok(price)
+    }
+
+    /// `amount` of `asset_code` formatted in `currency`, e.g. `"≈ 12.34 USD"`.
+    /// Returns `None` rather than an error when no price is available, since
+    /// fiat values are only ever a display hint.
+    pub async fn fiat_label(&self, amount: &str, asset_code: &str, currency: &str) -> Option<String> {
+        let price = self.price(asset_code, currency).await.ok()??;
+        fiat_label(amount, price, currency)
+    }
+
+    async fn fetch(&self, coin_id: &str, currency: &str) -> Result<Option<f64>> {
+        let response = self
+            .http
+            .get(format!("{}/simple/price", self.base_url))
+            .query(&[("ids", coin_id), ("vs_currencies", currency)])
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Price request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::InternalError(format!("Price provider returned HTTP {}", response.status())));
+        }
+
+        let prices = response
+            .json::<HashMap<String, HashMap<String, f64>>>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Invalid price response: {}", e)))?;
+
+        Ok(prices.get(coin_id).and_then(|quotes| quotes.get(currency)).copied())
+    }
+}
+
+/// The provider's id for an asset code. Issued assets are matched by code only,
+/// so only well-known stablecoins and wrapped assets are priced.
+fn coin_id(asset_code: &str) -> Option<&'static str> {
+    match asset_code.to_uppercase().as_str() {
+        "XLM" => Some("stellar"),
+        "USDC" => Some("usd-coin"),
+        "EURC" => Some("euro-coin"),
+        "BTC" => Some("bitcoin"),
+        "ETH" => Some("ethereum"),
+        "AQUA" => Some("aquarius"),
+        _ => None,
+    }
+}
+
+fn fiat_label(amount: &str, price: f64, currency: &str) -> Option<String> {
+    let stroops = amount::parse_stroops(amount).ok()?;
+    let value = stroops as f64 / amount::STROOPS_PER_UNIT as f64 * price;
+
+    Some(format!("≈ {:.2} {}", value, currency.to_uppercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_known_asset_codes_case_insensitively() {
+        assert_eq!(coin_id("xlm"), Some("stellar"));
+        assert_eq!(coin_id("USDC"), Some("usd-coin"));
+        assert_eq!(coin_id("SCAMCOIN"), None);
+    }
+
+    #[test]
+    fn formats_fiat_values() {
+        assert_eq!(fiat_label("100.5000000", 0.1, "usd").as_deref(), Some("≈ 10.05 USD"));
+        assert_eq!(fiat_label("0", 0.1, "eur").as_deref(), Some("≈ 0.00 EUR"));
+        assert_eq!(fiat_label("lots", 0.1, "usd"), None);
+    }
+}
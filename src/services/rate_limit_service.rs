@@ -0,0 +1,296 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Logins and signups per minute from one IP address, unless
+/// `API_RATE_LIMIT_AUTH_PER_MINUTE` says otherwise.
+const DEFAULT_AUTH_PER_MINUTE: u32 = 10;
+
+/// Payments per minute from one account, unless
+/// `API_RATE_LIMIT_PAYMENTS_PER_MINUTE` says otherwise.
+const DEFAULT_PAYMENTS_PER_MINUTE: u32 = 5;
+
+/// The in-memory store drops full buckets once it tracks this many keys.
+const MAX_MEMORY_BUCKETS: usize = 10_000;
+
+/// What a limit applies to. Each has its own buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitedAction {
+    /// Logins and signups, per client IP address.
+    Auth,
+    /// Payment submissions, per account.
+    Payments,
+}
+
+impl LimitedAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LimitedAction::Auth => "auth",
+            LimitedAction::Payments => "payments",
+        }
+    }
+}
+
+/// A token bucket holding up to `per_minute` requests, refilled at
+/// `per_minute` a minute, so short bursts are fine but a steady stream isn't.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    fn capacity(&self) -> f64 {
+        f64::from(self.per_minute)
+    }
+
+    fn tokens_per_ms(&self) -> f64 {
+        f64::from(self.per_minute) / 60_000.0
+    }
+}
+
+/// Where buckets are kept.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from `key`'s bucket, or says how long until there is one.
+    async fn take(&self, key: &str, limit: RateLimit) -> Result<Option<Duration>>;
+}
+
+/// Token-bucket limits on the API calls worth abusing: guessing passwords,
+/// mass signups and hammering payment submission. Buckets live in memory,
+/// or in Redis when several API servers should share them.
+pub struct RateLimitService {
+    store: Arc<dyn RateLimitStore>,
+    auth: Option<RateLimit>,
+    payments: Option<RateLimit>,
+}
+
+impl Default for RateLimitService {
+    /// The default limits, kept in memory.
+    fn default() -> Self {
+        Self {
+            store: Arc::new(MemoryStore::default()),
+            auth: Some(RateLimit { per_minute: DEFAULT_AUTH_PER_MINUTE }),
+            payments: Some(RateLimit { per_minute: DEFAULT_PAYMENTS_PER_MINUTE }),
+        }
+    }
+}
+
+impl RateLimitService {
+    /// Limits from `API_RATE_LIMIT_AUTH_PER_MINUTE` and
+    /// `API_RATE_LIMIT_PAYMENTS_PER_MINUTE` (0 turns one off), kept where
+    /// `API_RATE_LIMIT_STORE` says: `memory` (the default) or `redis`, at
+    /// `REDIS_URL`.
+    pub fn from_env() -> Result<Self> {
+        let store: Arc<dyn RateLimitStore> = match config::var("API_RATE_LIMIT_STORE").unwrap_or_default().to_lowercase().as_str() {
+            "" | "memory" => Arc::new(MemoryStore::default()),
+            "redis" => Arc::new(redis_store()?),
+            other => {
+                return Err(AppError::ValidationError(format!(
+                    "Unknown API_RATE_LIMIT_STORE '{}', expected memory or redis",
+                    other
+                )))
+            }
+        };
+
+        Ok(Self {
+            store,
+            auth: configured_limit("API_RATE_LIMIT_AUTH_PER_MINUTE", DEFAULT_AUTH_PER_MINUTE)?,
+            payments: configured_limit("API_RATE_LIMIT_PAYMENTS_PER_MINUTE", DEFAULT_PAYMENTS_PER_MINUTE)?,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_limits(auth: Option<RateLimit>, payments: Option<RateLimit>) -> Self {
+        Self {
+            auth,
+            payments,
+            ..Self::default()
+        }
+    }
+
+    /// Counts a request by `key` (an IP address or user id) and returns how
+    /// long it must wait if it's over the limit. A store that can't be
+    /// reached doesn't block requests; it's logged and the request goes on.
+    pub async fn check(&self, action: LimitedAction, key: &str) -> Option<Duration> {
+        let limit = match action {
+            LimitedAction::Auth => self.auth,
+            LimitedAction::Payments => self.payments,
+        }?;
+
+        match self.store.take(&format!("rate_limit:{}:{}", action.as_str(), key), limit).await {
+            Ok(wait) => wait,
+            Err(e) => {
+                eprintln!("Rate limit check failed, allowing the request: {}", e);
+                None
+            }
+        }
+    }
+}
+
+fn configured_limit(name: &str, default: u32) -> Result<Option<RateLimit>> {
+    let per_minute = match config::var(name) {
+        Some(value) => value
+            .parse::<u32>()
+            .map_err(|_| AppError::ValidationError(format!("{} must be a whole number of requests a minute", name)))?,
+        None => default,
+    };
+    Ok((per_minute > 0).then_some(RateLimit { per_minute }))
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Buckets for this process only.
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl MemoryStore {
+    fn take_at(&self, key: &str, limit: RateLimit, now: Instant) -> Option<Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_MEMORY_BUCKETS {
+            buckets.retain(|_, bucket| refilled(bucket, limit, now) < limit.capacity());
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: limit.capacity(),
+            updated: now,
+        });
+        bucket.tokens = refilled(bucket, limit, now);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_millis(((1.0 - bucket.tokens) / limit.tokens_per_ms()).ceil() as u64))
+        }
+    }
+}
+
+fn refilled(bucket: &Bucket, limit: RateLimit, now: Instant) -> f64 {
+    let elapsed_ms = now.saturating_duration_since(bucket.updated).as_secs_f64() * 1000.0;
+    (bucket.tokens + elapsed_ms * limit.tokens_per_ms()).min(limit.capacity())
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(&self, key: &str, limit: RateLimit) -> Result<Option<Duration>> {
+        Ok(self.take_at(key, limit, Instant::now()))
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_store() -> Result<RedisStore> {
+    let url = std::env::var("REDIS_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .ok_or_else(|| AppError::ValidationError("API_RATE_LIMIT_STORE=redis needs REDIS_URL".to_string()))?;
+    let client = redis::Client::open(url).map_err(|e| AppError::ValidationError(format!("Invalid REDIS_URL: {}", e)))?;
+
+    Ok(RedisStore {
+        client,
+        connection: tokio::sync::OnceCell::new(),
+    })
+}
+
+#[cfg(not(feature = "redis"))]
+fn redis_store() -> Result<MemoryStore> {
+    Err(AppError::ValidationError(
+        "API_RATE_LIMIT_STORE=redis needs a build with the redis feature".to_string(),
+    ))
+}
+
+/// The same bucket as [`MemoryStore`], updated atomically in Redis. Redis
+/// expires buckets once they would be full again.
+#[cfg(feature = "redis")]
+const TAKE_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local per_ms = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(bucket[1]) or capacity
+local at = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - at) * per_ms)
+local wait = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+else
+  wait = math.ceil((1 - tokens) / per_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / per_ms))
+return wait
+"#;
+
+/// Buckets shared by every API server using the same Redis. Connects on
+/// first use and reconnects by itself.
+#[cfg(feature = "redis")]
+pub struct RedisStore {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::ConnectionManager>,
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl RateLimitStore for RedisStore {
+    async fn take(&self, key: &str, limit: RateLimit) -> Result<Option<Duration>> {
+        let failed = |e: redis::RedisError| AppError::InternalError(format!("Redis rate limit store failed: {}", e));
+        let mut connection = self
+            .connection
+            .get_or_try_init(|| redis::aio::ConnectionManager::new(self.client.clone()))
+            .await
+            .map_err(failed)?
+            .clone();
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let wait_ms: u64 = redis::Script::new(TAKE_SCRIPT)
+            .key(key)
+            .arg(limit.capacity())
+            .arg(limit.tokens_per_ms())
+            .arg(now_ms)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(failed)?;
+
+        Ok((wait_ms > 0).then(|| Duration::from_millis(wait_ms)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_a_burst_then_refills_over_the_minute() {
+        let store = MemoryStore::default();
+        let limit = RateLimit { per_minute: 3 };
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(store.take_at("ip", limit, start), None);
+        }
+        assert_eq!(store.take_at("ip", limit, start), Some(Duration::from_secs(20)));
+        assert_eq!(store.take_at("other ip", limit, start), None);
+
+        assert_eq!(store.take_at("ip", limit, start + Duration::from_secs(5)), Some(Duration::from_secs(15)));
+        assert_eq!(store.take_at("ip", limit, start + Duration::from_secs(20)), None);
+    }
+
+    #[tokio::test]
+    async fn limits_each_action_separately_and_zero_turns_one_off() {
+        let service = RateLimitService::with_limits(Some(RateLimit { per_minute: 1 }), None);
+
+        assert_eq!(service.check(LimitedAction::Auth, "203.0.113.7").await, None);
+        assert!(service.check(LimitedAction::Auth, "203.0.113.7").await.is_some());
+        for _ in 0..10 {
+            assert_eq!(service.check(LimitedAction::Payments, "203.0.113.7").await, None);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::reconciliation::{AssetReconciliation, ReconciliationReport};
+use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+use crate::services::history_service::from_payment;
+use crate::stellar::horizon::{EffectRecord, HorizonClient};
+use crate::stellar::network::Network;
+use futures::TryStreamExt;
+use std::collections::{BTreeMap, HashSet};
+
+/// Checks an account's local history against Horizon. Balances are re-derived
+/// from effects, which exclude fees and trades on both sides, so they compare
+/// payment history only, not the account's actual balance.
+pub struct ReconciliationService {
+    db: SqliteDatabase,
+    horizon: HorizonClient,
+}
+
+impl ReconciliationService {
+    pub fn new(db: SqliteDatabase, network: &Network) -> Self {
+        Self {
+            db,
+            horizon: HorizonClient::new(&network.horizon_url),
+        }
+    }
+
+    /// Reads the account's full effect and payment history from Horizon.
+    pub async fn reconcile(&self, address: &str) -> Result<ReconciliationReport> {
+        let effects: Vec<EffectRecord> = self.horizon.stream_effects(address).try_collect().await?;
+        let network = self
+            .horizon
+            .stream_payments(address)
+            .try_filter_map(|payment| async move { Ok(from_payment(address, &payment)) })
+            .try_collect()
+            .await?;
+        let local = self.db.get_transactions(address, u32::MAX).await?;
+
+        Ok(compare(&effects, network, local))
+    }
+
+    /// Adds the missing records and removes the duplicates, returning how many
+    /// rows changed. Unknown records are left for a person to look at.
+    pub async fn repair(&self, report: &ReconciliationReport) -> Result<usize> {
+        for tx in &report.missing {
+            self.db.upsert_transaction(tx).await?;
+        }
+        for tx in &report.duplicates {
+            self.db.delete_transaction(&tx.account, &tx.hash, tx.operation_index).await?;
+        }
+
+        Ok(report.missing.len() + report.duplicates.len())
+    }
+}
+
+/// Compares Horizon's view of an account (its effects and payments) with the
+/// local records.
+pub fn compare(effects: &[EffectRecord], network: Vec<WalletTransaction>, local: Vec<WalletTransaction>) -> ReconciliationReport {
+    let mut balances: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for (asset, stroops) in effects.iter().filter_map(EffectRecord::balance_change) {
+        balances.entry(asset.to_string()).or_default().0 += stroops;
+    }
+
+    let local: Vec<WalletTransaction> = local.into_iter().filter(|tx| tx.status == TransactionStatus::Confirmed).collect();
+    for tx in &local {
+        let sign = if tx.direction == TransactionDirection::Incoming { 1 } else { -1 };
+        balances.entry(tx.asset_code.clone()).or_default().1 += sign * tx.amount_stroops;
+    }
+
+    let network_keys: HashSet<(&str, i64)> = network.iter().map(|tx| (tx.hash.as_str(), tx.operation_index)).collect();
+    let local_keys: HashSet<(&str, i64)> = local.iter().map(|tx| (tx.hash.as_str(), tx.operation_index)).collect();
+    let matched_hashes: HashSet<&str> = local
+        .iter()
+        .filter(|tx| network_keys.contains(&(tx.hash.as_str(), tx.operation_index)))
+        .map(|tx| tx.hash.as_str())
+        .collect();
+
+    let missing = network
+        .iter()
+        .filter(|tx| !local_keys.contains(&(tx.hash.as_str(), tx.operation_index)))
+        .cloned()
+        .collect();
+    let (duplicates, unknown) = local
+        .iter()
+        .filter(|tx| !network_keys.contains(&(tx.hash.as_str(), tx.operation_index)))
+        .cloned()
+        .partition(|tx| matched_hashes.contains(tx.hash.as_str()));
+
+    ReconciliationReport {
+        assets: balances
+            .into_iter()
+            .map(|(asset_code, (network_stroops, local_stroops))| AssetReconciliation {
+                asset_code,
+                network_stroops,
+                local_stroops,
+            })
+            .collect(),
+        missing,
+        duplicates,
+        unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    const ACCOUNT: &str = "GACCOUNT";
+
+    fn effect(effect_type: &str, amount: &str) -> EffectRecord {
+        EffectRecord {
+            effect_type: effect_type.to_string(),
+            amount: Some(amount.to_string()),
+            asset_type: Some("native".to_string()),
+            asset_code: None,
+            starting_balance: Some(amount.to_string()),
+        }
+    }
+
+    fn tx(hash: &str, operation_index: i64, direction: TransactionDirection, stroops: i64) -> WalletTransaction {
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: ACCOUNT.to_string(),
+            hash: hash.to_string(),
+            operation_index,
+            direction,
+            asset_code: "XLM".to_string(),
+            amount_stroops: stroops,
+            counterparty: "GOTHER".to_string(),
+            memo: None,
+            status: TransactionStatus::Confirmed,
+            ledger: Some(1),
+            error: None,
+            request_id: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn a_matching_history_is_clean() {
+        let effects = [effect("account_created", "10"), effect("account_debited", "3"), effect("signer_created", "0")];
+        let network = vec![
+            tx("aa", 1, TransactionDirection::Incoming, 100_000_000),
+            tx("bb", 1, TransactionDirection::Outgoing, 30_000_000),
+        ];
+
+        let report = compare(&effects, network.clone(), network);
+        assert!(report.is_clean(), "{:?}", report);
+        assert_eq!(report.assets[0].network_stroops, 70_000_000);
+    }
+
+    #[test]
+    fn finds_missing_duplicate_and_unknown_records() {
+        let effects = [effect("account_created", "10"), effect("account_debited", "3")];
+        let network = vec![
+            tx("aa", 1, TransactionDirection::Incoming, 100_000_000),
+            tx("bb", 1, TransactionDirection::Outgoing, 30_000_000),
+        ];
+        let mut pending = tx("dd", 1, TransactionDirection::Outgoing, 5);
+        pending.status = TransactionStatus::Pending;
+        let local = vec![
+            tx("bb", 1, TransactionDirection::Outgoing, 30_000_000),
+            tx("bb", 2, TransactionDirection::Outgoing, 30_000_000),
+            tx("cc", 1, TransactionDirection::Incoming, 7),
+            pending,
+        ];
+
+        let report = compare(&effects, network, local);
+
+        assert_eq!(report.missing.iter().map(|tx| tx.hash.as_str()).collect::<Vec<_>>(), ["aa"]);
+        assert_eq!(report.duplicates.iter().map(|tx| (tx.hash.as_str(), tx.operation_index)).collect::<Vec<_>>(), [("bb", 2)]);
+        assert_eq!(report.unknown.iter().map(|tx| tx.hash.as_str()).collect::<Vec<_>>(), ["cc"]);
+        assert_eq!(report.assets[0].difference(), (-60_000_000 + 7) - 70_000_000);
+        assert!(report.is_repairable());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::errors::{AppError, Result};
+use crate::models::session::SavedLogin;
+use crate::services::token_service::SessionStore;
+use chrono::Utc;
+use jsonwebtoken::{DecodingKey, Validation};
+use serde_json::Value;
+use stellar_wallet::client::{Client, Login, NewAccount, Payment, PaymentReceipt, SecondFactor, Stats, Transaction, User};
+
+/// Access tokens this close to expiring are refreshed before use.
+const REFRESH_MARGIN_SECS: i64 = 30;
+
+/// The CLI's operations against a deployed wallet's REST API, for
+/// `--remote`. Logins are saved like local ones, marked with the
+/// deployment's URL, so the commands run as the saved login and the
+/// interactive wallet can resume it.
+pub struct RemoteService {
+    client: Client,
+    session_store: SessionStore,
+}
+
+impl RemoteService {
+    pub fn new(base_url: &str, session_store: SessionStore) -> Result<Self> {
+        Ok(Self {
+            client: Client::new(base_url)?,
+            session_store,
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.client.base_url()
+    }
+
+    pub async fn create_account(&self, account: &NewAccount) -> Result<User> {
+        Ok(self.client.create_account(account, None).await?)
+    }
+
+    /// Logs in and saves the login in place of any other saved one.
+    pub async fn login(&self, identifier: &str, password: &str, code: Option<&str>, scopes: &[&str]) -> Result<Login> {
+        let login = self.client.clone().login(identifier, password, code.map(SecondFactor::Code), scopes).await?;
+        self.save(&login)?;
+        Ok(login)
+    }
+
+    /// Ends the saved login's session and forgets it. Succeeds when there is
+    /// nothing to end.
+    pub async fn logout(&self) -> Result<()> {
+        match self.client().await {
+            Ok(mut client) => match client.logout().await.map_err(AppError::from) {
+                Ok(()) | Err(AppError::AuthenticationError(_)) => {}
+                Err(e) => return Err(e),
+            },
+            Err(AppError::AuthenticationError(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.session_store.clear()
+    }
+
+    pub async fn me(&self) -> Result<User> {
+        Ok(self.client().await?.me().await?)
+    }
+
+    pub async fn pay(&self, payment: &Payment) -> Result<PaymentReceipt> {
+        Ok(self.client().await?.send_payment(payment, None).await?)
+    }
+
+    pub async fn transactions(&self, address: &str, limit: Option<u32>) -> Result<Vec<Transaction>> {
+        Ok(self.client().await?.transactions(address, limit).await?)
+    }
+
+    pub async fn stats(&self) -> Result<Stats> {
+        Ok(self.client().await?.stats().await?)
+    }
+
+    /// A client acting as the saved login, refreshing its access token first
+    /// if it has expired or is about to. A login that has ended is forgotten.
+    async fn client(&self) -> Result<Client> {
+        let not_logged_in = || AppError::AuthenticationError(format!("Not logged in to {}; run `login` first", self.base_url()));
+        let saved = self.session_store.load()?.ok_or_else(not_logged_in)?;
+        if saved.remote.as_deref() != Some(self.base_url()) {
+            return Err(not_logged_in());
+        }
+        if !expires_soon(&saved.access_token) {
+            return Ok(self.client.clone().with_access_token(&saved.access_token));
+        }
+
+        let mut client = self.client.clone();
+        match client.refresh(&saved.refresh_token).await.map_err(AppError::from) {
+            Ok(login) => {
+                self.save(&login)?;
+                Ok(client)
+            }
+            Err(AppError::AuthenticationError(_)) => {
+                self.session_store.clear()?;
+                Err(AppError::AuthenticationError("Your saved login has ended; run `login` again".to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, login: &Login) -> Result<()> {
+        self.session_store.save(&SavedLogin {
+            access_token: login.access_token.clone(),
+            refresh_token: login.refresh_token.clone(),
+            remote: Some(self.base_url().to_string()),
+        })
+    }
+}
+
+/// Whether `access_token` expires within [`REFRESH_MARGIN_SECS`]. Its
+/// signature can't be checked here, but it is only read to decide when to
+/// refresh; the server checks it on every request.
+fn expires_soon(access_token: &str) -> bool {
+    let Ok(header) = jsonwebtoken::decode_header(access_token) else {
+        return true;
+    };
+    let mut validation = Validation::new(header.alg);
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.required_spec_claims.clear();
+
+    match jsonwebtoken::decode::<Value>(access_token, &DecodingKey::from_secret(&[]), &validation) {
+        Ok(token) => token.claims["exp"].as_i64().is_none_or(|exp| exp <= Utc::now().timestamp() + REFRESH_MARGIN_SECS),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{serve, ApiState};
+    use crate::database::sqlite::SqliteDatabase;
+    use crate::services::token_service::TokenService;
+    use crate::stellar::network::Network;
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn acts_as_the_saved_remote_login_until_logout() {
+        let db = SqliteDatabase::in_memory().await;
+        let tokens = TokenService::new(&[7; 32], None).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, ApiState::new(db, &Network::testnet(), tokens)));
+        let path = std::env::temp_dir().join(format!("wallet-remote-{}", Uuid::new_v4()));
+        let store = SessionStore::File(path.to_string_lossy().into_owned());
+        let remote = RemoteService::new(&base, store.clone()).unwrap();
+
+        assert!(matches!(remote.me().await, Err(AppError::AuthenticationError(_))));
+        let account = NewAccount {
+            email: "curlew@example.com".to_string(),
+            username: "curlew".to_string(),
+            password: "Misty-Curlew-27!".to_string().into(),
+        };
+        remote.create_account(&account).await.unwrap();
+        assert!(remote.login("curlew", "wrong-password", None, &[]).await.is_err());
+        assert_eq!(store.load().unwrap(), None);
+
+        let login = remote.login("curlew", "Misty-Curlew-27!", None, &[]).await.unwrap();
+        assert_eq!(store.load().unwrap().unwrap().remote.as_deref(), Some(base.as_str()));
+        assert_eq!(remote.me().await.unwrap().id, login.user.id);
+        assert!(matches!(remote.stats().await, Err(AppError::AuthenticationError(_))));
+
+        store
+            .save(&SavedLogin {
+                access_token: "expired".to_string(),
+                refresh_token: login.refresh_token.clone(),
+                remote: Some(base.clone()),
+            })
+            .unwrap();
+        assert_eq!(remote.me().await.unwrap().username, "curlew");
+        assert_ne!(store.load().unwrap().unwrap().refresh_token, login.refresh_token);
+
+        let elsewhere = RemoteService::new("https://wallet.example.com", store.clone()).unwrap();
+        assert!(matches!(elsewhere.me().await, Err(AppError::AuthenticationError(_))));
+
+        remote.logout().await.unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        assert!(matches!(remote.me().await, Err(AppError::AuthenticationError(_))));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::report::{ReportAggregate, ReportFilter, ReportGroup, ReportQuery, ReportRow, ReportSource};
+use crate::models::transaction::{TransactionDirection, TransactionStatus};
+use chrono::NaiveDate;
+
+/// Help text for the report language, shown by the CLI.
+pub const REPORT_SYNTAX: &str = "<source> [count] [where <filter> [and <filter>...]] [by <group>]
+  sources: users | transactions
+  filters: since YYYY-MM-DD | until YYYY-MM-DD
+           users: verified | unverified | linked | unlinked
+           transactions: pending | confirmed | failed | incoming | outgoing | asset CODE
+  groups:  day | month | status
+           users: wallet
+           transactions: asset";
+
+pub struct ReportService {
+    db: SqliteDatabase,
+}
+
+impl ReportService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    pub async fn run(&self, query: &str) -> Result<Vec<ReportRow>> {
+        let report = Self::parse(query)?;
+        self.db.run_report(&report).await
+    }
+
+    /// Parses a report such as `users where verified and since 2025-01-01 by month`
+    /// or `transactions where failed and asset xlm by day`.
+    pub fn parse(query: &str) -> Result<ReportQuery> {
+        let lowered = query.to_lowercase();
+        let mut tokens = lowered.split_whitespace().peekable();
+
+        let source = match tokens.next() {
+            Some("users") => ReportSource::Users,
+            Some("transactions") => ReportSource::Transactions,
+            Some(other) => return Err(syntax_error(&format!("unknown source '{}'", other))),
+            None => return Err(syntax_error("missing source")),
+        };
+
+        let mut report = ReportQuery {
+            source,
+            filters: Vec::new(),
+            group_by: None,
+            aggregate: ReportAggregate::Count,
+        };
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "count" => report.aggregate = ReportAggregate::Count,
+                "where" => loop {
+                    report.filters.push(parse_filter(&mut tokens)?);
+                    if tokens.peek() != Some(&"and") {
+                        break;
+                    }
+                    tokens.next();
+                },
+                "by" => {
+                    if report.group_by.is_some() {
+                        return Err(syntax_error("only one 'by' clause is allowed"));
+                    }
+                    report.group_by = Some(match tokens.next() {
+                        Some("day") => ReportGroup::Day,
+                        Some("month") => ReportGroup::Month,
+                        Some("status") => ReportGroup::Status,
+                        Some("wallet") => ReportGroup::Wallet,
+                        Some("asset") => ReportGroup::Asset,
+                        Some(other) => return Err(syntax_error(&format!("unknown group '{}'", other))),
+                        None => return Err(syntax_error("'by' needs a group")),
+                    });
+                }
+                other => return Err(syntax_error(&format!("unexpected '{}'", other))),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+fn parse_filter<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<ReportFilter> {
+    match tokens.next() {
+        Some("verified") => Ok(ReportFilter::Verified(true)),
+        Some("unverified") => Ok(ReportFilter::Verified(false)),
+        Some("linked") => Ok(ReportFilter::WalletLinked(true)),
+        Some("unlinked") => Ok(ReportFilter::WalletLinked(false)),
+        Some("asset") => match tokens.next() {
+            Some(code) => Ok(ReportFilter::Asset(code.to_uppercase())),
+            None => Err(syntax_error("'asset' needs an asset code")),
+        },
+        Some("since") => Ok(ReportFilter::Since(parse_date(tokens.next())?)),
+        Some("until") => Ok(ReportFilter::Until(parse_date(tokens.next())?)),
+        Some(other) => {
+            if let Some(status) = TransactionStatus::parse(other) {
+                return Ok(ReportFilter::Status(status));
+            }
+            if let Some(direction) = TransactionDirection::parse(other) {
+                return Ok(ReportFilter::Direction(direction));
+            }
+            Err(syntax_error(&format!("unknown filter '{}'", other)))
+        }
+        None => Err(syntax_error("'where' needs a filter")),
+    }
+}
+
+fn parse_date(token: Option<&str>) -> Result<NaiveDate> {
+    let token = token.ok_or_else(|| syntax_error("expected a date (YYYY-MM-DD)"))?;
+    NaiveDate::parse_from_str(token, "%Y-%m-%d").map_err(|_| syntax_error(&format!("invalid date '{}'", token)))
+}
+
+fn syntax_error(message: &str) -> AppError {
+    AppError::ValidationError(format!("Report syntax: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filters_and_grouping() {
+        let report = ReportService::parse("Users where verified and since 2025-01-01 and unlinked by month").unwrap();
+
+        assert_eq!(report.source, ReportSource::Users);
+        assert_eq!(
+            report.filters,
+            vec![
+                ReportFilter::Verified(true),
+                ReportFilter::Since(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()),
+                ReportFilter::WalletLinked(false),
+            ]
+        );
+        assert_eq!(report.group_by, Some(ReportGroup::Month));
+        assert_eq!(report.aggregate, ReportAggregate::Count);
+    }
+
+    #[test]
+    fn parses_transaction_reports() {
+        let report = ReportService::parse("transactions where failed and outgoing and asset usdc by asset").unwrap();
+
+        assert_eq!(report.source, ReportSource::Transactions);
+        assert_eq!(
+            report.filters,
+            vec![
+                ReportFilter::Status(TransactionStatus::Failed),
+                ReportFilter::Direction(TransactionDirection::Outgoing),
+                ReportFilter::Asset("USDC".to_string()),
+            ]
+        );
+        assert_eq!(report.group_by, Some(ReportGroup::Asset));
+    }
+
+    #[test]
+    fn bare_source_counts_everything() {
+        let report = ReportService::parse("users").unwrap();
+
+        assert!(report.filters.is_empty());
+        assert_eq!(report.group_by, None);
+    }
+
+    #[test]
+    fn rejects_malformed_reports() {
+        for query in [
+            "",
+            "wallets",
+            "users where",
+            "users where rich",
+            "users where since 2025-13-01",
+            "users by",
+            "transactions where asset",
+            "users by day by month",
+            "users; DROP TABLE users",
+        ] {
+            assert!(ReportService::parse(query).is_err(), "{:?} should be rejected", query);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use crate::models::security_event::SecurityEvent;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::{TcpStream, UdpSocket};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+static SINK: OnceLock<Mutex<SecurityEventSink>> = OnceLock::new();
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where audit events are mirrored as ECS JSON lines for a SIEM, configured
+/// with `SECURITY_EVENTS_SINK`:
+///
+/// - a file path, appended to (point Filebeat or a Splunk forwarder at it);
+/// - `udp://host:port`, one datagram per event;
+/// - `tcp://host:port`, newline-delimited, reconnecting after errors.
+///
+/// The audit table stays the record of truth. Shipping is best effort, so a
+/// collector being down never blocks a login or payment.
+pub enum SecurityEventSink {
+    File(File),
+    Udp { socket: UdpSocket, target: String },
+    Tcp { target: String, stream: Option<TcpStream> },
+}
+
+impl SecurityEventSink {
+    pub fn from_env() -> Result<Option<Self>> {
+        config::var("SECURITY_EVENTS_SINK").map(|value| Self::parse(&value)).transpose()
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        if let Some(target) = value.strip_prefix("udp://") {
+            let socket = UdpSocket::bind("0.0.0.0:0")
+                .map_err(|e| AppError::InternalError(format!("Can't open a UDP socket for security events: {}", e)))?;
+            return Ok(Self::Udp { socket, target: target.to_string() });
+        }
+        if let Some(target) = value.strip_prefix("tcp://") {
+            return Ok(Self::Tcp { target: target.to_string(), stream: None });
+        }
+        if value.contains("://") {
+            return Err(AppError::ValidationError(format!(
+                "SECURITY_EVENTS_SINK must be a file path, udp://host:port or tcp://host:port, not '{}'",
+                value
+            )));
+        }
+
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(value)
+            .map_err(|e| AppError::ValidationError(format!("Can't open SECURITY_EVENTS_SINK {}: {}", value, e)))?;
+        Ok(Self::File(file))
+    }
+
+    /// Makes this the sink [`SecurityEventSink::emit`] writes to. Only the
+    /// first call has any effect.
+    pub fn install(self) {
+        let _ = SINK.set(Mutex::new(self));
+    }
+
+    /// Ships `event` to the installed sink, if any.
+    pub fn emit(event: &SecurityEvent) {
+        let Some(sink) = SINK.get() else {
+            return;
+        };
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+
+        if let Ok(mut sink) = sink.lock() {
+            let _ = sink.write_line(&line);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        match self {
+            Self::File(file) => writeln!(file, "{}", line),
+            Self::Udp { socket, target } => socket.send_to(line.as_bytes(), target.as_str()).map(|_| ()),
+            Self::Tcp { target, stream } => {
+                if stream.is_none() {
+                    *stream = Some(connect(target)?);
+                }
+                let result = stream.as_mut().map_or(Ok(()), |s| writeln!(s, "{}", line));
+                if result.is_err() {
+                    // Try a fresh connection with the next event.
+                    *stream = None;
+                }
+                result
+            }
+        }
+    }
+}
+
+fn connect(target: &str) -> std::io::Result<TcpStream> {
+    use std::net::ToSocketAddrs;
+
+    let address = target
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address for security events sink"))?;
+    TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    #[test]
+    fn parses_sink_kinds() {
+        assert!(matches!(SecurityEventSink::parse("udp://127.0.0.1:514").unwrap(), SecurityEventSink::Udp { .. }));
+        assert!(matches!(SecurityEventSink::parse("tcp://siem:5000").unwrap(), SecurityEventSink::Tcp { stream: None, .. }));
+        assert!(SecurityEventSink::parse("https://siem.example.com").is_err());
+    }
+
+    #[test]
+    fn writes_one_json_line_per_event_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut sink = SecurityEventSink::parse(&format!("tcp://{}", listener.local_addr().unwrap())).unwrap();
+
+        sink.write_line(r#"{"a":1}"#).unwrap();
+        sink.write_line(r#"{"a":2}"#).unwrap();
+
+        let (stream, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(stream).lines().take(2).map(|line| line.unwrap()).collect();
+        assert_eq!(lines, [r#"{"a":1}"#, r#"{"a":2}"#]);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::models::audit::AuditEvent;
+use crate::models::session::{Session, SESSION_TTL};
+use crate::services::audit_service::AuditService;
+use chrono::{Duration, Utc};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::env;
+use uuid::Uuid;
+
+pub struct SessionService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+    ttl: Duration,
+}
+
+impl SessionService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+            ttl: configured_ttl(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_ttl(db: SqliteDatabase, ttl: Duration) -> Self {
+        Self { ttl, ..Self::new(db) }
+    }
+
+    /// Issues a session after a successful login.
+    pub async fn start(&self, user_id: &Uuid, device_label: &str) -> Result<Session> {
+        self.start_with_scopes(user_id, device_label, &ApiScope::ALL).await
+    }
+
+    /// Issues a session whose tokens may only be used for `scopes`, for
+    /// clients that don't need everything a login can do.
+    pub async fn start_with_scopes(&self, user_id: &Uuid, device_label: &str, scopes: &[ApiScope]) -> Result<Session> {
+        if scopes.is_empty() {
+            return Err(AppError::ValidationError("Ask for at least one scope".to_string()));
+        }
+        let now = Utc::now();
+        let session = Session {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            device_label: device_label.to_string(),
+            created_at: now,
+            expires_at: now + self.ttl,
+            last_seen_at: now,
+            revoked_at: None,
+            scopes: scopes.to_vec(),
+        };
+
+        self.db.create_session(&session).await?;
+        Ok(session)
+    }
+
+    /// Checks the session is still usable and records activity on it. Fails
+    /// once it has expired or been revoked, e.g. from another device.
+    pub async fn validate(&self, session_id: &Uuid) -> Result<Session> {
+        let now = Utc::now();
+        let session = self
+            .db
+            .get_session(session_id)
+            .await?
+            .filter(|session| session.is_active(now))
+            .ok_or_else(|| AppError::AuthenticationError("Your session has ended; please log in again".to_string()))?;
+
+        self.db.touch_session(session_id, now).await?;
+        Ok(Session {
+            last_seen_at: now,
+            ..session
+        })
+    }
+
+    pub async fn active_sessions(&self, user_id: &Uuid) -> Result<Vec<Session>> {
+        self.db.get_active_sessions(user_id, Utc::now()).await
+    }
+
+    /// Ends one of the user's sessions from the session list.
+    pub async fn revoke(&self, user_id: &Uuid, session_id: &Uuid) -> Result<()> {
+        self.end(user_id, session_id, AuditEvent::SessionRevoked).await
+    }
+
+    /// Ends all of the user's sessions except `current`, e.g. after losing a
+    /// device. Returns how many were ended.
+    pub async fn revoke_others(&self, user_id: &Uuid, current: &Uuid) -> Result<u64> {
+        let revoked = self.db.revoke_other_sessions(user_id, current, Utc::now()).await?;
+        if revoked > 0 {
+            self.audit_service
+                .record(Some(user_id), AuditEvent::SessionRevoked, &format!("{} session(s) other than {}", revoked, current))
+                .await?;
+        }
+        Ok(revoked)
+    }
+
+    /// Ends the session the user is logged in with.
+    pub async fn logout(&self, user_id: &Uuid, session_id: &Uuid) -> Result<()> {
+        self.end(user_id, session_id, AuditEvent::Logout).await
+    }
+
+    /// A new refresh token for `session`. Only its hash is stored.
+    pub async fn issue_refresh_token(&self, session: &Session) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+
+        self.db.create_refresh_token(&session.id, &hash_refresh_token(&token), Utc::now()).await?;
+        Ok(token)
+    }
+
+    /// Trades a refresh token for its session and the next token. Each token
+    /// works once: presenting one that was already used means someone else
+    /// has a copy, so the session and every token issued for it are revoked.
+    pub async fn rotate_refresh_token(&self, token: &str) -> Result<(Session, String)> {
+        let ended = || AppError::AuthenticationError("Your session has ended; please log in again".to_string());
+        let token_hash = hash_refresh_token(token);
+        let session_id = self.db.get_refresh_token_session(&token_hash).await?.ok_or_else(ended)?;
+
+        if !self.db.mark_refresh_token_used(&token_hash, Utc::now()).await? {
+            if let Some(session) = self.db.get_session(&session_id).await? {
+                if self.db.revoke_session(&session.user_id, &session.id, Utc::now()).await? {
+                    self.audit_service
+                        .record(Some(&session.user_id), AuditEvent::RefreshTokenReused, &format!("session {}", session.id))
+                        .await?;
+                }
+            }
+            return Err(ended());
+        }
+
+        let session = self.validate(&session_id).await?;
+        let next = self.issue_refresh_token(&session).await?;
+        Ok((session, next))
+    }
+
+    async fn end(&self, user_id: &Uuid, session_id: &Uuid, event: AuditEvent) -> Result<()> {
+        if !self.db.revoke_session(user_id, session_id, Utc::now()).await? {
+            return Err(AppError::ValidationError("Session not found or already ended".to_string()));
+        }
+        self.audit_service.record(Some(user_id), event, &format!("session {}", session_id)).await
+    }
+}
+
+/// `SESSION_TTL_HOURS`, or [`SESSION_TTL`] if unset or not a positive number.
+fn configured_ttl() -> Duration {
+    config::var("SESSION_TTL_HOURS")
+        .and_then(|hours| hours.parse::<i64>().ok())
+        .filter(|hours| *hours > 0)
+        .map_or(SESSION_TTL, Duration::hours)
+}
+
+fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Names this machine in session lists, e.g. "CLI on laptop".
+pub fn device_label() -> String {
+    let host = env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty());
+
+    match host {
+        Some(host) => format!("CLI on {}", host),
+        None => "CLI".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn login_sessions_are_listed_until_revoked() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = SessionService::new(db);
+
+        let laptop = service.start(&user, "CLI on laptop").await.unwrap();
+        let desktop = service.start(&user, "CLI on desktop").await.unwrap();
+        assert_eq!(service.active_sessions(&user).await.unwrap().len(), 2);
+
+        service.revoke(&user, &laptop.id).await.unwrap();
+        let active = service.active_sessions(&user).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, desktop.id);
+
+        assert!(service.validate(&laptop.id).await.is_err());
+        assert!(service.validate(&desktop.id).await.is_ok());
+        assert!(service.revoke(&user, &laptop.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn revoking_other_sessions_keeps_the_current_one() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let other_user = db.insert_test_user().await;
+        let service = SessionService::new(db);
+
+        let current = service.start(&user, "CLI on laptop").await.unwrap();
+        service.start(&user, "CLI on desktop").await.unwrap();
+        service.start(&user, "CLI on server").await.unwrap();
+        let elsewhere = service.start(&other_user, "CLI").await.unwrap();
+
+        assert_eq!(service.revoke_others(&user, &current.id).await.unwrap(), 2);
+        let active = service.active_sessions(&user).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, current.id);
+        assert!(service.validate(&elsewhere.id).await.is_ok());
+        assert_eq!(service.revoke_others(&user, &current.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_fail_validation() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = SessionService::with_ttl(db, Duration::zero());
+
+        let session = service.start(&user, "CLI").await.unwrap();
+
+        assert!(service.validate(&session.id).await.is_err());
+        assert!(service.active_sessions(&user).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn users_cannot_revoke_each_others_sessions() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let mallory = db.insert_test_user().await;
+        let service = SessionService::new(db);
+
+        let session = service.start(&alice, "CLI").await.unwrap();
+
+        assert!(service.revoke(&mallory, &session.id).await.is_err());
+        assert!(service.validate(&session.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn reusing_a_refresh_token_revokes_the_session() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = SessionService::new(db);
+
+        let session = service.start(&user, "CLI").await.unwrap();
+        let first = service.issue_refresh_token(&session).await.unwrap();
+        let (rotated, second) = service.rotate_refresh_token(&first).await.unwrap();
+        assert_eq!(rotated.id, session.id);
+        assert_ne!(first, second);
+
+        assert!(service.rotate_refresh_token(&first).await.is_err());
+        assert!(service.validate(&session.id).await.is_err());
+        assert!(service.rotate_refresh_token(&second).await.is_err());
+        assert!(service.rotate_refresh_token("not a token").await.is_err());
+    }
+}
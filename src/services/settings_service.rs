@@ -0,0 +1,75 @@
+use crate::cli::theme::ThemeName;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::user_settings::UserSettings;
+use crate::utils::validation::Validator;
+use chrono::Utc;
+use uuid::Uuid;
+
+pub struct SettingsService {
+    db: SqliteDatabase,
+}
+
+impl SettingsService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self { db }
+    }
+
+    pub async fn settings(&self, user_id: &Uuid) -> Result<UserSettings> {
+        Ok(self
+            .db
+            .get_user_settings(user_id)
+            .await?
+            .unwrap_or_else(|| UserSettings::defaults_for(*user_id)))
+    }
+
+    pub async fn set_fiat_currency(&self, user_id: &Uuid, currency: &str) -> Result<UserSettings> {
+        let currency = currency.trim().to_lowercase();
+        Validator::validate_fiat_currency(&currency)?;
+
+        let mut settings = self.settings(user_id).await?;
+        settings.fiat_currency = currency;
+        settings.updated_at = Utc::now();
+
+        self.db.upsert_user_settings(&settings).await?;
+        Ok(settings)
+    }
+
+    pub async fn set_theme(&self, user_id: &Uuid, theme: ThemeName) -> Result<UserSettings> {
+        let mut settings = self.settings(user_id).await?;
+        settings.theme = theme.as_str().to_string();
+        settings.updated_at = Utc::now();
+
+        self.db.upsert_user_settings(&settings).await?;
+        Ok(settings)
+    }
+
+    /// Saves a number the user has proven they own, or removes it (which also
+    /// turns SMS verification off).
+    pub async fn set_sms_phone_number(&self, user_id: &Uuid, phone: Option<&str>) -> Result<UserSettings> {
+        if let Some(phone) = phone {
+            Validator::validate_phone_number(phone)?;
+        }
+
+        let mut settings = self.settings(user_id).await?;
+        settings.sms_phone_number = phone.map(str::to_string);
+        settings.sms_verification_enabled &= phone.is_some();
+        settings.updated_at = Utc::now();
+
+        self.db.upsert_user_settings(&settings).await?;
+        Ok(settings)
+    }
+
+    pub async fn set_sms_verification(&self, user_id: &Uuid, enabled: bool) -> Result<UserSettings> {
+        let mut settings = self.settings(user_id).await?;
+        if enabled && settings.sms_phone_number.is_none() {
+            return Err(AppError::ValidationError("Add a phone number before turning on SMS verification".to_string()));
+        }
+
+        settings.sms_verification_enabled = enabled;
+        settings.updated_at = Utc::now();
+
+        self.db.upsert_user_settings(&settings).await?;
+        Ok(settings)
+    }
+}
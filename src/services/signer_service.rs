@@ -0,0 +1,107 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::keystore::KeystoreEntry;
+use crate::models::ledger_account::LedgerAccount;
+use crate::services::keystore_service::KeystoreService;
+use crate::services::user_service::UserService;
+use crate::stellar::ledger::LedgerSigner;
+use crate::stellar::signer::Signer;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// How an address's transactions get signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerKind {
+    Software,
+    Ledger { account_index: u32 },
+}
+
+pub struct SignerService {
+    db: SqliteDatabase,
+    keystore_service: KeystoreService,
+    user_service: UserService,
+}
+
+impl SignerService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            keystore_service: KeystoreService::new(db.clone()),
+            user_service: UserService::new(db.clone()),
+            db,
+        }
+    }
+
+    pub async fn signer_kind(&self, user_id: &Uuid, public_key: &str) -> Result<SignerKind> {
+        Ok(match self.db.get_ledger_account(user_id, public_key).await? {
+            Some(account) => SignerKind::Ledger {
+                account_index: account.account_index,
+            },
+            None => SignerKind::Software,
+        })
+    }
+
+    /// Decrypts the keystore key that signs for `public_key`.
+    pub async fn unlock_software(&self, user_id: &Uuid, public_key: &str, password: &str) -> Result<Box<dyn Signer>> {
+        self.keystore_service.unlock_signer(user_id, public_key, password).await
+    }
+
+    /// Connects to the Ledger holding `public_key`, checking it's the right device.
+    pub async fn connect_ledger(&self, public_key: &str, account_index: u32) -> Result<Box<dyn Signer>> {
+        let signer = connect(account_index).await?;
+
+        if signer.public_key() != public_key {
+            return Err(AppError::StellarError(format!(
+                "The connected Ledger doesn't hold {} at account #{}",
+                public_key, account_index
+            )));
+        }
+
+        Ok(Box::new(signer))
+    }
+
+    /// Reads the Ledger address at `account_index`, has the user approve it on the
+    /// device, and makes it the user's primary address.
+    pub async fn link_ledger_account(&self, user_id: &Uuid, account_index: u32) -> Result<String> {
+        let signer = connect(account_index).await?;
+        let public_key = signer.public_key();
+
+        tokio::task::spawn_blocking(move || signer.confirm_address())
+            .await
+            .map_err(|e| AppError::InternalError(format!("Ledger task failed: {}", e)))??;
+
+        self.db
+            .create_ledger_account(&LedgerAccount {
+                id: Uuid::new_v4(),
+                user_id: *user_id,
+                public_key: public_key.clone(),
+                account_index,
+                created_at: Utc::now(),
+            })
+            .await?;
+        self.user_service.link_stellar_public_key(user_id, &public_key).await?;
+
+        Ok(public_key)
+    }
+
+    /// Keystore keys for addresses of their own, leaving out rotated-in signers.
+    pub async fn software_keys(&self, user_id: &Uuid) -> Result<Vec<KeystoreEntry>> {
+        let entries = self.db.get_keystore_entries_by_user(user_id).await?;
+        Ok(entries.into_iter().filter(|entry| entry.signs_for.is_none()).collect())
+    }
+
+    /// Makes a key already in the user's keystore their primary address again.
+    pub async fn link_software_key(&self, user_id: &Uuid, public_key: &str) -> Result<()> {
+        let entry = self.db.get_keystore_entry(user_id, public_key).await?;
+        if entry.is_none_or(|entry| entry.signs_for.is_some()) {
+            return Err(AppError::ValidationError(format!("No secret key stored for {}", public_key)));
+        }
+
+        self.user_service.link_stellar_public_key(user_id, public_key).await
+    }
+}
+
+async fn connect(account_index: u32) -> Result<LedgerSigner> {
+    tokio::task::spawn_blocking(move || LedgerSigner::connect(account_index))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Ledger task failed: {}", e)))?
+}
@@ -0,0 +1,190 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::signing_pin::SigningPin;
+use crate::services::audit_service::AuditService;
+use crate::services::user_service::UserService;
+use crate::utils::crypto::PasswordManager;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+const MIN_PIN_DIGITS: usize = 4;
+const MAX_PIN_DIGITS: usize = 8;
+
+/// Wrong PINs in a row before signing is locked.
+const MAX_PIN_FAILURES: i64 = 5;
+
+const PIN_LOCKOUT_MINUTES: i64 = 15;
+
+/// An optional PIN asked for before every transaction is signed, so a
+/// logged-in terminal left unattended can't be used to move funds. Setting or
+/// removing it takes the login password, so the PIN can't simply be turned off
+/// from that same terminal.
+pub struct SigningPinService {
+    db: SqliteDatabase,
+    user_service: UserService,
+    audit_service: AuditService,
+}
+
+impl SigningPinService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            user_service: UserService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+        }
+    }
+
+    pub async fn is_enabled(&self, user_id: &Uuid) -> Result<bool> {
+        Ok(self.db.get_signing_pin(user_id).await?.is_some())
+    }
+
+    /// Sets or replaces the user's PIN.
+    pub async fn set(&self, user_id: &Uuid, password: &str, pin: &str) -> Result<()> {
+        validate_pin(pin)?;
+        self.user_service.verify_password(user_id, password).await?;
+
+        let existed = self.is_enabled(user_id).await?;
+        self.db
+            .upsert_signing_pin(&SigningPin {
+                user_id: *user_id,
+                pin_hash: PasswordManager::hash_password(pin)?,
+                failures: 0,
+                locked_until: None,
+                created_at: Utc::now(),
+            })
+            .await?;
+
+        let details = if existed { "changed" } else { "set" };
+        self.audit_service.record(Some(user_id), AuditEvent::SigningPinChanged, details).await
+    }
+
+    pub async fn remove(&self, user_id: &Uuid, password: &str) -> Result<()> {
+        self.user_service.verify_password(user_id, password).await?;
+
+        if !self.db.delete_signing_pin(user_id).await? {
+            return Err(AppError::ValidationError("You don't have a signing PIN".to_string()));
+        }
+        self.audit_service.record(Some(user_id), AuditEvent::SigningPinChanged, "removed").await
+    }
+
+    /// Fails unless `pin` is the user's PIN and signing isn't locked. Passes
+    /// when the user has no PIN. While locked, the PIN isn't checked at all.
+    pub async fn verify(&self, user_id: &Uuid, pin: &str, now: DateTime<Utc>) -> Result<()> {
+        let Some(stored) = self.db.get_signing_pin(user_id).await? else {
+            return Ok(());
+        };
+        if let Some(until) = stored.locked_until.filter(|until| *until > now) {
+            return Err(locked_error(until, now));
+        }
+
+        if PasswordManager::verify_password(pin, &stored.pin_hash)? {
+            if stored.failures > 0 || stored.locked_until.is_some() {
+                self.db
+                    .upsert_signing_pin(&SigningPin {
+                        failures: 0,
+                        locked_until: None,
+                        ..stored
+                    })
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let failures = stored.failures + 1;
+        if failures < MAX_PIN_FAILURES {
+            self.db
+                .upsert_signing_pin(&SigningPin {
+                    failures,
+                    locked_until: None,
+                    ..stored
+                })
+                .await?;
+            return Err(AppError::AuthenticationError(format!(
+                "Incorrect PIN; {} attempt(s) left before signing is locked",
+                MAX_PIN_FAILURES - failures
+            )));
+        }
+
+        let until = now + Duration::minutes(PIN_LOCKOUT_MINUTES);
+        self.db
+            .upsert_signing_pin(&SigningPin {
+                failures: 0,
+                locked_until: Some(until),
+                ..stored
+            })
+            .await?;
+        self.audit_service
+            .record(
+                Some(user_id),
+                AuditEvent::SigningPinLockedOut,
+                &format!("{} wrong PINs in a row; locked until {}", failures, until.to_rfc3339()),
+            )
+            .await?;
+        Err(locked_error(until, now))
+    }
+}
+
+fn locked_error(until: DateTime<Utc>, now: DateTime<Utc>) -> AppError {
+    let minutes = ((until - now).num_seconds() + 59) / 60;
+    AppError::AuthenticationError(format!("Too many wrong PINs; signing is locked for {} minute(s)", minutes))
+}
+
+/// 4 to 8 digits, not all the same and not a straight run like 1234 or 9876.
+fn validate_pin(pin: &str) -> Result<()> {
+    if !(MIN_PIN_DIGITS..=MAX_PIN_DIGITS).contains(&pin.len()) || !pin.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AppError::ValidationError(format!(
+            "The PIN must be {} to {} digits",
+            MIN_PIN_DIGITS, MAX_PIN_DIGITS
+        )));
+    }
+
+    let steps: Vec<i16> = pin.as_bytes().windows(2).map(|pair| i16::from(pair[1]) - i16::from(pair[0])).collect();
+    if steps.iter().all(|step| *step == steps[0] && step.abs() <= 1) {
+        return Err(AppError::ValidationError(
+            "That PIN is too easy to guess; avoid repeated digits and runs like 1234".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_malformed_and_trivial_pins() {
+        for pin in ["123", "123456789", "12a4", "0000", "1234", "98765"] {
+            assert!(validate_pin(pin).is_err(), "{} was accepted", pin);
+        }
+        for pin in ["2580", "1357", "904512"] {
+            assert!(validate_pin(pin).is_ok(), "{} was rejected", pin);
+        }
+    }
+
+    #[tokio::test]
+    async fn locks_signing_after_repeated_wrong_pins() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let password_hash = PasswordManager::hash_password("Velvet-Otter-92!").unwrap();
+        db.update_user_password_hash(&user, &password_hash).await.unwrap();
+        let service = SigningPinService::new(db.clone());
+        let now = Utc::now();
+
+        assert!(service.verify(&user, "anything", now).await.is_ok());
+        assert!(service.set(&user, "wrong password", "2580").await.is_err());
+        service.set(&user, "Velvet-Otter-92!", "2580").await.unwrap();
+
+        assert!(service.verify(&user, "2580", now).await.is_ok());
+        for _ in 0..MAX_PIN_FAILURES {
+            assert!(service.verify(&user, "1357", now).await.is_err());
+        }
+        // Locked: even the right PIN is refused until the lock runs out.
+        assert!(service.verify(&user, "2580", now).await.is_err());
+        let later = now + Duration::minutes(PIN_LOCKOUT_MINUTES + 1);
+        assert!(service.verify(&user, "2580", later).await.is_ok());
+
+        service.remove(&user, "Velvet-Otter-92!").await.unwrap();
+        assert!(!service.is_enabled(&user).await.unwrap());
+    }
+}
@@ -0,0 +1,419 @@
+use crate::cli::branding::Branding;
+use crate::cli::output;
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::sms::{SmsCode, SmsMessage, SmsPurpose, SmsReceipt, SmsUsage, SMS_CODE_TTL};
+use crate::utils::validation::Validator;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const DEFAULT_TWILIO_API_URL: &str = "https://api.twilio.com/2010-04-01";
+
+/// Texts allowed per country calling code per hour unless `SMS_COUNTRY_LIMITS`
+/// says otherwise. Caps the bill if someone scripts login attempts.
+pub const DEFAULT_COUNTRY_HOURLY_LIMIT: i64 = 20;
+
+/// Security alerts texted to one account per hour. Later ones are dropped,
+/// so failed logins can't be used to flood someone's phone.
+pub const ACCOUNT_HOURLY_ALERT_LIMIT: i64 = 3;
+
+/// Calling codes that are one or two digits long; every other code has three.
+const SHORT_CALLING_CODES: &[&str] = &[
+    "1", "7", "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46", "47", "48", "49",
+    "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63", "64", "65", "66", "81", "82", "84", "86",
+    "90", "91", "92", "93", "94", "95", "98",
+];
+
+/// Something that can deliver a text message.
+#[async_trait]
+pub trait SmsSender: Send + Sync {
+    /// Short name stored with each message, e.g. `"twilio"`.
+    fn provider(&self) -> &'static str;
+
+    async fn send(&self, to: &str, body: &str) -> Result<SmsReceipt>;
+}
+
+/// Prints messages to the terminal instead of sending them, for development.
+pub struct ConsoleSmsSender;
+
+#[async_trait]
+impl SmsSender for ConsoleSmsSender {
+    fn provider(&self) -> &'static str {
+        "console"
+    }
+
+    async fn send(&self, to: &str, body: &str) -> Result<SmsReceipt> {
+        output::status(&format!("📱 SMS to {}: {}", to, body));
+        Ok(SmsReceipt {
+            provider_message_id: None,
+            cost_usd: Some(0.0),
+        })
+    }
+}
+
+/// Sends through Twilio's Programmable Messaging API.
+pub struct TwilioSmsSender {
+    base_url: String,
+    account_sid: String,
+    auth_token: String,
+    from: String,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwilioMessage {
+    sid: String,
+    /// Negative decimal in `price_unit`, often not known until delivery.
+    price: Option<String>,
+    price_unit: Option<String>,
+}
+
+impl TwilioSmsSender {
+    /// Reads `TWILIO_ACCOUNT_SID`, `TWILIO_AUTH_TOKEN` and `TWILIO_FROM_NUMBER`.
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| AppError::ValidationError(format!("SMS_PROVIDER=twilio needs {} to be set", name)))
+        };
+
+        let base_url = config::var("TWILIO_API_URL")
+            .unwrap_or_else(|| DEFAULT_TWILIO_API_URL.to_string());
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            account_sid: var("TWILIO_ACCOUNT_SID")?,
+            auth_token: var("TWILIO_AUTH_TOKEN")?,
+            from: var("TWILIO_FROM_NUMBER")?,
+            http: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl SmsSender for TwilioSmsSender {
+    fn provider(&self) -> &'static str {
+        "twilio"
+    }
+
+    async fn send(&self, to: &str, body: &str) -> Result<SmsReceipt> {
+        let response = self
+            .http
+            .post(format!("{}/Accounts/{}/Messages.json", self.base_url, self.account_sid))
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[("To", to), ("From", self.from.as_str()), ("Body", body)])
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("SMS request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::InternalError(format!("SMS provider returned HTTP {}", response.status())));
+        }
+
+        let message = response
+            .json::<TwilioMessage>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Invalid SMS provider response: {}", e)))?;
+
+        let cost_usd = match message.price_unit.as_deref() {
+            Some("USD") => message.price.and_then(|price| price.parse::<f64>().ok()).map(f64::abs),
+            _ => None,
+        };
+
+        Ok(SmsReceipt {
+            provider_message_id: Some(message.sid),
+            cost_usd,
+        })
+    }
+}
+
+/// Sends verification codes and security alerts through the provider picked by
+/// `SMS_PROVIDER` (`twilio` or `console`). SMS features are unavailable when
+/// it's unset.
+#[derive(Clone)]
+pub struct SmsService {
+    db: SqliteDatabase,
+    sender: std::result::Result<Option<Arc<dyn SmsSender>>, AppError>,
+    country_limits: HashMap<String, i64>,
+    default_limit: i64,
+    /// Cost recorded when the provider doesn't report one (`SMS_COST_PER_MESSAGE`).
+    estimated_cost_usd: Option<f64>,
+    /// How long a texted code stays valid (`SMS_CODE_TTL_MINUTES`).
+    code_ttl: Duration,
+}
+
+impl SmsService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        let sender: std::result::Result<Option<Arc<dyn SmsSender>>, AppError> =
+            match config::var("SMS_PROVIDER").unwrap_or_default().to_lowercase().as_str() {
+                "" => Ok(None),
+                "console" => Ok(Some(Arc::new(ConsoleSmsSender))),
+                "twilio" => TwilioSmsSender::from_env().map(|sender| Some(Arc::new(sender) as Arc<dyn SmsSender>)),
+                other => Err(AppError::ValidationError(format!("Unknown SMS_PROVIDER '{}'", other))),
+            };
+
+        let default_limit = config::var("SMS_COUNTRY_HOURLY_LIMIT")
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(DEFAULT_COUNTRY_HOURLY_LIMIT);
+
+        Self {
+            db,
+            sender,
+            country_limits: parse_country_limits(&config::var("SMS_COUNTRY_LIMITS").unwrap_or_default()),
+            default_limit,
+            estimated_cost_usd: config::var("SMS_COST_PER_MESSAGE").and_then(|cost| cost.parse().ok()),
+            code_ttl: config::var("SMS_CODE_TTL_MINUTES")
+                .and_then(|minutes| minutes.parse::<i64>().ok())
+                .filter(|minutes| *minutes > 0)
+                .map_or(SMS_CODE_TTL, Duration::minutes),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_sender(db: SqliteDatabase, sender: Arc<dyn SmsSender>, default_limit: i64) -> Self {
+        Self {
+            db,
+            sender: Ok(Some(sender)),
+            country_limits: HashMap::new(),
+            default_limit,
+            estimated_cost_usd: None,
+            code_ttl: SMS_CODE_TTL,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.sender, Ok(Some(_)))
+    }
+
+    fn sender(&self) -> Result<&Arc<dyn SmsSender>> {
+        match &self.sender {
+            Ok(Some(sender)) => Ok(sender),
+            Ok(None) => Err(AppError::ValidationError("SMS is disabled; set SMS_PROVIDER to enable it".to_string())),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// Texts `body` to `phone`, within the hourly limit for its country.
+    pub async fn send(&self, user_id: Option<&Uuid>, phone: &str, purpose: SmsPurpose, body: &str) -> Result<()> {
+        let sender = self.sender()?;
+        Validator::validate_phone_number(phone)?;
+        let country_code = calling_code(phone)
+            .ok_or_else(|| AppError::ValidationError(format!("Can't tell which country {} is in", phone)))?;
+
+        let limit = self.country_limits.get(country_code).copied().unwrap_or(self.default_limit);
+        let now = Utc::now();
+        if self.db.count_sms_messages_since(country_code, now - Duration::hours(1)).await? >= limit {
+            return Err(AppError::ValidationError(format!(
+                "Too many text messages to +{} numbers in the last hour; please try again later",
+                country_code
+            )));
+        }
+
+        let receipt = sender.send(phone, body).await?;
+
+        self.db
+            .create_sms_message(&SmsMessage {
+                id: Uuid::new_v4(),
+                user_id: user_id.copied(),
+                country_code: country_code.to_string(),
+                purpose,
+                provider: sender.provider().to_string(),
+                provider_message_id: receipt.provider_message_id,
+                cost_usd: receipt.cost_usd.or(self.estimated_cost_usd),
+                created_at: now,
+            })
+            .await
+    }
+
+    /// Texts a fresh six-digit code for the user to type back.
+    pub async fn send_code(&self, user_id: &Uuid, phone: &str, purpose: SmsPurpose) -> Result<SmsCode> {
+        let code = SmsCode {
+            code: random_code(),
+            expires_at: Utc::now() + self.code_ttl,
+        };
+
+        let body = format!(
+            "Your {} {} code is {}. It expires in {} minutes. Never share it.",
+            Branding::current().product_name,
+            code_label(purpose),
+            code.code,
+            self.code_ttl.num_minutes()
+        );
+        self.send(Some(user_id), phone, purpose, &body).await?;
+
+        Ok(code)
+    }
+
+    /// Texts a security alert to the user's confirmed number, if they have one
+    /// and haven't had [`ACCOUNT_HOURLY_ALERT_LIMIT`] in the last hour.
+    /// Best effort: a provider outage must not block the action being reported.
+    pub async fn alert(&self, user_id: &Uuid, message: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let Ok(Some(settings)) = self.db.get_user_settings(user_id).await else {
+            return;
+        };
+        let Some(phone) = settings.sms_phone_number else {
+            return;
+        };
+        let since = Utc::now() - Duration::hours(1);
+        match self.db.count_user_sms_messages_since(user_id, SmsPurpose::SecurityAlert, since).await {
+            Ok(sent) if sent < ACCOUNT_HOURLY_ALERT_LIMIT => {}
+            _ => return,
+        }
+
+        let body = format!("{} security alert: {}", Branding::current().product_name, message);
+        if let Err(e) = self.send(Some(user_id), &phone, SmsPurpose::SecurityAlert, &body).await {
+            output::status(&format!("⚠️  Could not send SMS alert: {}", e));
+        }
+    }
+
+    pub async fn usage_since(&self, since: chrono::DateTime<Utc>) -> Result<SmsUsage> {
+        self.db.get_sms_usage_since(since).await
+    }
+}
+
+/// The country calling code of an E.164 number, without the `+`.
+pub fn calling_code(phone: &str) -> Option<&str> {
+    let digits = phone.strip_prefix('+')?;
+    if digits.len() < 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    (1..=2)
+        .map(|len| &digits[..len])
+        .find(|code| SHORT_CALLING_CODES.contains(code))
+        .or(Some(&digits[..3]))
+}
+
+/// Parses overrides like `"1=100,254=5"`. Malformed entries are skipped.
+fn parse_country_limits(spec: &str) -> HashMap<String, i64> {
+    spec.split(',')
+        .filter_map(|entry| {
+            let (code, limit) = entry.split_once('=')?;
+            Some((code.trim().trim_start_matches('+').to_string(), limit.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn random_code() -> String {
+    // Reject the top of the range so every code is equally likely.
+    loop {
+        let n = OsRng.next_u32();
+        if n < u32::MAX - u32::MAX % 1_000_000 {
+            return format!("{:06}", n % 1_000_000);
+        }
+    }
+}
+
+fn code_label(purpose: SmsPurpose) -> &'static str {
+    match purpose {
+        SmsPurpose::PhoneConfirmation => "phone confirmation",
+        SmsPurpose::LoginCode => "login",
+        SmsPurpose::PaymentConfirmation => "payment confirmation",
+        SmsPurpose::SecurityAlert => "security",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user_settings::UserSettings;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl SmsSender for RecordingSender {
+        fn provider(&self) -> &'static str {
+            "test"
+        }
+
+        async fn send(&self, to: &str, body: &str) -> Result<SmsReceipt> {
+            self.sent.lock().unwrap().push((to.to_string(), body.to_string()));
+            Ok(SmsReceipt {
+                provider_message_id: Some("SM1".to_string()),
+                cost_usd: Some(0.05),
+            })
+        }
+    }
+
+    #[test]
+    fn finds_calling_codes_of_every_length() {
+        assert_eq!(calling_code("+15555550100"), Some("1"));
+        assert_eq!(calling_code("+447700900123"), Some("44"));
+        assert_eq!(calling_code("+254712345678"), Some("254"));
+        assert_eq!(calling_code("254712345678"), None);
+    }
+
+    #[test]
+    fn parses_country_limit_overrides() {
+        let limits = parse_country_limits("1=100, +254=5,bogus,44=x");
+        assert_eq!(limits.get("1"), Some(&100));
+        assert_eq!(limits.get("254"), Some(&5));
+        assert_eq!(limits.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn codes_are_texted_and_logged_with_their_cost() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let sender = Arc::new(RecordingSender::default());
+        let sms = SmsService::with_sender(db, sender.clone(), 10);
+
+        let code = sms.send_code(&user, "+254712345678", SmsPurpose::LoginCode).await.unwrap();
+
+        let sent = sender.sent.lock().unwrap().clone();
+        assert_eq!(sent[0].0, "+254712345678");
+        assert!(sent[0].1.contains(&code.code));
+        assert!(code.matches(&code.code, Utc::now()));
+        assert!(!code.matches(&code.code, code.expires_at));
+
+        let usage = sms.usage_since(Utc::now() - Duration::hours(1)).await.unwrap();
+        assert_eq!(usage, SmsUsage { messages: 1, cost_usd: 0.05 });
+    }
+
+    #[tokio::test]
+    async fn hourly_limits_apply_per_country() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let sms = SmsService::with_sender(db, Arc::new(RecordingSender::default()), 2);
+
+        for _ in 0..2 {
+            sms.send(Some(&user), "+254712345678", SmsPurpose::SecurityAlert, "hi").await.unwrap();
+        }
+
+        assert!(sms.send(Some(&user), "+254700000000", SmsPurpose::SecurityAlert, "hi").await.is_err());
+        assert!(sms.send(Some(&user), "+447700900123", SmsPurpose::SecurityAlert, "hi").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn alerts_are_limited_per_account() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let mut settings = UserSettings::defaults_for(user);
+        settings.sms_phone_number = Some("+254712345678".to_string());
+        db.upsert_user_settings(&settings).await.unwrap();
+        let sender = Arc::new(RecordingSender::default());
+        let sms = SmsService::with_sender(db, sender.clone(), 100);
+
+        for _ in 0..ACCOUNT_HOURLY_ALERT_LIMIT + 2 {
+            sms.alert(&user, "someone just failed to log in to your account.").await;
+        }
+        assert_eq!(sender.sent.lock().unwrap().len() as i64, ACCOUNT_HOURLY_ALERT_LIMIT);
+        sms.send_code(&user, "+254712345678", SmsPurpose::LoginCode).await.unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::stream_ticket::{StreamTicket, STREAM_TICKET_TTL};
+use crate::services::session_service::SessionService;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Tickets that open the event stream from a browser's `EventSource`, which
+/// can't send an `Authorization` header. Unlike an access token in the URL,
+/// where proxies and server logs keep it, a ticket works once and only for
+/// [`STREAM_TICKET_TTL`].
+pub struct StreamTicketService {
+    db: SqliteDatabase,
+    session_service: SessionService,
+}
+
+impl StreamTicketService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            session_service: SessionService::new(db.clone()),
+            db,
+        }
+    }
+
+    /// A ticket for a stream as `ticket` describes, and when it expires.
+    pub async fn issue(&self, ticket: &StreamTicket) -> Result<(String, DateTime<Utc>)> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = URL_SAFE_NO_PAD.encode(bytes);
+
+        let now = Utc::now();
+        let expires_at = now + STREAM_TICKET_TTL;
+        self.db.create_stream_ticket(&hash(&token), ticket, expires_at, now).await?;
+        Ok((token, expires_at))
+    }
+
+    /// Uses up `token`, which must not have expired, and whose login must
+    /// still be active.
+    pub async fn redeem(&self, token: &str) -> Result<StreamTicket> {
+        let ticket = self.db.take_stream_ticket(&hash(token.trim()), Utc::now()).await?.ok_or_else(|| {
+            AppError::AuthenticationError("This stream ticket has expired or was already used; ask for another".to_string())
+        })?;
+        if let Some(session_id) = &ticket.session_id {
+            self.session_service.validate(session_id).await?;
+        }
+        Ok(ticket)
+    }
+}
+
+fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
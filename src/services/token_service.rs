@@ -0,0 +1,306 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use crate::models::api_key::ApiScope;
+use crate::models::session::{AccessClaims, SavedLogin, Session};
+use crate::services::session_service::SessionService;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+
+/// HS256 keys shorter than the hash output are easy to brute-force.
+const MIN_SIGNING_KEY_LEN: usize = 32;
+
+/// Where "stay logged in" keeps its token when `WALLET_SESSION_FILE` is unset.
+pub const DEFAULT_SESSION_FILE: &str = ".wallet-session";
+
+/// Issues and checks signed access tokens for sessions. A token is only as
+/// good as its session: [`TokenService::authenticate`] rejects tokens whose
+/// session has been revoked or has expired, even if the token hasn't.
+pub struct TokenService {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    ttl: Option<Duration>,
+}
+
+impl TokenService {
+    /// Reads the signing key from `JWT_SIGNING_KEY`, which is never taken from
+    /// the config file. Tokens are disabled when it is unset.
+    /// `ACCESS_TOKEN_TTL_MINUTES` shortens tokens below the session lifetime.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(key) = env::var("JWT_SIGNING_KEY").ok().filter(|key| !key.is_empty()) else {
+            return Ok(None);
+        };
+
+        let ttl = config::var("ACCESS_TOKEN_TTL_MINUTES")
+            .map(|minutes| match minutes.parse::<i64>() {
+                Ok(minutes) if minutes > 0 => Ok(Duration::minutes(minutes)),
+                _ => Err(AppError::ValidationError(format!(
+                    "ACCESS_TOKEN_TTL_MINUTES must be a positive number of minutes, not '{}'",
+                    minutes
+                ))),
+            })
+            .transpose()?;
+
+        Self::new(key.as_bytes(), ttl).map(Some)
+    }
+
+    pub fn new(key: &[u8], ttl: Option<Duration>) -> Result<Self> {
+        if key.len() < MIN_SIGNING_KEY_LEN {
+            return Err(AppError::ValidationError(format!(
+                "JWT_SIGNING_KEY must be at least {} bytes",
+                MIN_SIGNING_KEY_LEN
+            )));
+        }
+
+        Ok(Self {
+            encoding_key: EncodingKey::from_secret(key),
+            decoding_key: DecodingKey::from_secret(key),
+            ttl,
+        })
+    }
+
+    /// Signs a token for `session`. It never outlives the session.
+    pub fn issue(&self, session: &Session) -> Result<String> {
+        let now = Utc::now();
+        let expires_at = match self.ttl {
+            Some(ttl) => session.expires_at.min(now + ttl),
+            None => session.expires_at,
+        };
+        let claims = AccessClaims {
+            sub: session.user_id,
+            sid: session.id,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+            scope: Some(session.scopes.iter().map(ApiScope::as_str).collect::<Vec<_>>().join(" ")),
+        };
+
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+            .map_err(|e| AppError::InternalError(format!("Failed to sign access token: {}", e)))
+    }
+
+    /// Checks the signature and expiry only. Use
+    /// [`TokenService::authenticate`] to also check the session.
+    pub fn verify(&self, token: &str) -> Result<AccessClaims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+
+        jsonwebtoken::decode::<AccessClaims>(token, &self.decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|_| AppError::AuthenticationError("Invalid or expired access token".to_string()))
+    }
+
+    /// The session a token stands for, if the token is valid and the session
+    /// is still active, with only the scopes both of them have. This is the
+    /// check every authenticated request makes.
+    pub async fn authenticate(&self, token: &str, session_service: &SessionService) -> Result<Session> {
+        let claims = self.verify(token)?;
+        let mut session = session_service.validate(&claims.sid).await?;
+
+        if session.user_id != claims.sub {
+            return Err(AppError::AuthenticationError("Invalid or expired access token".to_string()));
+        }
+        if let Some(scope) = &claims.scope {
+            let granted = scope.split(' ').filter_map(ApiScope::parse).collect::<Vec<_>>();
+            session.scopes.retain(|scope| granted.contains(scope));
+        }
+        Ok(session)
+    }
+}
+
+/// Keyring entry for the saved login when `WALLET_SESSION_STORE=keyring`.
+#[cfg(feature = "keyring")]
+const KEYRING_USER: &str = "saved-login";
+
+/// Where "stay logged in" keeps its tokens: a file readable only by its
+/// owner, or the OS keyring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionStore {
+    File(String),
+    Keyring,
+}
+
+impl SessionStore {
+    /// Reads `WALLET_SESSION_STORE`: `file`, the default, or `keyring`.
+    pub fn from_env() -> Result<Self> {
+        match config::var("WALLET_SESSION_STORE").unwrap_or_default().to_lowercase().as_str() {
+            "" | "file" => Ok(Self::file()),
+            "keyring" if cfg!(feature = "keyring") => Ok(SessionStore::Keyring),
+            "keyring" => Err(not_built_with_keyring()),
+            other => Err(AppError::ValidationError(format!("Unknown WALLET_SESSION_STORE '{}'", other))),
+        }
+    }
+
+    /// The file at `WALLET_SESSION_FILE`, or [`DEFAULT_SESSION_FILE`].
+    pub fn file() -> Self {
+        SessionStore::File(config::var("WALLET_SESSION_FILE").unwrap_or_else(|| DEFAULT_SESSION_FILE.to_string()))
+    }
+
+    /// The saved login, or `None` if there is none or it can't be read back.
+    pub fn load(&self) -> Result<Option<SavedLogin>> {
+        let json = match self {
+            SessionStore::File(path) => match fs::read_to_string(path) {
+                Ok(json) => Some(json),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => return Err(AppError::InternalError(format!("Can't read {}: {}", path, e))),
+            },
+            SessionStore::Keyring => keyring_load()?,
+        };
+
+        Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+
+    pub fn save(&self, login: &SavedLogin) -> Result<()> {
+        let json = serde_json::to_string(login)
+            .map_err(|e| AppError::InternalError(format!("Failed to serialize the saved login: {}", e)))?;
+
+        match self {
+            SessionStore::File(path) => {
+                let write_error = |e: std::io::Error| AppError::InternalError(format!("Can't write {}: {}", path, e));
+                let mut options = fs::OpenOptions::new();
+                options.create(true).write(true).truncate(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::OpenOptionsExt;
+                    options.mode(0o600);
+                }
+
+                let mut file = options.open(path).map_err(write_error)?;
+                std::io::Write::write_all(&mut file, json.as_bytes()).map_err(write_error)
+            }
+            SessionStore::Keyring => keyring_save(&json),
+        }
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        match self {
+            SessionStore::File(path) => match fs::remove_file(path) {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(AppError::InternalError(format!("Can't remove {}: {}", path, e))),
+            },
+            SessionStore::Keyring => keyring_clear(),
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_entry() -> Result<keyring::Entry> {
+    keyring::Entry::new(crate::database::encryption::KEYRING_SERVICE, KEYRING_USER).map_err(keyring_error)
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_error(e: keyring::Error) -> AppError {
+    AppError::InternalError(format!("OS keyring error: {}", e))
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_load() -> Result<Option<String>> {
+    match keyring_entry()?.get_password() {
+        Ok(json) => Ok(Some(json)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_save(json: &str) -> Result<()> {
+    keyring_entry()?.set_password(json).map_err(keyring_error)
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_clear() -> Result<()> {
+    match keyring_entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_load() -> Result<Option<String>> {
+    Err(not_built_with_keyring())
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_save(_json: &str) -> Result<()> {
+    Err(not_built_with_keyring())
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_clear() -> Result<()> {
+    Err(not_built_with_keyring())
+}
+
+fn not_built_with_keyring() -> AppError {
+    AppError::ValidationError(
+        "This build has no OS keyring support; rebuild with `--features keyring` to keep the saved login there".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::sqlite::SqliteDatabase;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[tokio::test]
+    async fn tokens_stop_working_when_their_session_ends() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let sessions = SessionService::new(db);
+        let tokens = TokenService::new(KEY, Some(Duration::minutes(5))).unwrap();
+
+        let session = sessions.start(&user, "CLI").await.unwrap();
+        let token = tokens.issue(&session).unwrap();
+        let claims = tokens.verify(&token).unwrap();
+        assert_eq!((claims.sub, claims.sid), (user, session.id));
+        assert!(claims.exp <= (Utc::now() + Duration::minutes(5)).timestamp());
+        assert_eq!(tokens.authenticate(&token, &sessions).await.unwrap().id, session.id);
+
+        sessions.logout(&user, &session.id).await.unwrap();
+        assert!(tokens.authenticate(&token, &sessions).await.is_err());
+    }
+
+    #[test]
+    fn session_files_round_trip_and_clear() {
+        let path = std::env::temp_dir().join(format!("wallet-session-{}", uuid::Uuid::new_v4()));
+        let store = SessionStore::File(path.to_string_lossy().to_string());
+        let login = SavedLogin {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            remote: None,
+        };
+
+        assert_eq!(store.load().unwrap(), None);
+        store.save(&login).unwrap();
+        assert_eq!(store.load().unwrap(), Some(login));
+        store.clear().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        store.clear().unwrap();
+    }
+
+    #[test]
+    fn rejects_foreign_expired_and_weak_tokens() {
+        let tokens = TokenService::new(KEY, None).unwrap();
+        let other = TokenService::new(b"another key that is long enough!!", None).unwrap();
+        let now = Utc::now();
+        let session = Session {
+            id: uuid::Uuid::new_v4(),
+            user_id: uuid::Uuid::new_v4(),
+            device_label: "CLI".to_string(),
+            created_at: now,
+            expires_at: now + Duration::hours(1),
+            last_seen_at: now,
+            revoked_at: None,
+            scopes: ApiScope::ALL.to_vec(),
+        };
+
+        let token = other.issue(&session).unwrap();
+        assert!(tokens.verify(&token).is_err());
+        let expired = tokens.issue(&Session { expires_at: now - Duration::seconds(1), ..session }).unwrap();
+        assert!(tokens.verify(&expired).is_err());
+        assert!(TokenService::new(b"short", None).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::transaction::WalletTransaction;
+use crate::models::transaction_export::{ExportCheckpoint, TransactionExportReport, TRANSACTION_EXPORT_SCHEMA_VERSION};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Where Parquet exports go when `EXPORT_DIR` is unset.
+pub const DEFAULT_EXPORT_DIR: &str = "exports";
+
+/// Transactions read, and written per month, at a time.
+const BATCH_SIZE: u32 = 10_000;
+
+const CHECKPOINT_FILE: &str = "_checkpoint.json";
+
+/// Copies transaction history to Parquet files for analytics pipelines. Each
+/// run only appends: transactions inserted or changed since the checkpoint go
+/// to new files under `month=YYYY-MM` directories, by when they were created,
+/// and files already written are never touched again. A transaction that
+/// changes after it was exported, e.g. from pending to confirmed, is written
+/// again; readers keep the row with the highest `change_seq` for each `id`.
+/// Export before `db archive` removes old records, or they won't be included.
+pub struct TransactionExportService {
+    db: SqliteDatabase,
+    dir: String,
+}
+
+impl TransactionExportService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            db,
+            dir: config::var("EXPORT_DIR").unwrap_or_else(|| DEFAULT_EXPORT_DIR.to_string()),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_dir(db: SqliteDatabase, dir: &str) -> Self {
+        Self { db, dir: dir.to_string() }
+    }
+
+    /// Exports everything that changed since the last run. The checkpoint
+    /// moves after each batch's files are synced, so a run that fails part
+    /// way picks up after the last whole batch and at worst writes a few
+    /// transactions twice.
+    pub async fn export(&self, now: DateTime<Utc>) -> Result<TransactionExportReport> {
+        let root = Path::new(&self.dir)
+            .join("transactions")
+            .join(format!("v{}", TRANSACTION_EXPORT_SCHEMA_VERSION));
+        let mut checkpoint = read_checkpoint(&root)?;
+        let mut report = TransactionExportReport {
+            dir: root.to_string_lossy().to_string(),
+            schema_version: TRANSACTION_EXPORT_SCHEMA_VERSION,
+            exported: 0,
+            files: Vec::new(),
+            checkpoint: checkpoint.clone(),
+        };
+
+        loop {
+            let batch = self.db.get_transactions_changed_after(checkpoint.last_change_seq, BATCH_SIZE).await?;
+            let (Some((first, _)), Some((last, _))) = (batch.first(), batch.last()) else {
+                break;
+            };
+
+            let mut months: BTreeMap<String, Vec<&(i64, WalletTransaction)>> = BTreeMap::new();
+            for row in &batch {
+                months.entry(row.1.created_at.format("%Y-%m").to_string()).or_default().push(row);
+            }
+            for (month, rows) in months {
+                let path = root.join(format!("month={}", month)).join(format!("part-{:012}-{:012}.parquet", first, last));
+                write_partition(&path, &rows)?;
+                report.files.push(path.to_string_lossy().to_string());
+            }
+
+            checkpoint = ExportCheckpoint {
+                last_change_seq: *last,
+                exported_at: Some(now),
+            };
+            write_checkpoint(&root, &checkpoint)?;
+            report.exported += batch.len();
+        }
+
+        report.checkpoint = checkpoint;
+        Ok(report)
+    }
+}
+
+fn read_checkpoint(root: &Path) -> Result<ExportCheckpoint> {
+    let path = root.join(CHECKPOINT_FILE);
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|e| AppError::ValidationError(format!("{} is not an export checkpoint: {}", path.display(), e))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(ExportCheckpoint::default()),
+        Err(e) => Err(AppError::InternalError(format!("Can't read {}: {}", path.display(), e))),
+    }
+}
+
+/// Replaces the checkpoint in one rename, so it is never half-written.
+fn write_checkpoint(root: &Path, checkpoint: &ExportCheckpoint) -> Result<()> {
+    let path = root.join(CHECKPOINT_FILE);
+    let partial = root.join(format!("{}.tmp", CHECKPOINT_FILE));
+    let write_error = |e: std::io::Error| AppError::InternalError(format!("Can't write {}: {}", path.display(), e));
+
+    let json = serde_json::to_vec_pretty(checkpoint)
+        .map_err(|e| AppError::InternalError(format!("Failed to encode export checkpoint: {}", e)))?;
+    fs::write(&partial, json).map_err(write_error)?;
+    fs::File::open(&partial).and_then(|file| file.sync_all()).map_err(write_error)?;
+    fs::rename(&partial, &path).map_err(write_error)
+}
+
+/// Writes `rows` to a new Parquet file at `path`. The file only appears under
+/// its name once it is complete and synced.
+#[cfg(feature = "parquet")]
+fn write_partition(path: &Path, rows: &[&(i64, WalletTransaction)]) -> Result<()> {
+    use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use parquet::arrow::ArrowWriter;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let write_error = |e: String| AppError::InternalError(format!("Can't write {}: {}", path.display(), e));
+    let partial = path.with_extension("parquet.tmp");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| write_error(e.to_string()))?;
+    }
+
+    let text = |value: fn(&WalletTransaction) -> Option<&str>| -> ArrayRef {
+        Arc::new(rows.iter().map(|(_, tx)| value(tx)).collect::<StringArray>())
+    };
+    let integer = |value: fn(&(i64, WalletTransaction)) -> Option<i64>| -> ArrayRef {
+        Arc::new(rows.iter().map(|row| value(row)).collect::<Int64Array>())
+    };
+    let timestamp = |value: fn(&WalletTransaction) -> DateTime<Utc>| -> ArrayRef {
+        Arc::new(TimestampMicrosecondArray::from_iter_values(rows.iter().map(|(_, tx)| value(tx).timestamp_micros())).with_timezone("UTC"))
+    };
+    let utc = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+
+    let schema = Schema::new_with_metadata(
+        vec![
+            Field::new("change_seq", DataType::Int64, false),
+            Field::new("id", DataType::Utf8, false),
+            Field::new("account", DataType::Utf8, false),
+            Field::new("hash", DataType::Utf8, false),
+            Field::new("operation_index", DataType::Int64, false),
+            Field::new("direction", DataType::Utf8, false),
+            Field::new("asset_code", DataType::Utf8, false),
+            Field::new("amount_stroops", DataType::Int64, false),
+            Field::new("counterparty", DataType::Utf8, false),
+            Field::new("memo", DataType::Utf8, true),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("ledger", DataType::Int64, true),
+            Field::new("error", DataType::Utf8, true),
+            Field::new("created_at", utc.clone(), false),
+            Field::new("updated_at", utc, false),
+        ],
+        HashMap::from([("schema_version".to_string(), TRANSACTION_EXPORT_SCHEMA_VERSION.to_string())]),
+    );
+    let ids: Vec<String> = rows.iter().map(|(_, tx)| tx.id.to_string()).collect();
+    let columns: Vec<ArrayRef> = vec![
+        integer(|(seq, _)| Some(*seq)),
+        Arc::new(StringArray::from_iter_values(ids)),
+        text(|tx| Some(&tx.account)),
+        text(|tx| Some(&tx.hash)),
+        integer(|(_, tx)| Some(tx.operation_index)),
+        text(|tx| Some(tx.direction.as_str())),
+        text(|tx| Some(&tx.asset_code)),
+        integer(|(_, tx)| Some(tx.amount_stroops)),
+        text(|tx| Some(&tx.counterparty)),
+        text(|tx| tx.memo.as_deref()),
+        text(|tx| Some(tx.status.as_str())),
+        integer(|(_, tx)| tx.ledger),
+        text(|tx| tx.error.as_deref()),
+        timestamp(|tx| tx.created_at),
+        timestamp(|tx| tx.updated_at),
+    ];
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).map_err(|e| write_error(e.to_string()))?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(&partial).map_err(|e| write_error(e.to_string()))?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).map_err(|e| write_error(e.to_string()))?;
+    writer.write(&batch).map_err(|e| write_error(e.to_string()))?;
+    let file = writer.into_inner().map_err(|e| write_error(e.to_string()))?;
+    file.sync_all().map_err(|e| write_error(e.to_string()))?;
+    fs::rename(&partial, path).map_err(|e| write_error(e.to_string()))
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_partition(_path: &Path, _rows: &[&(i64, WalletTransaction)]) -> Result<()> {
+    Err(AppError::ValidationError("`db export` needs a build with the parquet feature".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::{TransactionDirection, TransactionStatus};
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn transaction(hash: &str, at: DateTime<Utc>, status: TransactionStatus) -> WalletTransaction {
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: "GA".to_string(),
+            hash: hash.to_string(),
+            operation_index: 1,
+            direction: TransactionDirection::Incoming,
+            asset_code: "XLM".to_string(),
+            amount_stroops: 10_000_000,
+            counterparty: "GOTHER".to_string(),
+            memo: None,
+            status,
+            ledger: None,
+            error: None,
+            request_id: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    fn read(path: &str) -> (Vec<i64>, Vec<String>, String) {
+        use arrow_array::{Array, Int64Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(fs::File::open(path).unwrap()).unwrap();
+        let version = reader.schema().metadata()["schema_version"].clone();
+        let batch = reader.build().unwrap().next().unwrap().unwrap();
+        let seqs = batch.column_by_name("change_seq").unwrap().as_any().downcast_ref::<Int64Array>().unwrap();
+        let statuses = batch.column_by_name("status").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        (
+            seqs.values().to_vec(),
+            (0..statuses.len()).map(|i| statuses.value(i).to_string()).collect(),
+            version,
+        )
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[tokio::test]
+    async fn writes_nothing_without_the_parquet_feature() {
+        let db = SqliteDatabase::in_memory().await;
+        let dir = std::env::temp_dir().join(format!("wallet-export-{}", Uuid::new_v4()));
+        let service = TransactionExportService::with_dir(db.clone(), &dir.to_string_lossy());
+        let now = Utc.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap();
+
+        assert_eq!(service.export(now).await.unwrap().exported, 0);
+        db.upsert_transaction(&transaction("aa", now, TransactionStatus::Confirmed)).await.unwrap();
+        assert!(service.export(now).await.is_err());
+        assert!(!dir.exists());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn appends_what_changed_since_the_checkpoint_by_month() {
+        let db = SqliteDatabase::in_memory().await;
+        let dir = std::env::temp_dir().join(format!("wallet-export-{}", Uuid::new_v4()));
+        let service = TransactionExportService::with_dir(db.clone(), &dir.to_string_lossy());
+        let now = Utc.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap();
+        let at = |month, day| Utc.with_ymd_and_hms(2025, month, day, 9, 0, 0).unwrap();
+
+        db.upsert_transaction(&transaction("aa", at(4, 3), TransactionStatus::Confirmed)).await.unwrap();
+        db.upsert_transaction(&transaction("bb", at(5, 20), TransactionStatus::Pending)).await.unwrap();
+
+        let first = service.export(now).await.unwrap();
+        assert_eq!((first.exported, first.files.len(), first.checkpoint.last_change_seq), (2, 2, 2));
+        assert!(first.files[0].contains("month=2025-04") && first.files[1].contains("month=2025-05"));
+        assert_eq!(read(&first.files[1]), (vec![2], vec!["pending".to_string()], "1".to_string()));
+
+        let nothing = service.export(now).await.unwrap();
+        assert_eq!((nothing.exported, nothing.files.len()), (0, 0));
+
+        db.update_transaction_status("GA", "bb", TransactionStatus::Confirmed, Some(9), None).await.unwrap();
+        let second = service.export(now).await.unwrap();
+        assert_eq!((second.exported, second.checkpoint.last_change_seq), (1, 3));
+        assert_eq!(read(&second.files[0]).1, ["confirmed"]);
+        // The earlier file still holds the pending row.
+        assert_eq!(read(&first.files[1]).1, ["pending"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,417 @@
+use crate::cli::output;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+use crate::services::hook_service::{self, HookPoint, HookService};
+use crate::stellar::amount::{self, MIN_STARTING_BALANCE_STROOPS};
+use crate::stellar::horizon::{HorizonClient, SubmitTransactionResponse};
+use crate::stellar::network::Network;
+use crate::stellar::signer::Signer;
+use crate::stellar::transaction::{sign_transaction, TransactionBuilder, TRANSACTION_TIMEOUT_SECS};
+use crate::utils::request_id;
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long a sent payment is remembered when looking for accidental repeats.
+pub const DUPLICATE_WINDOW: Duration = Duration::from_secs(120);
+
+/// After this long, a payment Horizon hasn't seen can no longer get into a
+/// ledger: its time bounds have run out, with a minute's slack for clocks.
+const PENDING_LIMIT: chrono::Duration = chrono::Duration::seconds(TRANSACTION_TIMEOUT_SECS as i64 + 60);
+
+pub struct TransactionService {
+    horizon: HorizonClient,
+    network: Network,
+    hooks: Option<Arc<HookService>>,
+    db: Option<SqliteDatabase>,
+}
+
+impl TransactionService {
+    pub fn new(network: Network) -> Self {
+        Self {
+            horizon: HorizonClient::new(&network.horizon_url),
+            network,
+            hooks: None,
+            db: None,
+        }
+    }
+
+    /// Runs the operator's `before_payment` hook before every payment is signed.
+    pub fn with_hooks(mut self, hooks: Arc<HookService>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Records every payment it signs in the local `transactions` table.
+    pub fn with_db(mut self, db: SqliteDatabase) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Whether `address` already exists on the ledger.
+    pub async fn account_exists(&self, address: &str) -> Result<bool> {
+        Ok(self.horizon.get_account(address).await?.is_some())
+    }
+
+    /// How long ago an identical payment (same source, destination, amount and
+    /// memo) was sent, if within `DUPLICATE_WINDOW` and it hasn't failed.
+    /// Catches fat-fingered retries, from this process or any other sharing
+    /// the database; callers should ask before sending it again.
+    pub async fn recent_duplicate(&self, source: &str, destination: &str, amount: &str, memo: Option<&str>) -> Result<Option<Duration>> {
+        let Some(db) = &self.db else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        let window = chrono::Duration::from_std(DUPLICATE_WINDOW).unwrap_or_default();
+
+        let sent_at = db.last_matching_payment(source, destination, amount::to_stroops(amount)?, memo, now - window).await?;
+        Ok(sent_at.map(|sent_at| (now - sent_at).to_std().unwrap_or_default()))
+    }
+
+    /// Builds, signs and submits a native XLM payment from `source`. Destinations
+    /// that don't exist yet are created with a `create_account` operation instead.
+    pub async fn send_payment(&self, source: &dyn Signer, destination: &str, amount: &str, memo: Option<&str>) -> Result<SubmitTransactionResponse> {
+        let stroops = amount::to_stroops(amount)?;
+
+        let account = self
+            .horizon
+            .get_account(&source.public_key())
+            .await?
+            .ok_or_else(|| AppError::StellarError("Your account is not funded on the network yet".to_string()))?;
+
+        let builder = TransactionBuilder::new(&source.public_key(), account.sequence_number()?)?;
+
+        let destination_exists = self.account_exists(destination).await?;
+
+        if let Some(hooks) = &self.hooks {
+            let event = hook_service::event(&[
+                ("source", source.public_key().into()),
+                ("destination", destination.into()),
+                ("amount", amount.into()),
+                ("asset", "XLM".into()),
+                ("memo", memo.unwrap_or_default().into()),
+                ("network", self.network.name.clone().into()),
+                ("new_account", (!destination_exists).into()),
+            ]);
+
+            for note in hooks.check(HookPoint::BeforePayment, event)? {
+                output::status(&format!("📎 {}", note));
+            }
+        }
+
+        let mut builder = if destination_exists {
+            builder.payment(destination, stroops)?
+        } else {
+            if stroops < MIN_STARTING_BALANCE_STROOPS {
+                return Err(AppError::ValidationError(
+                    "The destination account doesn't exist yet; send at least 1 XLM to create it".to_string(),
+                ));
+            }
+            builder.create_account(destination, stroops)?
+        };
+
+        if let Some(memo) = memo {
+            builder = builder.memo_text(memo)?;
+        }
+
+        let signed = sign_transaction(builder.build()?, source, &self.network)?;
+        output::status(&format!("✍️  Signed transaction {}", signed.hash));
+
+        let source_key = source.public_key();
+        let now = Utc::now();
+        let outgoing = WalletTransaction {
+            id: Uuid::new_v4(),
+            account: source_key.clone(),
+            hash: signed.hash.clone(),
+            operation_index: 1,
+            direction: TransactionDirection::Outgoing,
+            asset_code: "XLM".to_string(),
+            amount_stroops: stroops,
+            counterparty: destination.to_string(),
+            memo: memo.map(str::to_string),
+            status: TransactionStatus::Pending,
+            ledger: None,
+            error: None,
+            request_id: request_id::current(),
+            created_at: now,
+            updated_at: now,
+        };
+        if let Some(db) = &self.db {
+            db.upsert_transaction(&outgoing).await?;
+        }
+
+        let response = match self.horizon.submit_transaction(&signed).await {
+            Ok(response) => response,
+            Err(e) => {
+                // An unconfirmed payment stays pending: it may still go
+                // through, and `confirm` finds out whether it did.
+                let status = match e {
+                    AppError::UnconfirmedError { .. } => TransactionStatus::Pending,
+                    _ => TransactionStatus::Failed,
+                };
+                if let Some(db) = &self.db {
+                    if let Err(save) = db.update_transaction_status(&source_key, &signed.hash, status, None, Some(&e.to_string())).await {
+                        tracing::warn!(hash = %signed.hash, "Couldn't record how a payment ended: {}", save);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        self.record_confirmed(&outgoing, response.ledger).await;
+
+        Ok(response)
+    }
+
+    /// Who received the payment sent as `hash` to `destination`, and the
+    /// copy in their history, if `destination` is a wallet address here.
+    pub async fn received(&self, hash: &str, destination: &str) -> Result<Option<(Uuid, WalletTransaction)>> {
+        let Some(db) = &self.db else {
+            return Ok(None);
+        };
+        let Some(owner) = db.get_wallet_owner(destination).await? else {
+            return Ok(None);
+        };
+        Ok(db.get_transaction(destination, hash, 1).await?.map(|transaction| (owner, transaction)))
+    }
+
+    /// How a payment that ended in [`AppError::UnconfirmedError`] turned
+    /// out: its response once it is in a ledger, and a Stellar error if it
+    /// failed there or its time bounds ran out before it got in, after which
+    /// it is safe to send again. Until then it is still unconfirmed.
+    pub async fn confirm(&self, hash: &str) -> Result<SubmitTransactionResponse> {
+        let sent = match &self.db {
+            Some(db) => db.get_outgoing_transaction(hash).await?,
+            None => None,
+        };
+        let still_pending = |reason: &str| AppError::UnconfirmedError {
+            hash: hash.to_string(),
+            message: format!("Transaction {} hasn't been confirmed yet ({}); retry shortly", hash, reason),
+        };
+
+        let found = self.horizon.get_transaction(hash).await.map_err(|e| still_pending(e.message()))?;
+        let error = match found {
+            Some(found) if found.successful => {
+                if let Some(sent) = &sent {
+                    self.record_confirmed(sent, found.ledger).await;
+                }
+                return Ok(SubmitTransactionResponse {
+                    hash: found.hash,
+                    ledger: found.ledger,
+                });
+            }
+            Some(found) => format!("Transaction {} failed in ledger {}", hash, found.ledger),
+            None => match &sent {
+                Some(sent) if Utc::now() > sent.created_at + PENDING_LIMIT => {
+                    format!("Transaction {} never made it into a ledger; it's safe to send the payment again", hash)
+                }
+                _ => return Err(still_pending("not in a ledger yet")),
+            },
+        };
+
+        if let (Some(db), Some(sent)) = (&self.db, &sent) {
+            db.update_transaction_status(&sent.account, hash, TransactionStatus::Failed, None, Some(&error)).await?;
+        }
+        Err(AppError::StellarError(error))
+    }
+
+    /// Marks `sent` confirmed in `ledger` and, for a payment to another
+    /// wallet address here, adds it to its owner's history right away,
+    /// without waiting for their next Horizon backfill. The copy keeps the
+    /// API request that sent it, which tells the event watcher the API has
+    /// already announced it. The payment has gone through by now,
+    /// so failing to record it is logged rather than returned.
+    async fn record_confirmed(&self, sent: &WalletTransaction, ledger: u32) {
+        let Some(db) = &self.db else {
+            return;
+        };
+        let confirmed = WalletTransaction {
+            status: TransactionStatus::Confirmed,
+            ledger: Some(ledger.into()),
+            error: None,
+            ..sent.clone()
+        };
+        let recorded = async {
+            db.update_transaction_status(&sent.account, &sent.hash, TransactionStatus::Confirmed, confirmed.ledger, None)
+                .await?;
+            if db.get_wallet_owner(&sent.counterparty).await?.is_some() {
+                db.upsert_transaction(&as_received(&confirmed)).await?;
+            }
+            Ok::<_, AppError>(())
+        };
+        if let Err(e) = recorded.await {
+            tracing::warn!(hash = %sent.hash, "Couldn't record a confirmed payment: {}", e);
+        }
+    }
+}
+
+/// `sent` as recorded in the recipient's history. The sender's request is
+/// none of the recipient's business.
+fn as_received(sent: &WalletTransaction) -> WalletTransaction {
+    WalletTransaction {
+        id: Uuid::new_v4(),
+        account: sent.counterparty.clone(),
+        direction: TransactionDirection::Incoming,
+        counterparty: sent.account.clone(),
+        updated_at: Utc::now(),
+        ..sent.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar::keypair::Keypair;
+    use axum::extract::Path;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::net::TcpListener;
+
+    const SOURCE: &str = "GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7";
+    const DESTINATION: &str = "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H";
+
+    #[test]
+    fn received_copy_swaps_account_and_counterparty() {
+        let now = Utc::now();
+        let sent = WalletTransaction {
+            id: Uuid::new_v4(),
+            account: SOURCE.to_string(),
+            hash: "aa".to_string(),
+            operation_index: 1,
+            direction: TransactionDirection::Outgoing,
+            asset_code: "XLM".to_string(),
+            amount_stroops: 10,
+            counterparty: DESTINATION.to_string(),
+            memo: Some("rent".to_string()),
+            status: TransactionStatus::Confirmed,
+            ledger: Some(7),
+            error: None,
+            request_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let received = as_received(&sent);
+        assert_eq!((received.account.as_str(), received.counterparty.as_str()), (DESTINATION, SOURCE));
+        assert_eq!(received.direction, TransactionDirection::Incoming);
+        assert_eq!((received.hash, received.amount_stroops, received.ledger), (sent.hash, 10, Some(7)));
+        assert_ne!(received.id, sent.id);
+    }
+
+    /// A payment from SOURCE to DESTINATION recorded `ago` with `status`.
+    fn sent(amount_stroops: i64, memo: Option<&str>, status: TransactionStatus, ago: chrono::Duration) -> WalletTransaction {
+        let at = Utc::now() - ago;
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: SOURCE.to_string(),
+            hash: hex::encode(Uuid::new_v4().as_bytes()),
+            operation_index: 1,
+            direction: TransactionDirection::Outgoing,
+            asset_code: "XLM".to_string(),
+            amount_stroops,
+            counterparty: DESTINATION.to_string(),
+            memo: memo.map(str::to_string),
+            status,
+            ledger: None,
+            error: None,
+            request_id: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_payments_are_found_in_the_database_even_while_pending() {
+        let db = SqliteDatabase::in_memory().await;
+        let service = TransactionService::new(Network::testnet()).with_db(db.clone());
+        assert_eq!(service.recent_duplicate(SOURCE, DESTINATION, "10", Some("rent")).await.unwrap(), None);
+
+        db.upsert_transaction(&sent(100_000_000, Some("rent"), TransactionStatus::Pending, chrono::Duration::seconds(30))).await.unwrap();
+        let ago = service.recent_duplicate(SOURCE, DESTINATION, "10.0000000", Some("rent")).await.unwrap().unwrap();
+        assert!((Duration::from_secs(29)..Duration::from_secs(40)).contains(&ago), "{:?}", ago);
+
+        // A separate service, like the one each CLI command builds, sees it too.
+        let other = TransactionService::new(Network::testnet()).with_db(db);
+        assert!(other.recent_duplicate(SOURCE, DESTINATION, "10", Some("rent")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn different_failed_or_old_payments_are_not_duplicates() {
+        let db = SqliteDatabase::in_memory().await;
+        let service = TransactionService::new(Network::testnet()).with_db(db.clone());
+        let window = chrono::Duration::from_std(DUPLICATE_WINDOW).unwrap();
+        db.upsert_transaction(&sent(100_000_000, Some("rent"), TransactionStatus::Confirmed, chrono::Duration::zero())).await.unwrap();
+        db.upsert_transaction(&sent(50_000_000, None, TransactionStatus::Failed, chrono::Duration::zero())).await.unwrap();
+        db.upsert_transaction(&sent(70_000_000, None, TransactionStatus::Confirmed, window + chrono::Duration::seconds(1))).await.unwrap();
+
+        assert_eq!(service.recent_duplicate(SOURCE, DESTINATION, "10.5", Some("rent")).await.unwrap(), None);
+        assert_eq!(service.recent_duplicate(SOURCE, DESTINATION, "10", None).await.unwrap(), None);
+        assert_eq!(service.recent_duplicate(SOURCE, SOURCE, "10", Some("rent")).await.unwrap(), None);
+        assert_eq!(service.recent_duplicate(SOURCE, DESTINATION, "5", None).await.unwrap(), None);
+        assert_eq!(service.recent_duplicate(SOURCE, DESTINATION, "7", None).await.unwrap(), None);
+    }
+
+    /// A Horizon that times out every submission, and has the transaction
+    /// in ledger 77 once `landed` is set.
+    async fn timing_out_horizon(landed: Arc<AtomicBool>) -> Network {
+        let app = Router::new()
+            .route("/accounts/{id}", get(|| async { Json(json!({ "sequence": "100", "balances": [] })) }))
+            .route("/transactions", post(|| async { StatusCode::GATEWAY_TIMEOUT }))
+            .route(
+                "/transactions/{hash}",
+                get(move |Path(hash): Path<String>| async move {
+                    if landed.load(Ordering::SeqCst) {
+                        Ok(Json(json!({ "hash": hash, "ledger": 77, "successful": true })))
+                    } else {
+                        Err(StatusCode::NOT_FOUND)
+                    }
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let horizon_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Network {
+            horizon_url,
+            ..Network::testnet()
+        }
+    }
+
+    #[tokio::test]
+    async fn timed_out_payments_stay_pending_until_confirmed() {
+        let landed = Arc::new(AtomicBool::new(false));
+        let db = SqliteDatabase::in_memory().await;
+        let service = TransactionService::new(timing_out_horizon(landed.clone()).await).with_db(db.clone());
+        let source = Keypair::random();
+
+        let Err(AppError::UnconfirmedError { hash, .. }) = service.send_payment(&source, DESTINATION, "5", None).await else {
+            panic!("expected an unconfirmed payment");
+        };
+        let status = || async { db.get_outgoing_transaction(&hash).await.unwrap().unwrap().status };
+        assert_eq!(status().await, TransactionStatus::Pending);
+
+        assert!(matches!(service.confirm(&hash).await, Err(AppError::UnconfirmedError { .. })));
+        assert_eq!(status().await, TransactionStatus::Pending);
+
+        assert!(service.received(&hash, DESTINATION).await.unwrap().is_none());
+        let recipient = db.insert_test_user().await;
+        db.update_user_stellar_public_key(&recipient, DESTINATION).await.unwrap();
+
+        landed.store(true, Ordering::SeqCst);
+        let confirmed = service.confirm(&hash).await.unwrap();
+        assert_eq!((confirmed.hash.as_str(), confirmed.ledger), (hash.as_str(), 77));
+        assert_eq!(status().await, TransactionStatus::Confirmed);
+
+        let (owner, received) = service.received(&hash, DESTINATION).await.unwrap().unwrap();
+        assert_eq!(owner, recipient);
+        assert_eq!((received.direction, received.counterparty, received.ledger), (TransactionDirection::Incoming, source.public_key(), Some(77)));
+    }
+}
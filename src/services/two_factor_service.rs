@@ -0,0 +1,260 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::two_factor::{TotpEnrollment, TotpSecret, RECOVERY_CODE_COUNT};
+use crate::services::audit_service::AuditService;
+use crate::utils::crypto::{EnvelopeCipher, KeyRing, RecoveryCode};
+use chrono::{DateTime, Utc};
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, TOTP};
+use uuid::Uuid;
+
+/// Seconds per TOTP code, the RFC 6238 default every authenticator app uses.
+const STEP_SECS: i64 = 30;
+
+/// Codes from one step either side are accepted, for clock drift.
+const SKEW_STEPS: i64 = 1;
+
+/// How a second-factor check went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    /// A current code from the authenticator app.
+    Code,
+    /// A recovery code, now used up.
+    RecoveryCode { remaining: i64 },
+    Rejected,
+}
+
+/// TOTP second factor with one-time recovery codes. Secrets are sealed under
+/// `CUSTOMER_DATA_KEYS`, so 2FA is only offered when those are configured.
+pub struct TwoFactorService {
+    db: SqliteDatabase,
+    audit_service: AuditService,
+    keys: std::result::Result<Option<KeyRing>, AppError>,
+}
+
+impl TwoFactorService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            audit_service: AuditService::new(db.clone()),
+            db,
+            keys: KeyRing::from_env(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_keys(db: SqliteDatabase, keys: KeyRing) -> Self {
+        Self {
+            keys: Ok(Some(keys)),
+            ..Self::new(db)
+        }
+    }
+
+    fn keys(&self) -> Result<&KeyRing> {
+        match &self.keys {
+            Ok(Some(keys)) => Ok(keys),
+            Ok(None) => Err(AppError::ValidationError(
+                "Two-factor authentication is unavailable; set CUSTOMER_DATA_KEYS to enable it".to_string(),
+            )),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.keys().is_ok()
+    }
+
+    pub async fn is_enabled(&self, user_id: &Uuid) -> Result<bool> {
+        Ok(self.db.get_totp_secret(user_id).await?.is_some_and(|secret| secret.is_enabled()))
+    }
+
+    /// Generates a new secret for the user to add to their app. 2FA isn't on
+    /// until [`TwoFactorService::confirm_enrollment`] sees a code from it.
+    pub async fn begin_enrollment(&self, user_id: &Uuid, account_name: &str, issuer: &str) -> Result<TotpEnrollment> {
+        if self.is_enabled(user_id).await? {
+            return Err(AppError::ValidationError("Two-factor authentication is already on".to_string()));
+        }
+
+        let mut secret = vec![0u8; 20];
+        OsRng.fill_bytes(&mut secret);
+        let envelope = EnvelopeCipher::seal(&secret, &context(user_id), self.keys()?)?;
+        self.db
+            .upsert_totp_secret(&TotpSecret {
+                user_id: *user_id,
+                envelope,
+                confirmed_at: None,
+                last_used_step: None,
+                created_at: Utc::now(),
+            })
+            .await?;
+
+        let totp = totp(secret, Some(issuer.replace(':', "")), account_name.replace(':', ""))?;
+        Ok(TotpEnrollment {
+            secret_base32: totp.get_secret_base32(),
+            otpauth_url: totp.get_url(),
+        })
+    }
+
+    /// Turns 2FA on once `code` matches the pending secret, returning the
+    /// recovery codes. They are only ever shown this once.
+    pub async fn confirm_enrollment(&self, user_id: &Uuid, code: &str, now: DateTime<Utc>) -> Result<Option<Vec<String>>> {
+        let Some(secret) = self.db.get_totp_secret(user_id).await? else {
+            return Err(AppError::ValidationError("Start two-factor setup first".to_string()));
+        };
+        if secret.is_enabled() {
+            return Err(AppError::ValidationError("Two-factor authentication is already on".to_string()));
+        }
+        if !self.check_code(&secret, code, now).await? {
+            return Ok(None);
+        }
+
+        self.db.confirm_totp_secret(user_id, now).await?;
+        self.audit_service
+            .record(Some(user_id), AuditEvent::ProfileUpdated, "two-factor authentication turned on")
+            .await?;
+        self.regenerate_recovery_codes(user_id).await.map(Some)
+    }
+
+    /// Checks a code from the app, or a recovery code, for a user with 2FA on.
+    pub async fn verify(&self, user_id: &Uuid, code: &str, now: DateTime<Utc>) -> Result<SecondFactor> {
+        let Some(secret) = self.db.get_totp_secret(user_id).await?.filter(|secret| secret.is_enabled()) else {
+            return Err(AppError::ValidationError("Two-factor authentication is off".to_string()));
+        };
+
+        let code = code.trim();
+        if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+            let accepted = self.check_code(&secret, code, now).await?;
+            return Ok(if accepted { SecondFactor::Code } else { SecondFactor::Rejected });
+        }
+
+        if !self.db.use_recovery_code(user_id, &hash_recovery_code(code)).await? {
+            return Ok(SecondFactor::Rejected);
+        }
+        let remaining = self.db.count_recovery_codes(user_id).await?;
+        self.audit_service
+            .record(Some(user_id), AuditEvent::ProfileUpdated, &format!("recovery code used, {} left", remaining))
+            .await?;
+        Ok(SecondFactor::RecoveryCode { remaining })
+    }
+
+    /// Replaces the user's recovery codes with a new set and returns them.
+    pub async fn regenerate_recovery_codes(&self, user_id: &Uuid) -> Result<Vec<String>> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| RecoveryCode::generate(2)).collect();
+        let hashes: Vec<String> = codes.iter().map(|code| hash_recovery_code(code)).collect();
+
+        self.db.replace_recovery_codes(user_id, &hashes, Utc::now()).await?;
+        Ok(codes)
+    }
+
+    pub async fn remaining_recovery_codes(&self, user_id: &Uuid) -> Result<i64> {
+        self.db.count_recovery_codes(user_id).await
+    }
+
+    pub async fn disable(&self, user_id: &Uuid) -> Result<()> {
+        if !self.db.delete_two_factor(user_id).await? {
+            return Err(AppError::ValidationError("Two-factor authentication is off".to_string()));
+        }
+        self.audit_service
+            .record(Some(user_id), AuditEvent::ProfileUpdated, "two-factor authentication turned off")
+            .await
+    }
+
+    /// Rewraps TOTP secrets under the active key in `CUSTOMER_DATA_KEYS`.
+    pub async fn rotate_keys(&self) -> Result<usize> {
+        let keys = self.keys()?;
+        let stale = self.db.get_totp_secrets_not_under_key(keys.active_id()).await?;
+
+        for secret in &stale {
+            self.db
+                .upsert_totp_secret(&TotpSecret {
+                    envelope: EnvelopeCipher::rewrap(&secret.envelope, keys)?,
+                    ..secret.clone()
+                })
+                .await?;
+        }
+
+        Ok(stale.len())
+    }
+
+    /// Whether `code` is the app's code for a step near `now` that hasn't been
+    /// used yet. An accepted step is recorded so the same code can't be replayed.
+    async fn check_code(&self, secret: &TotpSecret, code: &str, now: DateTime<Utc>) -> Result<bool> {
+        let key = EnvelopeCipher::open(&secret.envelope, &context(&secret.user_id), self.keys()?)?;
+        let totp = totp(key.to_vec(), None, String::new())?;
+        let current = now.timestamp() / STEP_SECS;
+
+        for step in current - SKEW_STEPS..=current + SKEW_STEPS {
+            if step <= secret.last_used_step.unwrap_or(i64::MIN) {
+                continue;
+            }
+            if totp.check(code.trim(), (step * STEP_SECS) as u64) {
+                return self.db.record_totp_step(&secret.user_id, step).await;
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn totp(secret: Vec<u8>, issuer: Option<String>, account_name: String) -> Result<TOTP> {
+    TOTP::new(Algorithm::SHA1, 6, 0, STEP_SECS as u64, secret, issuer, account_name)
+        .map_err(|e| AppError::InternalError(format!("Invalid TOTP parameters: {}", e)))
+}
+
+/// Binds each sealed secret to its owner, so rows can't be swapped.
+fn context(user_id: &Uuid) -> Vec<u8> {
+    format!("{}/totp", user_id).into_bytes()
+}
+
+/// Hashes a recovery code, ignoring case, spaces and dashes.
+fn hash_recovery_code(code: &str) -> String {
+    hex::encode(Sha256::digest(RecoveryCode::normalize(code).as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use totp_rs::Secret;
+
+    const KEY: &str = "v1:0101010101010101010101010101010101010101010101010101010101010101";
+
+    fn code_at(enrollment: &TotpEnrollment, at: DateTime<Utc>) -> String {
+        let secret = Secret::Encoded(enrollment.secret_base32.clone()).to_bytes().unwrap();
+        totp(secret, None, String::new()).unwrap().generate(at.timestamp() as u64)
+    }
+
+    #[tokio::test]
+    async fn codes_work_once_and_recovery_codes_are_single_use() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = TwoFactorService::with_keys(db, KeyRing::parse(KEY).unwrap());
+        let now = Utc::now();
+
+        let enrollment = service.begin_enrollment(&user, "alice", "Wallet").await.unwrap();
+        assert!(enrollment.otpauth_url.starts_with("otpauth://totp/Wallet:alice?"));
+        assert!(!service.is_enabled(&user).await.unwrap());
+        let stale = code_at(&enrollment, now - Duration::minutes(10));
+        assert!(service.confirm_enrollment(&user, &stale, now).await.unwrap().is_none());
+
+        let codes = service.confirm_enrollment(&user, &code_at(&enrollment, now), now).await.unwrap().unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(service.is_enabled(&user).await.unwrap());
+
+        // The enrollment code can't be replayed, but the next step's can be used.
+        assert_eq!(service.verify(&user, &code_at(&enrollment, now), now).await.unwrap(), SecondFactor::Rejected);
+        let later = now + Duration::seconds(STEP_SECS);
+        assert_eq!(service.verify(&user, &code_at(&enrollment, later), later).await.unwrap(), SecondFactor::Code);
+
+        let recovery = codes[0].to_uppercase();
+        assert_eq!(
+            service.verify(&user, &recovery, later).await.unwrap(),
+            SecondFactor::RecoveryCode { remaining: RECOVERY_CODE_COUNT as i64 - 1 }
+        );
+        assert_eq!(service.verify(&user, &recovery, later).await.unwrap(), SecondFactor::Rejected);
+
+        service.disable(&user).await.unwrap();
+        assert!(!service.is_enabled(&user).await.unwrap());
+        assert_eq!(service.remaining_recovery_codes(&user).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::cli::theme::ThemeName;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::undo::{LocalAction, UndoableSetting};
+use crate::services::settings_service::SettingsService;
+use std::cell::RefCell;
+
+/// Oldest actions are forgotten past this many.
+const MAX_UNDO_DEPTH: usize = 50;
+
+/// Undo and redo stacks for the local changes made during one login.
+pub struct UndoService {
+    db: SqliteDatabase,
+    settings_service: SettingsService,
+    undo: RefCell<Vec<LocalAction>>,
+    redo: RefCell<Vec<LocalAction>>,
+}
+
+impl UndoService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self {
+            settings_service: SettingsService::new(db.clone()),
+            db,
+            undo: RefCell::new(Vec::new()),
+            redo: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Remembers an action the user just took. A new action ends the redo
+    /// history, as in an editor.
+    pub fn record(&self, action: LocalAction) {
+        let mut undo = self.undo.borrow_mut();
+        undo.push(action);
+        if undo.len() > MAX_UNDO_DEPTH {
+            undo.remove(0);
+        }
+        self.redo.borrow_mut().clear();
+    }
+
+    /// What [`UndoService::undo`] would reverse.
+    pub fn next_undo(&self) -> Option<String> {
+        self.undo.borrow().last().map(LocalAction::describe)
+    }
+
+    /// What [`UndoService::redo`] would repeat.
+    pub fn next_redo(&self) -> Option<String> {
+        self.redo.borrow().last().map(LocalAction::describe)
+    }
+
+    /// Reverses the latest action, returning its description, or `None` if
+    /// there is nothing to undo. A failed undo stays on the stack.
+    pub async fn undo(&self) -> Result<Option<String>> {
+        let Some(action) = self.undo.borrow_mut().pop() else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self.apply(&action.inverse()).await {
+            self.undo.borrow_mut().push(action);
+            return Err(e);
+        }
+
+        let description = action.describe();
+        self.redo.borrow_mut().push(action);
+        Ok(Some(description))
+    }
+
+    /// Repeats the latest undone action, returning its description.
+    pub async fn redo(&self) -> Result<Option<String>> {
+        let Some(action) = self.redo.borrow_mut().pop() else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self.apply(&action).await {
+            self.redo.borrow_mut().push(action);
+            return Err(e);
+        }
+
+        let description = action.describe();
+        self.undo.borrow_mut().push(action);
+        Ok(Some(description))
+    }
+
+    async fn apply(&self, action: &LocalAction) -> Result<()> {
+        match action {
+            LocalAction::ContactAdded(contact) => self.db.create_contact(contact).await,
+            LocalAction::ContactDeleted(contact) => {
+                if !self.db.delete_contact(&contact.user_id, &contact.id).await? {
+                    return Err(AppError::ValidationError(format!("Contact '{}' no longer exists", contact.name)));
+                }
+                Ok(())
+            }
+            LocalAction::TagAdded { account, hash, tag } => self.db.add_transaction_tag(account, hash, tag).await,
+            LocalAction::TagRemoved { account, hash, tag } => {
+                self.db.remove_transaction_tag(account, hash, tag).await?;
+                Ok(())
+            }
+            LocalAction::SettingChanged { user_id, setting, after, .. } => {
+                match setting {
+                    UndoableSetting::FiatCurrency => self.settings_service.set_fiat_currency(user_id, after).await?,
+                    UndoableSetting::Theme => self.settings_service.set_theme(user_id, ThemeName::parse(after)?).await?,
+                };
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::contact::CreateContactRequest;
+    use crate::services::contact_service::ContactService;
+
+    #[tokio::test]
+    async fn undoes_and_redoes_local_changes_in_order() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let undo = UndoService::new(db.clone());
+        let settings = SettingsService::new(db.clone());
+
+        let contact = ContactService::new(db.clone())
+            .add_contact(
+                &user,
+                CreateContactRequest {
+                    name: "Bob".to_string(),
+                    address: stellar_strkey::ed25519::PublicKey([1; 32]).to_string(),
+                    memo: None,
+                    federation_name: None,
+                },
+            )
+            .await
+            .unwrap();
+        undo.record(LocalAction::ContactAdded(contact));
+
+        settings.set_fiat_currency(&user, "eur").await.unwrap();
+        undo.record(LocalAction::SettingChanged {
+            user_id: user,
+            setting: UndoableSetting::FiatCurrency,
+            before: "usd".to_string(),
+            after: "eur".to_string(),
+        });
+
+        assert_eq!(undo.undo().await.unwrap().unwrap(), "change display currency from usd to eur");
+        assert_eq!(settings.settings(&user).await.unwrap().fiat_currency, "usd");
+        assert_eq!(undo.undo().await.unwrap().unwrap(), "add contact 'Bob'");
+        assert!(db.get_contacts_by_user(&user).await.unwrap().is_empty());
+        assert!(undo.undo().await.unwrap().is_none());
+
+        assert_eq!(undo.redo().await.unwrap().unwrap(), "add contact 'Bob'");
+        assert_eq!(db.get_contacts_by_user(&user).await.unwrap().len(), 1);
+
+        // A new action drops what was left to redo.
+        undo.record(LocalAction::TagAdded {
+            account: "GME".to_string(),
+            hash: "aa".to_string(),
+            tag: "rent".to_string(),
+        });
+        assert!(undo.next_redo().is_none());
+    }
+}
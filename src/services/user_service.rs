@@ -1,81 +1,428 @@
+use crate::cli::output;
 use crate::database::sqlite::SqliteDatabase;
+use crate::database::user_repository::UserRepository;
+use crate::services::account_cache_service::AccountCacheService;
+use crate::services::hook_service::{self, HookPoint, HookService};
 use crate::errors::{AppError, Result};
+use crate::models::role::Role;
 use crate::models::user::{CreateUserRequest, User, UserResponse};
 use crate::utils::crypto::PasswordManager;
 use crate::utils::validation::Validator;
 use chrono::Utc;
+use secrecy::ExposeSecret;
+use std::sync::Arc;
 use uuid::Uuid;
-use std::env;
 
 pub struct UserService {
-    db: SqliteDatabase,
+    users: Arc<dyn UserRepository>,
+    hooks: Option<Arc<HookService>>,
 }
 
 impl UserService {
-    pub async fn new() -> Result<Self> {
-        // Get current directory and create database path
-        let current_dir = env::current_dir()
-            .map_err(|e| AppError::DatabaseError(format!("Failed to get current directory: {}", e)))?;
-        
-        let db_path = current_dir.join("stellar_wallet.db");
-        let db_path_str = db_path.to_string_lossy();
-        
-        println!("📂 Database path: {}", db_path_str);
-        
-        let db = SqliteDatabase::new(&db_path_str).await?;
-        Ok(Self { db })
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self::with_repository(Arc::new(db))
+    }
+
+    pub fn with_repository(users: Arc<dyn UserRepository>) -> Self {
+        Self { users, hooks: None }
+    }
+
+    /// Serves profiles through `cache`; see [`AccountCacheService::users`].
+    pub fn with_cache(mut self, cache: &AccountCacheService) -> Self {
+        self.users = cache.users(self.users);
+        self
+    }
+
+    /// Runs the operator's `after_signup` hook on each new signup.
+    pub fn with_hooks(mut self, hooks: Arc<HookService>) -> Self {
+        self.hooks = Some(hooks);
+        self
     }
 
     pub async fn create_user(&self, request: CreateUserRequest) -> Result<UserResponse> {
+        let hooks = self.hooks.clone();
+        let user = hashing(move || Self::new_user(hooks.as_deref(), request)).await??;
+
+        // Save to database. Saying which of the two is taken would let anyone
+        // check whether an email has an account here.
+        self.users.create_user(&user).await.map_err(|e| match e {
+            AppError::ValidationError(_) => {
+                AppError::ValidationError("That email or username can't be used; please choose another".to_string())
+            }
+            e => e,
+        })?;
+
+        Ok(user.into())
+    }
+
+    /// Signs up every request in `requests` at once, e.g. to bring over a
+    /// partner's existing users, with a result for each in the same order.
+    /// The valid ones are saved in one transaction. Only staff provision
+    /// accounts, so errors say which field is taken.
+    pub async fn create_users(&self, requests: Vec<CreateUserRequest>) -> Result<Vec<Result<UserResponse>>> {
+        let hooks = self.hooks.clone();
+        let users: Vec<Result<User>> =
+            hashing(move || requests.into_iter().map(|request| Self::new_user(hooks.as_deref(), request)).collect()).await?;
+        let valid: Vec<User> = users.iter().filter_map(|user| user.as_ref().ok()).cloned().collect();
+        let mut saved = self.users.create_users(&valid).await?.into_iter();
+
+        Ok(users
+            .into_iter()
+            .map(|user| {
+                let user = user?;
+                saved.next().expect("a result for each saved user")?;
+                Ok(user.into())
+            })
+            .collect())
+    }
+
+    /// Checks a signup and builds the user it creates, without saving it.
+    /// Hashes the password, so it runs on the blocking pool.
+    fn new_user(hooks: Option<&HookService>, request: CreateUserRequest) -> Result<User> {
         // Validate input
         Validator::validate_email(&request.email)?;
         Validator::validate_username(&request.username)?;
-        Validator::validate_password(&request.password)?;
+        Validator::validate_password(request.password.expose_secret())?;
+
+        if let Some(hooks) = hooks {
+            let event = hook_service::event(&[
+                ("email", request.email.clone().into()),
+                ("username", request.username.clone().into()),
+            ]);
+
+            for note in hooks.check(HookPoint::AfterSignup, event)? {
+                output::status(&format!("📎 {}", note));
+            }
+        }
 
         // Hash password
-        let password_hash = PasswordManager::hash_password(&request.password)?;
+        let password_hash = PasswordManager::hash_password(request.password.expose_secret())?;
 
         // Create user
         let user_id = Uuid::new_v4();
         let now = Utc::now();
         
-        let user = User {
+        Ok(User {
             id: user_id,
             email: request.email.clone(),
             username: request.username.clone(),
             password_hash,
             is_verified: false,
             stellar_public_key: None,
+            last_login_at: None,
+            login_count: 0,
+            role: Role::User,
+            disabled_at: None,
             created_at: now,
             updated_at: now,
+        })
+    }
+
+    pub async fn authenticate_user(&self, email_or_username: &str, password: &str) -> Result<UserResponse> {
+        let user = self.lookup(email_or_username).await?;
+
+        // The hash is checked even for unknown users, so the time taken doesn't
+        // tell whether the email or username exists.
+        let matches = match &user {
+            Some(user) => PasswordManager::verify_password(password, &user.password_hash)?,
+            None => PasswordManager::verify_dummy(password)?,
         };
+        let Some(user) = user.filter(|_| matches) else {
+            return Err(AppError::AuthenticationError("Invalid email/username or password".to_string()));
+        };
+        if user.disabled_at.is_some() {
+            return Err(AppError::AuthenticationError("This account has been disabled".to_string()));
+        }
+        // Only now is the password known, so this is when old hashes can be upgraded.
+        if PasswordManager::needs_rehash(&user.password_hash)? {
+            let password_hash = PasswordManager::hash_password(password)?;
+            self.users.update_user_password_hash(&user.id, &password_hash).await?;
+        }
 
-        // Save to database
-        self.db.create_user(&user).await?;
+        self.users.record_user_login(&user.id, Utc::now()).await?;
 
-        Ok(user.into())
+        output::status(&format!("✅ Authentication successful for user: {}", user.username));
+        // Keep the previous last_login_at so the caller can show it.
+        Ok(UserResponse {
+            login_count: user.login_count + 1,
+            ..user.into()
+        })
     }
 
-    pub async fn authenticate_user(&self, email_or_username: &str, password: &str) -> Result<UserResponse> {
+    /// Logs in a user someone else vouched for, such as an OAuth provider or
+    /// a passkey: checks the account isn't disabled and counts the login, like
+    /// [`UserService::authenticate_user`] does after the password.
+    pub async fn authenticate_linked_user(&self, user_id: &Uuid) -> Result<UserResponse> {
+        let user = self
+            .users
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::AuthenticationError("The linked account no longer exists".to_string()))?;
+        if user.disabled_at.is_some() {
+            return Err(AppError::AuthenticationError("This account has been disabled".to_string()));
+        }
+
+        self.users.record_user_login(&user.id, Utc::now()).await?;
+        Ok(UserResponse {
+            login_count: user.login_count + 1,
+            ..user.into()
+        })
+    }
+
+    pub async fn get_user(&self, user_id: &Uuid) -> Result<Option<UserResponse>> {
+        Ok(self.users.get_user_by_id(user_id).await?.map(Into::into))
+    }
+
+    /// Like [`UserService::get_user`], but possibly a few seconds old when
+    /// profiles are cached. Only for showing the user.
+    pub async fn get_profile(&self, user_id: &Uuid) -> Result<Option<UserResponse>> {
+        Ok(self.users.get_user_profile(user_id).await?.map(Into::into))
+    }
+
+    /// Finds a user by email or username without checking any password.
+    pub async fn find_user(&self, email_or_username: &str) -> Result<Option<UserResponse>> {
+        Ok(self.lookup(email_or_username).await?.map(Into::into))
+    }
+
+    async fn lookup(&self, email_or_username: &str) -> Result<Option<User>> {
         // Try to find user by email first, then by username
-        let user = if let Some(user) = self.db.get_user_by_email(email_or_username).await? {
-            user
-        } else if let Some(user) = self.db.get_user_by_username(email_or_username).await? {
-            user
-        } else {
-            return Err(AppError::AuthenticationError("Invalid email/username or password".to_string()));
+        if let Some(user) = self.users.get_user_by_email(email_or_username).await? {
+            return Ok(Some(user));
+        }
+        self.users.get_user_by_username(email_or_username).await
+    }
+
+    /// The user whose linked address is `public_key`, e.g. to match an
+    /// incoming payment to an account.
+    pub async fn find_user_by_stellar_public_key(&self, public_key: &str) -> Result<Option<UserResponse>> {
+        Ok(self.users.get_user_by_stellar_public_key(public_key).await?.map(Into::into))
+    }
+
+    pub async fn link_stellar_public_key(&self, user_id: &Uuid, public_key: &str) -> Result<()> {
+        self.users.update_user_stellar_public_key(user_id, public_key).await
+    }
+
+    pub async fn change_username(&self, user_id: &Uuid, new_username: &str) -> Result<UserResponse> {
+        let new_username = new_username.trim();
+        Validator::validate_username(new_username)?;
+        let user = self.user_by_id(user_id).await?;
+
+        self.update_user(User {
+            username: new_username.to_string(),
+            ..user
+        })
+        .await
+    }
+
+    /// Deletes the account and everything stored for it, after checking the password.
+    pub async fn delete_user(&self, user_id: &Uuid, password: &str) -> Result<()> {
+        self.verified_user(user_id, password).await?;
+        self.users.delete_user(user_id).await?;
+        Ok(())
+    }
+
+    /// Every user, by username.
+    pub async fn list_users(&self) -> Result<Vec<UserResponse>> {
+        Ok(self.users.get_users().await?.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn set_role(&self, user_id: &Uuid, role: Role) -> Result<UserResponse> {
+        self.user_by_id(user_id).await?;
+        self.users.update_user_role(user_id, role, Utc::now()).await?;
+        Ok(self.user_by_id(user_id).await?.into())
+    }
+
+    /// Disables or re-enables logging in to the account.
+    pub async fn set_disabled(&self, user_id: &Uuid, disabled: bool) -> Result<UserResponse> {
+        self.user_by_id(user_id).await?;
+        let now = Utc::now();
+        self.users.update_user_disabled(user_id, disabled.then_some(now), now).await?;
+        Ok(self.user_by_id(user_id).await?.into())
+    }
+
+    async fn update_user(&self, user: User) -> Result<UserResponse> {
+        let user = User {
+            updated_at: Utc::now(),
+            ..user
         };
+        self.users.update_user(&user).await?;
+        Ok(user.into())
+    }
+
+    /// Checks `password` against the user's own without logging them in.
+    pub async fn verify_password(&self, user_id: &Uuid, password: &str) -> Result<()> {
+        self.verified_user(user_id, password).await.map(|_| ())
+    }
 
-        // Verify password
+    async fn user_by_id(&self, user_id: &Uuid) -> Result<User> {
+        self.users
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::ValidationError("User not found".to_string()))
+    }
+
+    async fn verified_user(&self, user_id: &Uuid, password: &str) -> Result<User> {
+        let user = self.user_by_id(user_id).await?;
         if !PasswordManager::verify_password(password, &user.password_hash)? {
-            return Err(AppError::AuthenticationError("Invalid email/username or password".to_string()));
+            return Err(AppError::AuthenticationError("Incorrect password".to_string()));
         }
-
-        println!("✅ Authentication successful for user: {}", user.username);
-        Ok(user.into())
+        Ok(user)
     }
 
     pub async fn get_user_count(&self) -> Result<i64> {
-        self.db.get_user_count().await
+        self.users.get_user_count().await
+    }
+}
+
+/// Runs `work` on the blocking pool. Argon2 takes long enough per password
+/// that hashing a batch on an async worker would stall every other request
+/// sharing it.
+async fn hashing<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AppError::InternalError(format!("Password hashing task failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::user_repository::InMemoryUserRepository;
+
+    fn service() -> UserService {
+        UserService::with_repository(Arc::new(InMemoryUserRepository::default()))
+    }
+
+    fn request(email: &str, username: &str) -> CreateUserRequest {
+        CreateUserRequest {
+            email: email.to_string(),
+            username: username.to_string(),
+            password: "Velvet-Otter-92!".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn creates_and_authenticates_by_email_or_username() {
+        let service = service();
+        let created = service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        assert_eq!(service.authenticate_user("alice@example.com", "Velvet-Otter-92!").await.unwrap().id, created.id);
+        assert_eq!(service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap().id, created.id);
+        assert_eq!(service.get_user_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn tracks_previous_login_and_count() {
+        let service = service();
+        service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        let first = service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap();
+        assert_eq!((first.last_login_at, first.login_count), (None, 1));
+
+        let second = service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap();
+        assert!(second.last_login_at.is_some());
+        assert_eq!(second.login_count, 2);
+
+        service.authenticate_user("alice", "Wr0ngpass!").await.unwrap_err();
+        assert_eq!(service.find_user("alice").await.unwrap().unwrap().login_count, 2);
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_password_and_unknown_user_alike() {
+        let service = service();
+        service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        let wrong_password = service.authenticate_user("alice", "Wr0ngpass!").await.unwrap_err();
+        let unknown_user = service.authenticate_user("bob", "Velvet-Otter-92!").await.unwrap_err();
+        assert_eq!(wrong_password.to_string(), unknown_user.to_string());
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicate_usernames() {
+        let service = service();
+        service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        assert!(service.create_user(request("other@example.com", "alice")).await.is_err());
+    }
+
+    #[test]
+    fn signup_requests_keep_the_password_out_of_debug_output() {
+        let debug = format!("{:?}", request("alice@example.com", "alice"));
+        assert!(debug.contains("alice@example.com"));
+        assert!(!debug.contains("Velvet-Otter-92!"));
+    }
+
+    #[tokio::test]
+    async fn signup_conflicts_dont_say_which_field_is_taken() {
+        let service = service();
+        service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        let taken_email = service.create_user(request("alice@example.com", "alicia")).await.unwrap_err();
+        let taken_username = service.create_user(request("other@example.com", "alice")).await.unwrap_err();
+        assert_eq!(taken_email.to_string(), taken_username.to_string());
+    }
+
+    #[tokio::test]
+    async fn updates_profile_and_deletes_account() {
+        let service = service();
+        let alice = service.create_user(request("alice@example.com", "alice")).await.unwrap();
+        service.create_user(request("bob@example.com", "bob")).await.unwrap();
+
+        assert!(service.change_username(&alice.id, "bob").await.is_err());
+        assert_eq!(service.change_username(&alice.id, "alicia").await.unwrap().username, "alicia");
+        assert_eq!(service.authenticate_user("alice@example.com", "Velvet-Otter-92!").await.unwrap().username, "alicia");
+
+        assert!(service.delete_user(&alice.id, "Wr0ngpass!").await.is_err());
+        service.delete_user(&alice.id, "Velvet-Otter-92!").await.unwrap();
+        assert!(service.find_user("alicia").await.unwrap().is_none());
+        assert_eq!(service.get_user_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn upgrades_old_password_hashes_on_login() {
+        use argon2::password_hash::{PasswordHasher, SaltString};
+        use argon2::{Algorithm, Argon2, Params, Version};
+
+        let users = Arc::new(InMemoryUserRepository::default());
+        let service = UserService::with_repository(users.clone());
+        let alice = service.create_user(request("alice@example.com", "alice")).await.unwrap();
+        let legacy = Argon2::new(Algorithm::Argon2i, Version::V0x10, Params::default())
+            .hash_password(b"Velvet-Otter-92!", &SaltString::generate(&mut rand_core::OsRng))
+            .unwrap()
+            .to_string();
+        users.update_user_password_hash(&alice.id, &legacy).await.unwrap();
+
+        service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap();
+        let upgraded = users.get_user_by_id(&alice.id).await.unwrap().unwrap().password_hash;
+        assert!(upgraded.starts_with("$argon2id$v=19$"));
+        service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn disabled_accounts_cannot_log_in() {
+        let service = service();
+        let alice = service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        assert_eq!(service.set_role(&alice.id, Role::Support).await.unwrap().role, Role::Support);
+        assert!(service.set_disabled(&alice.id, true).await.unwrap().disabled_at.is_some());
+        let err = service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap_err();
+        assert!(err.to_string().contains("disabled"));
+
+        service.set_disabled(&alice.id, false).await.unwrap();
+        assert_eq!(service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap().role, Role::Support);
+    }
+
+    #[tokio::test]
+    async fn links_stellar_public_key() {
+        let service = service();
+        let user = service.create_user(request("alice@example.com", "alice")).await.unwrap();
+
+        service.link_stellar_public_key(&user.id, "GABC").await.unwrap();
+
+        let user = service.authenticate_user("alice", "Velvet-Otter-92!").await.unwrap();
+        assert_eq!(user.stellar_public_key.as_deref(), Some("GABC"));
+        assert_eq!(service.find_user_by_stellar_public_key("GABC").await.unwrap().unwrap().id, user.id);
+
+        let bob = service.create_user(request("bob@example.com", "bob")).await.unwrap();
+        assert!(service.link_stellar_public_key(&bob.id, "GABC").await.is_err());
     }
 }
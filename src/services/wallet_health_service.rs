@@ -0,0 +1,153 @@
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::Result;
+use crate::models::wallet_health::{WalletHealth, WalletStatus};
+use crate::stellar::amount::{self, format_stroops};
+use crate::stellar::horizon::{AccountRecord, HorizonClient, SubmitTransactionResponse};
+use crate::stellar::keypair::Keypair;
+use crate::stellar::network::Network;
+use crate::services::transaction_service::TransactionService;
+
+pub struct WalletHealthService {
+    db: SqliteDatabase,
+    horizon: HorizonClient,
+    transaction_service: TransactionService,
+}
+
+impl WalletHealthService {
+    pub fn new(db: SqliteDatabase, network: Network) -> Self {
+        Self {
+            db: db.clone(),
+            horizon: HorizonClient::new(&network.horizon_url),
+            transaction_service: TransactionService::new(network).with_db(db),
+        }
+    }
+
+    /// Checks every linked wallet, flagging those with less than
+    /// `threshold_stroops` of spendable XLM above their minimum balance.
+    pub async fn sweep(&self, threshold_stroops: i64) -> Result<Vec<WalletHealth>> {
+        let users = self.db.get_users_with_stellar_public_key().await?;
+        let mut report = Vec::with_capacity(users.len());
+
+        for user in users {
+            let Some(public_key) = user.stellar_public_key else {
+                continue;
+            };
+
+            let account = self.horizon.get_account(&public_key).await?;
+            report.push(assess_wallet(user.username, public_key, account.as_ref(), threshold_stroops)?);
+        }
+
+        Ok(report)
+    }
+
+    /// Sends `funding` enough XLM to lift a low-reserve wallet back to the threshold.
+    /// Returns `None` for wallets that don't need a top-up.
+    pub async fn top_up(&self, funding: &Keypair, wallet: &WalletHealth, threshold_stroops: i64) -> Result<Option<SubmitTransactionResponse>> {
+        let WalletStatus::LowReserve { headroom_stroops } = wallet.status else {
+            return Ok(None);
+        };
+
+        let amount = format_stroops(threshold_stroops - headroom_stroops);
+        let response = self
+            .transaction_service
+            .send_payment(funding, &wallet.public_key, &amount, Some("reserve top-up"))
+            .await?;
+
+        Ok(Some(response))
+    }
+}
+
+fn assess_wallet(username: String, public_key: String, account: Option<&AccountRecord>, threshold_stroops: i64) -> Result<WalletHealth> {
+    let Some(account) = account else {
+        return Ok(WalletHealth {
+            username,
+            public_key,
+            status: WalletStatus::Unfunded,
+            orphaned_trustlines: Vec::new(),
+        });
+    };
+
+    let balance = amount::parse_stroops(account.native_balance())?;
+    let headroom_stroops = balance - account.minimum_balance_stroops();
+
+    let status = if headroom_stroops < threshold_stroops {
+        WalletStatus::LowReserve { headroom_stroops }
+    } else {
+        WalletStatus::Healthy { headroom_stroops }
+    };
+
+    let orphaned_trustlines = account
+        .balances
+        .iter()
+        .filter(|balance| balance.asset_type != "native" && balance.asset_type != "liquidity_pool_shares")
+        .filter(|balance| amount::parse_stroops(&balance.balance).is_ok_and(|stroops| stroops == 0))
+        .map(|balance| match (&balance.asset_code, &balance.asset_issuer) {
+            (Some(code), Some(issuer)) => format!("{}:{}", code, issuer),
+            (Some(code), None) => code.clone(),
+            _ => balance.asset_type.clone(),
+        })
+        .collect();
+
+    Ok(WalletHealth {
+        username,
+        public_key,
+        status,
+        orphaned_trustlines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar::horizon::BalanceRecord;
+
+    fn balance(asset_code: Option<&str>, amount: &str) -> BalanceRecord {
+        BalanceRecord {
+            balance: amount.to_string(),
+            asset_type: if asset_code.is_some() { "credit_alphanum4" } else { "native" }.to_string(),
+            asset_code: asset_code.map(str::to_string),
+            asset_issuer: None,
+        }
+    }
+
+    fn account(balances: Vec<BalanceRecord>, subentry_count: u32) -> AccountRecord {
+        AccountRecord {
+            sequence: "1".to_string(),
+            balances,
+            subentry_count,
+            num_sponsoring: 0,
+            num_sponsored: 0,
+            home_domain: None,
+            signers: Vec::new(),
+        }
+    }
+
+    fn assess(account: Option<&AccountRecord>, threshold: &str) -> WalletHealth {
+        let threshold = amount::to_stroops(threshold).unwrap();
+        assess_wallet("alice".to_string(), "GABC".to_string(), account, threshold).unwrap()
+    }
+
+    #[test]
+    fn unfunded_wallets_are_reported() {
+        assert_eq!(assess(None, "1").status, WalletStatus::Unfunded);
+    }
+
+    #[test]
+    fn headroom_accounts_for_subentry_reserves() {
+        // Two subentries: minimum balance is (2 + 2) * 0.5 = 2 XLM.
+        let record = account(vec![balance(None, "5.0000000"), balance(Some("USDC"), "1.0000000")], 2);
+
+        assert_eq!(assess(Some(&record), "1").status, WalletStatus::Healthy { headroom_stroops: 30_000_000 });
+        assert_eq!(assess(Some(&record), "5").status, WalletStatus::LowReserve { headroom_stroops: 30_000_000 });
+    }
+
+    #[test]
+    fn zero_balance_trustlines_are_orphaned() {
+        let record = account(
+            vec![balance(None, "10.0000000"), balance(Some("USDC"), "0.0000000"), balance(Some("EURT"), "3.0000000")],
+            2,
+        );
+
+        assert_eq!(assess(Some(&record), "1").orphaned_trustlines, vec!["USDC".to_string()]);
+    }
+}
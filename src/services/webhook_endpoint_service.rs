@@ -0,0 +1,668 @@
+use crate::config;
+use crate::database::sqlite::SqliteDatabase;
+use crate::errors::{AppError, Result};
+use crate::models::audit::AuditEvent;
+use crate::models::role::Permission;
+use crate::models::wallet_event::{UserEvent, WalletEvent};
+use crate::models::webhook::{DeliveryStatus, WebhookDelivery, WebhookEndpoint, WebhookEventType};
+use crate::services::admin_service::AdminService;
+use crate::services::audit_service::AuditService;
+use crate::services::event_bus::EventBus;
+use crate::services::webhook_service::{event_body, post_event};
+use crate::utils::crypto::{EnvelopeCipher, KeyRing};
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use rand_core::{OsRng, RngCore};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Endpoints one account, or the operator, may register.
+pub const MAX_ENDPOINTS: i64 = 10;
+
+/// Attempts before a delivery is given up on.
+const MAX_ATTEMPTS: i64 = 8;
+
+/// The wait after the first failed attempt, doubling after each one after
+/// that: 30 seconds, a minute, two, ... just over an hour in all.
+const FIRST_RETRY_SECS: i64 = 30;
+
+/// How long an attempt may take before it counts as failed.
+const DELIVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How often due retries are looked for when no new event wakes the sender.
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Deliveries sent per pass.
+const BATCH_SIZE: u32 = 50;
+
+const MIN_SECRET_LEN: usize = 16;
+
+/// An endpoint to register.
+pub struct NewWebhook {
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    /// Generated when not given.
+    pub secret: Option<SecretString>,
+    /// Receive every account's events rather than the registering user's.
+    /// Only admins may.
+    pub all_accounts: bool,
+}
+
+/// Webhook endpoints that users register for their own account's events and
+/// admins for everyone's. Events are queued in the delivery log, signed like
+/// the operator's [`WebhookService`] events, and retried with exponential
+/// backoff until the endpoint answers with a 2xx. Secrets are sealed under
+/// `CUSTOMER_DATA_KEYS`, so webhooks are only offered when those are set.
+///
+/// Anyone can register an endpoint and read how its deliveries went, so
+/// deliveries only go to public addresses over HTTPS and don't follow
+/// redirects. Otherwise a webhook could reach the server's own network, such
+/// as a cloud metadata service. `WEBHOOKS_ALLOW_LOCAL=true` lifts this while
+/// developing a receiver on this machine.
+///
+/// [`WebhookService`]: crate::services::webhook_service::WebhookService
+pub struct WebhookEndpointService {
+    db: SqliteDatabase,
+    admin_service: AdminService,
+    audit_service: AuditService,
+    keys: std::result::Result<Option<KeyRing>, AppError>,
+    allow_local: bool,
+    http: reqwest::Client,
+}
+
+impl WebhookEndpointService {
+    pub fn new(db: SqliteDatabase) -> Self {
+        Self::with_reach(db, KeyRing::from_env(), config::var("WEBHOOKS_ALLOW_LOCAL").as_deref() == Some("true"))
+    }
+
+    fn with_reach(db: SqliteDatabase, keys: std::result::Result<Option<KeyRing>, AppError>, allow_local: bool) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(Policy::none());
+        let http = if allow_local { http } else { http.dns_resolver(Arc::new(PublicOnly)) };
+        Self {
+            admin_service: AdminService::new(db.clone()),
+            audit_service: AuditService::new(db.clone()),
+            db,
+            keys,
+            allow_local,
+            http: http.build().unwrap_or_default(),
+        }
+    }
+
+    /// For tests, whose receivers run on this machine.
+    #[cfg(test)]
+    fn with_keys(db: SqliteDatabase, keys: KeyRing) -> Self {
+        Self::with_reach(db, Ok(Some(keys)), true)
+    }
+
+    fn keys(&self) -> Result<&KeyRing> {
+        match &self.keys {
+            Ok(Some(keys)) => Ok(keys),
+            Ok(None) => Err(AppError::ValidationError(
+                "Webhooks are unavailable; set CUSTOMER_DATA_KEYS to enable them".to_string(),
+            )),
+            Err(e) => Err(e.clone()),
+        }
+    }
+
+    /// Registers an endpoint for `actor`'s events, or everyone's. Returns it
+    /// with its signing secret, which isn't shown again.
+    pub async fn register(&self, actor: &Uuid, webhook: NewWebhook) -> Result<(WebhookEndpoint, SecretString)> {
+        if webhook.all_accounts {
+            self.admin_service.authorize(actor, Permission::ManageWebhooks).await?;
+        }
+        let owner = (!webhook.all_accounts).then_some(*actor);
+
+        let url = validate_url(&webhook.url, self.allow_local)?;
+        let mut event_types = webhook.event_types;
+        event_types.sort_by_key(|event_type| event_type.as_str());
+        event_types.dedup();
+        if event_types.is_empty() {
+            return Err(AppError::ValidationError("Choose at least one event to receive".to_string()));
+        }
+        let secret = match webhook.secret {
+            Some(secret) if secret.expose_secret().len() < MIN_SECRET_LEN => {
+                return Err(AppError::ValidationError(format!("Webhook secrets must be at least {} characters", MIN_SECRET_LEN)))
+            }
+            Some(secret) => secret,
+            None => {
+                let mut bytes = [0u8; 32];
+                OsRng.fill_bytes(&mut bytes);
+                SecretString::from(format!("whsec_{}", hex::encode(bytes)))
+            }
+        };
+        if self.db.count_webhook_endpoints(owner.as_ref()).await? >= MAX_ENDPOINTS {
+            return Err(AppError::ValidationError(format!("At most {} webhooks can be registered; remove one first", MAX_ENDPOINTS)));
+        }
+
+        let id = Uuid::new_v4();
+        let endpoint = WebhookEndpoint {
+            id,
+            user_id: owner,
+            url,
+            event_types,
+            envelope: EnvelopeCipher::seal(secret.expose_secret().as_bytes(), &context(&id), self.keys()?)?,
+            created_by: *actor,
+            created_at: Utc::now(),
+        };
+        self.db.insert_webhook_endpoint(&endpoint).await?;
+
+        let scope = if owner.is_some() { "own account" } else { "every account" };
+        self.audit_service
+            .record(Some(actor), AuditEvent::WebhookAdded, &format!("{} for {} ({})", endpoint.url, scope, endpoint.id))
+            .await?;
+        Ok((endpoint, secret))
+    }
+
+    /// `actor`'s endpoints, or the ones for every account, which only admins see.
+    pub async fn list(&self, actor: &Uuid, all_accounts: bool) -> Result<Vec<WebhookEndpoint>> {
+        if all_accounts {
+            self.admin_service.authorize(actor, Permission::ManageWebhooks).await?;
+            return self.db.get_webhook_endpoints(None).await;
+        }
+        self.db.get_webhook_endpoints(Some(actor)).await
+    }
+
+    pub async fn remove(&self, actor: &Uuid, id: &Uuid) -> Result<()> {
+        let endpoint = self.endpoint(actor, id).await?;
+        self.db.delete_webhook_endpoint(&endpoint.id).await?;
+        self.audit_service
+            .record(Some(actor), AuditEvent::WebhookRemoved, &format!("{} ({})", endpoint.url, endpoint.id))
+            .await
+    }
+
+    /// The endpoint's newest `limit` deliveries.
+    pub async fn deliveries(&self, actor: &Uuid, id: &Uuid, limit: u32) -> Result<Vec<WebhookDelivery>> {
+        let endpoint = self.endpoint(actor, id).await?;
+        self.db.get_webhook_deliveries(&endpoint.id, limit).await
+    }
+
+    /// An endpoint `actor` may manage: one of theirs, or, for admins, one for
+    /// every account. Anyone else's looks like it doesn't exist.
+    async fn endpoint(&self, actor: &Uuid, id: &Uuid) -> Result<WebhookEndpoint> {
+        let not_found = || AppError::ValidationError(format!("No webhook {}", id));
+        let endpoint = self.db.get_webhook_endpoint(id).await?.ok_or_else(not_found)?;
+
+        match endpoint.user_id {
+            Some(owner) if owner == *actor => Ok(endpoint),
+            Some(_) => Err(not_found()),
+            None => {
+                self.admin_service.authorize(actor, Permission::ManageWebhooks).await?;
+                Ok(endpoint)
+            }
+        }
+    }
+
+    /// Rewraps every secret not under the active master key, so older master
+    /// keys can be removed. Returns how many were rewrapped.
+    pub async fn rotate_keys(&self) -> Result<usize> {
+        let keys = self.keys()?;
+        let stale = self.db.get_webhook_endpoints_not_under_key(keys.active_id()).await?;
+
+        for endpoint in &stale {
+            self.db
+                .update_webhook_endpoint_envelope(&endpoint.id, &EnvelopeCipher::rewrap(&endpoint.envelope, keys)?)
+                .await?;
+        }
+
+        Ok(stale.len())
+    }
+
+    /// Queues `event` for every endpoint that wants it, due at `now`. Returns
+    /// how many deliveries were queued; an event already queued isn't again.
+    pub async fn enqueue(&self, event: &UserEvent, now: DateTime<Utc>) -> Result<usize> {
+        let Some((event_type, event_id, data)) = webhook_event(event) else {
+            return Ok(0);
+        };
+        let payload = event_body(&event_id, event_type.as_str(), now, data);
+
+        let mut queued = 0;
+        for endpoint in self.db.get_webhook_endpoints_for_event(&event.user_id, event_type).await? {
+            let delivery = WebhookDelivery {
+                id: Uuid::new_v4(),
+                endpoint_id: endpoint.id,
+                user_id: event.user_id,
+                event_id: event_id.clone(),
+                event_type,
+                payload: payload.clone(),
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                response_status: None,
+                last_error: None,
+                request_id: request_id(&event.event),
+                next_attempt_at: Some(now),
+                created_at: now,
+                updated_at: now,
+            };
+            if self.db.insert_webhook_delivery(&delivery).await? {
+                queued += 1;
+            }
+        }
+        Ok(queued)
+    }
+
+    /// Attempts every delivery due by `now`, recording how each went. Returns
+    /// how many were attempted.
+    pub async fn deliver_due(&self, now: DateTime<Utc>) -> Result<usize> {
+        let lease = now + Duration::from_std(DELIVERY_TIMEOUT).unwrap_or_default() * 2;
+        let mut attempted = 0;
+
+        for mut delivery in self.db.get_due_webhook_deliveries(now, BATCH_SIZE).await? {
+            if !self.db.claim_webhook_delivery(&delivery, lease).await? {
+                continue;
+            }
+            let outcome = match self.db.get_webhook_endpoint(&delivery.endpoint_id).await? {
+                Some(endpoint) => self.attempt(&endpoint, &delivery).await,
+                None => continue,
+            };
+
+            delivery.attempts += 1;
+            delivery.updated_at = Utc::now();
+            match outcome {
+                Ok(status) if (200..300).contains(&status) => {
+                    delivery.status = DeliveryStatus::Delivered;
+                    delivery.response_status = Some(status);
+                    delivery.last_error = None;
+                    delivery.next_attempt_at = None;
+                }
+                failed => {
+                    (delivery.response_status, delivery.last_error) = match failed {
+                        Ok(status) => (Some(status), Some(format!("Endpoint answered HTTP {}", status))),
+                        Err(e) => (None, Some(e.message().to_string())),
+                    };
+                    if delivery.attempts >= MAX_ATTEMPTS {
+                        delivery.status = DeliveryStatus::Failed;
+                        delivery.next_attempt_at = None;
+                    } else {
+                        delivery.next_attempt_at = Some(now + retry_delay(delivery.attempts));
+                    }
+                }
+            }
+            self.db.update_webhook_delivery(&delivery).await?;
+            attempted += 1;
+        }
+
+        Ok(attempted)
+    }
+
+    async fn attempt(&self, endpoint: &WebhookEndpoint, delivery: &WebhookDelivery) -> Result<i64> {
+        // Checked again in case it was registered under laxer rules; names
+        // are checked as they resolve.
+        validate_url(&endpoint.url, self.allow_local)?;
+        let secret = EnvelopeCipher::open(&endpoint.envelope, &context(&endpoint.id), self.keys()?)?;
+        let status = post_event(
+            &self.http,
+            &endpoint.url,
+            &secret,
+            &delivery.event_id,
+            delivery.request_id.as_deref(),
+            delivery.payload.clone(),
+        )
+        .await?;
+        Ok(status.as_u16().into())
+    }
+
+    /// Runs until the process exits: queues the bus's events as they happen
+    /// and sends whatever is due, including retries left over from a restart.
+    pub async fn run(self: Arc<Self>, bus: EventBus) {
+        let wake = Arc::new(Notify::new());
+        let sender = {
+            let (service, wake) = (self.clone(), wake.clone());
+            async move {
+                loop {
+                    if let Err(e) = service.deliver_due(Utc::now()).await {
+                        eprintln!("Webhook delivery failed: {}", e);
+                    }
+                    tokio::select! {
+                        _ = wake.notified() => {}
+                        _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                    }
+                }
+            }
+        };
+        tokio::spawn(sender);
+
+        let mut events = bus.subscribe_all();
+        while let Some(event) = events.next().await {
+            match self.enqueue(&event, Utc::now()).await {
+                Ok(0) => {}
+                Ok(_) => wake.notify_one(),
+                Err(e) => eprintln!("Couldn't queue webhook deliveries: {}", e),
+            }
+        }
+    }
+}
+
+/// The webhook for a bus event, if there is one: its type, an id derived from
+/// what happened, so the same event always gets the same id, and its data.
+/// The message broker gets the same events under the same ids.
+pub fn webhook_event(event: &UserEvent) -> Option<(WebhookEventType, String, Value)> {
+    let user_id = event.user_id;
+    let (event_type, source, data) = match &event.event {
+        WalletEvent::PaymentReceived { transaction } => (
+            WebhookEventType::PaymentReceived,
+            format!("{}:{}:{}", transaction.account, transaction.hash, transaction.operation_index),
+            json!({ "user_id": user_id, "transaction": transaction }),
+        ),
+        WalletEvent::TransactionFailed { transaction } => (
+            WebhookEventType::TransactionFailed,
+            format!("{}:{}:{}", transaction.account, transaction.hash, transaction.operation_index),
+            json!({ "user_id": user_id, "transaction": transaction }),
+        ),
+        WalletEvent::AccountVerified { verified_at } => (
+            WebhookEventType::AccountVerified,
+            format!("{}:{}", user_id, verified_at.to_rfc3339()),
+            json!({ "user_id": user_id, "verified_at": verified_at }),
+        ),
+        WalletEvent::TransactionConfirmed { .. }
+        | WalletEvent::SessionRevoked { .. }
+        | WalletEvent::ApiKeyRevoked { .. }
+        | WalletEvent::AccountDisabled { .. } => return None,
+    };
+
+    let digest = Sha256::digest(format!("{}:{}", event_type.as_str(), source));
+    Some((event_type, format!("evt_{}", hex::encode(&digest[..16])), data))
+}
+
+/// The API request behind the payment an event is about, if there was one.
+fn request_id(event: &WalletEvent) -> Option<String> {
+    match event {
+        WalletEvent::PaymentReceived { transaction }
+        | WalletEvent::TransactionConfirmed { transaction }
+        | WalletEvent::TransactionFailed { transaction } => transaction.request_id.clone(),
+        WalletEvent::AccountVerified { .. }
+        | WalletEvent::SessionRevoked { .. }
+        | WalletEvent::ApiKeyRevoked { .. }
+        | WalletEvent::AccountDisabled { .. } => None,
+    }
+}
+
+/// How long to wait after the `attempts`th failed attempt.
+fn retry_delay(attempts: i64) -> Duration {
+    Duration::seconds(FIRST_RETRY_SECS << (attempts - 1).clamp(0, 16))
+}
+
+/// HTTPS to a name or public IP address. Where local receivers are allowed,
+/// also HTTP or HTTPS to this machine or a private network.
+fn validate_url(url: &str, allow_local: bool) -> Result<String> {
+    let invalid = || AppError::ValidationError(format!("'{}' isn't a webhook URL; use https://...", url.trim()));
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|_| invalid())?;
+    let host = parsed.host_str().ok_or_else(invalid)?;
+    let address = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>();
+    let local = host.eq_ignore_ascii_case("localhost") || address.is_ok_and(|address| !is_public(address));
+
+    match parsed.scheme() {
+        "https" | "http" if local && !allow_local => Err(AppError::ValidationError(format!(
+            "'{}' is on a private network; webhooks go to public addresses",
+            url.trim()
+        ))),
+        "https" => Ok(parsed.to_string()),
+        "http" if local => Ok(parsed.to_string()),
+        _ => Err(invalid()),
+    }
+}
+
+/// Whether `address` is on the public internet rather than this machine, a
+/// private or link-local network, or a range set aside for other uses.
+fn is_public(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_unspecified()
+                || v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_multicast()
+                || a == 0
+                || a >= 240
+                // Carrier-grade NAT and benchmarking.
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (18..20).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            // IPv4-mapped and NAT64 addresses reach IPv4 hosts.
+            let embedded = match segments {
+                [0, 0, 0, 0, 0, 0xffff, ..] | [0x64, 0xff9b, 0, 0, 0, 0, ..] => {
+                    let [.., high, low] = segments;
+                    Some(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low)))
+                }
+                _ => None,
+            };
+            match embedded {
+                Some(v4) => is_public(v4.into()),
+                None => !(v6.is_unspecified() || v6.is_loopback() || v6.is_multicast() || v6.is_unique_local() || v6.is_unicast_link_local()),
+            }
+        }
+    }
+}
+
+/// Resolves webhook hosts to their public addresses only, when the
+/// connection is made, so a name can't be pointed at a private address
+/// after it has been registered.
+struct PublicOnly;
+
+impl Resolve for PublicOnly {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Ties a sealed secret to its endpoint.
+fn context(endpoint_id: &Uuid) -> Vec<u8> {
+    format!("webhook_secret:{}", endpoint_id).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transaction::{TransactionDirection, TransactionStatus, WalletTransaction};
+    use axum::body::Bytes;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::Mutex;
+    use stellar_wallet::webhook::{self, REQUEST_ID_HEADER, SIGNATURE_HEADER};
+    use tokio::net::TcpListener;
+
+    const KEYS: &str = "k1:0000000000000000000000000000000000000000000000000000000000000001";
+    const SECRET: &str = "whsec_shared_with_the_receiver";
+
+    fn failed_payment(account: &str) -> WalletTransaction {
+        let now = Utc::now();
+        WalletTransaction {
+            id: Uuid::new_v4(),
+            account: account.to_string(),
+            hash: "ab".repeat(32),
+            operation_index: 1,
+            direction: TransactionDirection::Outgoing,
+            asset_code: "XLM".to_string(),
+            amount_stroops: 10_000_000,
+            counterparty: "GDEST".to_string(),
+            memo: None,
+            status: TransactionStatus::Failed,
+            ledger: None,
+            error: Some("tx_insufficient_balance".to_string()),
+            request_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Answers 500 to the first request and 200 after that, keeping each
+    /// signed body it receives with its request id.
+    async fn flaky_receiver() -> (String, Arc<Mutex<Vec<(String, Option<String>, Bytes)>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let log = log.clone();
+                async move {
+                    let mut log = log.lock().unwrap();
+                    let request_id = headers.get(REQUEST_ID_HEADER).map(|id| id.to_str().unwrap().to_string());
+                    log.push((headers[SIGNATURE_HEADER].to_str().unwrap().to_string(), request_id, body));
+                    if log.len() == 1 {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn retries_failed_deliveries_with_backoff_and_logs_them() {
+        let db = SqliteDatabase::in_memory().await;
+        let user = db.insert_test_user().await;
+        let service = WebhookEndpointService::with_keys(db.clone(), KeyRing::parse(KEYS).unwrap());
+        let (url, received) = flaky_receiver().await;
+
+        let webhook = NewWebhook {
+            url,
+            event_types: vec![WebhookEventType::TransactionFailed],
+            secret: Some(SECRET.to_string().into()),
+            all_accounts: false,
+        };
+        let (endpoint, _) = service.register(&user, webhook).await.unwrap();
+
+        let transaction = WalletTransaction {
+            request_id: Some("req-9".to_string()),
+            ..failed_payment("GSOURCE")
+        };
+        let event = UserEvent {
+            user_id: user,
+            event: WalletEvent::TransactionFailed { transaction },
+        };
+        let now = Utc::now();
+        assert_eq!(service.enqueue(&event, now).await.unwrap(), 1);
+        assert_eq!(service.enqueue(&event, now).await.unwrap(), 0);
+        let confirmed = UserEvent {
+            user_id: user,
+            event: WalletEvent::AccountVerified { verified_at: now },
+        };
+        assert_eq!(service.enqueue(&confirmed, now).await.unwrap(), 0);
+
+        assert_eq!(service.deliver_due(now).await.unwrap(), 1);
+        let log = service.deliveries(&user, &endpoint.id, 10).await.unwrap();
+        assert_eq!(log[0].status, DeliveryStatus::Pending);
+        assert_eq!(log[0].response_status, Some(500));
+        assert_eq!(log[0].next_attempt_at, Some(now + Duration::seconds(30)));
+
+        assert_eq!(service.deliver_due(now + Duration::seconds(29)).await.unwrap(), 0);
+        assert_eq!(service.deliver_due(now + Duration::seconds(30)).await.unwrap(), 1);
+        let log = service.deliveries(&user, &endpoint.id, 10).await.unwrap();
+        assert_eq!(log[0].status, DeliveryStatus::Delivered);
+        assert_eq!(log[0].attempts, 2);
+        assert_eq!(log[0].request_id.as_deref(), Some("req-9"));
+
+        let received = received.lock().unwrap();
+        let (signature, request_id, body) = &received[1];
+        assert_eq!(request_id.as_deref(), Some("req-9"));
+        assert_eq!(webhook::verify(SECRET.as_bytes(), signature, body, Utc::now().timestamp(), 60), Ok(()));
+        let body: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(body["type"], "transaction.failed");
+        assert_eq!(body["id"], log[0].event_id);
+        assert_eq!(body["data"]["transaction"]["error"], "tx_insufficient_balance");
+    }
+
+    #[tokio::test]
+    async fn keeps_endpoints_to_their_owners() {
+        let db = SqliteDatabase::in_memory().await;
+        let alice = db.insert_test_user().await;
+        let service = WebhookEndpointService::with_keys(db.clone(), KeyRing::parse(KEYS).unwrap());
+        let webhook = |url: &str, all_accounts| NewWebhook {
+            url: url.to_string(),
+            event_types: vec![WebhookEventType::PaymentReceived],
+            secret: None,
+            all_accounts,
+        };
+
+        assert!(service.register(&alice, webhook("http://hooks.example.com", false)).await.is_err());
+        assert!(service.register(&alice, webhook("https://hooks.example.com", true)).await.is_err());
+        let (endpoint, secret) = service.register(&alice, webhook("https://hooks.example.com/in", false)).await.unwrap();
+        assert!(secret.expose_secret().starts_with("whsec_"));
+
+        let stranger = Uuid::new_v4();
+        assert!(service.remove(&stranger, &endpoint.id).await.is_err());
+        assert_eq!(service.list(&alice, false).await.unwrap().len(), 1);
+        service.remove(&alice, &endpoint.id).await.unwrap();
+        assert!(service.list(&alice, false).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_reaches_private_networks_when_allowed() {
+        assert!(validate_url("https://hooks.example.com/in", false).is_ok());
+        assert!(validate_url("https://203.0.113.9.example.com", false).is_ok());
+        for url in [
+            "http://hooks.example.com",
+            "http://localhost:8080/hook",
+            "https://localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://[::1]/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://10.0.0.8",
+            "https://[fd00::1]",
+            "https://[::ffff:192.168.1.1]",
+            "ftp://hooks.example.com",
+        ] {
+            assert!(validate_url(url, false).is_err(), "{} was allowed", url);
+        }
+
+        assert!(validate_url("http://localhost:8080/hook", true).is_ok());
+        assert!(validate_url("https://10.0.0.8", true).is_ok());
+        assert!(validate_url("http://hooks.example.com", true).is_err());
+    }
+
+    #[test]
+    fn tells_public_addresses_from_private_ones() {
+        for public in ["8.8.8.8", "2606:4700::1111", "::ffff:1.1.1.1"] {
+            assert!(is_public(public.parse().unwrap()), "{}", public);
+        }
+        for private in ["127.0.0.1", "169.254.169.254", "172.16.0.1", "100.64.0.1", "0.0.0.0", "::1", "fe80::1", "fc00::1", "64:ff9b::a00:1"] {
+            assert!(!is_public(private.parse().unwrap()), "{}", private);
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_names_that_resolve_to_this_machine() {
+        let resolved = PublicOnly.resolve("localhost".parse().unwrap()).await;
+        assert!(resolved.is_err());
+    }
+
+    #[tokio::test]
+    async fn does_not_follow_redirects() {
+        let app = Router::new().route("/hook", post(|| async { axum::response::Redirect::temporary("/elsewhere") }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let service = WebhookEndpointService::with_keys(SqliteDatabase::in_memory().await, KeyRing::parse(KEYS).unwrap());
+        let status = post_event(&service.http, &url, b"secret", "evt_1", None, "{}".to_string()).await.unwrap();
+        assert_eq!(status, StatusCode::TEMPORARY_REDIRECT);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use crate::utils::request_id;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::env;
+use stellar_wallet::webhook::{self, EVENT_ID_HEADER, REQUEST_ID_HEADER, SIGNATURE_HEADER};
+use uuid::Uuid;
+
+struct WebhookEndpoint {
+    url: String,
+    secret: String,
+}
+
+/// Delivers signed events to the operator's endpoint (`WEBHOOK_URL`, signed
+/// with `WEBHOOK_SECRET`). Receivers check them with
+/// [`stellar_wallet::webhook::verify`].
+pub struct WebhookService {
+    endpoint: Option<WebhookEndpoint>,
+    http: reqwest::Client,
+}
+
+impl WebhookService {
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        match (config::var("WEBHOOK_URL"), var("WEBHOOK_SECRET")) {
+            (Some(url), Some(secret)) => Ok(Self::new(&url, &secret)),
+            (None, _) => Ok(Self::disabled()),
+            (Some(_), None) => Err(AppError::ValidationError("WEBHOOK_URL needs WEBHOOK_SECRET to sign events".to_string())),
+        }
+    }
+
+    /// Posts events to `url`, signed with `secret`.
+    pub fn new(url: &str, secret: &str) -> Self {
+        Self {
+            endpoint: Some(WebhookEndpoint {
+                url: url.to_string(),
+                secret: secret.to_string(),
+            }),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends nothing; every send fails saying to set `WEBHOOK_URL`.
+    pub fn disabled() -> Self {
+        Self {
+            endpoint: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Signs and posts an event, returning its id.
+    pub async fn send(&self, event_type: &str, data: Value) -> Result<String> {
+        let endpoint = self
+            .endpoint
+            .as_ref()
+            .ok_or_else(|| AppError::ValidationError("Webhooks are disabled; set WEBHOOK_URL to enable them".to_string()))?;
+
+        let id = format!("evt_{}", Uuid::new_v4().simple());
+        let body = event_body(&id, event_type, Utc::now(), data);
+
+        let request_id = request_id::current();
+        let status = post_event(&self.http, &endpoint.url, endpoint.secret.as_bytes(), &id, request_id.as_deref(), body).await?;
+        if !status.is_success() {
+            return Err(AppError::InternalError(format!("Webhook endpoint returned HTTP {}", status)));
+        }
+
+        Ok(id)
+    }
+
+    /// Sends a sample `webhook.test` event so operators can check their receiver.
+    pub async fn send_test(&self) -> Result<String> {
+        self.send("webhook.test", json!({ "message": "This is a test event from the wallet." })).await
+    }
+}
+
+/// The JSON body every webhook request carries.
+pub fn event_body(id: &str, event_type: &str, created_at: DateTime<Utc>, data: Value) -> String {
+    json!({
+        "id": id,
+        "type": event_type,
+        "created_at": created_at.to_rfc3339(),
+        "data": data,
+    })
+    .to_string()
+}
+
+/// Signs `body` with `secret` as of now and posts it, returning the status
+/// the endpoint answered with. Only failing to reach it is an error.
+pub async fn post_event(
+    http: &reqwest::Client,
+    url: &str,
+    secret: &[u8],
+    event_id: &str,
+    request_id: Option<&str>,
+    body: String,
+) -> Result<StatusCode> {
+    let mut request = http
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header(EVENT_ID_HEADER, event_id)
+        .header(SIGNATURE_HEADER, webhook::sign(secret, Utc::now().timestamp(), body.as_bytes()));
+    if let Some(request_id) = request_id {
+        request = request.header(REQUEST_ID_HEADER, request_id);
+    }
+    let response = request
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| AppError::InternalError(format!("Webhook delivery failed: {}", e)))?;
+
+    Ok(response.status())
+}
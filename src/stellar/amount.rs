@@ -0,0 +1,98 @@
+use crate::errors::{AppError, Result};
+use crate::utils::validation::Validator;
+
+/// Number of stroops (the smallest unit) in one lumen or asset unit.
+pub const STROOPS_PER_UNIT: i64 = 10_000_000;
+
+/// Smallest balance a new account can be created with (two base reserves).
+pub const MIN_STARTING_BALANCE_STROOPS: i64 = STROOPS_PER_UNIT;
+
+/// Reserve locked per ledger entry (the account itself counts as two).
+pub const BASE_RESERVE_STROOPS: i64 = STROOPS_PER_UNIT / 2;
+
+/// Converts a decimal amount such as `"12.5"` into stroops.
+pub fn to_stroops(amount: &str) -> Result<i64> {
+    Validator::validate_amount(amount)?;
+    parse_stroops(amount)
+}
+
+/// Parses a non-negative decimal amount as reported by Horizon (e.g.
+/// `"0.0000000"`) into stroops. Unlike [`to_stroops`], zero is accepted.
+pub fn parse_stroops(amount: &str) -> Result<i64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) || fraction.len() > 7 {
+        return Err(AppError::ValidationError(format!("Invalid amount: {}", amount)));
+    }
+
+    let fraction = format!("{:0<7}", fraction);
+
+    let whole: i64 = whole
+        .parse()
+        .map_err(|_| AppError::ValidationError("Invalid amount".to_string()))?;
+    let fraction: i64 = fraction
+        .parse()
+        .map_err(|_| AppError::ValidationError("Invalid amount".to_string()))?;
+
+    whole
+        .checked_mul(STROOPS_PER_UNIT)
+        .and_then(|stroops| stroops.checked_add(fraction))
+        .ok_or_else(|| AppError::ValidationError("Amount is too large".to_string()))
+}
+
+/// Formats stroops as a decimal amount with seven places, e.g. `"12.5000000"`.
+pub fn format_stroops(stroops: i64) -> String {
+    let sign = if stroops < 0 { "-" } else { "" };
+    let stroops = stroops.unsigned_abs();
+    let unit = STROOPS_PER_UNIT as u64;
+
+    format!("{}{}.{:07}", sign, stroops / unit, stroops % unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_whole_and_fractional_amounts() {
+        assert_eq!(to_stroops("1").unwrap(), 10_000_000);
+        assert_eq!(to_stroops("12.5").unwrap(), 125_000_000);
+        assert_eq!(to_stroops("0.0000001").unwrap(), 1);
+    }
+
+    #[test]
+    fn rejects_more_than_seven_decimal_places() {
+        assert!(to_stroops("0.00000001").is_err());
+    }
+
+    #[test]
+    fn rejects_zero_and_malformed_amounts() {
+        for amount in ["0", "0.0", "", "-1", "1.", ".5", "1e3", "abc"] {
+            assert!(to_stroops(amount).is_err(), "{:?} should be rejected", amount);
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_that_overflow() {
+        // Twelve integer digits pass validation but exceed i64 once scaled.
+        assert!(to_stroops("999999999999").is_err());
+        assert_eq!(to_stroops("922337203685").unwrap(), 922_337_203_685 * STROOPS_PER_UNIT);
+    }
+
+    #[test]
+    fn parses_horizon_balances_including_zero() {
+        assert_eq!(parse_stroops("0.0000000").unwrap(), 0);
+        assert_eq!(parse_stroops("10.0000000").unwrap(), 100_000_000);
+        assert!(parse_stroops("-1.0").is_err());
+        assert!(parse_stroops(".5").is_err());
+    }
+
+    #[test]
+    fn formats_stroops_with_seven_places() {
+        assert_eq!(format_stroops(125_000_000), "12.5000000");
+        assert_eq!(format_stroops(1), "0.0000001");
+        assert_eq!(format_stroops(-5_000_000), "-0.5000000");
+        assert_eq!(to_stroops(&format_stroops(123_456_789)).unwrap(), 123_456_789);
+    }
+}
@@ -0,0 +1,392 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::amount::{parse_stroops, BASE_RESERVE_STROOPS};
+use crate::stellar::paging::{PagedStream, MAX_PAGE_SIZE};
+use crate::stellar::transaction::SignedTransaction;
+use crate::utils::request_id;
+use serde::de::DeserializeOwned;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecord {
+    pub sequence: String,
+    pub balances: Vec<BalanceRecord>,
+    #[serde(default)]
+    pub subentry_count: u32,
+    #[serde(default)]
+    pub num_sponsoring: u32,
+    #[serde(default)]
+    pub num_sponsored: u32,
+    pub home_domain: Option<String>,
+    #[serde(default)]
+    pub signers: Vec<SignerRecord>,
+}
+
+impl AccountRecord {
+    pub fn sequence_number(&self) -> Result<i64> {
+        self.sequence
+            .parse()
+            .map_err(|_| AppError::StellarError(format!("Invalid sequence number from Horizon: {}", self.sequence)))
+    }
+
+    /// The XLM balance as reported by Horizon, e.g. `"10.0000000"`.
+    pub fn native_balance(&self) -> &str {
+        self.balances
+            .iter()
+            .find(|balance| balance.asset_type == "native")
+            .map(|balance| balance.balance.as_str())
+            .unwrap_or("0")
+    }
+
+    /// The balance the account must keep: two base reserves for the account plus
+    /// one per subentry, adjusted for sponsorships.
+    pub fn minimum_balance_stroops(&self) -> i64 {
+        let entries = 2 + self.subentry_count as i64 + self.num_sponsoring as i64 - self.num_sponsored as i64;
+        entries * BASE_RESERVE_STROOPS
+    }
+
+    /// The weight `public_key` signs for this account with, if it is a signer.
+    pub fn signer_weight(&self, public_key: &str) -> Option<u8> {
+        self.signers
+            .iter()
+            .find(|signer| signer.key == public_key)
+            .map(|signer| signer.weight)
+    }
+}
+
+/// One of the keys that can sign for an account, including its own (master) key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerRecord {
+    pub key: String,
+    pub weight: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceRecord {
+    pub balance: String,
+    pub asset_type: String,
+    pub asset_code: Option<String>,
+    pub asset_issuer: Option<String>,
+}
+
+/// An entry from an account's `/payments` feed, joined with its transaction.
+/// Covers payments, path payments and account creations.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentRecord {
+    /// Operation ID (a Stellar "TOID" packing ledger, transaction and operation order).
+    pub id: String,
+    pub transaction_hash: String,
+    pub created_at: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub amount: Option<String>,
+    pub asset_type: Option<String>,
+    pub asset_code: Option<String>,
+    pub funder: Option<String>,
+    pub account: Option<String>,
+    pub starting_balance: Option<String>,
+    pub transaction: Option<TransactionRecord>,
+}
+
+impl PaymentRecord {
+    pub fn sender(&self) -> Option<&str> {
+        self.from.as_deref().or(self.funder.as_deref())
+    }
+
+    pub fn recipient(&self) -> Option<&str> {
+        self.to.as_deref().or(self.account.as_deref())
+    }
+
+    pub fn amount(&self) -> Option<&str> {
+        self.amount.as_deref().or(self.starting_balance.as_deref())
+    }
+
+    /// `"XLM"` for native payments and account creations, otherwise the asset code.
+    pub fn asset(&self) -> &str {
+        match self.asset_type.as_deref() {
+            None | Some("native") => "XLM",
+            Some(other) => self.asset_code.as_deref().unwrap_or(other),
+        }
+    }
+
+    pub fn memo(&self) -> Option<&str> {
+        self.transaction.as_ref().and_then(|tx| tx.memo.as_deref())
+    }
+
+    /// Ledger the operation closed in, decoded from the upper 32 bits of its ID.
+    pub fn ledger(&self) -> Option<i64> {
+        self.id.parse::<i64>().ok().map(|toid| toid >> 32)
+    }
+
+    /// 1-based position of the operation within its transaction (lowest 12 bits of its ID).
+    pub fn operation_index(&self) -> Option<i64> {
+        self.id.parse::<i64>().ok().map(|toid| toid & 0xfff)
+    }
+}
+
+/// An entry from an account's `/effects` feed. Only the balance-changing
+/// fields are kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EffectRecord {
+    #[serde(rename = "type")]
+    pub effect_type: String,
+    pub amount: Option<String>,
+    pub asset_type: Option<String>,
+    pub asset_code: Option<String>,
+    pub starting_balance: Option<String>,
+}
+
+impl EffectRecord {
+    /// The asset and stroops this effect moved into (positive) or out of
+    /// (negative) the account, for credits, debits and account creation. Other
+    /// effects, such as trades or signer changes, return `None`.
+    pub fn balance_change(&self) -> Option<(&str, i64)> {
+        let (amount, sign) = match self.effect_type.as_str() {
+            "account_created" => (self.starting_balance.as_deref()?, 1),
+            "account_credited" => (self.amount.as_deref()?, 1),
+            "account_debited" => (self.amount.as_deref()?, -1),
+            _ => return None,
+        };
+        let asset = match self.asset_type.as_deref() {
+            None | Some("native") => "XLM",
+            Some(other) => self.asset_code.as_deref().unwrap_or(other),
+        };
+
+        Some((asset, sign * parse_stroops(amount).ok()?))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionRecord {
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubmitTransactionResponse {
+    pub hash: String,
+    pub ledger: u32,
+}
+
+/// A transaction from Horizon's `/transactions/{hash}`. Failed transactions
+/// are in a ledger too: they used up their fee and sequence number but
+/// changed nothing else.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LedgerTransaction {
+    pub hash: String,
+    pub ledger: u32,
+    pub successful: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct HorizonProblem {
+    title: String,
+    extras: Option<HorizonProblemExtras>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HorizonProblemExtras {
+    result_codes: Option<serde_json::Value>,
+}
+
+/// How long [`HorizonClient::is_reachable`] waits before calling Horizon unreachable.
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A thin client for the parts of the Horizon REST API the wallet needs.
+#[derive(Clone)]
+pub struct HorizonClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl HorizonClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Whether Horizon answers at all, within a few seconds. Used to decide
+    /// between sending a payment and queuing it for later.
+    pub async fn is_reachable(&self) -> bool {
+        self.http
+            .get(&self.base_url)
+            .timeout(REACHABILITY_TIMEOUT)
+            .send()
+            .await
+            .is_ok_and(|response| !response.status().is_server_error())
+    }
+
+    /// Loads an account, returning `None` if it hasn't been funded on the network yet.
+    pub async fn get_account(&self, address: &str) -> Result<Option<AccountRecord>> {
+        let response = self
+            .http
+            .get(format!("{}/accounts/{}", self.base_url, address))
+            .send()
+            .await
+            .map_err(|e| AppError::StellarError(format!("Horizon request failed: {}", e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(Self::problem_error(response).await);
+        }
+
+        let account = response
+            .json::<AccountRecord>()
+            .await
+            .map_err(|e| AppError::StellarError(format!("Invalid account response from Horizon: {}", e)))?;
+
+        Ok(Some(account))
+    }
+
+    /// Every record of the collection at `path` (e.g. `accounts/G.../effects`),
+    /// newest first, fetched a page at a time as the stream is read.
+    pub fn stream<T: DeserializeOwned + Send + 'static>(&self, path: &str, query: &[(&str, &str)]) -> PagedStream<T> {
+        let mut url = format!("{}/{}?order=desc&limit={}", self.base_url, path.trim_start_matches('/'), MAX_PAGE_SIZE);
+        for (name, value) in query {
+            url.push_str(&format!("&{}={}", name, urlencoding::encode(value)));
+        }
+        PagedStream::new(self.http.clone(), url)
+    }
+
+    /// Payments involving `address`, newest first, joined with their
+    /// transactions. Unfunded accounts have no history and yield nothing.
+    pub fn stream_payments(&self, address: &str) -> PagedStream<PaymentRecord> {
+        self.stream(&format!("accounts/{}/payments", address), &[("join", "transactions")])
+    }
+
+    /// Every payment on the network after the operation `cursor`, oldest
+    /// first, joined with their transactions.
+    pub fn stream_network_payments_after(&self, cursor: &str) -> PagedStream<PaymentRecord> {
+        let url = format!(
+            "{}/payments?order=asc&limit={}&cursor={}&join=transactions",
+            self.base_url,
+            MAX_PAGE_SIZE,
+            urlencoding::encode(cursor)
+        );
+        PagedStream::new(self.http.clone(), url)
+    }
+
+    /// Effects on `address`, newest first.
+    pub fn stream_effects(&self, address: &str) -> PagedStream<EffectRecord> {
+        self.stream(&format!("accounts/{}/effects", address), &[])
+    }
+
+    /// Submits a signed transaction. During an API request, the request's id
+    /// goes along as `X-Request-Id`, so a Horizon you run can log it.
+    ///
+    /// Fails with [`AppError::UnconfirmedError`] when the transaction may
+    /// have been sent but Horizon didn't say how it ended, e.g. it timed out
+    /// or answered with a server error, and with a Stellar error when it
+    /// certainly wasn't applied.
+    pub async fn submit_transaction(&self, signed: &SignedTransaction) -> Result<SubmitTransactionResponse> {
+        let unconfirmed = |reason: String| AppError::UnconfirmedError {
+            hash: signed.hash.clone(),
+            message: format!(
+                "Horizon didn't confirm transaction {} ({}); it may still go through, so check its status before sending it again",
+                signed.hash, reason
+            ),
+        };
+
+        let mut request = self.http.post(format!("{}/transactions", self.base_url)).form(&[("tx", &signed.envelope_xdr)]);
+        if let Some(request_id) = request_id::current() {
+            request = request.header("X-Request-Id", request_id);
+        }
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) if e.is_connect() => return Err(AppError::StellarError(format!("Horizon request failed: {}", e))),
+            Err(e) => return Err(unconfirmed(format!("request failed: {}", e))),
+        };
+
+        if response.status().is_server_error() {
+            return Err(unconfirmed(format!("HTTP {}", response.status())));
+        }
+        if !response.status().is_success() {
+            return Err(Self::problem_error(response).await);
+        }
+
+        response
+            .json::<SubmitTransactionResponse>()
+            .await
+            .map_err(|e| unconfirmed(format!("unreadable response: {}", e)))
+    }
+
+    /// The transaction with `hash` once it is in a ledger, whether or not it
+    /// succeeded there. `None` until then.
+    pub async fn get_transaction(&self, hash: &str) -> Result<Option<LedgerTransaction>> {
+        let response = self
+            .http
+            .get(format!("{}/transactions/{}", self.base_url, hash))
+            .send()
+            .await
+            .map_err(|e| AppError::StellarError(format!("Horizon request failed: {}", e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(Self::problem_error(response).await);
+        }
+
+        let transaction = response
+            .json::<LedgerTransaction>()
+            .await
+            .map_err(|e| AppError::StellarError(format!("Invalid transaction response from Horizon: {}", e)))?;
+
+        Ok(Some(transaction))
+    }
+
+    pub(super) async fn problem_error(response: reqwest::Response) -> AppError {
+        let status = response.status();
+
+        match response.json::<HorizonProblem>().await {
+            Ok(problem) => {
+                let codes = problem
+                    .extras
+                    .and_then(|extras| extras.result_codes)
+                    .map(|codes| format!(" ({})", codes))
+                    .unwrap_or_default();
+                AppError::StellarError(format!("{}{}", problem.title, codes))
+            }
+            Err(_) => AppError::StellarError(format!("Horizon returned HTTP {}", status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar::paging::Page;
+
+    #[test]
+    fn parses_payment_and_create_account_records() {
+        let body = r#"{"_embedded": {"records": [
+            {"id": "237044848320512002", "transaction_hash": "aa11", "type": "payment",
+             "created_at": "2025-03-01T10:00:00Z", "from": "GA", "to": "GB",
+             "amount": "1.5000000", "asset_type": "credit_alphanum4", "asset_code": "USDC",
+             "transaction": {"memo_type": "text", "memo": "rent"}},
+            {"id": "237044848320512001", "transaction_hash": "bb22", "type": "create_account",
+             "created_at": "2025-02-01T10:00:00Z", "funder": "GA",
+             "account": "GB", "starting_balance": "2.0000000", "transaction": {"memo_type": "none"}}
+        ]}}"#;
+
+        let page: Page<PaymentRecord> = serde_json::from_str(body).unwrap();
+        let [payment, creation] = &page.embedded.records[..] else {
+            panic!("expected two records");
+        };
+
+        assert_eq!((payment.sender(), payment.recipient(), payment.amount()), (Some("GA"), Some("GB"), Some("1.5000000")));
+        assert_eq!((payment.asset(), payment.memo()), ("USDC", Some("rent")));
+        assert_eq!((creation.sender(), creation.recipient(), creation.amount()), (Some("GA"), Some("GB"), Some("2.0000000")));
+        assert_eq!((creation.asset(), creation.memo()), ("XLM", None));
+        assert_eq!((payment.ledger(), payment.operation_index()), (Some(55191304), Some(2)));
+    }
+}
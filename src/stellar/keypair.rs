@@ -0,0 +1,89 @@
+use crate::errors::{AppError, Result};
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use rand_core::OsRng;
+use stellar_strkey::ed25519::{PrivateKey, PublicKey};
+use stellar_xdr::curr::{DecoratedSignature, Signature, SignatureHint};
+use zeroize::Zeroizing;
+
+/// An ed25519 Stellar keypair. Deliberately not `Debug` so the secret seed can't
+/// end up in logs by accident. The signing key is wiped when dropped.
+pub struct Keypair {
+    signing_key: SigningKey,
+}
+
+impl Keypair {
+    pub fn random() -> Self {
+        Self {
+            signing_key: SigningKey::generate(&mut OsRng),
+        }
+    }
+
+    pub fn from_seed_bytes(seed: &[u8]) -> Result<Self> {
+        let seed: Zeroizing<[u8; 32]> = Zeroizing::new(
+            seed.try_into()
+                .map_err(|_| AppError::InternalError("Secret seed must be 32 bytes".to_string()))?,
+        );
+
+        Ok(Self {
+            signing_key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Parses an `S...` secret seed.
+    pub fn from_secret(secret: &str) -> Result<Self> {
+        let seed = PrivateKey::from_string(secret.trim())
+            .map(|seed| Zeroizing::new(seed.0))
+            .map_err(|_| AppError::ValidationError("Invalid Stellar secret seed".to_string()))?;
+
+        Self::from_seed_bytes(seed.as_ref())
+    }
+
+    /// The `G...` account address.
+    pub fn public_key(&self) -> String {
+        PublicKey(self.signing_key.verifying_key().to_bytes()).to_string()
+    }
+
+    pub fn seed_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.signing_key.to_bytes())
+    }
+
+    /// X25519 shared secret with the holder of the `G...` address `public_key`,
+    /// computed on the Montgomery forms of both ed25519 keys. The other side
+    /// gets the same value from their secret and our address.
+    pub fn shared_secret(&self, public_key: &str) -> Result<Zeroizing<[u8; 32]>> {
+        let their_key = PublicKey::from_string(public_key)
+            .ok()
+            .and_then(|key| VerifyingKey::from_bytes(&key.0).ok())
+            .ok_or_else(|| AppError::ValidationError(format!("Invalid Stellar address {}", public_key)))?;
+
+        let scalar = Zeroizing::new(self.signing_key.to_scalar_bytes());
+        let shared = Zeroizing::new(their_key.to_montgomery().mul_clamped(*scalar).to_bytes());
+
+        // A low-order point would make the secret predictable.
+        if *shared == [0u8; 32] {
+            return Err(AppError::ValidationError(format!("{} can't be used for key agreement", public_key)));
+        }
+
+        Ok(shared)
+    }
+
+    /// Signs `message` and tags the signature with the last four bytes of the
+    /// public key, as Stellar transaction envelopes expect.
+    pub fn sign_decorated(&self, message: &[u8]) -> Result<DecoratedSignature> {
+        let public_key = self.signing_key.verifying_key().to_bytes();
+        let signature = self.signing_key.sign(message).to_bytes();
+
+        let mut hint = [0u8; 4];
+        hint.copy_from_slice(&public_key[28..]);
+
+        Ok(DecoratedSignature {
+            hint: SignatureHint(hint),
+            signature: Signature(
+                signature
+                    .to_vec()
+                    .try_into()
+                    .map_err(|_| AppError::InternalError("Invalid signature length".to_string()))?,
+            ),
+        })
+    }
+}
@@ -0,0 +1,408 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::signer::Signer;
+use stellar_strkey::ed25519::PublicKey;
+use stellar_xdr::curr::{DecoratedSignature, Signature, SignatureHint};
+use std::sync::Mutex;
+
+// APDUs understood by the Stellar app on Ledger devices.
+const CLA: u8 = 0xe0;
+const INS_GET_PK: u8 = 0x02;
+const INS_SIGN_TX: u8 = 0x04;
+const P1_FIRST: u8 = 0x00;
+const P1_MORE: u8 = 0x80;
+const P2_LAST: u8 = 0x00;
+const P2_MORE: u8 = 0x80;
+const P2_NON_CONFIRM: u8 = 0x00;
+const P2_CONFIRM: u8 = 0x01;
+
+/// Largest data chunk the Stellar app accepts per APDU.
+const APDU_CHUNK_SIZE: usize = 150;
+
+const SW_OK: u16 = 0x9000;
+const SW_DENIED: u16 = 0x6985;
+const SW_APP_NOT_OPEN: [u16; 3] = [0x6511, 0x6d00, 0x6e00];
+
+const HARDENED: u32 = 0x8000_0000;
+
+/// Sends one APDU to the device and returns the response data, with the
+/// trailing status word already checked.
+pub trait LedgerTransport: Send {
+    fn exchange(&mut self, apdu: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Signs with the Stellar app on a Ledger device, at SEP-5 path `m/44'/148'/index'`.
+pub struct LedgerSigner {
+    transport: Mutex<Box<dyn LedgerTransport>>,
+    account_index: u32,
+    public_key: [u8; 32],
+}
+
+impl LedgerSigner {
+    /// Connects to the first Ledger found over USB.
+    pub fn connect(account_index: u32) -> Result<Self> {
+        Self::with_transport(Box::new(hid::HidTransport::open()?), account_index)
+    }
+
+    pub fn with_transport(mut transport: Box<dyn LedgerTransport>, account_index: u32) -> Result<Self> {
+        let public_key = get_public_key(transport.as_mut(), account_index, false)?;
+
+        Ok(Self {
+            transport: Mutex::new(transport),
+            account_index,
+            public_key,
+        })
+    }
+
+    /// Shows the address on the device screen and waits for the user to approve it.
+    pub fn confirm_address(&self) -> Result<()> {
+        let mut transport = self.transport()?;
+        let shown = get_public_key(transport.as_mut(), self.account_index, true)?;
+
+        if shown != self.public_key {
+            return Err(AppError::StellarError("Ledger returned a different address than before".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn transport(&self) -> Result<std::sync::MutexGuard<'_, Box<dyn LedgerTransport>>> {
+        self.transport
+            .lock()
+            .map_err(|_| AppError::InternalError("Ledger transport lock poisoned".to_string()))
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn public_key(&self) -> String {
+        PublicKey(self.public_key).to_string()
+    }
+
+    fn sign_transaction(&self, signature_payload: &[u8], _hash: &[u8; 32]) -> Result<DecoratedSignature> {
+        let mut transport = self.transport()?;
+        let mut response = Vec::new();
+
+        for apdu in sign_apdus(self.account_index, signature_payload) {
+            response = transport.exchange(&apdu)?;
+        }
+
+        let signature: [u8; 64] = response
+            .get(..64)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| AppError::StellarError("Ledger returned a malformed signature".to_string()))?;
+
+        let mut hint = [0u8; 4];
+        hint.copy_from_slice(&self.public_key[28..]);
+
+        Ok(DecoratedSignature {
+            hint: SignatureHint(hint),
+            signature: Signature(
+                signature
+                    .to_vec()
+                    .try_into()
+                    .map_err(|_| AppError::InternalError("Invalid signature length".to_string()))?,
+            ),
+        })
+    }
+}
+
+fn get_public_key(transport: &mut dyn LedgerTransport, account_index: u32, confirm: bool) -> Result<[u8; 32]> {
+    let path = encode_path(account_index);
+    let p2 = if confirm { P2_CONFIRM } else { P2_NON_CONFIRM };
+
+    let mut apdu = vec![CLA, INS_GET_PK, P1_FIRST, p2, path.len() as u8];
+    apdu.extend_from_slice(&path);
+
+    let response = transport.exchange(&apdu)?;
+    response
+        .get(..32)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| AppError::StellarError("Ledger returned a malformed public key".to_string()))
+}
+
+/// Splits a signing request into the APDU sequence the Stellar app expects: the
+/// derivation path followed by the payload, in chunks of at most 150 bytes.
+fn sign_apdus(account_index: u32, signature_payload: &[u8]) -> Vec<Vec<u8>> {
+    let mut data = encode_path(account_index);
+    data.extend_from_slice(signature_payload);
+
+    let chunks: Vec<&[u8]> = data.chunks(APDU_CHUNK_SIZE).collect();
+    let last = chunks.len() - 1;
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let p1 = if i == 0 { P1_FIRST } else { P1_MORE };
+            let p2 = if i == last { P2_LAST } else { P2_MORE };
+
+            let mut apdu = vec![CLA, INS_SIGN_TX, p1, p2, chunk.len() as u8];
+            apdu.extend_from_slice(chunk);
+            apdu
+        })
+        .collect()
+}
+
+/// BIP-32 path `m/44'/148'/index'` as a component count followed by big-endian indexes.
+fn encode_path(account_index: u32) -> Vec<u8> {
+    let components = [44 | HARDENED, 148 | HARDENED, account_index | HARDENED];
+
+    let mut path = vec![components.len() as u8];
+    for component in components {
+        path.extend_from_slice(&component.to_be_bytes());
+    }
+    path
+}
+
+fn status_error(status: u16) -> AppError {
+    match status {
+        SW_DENIED => AppError::AuthenticationError("Request rejected on the Ledger".to_string()),
+        status if SW_APP_NOT_OPEN.contains(&status) => {
+            AppError::StellarError("Unlock your Ledger and open the Stellar app".to_string())
+        }
+        status => AppError::StellarError(format!("Ledger returned status {:04x}", status)),
+    }
+}
+
+/// Ledger's HID framing, which wraps each APDU in 64-byte reports.
+mod hid {
+    use super::{status_error, LedgerTransport, SW_OK};
+    use crate::errors::{AppError, Result};
+
+    const LEDGER_VENDOR_ID: &str = "00002C97";
+    const CHANNEL: u16 = 0x0101;
+    const TAG_APDU: u8 = 0x05;
+    const PACKET_SIZE: usize = 64;
+
+    /// Splits an APDU into HID reports: channel, tag and sequence number on every
+    /// packet, with the total length prefixed on the first.
+    pub(super) fn frame(apdu: &[u8]) -> Vec<[u8; PACKET_SIZE]> {
+        let mut data = (apdu.len() as u16).to_be_bytes().to_vec();
+        data.extend_from_slice(apdu);
+
+        data.chunks(PACKET_SIZE - 5)
+            .enumerate()
+            .map(|(sequence, chunk)| {
+                let mut packet = [0u8; PACKET_SIZE];
+                packet[..2].copy_from_slice(&CHANNEL.to_be_bytes());
+                packet[2] = TAG_APDU;
+                packet[3..5].copy_from_slice(&(sequence as u16).to_be_bytes());
+                packet[5..5 + chunk.len()].copy_from_slice(chunk);
+                packet
+            })
+            .collect()
+    }
+
+    /// Reassembles a response from HID reports, returning `None` until enough
+    /// packets have arrived.
+    pub(super) fn unframe(packets: &[[u8; PACKET_SIZE]]) -> Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+
+        for (sequence, packet) in packets.iter().enumerate() {
+            if packet[..2] != CHANNEL.to_be_bytes() || packet[2] != TAG_APDU {
+                return Err(AppError::StellarError("Unexpected packet from Ledger".to_string()));
+            }
+            if packet[3..5] != (sequence as u16).to_be_bytes() {
+                return Err(AppError::StellarError("Ledger packets arrived out of order".to_string()));
+            }
+            data.extend_from_slice(&packet[5..]);
+        }
+
+        if data.len() < 2 {
+            return Ok(None);
+        }
+
+        let length = u16::from_be_bytes([data[0], data[1]]) as usize;
+        if data.len() - 2 < length {
+            return Ok(None);
+        }
+
+        Ok(Some(data[2..2 + length].to_vec()))
+    }
+
+    /// A Ledger reached through Linux `hidraw`, found by scanning sysfs for the
+    /// Ledger USB vendor id.
+    pub(super) struct HidTransport {
+        #[cfg(target_os = "linux")]
+        device: std::fs::File,
+    }
+
+    #[cfg(target_os = "linux")]
+    impl HidTransport {
+        pub(super) fn open() -> Result<Self> {
+            use std::fs;
+
+            let not_found = || AppError::StellarError("No Ledger device found. Is it plugged in and unlocked?".to_string());
+
+            let mut candidates: Vec<_> = fs::read_dir("/sys/class/hidraw")
+                .map_err(|_| not_found())?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    fs::read_to_string(entry.path().join("device/uevent"))
+                        .map(|uevent| uevent.to_uppercase().contains(&format!(":{}:", LEDGER_VENDOR_ID)))
+                        .unwrap_or(false)
+                })
+                .map(|entry| {
+                    let sys_path = fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path());
+                    (sys_path.to_string_lossy().contains(":1.0/"), entry.file_name())
+                })
+                .collect();
+
+            // The APDU channel is USB interface 0; later interfaces are U2F/WebUSB.
+            candidates.sort_by_key(|(is_apdu_interface, _)| !is_apdu_interface);
+            let (_, name) = candidates.into_iter().next().ok_or_else(not_found)?;
+
+            let device = fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(std::path::Path::new("/dev").join(&name))
+                .map_err(|e| AppError::StellarError(format!("Failed to open Ledger ({}): {}", name.to_string_lossy(), e)))?;
+
+            Ok(Self { device })
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    impl HidTransport {
+        pub(super) fn open() -> Result<Self> {
+            Err(AppError::StellarError("Ledger signing is only supported on Linux".to_string()))
+        }
+    }
+
+    impl LedgerTransport for HidTransport {
+        #[cfg(target_os = "linux")]
+        fn exchange(&mut self, apdu: &[u8]) -> Result<Vec<u8>> {
+            use std::io::{Read, Write};
+
+            let io_error = |e: std::io::Error| AppError::StellarError(format!("Ledger communication failed: {}", e));
+
+            for packet in frame(apdu) {
+                // hidraw expects a leading report id, which Ledger doesn't use.
+                let mut report = vec![0u8];
+                report.extend_from_slice(&packet);
+                self.device.write_all(&report).map_err(io_error)?;
+            }
+
+            let mut packets = Vec::new();
+            let response = loop {
+                let mut packet = [0u8; PACKET_SIZE];
+                self.device.read_exact(&mut packet).map_err(io_error)?;
+                packets.push(packet);
+
+                if let Some(response) = unframe(&packets)? {
+                    break response;
+                }
+            };
+
+            if response.len() < 2 {
+                return Err(AppError::StellarError("Ledger returned an empty response".to_string()));
+            }
+
+            let (data, status) = response.split_at(response.len() - 2);
+            match u16::from_be_bytes([status[0], status[1]]) {
+                SW_OK => Ok(data.to_vec()),
+                status => Err(status_error(status)),
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        fn exchange(&mut self, _apdu: &[u8]) -> Result<Vec<u8>> {
+            Err(AppError::StellarError("Ledger signing is only supported on Linux".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar::keypair::Keypair;
+    use sha2::Digest;
+
+    /// Answers like the Stellar app would, backed by a software key.
+    struct FakeDevice {
+        keypair: Keypair,
+        sent: std::sync::Arc<Mutex<Vec<Vec<u8>>>>,
+        payload: Vec<u8>,
+    }
+
+    impl LedgerTransport for FakeDevice {
+        fn exchange(&mut self, apdu: &[u8]) -> Result<Vec<u8>> {
+            self.sent.lock().unwrap().push(apdu.to_vec());
+
+            match apdu[1] {
+                INS_GET_PK => Ok(stellar_strkey::ed25519::PublicKey::from_string(&self.keypair.public_key()).unwrap().0.to_vec()),
+                INS_SIGN_TX => {
+                    let data = &apdu[5..];
+                    // The first chunk starts with the 13-byte derivation path.
+                    self.payload.extend_from_slice(if apdu[2] == P1_FIRST { &data[13..] } else { data });
+
+                    if apdu[3] == P2_MORE {
+                        return Ok(Vec::new());
+                    }
+                    let hash: [u8; 32] = sha2::Sha256::digest(&self.payload).into();
+                    Ok(self.keypair.sign_decorated(&hash)?.signature.0.to_vec())
+                }
+                _ => Err(status_error(0x6d00)),
+            }
+        }
+    }
+
+    #[test]
+    fn encodes_hardened_sep5_path() {
+        assert_eq!(
+            encode_path(1),
+            vec![3, 0x80, 0, 0, 44, 0x80, 0, 0, 148, 0x80, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn splits_long_payloads_into_flagged_chunks() {
+        let apdus = sign_apdus(0, &[7u8; 300]);
+
+        assert_eq!(apdus.len(), 3);
+        assert_eq!((apdus[0][2], apdus[0][3], apdus[0][4]), (P1_FIRST, P2_MORE, 150));
+        assert_eq!((apdus[1][2], apdus[1][3]), (P1_MORE, P2_MORE));
+        assert_eq!((apdus[2][2], apdus[2][3], apdus[2][4] as usize), (P1_MORE, P2_LAST, 313 - 300));
+    }
+
+    #[test]
+    fn hid_frames_round_trip() {
+        let apdu: Vec<u8> = (0..200u8).collect();
+        let packets = hid::frame(&apdu);
+
+        assert_eq!(packets.len(), 4);
+        assert_eq!(&packets[1][..5], &[0x01, 0x01, 0x05, 0x00, 0x01]);
+        assert_eq!(hid::unframe(&packets[..3]).unwrap(), None);
+        assert_eq!(hid::unframe(&packets).unwrap(), Some(apdu));
+    }
+
+    #[test]
+    fn signs_through_the_device() {
+        let keypair = Keypair::random();
+        let expected_address = keypair.public_key();
+        let sent = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let device = FakeDevice {
+            keypair,
+            sent: sent.clone(),
+            payload: Vec::new(),
+        };
+
+        let signer = LedgerSigner::with_transport(Box::new(device), 0).unwrap();
+        assert_eq!(signer.public_key(), expected_address);
+
+        let payload = vec![42u8; 400];
+        let hash: [u8; 32] = sha2::Sha256::digest(&payload).into();
+        let signature = signer.sign_transaction(&payload, &hash).unwrap();
+
+        let public_key = stellar_strkey::ed25519::PublicKey::from_string(&expected_address).unwrap().0;
+        let signature = ed25519_dalek::Signature::from_slice(&signature.signature.0).unwrap();
+        ed25519_dalek::VerifyingKey::from_bytes(&public_key).unwrap().verify_strict(&hash, &signature).unwrap();
+
+        assert_eq!(sent.lock().unwrap().len(), 1 + 3);
+    }
+
+    #[test]
+    fn maps_rejection_to_a_friendly_error() {
+        assert!(matches!(status_error(SW_DENIED), AppError::AuthenticationError(_)));
+        assert!(status_error(0x6e00).to_string().contains("Stellar app"));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod amount;
+pub mod horizon;
+pub mod keypair;
+pub mod ledger;
+pub mod network;
+pub mod paging;
+pub mod sep5;
+pub mod sep7;
+pub mod signer;
+pub mod stellar_toml;
+pub mod transaction;
+pub mod vanity;
\ No newline at end of file
@@ -0,0 +1,86 @@
+use crate::config;
+use crate::errors::{AppError, Result};
+use sha2::{Digest, Sha256};
+
+pub const TESTNET_HORIZON_URL: &str = "https://horizon-testnet.stellar.org";
+pub const TESTNET_PASSPHRASE: &str = "Test SDF Network ; September 2015";
+pub const PUBLIC_HORIZON_URL: &str = "https://horizon.stellar.org";
+pub const PUBLIC_PASSPHRASE: &str = "Public Global Stellar Network ; September 2015";
+
+/// The Stellar network the wallet talks to.
+#[derive(Debug, Clone)]
+pub struct Network {
+    pub name: String,
+    pub horizon_url: String,
+    pub passphrase: String,
+}
+
+impl Network {
+    pub fn testnet() -> Self {
+        Self {
+            name: "testnet".to_string(),
+            horizon_url: TESTNET_HORIZON_URL.to_string(),
+            passphrase: TESTNET_PASSPHRASE.to_string(),
+        }
+    }
+
+    pub fn public() -> Self {
+        Self {
+            name: "public".to_string(),
+            horizon_url: PUBLIC_HORIZON_URL.to_string(),
+            passphrase: PUBLIC_PASSPHRASE.to_string(),
+        }
+    }
+
+    /// Picks the network from `STELLAR_NETWORK` (`testnet` by default,
+    /// `public`/`mainnet`, or `custom`), with `HORIZON_URL` overriding the
+    /// Horizon endpoint. A custom network, such as a private or standalone
+    /// one, needs both `HORIZON_URL` and `STELLAR_NETWORK_PASSPHRASE`.
+    pub fn from_env() -> Result<Self> {
+        let horizon_url = config::var("HORIZON_URL");
+        let passphrase = config::var("STELLAR_NETWORK_PASSPHRASE");
+
+        let mut network = match config::var("STELLAR_NETWORK").unwrap_or_default().to_lowercase().as_str() {
+            "" | "testnet" => Self::testnet(),
+            "public" | "mainnet" => Self::public(),
+            "custom" => match (&horizon_url, &passphrase) {
+                (Some(horizon_url), Some(passphrase)) => Self {
+                    name: "custom".to_string(),
+                    horizon_url: horizon_url.clone(),
+                    passphrase: passphrase.clone(),
+                },
+                _ => {
+                    return Err(AppError::ValidationError(
+                        "STELLAR_NETWORK=custom needs HORIZON_URL and STELLAR_NETWORK_PASSPHRASE".to_string(),
+                    ))
+                }
+            },
+            other => {
+                return Err(AppError::ValidationError(format!(
+                    "Unknown STELLAR_NETWORK '{}' (expected testnet, public or custom)",
+                    other
+                )))
+            }
+        };
+
+        if network.name != "custom" && passphrase.is_some() {
+            return Err(AppError::ValidationError(
+                "STELLAR_NETWORK_PASSPHRASE only applies with STELLAR_NETWORK=custom".to_string(),
+            ));
+        }
+        if let Some(horizon_url) = horizon_url {
+            network.horizon_url = horizon_url;
+        }
+
+        Ok(network)
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.passphrase == PUBLIC_PASSPHRASE
+    }
+
+    /// The network ID mixed into every transaction hash that gets signed.
+    pub fn network_id(&self) -> [u8; 32] {
+        Sha256::digest(self.passphrase.as_bytes()).into()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::horizon::HorizonClient;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Horizon's largest page size.
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Deserialize)]
+pub(super) struct Page<T> {
+    #[serde(rename = "_embedded")]
+    pub(super) embedded: Embedded<T>,
+    #[serde(rename = "_links")]
+    links: Option<PageLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct Embedded<T> {
+    pub(super) records: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageLinks {
+    next: Option<Link>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    href: String,
+}
+
+/// The records of a paginated Horizon collection (payments, effects, offers,
+/// trades, ...) as one async stream. Pages are fetched lazily by following
+/// each page's `next` link, so `.take(n)` only requests what it needs. The
+/// stream ends at the first empty page; a 404 (an unfunded account) is an
+/// empty collection.
+pub struct PagedStream<T> {
+    inner: BoxStream<'static, Result<T>>,
+}
+
+impl<T: DeserializeOwned + Send + 'static> PagedStream<T> {
+    pub(super) fn new(http: reqwest::Client, first_url: String) -> Self {
+        let inner = stream::try_unfold((Some(first_url), VecDeque::new()), move |(mut next_url, mut buffered)| {
+            let http = http.clone();
+            async move {
+                loop {
+                    if let Some(record) = buffered.pop_front() {
+                        return Ok(Some((record, (next_url, buffered))));
+                    }
+                    let Some(url) = next_url.take() else {
+                        return Ok(None);
+                    };
+                    let Some(page) = fetch_page::<T>(&http, &url).await? else {
+                        return Ok(None);
+                    };
+                    if page.embedded.records.is_empty() {
+                        return Ok(None);
+                    }
+
+                    next_url = page.links.and_then(|links| links.next).map(|link| link.href).filter(|href| *href != url);
+                    buffered.extend(page.embedded.records);
+                }
+            }
+        })
+        .boxed();
+
+        Self { inner }
+    }
+}
+
+impl<T> Stream for PagedStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+async fn fetch_page<T: DeserializeOwned>(http: &reqwest::Client, url: &str) -> Result<Option<Page<T>>> {
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| AppError::StellarError(format!("Horizon request failed: {}", e)))?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    if !response.status().is_success() {
+        return Err(HorizonClient::problem_error(response).await);
+    }
+
+    response
+        .json::<Page<T>>()
+        .await
+        .map(Some)
+        .map_err(|e| AppError::StellarError(format!("Invalid page from Horizon: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        id: String,
+    }
+
+    /// Serves `/items` as three pages (two records, one, then none) and 404s
+    /// anything else.
+    async fn serve_pages() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let links = base.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let read = socket.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+
+                let (status, records, cursor) = match path.as_str() {
+                    "/items" => ("200 OK", r#"[{"id":"1"},{"id":"2"}]"#, 2),
+                    "/items?cursor=2" => ("200 OK", r#"[{"id":"3"}]"#, 3),
+                    "/items?cursor=3" => ("200 OK", "[]", 3),
+                    _ => ("404 Not Found", "[]", 0),
+                };
+                let body = format!(
+                    r#"{{"_links":{{"next":{{"href":"{}/items?cursor={}"}}}},"_embedded":{{"records":{}}}}}"#,
+                    links, cursor, records
+                );
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        base
+    }
+
+    #[tokio::test]
+    async fn follows_next_links_until_an_empty_page() {
+        let base = serve_pages().await;
+        let http = reqwest::Client::new();
+
+        let items: Vec<Item> = PagedStream::new(http.clone(), format!("{}/items", base)).try_collect().await.unwrap();
+        assert_eq!(items.iter().map(|item| item.id.as_str()).collect::<Vec<_>>(), ["1", "2", "3"]);
+
+        let first: Vec<Item> = PagedStream::new(http.clone(), format!("{}/items", base)).take(1).try_collect().await.unwrap();
+        assert_eq!(first.len(), 1);
+
+        let missing: Vec<Item> = PagedStream::new(http, format!("{}/accounts/GNONE/items", base)).try_collect().await.unwrap();
+        assert!(missing.is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::keypair::Keypair;
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// SEP-5 uses the BIP-44 path `m/44'/148'/index'`.
+const PURPOSE: u32 = 44;
+const STELLAR_COIN_TYPE: u32 = 148;
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Generates a fresh 24-word BIP-39 recovery phrase.
+pub fn generate_mnemonic() -> Result<Zeroizing<String>> {
+    let mut entropy = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(entropy.as_mut());
+
+    let mnemonic = Mnemonic::from_entropy(entropy.as_ref())
+        .map_err(|e| AppError::InternalError(format!("Failed to generate recovery phrase: {}", e)))?;
+
+    Ok(Zeroizing::new(mnemonic.to_string()))
+}
+
+/// Derives the Stellar account at `index` from a recovery phrase, following SEP-5.
+pub fn derive_keypair(phrase: &str, index: u32) -> Result<Keypair> {
+    let mnemonic = Mnemonic::parse(phrase)
+        .map_err(|e| AppError::ValidationError(format!("Invalid recovery phrase: {}", e)))?;
+
+    if index >= HARDENED_OFFSET {
+        return Err(AppError::ValidationError("Account index is too large".to_string()));
+    }
+
+    let seed = Zeroizing::new(mnemonic.to_seed(""));
+    let (mut key, mut chain_code) = hmac_split(b"ed25519 seed", seed.as_ref())?;
+
+    // SLIP-10 ed25519 only supports hardened children.
+    for segment in [PURPOSE, STELLAR_COIN_TYPE, index] {
+        let mut data = Zeroizing::new(Vec::with_capacity(37));
+        data.push(0);
+        data.extend_from_slice(key.as_ref());
+        data.extend_from_slice(&(segment | HARDENED_OFFSET).to_be_bytes());
+
+        (key, chain_code) = hmac_split(chain_code.as_ref(), &data)?;
+    }
+
+    Keypair::from_seed_bytes(key.as_ref())
+}
+
+/// Half of an HMAC-SHA512 output, wiped on drop.
+type HalfOutput = Zeroizing<[u8; 32]>;
+
+/// Splits HMAC-SHA512 output into a key and a chain code.
+fn hmac_split(key: &[u8], data: &[u8]) -> Result<(HalfOutput, HalfOutput)> {
+    let mut mac = HmacSha512::new_from_slice(key)
+        .map_err(|e| AppError::InternalError(format!("HMAC error: {}", e)))?;
+    mac.update(data);
+    let output: Zeroizing<[u8; 64]> = Zeroizing::new(mac.finalize().into_bytes().into());
+
+    let mut left = Zeroizing::new([0u8; 32]);
+    let mut right = Zeroizing::new([0u8; 32]);
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    Ok((left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector 1 from the SEP-5 specification.
+    const PHRASE: &str = "illness spike retreat truth genius clock brain pass fit cave bargain toe";
+
+    #[test]
+    fn derives_sep5_test_vector_accounts() {
+        assert_eq!(
+            derive_keypair(PHRASE, 0).unwrap().public_key(),
+            "GDRXE2BQUC3AZNPVFSCEZ76NJ3WWL25FYFK6RGZGIEKWE4SOOHSUJUJ6"
+        );
+        assert_eq!(
+            derive_keypair(PHRASE, 1).unwrap().public_key(),
+            "GBAW5XGWORWVFE2XTJYDTLDHXTY2Q2MO73HYCGB3XMFMQ562Q2W2GJQX"
+        );
+    }
+
+    #[test]
+    fn generated_phrase_has_24_words_and_derives() {
+        let phrase = generate_mnemonic().unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 24);
+        assert!(derive_keypair(&phrase, 0).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_phrase_and_index() {
+        assert!(derive_keypair("not a real recovery phrase", 0).is_err());
+        assert!(derive_keypair(PHRASE, HARDENED_OFFSET).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use crate::errors::{AppError, Result};
+use crate::utils::validation::Validator;
+
+const SEP7_PAY_PREFIX: &str = "web+stellar:pay";
+
+/// A SEP-7 `pay` request asking a wallet to send funds to `destination`.
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    pub destination: String,
+    pub amount: Option<String>,
+    pub memo: Option<String>,
+}
+
+impl PaymentRequest {
+    pub fn new(destination: &str) -> Self {
+        Self {
+            destination: destination.to_string(),
+            amount: None,
+            memo: None,
+        }
+    }
+
+    pub fn with_amount(mut self, amount: &str) -> Result<Self> {
+        Validator::validate_amount(amount)?;
+        self.amount = Some(amount.to_string());
+        Ok(self)
+    }
+
+    pub fn with_memo(mut self, memo: &str) -> Result<Self> {
+        Validator::validate_memo(memo)?;
+        self.memo = Some(memo.to_string());
+        Ok(self)
+    }
+
+    pub fn to_uri(&self) -> Result<String> {
+        if self.destination.is_empty() {
+            return Err(AppError::StellarError("Payment request has no destination".to_string()));
+        }
+
+        let mut uri = format!("{}?destination={}", SEP7_PAY_PREFIX, urlencoding::encode(&self.destination));
+
+        if let Some(amount) = &self.amount {
+            uri.push_str(&format!("&amount={}", urlencoding::encode(amount)));
+        }
+
+        if let Some(memo) = &self.memo {
+            uri.push_str(&format!("&memo={}&memo_type=MEMO_TEXT", urlencoding::encode(memo)));
+        }
+
+        Ok(uri)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::keypair::Keypair;
+use stellar_xdr::curr::DecoratedSignature;
+use zeroize::Zeroizing;
+
+/// Something that can authorise transactions for a Stellar account: a software
+/// key from the keystore or an external device such as a Ledger. Signers may
+/// be used from the API server's worker threads.
+pub trait Signer: Send + Sync {
+    /// The `G...` address signatures are valid for.
+    fn public_key(&self) -> String;
+
+    /// Signs a transaction. `signature_payload` is the XDR-encoded
+    /// `TransactionSignaturePayload` (so devices can show what is being signed)
+    /// and `hash` is its SHA-256.
+    fn sign_transaction(&self, signature_payload: &[u8], hash: &[u8; 32]) -> Result<DecoratedSignature>;
+
+    /// X25519 shared secret with another account, used to encrypt payment notes.
+    /// Devices that never reveal their key can't provide one.
+    fn shared_secret(&self, _public_key: &str) -> Result<Zeroizing<[u8; 32]>> {
+        Err(AppError::ValidationError("This signing device can't encrypt payment notes".to_string()))
+    }
+}
+
+impl Signer for Keypair {
+    fn public_key(&self) -> String {
+        Keypair::public_key(self)
+    }
+
+    fn sign_transaction(&self, _signature_payload: &[u8], hash: &[u8; 32]) -> Result<DecoratedSignature> {
+        self.sign_decorated(hash)
+    }
+
+    fn shared_secret(&self, public_key: &str) -> Result<Zeroizing<[u8; 32]>> {
+        Keypair::shared_secret(self, public_key)
+    }
+}
+
+/// A keystore key signing for an account other than its own address, after it
+/// replaced the account's key in a rotation.
+pub struct DelegatedSigner {
+    account: String,
+    keypair: Keypair,
+}
+
+impl DelegatedSigner {
+    pub fn new(account: &str, keypair: Keypair) -> Self {
+        Self {
+            account: account.to_string(),
+            keypair,
+        }
+    }
+}
+
+impl Signer for DelegatedSigner {
+    fn public_key(&self) -> String {
+        self.account.clone()
+    }
+
+    fn sign_transaction(&self, _signature_payload: &[u8], hash: &[u8; 32]) -> Result<DecoratedSignature> {
+        self.keypair.sign_decorated(hash)
+    }
+
+    /// Notes are keyed to the account's original key, which no longer signs.
+    fn shared_secret(&self, _public_key: &str) -> Result<Zeroizing<[u8; 32]>> {
+        Err(AppError::ValidationError(
+            "Payment notes can't be sent from an address whose signing key was rotated".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,133 @@
+use crate::errors::{AppError, Result};
+use serde::Deserialize;
+
+/// Largest stellar.toml SEP-1 allows clients to accept.
+const MAX_TOML_BYTES: usize = 100 * 1024;
+
+/// The parts of an issuer's SEP-1 `stellar.toml` the wallet displays.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StellarToml {
+    #[serde(rename = "TRANSFER_SERVER")]
+    pub transfer_server: Option<String>,
+    #[serde(rename = "TRANSFER_SERVER_SEP0024")]
+    pub transfer_server_sep0024: Option<String>,
+    #[serde(rename = "WEB_AUTH_ENDPOINT")]
+    pub web_auth_endpoint: Option<String>,
+    #[serde(rename = "DOCUMENTATION")]
+    pub documentation: Option<OrgDocumentation>,
+    #[serde(rename = "CURRENCIES", default)]
+    pub currencies: Vec<CurrencyInfo>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OrgDocumentation {
+    #[serde(rename = "ORG_NAME")]
+    pub org_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurrencyInfo {
+    pub code: Option<String>,
+    pub issuer: Option<String>,
+    pub name: Option<String>,
+    pub image: Option<String>,
+}
+
+impl StellarToml {
+    pub fn parse(contents: &str) -> Result<Self> {
+        toml::from_str(contents).map_err(|e| AppError::StellarError(format!("Invalid stellar.toml: {}", e)))
+    }
+
+    /// Downloads and parses `https://<domain>/.well-known/stellar.toml`.
+    pub async fn fetch(http: &reqwest::Client, domain: &str) -> Result<Self> {
+        let response = http
+            .get(Self::url(domain))
+            .send()
+            .await
+            .map_err(|e| AppError::StellarError(format!("Failed to fetch stellar.toml from {}: {}", domain, e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::StellarError(format!(
+                "{} returned HTTP {} for stellar.toml",
+                domain,
+                response.status()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| AppError::StellarError(format!("Failed to read stellar.toml from {}: {}", domain, e)))?;
+
+        if body.len() > MAX_TOML_BYTES {
+            return Err(AppError::StellarError(format!("stellar.toml from {} is too large", domain)));
+        }
+
+        Self::parse(&String::from_utf8_lossy(&body))
+    }
+
+    pub fn url(domain: &str) -> String {
+        format!("https://{}/.well-known/stellar.toml", domain.trim_end_matches('/'))
+    }
+
+    /// The `[[CURRENCIES]]` entry for `code` issued by `issuer`, if the file lists it.
+    pub fn currency(&self, code: &str, issuer: &str) -> Option<&CurrencyInfo> {
+        self.currencies
+            .iter()
+            .find(|currency| currency.code.as_deref() == Some(code) && currency.issuer.as_deref() == Some(issuer))
+    }
+
+    pub fn org_name(&self) -> Option<&str> {
+        self.documentation.as_ref().and_then(|doc| doc.org_name.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISSUER: &str = "GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN";
+
+    const SAMPLE: &str = r#"
+        VERSION = "2.0.0"
+        NETWORK_PASSPHRASE = "Public Global Stellar Network ; September 2015"
+        TRANSFER_SERVER_SEP0024 = "https://anchor.example.com/sep24"
+        WEB_AUTH_ENDPOINT = "https://anchor.example.com/auth"
+
+        [DOCUMENTATION]
+        ORG_NAME = "Example Anchor"
+
+        [[CURRENCIES]]
+        code = "USDC"
+        issuer = "GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN"
+        name = "USD Coin"
+        image = "https://example.com/usdc.png"
+        display_decimals = 2
+    "#;
+
+    #[test]
+    fn parses_currencies_and_anchor_endpoints() {
+        let toml = StellarToml::parse(SAMPLE).unwrap();
+
+        assert_eq!(toml.org_name(), Some("Example Anchor"));
+        assert_eq!(toml.transfer_server_sep0024.as_deref(), Some("https://anchor.example.com/sep24"));
+        assert!(toml.transfer_server.is_none());
+
+        let usdc = toml.currency("USDC", ISSUER).unwrap();
+        assert_eq!(usdc.name.as_deref(), Some("USD Coin"));
+        assert_eq!(usdc.image.as_deref(), Some("https://example.com/usdc.png"));
+    }
+
+    #[test]
+    fn currency_requires_matching_issuer() {
+        let toml = StellarToml::parse(SAMPLE).unwrap();
+
+        assert!(toml.currency("USDC", "GBOTHERISSUER").is_none());
+        assert!(toml.currency("EURC", ISSUER).is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_toml() {
+        assert!(StellarToml::parse("CURRENCIES = [").is_err());
+    }
+}
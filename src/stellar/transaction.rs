@@ -0,0 +1,301 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::network::Network;
+use crate::stellar::signer::Signer;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use stellar_xdr::curr::{
+    AccountId, Asset, CreateAccountOp, Hash, Limits, Memo, MuxedAccount, Operation, OperationBody, PaymentOp,
+    Preconditions, SequenceNumber, SetOptionsOp, Signer as AccountSigner, SignerKey, StringM, TimeBounds, TimePoint,
+    Transaction, TransactionEnvelope, TransactionExt, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, TransactionV1Envelope, WriteXdr,
+};
+
+/// Fee per operation, in stroops.
+pub const BASE_FEE: u32 = 100;
+
+/// How long a signed transaction stays valid for submission.
+pub const TRANSACTION_TIMEOUT_SECS: u64 = 300;
+
+pub struct TransactionBuilder {
+    source: MuxedAccount,
+    sequence: i64,
+    memo: Memo,
+    operations: Vec<Operation>,
+}
+
+impl TransactionBuilder {
+    /// `current_sequence` is the account's sequence as reported by Horizon; the
+    /// built transaction uses the next one.
+    pub fn new(source_address: &str, current_sequence: i64) -> Result<Self> {
+        Ok(Self {
+            source: parse_muxed_account(source_address)?,
+            sequence: current_sequence + 1,
+            memo: Memo::None,
+            operations: Vec::new(),
+        })
+    }
+
+    pub fn memo_text(mut self, memo: &str) -> Result<Self> {
+        // `StringM::from_str` unescapes backslashes and skips the length check, so
+        // go through the raw bytes instead.
+        let text = StringM::<28>::try_from(memo.as_bytes().to_vec())
+            .map_err(|_| AppError::ValidationError("Memo must be at most 28 bytes".to_string()))?;
+        self.memo = Memo::Text(text);
+        Ok(self)
+    }
+
+    pub fn payment(mut self, destination: &str, amount_stroops: i64) -> Result<Self> {
+        self.operations.push(Operation {
+            source_account: None,
+            body: OperationBody::Payment(PaymentOp {
+                destination: parse_muxed_account(destination)?,
+                asset: Asset::Native,
+                amount: amount_stroops,
+            }),
+        });
+        Ok(self)
+    }
+
+    /// Funds a brand new account; a plain payment to an address that doesn't exist
+    /// on the ledger yet fails with `op_no_destination`.
+    pub fn create_account(mut self, destination: &str, starting_balance_stroops: i64) -> Result<Self> {
+        let destination = AccountId::from_str(destination)
+            .map_err(|_| AppError::ValidationError(format!("Invalid Stellar address: {}", destination)))?;
+
+        self.operations.push(Operation {
+            source_account: None,
+            body: OperationBody::CreateAccount(CreateAccountOp {
+                destination,
+                starting_balance: starting_balance_stroops,
+            }),
+        });
+        Ok(self)
+    }
+
+    /// Adds `public_key` as a signer on the source account with `weight`, or
+    /// updates its weight if it already is one. A weight of 0 removes it.
+    pub fn set_signer(mut self, public_key: &str, weight: u8) -> Result<Self> {
+        let key = match SignerKey::from_str(public_key) {
+            Ok(key @ SignerKey::Ed25519(_)) => key,
+            _ => return Err(AppError::ValidationError(format!("Invalid Stellar address: {}", public_key))),
+        };
+
+        self.operations.push(Operation {
+            source_account: None,
+            body: OperationBody::SetOptions(SetOptionsOp {
+                signer: Some(AccountSigner {
+                    key,
+                    weight: weight.into(),
+                }),
+                ..SetOptionsOp::default()
+            }),
+        });
+        Ok(self)
+    }
+
+    /// Sets the weight of the source account's own key. 0 means it can no
+    /// longer sign for the account.
+    pub fn master_weight(mut self, weight: u8) -> Self {
+        self.operations.push(Operation {
+            source_account: None,
+            body: OperationBody::SetOptions(SetOptionsOp {
+                master_weight: Some(weight.into()),
+                ..SetOptionsOp::default()
+            }),
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<Transaction> {
+        if self.operations.is_empty() {
+            return Err(AppError::StellarError("Transaction has no operations".to_string()));
+        }
+
+        let fee = BASE_FEE * self.operations.len() as u32;
+        let operations = self
+            .operations
+            .try_into()
+            .map_err(|_| AppError::StellarError("Too many operations in one transaction".to_string()))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| AppError::InternalError(format!("System clock error: {}", e)))?
+            .as_secs();
+
+        Ok(Transaction {
+            source_account: self.source,
+            fee,
+            seq_num: SequenceNumber(self.sequence),
+            cond: Preconditions::Time(TimeBounds {
+                min_time: TimePoint(0),
+                max_time: TimePoint(now + TRANSACTION_TIMEOUT_SECS),
+            }),
+            memo: self.memo,
+            operations,
+            ext: TransactionExt::V0,
+        })
+    }
+}
+
+/// A signed transaction ready for submission.
+pub struct SignedTransaction {
+    pub hash: String,
+    pub envelope_xdr: String,
+}
+
+pub fn sign_transaction(transaction: Transaction, signer: &dyn Signer, network: &Network) -> Result<SignedTransaction> {
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(network.network_id()),
+        tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(transaction.clone()),
+    }
+    .to_xdr(Limits::none())
+    .map_err(|e| AppError::StellarError(format!("Failed to encode transaction: {}", e)))?;
+
+    let hash: [u8; 32] = Sha256::digest(&payload).into();
+
+    let signature = signer.sign_transaction(&payload, &hash)?;
+    let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx: transaction,
+        signatures: vec![signature]
+            .try_into()
+            .map_err(|_| AppError::StellarError("Too many signatures".to_string()))?,
+    });
+
+    let envelope_xdr = envelope
+        .to_xdr_base64(Limits::none())
+        .map_err(|e| AppError::StellarError(format!("Failed to encode transaction: {}", e)))?;
+
+    Ok(SignedTransaction {
+        hash: hex::encode(hash),
+        envelope_xdr,
+    })
+}
+
+fn parse_muxed_account(address: &str) -> Result<MuxedAccount> {
+    MuxedAccount::from_str(address)
+        .map_err(|_| AppError::ValidationError(format!("Invalid Stellar address: {}", address)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stellar::keypair::Keypair;
+    use ed25519_dalek::{Signature as DalekSignature, Verifier, VerifyingKey};
+    use stellar_xdr::curr::{ReadXdr, TransactionEnvelope};
+
+    fn builder(source: &Keypair) -> TransactionBuilder {
+        TransactionBuilder::new(&source.public_key(), 41).unwrap()
+    }
+
+    #[test]
+    fn build_requires_an_operation() {
+        let source = Keypair::random();
+        assert!(builder(&source).build().is_err());
+    }
+
+    #[test]
+    fn uses_next_sequence_and_fee_per_operation() {
+        let source = Keypair::random();
+        let destination = Keypair::random().public_key();
+
+        let tx = builder(&source)
+            .payment(&destination, 1)
+            .unwrap()
+            .payment(&destination, 2)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(tx.seq_num, SequenceNumber(42));
+        assert_eq!(tx.fee, 2 * BASE_FEE);
+    }
+
+    #[test]
+    fn create_account_uses_create_account_operation() {
+        let source = Keypair::random();
+        let tx = builder(&source)
+            .create_account(&Keypair::random().public_key(), 10_000_000)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(matches!(tx.operations[0].body, OperationBody::CreateAccount(_)));
+    }
+
+    #[test]
+    fn signer_changes_use_set_options() {
+        let source = Keypair::random();
+        let tx = builder(&source)
+            .set_signer(&Keypair::random().public_key(), 1)
+            .unwrap()
+            .master_weight(0)
+            .build()
+            .unwrap();
+
+        let OperationBody::SetOptions(added) = &tx.operations[0].body else {
+            panic!("expected set_options");
+        };
+        assert_eq!(added.signer.as_ref().map(|signer| signer.weight), Some(1));
+        assert_eq!(added.master_weight, None);
+
+        let OperationBody::SetOptions(master) = &tx.operations[1].body else {
+            panic!("expected set_options");
+        };
+        assert_eq!((master.master_weight, master.signer.is_none()), (Some(0), true));
+
+        assert!(builder(&source).set_signer("GNOTANADDRESS", 1).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_destination_and_long_memo() {
+        let source = Keypair::random();
+        assert!(builder(&source).payment("GNOTANADDRESS", 1).is_err());
+        assert!(builder(&source).memo_text(&"m".repeat(29)).is_err());
+    }
+
+    #[test]
+    fn memo_text_keeps_backslashes_verbatim() {
+        let source = Keypair::random();
+        let tx = builder(&source)
+            .memo_text("a\\x41")
+            .unwrap()
+            .payment(&Keypair::random().public_key(), 1)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let Memo::Text(text) = tx.memo else {
+            panic!("expected a text memo");
+        };
+        assert_eq!(text.as_slice(), b"a\\x41");
+    }
+
+    #[test]
+    fn signature_verifies_against_transaction_hash() {
+        let source = Keypair::random();
+        let network = Network::testnet();
+        let tx = builder(&source)
+            .payment(&Keypair::random().public_key(), 5)
+            .unwrap()
+            .build()
+            .unwrap();
+        let hash = tx.hash(network.network_id()).unwrap();
+
+        let signed = sign_transaction(tx, &source, &network).unwrap();
+        assert_eq!(signed.hash, hex::encode(hash));
+
+        let TransactionEnvelope::Tx(envelope) =
+            TransactionEnvelope::from_xdr_base64(&signed.envelope_xdr, Limits::none()).unwrap()
+        else {
+            panic!("expected a v1 envelope");
+        };
+
+        let public_key = stellar_strkey::ed25519::PublicKey::from_string(&source.public_key()).unwrap().0;
+        let decorated = &envelope.signatures[0];
+        assert_eq!(decorated.hint.0, public_key[28..]);
+
+        let signature = DalekSignature::from_slice(&decorated.signature.0).unwrap();
+        VerifyingKey::from_bytes(&public_key).unwrap().verify(&hash, &signature).unwrap();
+    }
+}
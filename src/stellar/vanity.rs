@@ -0,0 +1,199 @@
+use crate::errors::{AppError, Result};
+use crate::stellar::keypair::Keypair;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Every extra character multiplies the expected work by 32, so anything longer
+/// than this would keep a laptop busy for hours.
+pub const MAX_SUFFIX_LEN: usize = 5;
+
+const BASE32_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+const ATTEMPT_BATCH: u64 = 256;
+
+pub struct VanityProgress {
+    pub attempts: u64,
+    pub expected_attempts: u64,
+    pub elapsed: Duration,
+}
+
+impl VanityProgress {
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.attempts as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated time until the expected number of attempts is reached. The search
+    /// is random, so this is a rough guide rather than a deadline.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate();
+        if rate <= 0.0 {
+            return None;
+        }
+
+        let remaining = self.expected_attempts.saturating_sub(self.attempts);
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
+}
+
+pub struct VanityGenerator {
+    suffix: String,
+    threads: usize,
+}
+
+impl VanityGenerator {
+    pub fn new(suffix: &str) -> Result<Self> {
+        let suffix = suffix.trim().to_uppercase();
+
+        if suffix.is_empty() {
+            return Err(AppError::ValidationError("Suffix cannot be empty".to_string()));
+        }
+
+        if suffix.len() > MAX_SUFFIX_LEN {
+            return Err(AppError::ValidationError(format!(
+                "Suffix can be at most {} characters long",
+                MAX_SUFFIX_LEN
+            )));
+        }
+
+        if !suffix.chars().all(|c| BASE32_ALPHABET.contains(c)) {
+            return Err(AppError::ValidationError(
+                "Suffix can only contain letters A-Z and digits 2-7".to_string(),
+            ));
+        }
+
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+        Ok(Self { suffix, threads })
+    }
+
+    pub fn suffix(&self) -> &str {
+        &self.suffix
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn expected_attempts(&self) -> u64 {
+        32u64.pow(self.suffix.len() as u32)
+    }
+
+    /// Generates keypairs on every core until one's address ends with the suffix,
+    /// calling `on_progress` periodically from the current thread.
+    pub fn search<F: FnMut(&VanityProgress)>(&self, mut on_progress: F) -> Result<Keypair> {
+        let found = AtomicBool::new(false);
+        let attempts = AtomicU64::new(0);
+        let started = Instant::now();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let sender = sender.clone();
+                let found = &found;
+                let attempts = &attempts;
+                let suffix = self.suffix.as_str();
+
+                scope.spawn(move || {
+                    let mut local_attempts = 0;
+
+                    while !found.load(Ordering::Relaxed) {
+                        let keypair = Keypair::random();
+                        local_attempts += 1;
+
+                        if keypair.public_key().ends_with(suffix) {
+                            found.store(true, Ordering::Relaxed);
+                            let _ = sender.send(keypair);
+                            break;
+                        }
+
+                        if local_attempts == ATTEMPT_BATCH {
+                            attempts.fetch_add(local_attempts, Ordering::Relaxed);
+                            local_attempts = 0;
+                        }
+                    }
+
+                    attempts.fetch_add(local_attempts, Ordering::Relaxed);
+                });
+            }
+            drop(sender);
+
+            loop {
+                match receiver.recv_timeout(PROGRESS_INTERVAL) {
+                    Ok(keypair) => {
+                        found.store(true, Ordering::Relaxed);
+                        return Ok(keypair);
+                    }
+                    Err(RecvTimeoutError::Timeout) => on_progress(&VanityProgress {
+                        attempts: attempts.load(Ordering::Relaxed),
+                        expected_attempts: self.expected_attempts(),
+                        elapsed: started.elapsed(),
+                    }),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        found.store(true, Ordering::Relaxed);
+                        return Err(AppError::InternalError("Vanity search workers stopped unexpectedly".to_string()));
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_empty_suffix() {
+        assert!(VanityGenerator::new("").is_err());
+        assert!(VanityGenerator::new("   ").is_err());
+    }
+
+    #[test]
+    fn rejects_suffix_longer_than_cap() {
+        assert!(VanityGenerator::new(&"A".repeat(MAX_SUFFIX_LEN + 1)).is_err());
+        assert!(VanityGenerator::new(&"A".repeat(MAX_SUFFIX_LEN)).is_ok());
+    }
+
+    #[test]
+    fn rejects_non_base32_characters() {
+        for suffix in ["A1", "A8", "A0", "A-", "É"] {
+            assert!(VanityGenerator::new(suffix).is_err(), "{} should be rejected", suffix);
+        }
+    }
+
+    #[test]
+    fn normalizes_suffix_to_uppercase() {
+        let generator = VanityGenerator::new(" ab7 ").unwrap();
+        assert_eq!(generator.suffix(), "AB7");
+    }
+
+    #[test]
+    fn expected_attempts_grows_by_32_per_character() {
+        assert_eq!(VanityGenerator::new("A").unwrap().expected_attempts(), 32);
+        assert_eq!(VanityGenerator::new("AB").unwrap().expected_attempts(), 1024);
+    }
+
+    #[test]
+    fn one_character_search_finds_matching_address() {
+        let generator = VanityGenerator::new("Q").unwrap();
+        let keypair = generator.search(|_| {}).unwrap();
+        assert!(keypair.public_key().ends_with('Q'));
+    }
+
+    #[test]
+    fn eta_is_unknown_before_any_progress() {
+        let progress = VanityProgress {
+            attempts: 0,
+            expected_attempts: 32,
+            elapsed: Duration::ZERO,
+        };
+        assert!(progress.eta().is_none());
+    }
+}
@@ -1,14 +1,25 @@
+use crate::config;
 use crate::errors::{AppError, Result};
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use argon2::password_hash::SaltString;
-use rand_core::OsRng;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
 
 pub struct PasswordManager;
 
 impl PasswordManager {
+    /// Hashes with Argon2id and the configured cost, see [`password_params`].
     pub fn hash_password(password: &str) -> Result<String> {
         let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, password_params()?);
         
         let password_hash = argon2
             .hash_password(password.as_bytes(), &salt)
@@ -17,6 +28,8 @@ impl PasswordManager {
         Ok(password_hash.to_string())
     }
     
+    /// Checks `password` using the algorithm and cost stored in `hash`, so
+    /// hashes made with older settings keep working.
     pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
         let parsed_hash = PasswordHash::new(hash)
             .map_err(|e| AppError::InternalError(format!("Invalid password hash: {}", e)))?;
@@ -28,4 +41,541 @@ impl PasswordManager {
             Err(_) => Ok(false),
         }
     }
+
+    /// Runs a verification against a throwaway hash with the configured cost
+    /// and always returns `false`. Lets a login for an unknown user take as
+    /// long as one with a wrong password. The hash is made on first use.
+    pub fn verify_dummy(password: &str) -> Result<bool> {
+        static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+        let hash = match DUMMY_HASH.get() {
+            Some(hash) => hash,
+            None => {
+                let hash = Self::hash_password(&RecoveryCode::generate(4))?;
+                DUMMY_HASH.get_or_init(|| hash)
+            }
+        };
+        Self::verify_password(password, hash).map(|_| false)
+    }
+
+    /// Whether `hash` was made with another algorithm or cost than new hashes
+    /// get, so it should be replaced the next time the password is known.
+    pub fn needs_rehash(hash: &str) -> Result<bool> {
+        let parsed_hash = PasswordHash::new(hash)
+            .map_err(|e| AppError::InternalError(format!("Invalid password hash: {}", e)))?;
+        let wanted = password_params()?;
+
+        let current = Params::try_from(&parsed_hash).ok();
+        Ok(parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+            || current.is_none_or(|current| {
+                (current.m_cost(), current.t_cost(), current.p_cost()) != (wanted.m_cost(), wanted.t_cost(), wanted.p_cost())
+            }))
+    }
+}
+
+/// Argon2 cost for new password hashes: `PASSWORD_HASH_MEMORY_KIB`,
+/// `PASSWORD_HASH_ITERATIONS` and `PASSWORD_HASH_PARALLELISM`. Unset ones
+/// keep the argon2 crate's defaults of 19 MiB, 2 iterations and 1 lane.
+fn password_params() -> Result<Params> {
+    let setting = |name: &str, default: u32| -> Result<u32> {
+        config::var(name).map_or(Ok(default), |value| {
+            value
+                .parse::<u32>()
+                .map_err(|_| AppError::ValidationError(format!("{} must be a positive number, not '{}'", name, value)))
+        })
+    };
+
+    Params::new(
+        setting("PASSWORD_HASH_MEMORY_KIB", Params::DEFAULT_M_COST)?,
+        setting("PASSWORD_HASH_ITERATIONS", Params::DEFAULT_T_COST)?,
+        setting("PASSWORD_HASH_PARALLELISM", Params::DEFAULT_P_COST)?,
+        None,
+    )
+    .map_err(|e| AppError::ValidationError(format!("Invalid password hashing settings: {}", e)))
+}
+
+/// A secret encrypted with a key derived from the user's password. All fields are
+/// hex encoded so they can be stored as plain TEXT columns.
+pub struct EncryptedSecret {
+    pub ciphertext: String,
+    pub salt: String,
+    pub nonce: String,
+}
+
+pub struct SecretCipher;
+
+impl SecretCipher {
+    pub fn encrypt(plaintext: &[u8], password: &str) -> Result<EncryptedSecret> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let cipher = Self::cipher_for(password, &salt)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| AppError::InternalError(format!("Secret encryption failed: {}", e)))?;
+
+        Ok(EncryptedSecret {
+            ciphertext: hex::encode(ciphertext),
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+        })
+    }
+
+    /// Fails with an authentication error if the password is wrong or the stored
+    /// data has been tampered with.
+    pub fn decrypt(encrypted: &EncryptedSecret, password: &str) -> Result<Zeroizing<Vec<u8>>> {
+        let ciphertext = Self::decode_hex(&encrypted.ciphertext)?;
+        let salt = Self::decode_hex(&encrypted.salt)?;
+        let nonce = Self::decode_hex(&encrypted.nonce)?;
+
+        if nonce.len() != 12 {
+            return Err(AppError::InternalError("Invalid nonce length".to_string()));
+        }
+
+        let cipher = Self::cipher_for(password, &salt)?;
+
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map(Zeroizing::new)
+            .map_err(|_| AppError::AuthenticationError("Unable to decrypt secret: wrong password".to_string()))
+    }
+
+    fn decode_hex(value: &str) -> Result<Vec<u8>> {
+        hex::decode(value).map_err(|e| AppError::InternalError(format!("Invalid encrypted data: {}", e)))
+    }
+
+    fn cipher_for(password: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(password.as_bytes(), salt, key.as_mut())
+            .map_err(|e| AppError::InternalError(format!("Key derivation failed: {}", e)))?;
+
+        Ok(ChaCha20Poly1305::new(key.as_ref().into()))
+    }
+}
+
+/// Master keys ("key-encryption keys") for envelope encryption, read from
+/// `CUSTOMER_DATA_KEYS` as comma-separated `id:hex` pairs. The first key is
+/// used for new data; the rest stay available to open and rewrap older data.
+pub struct KeyRing {
+    active_id: String,
+    keys: HashMap<String, Zeroizing<[u8; 32]>>,
+}
+
+impl KeyRing {
+    /// `None` when `CUSTOMER_DATA_KEYS` isn't set.
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var("CUSTOMER_DATA_KEYS") {
+            Ok(spec) if !spec.trim().is_empty() => Self::parse(&spec).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut active_id = None;
+        let mut keys = HashMap::new();
+
+        for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (id, key_hex) = pair
+                .split_once(':')
+                .ok_or_else(|| AppError::ValidationError(format!("Expected id:hex key, got '{}'", pair)))?;
+
+            let key: [u8; 32] = hex::decode(key_hex.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| AppError::ValidationError(format!("Key '{}' must be 64 hex characters", id)))?;
+
+            if keys.insert(id.trim().to_string(), Zeroizing::new(key)).is_some() {
+                return Err(AppError::ValidationError(format!("Key '{}' is listed twice", id)));
+            }
+            active_id.get_or_insert_with(|| id.trim().to_string());
+        }
+
+        let active_id = active_id.ok_or_else(|| AppError::ValidationError("No data keys configured".to_string()))?;
+        Ok(Self { active_id, keys })
+    }
+
+    pub fn active_id(&self) -> &str {
+        &self.active_id
+    }
+
+    fn cipher(&self, id: &str) -> Result<ChaCha20Poly1305> {
+        let key = self
+            .keys
+            .get(id)
+            .ok_or_else(|| AppError::InternalError(format!("Data key '{}' is not configured", id)))?;
+
+        Ok(ChaCha20Poly1305::new((&**key).into()))
+    }
+}
+
+/// A value encrypted under its own random data key, with that data key in turn
+/// encrypted ("wrapped") by a master key from the `KeyRing`. Hex encoded for TEXT columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub ciphertext: String,
+    pub nonce: String,
+    pub wrapped_key: String,
+    pub key_nonce: String,
+    pub key_id: String,
+}
+
+pub struct EnvelopeCipher;
+
+impl EnvelopeCipher {
+    /// Encrypts `plaintext` under a fresh data key. `context` is bound to the
+    /// ciphertext, so an envelope copied to another record won't open.
+    pub fn seal(plaintext: &[u8], context: &[u8], keys: &KeyRing) -> Result<Envelope> {
+        let data_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = ChaCha20Poly1305::new(&data_key)
+            .encrypt(&nonce, Payload { msg: plaintext, aad: context })
+            .map_err(|e| AppError::InternalError(format!("Field encryption failed: {}", e)))?;
+
+        let (wrapped_key, key_nonce) = Self::wrap(data_key.as_slice(), keys)?;
+
+        Ok(Envelope {
+            ciphertext: hex::encode(ciphertext),
+            nonce: hex::encode(nonce),
+            wrapped_key,
+            key_nonce,
+            key_id: keys.active_id().to_string(),
+        })
+    }
+
+    pub fn open(envelope: &Envelope, context: &[u8], keys: &KeyRing) -> Result<Zeroizing<Vec<u8>>> {
+        let data_key = Self::unwrap(envelope, keys)?;
+        let nonce = Self::nonce(&envelope.nonce)?;
+
+        ChaCha20Poly1305::new(data_key.as_slice().into())
+            .decrypt(&nonce, Payload { msg: &SecretCipher::decode_hex(&envelope.ciphertext)?, aad: context })
+            .map(Zeroizing::new)
+            .map_err(|_| AppError::AuthenticationError("Unable to decrypt field: data has been tampered with".to_string()))
+    }
+
+    /// Re-encrypts the data key under the active master key. The field itself is
+    /// untouched, so rotating a master key never needs the plaintext.
+    pub fn rewrap(envelope: &Envelope, keys: &KeyRing) -> Result<Envelope> {
+        let data_key = Self::unwrap(envelope, keys)?;
+        let (wrapped_key, key_nonce) = Self::wrap(&data_key, keys)?;
+
+        Ok(Envelope {
+            wrapped_key,
+            key_nonce,
+            key_id: keys.active_id().to_string(),
+            ..envelope.clone()
+        })
+    }
+
+    fn wrap(data_key: &[u8], keys: &KeyRing) -> Result<(String, String)> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let wrapped = keys
+            .cipher(keys.active_id())?
+            .encrypt(&nonce, data_key)
+            .map_err(|e| AppError::InternalError(format!("Data key wrapping failed: {}", e)))?;
+
+        Ok((hex::encode(wrapped), hex::encode(nonce)))
+    }
+
+    fn unwrap(envelope: &Envelope, keys: &KeyRing) -> Result<Zeroizing<Vec<u8>>> {
+        let nonce = Self::nonce(&envelope.key_nonce)?;
+
+        keys.cipher(&envelope.key_id)?
+            .decrypt(&nonce, SecretCipher::decode_hex(&envelope.wrapped_key)?.as_slice())
+            .map(Zeroizing::new)
+            .map_err(|_| AppError::AuthenticationError(format!("Unable to unwrap data key with '{}'", envelope.key_id)))
+    }
+
+    fn nonce(value: &str) -> Result<Nonce> {
+        let nonce = SecretCipher::decode_hex(value)?;
+        if nonce.len() != 12 {
+            return Err(AppError::InternalError("Invalid nonce length".to_string()));
+        }
+        Ok(*Nonce::from_slice(&nonce))
+    }
+}
+
+/// A payment note encrypted under a key shared by its sender and recipient.
+/// Hex encoded for TEXT columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedNote {
+    pub ciphertext: String,
+    pub nonce: String,
+}
+
+/// Encrypts notes between two Stellar accounts using their X25519 shared secret,
+/// so only the holders of either secret key can read them.
+pub struct NoteCipher;
+
+impl NoteCipher {
+    /// `context` (the payment and both addresses) is bound to the ciphertext, so
+    /// a note can't be moved onto another payment.
+    pub fn seal(plaintext: &[u8], shared_secret: &[u8; 32], context: &[u8]) -> Result<EncryptedNote> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = Self::cipher(shared_secret)?
+            .encrypt(&nonce, Payload { msg: plaintext, aad: context })
+            .map_err(|e| AppError::InternalError(format!("Note encryption failed: {}", e)))?;
+
+        Ok(EncryptedNote {
+            ciphertext: hex::encode(ciphertext),
+            nonce: hex::encode(nonce),
+        })
+    }
+
+    pub fn open(note: &EncryptedNote, shared_secret: &[u8; 32], context: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let nonce = EnvelopeCipher::nonce(&note.nonce)?;
+
+        Self::cipher(shared_secret)?
+            .decrypt(&nonce, Payload { msg: &SecretCipher::decode_hex(&note.ciphertext)?, aad: context })
+            .map(Zeroizing::new)
+            .map_err(|_| AppError::AuthenticationError("Unable to decrypt note: wrong key or tampered data".to_string()))
+    }
+
+    /// The raw X25519 output isn't uniformly random, so hash it into a key.
+    fn cipher(shared_secret: &[u8; 32]) -> Result<ChaCha20Poly1305> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(shared_secret)
+            .map_err(|e| AppError::InternalError(format!("Key derivation failed: {}", e)))?;
+        mac.update(b"stellar-wallet payment note v1");
+
+        Ok(ChaCha20Poly1305::new(&mac.finalize().into_bytes()))
+    }
+}
+
+/// A secret sealed with a passphrase for storage outside the wallet, e.g. a
+/// backup file. The Argon2id cost is stored with it so files stay readable
+/// when the defaults change. Hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedSecret {
+    pub kdf: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub salt: String,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+pub struct PassphraseCipher;
+
+impl PassphraseCipher {
+    const KDF: &'static str = "argon2id";
+    const CIPHER: &'static str = "chacha20-poly1305";
+    /// Files may be attacked offline for as long as they exist, so this costs
+    /// more than the login hash: 64 MiB and 3 passes.
+    const MEMORY_KIB: u32 = 64 * 1024;
+    const ITERATIONS: u32 = 3;
+    /// Refuse files that would make us allocate more than 1 GiB.
+    const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+
+    /// `context` is bound to the ciphertext, so the surrounding metadata can't
+    /// be swapped without the file failing to open.
+    pub fn seal(plaintext: &[u8], passphrase: &str, context: &[u8]) -> Result<SealedSecret> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = Self::cipher(passphrase, &salt, Self::MEMORY_KIB, Self::ITERATIONS, 1)?
+            .encrypt(&nonce, Payload { msg: plaintext, aad: context })
+            .map_err(|e| AppError::InternalError(format!("Secret encryption failed: {}", e)))?;
+
+        Ok(SealedSecret {
+            kdf: Self::KDF.to_string(),
+            memory_kib: Self::MEMORY_KIB,
+            iterations: Self::ITERATIONS,
+            parallelism: 1,
+            salt: hex::encode(salt),
+            cipher: Self::CIPHER.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    pub fn open(sealed: &SealedSecret, passphrase: &str, context: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        if sealed.kdf != Self::KDF || sealed.cipher != Self::CIPHER {
+            return Err(AppError::ValidationError(format!("Unsupported encryption {} / {}", sealed.kdf, sealed.cipher)));
+        }
+        if sealed.memory_kib > Self::MAX_MEMORY_KIB {
+            return Err(AppError::ValidationError("Key derivation settings are too expensive".to_string()));
+        }
+
+        let nonce = EnvelopeCipher::nonce(&sealed.nonce)?;
+        let salt = SecretCipher::decode_hex(&sealed.salt)?;
+
+        Self::cipher(passphrase, &salt, sealed.memory_kib, sealed.iterations, sealed.parallelism)?
+            .decrypt(&nonce, Payload { msg: &SecretCipher::decode_hex(&sealed.ciphertext)?, aad: context })
+            .map(Zeroizing::new)
+            .map_err(|_| AppError::AuthenticationError("Unable to decrypt: wrong passphrase or tampered file".to_string()))
+    }
+
+    fn cipher(passphrase: &str, salt: &[u8], memory_kib: u32, iterations: u32, parallelism: u32) -> Result<ChaCha20Poly1305> {
+        let params = Params::new(memory_kib, iterations, parallelism, Some(32))
+            .map_err(|e| AppError::ValidationError(format!("Invalid key derivation settings: {}", e)))?;
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|e| AppError::InternalError(format!("Key derivation failed: {}", e)))?;
+
+        Ok(ChaCha20Poly1305::new(key.as_ref().into()))
+    }
+}
+
+/// One-time recovery codes, in groups of five characters for writing down.
+pub struct RecoveryCode;
+
+impl RecoveryCode {
+    /// Avoids characters that are easy to misread (0/o, 1/l/i).
+    const ALPHABET: &'static [u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+    /// `groups` random groups of five, shown as `xxxxx-xxxxx-…`.
+    pub fn generate(groups: usize) -> String {
+        (0..groups)
+            .map(|_| {
+                (0..5)
+                    .map(|_| Self::ALPHABET[(OsRng.next_u32() as usize) % Self::ALPHABET.len()] as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// The code as typed, ignoring case, spaces and dashes.
+    pub fn normalize(code: &str) -> String {
+        code.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_password_hashes_verify_and_need_rehashing() {
+        let current = PasswordManager::hash_password("Passw0rd!").unwrap();
+        assert!(current.starts_with("$argon2id$v=19$"));
+        assert!(!PasswordManager::needs_rehash(&current).unwrap());
+
+        let salt = SaltString::generate(&mut OsRng);
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default());
+        let weaker = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(8 * 1024, 1, 1, None).unwrap());
+        for legacy in [argon2i, weaker] {
+            let hash = legacy.hash_password(b"Passw0rd!", &salt).unwrap().to_string();
+            assert!(PasswordManager::verify_password("Passw0rd!", &hash).unwrap());
+            assert!(!PasswordManager::verify_password("Wr0ngPass!", &hash).unwrap());
+            assert!(PasswordManager::needs_rehash(&hash).unwrap());
+        }
+    }
+
+    #[test]
+    fn encrypt_does_not_leak_plaintext() {
+        let plaintext = [7u8; 32];
+        let encrypted = SecretCipher::encrypt(&plaintext, "Passw0rd!").unwrap();
+
+        assert_ne!(encrypted.ciphertext, hex::encode(plaintext));
+        // 32 bytes of plaintext plus the 16-byte Poly1305 tag.
+        assert_eq!(encrypted.ciphertext.len(), 2 * (32 + 16));
+        assert_eq!(encrypted.salt.len(), 32);
+        assert_eq!(encrypted.nonce.len(), 24);
+    }
+
+    #[test]
+    fn encrypt_uses_fresh_salt_and_nonce() {
+        let first = SecretCipher::encrypt(b"secret", "Passw0rd!").unwrap();
+        let second = SecretCipher::encrypt(b"secret", "Passw0rd!").unwrap();
+
+        assert_ne!(first.salt, second.salt);
+        assert_ne!(first.nonce, second.nonce);
+        assert_ne!(first.ciphertext, second.ciphertext);
+    }
+
+    #[test]
+    fn decrypt_round_trips_with_correct_password() {
+        let encrypted = SecretCipher::encrypt(b"stellar seed", "Passw0rd!").unwrap();
+        let decrypted = SecretCipher::decrypt(&encrypted, "Passw0rd!").unwrap();
+
+        assert_eq!(*decrypted, b"stellar seed");
+    }
+
+    #[test]
+    fn decrypt_fails_with_wrong_password() {
+        let encrypted = SecretCipher::encrypt(b"stellar seed", "Passw0rd!").unwrap();
+
+        assert!(matches!(
+            SecretCipher::decrypt(&encrypted, "Wr0ngPass!"),
+            Err(AppError::AuthenticationError(_))
+        ));
+    }
+
+    fn ring(spec: &str) -> KeyRing {
+        KeyRing::parse(spec).unwrap()
+    }
+
+    const OLD_KEY: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+    const NEW_KEY: &str = "0202020202020202020202020202020202020202020202020202020202020202";
+
+    #[test]
+    fn key_ring_uses_first_key_for_new_data() {
+        let keys = ring(&format!("v2:{}, v1:{}", NEW_KEY, OLD_KEY));
+        assert_eq!(keys.active_id(), "v2");
+
+        assert!(KeyRing::parse("v1:abcd").is_err());
+        assert!(KeyRing::parse("no-separator").is_err());
+        assert!(KeyRing::parse(&format!("v1:{},v1:{}", OLD_KEY, NEW_KEY)).is_err());
+        assert!(KeyRing::parse("").is_err());
+    }
+
+    #[test]
+    fn envelopes_round_trip_and_bind_their_context() {
+        let keys = ring(&format!("v1:{}", OLD_KEY));
+        let envelope = EnvelopeCipher::seal(b"Ada", b"user-1/first_name", &keys).unwrap();
+
+        assert_eq!(envelope.key_id, "v1");
+        assert_eq!(*EnvelopeCipher::open(&envelope, b"user-1/first_name", &keys).unwrap(), b"Ada");
+        assert!(EnvelopeCipher::open(&envelope, b"user-2/first_name", &keys).is_err());
+    }
+
+    #[test]
+    fn every_value_gets_its_own_data_key() {
+        let keys = ring(&format!("v1:{}", OLD_KEY));
+        let first = EnvelopeCipher::seal(b"same", b"ctx", &keys).unwrap();
+        let second = EnvelopeCipher::seal(b"same", b"ctx", &keys).unwrap();
+
+        assert_ne!(first.wrapped_key, second.wrapped_key);
+        assert_ne!(first.ciphertext, second.ciphertext);
+    }
+
+    #[test]
+    fn rewrap_moves_data_to_the_active_key() {
+        let old = ring(&format!("v1:{}", OLD_KEY));
+        let envelope = EnvelopeCipher::seal(b"1990-01-01", b"ctx", &old).unwrap();
+
+        let rotated = ring(&format!("v2:{},v1:{}", NEW_KEY, OLD_KEY));
+        let rewrapped = EnvelopeCipher::rewrap(&envelope, &rotated).unwrap();
+        assert_eq!(rewrapped.key_id, "v2");
+        assert_eq!(rewrapped.ciphertext, envelope.ciphertext);
+
+        // Once rewrapped, the old key can be retired.
+        let new_only = ring(&format!("v2:{}", NEW_KEY));
+        assert_eq!(*EnvelopeCipher::open(&rewrapped, b"ctx", &new_only).unwrap(), b"1990-01-01");
+        assert!(EnvelopeCipher::open(&envelope, b"ctx", &new_only).is_err());
+    }
+
+    #[test]
+    fn notes_open_with_the_shared_secret_and_context_only() {
+        let secret = [9u8; 32];
+        let note = NoteCipher::seal(b"thanks for lunch", &secret, b"tx-1").unwrap();
+
+        assert_eq!(*NoteCipher::open(&note, &secret, b"tx-1").unwrap(), b"thanks for lunch");
+        assert!(NoteCipher::open(&note, &secret, b"tx-2").is_err());
+        assert!(NoteCipher::open(&note, &[8u8; 32], b"tx-1").is_err());
+    }
 }
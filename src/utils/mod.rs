@@ -1,2 +1,6 @@
 pub mod crypto;
-pub mod validation;
\ No newline at end of file
+pub mod password_strength;
+pub mod qr;
+pub mod request_id;
+pub mod validation;
+pub mod webauthn;
\ No newline at end of file
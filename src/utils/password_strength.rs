@@ -0,0 +1,310 @@
+//! Estimates how many guesses a password would take, zxcvbn-style: the
+//! password is split into the cheapest mix of guessable patterns (common
+//! words, sequences, keyboard rows, repeats, years) and leftover characters,
+//! and the guesses for each part are added up in bits.
+
+/// Below this score a password is rejected.
+pub const MIN_PASSWORD_SCORE: u8 = 2;
+
+/// Bits per character not covered by a pattern; zxcvbn's brute-force guess of
+/// ten candidates per character.
+const BRUTEFORCE_BITS: f64 = std::f64::consts::LOG2_10;
+
+/// Guess counts (in bits) of 10³, 10⁶, 10⁸ and 10¹⁰, the upper bounds of scores 0–3.
+const SCORE_THRESHOLDS: [f64; 4] = [9.97, 19.93, 26.58, 33.22];
+
+/// Most-used passwords and words in them, most common first.
+const COMMON_WORDS: &[&str] = &[
+    "password", "qwerty", "letmein", "welcome", "admin", "login", "iloveyou", "monkey", "dragon",
+    "football", "baseball", "master", "sunshine", "shadow", "princess", "superman", "batman", "trustno", "starwars",
+    "hello", "freedom", "whatever", "charlie", "michael", "jennifer", "jordan", "hunter", "ranger", "soccer",
+    "hockey", "killer", "george", "andrew", "pepper", "summer", "winter", "spring", "autumn", "secret",
+    "love", "money", "stellar", "wallet", "lumens", "bitcoin", "crypto", "test", "changeme", "computer",
+    "internet", "google", "apple", "orange", "banana", "cookie", "chocolate", "flower", "purple", "pass",
+];
+
+const KEYBOARD_ROWS: &[&str] = &["qwertyuiop", "asdfghjkl", "zxcvbnm", "1234567890"];
+
+/// The kinds of guessable pattern the estimate looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pattern {
+    Word { capitalized: bool, substituted: bool },
+    Sequence,
+    Keyboard,
+    Repeat,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Match {
+    start: usize,
+    end: usize,
+    bits: f64,
+    pattern: Pattern,
+}
+
+/// How guessable a password is, with what to do about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordStrength {
+    /// 0 (guessed almost at once) to 4 (very unlikely to be guessed).
+    pub score: u8,
+    pub warning: Option<&'static str>,
+    pub suggestions: Vec<&'static str>,
+}
+
+impl PasswordStrength {
+    pub fn label(&self) -> &'static str {
+        match self.score {
+            0 => "very weak",
+            1 => "weak",
+            2 => "fair",
+            3 => "strong",
+            _ => "very strong",
+        }
+    }
+}
+
+pub fn estimate(password: &str) -> PasswordStrength {
+    let chars: Vec<char> = password.chars().collect();
+    let matches = find_matches(&chars);
+
+    // best[i]: fewest bits to guess the first i characters, and the pattern used last.
+    let mut best: Vec<(f64, Option<Match>)> = vec![(0.0, None); chars.len() + 1];
+    for end in 1..=chars.len() {
+        best[end] = (best[end - 1].0 + BRUTEFORCE_BITS, None);
+        for candidate in matches.iter().filter(|candidate| candidate.end == end) {
+            let bits = best[candidate.start].0 + candidate.bits;
+            if bits < best[end].0 {
+                best[end] = (bits, Some(*candidate));
+            }
+        }
+    }
+
+    let mut used = Vec::new();
+    let mut end = chars.len();
+    while end > 0 {
+        match best[end].1 {
+            Some(found) => {
+                used.push(found.pattern);
+                end = found.start;
+            }
+            None => end -= 1,
+        }
+    }
+
+    let bits = best[chars.len()].0;
+    let score = SCORE_THRESHOLDS.iter().filter(|threshold| bits >= **threshold).count() as u8;
+    let (warning, suggestions) = feedback(score, &used);
+
+    PasswordStrength {
+        score,
+        warning,
+        suggestions,
+    }
+}
+
+fn feedback(score: u8, used: &[Pattern]) -> (Option<&'static str>, Vec<&'static str>) {
+    let mut warning = None;
+    let mut suggestions = Vec::new();
+    let mut note = |message: &'static str, suggestion: &'static str| {
+        warning.get_or_insert(message);
+        if !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    };
+
+    for pattern in used {
+        match pattern {
+            Pattern::Word { capitalized, substituted } => {
+                note("Common words and passwords are easy to guess", "Avoid common words and well-known passwords");
+                if *capitalized {
+                    note("Capitalizing a common word barely helps", "Capital letters help more in the middle than at the start");
+                }
+                if *substituted {
+                    note("Swaps like '@' for 'a' are among the first things tried", "Don't rely on predictable substitutions like '@' for 'a'");
+                }
+            }
+            Pattern::Sequence => note("Sequences like abc or 6543 are easy to guess", "Avoid sequences"),
+            Pattern::Keyboard => note("Straight rows of keys are easy to guess", "Avoid keyboard patterns like qwerty"),
+            Pattern::Repeat => note("Repeats like 'aaa' are easy to guess", "Avoid repeated words and characters"),
+            Pattern::Year => note("Years are easy to guess", "Avoid years and dates that are associated with you"),
+        }
+    }
+    if score < 3 {
+        suggestions.push("Add another word or two; uncommon words are better");
+    }
+
+    (warning, suggestions)
+}
+
+fn find_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    word_matches(chars, &mut matches);
+    run_matches(chars, &mut matches);
+    keyboard_matches(chars, &mut matches);
+    year_matches(chars, &mut matches);
+    matches
+}
+
+/// Common words, case-insensitively and with l33t substitutions undone.
+fn word_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let normalized: Vec<char> = chars.iter().map(|c| unleet(c.to_ascii_lowercase())).collect();
+
+    for (rank, word) in COMMON_WORDS.iter().enumerate() {
+        let word: Vec<char> = word.chars().collect();
+        for start in 0..=normalized.len().saturating_sub(word.len()) {
+            let end = start + word.len();
+            if end > normalized.len() || normalized[start..end] != word[..] {
+                continue;
+            }
+
+            let original = &chars[start..end];
+            let capitalized = original.iter().any(|c| c.is_uppercase());
+            let substituted = original.iter().any(|c| !c.is_alphabetic() && unleet(*c) != *c);
+            let bits = ((rank + 1) as f64).log2() + f64::from(u8::from(capitalized)) + f64::from(u8::from(substituted));
+            matches.push(Match {
+                start,
+                end,
+                bits,
+                pattern: Pattern::Word { capitalized, substituted },
+            });
+        }
+    }
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        other => other,
+    }
+}
+
+/// Runs of one repeated character, and steady sequences like "abc" or "975".
+fn run_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        while end < chars.len() && chars[end] == chars[start] {
+            end += 1;
+        }
+        if end - start >= 3 {
+            matches.push(Match {
+                start,
+                end,
+                bits: BRUTEFORCE_BITS + ((end - start) as f64).log2(),
+                pattern: Pattern::Repeat,
+            });
+        }
+        start = end;
+    }
+
+    for start in 0..chars.len() {
+        let step = |i: usize| chars[i + 1] as i32 - chars[i] as i32;
+        if start + 1 >= chars.len() || step(start).abs() != 1 || !chars[start].is_ascii_alphanumeric() {
+            continue;
+        }
+        let mut end = start + 2;
+        while end < chars.len() && step(end - 1) == step(start) && chars[end].is_ascii_alphanumeric() {
+            end += 1;
+        }
+        if end - start >= 3 {
+            let base: f64 = if "aAzZ019".contains(chars[start]) {
+                4.0
+            } else if chars[start].is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let direction = if step(start) < 0 { 2.0 } else { 1.0 };
+            matches.push(Match {
+                start,
+                end,
+                bits: (base * (end - start) as f64 * direction).log2(),
+                pattern: Pattern::Sequence,
+            });
+        }
+    }
+}
+
+/// Four or more keys in a row of the keyboard, either direction.
+fn keyboard_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let lower: String = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let lower: Vec<char> = lower.chars().collect();
+
+    for row in KEYBOARD_ROWS {
+        let reversed: String = row.chars().rev().collect();
+        for row in [row.to_string(), reversed] {
+            for start in 0..lower.len() {
+                let mut end = start;
+                while end < lower.len() {
+                    let candidate: String = lower[start..=end].iter().collect();
+                    if !row.contains(&candidate) {
+                        break;
+                    }
+                    end += 1;
+                }
+                if end - start >= 4 {
+                    matches.push(Match {
+                        start,
+                        end,
+                        bits: (KEYBOARD_ROWS.len() as f64 * 2.0 * (end - start) as f64).log2(),
+                        pattern: Pattern::Keyboard,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Four-digit years from 1900 to 2039.
+fn year_matches(chars: &[char], matches: &mut Vec<Match>) {
+    for start in 0..chars.len().saturating_sub(3) {
+        let candidate: String = chars[start..start + 4].iter().collect();
+        if candidate.parse::<u32>().is_ok_and(|year| (1900..2040).contains(&year)) {
+            matches.push(Match {
+                start,
+                end: start + 4,
+                bits: 140f64.log2(),
+                pattern: Pattern::Year,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_passwords_with_decorations_are_weak() {
+        for password in ["Password123!", "P@ssw0rd!23", "Qwerty2024!", "Letmein1!"] {
+            let strength = estimate(password);
+            assert!(strength.score < MIN_PASSWORD_SCORE, "{} scored {}", password, strength.score);
+            assert!(strength.warning.is_some());
+        }
+    }
+
+    #[test]
+    fn random_and_multi_word_passwords_are_strong() {
+        for password in ["k7#Vq9!mZr2$wLx", "Velvet-Otter-Harbor-92!"] {
+            let strength = estimate(password);
+            assert!(strength.score >= 3, "{} scored {}", password, strength.score);
+        }
+    }
+
+    #[test]
+    fn feedback_names_the_patterns_found() {
+        let strength = estimate("Abcdef1!");
+        assert_eq!(strength.warning, Some("Sequences like abc or 6543 are easy to guess"));
+        assert!(strength.suggestions.contains(&"Avoid sequences"));
+
+        let strength = estimate("zzzzzzzz1999!A");
+        assert!(strength.suggestions.contains(&"Avoid repeated words and characters"));
+        assert!(strength.suggestions.contains(&"Avoid years and dates that are associated with you"));
+    }
+}
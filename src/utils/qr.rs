@@ -0,0 +1,21 @@
+use crate::errors::{AppError, Result};
+use qrcode::render::unicode;
+use qrcode::QrCode;
+
+pub struct QrRenderer;
+
+impl QrRenderer {
+    /// Renders `data` as a QR code made of half-block characters so it can be
+    /// scanned straight from the terminal.
+    pub fn render_terminal(data: &str) -> Result<String> {
+        let code = QrCode::new(data.as_bytes())
+            .map_err(|e| AppError::InternalError(format!("Failed to generate QR code: {}", e)))?;
+
+        Ok(code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .quiet_zone(true)
+            .build())
+    }
+}
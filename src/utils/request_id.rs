@@ -0,0 +1,56 @@
+//! The id of the API request being handled, for tying together what one
+//! request did: its error response, audit entries, the transactions it
+//! submitted to Horizon and the webhooks those set off. The API sets it for
+//! each request with [`scope`]; anything running outside a request, like the
+//! CLI, has none.
+
+use std::future::Future;
+use uuid::Uuid;
+
+/// Longest id accepted from a client.
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The current request's id, if there is one.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Runs `future` as part of the request `id`.
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// A client's own id, kept if it's 1 to 128 printable characters without
+/// spaces, so it can't break a header or a log line.
+pub fn parse(id: &str) -> Option<String> {
+    let valid = !id.is_empty() && id.len() <= MAX_LEN && id.chars().all(|c| c.is_ascii_graphic());
+    valid.then(|| id.to_string())
+}
+
+/// A fresh id for a request that didn't bring one.
+pub fn generate() -> String {
+    Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_code_inside_the_scope_sees_the_id() {
+        assert_eq!(current(), None);
+        let seen = scope("req-42".to_string(), async { current() }).await;
+        assert_eq!(seen.as_deref(), Some("req-42"));
+        assert_eq!(current(), None);
+
+        assert_eq!(parse("abc-123").as_deref(), Some("abc-123"));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("two words"), None);
+        assert_eq!(parse(&"x".repeat(129)), None);
+        assert_ne!(generate(), generate());
+    }
+}
@@ -1,4 +1,7 @@
 use crate::errors::{AppError, Result};
+use crate::models::customer_field::SEP9_FIELDS;
+use crate::models::user_settings::SUPPORTED_FIAT_CURRENCIES;
+use crate::utils::password_strength::{self, MIN_PASSWORD_SCORE};
 use regex::Regex;
 
 pub struct Validator;
@@ -68,6 +71,168 @@ impl Validator {
             return Err(AppError::ValidationError("Password must contain at least one special character".to_string()));
         }
         
+        let strength = password_strength::estimate(password);
+        if strength.score < MIN_PASSWORD_SCORE {
+            let mut message = format!("Password is too easy to guess ({})", strength.label());
+            if let Some(warning) = strength.warning {
+                message.push_str(&format!(": {}", warning.to_lowercase()));
+            }
+            return Err(AppError::ValidationError(message));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_amount(amount: &str) -> Result<()> {
+        let amount_regex = Regex::new(r"^\d{1,12}(\.\d{1,7})?$")
+            .map_err(|e| AppError::InternalError(format!("Regex error: {}", e)))?;
+
+        if !amount_regex.is_match(amount) {
+            return Err(AppError::ValidationError("Amount must be a number with at most 7 decimal places".to_string()));
+        }
+
+        if amount.chars().all(|c| c == '0' || c == '.') {
+            return Err(AppError::ValidationError("Amount must be greater than zero".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_memo(memo: &str) -> Result<()> {
+        if memo.len() > 28 {
+            return Err(AppError::ValidationError("Memo must be at most 28 bytes".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_stellar_address(address: &str) -> Result<()> {
+        stellar_strkey::ed25519::PublicKey::from_string(address)
+            .map_err(|_| AppError::ValidationError("Invalid Stellar address (expected a G... public key)".to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn validate_contact_name(name: &str) -> Result<()> {
+        if name.trim().is_empty() {
+            return Err(AppError::ValidationError("Contact name cannot be empty".to_string()));
+        }
+
+        if name.len() > 50 {
+            return Err(AppError::ValidationError("Contact name must be less than 50 characters".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_federation_name(federation_name: &str) -> Result<()> {
+        let federation_regex = Regex::new(r"^[^*\s]+\*[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$")
+            .map_err(|e| AppError::InternalError(format!("Regex error: {}", e)))?;
+
+        if !federation_regex.is_match(federation_name) {
+            return Err(AppError::ValidationError("Federation name must look like name*domain.com".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_fiat_currency(currency: &str) -> Result<()> {
+        if !SUPPORTED_FIAT_CURRENCIES.contains(&currency) {
+            return Err(AppError::ValidationError(format!(
+                "Unsupported currency. Choose one of: {}",
+                SUPPORTED_FIAT_CURRENCIES.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_customer_field(field_name: &str, value: &str) -> Result<()> {
+        if !SEP9_FIELDS.contains(&field_name) {
+            return Err(AppError::ValidationError(format!("'{}' is not a SEP-9 customer field", field_name)));
+        }
+
+        if value.trim().is_empty() || value.len() > 512 {
+            return Err(AppError::ValidationError("Value must be between 1 and 512 characters".to_string()));
+        }
+
         Ok(())
     }
+
+    /// Phone numbers for SMS must be E.164, e.g. `+254712345678`.
+    pub fn validate_phone_number(phone: &str) -> Result<()> {
+        let phone_regex = Regex::new(r"^\+[1-9][0-9]{6,14}$")
+            .map_err(|e| AppError::InternalError(format!("Regex error: {}", e)))?;
+
+        if !phone_regex.is_match(phone) {
+            return Err(AppError::ValidationError("Phone number must be in international format, e.g. +254712345678".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "GCMNQFUMKC3M7EGCZHUQDRMTCT6OOSWXMX5NCVR2ORYQREWZI7PD4BAB";
+
+    #[test]
+    fn accepts_valid_stellar_address() {
+        assert!(Validator::validate_stellar_address(ADDRESS).is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_stellar_address() {
+        assert!(Validator::validate_stellar_address("").is_err());
+        assert!(Validator::validate_stellar_address(&ADDRESS[..55]).is_err());
+        // Last character changed, so the checksum no longer matches.
+        assert!(Validator::validate_stellar_address(&format!("{}C", &ADDRESS[..55])).is_err());
+        assert!(Validator::validate_stellar_address("SCMNQFUMKC3M7EGCZHUQDRMTCT6OOSWXMX5NCVR2ORYQREWZI7PD4BAB").is_err());
+    }
+
+    #[test]
+    fn validates_contact_name_length() {
+        assert!(Validator::validate_contact_name("Bob").is_ok());
+        assert!(Validator::validate_contact_name("  ").is_err());
+        assert!(Validator::validate_contact_name(&"x".repeat(51)).is_err());
+    }
+
+    #[test]
+    fn validates_federation_name_format() {
+        assert!(Validator::validate_federation_name("bob*example.com").is_ok());
+        assert!(Validator::validate_federation_name("bob@example.com").is_err());
+        assert!(Validator::validate_federation_name("bob*example").is_err());
+        assert!(Validator::validate_federation_name("bo b*example.com").is_err());
+    }
+
+    #[test]
+    fn validates_memo_length() {
+        assert!(Validator::validate_memo(&"m".repeat(28)).is_ok());
+        assert!(Validator::validate_memo(&"m".repeat(29)).is_err());
+    }
+
+    #[test]
+    fn validates_fiat_currency() {
+        assert!(Validator::validate_fiat_currency("eur").is_ok());
+        assert!(Validator::validate_fiat_currency("EUR").is_err());
+        assert!(Validator::validate_fiat_currency("doge").is_err());
+    }
+
+    #[test]
+    fn validates_customer_fields() {
+        assert!(Validator::validate_customer_field("first_name", "Ada").is_ok());
+        assert!(Validator::validate_customer_field("favourite_colour", "blue").is_err());
+        assert!(Validator::validate_customer_field("last_name", "  ").is_err());
+        assert!(Validator::validate_customer_field("address", &"a".repeat(513)).is_err());
+    }
+
+    #[test]
+    fn validates_phone_numbers() {
+        assert!(Validator::validate_phone_number("+254712345678").is_ok());
+        assert!(Validator::validate_phone_number("0712345678").is_err());
+        assert!(Validator::validate_phone_number("+0712345678").is_err());
+        assert!(Validator::validate_phone_number("+1 555 0100").is_err());
+    }
 }
@@ -0,0 +1,313 @@
+use crate::errors::{AppError, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ciborium::Value;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::Deserialize;
+
+/// The COSE algorithms passkeys may use, in order of preference: ES256,
+/// EdDSA and RS256, which between them cover every platform authenticator.
+pub const SUPPORTED_ALGORITHMS: [i64; 3] = [ES256, EDDSA, RS256];
+
+const ES256: i64 = -7;
+const EDDSA: i64 = -8;
+const RS256: i64 = -257;
+
+const USER_PRESENT: u8 = 0x01;
+const USER_VERIFIED: u8 = 0x04;
+const ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// The `clientDataJSON` a browser signs along with the authenticator data.
+#[derive(Debug, Deserialize)]
+pub struct ClientData {
+    /// `webauthn.create` or `webauthn.get`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The challenge, base64url-encoded.
+    pub challenge: String,
+    /// The page that asked for the credential.
+    pub origin: String,
+}
+
+impl ClientData {
+    pub fn parse(json: &[u8]) -> Result<Self> {
+        serde_json::from_slice(json).map_err(|e| rejected(&format!("unreadable client data: {}", e)))
+    }
+}
+
+/// What the authenticator says about itself and the ceremony.
+#[derive(Debug)]
+pub struct AuthenticatorData {
+    /// SHA-256 of the relying party id the credential is scoped to.
+    pub rp_id_hash: [u8; 32],
+    pub user_present: bool,
+    /// The authenticator checked a PIN or biometric.
+    pub user_verified: bool,
+    /// 0 for authenticators that don't keep a counter.
+    pub sign_count: u32,
+    /// The new credential, when registering.
+    pub credential: Option<AttestedCredential>,
+}
+
+#[derive(Debug)]
+pub struct AttestedCredential {
+    pub id: Vec<u8>,
+    /// The credential's COSE public key, as the authenticator encoded it.
+    pub public_key: Vec<u8>,
+}
+
+impl AuthenticatorData {
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let truncated = || rejected("truncated authenticator data");
+        if bytes.len() < 37 {
+            return Err(truncated());
+        }
+        let flags = bytes[32];
+        let mut data = Self {
+            rp_id_hash: bytes[..32].try_into().map_err(|_| truncated())?,
+            user_present: flags & USER_PRESENT != 0,
+            user_verified: flags & USER_VERIFIED != 0,
+            sign_count: u32::from_be_bytes(bytes[33..37].try_into().map_err(|_| truncated())?),
+            credential: None,
+        };
+        if flags & ATTESTED_CREDENTIAL == 0 {
+            return Ok(data);
+        }
+
+        // The AAGUID (16 bytes), then the id's length and the id.
+        let rest = bytes.get(37 + 16..).ok_or_else(truncated)?;
+        let id_len = u16::from_be_bytes(rest.get(..2).ok_or_else(truncated)?.try_into().map_err(|_| truncated())?) as usize;
+        let id = rest.get(2..2 + id_len).ok_or_else(truncated)?.to_vec();
+        let key_and_extensions = &rest[2 + id_len..];
+        let mut reader = key_and_extensions;
+        ciborium::de::from_reader::<Value, _>(&mut reader).map_err(|e| rejected(&format!("unreadable public key: {}", e)))?;
+        let public_key = key_and_extensions[..key_and_extensions.len() - reader.len()].to_vec();
+
+        data.credential = Some(AttestedCredential { id, public_key });
+        Ok(data)
+    }
+}
+
+/// The authenticator data in an `attestationObject`. The attestation
+/// statement isn't checked: registration asks for none, since the wallet
+/// doesn't restrict which authenticators its users pick.
+pub fn attested_authenticator_data(attestation_object: &[u8]) -> Result<Vec<u8>> {
+    let object: Value = ciborium::de::from_reader(attestation_object).map_err(|e| rejected(&format!("unreadable attestation: {}", e)))?;
+    let fields = object.as_map().ok_or_else(|| rejected("unreadable attestation"))?;
+
+    fields
+        .iter()
+        .find(|(key, _)| key.as_text() == Some("authData"))
+        .and_then(|(_, value)| value.as_bytes())
+        .cloned()
+        .ok_or_else(|| rejected("the attestation has no authenticator data"))
+}
+
+/// A credential's public key, from its COSE encoding.
+#[derive(Debug)]
+enum PublicKey {
+    Es256 { point: Vec<u8> },
+    Ed25519 { key: Vec<u8> },
+    Rs256 { n: Vec<u8>, e: Vec<u8> },
+}
+
+impl PublicKey {
+    fn parse(cose: &[u8]) -> Result<Self> {
+        let key: Value = ciborium::de::from_reader(cose).map_err(|e| rejected(&format!("unreadable public key: {}", e)))?;
+        let fields = key.as_map().ok_or_else(|| rejected("unreadable public key"))?;
+        let field = |label: i64| {
+            fields
+                .iter()
+                .find(|(key, _)| key.as_integer().is_some_and(|key| i128::from(key) == i128::from(label)))
+                .map(|(_, value)| value)
+        };
+        let int = |label: i64| field(label).and_then(Value::as_integer).map(i128::from);
+        let bytes = |label: i64| field(label).and_then(Value::as_bytes).cloned().ok_or_else(|| rejected("incomplete public key"));
+
+        // Key type (1), algorithm (3), then the parameters for the type.
+        match (int(1), int(3)) {
+            (Some(2), Some(alg)) if alg == ES256 as i128 && int(-1) == Some(1) => {
+                let mut point = vec![0x04];
+                point.extend(bytes(-2)?);
+                point.extend(bytes(-3)?);
+                Ok(Self::Es256 { point })
+            }
+            (Some(1), Some(alg)) if alg == EDDSA as i128 && int(-1) == Some(6) => Ok(Self::Ed25519 { key: bytes(-2)? }),
+            (Some(3), Some(alg)) if alg == RS256 as i128 => Ok(Self::Rs256 { n: bytes(-1)?, e: bytes(-2)? }),
+            _ => Err(AppError::ValidationError(
+                "This passkey uses a key type the wallet doesn't support; use ES256, Ed25519 or RS256".to_string(),
+            )),
+        }
+    }
+}
+
+/// Checks that `cose` is a public key [`verify`] can use.
+pub fn check_public_key(cose: &[u8]) -> Result<()> {
+    PublicKey::parse(cose).map(drop)
+}
+
+/// Checks `signature` over `message` with the COSE public key `cose`.
+pub fn verify(cose: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+    let verified = match PublicKey::parse(cose)? {
+        PublicKey::Es256 { point } => UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_ASN1, point).verify(message, signature),
+        PublicKey::Ed25519 { key } => UnparsedPublicKey::new(&signature::ED25519, key).verify(message, signature),
+        PublicKey::Rs256 { n, e } => RsaPublicKeyComponents { n, e }.verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, signature),
+    };
+    verified.map_err(|_| rejected("the signature doesn't match the passkey"))
+}
+
+/// Decodes a base64url field, with or without padding.
+pub fn decode(field: &str, value: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim().trim_end_matches('='))
+        .map_err(|_| rejected(&format!("'{}' isn't base64url", field)))
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn rejected(reason: &str) -> AppError {
+    AppError::AuthenticationError(format!("The passkey's response was rejected: {}", reason))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::models::passkey::{AssertionResponse, AttestationResponse, PasskeyAssertion, PasskeyRegistration};
+    use ring::rand::SystemRandom;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    /// A software passkey with an ES256 key, answering challenges the way a
+    /// browser and platform authenticator would for `origin`.
+    pub(crate) struct SoftAuthenticator {
+        key: EcdsaKeyPair,
+        pub credential_id: Vec<u8>,
+        rp_id: String,
+        origin: String,
+        pub sign_count: u32,
+        pub user_verified: bool,
+    }
+
+    impl SoftAuthenticator {
+        pub(crate) fn new(rp_id: &str, origin: &str) -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+            Self {
+                key: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng).unwrap(),
+                credential_id: uuid::Uuid::new_v4().as_bytes().to_vec(),
+                rp_id: rp_id.to_string(),
+                origin: origin.to_string(),
+                sign_count: 0,
+                user_verified: true,
+            }
+        }
+
+        /// The new credential's response to registration `options`.
+        pub(crate) fn register(&self, options: &serde_json::Value) -> PasskeyRegistration {
+            let client_data = self.client_data("webauthn.create", options["challenge"].as_str().unwrap());
+            let point = self.key.public_key().as_ref();
+            let cose_key = Value::Map(vec![
+                (Value::from(1), Value::from(2)),
+                (Value::from(3), Value::from(ES256)),
+                (Value::from(-1), Value::from(1)),
+                (Value::from(-2), Value::Bytes(point[1..33].to_vec())),
+                (Value::from(-3), Value::Bytes(point[33..].to_vec())),
+            ]);
+
+            let mut auth_data = self.auth_data(ATTESTED_CREDENTIAL);
+            auth_data.extend([0; 16]);
+            auth_data.extend((self.credential_id.len() as u16).to_be_bytes());
+            auth_data.extend(&self.credential_id);
+            ciborium::ser::into_writer(&cose_key, &mut auth_data).unwrap();
+            let attestation = Value::Map(vec![
+                (Value::from("fmt"), Value::from("none")),
+                (Value::from("attStmt"), Value::Map(vec![])),
+                (Value::from("authData"), Value::Bytes(auth_data)),
+            ]);
+            let mut attestation_object = Vec::new();
+            ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+
+            PasskeyRegistration {
+                id: encode(&self.credential_id),
+                response: AttestationResponse {
+                    client_data_json: encode(&client_data),
+                    attestation_object: encode(&attestation_object),
+                },
+            }
+        }
+
+        /// A signed answer to login `options`, claiming to be `user_handle`.
+        pub(crate) fn assert(&mut self, options: &serde_json::Value, user_handle: Option<&str>) -> PasskeyAssertion {
+            self.sign_count += 1;
+            let client_data = self.client_data("webauthn.get", options["challenge"].as_str().unwrap());
+            let auth_data = self.auth_data(0);
+            let mut message = auth_data.clone();
+            message.extend(Sha256::digest(&client_data));
+            let signature = self.key.sign(&SystemRandom::new(), &message).unwrap();
+
+            PasskeyAssertion {
+                id: encode(&self.credential_id),
+                response: AssertionResponse {
+                    client_data_json: encode(&client_data),
+                    authenticator_data: encode(&auth_data),
+                    signature: encode(signature.as_ref()),
+                    user_handle: user_handle.map(str::to_string),
+                },
+            }
+        }
+
+        fn client_data(&self, kind: &str, challenge: &str) -> Vec<u8> {
+            serde_json::to_vec(&json!({ "type": kind, "challenge": challenge, "origin": self.origin })).unwrap()
+        }
+
+        fn auth_data(&self, extra_flags: u8) -> Vec<u8> {
+            let mut flags = USER_PRESENT | extra_flags;
+            if self.user_verified {
+                flags |= USER_VERIFIED;
+            }
+            let mut data = Sha256::digest(self.rp_id.as_bytes()).to_vec();
+            data.push(flags);
+            data.extend(self.sign_count.to_be_bytes());
+            data
+        }
+    }
+
+    #[test]
+    fn reads_the_credential_and_checks_signatures_with_it() {
+        let mut authenticator = SoftAuthenticator::new("wallet.example.com", "https://wallet.example.com");
+        let options = json!({ "challenge": "Y2hhbGxlbmdl" });
+        let registration = authenticator.register(&options);
+
+        let object = decode("attestationObject", &registration.response.attestation_object).unwrap();
+        let data = AuthenticatorData::parse(&attested_authenticator_data(&object).unwrap()).unwrap();
+        assert!(data.user_present && data.user_verified);
+        assert_eq!(data.rp_id_hash.as_slice(), Sha256::digest(b"wallet.example.com").as_slice());
+        let credential = data.credential.unwrap();
+        assert_eq!(credential.id, authenticator.credential_id);
+        check_public_key(&credential.public_key).unwrap();
+
+        let assertion = authenticator.assert(&options, None);
+        let auth_data = decode("authenticatorData", &assertion.response.authenticator_data).unwrap();
+        let client_data = decode("clientDataJSON", &assertion.response.client_data_json).unwrap();
+        assert_eq!(ClientData::parse(&client_data).unwrap().kind, "webauthn.get");
+        assert_eq!(AuthenticatorData::parse(&auth_data).unwrap().sign_count, 1);
+        let signature = decode("signature", &assertion.response.signature).unwrap();
+        let mut message = auth_data.clone();
+        message.extend(Sha256::digest(&client_data));
+        verify(&credential.public_key, &message, &signature).unwrap();
+
+        message[0] ^= 1;
+        assert!(verify(&credential.public_key, &message, &signature).is_err());
+    }
+
+    #[test]
+    fn refuses_unsupported_keys() {
+        let mut cose = Vec::new();
+        let key = Value::Map(vec![(Value::from(1), Value::from(2)), (Value::from(3), Value::from(-35))]);
+        ciborium::ser::into_writer(&key, &mut cose).unwrap();
+        assert!(matches!(check_public_key(&cose), Err(AppError::ValidationError(_))));
+    }
+}
@@ -0,0 +1,119 @@
+//! Signing and verification for the wallet's outgoing webhooks.
+//!
+//! Each request carries `X-Wallet-Signature: t=<unix seconds>,v1=<hex>`, where
+//! the hex is HMAC-SHA256 over `"<t>.<raw body>"` keyed with the shared secret.
+//! Receivers should call [`verify`] on the raw body before parsing it, and drop
+//! event ids they have already seen to rule out replays inside the tolerance
+//! window. Several `v1` entries may appear while a secret is being rotated.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+
+pub const SIGNATURE_HEADER: &str = "X-Wallet-Signature";
+pub const EVENT_ID_HEADER: &str = "X-Wallet-Event-Id";
+/// The wallet API request that set the event off, when one did, for quoting
+/// to the wallet's operator.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// How far a signature's timestamp may be from the receiver's clock.
+pub const DEFAULT_TOLERANCE_SECS: i64 = 300;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The header is missing a timestamp or a `v1` signature.
+    Malformed,
+    /// Signed too long ago (or too far in the future); possibly a replay.
+    Expired,
+    /// No `v1` signature matches the body and secret.
+    Mismatch,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Malformed => write!(f, "malformed webhook signature header"),
+            VerifyError::Expired => write!(f, "webhook signature timestamp is outside the tolerance window"),
+            VerifyError::Mismatch => write!(f, "webhook signature does not match"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// The `X-Wallet-Signature` value for `body` sent at `timestamp`.
+pub fn sign(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    let mac = signed_payload_mac(secret, timestamp, body);
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a signature header against the raw `body`, with `now` as the
+/// receiver's clock in unix seconds.
+pub fn verify(secret: &[u8], header: &str, body: &[u8], now: i64, tolerance_secs: i64) -> Result<(), VerifyError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = Some(value.parse::<i64>().map_err(|_| VerifyError::Malformed)?),
+            Some(("v1", value)) => signatures.push(hex::decode(value).map_err(|_| VerifyError::Malformed)?),
+            _ => {}
+        }
+    }
+
+    let timestamp = timestamp.ok_or(VerifyError::Malformed)?;
+    if signatures.is_empty() {
+        return Err(VerifyError::Malformed);
+    }
+    if (now - timestamp).abs() > tolerance_secs {
+        return Err(VerifyError::Expired);
+    }
+
+    // verify_slice compares in constant time.
+    if signatures
+        .iter()
+        .any(|signature| signed_payload_mac(secret, timestamp, body).verify_slice(signature).is_ok())
+    {
+        Ok(())
+    } else {
+        Err(VerifyError::Mismatch)
+    }
+}
+
+fn signed_payload_mac(secret: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"whsec_test";
+    const BODY: &[u8] = br#"{"id":"evt_1","type":"webhook.test"}"#;
+
+    #[test]
+    fn verifies_within_the_tolerance_window() {
+        let header = sign(SECRET, 1_700_000_000, BODY);
+
+        assert_eq!(verify(SECRET, &header, BODY, 1_700_000_100, DEFAULT_TOLERANCE_SECS), Ok(()));
+        assert_eq!(verify(SECRET, &header, BODY, 1_700_000_301, DEFAULT_TOLERANCE_SECS), Err(VerifyError::Expired));
+        assert_eq!(verify(b"other", &header, BODY, 1_700_000_000, DEFAULT_TOLERANCE_SECS), Err(VerifyError::Mismatch));
+        assert_eq!(verify(SECRET, &header, b"{}", 1_700_000_000, DEFAULT_TOLERANCE_SECS), Err(VerifyError::Mismatch));
+    }
+
+    #[test]
+    fn accepts_any_of_several_signatures_and_rejects_malformed_headers() {
+        let current = sign(SECRET, 1_700_000_000, BODY);
+        let old = sign(b"old secret", 1_700_000_000, BODY);
+        let rotating = format!("{},{}", old, current.split_once(',').unwrap().1);
+
+        assert_eq!(verify(SECRET, &rotating, BODY, 1_700_000_000, 0), Ok(()));
+        assert_eq!(verify(SECRET, "v1=abcd", BODY, 1_700_000_000, 0), Err(VerifyError::Malformed));
+        assert_eq!(verify(SECRET, "t=1700000000", BODY, 1_700_000_000, 0), Err(VerifyError::Malformed));
+        assert_eq!(verify(SECRET, "t=soon,v1=ab", BODY, 1_700_000_000, 0), Err(VerifyError::Malformed));
+    }
+}